@@ -0,0 +1,94 @@
+//! Parse, build and package benchmarks on large generated documents
+//!
+//! Each document (10k paragraphs, 200k runs, 1k tables, 500 images, 500
+//! equations) is converted end to end; the time of every stage reported by
+//! `markdown_to_docx_with_stages` is recorded as its own benchmark, so a
+//! regression in the parser, the builder or the packager shows up under that
+//! stage.
+//!
+//! Run with `cargo bench --bench pipeline`.
+
+#[path = "../tests/support/generate.rs"]
+mod generate;
+
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use md2docx::{markdown_to_docx_with_stages, DocumentConfig, Language, PlaceholderContext};
+
+const STAGES: [&str; 3] = ["parse", "build", "package"];
+
+/// Convert `markdown` once, returning how long each stage took
+fn run_stages(markdown: &str, config: &DocumentConfig) -> [Duration; 3] {
+    let mut durations = [Duration::ZERO; 3];
+    let mut start = Instant::now();
+    markdown_to_docx_with_stages(
+        markdown,
+        Language::English,
+        config,
+        None,
+        &PlaceholderContext::default(),
+        &mut |stage| {
+            let i = STAGES.iter().position(|s| *s == stage).unwrap();
+            durations[i] = start.elapsed();
+            start = Instant::now();
+        },
+    )
+    .unwrap();
+    durations
+}
+
+fn bench_document(c: &mut Criterion, name: &str, markdown: &str, config: &DocumentConfig) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for (i, stage) in STAGES.iter().enumerate() {
+        group.bench_function(*stage, |b| {
+            b.iter_custom(|iters| (0..iters).map(|_| run_stages(markdown, config)[i]).sum())
+        });
+    }
+    group.finish();
+}
+
+fn bench_paragraphs(c: &mut Criterion) {
+    let markdown = generate::paragraphs(10_000);
+    bench_document(c, "paragraphs_10k", &markdown, &DocumentConfig::default());
+}
+
+fn bench_runs(c: &mut Criterion) {
+    let markdown = generate::runs(200_000);
+    bench_document(c, "runs_200k", &markdown, &DocumentConfig::default());
+}
+
+fn bench_tables(c: &mut Criterion) {
+    let markdown = generate::tables(1_000, 5);
+    bench_document(c, "tables_1k", &markdown, &DocumentConfig::default());
+}
+
+fn bench_images(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let markdown = generate::images(dir.path(), 500);
+    let config = DocumentConfig {
+        base_path: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    };
+    bench_document(c, "images_500", &markdown, &config);
+}
+
+fn bench_equations(c: &mut Criterion) {
+    let markdown = generate::equations(500);
+    let config = DocumentConfig {
+        math_renderer: "rex".to_string(),
+        ..Default::default()
+    };
+    bench_document(c, "equations_500", &markdown, &config);
+}
+
+criterion_group!(
+    benches,
+    bench_paragraphs,
+    bench_runs,
+    bench_tables,
+    bench_images,
+    bench_equations
+);
+criterion_main!(benches);
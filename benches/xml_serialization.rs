@@ -0,0 +1,89 @@
+//! Serialization benchmarks for OOXML parts
+//!
+//! Compares the shared `XmlWriter` against the string building it replaced
+//! (`format!` plus chained `replace` escaping), on a body of runs like the
+//! ones written for document.xml, headers and footers.
+//!
+//! Run with `cargo bench --bench xml_serialization`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use md2docx::docx::XmlWriter;
+
+const RUNS: usize = 2_000;
+
+fn sample_text(i: usize) -> String {
+    format!("ภาษาไทย paragraph {i} with R&D <notes> and \"quotes\"")
+}
+
+/// The escaping previously done by hand before string concatenation
+fn escape_by_replace(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn runs_by_concat(texts: &[String]) -> Vec<u8> {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#);
+    xml.push_str("<w:body>");
+    for text in texts {
+        xml.push_str(&format!(
+            r#"<w:p><w:r><w:rPr><w:b/></w:rPr><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+            escape_by_replace(text)
+        ));
+    }
+    xml.push_str("</w:body>");
+    xml.into_bytes()
+}
+
+fn runs_by_writer(texts: &[String]) -> Vec<u8> {
+    let mut w = XmlWriter::new(Vec::new());
+    w.declaration().unwrap();
+    w.start("w:body", &[]).unwrap();
+    for text in texts {
+        w.start("w:p", &[]).unwrap();
+        w.start("w:r", &[]).unwrap();
+        w.start("w:rPr", &[]).unwrap();
+        w.empty("w:b", &[]).unwrap();
+        w.end("w:rPr").unwrap();
+        w.element("w:t", &[("xml:space", "preserve")], text)
+            .unwrap();
+        w.end("w:r").unwrap();
+        w.end("w:p").unwrap();
+    }
+    w.end("w:body").unwrap();
+    w.into_inner()
+}
+
+fn bench_runs(c: &mut Criterion) {
+    let texts: Vec<String> = (0..RUNS).map(sample_text).collect();
+
+    let mut group = c.benchmark_group("runs");
+    group.throughput(Throughput::Elements(RUNS as u64));
+    group.bench_function("string_concat", |b| {
+        b.iter(|| runs_by_concat(black_box(&texts)))
+    });
+    group.bench_function("xml_writer", |b| {
+        b.iter(|| runs_by_writer(black_box(&texts)))
+    });
+    group.finish();
+}
+
+fn bench_document(c: &mut Criterion) {
+    let markdown: String = (0..200)
+        .map(|i| {
+            format!(
+                "## Section {i}\n\n{} **bold** and `code`.\n\n",
+                sample_text(i)
+            )
+        })
+        .collect();
+
+    c.bench_function("markdown_to_docx", |b| {
+        b.iter(|| md2docx::markdown_to_docx(black_box(&markdown)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_runs, bench_document);
+criterion_main!(benches);
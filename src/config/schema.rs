@@ -3,10 +3,20 @@
 //! This module defines the structure of `md2docx.toml` configuration files
 //! and provides methods to load and parse them.
 
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+use once_cell::sync::Lazy;
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+use regex::Regex;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
+/// `${NAME}` or `${NAME:-default}`; `$${` is an escaped literal `${`
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+static ENV_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$\$\{|\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}").expect("Invalid regex")
+});
+
 /// Deep-merge two TOML tables. Values in `override_table` take precedence.
 /// Sub-tables are merged recursively; leaf values from override replace base.
 #[cfg(feature = "cli")]
@@ -25,6 +35,299 @@ fn deep_merge_toml(base: &mut toml::Table, override_table: &toml::Table) {
     }
 }
 
+/// Replace `${NAME}` references in every string value of `table`
+///
+/// `${NAME:-default}` falls back to `default` when the variable is unset;
+/// a plain `${NAME}` that is unset is an error naming the variable.
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn interpolate_env(
+    table: &mut toml::Table,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    for (_, value) in table.iter_mut() {
+        interpolate_env_value(value, lookup)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn interpolate_env_value(
+    value: &mut toml::Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    match value {
+        toml::Value::String(s) if s.contains('$') => {
+            let mut missing = None;
+            let replaced = ENV_REFERENCE.replace_all(s, |caps: &regex::Captures| {
+                let Some(name) = caps.get(1) else {
+                    return "${".to_string();
+                };
+                lookup(name.as_str())
+                    .or_else(|| caps.get(2).map(|d| d.as_str().to_string()))
+                    .unwrap_or_else(|| {
+                        missing.get_or_insert_with(|| name.as_str().to_string());
+                        String::new()
+                    })
+            });
+            if let Some(name) = missing {
+                return Err(name);
+            }
+            *s = replaced.into_owned();
+        }
+        toml::Value::Array(items) => {
+            for item in items {
+                interpolate_env_value(item, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => interpolate_env(table, lookup)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Read a TOML file into a table, naming the file in parse errors
+///
+/// Environment variable references (`${NAME}`) in string values are
+/// resolved here.
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn read_toml_table(path: &Path, what: &str) -> crate::Result<toml::Table> {
+    let content = std::fs::read_to_string(path)?;
+    let mut table: toml::Table = toml::from_str(&content).map_err(|e| {
+        crate::Error::Config(format!(
+            "Failed to parse {} {}: {}",
+            what,
+            path.display(),
+            e
+        ))
+    })?;
+    interpolate_env(&mut table, &|name| std::env::var(name).ok()).map_err(|name| {
+        crate::Error::Config(format!(
+            "Environment variable '{}' used in {} is not set",
+            name,
+            path.display()
+        ))
+    })?;
+    Ok(table)
+}
+
+/// Read a config file and resolve its `extends` / `include` keys
+///
+/// `extends = "path"` names a base config; `include = ["a.toml", ...]` lists
+/// further files merged in order on top of it. Paths are relative to the
+/// file that names them, and included files may themselves extend or
+/// include others. The file's own values are merged last, so they win.
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn read_config_table(path: &Path, what: &str) -> crate::Result<toml::Table> {
+    read_config_table_inner(path, what, &mut Vec::new())
+}
+
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn read_config_table_inner(
+    path: &Path,
+    what: &str,
+    chain: &mut Vec<PathBuf>,
+) -> crate::Result<toml::Table> {
+    let canonical = path.canonicalize().map_err(|e| {
+        crate::Error::Config(format!("Cannot read {} {}: {}", what, path.display(), e))
+    })?;
+    if chain.contains(&canonical) {
+        let cycle: Vec<String> = chain
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(crate::Error::Config(format!(
+            "Config inheritance cycle: {}",
+            cycle.join(" -> ")
+        )));
+    }
+
+    let mut table = read_toml_table(path, what)?;
+    let extends = table.remove("extends");
+    let includes = table.remove("include");
+
+    let mut parents = Vec::new();
+    match extends {
+        Some(toml::Value::String(parent)) => parents.push(parent),
+        Some(_) => {
+            return Err(crate::Error::Config(format!(
+                "'extends' in {} must be a path string",
+                path.display()
+            )))
+        }
+        None => {}
+    }
+    match includes {
+        Some(toml::Value::Array(items)) => {
+            for item in items {
+                match item {
+                    toml::Value::String(include) => parents.push(include),
+                    _ => {
+                        return Err(crate::Error::Config(format!(
+                            "'include' in {} must be a list of path strings",
+                            path.display()
+                        )))
+                    }
+                }
+            }
+        }
+        Some(_) => {
+            return Err(crate::Error::Config(format!(
+                "'include' in {} must be a list of path strings",
+                path.display()
+            )))
+        }
+        None => {}
+    }
+    if parents.is_empty() {
+        return Ok(table);
+    }
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    chain.push(canonical);
+    let mut merged = toml::Table::new();
+    for parent in parents {
+        let parent_table = read_config_table_inner(&dir.join(parent), "inherited config", chain)?;
+        deep_merge_toml(&mut merged, &parent_table);
+    }
+    chain.pop();
+
+    deep_merge_toml(&mut merged, &table);
+    Ok(merged)
+}
+
+/// Config sections an `[[output]]` entry may override with a sub-table
+#[cfg(feature = "cli")]
+const TARGET_SECTIONS: &[&str] = &[
+    "document",
+    "template",
+    "toc",
+    "fonts",
+    "code",
+    "chapters",
+    "appendices",
+    "parts",
+    "cover",
+    "mermaid",
+    "math",
+    "images",
+    "assets",
+    "remote",
+    "snippets",
+    "changelog",
+    "signatures",
+    "gallery",
+    "header",
+    "footer",
+    "numbering",
+    "footnotes",
+    "i18n",
+];
+
+/// Split a config table with an `[[output]]` array into one table per target
+///
+/// Each entry's plain keys (`file`, `watermark`, `exclude_tags`, ...) become that
+/// target's `[output]` section; sub-tables named after a config section
+/// (`toc.depth = 2`, `chapters.pattern = "ch0[12]_*.md"`) are deep-merged
+/// over the shared config. `template = "dir"` and `toc = false` are
+/// shorthands for `template.dir` and `toc.enabled`. A plain `[output]`
+/// table yields a single target.
+#[cfg(feature = "cli")]
+fn split_output_targets(mut table: toml::Table) -> crate::Result<Vec<toml::Table>> {
+    let entries = match table.remove("output") {
+        Some(toml::Value::Array(entries)) => entries,
+        Some(output) => {
+            table.insert("output".to_string(), output);
+            return Ok(vec![table]);
+        }
+        None => return Ok(vec![table]),
+    };
+    if entries.is_empty() {
+        return Err(crate::Error::Config(
+            "[[output]] must have at least one entry".to_string(),
+        ));
+    }
+
+    let several = entries.len() > 1;
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let toml::Value::Table(entry) = entry else {
+                return Err(crate::Error::Config(format!(
+                    "[[output]] entry {} must be a table",
+                    i + 1
+                )));
+            };
+            if several && !entry.contains_key("file") {
+                return Err(crate::Error::Config(format!(
+                    "[[output]] entry {} needs a file when there are several outputs",
+                    i + 1
+                )));
+            }
+
+            let mut overlay = toml::Table::new();
+            let mut output = toml::Table::new();
+            for (key, value) in entry {
+                let section = |key: &str, value: toml::Value| {
+                    toml::Value::Table(toml::Table::from_iter([(key.to_string(), value)]))
+                };
+                match (key.as_str(), value) {
+                    ("template", toml::Value::String(dir)) => {
+                        overlay.insert(key, section("dir", toml::Value::String(dir)));
+                    }
+                    ("toc", toml::Value::Boolean(enabled)) => {
+                        overlay.insert(key, section("enabled", toml::Value::Boolean(enabled)));
+                    }
+                    (name, value @ toml::Value::Table(_)) if TARGET_SECTIONS.contains(&name) => {
+                        overlay.insert(key, value);
+                    }
+                    (_, value) => {
+                        output.insert(key, value);
+                    }
+                }
+            }
+            overlay.insert("output".to_string(), toml::Value::Table(output));
+
+            let mut target = table.clone();
+            deep_merge_toml(&mut target, &overlay);
+            Ok(target)
+        })
+        .collect()
+}
+
+/// Deep-merge `[profile.<name>]` over the rest of the config table
+#[cfg(feature = "cli")]
+fn apply_profile(table: &mut toml::Table, name: &str) -> crate::Result<()> {
+    let profiles = match table.get("profile") {
+        Some(toml::Value::Table(profiles)) => profiles.clone(),
+        _ => toml::Table::new(),
+    };
+    match profiles.get(name) {
+        Some(toml::Value::Table(profile)) => {
+            deep_merge_toml(table, profile);
+            Ok(())
+        }
+        Some(_) => Err(crate::Error::Config(format!(
+            "Profile '{}' must be a table",
+            name
+        ))),
+        None => {
+            let mut available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            available.sort_unstable();
+            Err(crate::Error::Config(format!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            )))
+        }
+    }
+}
+
 /// Top-level project configuration from md2docx.toml
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
@@ -37,9 +340,31 @@ pub struct ProjectConfig {
     pub code: CodeSection,
     pub chapters: ChaptersSection,
     pub appendices: AppendicesSection,
+    pub parts: PartsSection,
     pub cover: CoverSection,
     pub mermaid: MermaidSection,
     pub math: MathSection,
+    pub images: ImagesSection,
+    pub assets: AssetsSection,
+    pub remote: RemoteSection,
+    /// Reusable markdown for `{!snippet:name}`, by name
+    pub snippets: BTreeMap<String, String>,
+    pub changelog: ChangelogSection,
+    /// Version history for `{!revisions}`, in the order written
+    pub revisions: Vec<RevisionEntry>,
+    pub signatures: SignaturesSection,
+    pub gallery: GallerySection,
+    pub header: HeaderFooterSection,
+    pub footer: HeaderFooterSection,
+    pub numbering: NumberingSection,
+    pub footnotes: FootnotesSection,
+    /// Paragraph spacing per style id (`[styles.BodyText]`)
+    pub styles: BTreeMap<String, StyleSection>,
+    pub pagination: PaginationSection,
+    pub i18n: I18nSection,
+    /// Build profile applied when loading (not read from the file)
+    #[serde(skip)]
+    pub profile: Option<String>,
 }
 
 /// Document metadata section
@@ -50,7 +375,7 @@ pub struct DocumentSection {
     pub subtitle: String,
     pub author: String,
     pub date: String,     // "auto" or specific date
-    pub language: String, // "en" or "th"
+    pub language: String, // "en", "th", "lo", "km", "my", "vi", "zh", "ja", "ko", "ar" or "he"
     pub version: String,
     pub page_width: String,
     pub page_height: String,
@@ -58,6 +383,10 @@ pub struct DocumentSection {
     pub page_margin_bottom: String,
     pub page_margin_left: String,
     pub page_margin_right: String,
+    /// Body text layout: "default" or "thai-official"
+    pub paragraph_preset: String,
+    /// Gaps before headings and after tables: "blank", "spacing" or "none"
+    pub spacers: String,
     /// User-defined custom variables (any extra keys in [document])
     /// These are available as {{key}} placeholders in cover templates and output filenames.
     #[serde(flatten)]
@@ -79,6 +408,8 @@ impl Default for DocumentSection {
             page_margin_bottom: "25.4mm".to_string(),
             page_margin_left: "25.4mm".to_string(),
             page_margin_right: "25.4mm".to_string(),
+            paragraph_preset: String::new(),
+            spacers: String::new(),
             extra: HashMap::new(),
         }
     }
@@ -103,6 +434,33 @@ impl DocumentSection {
             })
             .collect()
     }
+
+    /// Set a document value by key, as `--var key=value` does
+    ///
+    /// Known keys (`title`, `author`, `page_width`, ...) replace the field;
+    /// anything else becomes a user-defined variable.
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let val = value.into();
+        match key {
+            "title" => self.title = val,
+            "subtitle" => self.subtitle = val,
+            "author" => self.author = val,
+            "date" => self.date = val,
+            "language" => self.language = val,
+            "version" => self.version = val,
+            "page_width" => self.page_width = val,
+            "page_height" => self.page_height = val,
+            "page_margin_top" => self.page_margin_top = val,
+            "page_margin_bottom" => self.page_margin_bottom = val,
+            "page_margin_left" => self.page_margin_left = val,
+            "page_margin_right" => self.page_margin_right = val,
+            "paragraph_preset" => self.paragraph_preset = val,
+            "spacers" => self.spacers = val,
+            _ => {
+                self.extra.insert(key.to_string(), toml::Value::String(val));
+            }
+        }
+    }
 }
 
 /// Template configuration section
@@ -119,13 +477,56 @@ pub struct TemplateSection {
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 pub struct OutputSection {
+    /// Label for this output when `[[output]]` lists several
+    pub name: Option<String>,
     pub file: Option<PathBuf>,
+    /// Diagonal text watermark shown behind every page (e.g. "DRAFT")
+    pub watermark: Option<String>,
+    /// Leave out chapters and appendices whose frontmatter has any of these tags
+    pub exclude_tags: Vec<String>,
+    /// Only include chapters and appendices with at least one of these tags
+    pub only_tags: Vec<String>,
+    /// ZIP deflate level, 0 (fastest) to 9 (smallest)
+    pub compression_level: Option<u32>,
+    /// Store PNG/JPEG media uncompressed (faster builds, near-identical size)
+    pub store_media: bool,
+    /// Rendered body XML kept in memory, in megabytes; the rest is spilled to disk
+    pub memory_budget_mb: Option<usize>,
+    /// Byte-identical output for identical input (fixed timestamps, content-derived IDs)
+    pub reproducible: bool,
+    /// Fail the build on missing includes and images and on unresolved
+    /// references instead of leaving them out
+    pub strict: bool,
+    /// Fail the build if the DOCX references a relationship that isn't written
+    pub audit_rels: bool,
 }
 
 impl OutputSection {
+    /// Whether a file with these frontmatter `tags` belongs in this output
+    pub fn includes_tags(&self, tags: &[String]) -> bool {
+        if tags.iter().any(|t| self.exclude_tags.contains(t)) {
+            return false;
+        }
+        self.only_tags.is_empty() || tags.iter().any(|t| self.only_tags.contains(t))
+    }
+
     /// Resolve filename by expanding placeholders like {{currenttime:FORMAT}}, {{title}}, {{author}}, etc.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn resolve_filename(&self, project_config: Option<&ProjectConfig>) -> Option<PathBuf> {
+        self.resolve_filename_with(project_config, &[])
+    }
+
+    /// Resolve filename, also expanding project-level variables
+    ///
+    /// `vars` holds `(name, value)` pairs known only once the project is
+    /// discovered, such as `chapter_count` and `git_hash`; each replaces
+    /// `{{name}}`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn resolve_filename_with(
+        &self,
+        project_config: Option<&ProjectConfig>,
+        vars: &[(&str, String)],
+    ) -> Option<PathBuf> {
         self.file.as_ref().map(|p| {
             let path_str = p.to_string_lossy();
             let mut result = path_str.to_string();
@@ -138,8 +539,17 @@ impl OutputSection {
             // Expand document variable placeholders if project_config is provided
             if let Some(config) = project_config {
                 result = expand_document_placeholders(&result, &config.document);
+                result = result.replace(
+                    "{{profile}}",
+                    &sanitize_filename(config.profile.as_deref().unwrap_or("default")),
+                );
             }
 
+            for (name, value) in vars {
+                result = result.replace(&format!("{{{{{}}}}}", name), &sanitize_filename(value));
+            }
+            result = result.replace("{{today}}", &today());
+
             PathBuf::from(result)
         })
     }
@@ -170,10 +580,22 @@ fn expand_currenttime_placeholder(template: &str) -> String {
     result
 }
 
+/// Today's local date as YYYY-MM-DD
+#[cfg(not(target_arch = "wasm32"))]
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
 /// Expand document variable placeholders like {{title}}, {{author}}, {{version}}, etc.
+///
+/// `{{date}}` falls back to today's date when `date` is empty or "auto".
 #[cfg(not(target_arch = "wasm32"))]
 fn expand_document_placeholders(template: &str, document: &DocumentSection) -> String {
     let mut result = template.to_string();
+    let date = match document.date.trim() {
+        "" | "auto" => today(),
+        date => sanitize_filename(date),
+    };
 
     // Define all available placeholders and their values
     let placeholders = [
@@ -182,7 +604,7 @@ fn expand_document_placeholders(template: &str, document: &DocumentSection) -> S
         ("{{version}}", sanitize_filename(&document.version)),
         ("{{subtitle}}", sanitize_filename(&document.subtitle)),
         ("{{language}}", document.language.clone()),
-        ("{{date}}", sanitize_filename(&document.date)),
+        ("{{date}}", date),
     ];
 
     for (placeholder, value) in &placeholders {
@@ -241,6 +663,20 @@ pub struct TocSection {
     pub depth: u8,
     pub title: String,
     pub after_cover: bool, // If true, TOC comes after cover content
+    /// Extra paragraph styles to list, mapped to their TOC level (`{ "Title" = 1 }`)
+    pub include_styles: BTreeMap<String, u8>,
+    /// Heading styles to leave out (e.g. `["Heading 3"]`)
+    pub exclude_styles: Vec<String>,
+    /// Left indent per level ("0", "0.5cm", "1cm", ...)
+    pub indents: Vec<String>,
+    /// Dotted leader before right-aligned page numbers
+    pub dot_leader: bool,
+    /// "right" (default), "inline" or "none"
+    pub page_numbers: String,
+    /// Link entries to their headings
+    pub hyperlinks: bool,
+    /// List headings of front-matter chapters (`ch00_*.md`)
+    pub front_matter: bool,
 }
 
 impl Default for TocSection {
@@ -250,6 +686,13 @@ impl Default for TocSection {
             depth: 3,
             title: "Table of Contents".to_string(),
             after_cover: true,
+            include_styles: BTreeMap::new(),
+            exclude_styles: Vec::new(),
+            indents: Vec::new(),
+            dot_leader: true,
+            page_numbers: "right".to_string(),
+            hyperlinks: true,
+            front_matter: true,
         }
     }
 }
@@ -270,6 +713,8 @@ pub struct FontsSection {
     pub embed: bool,
     /// Directory containing .ttf/.otf font files to embed
     pub embed_dir: Option<PathBuf>,
+    /// Reduce embedded TrueType fonts to the glyphs the document uses
+    pub subset: bool,
 }
 
 impl Default for FontsSection {
@@ -285,6 +730,7 @@ impl Default for FontsSection {
             code_based_size: 10,
             embed: false,
             embed_dir: None,
+            subset: false,
         }
     }
 }
@@ -311,11 +757,50 @@ impl Default for CodeSection {
 }
 
 /// Chapters configuration section
+///
+/// ```toml
+/// [chapters]
+/// files = ["intro.md", "setup.md", "usage.md"]
+/// frontmatter = ["preface.md", "foreword.md"]
+/// backmatter = ["glossary.md", "ap01_faq.md"]
+/// ```
+///
+/// `files` lists the chapters in order instead of discovering them with
+/// `pattern`. Front matter comes after the cover and back matter after the
+/// appendices; files listed there are taken out of the chapters and
+/// appendices. Front matter pages are numbered with `frontmatter_pages` and
+/// the chapters restart at 1; back matter continues the chapters' numbering
+/// unless `backmatter_pages` is set.
+///
+/// `exclude` globs (`["drafts/**", "*.draft.md"]`), together with the lines
+/// of the project's `.md2docxignore`, keep files out of discovery; files
+/// listed by name above are still included.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct ChaptersSection {
     pub pattern: String,
     pub sort: String,
+    /// Chapter files in reading order, relative to the project directory
+    pub files: Vec<PathBuf>,
+    /// Glob patterns of files and directories never to discover
+    pub exclude: Vec<String>,
+    /// Preface, foreword and the like, before the chapters
+    pub frontmatter: Vec<PathBuf>,
+    /// Glossary, index and the like, after the chapters and appendices
+    pub backmatter: Vec<PathBuf>,
+    /// Page number format of the front matter (`lowerRoman`, `upperRoman`, ...)
+    pub frontmatter_pages: String,
+    /// Page number format of the back matter, restarting at 1
+    pub backmatter_pages: Option<String>,
+    /// Where each chapter starts: "nextPage", "oddPage" (duplex printing),
+    /// "evenPage" or "continuous"
+    pub chapter_break: String,
+    /// Start each H1 on a new page, without needing `---` before it
+    pub h1_page_break: bool,
+    /// Keep each H2 on the same page as the paragraph after it
+    pub h2_keep_with_next: bool,
+    /// Per-chapter settings keyed by file name or stem (`[chapters.overrides.ch02_setup]`)
+    pub overrides: BTreeMap<String, ChapterOverride>,
 }
 
 impl Default for ChaptersSection {
@@ -323,6 +808,60 @@ impl Default for ChaptersSection {
         Self {
             pattern: "ch*_*.md".to_string(),
             sort: "numeric".to_string(),
+            files: Vec::new(),
+            exclude: Vec::new(),
+            frontmatter: Vec::new(),
+            backmatter: Vec::new(),
+            frontmatter_pages: "lowerRoman".to_string(),
+            backmatter_pages: None,
+            chapter_break: "nextPage".to_string(),
+            h1_page_break: false,
+            h2_keep_with_next: false,
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+impl ChaptersSection {
+    /// Overrides configured for the chapter file `path`
+    pub fn override_for(&self, path: &Path) -> Option<&ChapterOverride> {
+        let name = path.file_name()?.to_str()?;
+        let stem = path.file_stem()?.to_str()?;
+        self.overrides
+            .get(name)
+            .or_else(|| self.overrides.get(stem))
+    }
+}
+
+/// Settings for a single chapter
+///
+/// Set in `[chapters.overrides.<file>]` or in the chapter's own
+/// frontmatter (`font:`, `restart_numbering:`, `page_format:`, `toc:`,
+/// `header:`); the config table wins where both set a value.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ChapterOverride {
+    /// Font for the chapter's text
+    pub font: Option<String>,
+    /// Restart page numbering at this number
+    pub restart_numbering: Option<u32>,
+    /// Page number format of the chapter (`lowerRoman`, `decimal`, ...)
+    pub page_format: Option<String>,
+    /// List the chapter's headings in the TOC
+    pub toc: Option<bool>,
+    /// Header/footer variant: "default" or "none"
+    pub header: Option<String>,
+}
+
+impl ChapterOverride {
+    /// Fill unset values from `other`
+    pub fn or(self, other: ChapterOverride) -> ChapterOverride {
+        ChapterOverride {
+            font: self.font.or(other.font),
+            restart_numbering: self.restart_numbering.or(other.restart_numbering),
+            page_format: self.page_format.or(other.page_format),
+            toc: self.toc.or(other.toc),
+            header: self.header.or(other.header),
         }
     }
 }
@@ -344,6 +883,34 @@ impl Default for AppendicesSection {
     }
 }
 
+/// Parts configuration section
+///
+/// Top-level directories matching `pattern` (`part-01-intro/`) are parts:
+/// each starts with a divider page and holds numbered chapter files
+/// (`01-overview.md`). The divider is the part's own `part.md` when it has
+/// one, else `template`, else a heading with the part label and title;
+/// `{{part}}`, `{{part_number}}` and `{{part_title}}` are filled in.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PartsSection {
+    /// Glob pattern for part directory names
+    pub pattern: String,
+    /// Markdown file used as the divider page of every part
+    pub template: Option<PathBuf>,
+    /// Number parts with Roman numerals (Part I, Part II) instead of digits
+    pub roman: bool,
+}
+
+impl Default for PartsSection {
+    fn default() -> Self {
+        Self {
+            pattern: "part*".to_string(),
+            template: None,
+            roman: true,
+        }
+    }
+}
+
 /// Cover page configuration section
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
@@ -403,12 +970,412 @@ impl Default for MathSection {
     }
 }
 
+/// Image embedding configuration section
+///
+/// Oversized photos and screenshots are resampled before they are embedded:
+///
+/// ```toml
+/// [images]
+/// max_dpi = 220     # at the size the image is shown
+/// max_width = 2000  # pixels
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImagesSection {
+    /// Highest resolution kept at the displayed size
+    pub max_dpi: Option<u32>,
+    /// Widest image kept, in pixels
+    pub max_width: Option<u32>,
+    /// JPEG quality (1-100) used when re-encoding (default: 85)
+    pub jpeg_quality: u8,
+}
+
+impl Default for ImagesSection {
+    fn default() -> Self {
+        Self {
+            max_dpi: None,
+            max_width: None,
+            jpeg_quality: 85,
+        }
+    }
+}
+
+/// Shared assets directory
+///
+/// Images written as `@assets/logo.png` are read from this directory, relative
+/// to the project root, whichever file references them:
+///
+/// ```toml
+/// [assets]
+/// dir = "shared/images"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AssetsSection {
+    /// Directory `@assets/` paths point into (default: "assets")
+    pub dir: PathBuf,
+}
+
+impl Default for AssetsSection {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("assets"),
+        }
+    }
+}
+
+/// Markdown included from URLs (`{!include:https://...}`)
+///
+/// Downloads are cached in `cache_dir`, relative to the project root, and
+/// fetched again once older than `max_age_hours`. Offline builds only read
+/// the cache:
+///
+/// ```toml
+/// [remote]
+/// offline = true
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RemoteSection {
+    /// Download cache directory (default: ".md2docx/cache")
+    pub cache_dir: PathBuf,
+    /// Never download, only use cached copies
+    pub offline: bool,
+    /// Hours a cached download is used before fetching it again (default: 24)
+    pub max_age_hours: u64,
+}
+
+impl Default for RemoteSection {
+    fn default() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".md2docx/cache"),
+            offline: false,
+            max_age_hours: 24,
+        }
+    }
+}
+
+/// Revision history appendix built from the git log (needs the `git` feature)
+///
+/// Lists the commits that changed the project's markdown files, newest
+/// first, with their date, tags, author and summary:
+///
+/// ```toml
+/// [changelog]
+/// enabled = true
+/// types = ["feat", "fix", "docs"]  # conventional commit types to list
+/// max_entries = 30
+/// ```
+///
+/// With `types` set, only commits whose summary starts with one of them
+/// (`docs: ...`, `fix(setup)!: ...`) are listed; by default every commit is.
+///
+/// `chapters = true` looks up who last changed each chapter file and when,
+/// for `{chapter_author}` and `{chapter_date}` in headers and footers and a
+/// table of chapters in the appendix. It works without `enabled`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChangelogSection {
+    pub enabled: bool,
+    /// Appendix heading; defaults to the language's "Revision History"
+    pub title: Option<String>,
+    pub types: Vec<String>,
+    /// Most recent commits listed; all when unset
+    pub max_entries: Option<usize>,
+    /// Last author and edit date per chapter
+    pub chapters: bool,
+}
+
+/// One `[[revisions]]` row of the `{!revisions}` table
+///
+/// ```toml
+/// [[revisions]]
+/// version = "1.0"
+/// date = "2025-01-15"
+/// author = "Somchai Jaidee"
+/// description = "First issue"
+/// ```
+///
+/// A `YYYY-MM-DD` date is written in the document language's date format;
+/// anything else is kept as written.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RevisionEntry {
+    pub version: String,
+    pub date: String,
+    pub author: String,
+    pub description: String,
+}
+
+/// Signers of the `{!signatures}` approval table
+///
+/// Each signer is a column with a signature line and their name, position
+/// and date:
+///
+/// ```toml
+/// [[signatures.signers]]
+/// name = "Somchai Jaidee"
+/// position = "Project Engineer"
+///
+/// [[signatures.signers]]
+/// role = "Checked by"
+/// name = "Suda Rakdee"
+/// ```
+///
+/// Without signers the table has blank "Prepared by", "Reviewed by" and
+/// "Approved by" columns to fill in by hand.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SignaturesSection {
+    pub signers: Vec<Signer>,
+}
+
+/// One column of the `{!signatures}` table; blank fields become dotted lines
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Signer {
+    /// Column heading; defaults to "Prepared by", "Reviewed by" and
+    /// "Approved by" for the first three signers
+    pub role: Option<String>,
+    pub name: String,
+    pub position: String,
+    pub date: String,
+}
+
+/// `[gallery]` styles Word offers to people editing the generated document
+///
+/// ```toml
+/// [gallery]
+/// styles = ["Heading1", "Heading2", "BodyText", "Quote", "Caption"]
+/// hide_builtin = true
+/// lock_builtin = true
+/// ```
+///
+/// Styles are named by id or by name ("Body Text"). `lock_builtin` takes
+/// effect when formatting restrictions are turned on in Word.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GallerySection {
+    /// Styles in the Quick Styles gallery; every md2docx style when unset
+    pub styles: Option<Vec<String>>,
+    /// Hide Word's other built-in styles until they are used
+    pub hide_builtin: bool,
+    /// Lock Word's other built-in styles
+    pub lock_builtin: bool,
+}
+
+/// `[header]` / `[footer]` content, used when the template has no header-footer.docx
+///
+/// Each slot is text with `{{placeholder}}` values from `[document]` and the
+/// Word fields `{page}`, `{pages}`, `{chapter}`, `{chapter_author}` and
+/// `{chapter_date}`:
+///
+/// ```toml
+/// [footer]
+/// left = "{{title}}"
+/// center = "page {page} of {pages}"
+/// ```
+///
+/// Setting any slot replaces the built-in header (or footer); unset slots
+/// are left empty.
+///
+/// `logo` adds a picture in front of one slot's text. It is 1cm tall unless
+/// `logo_width` or `logo_height` is set:
+///
+/// ```toml
+/// [header]
+/// logo = "assets/logo.png"
+/// logo_position = "right"
+/// logo_height = "12mm"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HeaderFooterSection {
+    pub left: Option<String>,
+    pub center: Option<String>,
+    pub right: Option<String>,
+    /// Image file, relative to the project directory
+    pub logo: Option<PathBuf>,
+    /// Slot holding the logo: left (default), center or right
+    pub logo_position: Option<String>,
+    /// Logo width, e.g. "3cm"
+    pub logo_width: Option<String>,
+    /// Logo height, e.g. "12mm"
+    pub logo_height: Option<String>,
+}
+
+impl HeaderFooterSection {
+    /// Whether any slot or the logo is configured
+    pub fn is_set(&self) -> bool {
+        self.left.is_some() || self.center.is_some() || self.right.is_some() || self.logo.is_some()
+    }
+}
+
+/// `[footnotes]` numbering of footnotes and endnotes and the line above them
+///
+/// ```toml
+/// [footnotes]
+/// format = "symbols"      # *, †, ‡, §
+/// endnote_format = "decimal"
+/// restart = "section"
+/// separator = ""          # no line
+/// ```
+///
+/// Formats are the `[numbering]` ones plus `symbols`. `restart` is
+/// continuous (the default), section or page; endnotes do not restart per
+/// page. `separator` is text drawn instead of Word's short line.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FootnotesSection {
+    pub format: Option<String>,
+    pub endnote_format: Option<String>,
+    pub restart: Option<String>,
+    pub separator: Option<String>,
+}
+
+/// `[numbering]` list formats, one entry per nesting level, and the page
+/// number format
+///
+/// ```toml
+/// [numbering]
+/// ordered = ["thaiLetters", "thaiNumbers", "lowerRoman"]
+/// bullets = ["•", "◦", "▪"]
+/// pages = "thaiNumbers"
+/// ```
+///
+/// Ordered and page formats are Word number formats: decimal, lowerLetter,
+/// upperLetter, lowerRoman, upperRoman, thaiLetters (ก ข ค), thaiNumbers
+/// (๑ ๒ ๓) and thaiCounting. Deeper levels repeat the list from the start.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NumberingSection {
+    pub ordered: Vec<String>,
+    pub bullets: Vec<String>,
+    pub pages: Option<String>,
+}
+
+/// `[styles.<StyleId>]` paragraph spacing of one style
+///
+/// ```toml
+/// [styles.BodyText]
+/// space_before = 0
+/// space_after = 6
+/// line_spacing = 1.5
+///
+/// [styles.Heading1]
+/// space_before = 24
+/// ```
+///
+/// Spaces are in points and `line_spacing` is a multiple of single line
+/// spacing. Keys are style ids: BodyText, Normal, Heading1-Heading6, Code,
+/// CodeFilename, Caption, TOC1-TOC3 and the other built-in styles. Unset
+/// values keep the built-in spacing.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StyleSection {
+    pub space_before: Option<f32>,
+    pub space_after: Option<f32>,
+    pub line_spacing: Option<f32>,
+}
+
+/// `[pagination]` how paragraphs and tables break across pages
+///
+/// ```toml
+/// [pagination]
+/// widow_control = true
+/// table_rows_cant_split = true
+/// repeat_table_header = true
+/// table_continuation_rows = 25
+/// code_continuation_lines = 45
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PaginationSection {
+    /// Don't leave the first or last line of a paragraph alone on a page
+    pub widow_control: bool,
+    /// Keep each table row on one page instead of splitting it
+    pub table_rows_cant_split: bool,
+    /// Repeat a table's header row at the top of each page it runs onto
+    pub repeat_table_header: bool,
+    /// Estimated data rows per page; longer tables are split and continue
+    /// on the next page under a "Table N (Continued)" caption
+    pub table_continuation_rows: Option<usize>,
+    /// Estimated code lines per page; longer code blocks are split and
+    /// continue on the next page under a "(Continued)" line
+    pub code_continuation_lines: Option<usize>,
+}
+
+impl Default for PaginationSection {
+    fn default() -> Self {
+        Self {
+            widow_control: true,
+            table_rows_cant_split: false,
+            repeat_table_header: true,
+            table_continuation_rows: None,
+            code_continuation_lines: None,
+        }
+    }
+}
+
+/// `[i18n]` localization settings
+///
+/// ```toml
+/// [i18n]
+/// date_format = "%-d %B %Y"
+/// smart_punctuation = true
+/// quotes = "«»‹›"
+/// punctuation_spacing = "narrow"
+///
+/// [i18n.strings]
+/// toc = "สารบัญเรื่อง"
+/// figure = "ภาพที่"
+/// chapter = "บทที่ {n}"
+/// ```
+///
+/// `date_format` renders `{{date}}` on covers and in headers; it defaults to
+/// the document language's format (Thai: "28 มกราคม พ.ศ. 2568"). See
+/// `i18n::date` for the supported specifiers.
+///
+/// `smart_punctuation` turns straight quotes, `--`, `---` and `...` into
+/// typographic punctuation. Quotes default to the document language's
+/// (“ ” for English and Thai, 「 」 for Japanese); `quotes` overrides them
+/// with two or four characters (primary, then inner pair).
+/// `punctuation_spacing = "narrow"` adds French-style narrow no-break spaces
+/// inside quotes and before `; : ! ?`; the default is "none".
+///
+/// `strings` replaces generated text: toc, figure, table, chapter, appendix,
+/// index, references, continued, part, summary and the revision history's
+/// revision_history, revision_date, revision_version, revision_author,
+/// revision_description and revision_chapter. `{n}` in `chapter` and `part`
+/// is the chapter or part number.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct I18nSection {
+    pub date_format: Option<String>,
+    pub smart_punctuation: bool,
+    pub quotes: Option<String>,
+    pub punctuation_spacing: Option<String>,
+    pub strings: HashMap<String, String>,
+}
+
 impl ProjectConfig {
     /// Load config from a TOML file
+    ///
+    /// Files named by `extends = "base.toml"` and `include = ["a.toml", ...]`
+    /// are deep-merged first, relative to this file; its own values win.
     #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
     pub fn from_file(path: &Path) -> crate::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        Self::parse_toml(&content)
+        let table = read_config_table(path, "config")?;
+        Self::first_target(table, "Failed to parse config")
+    }
+
+    /// Deserialize the first output target of a config table
+    #[cfg(feature = "cli")]
+    fn first_target(table: toml::Table, context: &str) -> crate::Result<Self> {
+        let target = split_output_targets(table)?.swap_remove(0);
+        toml::Value::Table(target)
+            .try_into()
+            .map_err(|e| crate::Error::Config(format!("{}: {}", context, e)))
     }
 
     /// Load layered config: template md2docx.toml as base defaults,
@@ -420,39 +1387,90 @@ impl ProjectConfig {
         template_config_path: Option<&Path>,
         root_config_path: Option<&Path>,
     ) -> crate::Result<Self> {
-        match (template_config_path, root_config_path) {
-            (Some(tmpl), Some(root)) if tmpl.exists() && root.exists() => {
-                let tmpl_content = std::fs::read_to_string(tmpl)?;
-                let root_content = std::fs::read_to_string(root)?;
-
-                let mut base_table: toml::Table = toml::from_str(&tmpl_content)
-                    .map_err(|e| crate::Error::Config(format!(
-                        "Failed to parse template config {}: {}", tmpl.display(), e
-                    )))?;
-                let override_table: toml::Table = toml::from_str(&root_content)
-                    .map_err(|e| crate::Error::Config(format!(
-                        "Failed to parse root config {}: {}", root.display(), e
-                    )))?;
+        Self::from_files_layered_with_profile(template_config_path, root_config_path, None)
+    }
 
-                deep_merge_toml(&mut base_table, &override_table);
+    /// Load layered config and apply a build profile on top
+    ///
+    /// A profile is a `[profile.<name>]` table holding any config keys; it is
+    /// deep-merged over the layered config:
+    ///
+    /// ```toml
+    /// [profile.draft]
+    /// output.watermark = "DRAFT"
+    /// mermaid.output_format = "svg"
+    /// fonts.embed = false
+    /// ```
+    ///
+    /// Returns an error if `profile` is given but not defined. With
+    /// `[[output]]` entries this is the config of the first output; see
+    /// [`ProjectConfig::targets_from_files_layered`].
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    pub fn from_files_layered_with_profile(
+        template_config_path: Option<&Path>,
+        root_config_path: Option<&Path>,
+        profile: Option<&str>,
+    ) -> crate::Result<Self> {
+        Self::targets_from_files_layered(template_config_path, root_config_path, profile)
+            .map(|mut targets| targets.swap_remove(0))
+    }
 
-                toml::Value::Table(base_table)
-                    .try_into()
-                    .map_err(|e| crate::Error::Config(format!(
-                        "Failed to deserialize merged config: {}", e
-                    )))
+    /// Load layered config as one config per output target
+    ///
+    /// Each `[[output]]` entry produces a separate document:
+    ///
+    /// ```toml
+    /// [[output]]
+    /// file = "manual.docx"
+    ///
+    /// [[output]]
+    /// file = "summary.docx"
+    /// template = "template-exec"
+    /// toc = false
+    /// chapters.pattern = "ch0[12]_*.md"
+    /// ```
+    ///
+    /// A plain `[output]` table gives a single target. The profile, if any,
+    /// is applied to every target.
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    pub fn targets_from_files_layered(
+        template_config_path: Option<&Path>,
+        root_config_path: Option<&Path>,
+        profile: Option<&str>,
+    ) -> crate::Result<Vec<Self>> {
+        let table = match (template_config_path, root_config_path) {
+            (Some(tmpl), Some(root)) if tmpl.exists() && root.exists() => {
+                let mut base_table = read_config_table(tmpl, "template config")?;
+                let override_table = read_config_table(root, "root config")?;
+                deep_merge_toml(&mut base_table, &override_table);
+                base_table
             }
-            (_, Some(root)) if root.exists() => Self::from_file(root),
-            (Some(tmpl), _) if tmpl.exists() => Self::from_file(tmpl),
-            _ => Ok(Self::default()),
-        }
+            (_, Some(root)) if root.exists() => read_config_table(root, "config")?,
+            (Some(tmpl), _) if tmpl.exists() => read_config_table(tmpl, "config")?,
+            _ => toml::Table::new(),
+        };
+
+        split_output_targets(table)?
+            .into_iter()
+            .map(|mut target| {
+                if let Some(name) = profile {
+                    apply_profile(&mut target, name)?;
+                }
+                let mut config: Self = toml::Value::Table(target).try_into().map_err(|e| {
+                    crate::Error::Config(format!("Failed to deserialize merged config: {}", e))
+                })?;
+                config.profile = profile.map(str::to_string);
+                Ok(config)
+            })
+            .collect()
     }
 
     /// Parse config from a TOML string
     #[cfg(feature = "cli")]
     pub fn parse_toml(toml_content: &str) -> crate::Result<Self> {
-        toml::from_str(toml_content)
-            .map_err(|e| crate::Error::Config(format!("Failed to parse config: {}", e)))
+        let table: toml::Table = toml::from_str(toml_content)
+            .map_err(|e| crate::Error::Config(format!("Failed to parse config: {}", e)))?;
+        Self::first_target(table, "Failed to parse config")
     }
 
     /// Get the effective language (default to "en" if not specified)
@@ -799,6 +1817,40 @@ title = "Missing closing bracket"
         assert_eq!(resolved_str, "My Document-v1.2.3-John Doe.docx");
     }
 
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_resolve_filename_with_project_vars() {
+        use super::ProjectConfig;
+
+        let mut project_config = ProjectConfig::default();
+        project_config.document.language = "th".to_string();
+        project_config.profile = Some("draft".to_string());
+
+        let mut output = OutputSection {
+            file: Some(PathBuf::from(
+                "out/{{profile}}-{{language}}-{{chapter_count}}ch-{{git_hash}}-{{date}}.docx",
+            )),
+            ..Default::default()
+        };
+
+        let vars = [
+            ("chapter_count", "3".to_string()),
+            ("git_hash", "1a2b3c4".to_string()),
+        ];
+        let resolved = output
+            .resolve_filename_with(Some(&project_config), &vars)
+            .unwrap();
+        assert_eq!(
+            resolved,
+            PathBuf::from(format!("out/draft-th-3ch-1a2b3c4-{}.docx", today()))
+        );
+
+        project_config.profile = None;
+        output.file = Some(PathBuf::from("{{profile}}.docx"));
+        let resolved = output.resolve_filename(Some(&project_config)).unwrap();
+        assert_eq!(resolved, PathBuf::from("default.docx"));
+    }
+
     #[test]
     #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
     fn test_sanitize_filename() {
@@ -896,4 +1948,587 @@ dpi = 300
         assert_eq!(config.mermaid.output_format, "svg");
         assert_eq!(config.mermaid.dpi, 300);
     }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_apply_profile() {
+        let toml = r##"
+[output]
+file = "book.docx"
+
+[fonts]
+embed = true
+
+[profile.draft]
+output.watermark = "DRAFT"
+mermaid.output_format = "svg"
+fonts.embed = false
+
+[profile.final]
+output.file = "book-final.docx"
+"##;
+        let mut table: toml::Table = toml::from_str(toml).unwrap();
+        apply_profile(&mut table, "draft").unwrap();
+        let config: ProjectConfig = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(config.output.watermark.as_deref(), Some("DRAFT"));
+        assert_eq!(config.output.file, Some(PathBuf::from("book.docx")));
+        assert_eq!(config.mermaid.output_format, "svg");
+        assert!(!config.fonts.embed);
+
+        let mut table: toml::Table = toml::from_str(toml).unwrap();
+        let err = apply_profile(&mut table, "review").unwrap_err().to_string();
+        assert!(err.contains("Unknown profile 'review' (available: draft, final)"));
+    }
+
+    #[test]
+    fn test_document_set_var() {
+        let mut config = ProjectConfig::default();
+        config.document.title = "Manual".into();
+        config.output.file = Some(PathBuf::from("{{customer}}_v{{version}}.docx"));
+
+        config.document.set("version", "2.3");
+        config.document.set("customer", "ACME");
+        assert_eq!(config.document.version, "2.3");
+        assert_eq!(config.document.title, "Manual");
+        assert_eq!(
+            config.output.resolve_filename(Some(&config)),
+            Some(PathBuf::from("ACME_v2.3.docx"))
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_config_extends_and_include() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("shared")).unwrap();
+        std::fs::create_dir_all(root.join("project")).unwrap();
+        std::fs::write(
+            root.join("shared/base.toml"),
+            "include = [\"fonts.toml\"]\n[document]\nauthor = \"ACME\"\nlanguage = \"th\"\n[toc]\nenabled = true\ndepth = 2\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("shared/fonts.toml"),
+            "[fonts]\ndefault = \"Noto Sans Thai\"\ncode = \"Fira Code\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("project/extra.toml"),
+            "[fonts]\ncode = \"JetBrains Mono\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("project/md2docx.toml"),
+            "extends = \"../shared/base.toml\"\ninclude = [\"extra.toml\"]\n[document]\ntitle = \"Manual\"\n[toc]\ndepth = 3\n",
+        )
+        .unwrap();
+
+        let config = ProjectConfig::from_file(&root.join("project/md2docx.toml")).unwrap();
+        assert_eq!(config.document.title, "Manual");
+        assert_eq!(config.document.author, "ACME");
+        assert_eq!(config.document.language, "th");
+        assert!(config.toc.enabled);
+        assert_eq!(config.toc.depth, 3);
+        assert_eq!(config.fonts.default, "Noto Sans Thai");
+        assert_eq!(config.fonts.code, "JetBrains Mono");
+        assert!(config.document.extra.is_empty());
+
+        std::fs::write(root.join("shared/fonts.toml"), "extends = \"base.toml\"\n").unwrap();
+        let err = ProjectConfig::from_file(&root.join("project/md2docx.toml"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Config inheritance cycle"), "{}", err);
+    }
+
+    #[test]
+    fn test_output_tag_filter() {
+        let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let config =
+            ProjectConfig::parse_toml("[output]\nexclude_tags = [\"internal\"]\n").unwrap();
+        assert!(config.output.includes_tags(&[]));
+        assert!(config.output.includes_tags(&tags(&["public"])));
+        assert!(!config.output.includes_tags(&tags(&["public", "internal"])));
+
+        let config = ProjectConfig::parse_toml("[output]\nonly_tags = [\"exec\"]\n").unwrap();
+        assert!(!config.output.includes_tags(&[]));
+        assert!(config.output.includes_tags(&tags(&["exec"])));
+    }
+
+    #[test]
+    fn test_images_section() {
+        let config = ProjectConfig::parse_toml("[document]\ntitle = \"T\"\n").unwrap();
+        assert_eq!(config.images.max_dpi, None);
+        assert_eq!(config.images.jpeg_quality, 85);
+
+        let config =
+            ProjectConfig::parse_toml("[images]\nmax_dpi = 220\nmax_width = 1600\n").unwrap();
+        assert_eq!(config.images.max_dpi, Some(220));
+        assert_eq!(config.images.max_width, Some(1600));
+    }
+
+    #[test]
+    fn test_output_compression() {
+        let config = ProjectConfig::parse_toml("[output]\nfile = \"a.docx\"\n").unwrap();
+        assert_eq!(config.output.compression_level, None);
+        assert!(!config.output.store_media);
+
+        let config =
+            ProjectConfig::parse_toml("[output]\ncompression_level = 1\nstore_media = true\n")
+                .unwrap();
+        assert_eq!(config.output.compression_level, Some(1));
+        assert!(config.output.store_media);
+    }
+
+    #[test]
+    fn test_output_memory_budget() {
+        let config = ProjectConfig::parse_toml("[output]\nfile = \"a.docx\"\n").unwrap();
+        assert_eq!(config.output.memory_budget_mb, None);
+
+        let config = ProjectConfig::parse_toml("[output]\nmemory_budget_mb = 64\n").unwrap();
+        assert_eq!(config.output.memory_budget_mb, Some(64));
+    }
+
+    #[test]
+    fn test_output_reproducible() {
+        let config = ProjectConfig::parse_toml("[output]\nfile = \"a.docx\"\n").unwrap();
+        assert!(!config.output.reproducible);
+
+        let config = ProjectConfig::parse_toml("[output]\nreproducible = true\n").unwrap();
+        assert!(config.output.reproducible);
+        assert!(!config.output.audit_rels);
+
+        let config = ProjectConfig::parse_toml("[output]\naudit_rels = true\n").unwrap();
+        assert!(config.output.audit_rels);
+    }
+
+    #[test]
+    fn test_output_strict() {
+        let config = ProjectConfig::parse_toml("[output]\nfile = \"a.docx\"\n").unwrap();
+        assert!(!config.output.strict);
+
+        let config = ProjectConfig::parse_toml("[output]\nstrict = true\n").unwrap();
+        assert!(config.output.strict);
+    }
+
+    #[test]
+    fn test_styles_section() {
+        let config = ProjectConfig::default();
+        assert!(config.styles.is_empty());
+
+        let config = ProjectConfig::parse_toml(
+            "[styles.BodyText]\nline_spacing = 1.5\nspace_after = 6\n\n[styles.Heading1]\nspace_before = 24\n",
+        )
+        .unwrap();
+        let body = &config.styles["BodyText"];
+        assert_eq!(body.line_spacing, Some(1.5));
+        assert_eq!(body.space_after, Some(6.0));
+        assert_eq!(body.space_before, None);
+        assert_eq!(config.styles["Heading1"].space_before, Some(24.0));
+    }
+
+    #[test]
+    fn test_pagination_section() {
+        let config = ProjectConfig::default();
+        assert!(config.pagination.widow_control);
+        assert!(!config.pagination.table_rows_cant_split);
+        assert!(config.pagination.repeat_table_header);
+
+        let config = ProjectConfig::parse_toml(
+            "[pagination]\nwidow_control = false\ntable_rows_cant_split = true\nrepeat_table_header = false\n",
+        )
+        .unwrap();
+        assert!(!config.pagination.widow_control);
+        assert!(config.pagination.table_rows_cant_split);
+        assert!(!config.pagination.repeat_table_header);
+        assert_eq!(config.pagination.table_continuation_rows, None);
+        assert_eq!(config.pagination.code_continuation_lines, None);
+
+        let config =
+            ProjectConfig::parse_toml("[pagination]\ntable_continuation_rows = 25\n").unwrap();
+        assert_eq!(config.pagination.table_continuation_rows, Some(25));
+
+        let config =
+            ProjectConfig::parse_toml("[pagination]\ncode_continuation_lines = 45\n").unwrap();
+        assert_eq!(config.pagination.code_continuation_lines, Some(45));
+    }
+
+    #[test]
+    fn test_paragraph_preset() {
+        let config = ProjectConfig::default();
+        assert!(config.document.paragraph_preset.is_empty());
+
+        let config =
+            ProjectConfig::parse_toml("[document]\nparagraph_preset = \"thai-official\"\n")
+                .unwrap();
+        assert_eq!(config.document.paragraph_preset, "thai-official");
+        assert!(!config.document.extra.contains_key("paragraph_preset"));
+
+        let config = ProjectConfig::parse_toml("[document]\nspacers = \"spacing\"\n").unwrap();
+        assert_eq!(config.document.spacers, "spacing");
+        assert!(!config.document.extra.contains_key("spacers"));
+    }
+
+    #[test]
+    fn test_fonts_subset() {
+        let config = ProjectConfig::default();
+        assert!(!config.fonts.subset);
+
+        let config = ProjectConfig::parse_toml("[fonts]\nembed = true\nsubset = true\n").unwrap();
+        assert!(config.fonts.embed);
+        assert!(config.fonts.subset);
+    }
+
+    #[test]
+    fn test_revisions() {
+        let config = ProjectConfig::parse_toml(
+            "[[revisions]]\nversion = \"1.0\"\ndate = \"2025-01-15\"\n\n[[revisions]]\nversion = \"1.1\"\nauthor = \"Suda\"\ndescription = \"Budget update\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.revisions.len(), 2);
+        assert_eq!(config.revisions[0].date, "2025-01-15");
+        assert_eq!(config.revisions[1].author, "Suda");
+        assert!(config.revisions[1].date.is_empty());
+    }
+
+    #[test]
+    fn test_gallery_section() {
+        let config = ProjectConfig::parse_toml("").unwrap();
+        assert_eq!(config.gallery.styles, None);
+        assert!(!config.gallery.hide_builtin);
+
+        let config = ProjectConfig::parse_toml(
+            "[gallery]\nstyles = [\"Heading1\", \"Body Text\"]\nhide_builtin = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.gallery.styles.as_deref(),
+            Some(&["Heading1".to_string(), "Body Text".to_string()][..])
+        );
+        assert!(config.gallery.hide_builtin);
+        assert!(!config.gallery.lock_builtin);
+    }
+
+    #[test]
+    fn test_signatures_section() {
+        let config = ProjectConfig::parse_toml("").unwrap();
+        assert!(config.signatures.signers.is_empty());
+
+        let config = ProjectConfig::parse_toml(
+            "[[signatures.signers]]\nname = \"Somchai\"\n\n[[signatures.signers]]\nrole = \"Checked by\"\nposition = \"Manager\"\n",
+        )
+        .unwrap();
+        let signers = &config.signatures.signers;
+        assert_eq!(signers.len(), 2);
+        assert_eq!(signers[0].name, "Somchai");
+        assert_eq!(signers[0].role, None);
+        assert_eq!(signers[1].role.as_deref(), Some("Checked by"));
+        assert_eq!(signers[1].position, "Manager");
+    }
+
+    #[test]
+    fn test_changelog_section() {
+        let config = ProjectConfig::default();
+        assert!(!config.changelog.enabled);
+        assert!(config.changelog.types.is_empty());
+
+        let config = ProjectConfig::parse_toml(
+            "[changelog]\nenabled = true\ntitle = \"History\"\ntypes = [\"feat\", \"fix\"]\nmax_entries = 10\n",
+        )
+        .unwrap();
+        assert!(config.changelog.enabled);
+        assert_eq!(config.changelog.title.as_deref(), Some("History"));
+        assert_eq!(config.changelog.types, ["feat", "fix"]);
+        assert_eq!(config.changelog.max_entries, Some(10));
+        assert!(!config.changelog.chapters);
+
+        let config = ProjectConfig::parse_toml("[changelog]\nchapters = true\n").unwrap();
+        assert!(config.changelog.chapters);
+        assert!(!config.changelog.enabled);
+    }
+
+    #[test]
+    fn test_footnotes_section() {
+        let config = ProjectConfig::parse_toml("").unwrap();
+        assert_eq!(config.footnotes.format, None);
+        assert_eq!(config.footnotes.separator, None);
+
+        let config = ProjectConfig::parse_toml(
+            "[footnotes]\nformat = \"symbols\"\nrestart = \"section\"\nseparator = \"\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.footnotes.format.as_deref(), Some("symbols"));
+        assert_eq!(config.footnotes.endnote_format, None);
+        assert_eq!(config.footnotes.restart.as_deref(), Some("section"));
+        assert_eq!(config.footnotes.separator.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_numbering_section() {
+        let config = ProjectConfig::parse_toml(
+            "[numbering]\nordered = [\"decimal\", \"thaiLetters\"]\nbullets = [\"-\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.numbering.ordered, vec!["decimal", "thaiLetters"]);
+        assert_eq!(config.numbering.bullets, vec!["-"]);
+        assert_eq!(config.numbering.pages, None);
+
+        let config = ProjectConfig::parse_toml("[numbering]\npages = \"thaiNumbers\"\n").unwrap();
+        assert_eq!(config.numbering.pages.as_deref(), Some("thaiNumbers"));
+        assert!(ProjectConfig::default().numbering.ordered.is_empty());
+    }
+
+    #[test]
+    fn test_i18n_section() {
+        let config = ProjectConfig::parse_toml("[i18n]\ndate_format = \"%e %b %Y\"\n").unwrap();
+        assert_eq!(config.i18n.date_format.as_deref(), Some("%e %b %Y"));
+        assert!(config.i18n.strings.is_empty());
+
+        let config = ProjectConfig::parse_toml("[i18n.strings]\nfigure = \"ภาพที่\"\n").unwrap();
+        assert_eq!(
+            config.i18n.strings.get("figure").map(String::as_str),
+            Some("ภาพที่")
+        );
+        assert!(ProjectConfig::default().i18n.date_format.is_none());
+
+        let config = ProjectConfig::parse_toml(
+            "[i18n]\nsmart_punctuation = true\nquotes = \"«»\"\npunctuation_spacing = \"narrow\"\n",
+        )
+        .unwrap();
+        assert!(config.i18n.smart_punctuation);
+        assert_eq!(config.i18n.quotes.as_deref(), Some("«»"));
+        assert_eq!(config.i18n.punctuation_spacing.as_deref(), Some("narrow"));
+        assert!(!ProjectConfig::default().i18n.smart_punctuation);
+    }
+
+    #[test]
+    fn test_chapter_overrides() {
+        let config = ProjectConfig::parse_toml(
+            r#"
+[chapters.overrides."ch02_setup.md"]
+font = "TH Sarabun New"
+restart_numbering = 1
+
+[chapters.overrides.ch03_reference]
+toc = false
+header = "none"
+"#,
+        )
+        .unwrap();
+        let chapters = &config.chapters;
+
+        let setup = chapters
+            .override_for(Path::new("src/ch02_setup.md"))
+            .unwrap();
+        assert_eq!(setup.font.as_deref(), Some("TH Sarabun New"));
+        assert_eq!(setup.restart_numbering, Some(1));
+        assert_eq!(setup.toc, None);
+
+        let reference = chapters
+            .override_for(Path::new("ch03_reference.md"))
+            .unwrap();
+        assert_eq!(reference.toc, Some(false));
+        assert_eq!(reference.header.as_deref(), Some("none"));
+        assert!(chapters.override_for(Path::new("ch01_intro.md")).is_none());
+
+        let merged = setup.clone().or(reference.clone());
+        assert_eq!(merged.font.as_deref(), Some("TH Sarabun New"));
+        assert_eq!(merged.toc, Some(false));
+    }
+
+    #[test]
+    fn test_chapter_matter() {
+        let config = ProjectConfig::parse_toml(
+            r#"
+[chapters]
+files = ["intro.md", "setup.md"]
+frontmatter = ["preface.md"]
+backmatter = ["glossary.md"]
+backmatter_pages = "upperRoman"
+exclude = ["drafts/**", "*.draft.md"]
+chapter_break = "oddPage"
+h1_page_break = true
+"#,
+        )
+        .unwrap();
+        let chapters = &config.chapters;
+        assert_eq!(chapters.exclude, ["drafts/**", "*.draft.md"]);
+        assert_eq!(
+            chapters.files,
+            [PathBuf::from("intro.md"), PathBuf::from("setup.md")]
+        );
+        assert_eq!(chapters.frontmatter, [PathBuf::from("preface.md")]);
+        assert_eq!(chapters.backmatter, [PathBuf::from("glossary.md")]);
+        assert_eq!(chapters.frontmatter_pages, "lowerRoman");
+        assert_eq!(chapters.backmatter_pages.as_deref(), Some("upperRoman"));
+        assert_eq!(chapters.chapter_break, "oddPage");
+        assert!(chapters.h1_page_break);
+        assert!(!chapters.h2_keep_with_next);
+        assert!(ProjectConfig::default().chapters.files.is_empty());
+        assert_eq!(ProjectConfig::default().chapters.chapter_break, "nextPage");
+    }
+
+    #[test]
+    fn test_parts_section() {
+        let config = ProjectConfig::default();
+        assert_eq!(config.parts.pattern, "part*");
+        assert!(config.parts.roman);
+        assert!(config.parts.template.is_none());
+
+        let config = ProjectConfig::parse_toml(
+            r#"
+[parts]
+pattern = "section-*"
+template = "divider.md"
+roman = false
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.parts.pattern, "section-*");
+        assert_eq!(config.parts.template, Some(PathBuf::from("divider.md")));
+        assert!(!config.parts.roman);
+    }
+
+    #[test]
+    fn test_assets_section() {
+        assert_eq!(ProjectConfig::default().assets.dir, PathBuf::from("assets"));
+        let config = ProjectConfig::parse_toml("[assets]\ndir = \"shared/images\"\n").unwrap();
+        assert_eq!(config.assets.dir, PathBuf::from("shared/images"));
+    }
+
+    #[test]
+    fn test_header_logo() {
+        assert!(!ProjectConfig::default().header.is_set());
+        let config = ProjectConfig::parse_toml(
+            "[header]\nlogo = \"assets/logo.png\"\nlogo_position = \"right\"\nlogo_height = \"12mm\"\n",
+        )
+        .unwrap();
+        assert!(config.header.is_set());
+        assert_eq!(config.header.logo, Some(PathBuf::from("assets/logo.png")));
+        assert_eq!(config.header.logo_position.as_deref(), Some("right"));
+        assert_eq!(config.header.logo_height.as_deref(), Some("12mm"));
+        assert!(!config.footer.is_set());
+    }
+
+    #[test]
+    fn test_remote_section() {
+        let remote = ProjectConfig::default().remote;
+        assert_eq!(remote.cache_dir, PathBuf::from(".md2docx/cache"));
+        assert!(!remote.offline);
+        assert_eq!(remote.max_age_hours, 24);
+        let config =
+            ProjectConfig::parse_toml("[remote]\noffline = true\nmax_age_hours = 1\n").unwrap();
+        assert!(config.remote.offline);
+        assert_eq!(config.remote.max_age_hours, 1);
+    }
+
+    #[test]
+    fn test_snippets_section() {
+        assert!(ProjectConfig::default().snippets.is_empty());
+        let config = ProjectConfig::parse_toml(
+            "[snippets]\ncompany = \"ACME Co., Ltd.\"\nwarning = \"> **Warning:** {{text}}\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.snippets.len(), 2);
+        assert_eq!(config.snippets["company"], "ACME Co., Ltd.");
+    }
+
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_output_targets() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("md2docx.toml");
+        std::fs::write(
+            &path,
+            r#"
+[document]
+title = "Manual"
+
+[toc]
+enabled = true
+depth = 3
+
+[[output]]
+name = "full"
+file = "manual.docx"
+
+[[output]]
+file = "summary.docx"
+template = "template-exec"
+toc = false
+chapters.pattern = "ch0[12]_*.md"
+
+[profile.draft.output]
+watermark = "DRAFT"
+"#,
+        )
+        .unwrap();
+
+        let targets =
+            ProjectConfig::targets_from_files_layered(None, Some(&path), Some("draft")).unwrap();
+        assert_eq!(targets.len(), 2);
+
+        let (full, summary) = (&targets[0], &targets[1]);
+        assert_eq!(full.output.name.as_deref(), Some("full"));
+        assert_eq!(full.output.file, Some(PathBuf::from("manual.docx")));
+        assert!(full.toc.enabled);
+        assert_eq!(full.chapters.pattern, "ch*_*.md");
+        assert_eq!(full.output.watermark.as_deref(), Some("DRAFT"));
+
+        assert_eq!(summary.document.title, "Manual");
+        assert_eq!(summary.output.file, Some(PathBuf::from("summary.docx")));
+        assert_eq!(summary.template.dir, Some(PathBuf::from("template-exec")));
+        assert!(!summary.toc.enabled);
+        assert_eq!(summary.toc.depth, 3);
+        assert_eq!(summary.chapters.pattern, "ch0[12]_*.md");
+        assert_eq!(summary.output.watermark.as_deref(), Some("DRAFT"));
+
+        // The single-config loader sees the first target
+        let config = ProjectConfig::from_file(&path).unwrap();
+        assert_eq!(config.output.file, Some(PathBuf::from("manual.docx")));
+
+        std::fs::write(
+            &path,
+            "[[output]]\nfile = \"a.docx\"\n[[output]]\ntoc = false\n",
+        )
+        .unwrap();
+        let err = ProjectConfig::targets_from_files_layered(None, Some(&path), None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("needs a file"), "{}", err);
+    }
+
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_interpolate_env() {
+        let lookup = |name: &str| match name {
+            "VERSION" => Some("2.3".to_string()),
+            "OUT_DIR" => Some("build".to_string()),
+            _ => None,
+        };
+        let mut table: toml::Table = toml::from_str(
+            r#"
+[document]
+title = "Manual v${VERSION}"
+author = "${AUTHOR:-Docs Team}"
+subtitle = "Costs $${PRICE}"
+
+[output]
+file = "${OUT_DIR}/manual.docx"
+"#,
+        )
+        .unwrap();
+        interpolate_env(&mut table, &lookup).unwrap();
+        let config: ProjectConfig = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(config.document.title, "Manual v2.3");
+        assert_eq!(config.document.author, "Docs Team");
+        assert_eq!(config.document.subtitle, "Costs ${PRICE}");
+        assert_eq!(config.output.file, Some(PathBuf::from("build/manual.docx")));
+
+        let mut table: toml::Table = toml::from_str("[template]\ndir = \"${TEMPLATES}\"").unwrap();
+        assert_eq!(
+            interpolate_env(&mut table, &lookup),
+            Err("TEMPLATES".to_string())
+        );
+    }
 }
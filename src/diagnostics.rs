@@ -0,0 +1,479 @@
+//! Build warnings and diagnostics
+//!
+//! Non-fatal problems found while building (a diagram that failed to
+//! render, a missing font directory, ...) are reported through [`warn`],
+//! or [`warn_at`] when they point at a place in a source file.
+//! Warnings are logged at `warn` level through the [`log`] facade and,
+//! while a [`capture`] scope is active on the current thread, also
+//! collected so callers can include them in a build report or fail the
+//! build according to a [`WarningPolicy`].
+//!
+//! Warnings and errors both convert to a [`Diagnostic`], which carries a
+//! severity, a code and the source location, and renders with the source
+//! line it points at.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+    /// Set while warnings are captured without logging them
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether warnings with a source location are logged when reported
+static LOG_LOCATED: AtomicBool = AtomicBool::new(true);
+
+/// Kind of build warning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningCategory {
+    /// An image file could not be read
+    MissingImage,
+    /// A mermaid diagram failed to render or was simplified
+    Diagram,
+    /// An equation fell back to a different renderer
+    Math,
+    /// `{ref:...}` or a link to another file does not match any anchor
+    UnresolvedRef,
+    /// Font embedding problems
+    Font,
+    /// Anything else
+    Other,
+}
+
+impl WarningCategory {
+    /// All categories, in documentation order
+    pub const ALL: [WarningCategory; 6] = [
+        WarningCategory::MissingImage,
+        WarningCategory::Diagram,
+        WarningCategory::Math,
+        WarningCategory::UnresolvedRef,
+        WarningCategory::Font,
+        WarningCategory::Other,
+    ];
+
+    /// Short machine-friendly name of the category
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningCategory::MissingImage => "missing-image",
+            WarningCategory::Diagram => "diagram",
+            WarningCategory::Math => "math",
+            WarningCategory::UnresolvedRef => "unresolved-ref",
+            WarningCategory::Font => "font",
+            WarningCategory::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for WarningCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WarningCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|c| c.as_str()).collect();
+                format!(
+                    "unknown warning category '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The build failed
+    Error,
+    /// The build succeeded, but the output may not be what was meant
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// Place in a source file a diagnostic points at
+///
+/// Lines and columns count from 1; 0 means the position isn't known.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceLocation {
+    /// Location of a line and column in `file`
+    pub fn new(file: impl Into<PathBuf>, line: usize, column: usize) -> Self {
+        Self {
+            file: file.into(),
+            line,
+            column,
+        }
+    }
+
+    /// Location of a whole file
+    pub fn file(file: impl Into<PathBuf>) -> Self {
+        Self::new(file, 0, 0)
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if self.line > 0 {
+            write!(f, ":{}", self.line)?;
+            if self.column > 0 {
+                write!(f, ":{}", self.column)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A non-fatal problem reported while building
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Warning {
+    pub category: WarningCategory,
+    pub message: String,
+    /// Where in the sources the problem is, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<SourceLocation>,
+}
+
+impl Warning {
+    /// Warning without a source location
+    pub fn new(category: WarningCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            message: message.into(),
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{} [{}]", self.message, self.category)
+    }
+}
+
+/// A warning or error with its severity, code and source location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Warning category or error kind, like `unresolved-ref` or `include`
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<SourceLocation>,
+}
+
+impl Diagnostic {
+    /// Render the diagnostic like a compiler message
+    ///
+    /// `source` is the text of the file the location points into; the line
+    /// is quoted with a marker under the column.
+    ///
+    /// ```text
+    /// warning[unresolved-ref]: Broken link: no file 'setup.md'
+    ///   --> ch01_intro.md:12:5
+    ///    |
+    /// 12 | See [setup](setup.md).
+    ///    |     ^
+    /// ```
+    pub fn render(&self, source: Option<&str>) -> String {
+        let mut out = format!("{}[{}]: {}", self.severity, self.code, self.message);
+        let Some(location) = &self.location else {
+            return out;
+        };
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        out.push_str(&format!("\n{}--> {}", gutter, location));
+
+        let line = location
+            .line
+            .checked_sub(1)
+            .and_then(|index| source?.lines().nth(index));
+        if let Some(line) = line {
+            out.push_str(&format!("\n{} |\n{} | {}", gutter, number, line));
+            if location.column > 0 {
+                // Keep tabs so the marker lines up under the column
+                let indent: String = line
+                    .chars()
+                    .take(location.column - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                out.push_str(&format!("\n{} | {}^", gutter, indent));
+            }
+        }
+        out
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code: warning.category.as_str().to_string(),
+            message: warning.message.clone(),
+            location: warning.location.clone(),
+        }
+    }
+}
+
+impl From<&crate::Error> for Diagnostic {
+    fn from(error: &crate::Error) -> Self {
+        let (location, error) = match error {
+            crate::Error::Located { location, source } => (Some(location.clone()), &**source),
+            error => (None, error),
+        };
+        Self {
+            severity: Severity::Error,
+            code: error.code().to_string(),
+            message: error.to_string(),
+            location,
+        }
+    }
+}
+
+/// DOCX bytes of a successful build and the warnings reported on the way
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    pub bytes: Vec<u8>,
+    pub warnings: Vec<Warning>,
+}
+
+impl BuildReport {
+    /// Run `build`, collecting the warnings it reports
+    pub fn capture(build: impl FnOnce() -> crate::Result<Vec<u8>>) -> crate::Result<Self> {
+        let (bytes, warnings) = capture(build);
+        Ok(Self {
+            bytes: bytes?,
+            warnings,
+        })
+    }
+
+    /// The warnings as diagnostics
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.warnings.iter().map(Diagnostic::from).collect()
+    }
+}
+
+/// Which warnings should fail a build
+#[derive(Debug, Clone, Default)]
+pub struct WarningPolicy {
+    /// Treat every warning as an error
+    pub strict: bool,
+    /// Categories treated as errors
+    pub as_errors: Vec<WarningCategory>,
+}
+
+impl WarningPolicy {
+    /// Whether `warning` should fail the build
+    pub fn is_error(&self, warning: &Warning) -> bool {
+        self.strict || self.as_errors.contains(&warning.category)
+    }
+
+    /// The warnings in `warnings` that should fail the build
+    pub fn errors<'a>(&self, warnings: &'a [Warning]) -> Vec<&'a Warning> {
+        warnings.iter().filter(|w| self.is_error(w)).collect()
+    }
+}
+
+/// Report a non-fatal build warning
+pub fn warn(category: WarningCategory, message: impl Into<String>) {
+    report(Warning::new(category, message));
+}
+
+/// Report a non-fatal build warning about a place in a source file
+pub fn warn_at(category: WarningCategory, location: SourceLocation, message: impl Into<String>) {
+    report(Warning {
+        location: Some(location),
+        ..Warning::new(category, message)
+    });
+}
+
+/// Whether warnings with a source location are logged as they are reported
+///
+/// On by default. A caller that renders captured warnings itself, like
+/// the command line with [`Diagnostic::render`], turns it off so they
+/// aren't shown twice.
+pub fn log_located_warnings(enabled: bool) {
+    LOG_LOCATED.store(enabled, Ordering::Relaxed);
+}
+
+fn report(warning: Warning) {
+    if !QUIET.get() && (warning.location.is_none() || LOG_LOCATED.load(Ordering::Relaxed)) {
+        log::warn!("{}", warning);
+    }
+    CAPTURED.with(|captured| {
+        if let Some(warnings) = captured.borrow_mut().as_mut() {
+            warnings.push(warning);
+        }
+    });
+}
+
+/// Run `f` and return its result together with the warnings it reported
+///
+/// Captures nest: warnings from an inner scope are also passed on to the
+/// enclosing one.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Warning>) {
+    let outer = CAPTURED.with(|captured| captured.borrow_mut().replace(Vec::new()));
+    let result = f();
+    let warnings = CAPTURED.with(|captured| {
+        let mut captured = captured.borrow_mut();
+        let warnings = captured.take().unwrap_or_default();
+        *captured = outer.map(|mut outer| {
+            outer.extend(warnings.iter().cloned());
+            outer
+        });
+        warnings
+    });
+    (result, warnings)
+}
+
+/// Run `f` and return the warnings it reported, neither logged nor passed on
+///
+/// For work done ahead on another thread that may be thrown away: the
+/// warnings of work that is kept are reported with [`report_all`].
+#[cfg(feature = "parallel")]
+pub(crate) fn capture_quietly<T>(f: impl FnOnce() -> T) -> (T, Vec<Warning>) {
+    let outer = CAPTURED.with(|captured| captured.borrow_mut().replace(Vec::new()));
+    let quiet = QUIET.replace(true);
+    let result = f();
+    QUIET.set(quiet);
+    let warnings = CAPTURED.with(|captured| std::mem::replace(&mut *captured.borrow_mut(), outer));
+    (result, warnings.unwrap_or_default())
+}
+
+/// Report warnings captured with [`capture_quietly`] as if they were reported here
+#[cfg(feature = "parallel")]
+pub(crate) fn report_all(warnings: Vec<Warning>) {
+    for warning in warnings {
+        report(warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(warnings: &[Warning]) -> Vec<&str> {
+        warnings.iter().map(|w| w.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_capture_collects_warnings() {
+        let (value, warnings) = capture(|| {
+            warn(WarningCategory::Other, "first");
+            warn(WarningCategory::Diagram, format!("second {}", 2));
+            42
+        });
+        assert_eq!(value, 42);
+        assert_eq!(messages(&warnings), vec!["first", "second 2"]);
+        assert_eq!(warnings[1].category, WarningCategory::Diagram);
+
+        // Outside a capture scope warnings are only logged
+        let (_, warnings) = capture(|| ());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_nested_capture() {
+        let (inner, outer) = capture(|| {
+            warn(WarningCategory::Other, "outer");
+            let (_, inner) = capture(|| warn(WarningCategory::Other, "inner"));
+            inner
+        });
+        assert_eq!(messages(&inner), vec!["inner"]);
+        assert_eq!(messages(&outer), vec!["outer", "inner"]);
+    }
+
+    #[test]
+    fn test_warning_policy() {
+        let (_, warnings) = capture(|| {
+            warn(WarningCategory::MissingImage, "image");
+            warn(WarningCategory::Math, "math");
+        });
+
+        assert!(WarningPolicy::default().errors(&warnings).is_empty());
+
+        let policy = WarningPolicy {
+            strict: false,
+            as_errors: vec!["missing-image".parse().unwrap()],
+        };
+        assert_eq!(policy.errors(&warnings), vec![&warnings[0]]);
+
+        let strict = WarningPolicy {
+            strict: true,
+            ..Default::default()
+        };
+        assert_eq!(strict.errors(&warnings).len(), 2);
+
+        assert!("nope".parse::<WarningCategory>().is_err());
+    }
+
+    #[test]
+    fn test_located_diagnostics() {
+        let location = SourceLocation::new("ch01_intro.md", 3, 5);
+        let (_, warnings) = capture(|| {
+            warn_at(
+                WarningCategory::UnresolvedRef,
+                location.clone(),
+                "Broken link: no file 'setup.md'",
+            )
+        });
+        assert_eq!(warnings[0].location.as_ref(), Some(&location));
+        assert_eq!(
+            warnings[0].to_string(),
+            "ch01_intro.md:3:5: Broken link: no file 'setup.md' [unresolved-ref]"
+        );
+
+        let diagnostic = Diagnostic::from(&warnings[0]);
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(
+            diagnostic.render(Some("# Intro\n\nSee [setup](setup.md).\n")),
+            "warning[unresolved-ref]: Broken link: no file 'setup.md'\n --> ch01_intro.md:3:5\n  |\n3 | See [setup](setup.md).\n  |     ^"
+        );
+        assert_eq!(
+            diagnostic.render(None),
+            "warning[unresolved-ref]: Broken link: no file 'setup.md'\n --> ch01_intro.md:3:5"
+        );
+
+        let error = crate::Error::Include("Cannot read missing.md".into())
+            .at(SourceLocation::file("book.md"));
+        let diagnostic = Diagnostic::from(&error);
+        assert_eq!(diagnostic.code, "include");
+        assert_eq!(diagnostic.message, "Include error: Cannot read missing.md");
+        assert_eq!(
+            diagnostic.render(None),
+            "error[include]: Include error: Cannot read missing.md\n --> book.md"
+        );
+    }
+}
@@ -1 +1,701 @@
-//! Git diff support - TODO: Implement
+//! Redline comparison of two versions of a document
+//!
+//! Both versions are built, and the later one is written with every
+//! difference recorded as a Word tracked change by [`AUTHOR`]: removed text
+//! as deletions, new text as insertions. Reviewers step through the changes
+//! in Word, and accepting them all gives the later version.
+//!
+//! Paragraphs (headings, body text, list items, code lines) are matched by
+//! style and text, and paragraphs that changed are compared word by word.
+//! Tables, images and other blocks are taken from the later version as they
+//! are; changes inside them, in footnotes and in formatting alone are not
+//! marked.
+//!
+//! ```rust,no_run
+//! use md2docx::diff::{compare, Version};
+//! use std::path::Path;
+//!
+//! let old = Version::from_file(Path::new("v1/manual.md")).unwrap();
+//! let new = Version::from_file(Path::new("v2/manual.md")).unwrap();
+//! std::fs::write("changes.docx", compare(&old, &new).unwrap()).unwrap();
+//! ```
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::docx::builder::build_document;
+use crate::docx::ooxml::{
+    CoreProperties, DocElement, Paragraph, ParagraphChild, Revision, RevisionKind, Run,
+};
+use crate::docx::rels_manager::RelIdManager;
+use crate::error::{Error, Result};
+use crate::parser::parse_markdown_with_frontmatter;
+use crate::{DocumentConfig, IncludeConfig, Language};
+
+/// Author recorded on every tracked change
+pub const AUTHOR: &str = "md2docx";
+
+/// Largest edit table (old items × new items) compared exactly; bigger
+/// changed regions are shown as deleted and inserted wholesale
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// One version of a document: its markdown and how to build it
+#[derive(Debug, Clone, Default)]
+pub struct Version {
+    pub markdown: String,
+    /// Document language; `None` reads the frontmatter's `language:`
+    pub lang: Option<Language>,
+    /// Base path, includes and file map the markdown is built with
+    pub config: DocumentConfig,
+}
+
+impl Version {
+    /// Version built from markdown with the default configuration
+    pub fn new(markdown: impl Into<String>) -> Self {
+        Self {
+            markdown: markdown.into(),
+            lang: None,
+            config: DocumentConfig::default(),
+        }
+    }
+
+    /// Read a markdown file, resolving its includes and images against its
+    /// directory
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let markdown = std::fs::read_to_string(path)?;
+        Ok(Self::in_dir(markdown, parent_dir(path)))
+    }
+
+    /// Read a markdown file, or a project directory or its `md2docx.toml`
+    ///
+    /// A project's chapters and appendices are combined as `md2docx build
+    /// --dir` combines them; see
+    /// [`ProjectBuilder::to_version`](crate::project::ProjectBuilder::to_version).
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    pub fn from_path(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            crate::project::ProjectBuilder::from_directory(path)?.to_version()
+        } else if path.file_name() == Some(std::ffi::OsStr::new("md2docx.toml")) {
+            crate::project::ProjectBuilder::from_directory(parent_dir(path))?.to_version()
+        } else {
+            Self::from_file(path)
+        }
+    }
+
+    /// Language set on the version, else the frontmatter's `language:`,
+    /// else English
+    pub fn language(&self) -> Language {
+        self.lang.unwrap_or_else(|| {
+            crate::parser::parse_frontmatter(&self.markdown)
+                .0
+                .and_then(|fm| fm.language)
+                .and_then(|code| Language::from_code(&code))
+                .unwrap_or_default()
+        })
+    }
+
+    fn in_dir(markdown: String, dir: PathBuf) -> Self {
+        Self {
+            markdown,
+            lang: None,
+            config: DocumentConfig {
+                base_path: Some(dir.clone()),
+                includes: Some(IncludeConfig {
+                    base_path: dir.clone(),
+                    source_root: dir,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl Version {
+    /// Read a markdown file as it was at a git revision
+    ///
+    /// Every file in the revision's tree is loaded into a
+    /// [`FileMap`](crate::FileMap), so includes and images resolve as they
+    /// were at that revision too.
+    pub fn at_revision(path: &Path, rev: &str) -> Result<Self> {
+        let git_error = |e: &dyn std::fmt::Display| Error::Git(format!("{}: {}", rev, e));
+
+        let dir = parent_dir(path);
+        let repo = gix::discover(&dir).map_err(|e| git_error(&e))?;
+        let root = repo
+            .work_dir()
+            .ok_or_else(|| Error::Git("bare repositories have no working tree".to_string()))?
+            .canonicalize()?;
+        // The file itself may no longer exist in the working tree
+        let file = dir
+            .canonicalize()?
+            .join(path.file_name().unwrap_or_default());
+        let relative = file
+            .strip_prefix(&root)
+            .map_err(|_| Error::Git(format!("{} is outside the repository", path.display())))?;
+
+        let tree = repo
+            .rev_parse_single(rev)
+            .map_err(|e| git_error(&e))?
+            .object()
+            .map_err(|e| git_error(&e))?
+            .peel_to_tree()
+            .map_err(|e| git_error(&e))?;
+        let mut recorder = gix::traverse::tree::Recorder::default();
+        tree.traverse()
+            .breadthfirst(&mut recorder)
+            .map_err(|e| git_error(&e))?;
+
+        let mut files = crate::FileMap::new();
+        for entry in recorder.records.iter().filter(|e| e.mode.is_blob()) {
+            let blob = repo.find_object(entry.oid).map_err(|e| git_error(&e))?;
+            files.insert(gix::path::from_bstr(&entry.filepath), blob.detach().data);
+        }
+
+        let markdown = files.read_to_string(relative).ok_or_else(|| {
+            Error::Git(format!("{} does not exist at {}", relative.display(), rev))
+        })?;
+        let mut version = Self::in_dir(markdown, parent_dir(relative));
+        version.config.files = Some(Arc::new(files));
+        Ok(version)
+    }
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Build `new` with its differences from `old` as tracked changes
+///
+/// The result is `new` as [`markdown_to_docx_with_config`] would build it,
+/// in `new`'s language and configuration.
+///
+/// [`markdown_to_docx_with_config`]: crate::markdown_to_docx_with_config
+pub fn compare(old: &Version, new: &Version) -> Result<Vec<u8>> {
+    let mut config = new.config.clone();
+    config.compare_to = Some(Arc::new(old.clone()));
+    crate::markdown_to_docx_with_config(&new.markdown, new.language(), &config)
+}
+
+/// Merge the build of `baseline` into `elements` as tracked changes
+pub(crate) fn redline(
+    baseline: &Version,
+    lang: Language,
+    config: &DocumentConfig,
+    elements: &mut Vec<DocElement>,
+) -> Result<()> {
+    if config.memory_budget.is_some() {
+        return Err(Error::Config(
+            "Comparing versions needs the whole document in memory; remove memory_budget"
+                .to_string(),
+        ));
+    }
+
+    let mut old_config = baseline.config.clone();
+    old_config.compare_to = None;
+    old_config.memory_budget = None;
+    let parsed = parse_markdown_with_frontmatter(&baseline.markdown);
+    let old = build_document(
+        &parsed,
+        lang,
+        &old_config,
+        &mut RelIdManager::new(),
+        None,
+        None,
+    )?;
+
+    let mut marks = Marks {
+        next_id: 1,
+        taken: bookmark_ids(elements),
+        date: (!config.reproducible).then(|| CoreProperties::current_iso_time().into()),
+    };
+    let new = std::mem::take(elements);
+    *elements = merge(&old.document.elements, new, &mut marks);
+    Ok(())
+}
+
+/// Allocates revision IDs and stamps revisions
+struct Marks {
+    next_id: u32,
+    /// Bookmark IDs already in the document, which revisions must not reuse
+    taken: HashSet<u32>,
+    date: Option<Arc<str>>,
+}
+
+impl Marks {
+    fn revision(&mut self, kind: RevisionKind) -> Revision {
+        while self.taken.contains(&self.next_id) {
+            self.next_id += 1;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        Revision {
+            kind,
+            id,
+            author: AUTHOR,
+            date: self.date.clone(),
+        }
+    }
+}
+
+fn bookmark_ids(elements: &[DocElement]) -> HashSet<u32> {
+    let mut ids = HashSet::new();
+    for p in elements.iter().filter_map(compared_paragraph) {
+        ids.extend(p.bookmark_start.as_ref().map(|b| b.id));
+        for child in &p.children {
+            if let ParagraphChild::BookmarkStart { id, .. } = child {
+                ids.insert(*id);
+            }
+        }
+    }
+    ids
+}
+
+/// Paragraphs take part in the comparison; section breaks and everything
+/// else pass through from the later version
+fn compared_paragraph(element: &DocElement) -> Option<&Paragraph> {
+    match element {
+        DocElement::Paragraph(p) if !p.is_section_break() => Some(p.as_ref()),
+        _ => None,
+    }
+}
+
+/// What makes two paragraphs the same paragraph
+#[derive(PartialEq)]
+struct Key<'a> {
+    style: Option<&'a str>,
+    level: Option<u32>,
+    text: String,
+}
+
+impl<'a> Key<'a> {
+    fn of(p: &'a Paragraph) -> Self {
+        let mut text = String::new();
+        for child in &p.children {
+            match child {
+                ParagraphChild::Run(run) => text.push_str(&run.text),
+                ParagraphChild::Hyperlink(link) => {
+                    for run in &link.children {
+                        text.push_str(&run.text);
+                    }
+                }
+                ParagraphChild::OfficeMath(xml) => text.push_str(xml),
+                ParagraphChild::InlineImage(_) => text.push('\u{FFFC}'),
+                ParagraphChild::BookmarkStart { .. } | ParagraphChild::BookmarkEnd { .. } => {}
+            }
+        }
+        Self {
+            style: p.style_id.as_deref(),
+            level: p.numbering_level,
+            text,
+        }
+    }
+}
+
+/// The later version's elements with the earlier version's differences
+/// marked in
+fn merge(old: &[DocElement], mut new: Vec<DocElement>, marks: &mut Marks) -> Vec<DocElement> {
+    let old_paragraphs: Vec<&Paragraph> = old.iter().filter_map(compared_paragraph).collect();
+    let positions: Vec<usize> = new
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| compared_paragraph(e).map(|_| i))
+        .collect();
+
+    let ops = {
+        let old_keys: Vec<Key> = old_paragraphs.iter().map(|p| Key::of(p)).collect();
+        let new_keys: Vec<Key> = positions
+            .iter()
+            .filter_map(|&i| compared_paragraph(&new[i]))
+            .map(Key::of)
+            .collect();
+        diff(&old_keys, &new_keys)
+    };
+
+    // Deleted paragraphs go before the next paragraph of the later version
+    let mut deleted_before: Vec<Vec<Paragraph>> = vec![Vec::new(); new.len()];
+    let mut pending = Vec::new();
+    let mut i = 0;
+    while i < ops.len() {
+        if let Op::Equal(_, n) = ops[i] {
+            deleted_before[positions[n]].append(&mut pending);
+            i += 1;
+            continue;
+        }
+
+        // A run of changes: pair deleted and inserted paragraphs in order
+        let end = ops[i..]
+            .iter()
+            .position(|op| matches!(op, Op::Equal(..)))
+            .map_or(ops.len(), |len| i + len);
+        let deleted: Vec<&Paragraph> = ops[i..end]
+            .iter()
+            .filter_map(|op| match op {
+                Op::Delete(o) => Some(old_paragraphs[*o]),
+                _ => None,
+            })
+            .collect();
+        let inserted: Vec<usize> = ops[i..end]
+            .iter()
+            .filter_map(|op| match op {
+                Op::Insert(n) => Some(positions[*n]),
+                _ => None,
+            })
+            .collect();
+
+        for k in 0..deleted.len().max(inserted.len()) {
+            let old_p = deleted.get(k).copied();
+            let Some(&position) = inserted.get(k) else {
+                pending.extend(old_p.map(|p| deleted_paragraph(p, marks)));
+                continue;
+            };
+            let DocElement::Paragraph(new_p) = &mut new[position] else {
+                continue;
+            };
+            match old_p {
+                Some(old_p)
+                    if old_p.style_id == new_p.style_id && is_plain(old_p) && is_plain(new_p) =>
+                {
+                    redline_words(old_p, new_p, marks)
+                }
+                Some(old_p) => {
+                    pending.push(deleted_paragraph(old_p, marks));
+                    mark_inserted(new_p, marks);
+                }
+                None => mark_inserted(new_p, marks),
+            }
+            deleted_before[position].append(&mut pending);
+        }
+        i = end;
+    }
+
+    let mut merged = Vec::with_capacity(new.len() + pending.len());
+    for (element, deleted) in new.into_iter().zip(deleted_before) {
+        merged.extend(deleted.into_iter().map(Box::new).map(DocElement::Paragraph));
+        merged.push(element);
+    }
+    merged.extend(pending.into_iter().map(Box::new).map(DocElement::Paragraph));
+    merged
+}
+
+/// Whether a paragraph holds only text runs, so it can be split by word
+fn is_plain(p: &Paragraph) -> bool {
+    p.children.iter().all(|child| match child {
+        ParagraphChild::Run(run) => is_text_run(run) && !run.tab && run.break_type.is_none(),
+        _ => false,
+    })
+}
+
+/// Runs that carry visible content rather than field codes or note marks
+fn is_text_run(run: &Run) -> bool {
+    run.field_char.is_none() && !run.instr_text && run.footnote_id.is_none() && !run.footnote_ref
+}
+
+fn mark_inserted(p: &mut Paragraph, marks: &mut Marks) {
+    for child in &mut p.children {
+        match child {
+            ParagraphChild::Run(run) => run.revision = Some(marks.revision(RevisionKind::Insert)),
+            ParagraphChild::Hyperlink(link) => {
+                for run in &mut link.children {
+                    run.revision = Some(marks.revision(RevisionKind::Insert));
+                }
+            }
+            _ => {}
+        }
+    }
+    p.mark_revision = Some(marks.revision(RevisionKind::Insert));
+}
+
+/// A paragraph of the earlier version, shown as deleted
+///
+/// Only its text survives: hyperlinks, images, notes, bookmarks and list
+/// numbering refer to parts and IDs of the earlier build, which this
+/// document does not have.
+fn deleted_paragraph(old: &Paragraph, marks: &mut Marks) -> Paragraph {
+    let mut p = old.clone();
+    p.numbering_id = None;
+    p.numbering_level = None;
+    p.bookmark_start = None;
+    p.bookmark_end = false;
+    p.children = old
+        .children
+        .iter()
+        .flat_map(|child| match child {
+            ParagraphChild::Run(run) => std::slice::from_ref(run),
+            ParagraphChild::Hyperlink(link) => link.children.as_slice(),
+            _ => &[],
+        })
+        .filter(|run| is_text_run(run))
+        .map(|run| {
+            let mut run = run.clone();
+            run.revision = Some(marks.revision(RevisionKind::Delete));
+            ParagraphChild::Run(run)
+        })
+        .collect();
+    p.mark_revision = Some(marks.revision(RevisionKind::Delete));
+    p
+}
+
+/// Replace `new`'s runs with its word-level differences from `old`
+///
+/// Unchanged and inserted words keep the formatting of the run they come
+/// from in `new`, deleted words that of their run in `old`.
+fn redline_words(old: &Paragraph, new: &mut Paragraph, marks: &mut Marks) {
+    let old_runs = text_runs(old);
+    let new_runs = text_runs(new);
+    let old_words = words(&old_runs);
+    let new_words = words(&new_runs);
+    let ops = diff(
+        &old_words.iter().map(|w| w.0).collect::<Vec<_>>(),
+        &new_words.iter().map(|w| w.0).collect::<Vec<_>>(),
+    );
+
+    // Consecutive words from the same run with the same change share a run
+    let mut pieces: Vec<(&Run, Option<RevisionKind>, String)> = Vec::new();
+    for op in ops {
+        let (word, run, kind) = match op {
+            Op::Equal(_, n) => (new_words[n].0, new_runs[new_words[n].1], None),
+            Op::Insert(n) => (
+                new_words[n].0,
+                new_runs[new_words[n].1],
+                Some(RevisionKind::Insert),
+            ),
+            Op::Delete(o) => (
+                old_words[o].0,
+                old_runs[old_words[o].1],
+                Some(RevisionKind::Delete),
+            ),
+        };
+        match pieces.last_mut() {
+            Some((last, last_kind, text)) if std::ptr::eq(*last, run) && *last_kind == kind => {
+                text.push_str(word)
+            }
+            _ => pieces.push((run, kind, word.to_string())),
+        }
+    }
+
+    let children = pieces
+        .into_iter()
+        .map(|(run, kind, text)| {
+            let mut run = run.clone();
+            run.text = text;
+            run.revision = kind.map(|kind| marks.revision(kind));
+            ParagraphChild::Run(run)
+        })
+        .collect();
+    new.children = children;
+}
+
+fn text_runs(p: &Paragraph) -> Vec<&Run> {
+    p.children
+        .iter()
+        .filter_map(|child| match child {
+            ParagraphChild::Run(run) => Some(run),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Words, spaces and punctuation of the runs, each with its run's index
+fn words<'a>(runs: &[&'a Run]) -> Vec<(&'a str, usize)> {
+    runs.iter()
+        .enumerate()
+        .flat_map(|(i, &run)| run.text.split_word_bounds().map(move |word| (word, i)))
+        .collect()
+}
+
+/// Step of an edit script, by index into the old and new sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Edit script turning `old` into `new` with the fewest deletions and
+/// insertions, deleting before inserting within a change
+fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    let (a, b) = (&old[prefix..old_end], &new[prefix..new_end]);
+
+    let mut ops: Vec<Op> = (0..prefix).map(|i| Op::Equal(i, i)).collect();
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        ops.extend((prefix..old_end).map(Op::Delete));
+        ops.extend((prefix..new_end).map(Op::Insert));
+    } else {
+        // lcs[i * width + j]: longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                ops.push(Op::Equal(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                ops.push(Op::Delete(prefix + i));
+                i += 1;
+            } else {
+                ops.push(Op::Insert(prefix + j));
+                j += 1;
+            }
+        }
+        ops.extend((prefix + i..old_end).map(Op::Delete));
+        ops.extend((prefix + j..new_end).map(Op::Insert));
+    }
+    ops.extend((0..suffix).map(|k| Op::Equal(old_end + k, new_end + k)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// document.xml with its indentation removed
+    fn document_xml(docx: &[u8]) -> String {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(docx)).unwrap();
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("word/document.xml").unwrap(), &mut xml)
+            .unwrap();
+        xml.lines().map(str::trim_start).collect()
+    }
+
+    fn redlined(old: &str, new: &str) -> String {
+        let mut new = Version::new(new);
+        new.config.toc.enabled = false;
+        new.config.reproducible = true;
+        document_xml(&compare(&Version::new(old), &new).unwrap())
+    }
+
+    #[test]
+    fn test_diff_edit_script() {
+        let ops = diff(&["a", "b", "c", "d"], &["a", "x", "c", "d", "e"]);
+        assert_eq!(
+            ops,
+            vec![
+                Op::Equal(0, 0),
+                Op::Delete(1),
+                Op::Insert(1),
+                Op::Equal(2, 2),
+                Op::Equal(3, 3),
+                Op::Insert(4),
+            ]
+        );
+        assert!(diff::<&str>(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_changed_words_are_tracked() {
+        let xml = redlined(
+            "# Guide\n\nThe quick brown fox.\n",
+            "# Guide\n\nThe quick red fox.\n",
+        );
+
+        assert!(xml.contains(r#"<w:del w:id="#));
+        assert!(xml.contains(r#"w:author="md2docx""#));
+        assert!(xml.contains(">brown</w:delText>"));
+        assert!(xml.contains(">red</w:t></w:r></w:ins>"));
+        assert!(xml.contains(">The quick </w:t>"));
+        // Reproducible builds leave the date out
+        assert!(!xml.contains("w:date="));
+    }
+
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_revisions_are_dated_today() {
+        let today = || chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let before = today();
+        let mut new = Version::new("The quick red fox.\n");
+        new.config.toc.enabled = false;
+        let xml = document_xml(&compare(&Version::new("The quick brown fox.\n"), &new).unwrap());
+        let after = today();
+
+        let start = xml.find(r#"w:date=""#).unwrap() + r#"w:date=""#.len();
+        let date = &xml[start..start + 20];
+        let parsed = chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%SZ").unwrap();
+        let day = parsed.format("%Y-%m-%d").to_string();
+        assert!(day == before || day == after, "{date} is not today");
+    }
+
+    #[test]
+    fn test_added_and_removed_paragraphs_are_tracked() {
+        let xml = redlined(
+            "First paragraph.\n\nRemoved paragraph.\n\nLast paragraph.\n",
+            "First paragraph.\n\nLast paragraph.\n\nAdded paragraph.\n",
+        );
+
+        assert!(xml.contains(">Removed paragraph.</w:delText>"));
+        assert!(xml.contains(">Added paragraph.</w:t></w:r></w:ins>"));
+        // Whole paragraphs also mark their paragraph mark
+        assert!(xml.contains("<w:rPr><w:del "));
+        assert!(xml.contains("<w:rPr><w:ins "));
+        // Unchanged paragraphs carry no revisions
+        let first = xml.find(">First paragraph.<").unwrap();
+        let paragraph_start = xml[..first].rfind("<w:p>").unwrap();
+        assert!(!xml[paragraph_start..first].contains("<w:ins"));
+        assert!(xml.find(">Removed paragraph.<").unwrap() < xml.find(">Last paragraph.<").unwrap());
+    }
+
+    #[test]
+    fn test_identical_versions_have_no_revisions() {
+        let md = "# Title\n\nSome text with a [link](https://example.com).\n\n- one\n- two\n";
+        let xml = redlined(md, md);
+        assert!(!xml.contains("<w:ins "));
+        assert!(!xml.contains("<w:del "));
+    }
+
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_project_versions_are_compared() {
+        let project = |chapters: &[(&str, &str)]| {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(
+                dir.path().join("md2docx.toml"),
+                "[document]\nlanguage = \"th\"\n\n[toc]\nenabled = false\n",
+            )
+            .unwrap();
+            for (name, content) in chapters {
+                std::fs::write(dir.path().join(name), content).unwrap();
+            }
+            dir
+        };
+        let old = project(&[("ch01_intro.md", "# Intro\n\nOld text.\n")]);
+        let new = project(&[
+            ("ch01_intro.md", "# Intro\n\nNew text.\n"),
+            ("ch02_usage.md", "# Usage\n\nAdded chapter.\n"),
+        ]);
+
+        let old = Version::from_path(old.path()).unwrap();
+        let new = Version::from_path(&new.path().join("md2docx.toml")).unwrap();
+        assert_eq!(new.language(), Language::Thai);
+        let xml = document_xml(&compare(&old, &new).unwrap());
+
+        assert!(xml.contains(">Old</w:delText>"));
+        assert!(xml.contains(">New</w:t></w:r></w:ins>"));
+        assert!(xml.contains(">Added chapter.</w:t></w:r></w:ins>"));
+    }
+}
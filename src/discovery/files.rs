@@ -1,23 +1,35 @@
 //! File discovery for md2docx projects
 //!
 //! This module handles discovering and organizing markdown files in a project directory,
-//! including cover pages, chapters, appendices, and bibliography files.
+//! including cover pages, front matter, chapters (flat or grouped into part
+//! directories), appendices, back matter, and bibliography files.
 
 use std::path::{Path, PathBuf};
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::config::ProjectConfig;
+use crate::diagnostics::WarningCategory;
 use crate::Result;
 
+/// Project file listing paths to leave out of discovery, one glob per line
+pub const IGNORE_FILE: &str = ".md2docxignore";
+
 /// Discovered project structure
 #[derive(Debug, Clone, Default)]
 pub struct DiscoveredProject {
     /// Cover page file (cover.md)
     pub cover: Option<PathBuf>,
-    /// Chapter files sorted by number (ch##_*.md)
+    /// Front matter files in the order of `chapters.frontmatter`
+    pub frontmatter: Vec<PathBuf>,
+    /// Chapter files sorted by number (ch##_*.md), or in the order of
+    /// `chapters.files`, followed by the chapters of each part
     pub chapters: Vec<ChapterFile>,
+    /// Part directories sorted by number (part-##-*/)
+    pub parts: Vec<PartDir>,
     /// Appendix files sorted (ap##_*.md)
     pub appendices: Vec<AppendixFile>,
+    /// Back matter files in the order of `chapters.backmatter`
+    pub backmatter: Vec<PathBuf>,
     /// Bibliography file (bibliography.md or references.md)
     pub bibliography: Option<PathBuf>,
     /// Config file location (md2docx.toml)
@@ -35,6 +47,21 @@ pub struct ChapterFile {
     pub path: PathBuf,
     /// Extracted name (e.g., "intro" from ch01_intro.md)
     pub name: String,
+    /// Number of the part the chapter belongs to, if any
+    pub part: Option<u32>,
+}
+
+/// A discovered part directory
+#[derive(Debug, Clone)]
+pub struct PartDir {
+    /// Part number (e.g., 1 for part-01-intro/)
+    pub number: u32,
+    /// Full path to the directory
+    pub path: PathBuf,
+    /// Extracted name (e.g., "intro" from part-01-intro/)
+    pub name: String,
+    /// The part's own divider page (part.md inside the directory)
+    pub divider: Option<PathBuf>,
 }
 
 /// A discovered appendix file
@@ -93,29 +120,121 @@ impl DiscoveredProject {
             None
         };
 
-        // Look for cover page (case-insensitive)
-        let cover = Self::find_cover(&base_dir);
+        // Paths matching `chapters.exclude` or `.md2docxignore` are never
+        // discovered; files listed by name in the config still are
+        let excluded = exclusion_filter(&base_dir, &config.chapters.exclude)?;
 
-        // Find chapter files
-        let chapters = Self::find_chapters(&base_dir, &config.chapters.pattern)?;
+        // Look for cover page (case-insensitive)
+        let cover = Self::find_cover(&base_dir).filter(|cover| !excluded(cover));
+
+        // Find chapter files and parts, or take the chapters as listed
+        let (mut chapters, parts) = if config.chapters.files.is_empty() {
+            let mut chapters = Self::find_chapters(&base_dir, &config.chapters.pattern)?;
+            chapters.retain(|ch| !excluded(&ch.path));
+            let mut parts = Self::find_parts(&base_dir, &config.parts.pattern)?;
+            parts.retain(|part| !excluded(&part.path));
+            Self::add_part_chapters(&mut chapters, &parts, &excluded)?;
+            (chapters, parts)
+        } else {
+            let chapters = Self::listed_chapters(&base_dir, &config.chapters.files)?;
+            (chapters, Vec::new())
+        };
 
         // Find appendix files
-        let appendices = Self::find_appendices(&base_dir, &config.appendices.pattern)?;
+        let mut appendices = Self::find_appendices(&base_dir, &config.appendices.pattern)?;
+        appendices.retain(|ap| !excluded(&ap.path));
 
         // Look for bibliography (case-insensitive)
-        let bibliography = Self::find_bibliography(&base_dir);
+        let mut bibliography = Self::find_bibliography(&base_dir).filter(|bib| !excluded(bib));
+
+        // Files placed in the front or back matter leave the group they
+        // were discovered in
+        let frontmatter = Self::listed_files(&base_dir, &config.chapters.frontmatter)?;
+        let backmatter = Self::listed_files(&base_dir, &config.chapters.backmatter)?;
+        let placed = |path: &PathBuf| frontmatter.contains(path) || backmatter.contains(path);
+        chapters.retain(|ch| !placed(&ch.path));
+        appendices.retain(|ap| !placed(&ap.path));
+        bibliography = bibliography.filter(|bib| !placed(bib));
 
         Ok(DiscoveredProject {
             cover,
+            frontmatter,
             chapters,
+            parts,
             appendices,
+            backmatter,
             bibliography,
             config_file,
             base_dir,
         })
     }
 
-    /// Get all markdown files in order (cover, chapters, appendices, bibliography)
+    /// Arrange files that are already known, such as markdown held in memory
+    ///
+    /// Nothing is read from disk and the files keep the given order:
+    /// `cover.md` is the cover page, files listed in `chapters.frontmatter`
+    /// or `chapters.backmatter` go to the front or back matter, appendix
+    /// names (`ap01_*.md`) become appendices, `bibliography.md` or
+    /// `references.md` the bibliography, and every other file a chapter,
+    /// numbered from its name (`ch03_*.md`) or its position.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_files(base_dir: &Path, files: &[PathBuf], config: &ProjectConfig) -> Self {
+        let mut project = DiscoveredProject {
+            base_dir: base_dir.to_path_buf(),
+            ..Default::default()
+        };
+        let listed = |list: &[PathBuf], path: &Path| list.iter().any(|f| base_dir.join(f) == path);
+
+        for file in files {
+            let path = base_dir.join(file);
+            let filename = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+            let lower = filename.to_lowercase();
+            if listed(&config.chapters.frontmatter, &path) {
+                project.frontmatter.push(path);
+            } else if listed(&config.chapters.backmatter, &path) {
+                project.backmatter.push(path);
+            } else if lower == "cover.md" && project.cover.is_none() {
+                project.cover = Some(path);
+            } else if matches!(lower.as_str(), "bibliography.md" | "references.md")
+                && project.bibliography.is_none()
+            {
+                project.bibliography = Some(path);
+            } else if let Some((_, name)) = parse_appendix_filename(&filename) {
+                let number = project.appendices.len() as u32 + 1;
+                let letter = if number <= 26 {
+                    (b'A' + (number - 1) as u8) as char
+                } else {
+                    '?'
+                };
+                project.appendices.push(AppendixFile {
+                    number,
+                    path,
+                    name,
+                    letter,
+                });
+            } else {
+                let position = project.chapters.len() as u32 + 1;
+                let (number, name) = parse_chapter_filename(&filename).unwrap_or_else(|| {
+                    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                    (position, stem.to_string())
+                });
+                project.chapters.push(ChapterFile {
+                    number,
+                    path,
+                    name,
+                    part: None,
+                });
+            }
+        }
+        project
+    }
+
+    /// Get all markdown files in order (cover, front matter, chapters,
+    /// appendices, back matter, bibliography)
     ///
     /// # Returns
     /// A vector of references to all markdown file paths in document order
@@ -124,12 +243,14 @@ impl DiscoveredProject {
         if let Some(ref cover) = self.cover {
             files.push(cover);
         }
+        files.extend(&self.frontmatter);
         for ch in &self.chapters {
             files.push(&ch.path);
         }
         for ap in &self.appendices {
             files.push(&ap.path);
         }
+        files.extend(&self.backmatter);
         if let Some(ref bib) = self.bibliography {
             files.push(bib);
         }
@@ -138,12 +259,13 @@ impl DiscoveredProject {
 
     /// Check if this looks like a valid project directory
     ///
-    /// A valid project has at least one chapter or a cover page.
+    /// A valid project has at least one chapter, front matter file or a
+    /// cover page.
     ///
     /// # Returns
     /// `true` if the directory contains valid project files
     pub fn is_valid(&self) -> bool {
-        !self.chapters.is_empty() || self.cover.is_some()
+        !self.chapters.is_empty() || !self.frontmatter.is_empty() || self.cover.is_some()
     }
 
     /// Find cover page file (case-insensitive)
@@ -161,9 +283,10 @@ impl DiscoveredProject {
 
     /// Find chapter files matching pattern
     #[cfg(not(target_arch = "wasm32"))]
-    fn find_chapters(base_dir: &Path, _pattern: &str) -> Result<Vec<ChapterFile>> {
+    fn find_chapters(base_dir: &Path, pattern: &str) -> Result<Vec<ChapterFile>> {
         use glob::glob;
 
+        let filter = filename_filter(pattern)?;
         let mut chapters = Vec::new();
         let pattern_str = format!("{}/*.md", base_dir.display());
 
@@ -176,14 +299,25 @@ impl DiscoveredProject {
             match entry {
                 Ok(path) => {
                     if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
+                        if !filter(filename) {
+                            continue;
+                        }
                         if let Some((number, name)) = parse_chapter_filename(filename) {
-                            chapters.push(ChapterFile { number, path, name });
+                            chapters.push(ChapterFile {
+                                number,
+                                path,
+                                name,
+                                part: None,
+                            });
                         }
                     }
                 }
                 Err(e) => {
                     // Log warning but continue
-                    eprintln!("Warning: Error reading file: {}", e);
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        format!("Error reading file: {}", e),
+                    );
                 }
             }
         }
@@ -194,11 +328,127 @@ impl DiscoveredProject {
         Ok(chapters)
     }
 
+    /// Chapters listed in `chapters.files`, numbered in the order given
+    #[cfg(not(target_arch = "wasm32"))]
+    fn listed_chapters(base_dir: &Path, files: &[PathBuf]) -> Result<Vec<ChapterFile>> {
+        let paths = Self::listed_files(base_dir, files)?;
+        Ok(paths
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let name = parse_chapter_filename(filename)
+                    .map(|(_, name)| name)
+                    .or_else(|| path.file_stem()?.to_str().map(str::to_string))
+                    .unwrap_or_default();
+                ChapterFile {
+                    number: i as u32 + 1,
+                    path,
+                    name,
+                    part: None,
+                }
+            })
+            .collect())
+    }
+
+    /// Find part directories whose name matches `pattern`
+    #[cfg(not(target_arch = "wasm32"))]
+    fn find_parts(base_dir: &Path, pattern: &str) -> Result<Vec<PartDir>> {
+        let filter = filename_filter(pattern)?;
+        let mut parts = Vec::new();
+        for entry in std::fs::read_dir(base_dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(dirname) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !filter(dirname) {
+                continue;
+            }
+            if let Some((number, name)) = parse_part_dirname(dirname) {
+                let divider = Some(path.join("part.md")).filter(|p| p.is_file());
+                parts.push(PartDir {
+                    number,
+                    path,
+                    name,
+                    divider,
+                });
+            }
+        }
+        parts.sort_by_key(|part| part.number);
+        Ok(parts)
+    }
+
+    /// Append the chapters of each part, numbered on from the chapters
+    /// before them
+    ///
+    /// Inside a part, chapter files are ordered by their number prefix
+    /// (`01-overview.md` or `ch01_overview.md`). Excluded files are skipped
+    /// before numbering, so they leave no gap.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn add_part_chapters(
+        chapters: &mut Vec<ChapterFile>,
+        parts: &[PartDir],
+        excluded: impl Fn(&Path) -> bool,
+    ) -> Result<()> {
+        let mut next = chapters.last().map_or(1, |ch| ch.number + 1);
+        for part in parts {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(&part.path)? {
+                let path = entry?.path();
+                if excluded(&path) {
+                    continue;
+                }
+                let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if let Some((number, name)) =
+                    parse_chapter_filename(filename).or_else(|| parse_numbered_filename(filename))
+                {
+                    files.push((number, path, name));
+                }
+            }
+            files.sort_by_key(|(number, _, _)| *number);
+            for (_, path, name) in files {
+                chapters.push(ChapterFile {
+                    number: next,
+                    path,
+                    name,
+                    part: Some(part.number),
+                });
+                next += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve files listed in the config against the project directory
+    ///
+    /// A listed file that doesn't exist is an error, so a typo can't drop a
+    /// chapter from the document unnoticed.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn listed_files(base_dir: &Path, files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        files
+            .iter()
+            .map(|file| {
+                base_dir.join(file).canonicalize().map_err(|_| {
+                    crate::Error::Config(format!(
+                        "File listed in [chapters] not found: {}",
+                        file.display()
+                    ))
+                })
+            })
+            .collect()
+    }
+
     /// Find appendix files matching pattern
     #[cfg(not(target_arch = "wasm32"))]
-    fn find_appendices(base_dir: &Path, _pattern: &str) -> Result<Vec<AppendixFile>> {
+    fn find_appendices(base_dir: &Path, pattern: &str) -> Result<Vec<AppendixFile>> {
         use glob::glob;
 
+        let filter = filename_filter(pattern)?;
         let mut appendices = Vec::new();
         let pattern_str = format!("{}/*.md", base_dir.display());
 
@@ -211,6 +461,9 @@ impl DiscoveredProject {
             match entry {
                 Ok(path) => {
                     if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
+                        if !filter(filename) {
+                            continue;
+                        }
                         if let Some((number, name)) = parse_appendix_filename(filename) {
                             // Convert number to letter (1 -> A, 2 -> B, etc.)
                             let letter = if number > 0 && number <= 26 {
@@ -230,7 +483,10 @@ impl DiscoveredProject {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Warning: Error reading file: {}", e);
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        format!("Error reading file: {}", e),
+                    );
                 }
             }
         }
@@ -262,6 +518,74 @@ impl DiscoveredProject {
     }
 }
 
+/// Build a case-insensitive filename matcher from a `chapters.pattern` style glob
+#[cfg(not(target_arch = "wasm32"))]
+fn filename_filter(pattern: &str) -> Result<impl Fn(&str) -> bool> {
+    let pattern = glob::Pattern::new(pattern)
+        .map_err(|e| crate::Error::Config(format!("Invalid file pattern '{}': {}", pattern, e)))?;
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+    Ok(move |filename: &str| pattern.matches_with(filename, options))
+}
+
+/// Build a matcher for paths left out by `chapters.exclude` and the
+/// project's `.md2docxignore`
+///
+/// Patterns are matched case-insensitively against the path relative to
+/// `base_dir`, gitignore style: a pattern without `/` (`*.draft.md`) matches
+/// a file or directory name at any depth, one with `/` (`drafts/**`) the
+/// path from the project directory, and anything inside an excluded
+/// directory is excluded too.
+#[cfg(not(target_arch = "wasm32"))]
+fn exclusion_filter(base_dir: &Path, exclude: &[String]) -> Result<impl Fn(&Path) -> bool> {
+    let mut lines = exclude.to_vec();
+    if let Ok(ignore) = std::fs::read_to_string(base_dir.join(IGNORE_FILE)) {
+        lines.extend(
+            ignore
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    let patterns = lines
+        .iter()
+        .map(|line| {
+            let pattern = line.trim_matches('/');
+            glob::Pattern::new(pattern)
+                .map(|glob| (glob, pattern.contains('/')))
+                .map_err(|e| {
+                    crate::Error::Config(format!("Invalid exclude pattern '{}': {}", line, e))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let base_dir = base_dir.to_path_buf();
+    Ok(move |path: &Path| {
+        let Ok(relative) = path.strip_prefix(&base_dir) else {
+            return false;
+        };
+        let mut prefix = PathBuf::new();
+        relative.components().any(|component| {
+            prefix.push(component);
+            let name = component.as_os_str().to_string_lossy();
+            patterns.iter().any(|(glob, anchored)| {
+                if *anchored {
+                    glob.matches_path_with(&prefix, options)
+                } else {
+                    glob.matches_with(&name, options)
+                }
+            })
+        })
+    })
+}
+
 /// Parse chapter number and name from filename
 ///
 /// Supports patterns like:
@@ -299,6 +623,35 @@ pub fn parse_chapter_filename(filename: &str) -> Option<(u32, String)> {
     Some((number, name))
 }
 
+/// Parse part number and name from a directory name
+///
+/// Supports names like:
+/// - `part-01-intro` -> (1, "intro")
+/// - `part2_advanced` -> (2, "advanced")
+/// - `Part-03` -> (3, "")
+pub fn parse_part_dirname(dirname: &str) -> Option<(u32, String)> {
+    if !dirname.get(..4)?.eq_ignore_ascii_case("part") {
+        return None;
+    }
+    let rest = dirname[4..].trim_start_matches(['-', '_', ' ']);
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let number: u32 = rest[..digits].parse().ok()?;
+    let name = rest[digits..]
+        .trim_start_matches(['-', '_', ' '])
+        .to_string();
+    Some((number, name))
+}
+
+/// Parse the number prefix and name of a chapter file inside a part
+///
+/// Supports names like `01-overview.md` -> (1, "overview") and
+/// `02_setup.md` -> (2, "setup").
+pub fn parse_numbered_filename(filename: &str) -> Option<(u32, String)> {
+    let stem = filename.strip_suffix(".md")?;
+    let (number, name) = stem.split_once(['-', '_'])?;
+    Some((number.parse().ok()?, name.to_string()))
+}
+
 /// Parse appendix number and name from filename
 ///
 /// Supports patterns like:
@@ -417,6 +770,27 @@ mod tests {
         assert_eq!(parse_appendix_filename("apxx_troubleshooting.md"), None); // Invalid number
     }
 
+    #[test]
+    fn test_parse_part_dirname() {
+        assert_eq!(
+            parse_part_dirname("part-01-intro"),
+            Some((1, "intro".to_string()))
+        );
+        assert_eq!(
+            parse_part_dirname("Part2_advanced"),
+            Some((2, "advanced".to_string()))
+        );
+        assert_eq!(parse_part_dirname("part-03"), Some((3, String::new())));
+        assert_eq!(parse_part_dirname("part-intro"), None);
+        assert_eq!(parse_part_dirname("images"), None);
+        assert_eq!(
+            parse_numbered_filename("01-overview.md"),
+            Some((1, "overview".to_string()))
+        );
+        assert_eq!(parse_numbered_filename("part.md"), None);
+        assert_eq!(parse_numbered_filename("01-overview.txt"), None);
+    }
+
     #[test]
     fn test_discovered_project_default() {
         let project = DiscoveredProject::default();
@@ -439,11 +813,13 @@ mod tests {
             number: 1,
             path: PathBuf::from("/test/ch01_intro.md"),
             name: "intro".to_string(),
+            part: None,
         });
         project.chapters.push(ChapterFile {
             number: 2,
             path: PathBuf::from("/test/ch02_setup.md"),
             name: "setup".to_string(),
+            part: None,
         });
         project.appendices.push(AppendixFile {
             number: 1,
@@ -452,14 +828,18 @@ mod tests {
             letter: 'A',
         });
         project.bibliography = Some(PathBuf::from("/test/bibliography.md"));
+        project.frontmatter.push(PathBuf::from("/test/preface.md"));
+        project.backmatter.push(PathBuf::from("/test/glossary.md"));
 
         let files = project.all_files();
-        assert_eq!(files.len(), 5);
+        assert_eq!(files.len(), 7);
         assert_eq!(files[0], &PathBuf::from("/test/cover.md"));
-        assert_eq!(files[1], &PathBuf::from("/test/ch01_intro.md"));
-        assert_eq!(files[2], &PathBuf::from("/test/ch02_setup.md"));
-        assert_eq!(files[3], &PathBuf::from("/test/ap01_troubleshooting.md"));
-        assert_eq!(files[4], &PathBuf::from("/test/bibliography.md"));
+        assert_eq!(files[1], &PathBuf::from("/test/preface.md"));
+        assert_eq!(files[2], &PathBuf::from("/test/ch01_intro.md"));
+        assert_eq!(files[3], &PathBuf::from("/test/ch02_setup.md"));
+        assert_eq!(files[4], &PathBuf::from("/test/ap01_troubleshooting.md"));
+        assert_eq!(files[5], &PathBuf::from("/test/glossary.md"));
+        assert_eq!(files[6], &PathBuf::from("/test/bibliography.md"));
     }
 
     #[test]
@@ -479,6 +859,7 @@ mod tests {
             number: 1,
             path: PathBuf::from("/test/ch01_intro.md"),
             name: "intro".to_string(),
+            part: None,
         });
         assert!(project.is_valid());
     }
@@ -646,4 +1027,196 @@ mod tests {
 
         fs::remove_dir_all(test_dir).unwrap();
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_discover_project_chapter_pattern() {
+        use std::fs;
+
+        let temp_dir = std::env::temp_dir();
+        let test_dir = temp_dir.join("md2docx_test_pattern");
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("ch01_intro.md"), "# Chapter 1").unwrap();
+        fs::write(test_dir.join("CH02_setup.md"), "# Chapter 2").unwrap();
+        fs::write(test_dir.join("ch03_extra.md"), "# Chapter 3").unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.chapters.pattern = "ch0[12]_*.md".to_string();
+        let project = DiscoveredProject::discover_with_config(&test_dir, &config).unwrap();
+
+        let numbers: Vec<_> = project.chapters.iter().map(|c| c.number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_discover_project_listed_files() {
+        use std::fs;
+
+        let temp_dir = std::env::temp_dir();
+        let test_dir = temp_dir.join("md2docx_test_listed");
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("preface.md"), "# Preface").unwrap();
+        fs::write(test_dir.join("setup.md"), "# Setup").unwrap();
+        fs::write(test_dir.join("ch05_intro.md"), "# Intro").unwrap();
+        fs::write(test_dir.join("ap01_faq.md"), "# FAQ").unwrap();
+        fs::write(test_dir.join("ap02_glossary.md"), "# Glossary").unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.chapters.files = vec!["ch05_intro.md".into(), "setup.md".into()];
+        config.chapters.frontmatter = vec!["preface.md".into()];
+        config.chapters.backmatter = vec!["ap02_glossary.md".into()];
+        let project = DiscoveredProject::discover_with_config(&test_dir, &config).unwrap();
+
+        let chapters: Vec<_> = project
+            .chapters
+            .iter()
+            .map(|c| (c.number, c.name.as_str()))
+            .collect();
+        assert_eq!(chapters, vec![(1, "intro"), (2, "setup")]);
+        assert_eq!(project.appendices.len(), 1);
+        let names: Vec<_> = project
+            .all_files()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "preface.md",
+                "ch05_intro.md",
+                "setup.md",
+                "ap01_faq.md",
+                "ap02_glossary.md"
+            ]
+        );
+
+        config.chapters.files.push("missing.md".into());
+        assert!(DiscoveredProject::discover_with_config(&test_dir, &config).is_err());
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_discover_project_parts() {
+        use std::fs;
+
+        let temp_dir = std::env::temp_dir();
+        let test_dir = temp_dir.join("md2docx_test_parts");
+        let _ = fs::remove_dir_all(&test_dir);
+        let intro = test_dir.join("part-01-intro");
+        let advanced = test_dir.join("part-02-advanced");
+        fs::create_dir_all(&intro).unwrap();
+        fs::create_dir_all(&advanced).unwrap();
+        fs::create_dir_all(test_dir.join("images")).unwrap();
+
+        fs::write(test_dir.join("ch01_welcome.md"), "# Welcome").unwrap();
+        fs::write(intro.join("02-setup.md"), "# Setup").unwrap();
+        fs::write(intro.join("01-overview.md"), "# Overview").unwrap();
+        fs::write(intro.join("part.md"), "# {{part}}").unwrap();
+        fs::write(advanced.join("01-tuning.md"), "# Tuning").unwrap();
+
+        let project = DiscoveredProject::discover(&test_dir).unwrap();
+
+        let parts: Vec<_> = project
+            .parts
+            .iter()
+            .map(|p| (p.number, p.name.as_str(), p.divider.is_some()))
+            .collect();
+        assert_eq!(parts, vec![(1, "intro", true), (2, "advanced", false)]);
+        let chapters: Vec<_> = project
+            .chapters
+            .iter()
+            .map(|c| (c.number, c.name.as_str(), c.part))
+            .collect();
+        assert_eq!(
+            chapters,
+            vec![
+                (1, "welcome", None),
+                (2, "overview", Some(1)),
+                (3, "setup", Some(1)),
+                (4, "tuning", Some(2)),
+            ]
+        );
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_discover_project_exclude() {
+        use std::fs;
+
+        let temp_dir = std::env::temp_dir();
+        let test_dir = temp_dir.join("md2docx_test_exclude");
+        let _ = fs::remove_dir_all(&test_dir);
+        let part = test_dir.join("part-01-intro");
+        fs::create_dir_all(test_dir.join("drafts")).unwrap();
+        fs::create_dir_all(&part).unwrap();
+
+        fs::write(test_dir.join("ch01_intro.md"), "# Intro").unwrap();
+        fs::write(test_dir.join("ch02_next.draft.md"), "# WIP").unwrap();
+        fs::write(test_dir.join("ap01_notes.md"), "# Notes").unwrap();
+        fs::write(part.join("01-overview.md"), "# Overview").unwrap();
+        fs::write(part.join("02-todo.md"), "# TODO").unwrap();
+        fs::write(part.join("03-setup.md"), "# Setup").unwrap();
+        fs::write(
+            test_dir.join(IGNORE_FILE),
+            "# WIP\nap01_*.md\npart-01-intro/02-*\n",
+        )
+        .unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.chapters.exclude = vec!["*.DRAFT.md".to_string(), "drafts/**".to_string()];
+        let project = DiscoveredProject::discover_with_config(&test_dir, &config).unwrap();
+
+        let chapters: Vec<_> = project
+            .chapters
+            .iter()
+            .map(|c| (c.number, c.name.as_str()))
+            .collect();
+        assert_eq!(chapters, vec![(1, "intro"), (2, "overview"), (3, "setup")]);
+        assert!(project.appendices.is_empty());
+
+        config.chapters.exclude = vec!["[".to_string()];
+        assert!(DiscoveredProject::discover_with_config(&test_dir, &config).is_err());
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_project_from_files() {
+        let mut config = ProjectConfig::default();
+        config.chapters.frontmatter = vec!["preface.md".into()];
+        let files: Vec<PathBuf> = [
+            "cover.md",
+            "preface.md",
+            "ch02_setup.md",
+            "overview.md",
+            "ap03_faq.md",
+            "references.md",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let project = DiscoveredProject::from_files(Path::new(""), &files, &config);
+
+        assert_eq!(project.cover, Some(PathBuf::from("cover.md")));
+        assert_eq!(project.frontmatter, vec![PathBuf::from("preface.md")]);
+        let chapters: Vec<_> = project
+            .chapters
+            .iter()
+            .map(|c| (c.number, c.name.as_str()))
+            .collect();
+        assert_eq!(chapters, vec![(2, "setup"), (2, "overview")]);
+        assert_eq!(project.appendices[0].letter, 'A');
+        assert_eq!(project.bibliography, Some(PathBuf::from("references.md")));
+        assert_eq!(project.all_files().len(), 6);
+    }
 }
@@ -4,23 +4,112 @@
 //! document structure, converting markdown elements to DOCX paragraphs
 //! and runs.
 
+use crate::diagnostics::WarningCategory;
 use crate::docx::image_utils::{
-    calculate_image_size_emu, default_image_size_emu, read_image_dimensions,
+    calculate_image_size_emu, default_image_size_emu, inline_image_size_emu, read_image_dimensions,
+    ImageDimensions,
 };
+use crate::docx::ooxml::numbering::ORDERED_FORMATS;
 use crate::docx::ooxml::{
-    DocElement, DocumentXml, FooterConfig, FooterXml, FootnotesXml, HeaderConfig, HeaderFooterRefs,
-    HeaderXml, ImageElement, Paragraph, ParagraphChild, Run, Table, TableCellElement, TableRow,
-    TableWidth, TabStop,
+    heading_style_id, DocElement, DocumentXml, FooterConfig, FooterXml, FootnotesXml, HeaderConfig,
+    HeaderFooterField, HeaderFooterRefs, HeaderXml, ImageElement, NumberingConfig, Paragraph,
+    ParagraphChild, ParagraphPreset, Run, StyleSpacing, TabStop, Table, TableCellElement, TableRow,
+    TableWidth, SECTION_BREAKS,
 };
 use crate::docx::rels_manager::RelIdManager;
+use crate::docx::spill::{AssetStore, FragmentStore};
 use crate::docx::toc::{TocBuilder, TocConfig};
 use crate::docx::xref::CrossRefContext;
+use crate::i18n::{default_label, Label, Punctuation, SmartPunctuation, Strings};
 use crate::parser::{
-    extract_inline_text, Alignment as ParserAlignment, Block, Inline, ListItem, ParsedDocument,
-    RefType, TableCell as ParserTableCell,
+    callout_glyph, extract_inline_text, Alignment as ParserAlignment, Block, IncludeResolver,
+    Inline, ListItem, ParsedDocument, RefType, TableCell as ParserTableCell,
 };
 use crate::template::extract::table::TableTemplate;
+use crate::vfs::FileMap;
 use crate::Language;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// Matches `<!-- {toc:off} -->` / `<!-- {toc:on} -->`, which stop and resume
+/// listing headings in the TOC
+static TOC_DIRECTIVE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<!--\s*\{toc:(on|off)\}\s*-->\s*$").expect("Invalid regex"));
+
+/// Matches `<!-- {section: restart=1, format=lowerRoman, header=none} -->`,
+/// which sets page numbering, header/footer and break options of the
+/// section containing it
+static SECTION_DIRECTIVE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<!--\s*\{section:([^}]*)\}\s*-->\s*$").expect("Invalid regex"));
+
+/// Matches `<!-- {edited: Somchai | 1 March 2025} -->`, the last author and
+/// edit date of a chapter, written after its next heading as hidden
+/// paragraphs that `{chapter_author}` / `{chapter_date}` header fields read
+static EDITED_DIRECTIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^<!--\s*\{edited:([^|}]*)\|([^}]*)\}\s*-->\s*$").expect("Invalid regex")
+});
+
+/// Section options from a `<!-- {section:...} -->` directive
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SectionOptions {
+    /// Restart page numbering at this number
+    page_num_start: Option<u32>,
+    /// Page number format (`lowerRoman`, `decimal`, ...)
+    page_num_format: Option<String>,
+    /// Use the empty header/footer (`header=none`)
+    suppress_header_footer: bool,
+    /// How the section starts (`break=oddPage`)
+    section_break: Option<String>,
+}
+
+impl SectionOptions {
+    fn parse(params: &str) -> Self {
+        let mut options = Self::default();
+        for item in params.split([',', ' ']).filter(|s| !s.is_empty()) {
+            match item.split_once('=') {
+                Some(("restart", n)) => match n.parse() {
+                    Ok(n) => options.page_num_start = Some(n),
+                    Err(_) => crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        format!("Invalid page number in section directive: {}", item),
+                    ),
+                },
+                Some(("format", format)) => {
+                    match ORDERED_FORMATS
+                        .iter()
+                        .find(|f| f.eq_ignore_ascii_case(format))
+                    {
+                        Some(known) => options.page_num_format = Some(known.to_string()),
+                        None => crate::diagnostics::warn(
+                            WarningCategory::Other,
+                            format!("Unknown page number format in section directive: {}", item),
+                        ),
+                    }
+                }
+                Some(("break", kind)) => {
+                    match SECTION_BREAKS.iter().find(|b| b.eq_ignore_ascii_case(kind)) {
+                        Some(known) => options.section_break = Some(known.to_string()),
+                        None => crate::diagnostics::warn(
+                            WarningCategory::Other,
+                            format!("Unknown section break in section directive: {}", item),
+                        ),
+                    }
+                }
+                Some(("header", "none")) => options.suppress_header_footer = true,
+                Some(("header", "default")) => {}
+                _ => crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!("Unknown section directive option: {}", item),
+                ),
+            }
+        }
+        options
+    }
+}
 
 /// Tracks images during document building
 #[derive(Debug, Default)]
@@ -29,17 +118,83 @@ pub(crate) struct ImageContext {
     pub images: Vec<ImageInfo>,
     /// Base directory for resolving relative image paths
     pub base_path: Option<std::path::PathBuf>,
+    /// Image sizes read ahead of the build, keyed by resolved path
+    pub probed_dimensions: HashMap<String, Option<ImageDimensions>>,
+    /// Writes rendered images past the memory budget to disk
+    pub assets: Option<AssetStore>,
+    /// Files to embed images from instead of the file system
+    pub files: Option<Arc<FileMap>>,
 }
 
 /// Information about an embedded image
 #[derive(Debug, Clone)]
 pub(crate) struct ImageInfo {
-    pub filename: String,      // e.g., "image1.png"
-    pub rel_id: String,        // e.g., "rId4"
-    pub src: String,           // Original source path/URL
-    pub data: Option<Vec<u8>>, // Image bytes (None if external)
-    pub width_emu: i64,        // Width in EMUs
-    pub height_emu: i64,       // Height in EMUs
+    pub filename: String,        // e.g., "image1.png"
+    pub rel_id: String,          // e.g., "rId4"
+    pub src: String,             // Original source path/URL
+    pub data: Option<Arc<[u8]>>, // Image bytes (None if read from `src`)
+    pub width_emu: i64,          // Width in EMUs
+    pub height_emu: i64,         // Height in EMUs
+}
+
+/// Reader over an image's bytes, from disk when not in memory
+fn content_reader(image: &ImageInfo) -> Option<Box<dyn std::io::Read + '_>> {
+    match &image.data {
+        Some(data) => Some(Box::new(&data[..])),
+        #[cfg(not(target_arch = "wasm32"))]
+        None => Some(Box::new(std::fs::File::open(&image.src).ok()?)),
+        #[cfg(target_arch = "wasm32")]
+        None => None,
+    }
+}
+
+/// Hash and length of an image's bytes, streamed from disk when not in memory
+///
+/// Returns `None` for images that cannot be read; those are reported as
+/// missing during packaging.
+fn content_key(image: &ImageInfo) -> Option<(u64, u64)> {
+    struct HashWriter(DefaultHasher, u64);
+
+    impl std::io::Write for HashWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf);
+            self.1 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut hasher = HashWriter(DefaultHasher::new(), 0);
+    std::io::copy(&mut content_reader(image)?, &mut hasher).ok()?;
+    Some((hasher.0.finish(), hasher.1))
+}
+
+/// Whether two images have the same bytes, compared a buffer at a time
+fn same_content(a: &ImageInfo, b: &ImageInfo) -> bool {
+    use std::io::BufRead;
+
+    let (Some(a), Some(b)) = (content_reader(a), content_reader(b)) else {
+        return false;
+    };
+    let mut a = std::io::BufReader::new(a);
+    let mut b = std::io::BufReader::new(b);
+    loop {
+        let (Ok(left), Ok(right)) = (a.fill_buf(), b.fill_buf()) else {
+            return false;
+        };
+        if left.is_empty() || right.is_empty() {
+            return left.is_empty() && right.is_empty();
+        }
+        let len = left.len().min(right.len());
+        if left[..len] != right[..len] {
+            return false;
+        }
+        a.consume(len);
+        b.consume(len);
+    }
 }
 
 /// Tracks hyperlinks during document building
@@ -64,7 +219,7 @@ impl HyperlinkContext {
 
     /// Add a hyperlink and return its relationship ID
     pub fn add_hyperlink(&mut self, url: &str, rel_manager: &mut RelIdManager) -> String {
-        let rel_id = rel_manager.next_id();
+        let rel_id = rel_manager.id_for(url);
         self.hyperlinks.push(HyperlinkInfo {
             url: url.to_string(),
             rel_id: rel_id.clone(),
@@ -81,7 +236,6 @@ impl HyperlinkContext {
 pub(crate) struct NumberingContext {
     /// List of (numId, is_ordered) pairs for all lists
     pub lists: Vec<NumberingInfo>,
-    next_id: u32,
 }
 
 /// Information about a list numbering instance
@@ -93,22 +247,20 @@ pub(crate) struct NumberingInfo {
 
 impl NumberingContext {
     pub fn new() -> Self {
-        Self {
-            lists: Vec::new(),
-            next_id: 1,
-        }
+        Self { lists: Vec::new() }
     }
 
     /// Register a new list and return its unique numId
     ///
     /// Each call creates a new list instance that will restart numbering.
-    pub fn add_list(&mut self, ordered: bool) -> u32 {
-        let num_id = self.next_id;
+    /// The numId comes from `rel_manager`, so lists from every build that
+    /// shares it can go into one numbering.xml.
+    pub fn add_list(&mut self, ordered: bool, rel_manager: &mut RelIdManager) -> u32 {
+        let num_id = rel_manager.next_num_id();
         self.lists.push(NumberingInfo {
             num_id,
             is_ordered: ordered,
         });
-        self.next_id += 1;
         num_id
     }
 }
@@ -118,6 +270,9 @@ impl ImageContext {
         Self {
             images: Vec::new(),
             base_path: None,
+            probed_dimensions: HashMap::new(),
+            assets: None,
+            files: None,
         }
     }
 
@@ -129,7 +284,7 @@ impl ImageContext {
     }
 
     /// Resolve an image source path against the base path if set
-    fn resolve_image_path(&self, src: &str) -> String {
+    pub(crate) fn resolve_image_path(&self, src: &str) -> String {
         // Skip if it's a URL, absolute path, or data URI
         if src.starts_with("http://")
             || src.starts_with("https://")
@@ -159,28 +314,89 @@ impl ImageContext {
         width: Option<&str>,
         rel_manager: &mut RelIdManager,
     ) -> String {
-        let rel_id = rel_manager.next_id();
+        self.register_image(src, rel_manager, |ctx, dims| ctx.parse_dimensions(width, dims))
+    }
+
+    /// Add an image that sits in a line of text and return its relationship ID
+    ///
+    /// Inline images keep their natural size at 96 DPI, so icons and badges
+    /// stay small on the text baseline; larger images are scaled down to fit
+    /// `max_width_emu`, the width of the text column. Returns `None` (with a
+    /// warning) when the image file doesn't exist, so no relationship is
+    /// left without a media part behind it.
+    pub fn add_inline_image(
+        &mut self,
+        src: &str,
+        max_width_emu: i64,
+        rel_manager: &mut RelIdManager,
+    ) -> Option<String> {
+        let resolved_src = self.resolve_image_path(src);
+        if !self.image_exists(&resolved_src) {
+            crate::diagnostics::warn(
+                WarningCategory::MissingImage,
+                format!("Image not found: {}", resolved_src),
+            );
+            return None;
+        }
+        Some(self.register_image(src, rel_manager, |_, dims| {
+            inline_image_size_emu(dims, max_width_emu)
+        }))
+    }
+
+    /// Whether an image can be embedded from the file map or the file system
+    fn image_exists(&self, resolved_src: &str) -> bool {
+        let path = std::path::Path::new(resolved_src);
+        match &self.files {
+            Some(files) => files.contains(path),
+            #[cfg(not(target_arch = "wasm32"))]
+            None => path.is_file(),
+            #[cfg(target_arch = "wasm32")]
+            None => false,
+        }
+    }
+
+    /// Register an image, sizing it from its actual dimensions with `size`
+    fn register_image(
+        &mut self,
+        src: &str,
+        rel_manager: &mut RelIdManager,
+        size: impl FnOnce(&Self, Option<ImageDimensions>) -> (i64, i64),
+    ) -> String {
+        let rel_id = rel_manager.id_for(src);
         let filename = self.generate_filename(src, rel_id.clone());
 
         // Resolve the source path against base path
         let resolved_src = self.resolve_image_path(src);
 
-        // Try to read actual dimensions from resolved path
-        let mut actual_dims = None;
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            if let Ok(data) = std::fs::read(&resolved_src) {
-                actual_dims = read_image_dimensions(&data);
+        // Images registered in a file map are embedded from memory
+        let data = self
+            .files
+            .as_ref()
+            .and_then(|files| files.get(std::path::Path::new(&resolved_src)));
+
+        // Use the size probed ahead of the build, or read it from the file
+        let actual_dims = match (&data, self.probed_dimensions.get(&resolved_src)) {
+            (Some(data), _) => read_image_dimensions(data),
+            (None, Some(dims)) => *dims,
+            #[cfg(not(target_arch = "wasm32"))]
+            (None, None) if self.files.is_none() => {
+                crate::docx::image_utils::read_image_dimensions_from_file(std::path::Path::new(
+                    &resolved_src,
+                ))
             }
-        }
+            #[cfg(not(target_arch = "wasm32"))]
+            (None, None) => None,
+            #[cfg(target_arch = "wasm32")]
+            (None, None) => None,
+        };
 
-        let (width_emu, height_emu) = self.parse_dimensions(width, actual_dims);
+        let (width_emu, height_emu) = size(self, actual_dims);
 
         self.images.push(ImageInfo {
             filename: filename.clone(),
             rel_id: rel_id.clone(),
             src: resolved_src, // Store resolved path for later reading
-            data: None,        // Data loaded during packaging
+            data,              // Otherwise loaded during packaging
             width_emu,
             height_emu,
         });
@@ -188,6 +404,44 @@ impl ImageContext {
         rel_id
     }
 
+    /// Point images with identical content at a single media file
+    ///
+    /// The same logo or screenshot used in several chapters is then packaged
+    /// once: every use keeps its relationship ID, and those relationships
+    /// share the first image's file. Images referencing the same path are
+    /// matched without reading them; others are compared by content hash,
+    /// then byte for byte.
+    pub fn dedupe_media(&mut self) {
+        let mut by_path: HashMap<String, String> = HashMap::new();
+        // Images with distinct bytes for each hash, as indexes into `images`
+        let mut by_content: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+
+        for i in 0..self.images.len() {
+            let image = &self.images[i];
+            if image.data.is_none() {
+                if let Some(filename) = by_path.get(&image.src) {
+                    self.images[i].filename = filename.clone();
+                    continue;
+                }
+            }
+            if let Some(key) = content_key(image) {
+                // A matching hash is only a candidate; the bytes decide
+                let firsts = by_content.entry(key).or_default();
+                match firsts
+                    .iter()
+                    .find(|&&first| same_content(&self.images[first], image))
+                {
+                    Some(&first) => self.images[i].filename = self.images[first].filename.clone(),
+                    None => firsts.push(i),
+                }
+            }
+            let image = &self.images[i];
+            if image.data.is_none() {
+                by_path.insert(image.src.clone(), image.filename.clone());
+            }
+        }
+    }
+
     /// Add image from raw data (for generated images like mermaid PNGs)
     pub fn add_image_data(
         &mut self,
@@ -196,7 +450,7 @@ impl ImageContext {
         width: Option<&str>,
         rel_manager: &mut RelIdManager,
     ) -> String {
-        let rel_id = rel_manager.next_id();
+        let rel_id = rel_manager.id_for(filename);
 
         // Try to read dimensions from the image data
         let (width_emu, height_emu) = if let Some(dims) = read_image_dimensions(&data) {
@@ -213,11 +467,28 @@ impl ImageContext {
             (width_emu, height_emu)
         };
 
+        // Past the memory budget, package the image from a spill file
+        let spilled = match self.assets.as_mut().map(|a| a.spill(filename, &data)) {
+            Some(Ok(path)) => path,
+            Some(Err(e)) => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!("Keeping {} in memory, spilling failed: {}", filename, e),
+                );
+                None
+            }
+            None => None,
+        };
+        let (src, data) = match spilled {
+            Some(path) => (path.to_string_lossy().into_owned(), None),
+            None => (filename.to_string(), Some(data.into())),
+        };
+
         self.images.push(ImageInfo {
             filename: filename.to_string(),
             rel_id: rel_id.clone(),
-            src: filename.to_string(),
-            data: Some(data),
+            src,
+            data,
             width_emu: final_width,
             height_emu: final_height,
         });
@@ -401,6 +672,36 @@ pub fn parse_length_to_twips(length: &str) -> Option<u32> {
     Some(twips.round() as u32)
 }
 
+/// Height of the blank line a spacer paragraph leaves (12pt), in twips
+const SPACER_TWIPS: u32 = 240;
+
+/// How the gaps before headings and after tables are made, set with
+/// `[document] spacers`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Spacers {
+    /// An empty paragraph before a heading that follows body text and
+    /// after every table
+    #[default]
+    Blank,
+    /// No empty paragraphs; the heading or paragraph that would follow one
+    /// gets 12pt of space before instead
+    Spacing,
+    /// No empty paragraphs and no added space
+    None,
+}
+
+impl Spacers {
+    /// Policy from its config name (`blank`, `spacing` or `none`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "" | "blank" => Some(Self::Blank),
+            "spacing" => Some(Self::Spacing),
+            "none" | "off" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
 /// Document build configuration
 #[derive(Debug, Clone)]
 pub struct DocumentConfig {
@@ -411,8 +712,6 @@ pub struct DocumentConfig {
     pub different_first_page: bool, // Hide header/footer on first page
     /// Template directory path (optional)
     pub template_dir: Option<std::path::PathBuf>,
-    /// Offset for IDs to avoid collisions (default: 0)
-    pub id_offset: u32,
     /// If true, include all headings in TOC even if they appear before a thematic break
     /// (Used when cover page is handled via template system)
     pub process_all_headings: bool,
@@ -432,6 +731,12 @@ pub struct DocumentConfig {
     /// When set, fonts are automatically scanned and embedded from this directory.
     /// If `embedded_fonts` is also populated, this field is ignored.
     pub embed_dir: Option<std::path::PathBuf>,
+    /// Cut embedded TrueType fonts down to the glyphs the document uses
+    pub subset_fonts: bool,
+    /// Spacing overrides per paragraph style id (`[styles.BodyText]`)
+    pub style_spacing: HashMap<String, StyleSpacing>,
+    /// Layout of the BodyText style (`[document] paragraph_preset`)
+    pub paragraph_preset: ParagraphPreset,
     /// Mermaid diagram spacing: (before, after) in twips
     pub mermaid_spacing: (u32, u32),
     /// Mermaid output format: "png" (default) or "svg"
@@ -444,6 +749,65 @@ pub struct DocumentConfig {
     pub math_font_size: String,
     /// Whether to number all display equations (including unlabeled ones)
     pub math_number_all: bool,
+    /// How each `---` chapter break starts the next section: "nextPage"
+    /// (default), "oddPage", "evenPage" or "continuous"
+    pub chapter_break: String,
+    /// Start every H1 on a new page (w:pageBreakBefore) unless a section
+    /// break already put it there
+    pub h1_page_break: bool,
+    /// Keep H2 headings on the same page as the paragraph after them
+    pub h2_keep_with_next: bool,
+    /// Keep the first and last lines of paragraphs off a page of their own
+    /// (w:widowControl)
+    pub widow_control: bool,
+    /// Keep each table row on one page (w:cantSplit)
+    pub table_rows_cant_split: bool,
+    /// Repeat a table's header row at the top of each page (w:tblHeader)
+    pub repeat_table_header: bool,
+    /// Split tables with more data rows than this into parts, each later
+    /// part starting a page under a "Table N (Continued)" caption
+    pub table_continuation_rows: Option<usize>,
+    /// Split code blocks with more lines than this into parts, each later
+    /// part starting a page under a "(Continued)" filename line
+    pub code_continuation_lines: Option<usize>,
+    /// Empty paragraphs before headings and after tables
+    pub spacers: Spacers,
+    /// Styles offered in Word's Quick Styles gallery and the visibility of
+    /// Word's other built-in styles
+    pub style_gallery: crate::docx::ooxml::StyleGallery,
+    /// Diagonal text watermark shown behind every page (e.g. "DRAFT")
+    pub watermark: Option<String>,
+    /// List number formats and bullet glyphs per level
+    pub numbering: NumberingConfig,
+    /// Footnote and endnote number format, restart and separator
+    pub notes: crate::docx::ooxml::NoteNumbering,
+    /// User replacements for generated strings (TOC title, caption prefixes...)
+    pub string_overrides: HashMap<Label, String>,
+    /// Smart punctuation rules; `None` keeps quotes and dashes as typed
+    pub punctuation: Option<Punctuation>,
+    /// ZIP compression of the packaged DOCX
+    pub compression: crate::docx::Compression,
+    /// Resampling of oversized images as they are embedded
+    pub image_limits: crate::docx::ImageLimits,
+    /// Render the body as it is built, spilling past this budget to disk
+    pub memory_budget: Option<crate::docx::MemoryBudget>,
+    /// Resolve `{!include:...}` and `{!code:...}` directives left in the
+    /// parsed document while building, one top-level block at a time
+    pub includes: Option<crate::parser::IncludeConfig>,
+    /// Produce byte-identical output for identical input: fixed timestamps,
+    /// sorted relationships and IDs derived from content
+    pub reproducible: bool,
+    /// Fail the build if a generated part references a relationship its
+    /// `.rels` part doesn't define (always checked in debug builds)
+    pub audit_rels: bool,
+    /// Files to read includes and images from instead of the file system
+    pub files: Option<Arc<FileMap>>,
+    /// Download settings for `{!include:https://...}`; URLs are rejected
+    /// when unset
+    pub remote_includes: Option<crate::parser::RemoteIncludes>,
+    /// Earlier version to compare against; its differences from this
+    /// document are written as tracked changes (see [`crate::diff`])
+    pub compare_to: Option<Arc<crate::diff::Version>>,
 }
 
 impl Default for DocumentConfig {
@@ -455,7 +819,6 @@ impl Default for DocumentConfig {
             footer: FooterConfig::default(),
             different_first_page: false,
             template_dir: None,
-            id_offset: 0,
             process_all_headings: false,
             header_footer_template: None,
             document_meta: None,
@@ -464,13 +827,54 @@ impl Default for DocumentConfig {
             page: None,
             embedded_fonts: Vec::new(),
             embed_dir: None,
+            subset_fonts: false,
+            style_spacing: HashMap::new(),
+            paragraph_preset: ParagraphPreset::Default,
             mermaid_spacing: (120, 120),
             mermaid_output_format: "png".to_string(),
             mermaid_dpi: 150,
             math_renderer: "image".to_string(),
             math_font_size: "10pt".to_string(),
             math_number_all: false,
+            chapter_break: "nextPage".to_string(),
+            h1_page_break: false,
+            h2_keep_with_next: false,
+            widow_control: true,
+            table_rows_cant_split: false,
+            repeat_table_header: true,
+            table_continuation_rows: None,
+            code_continuation_lines: None,
+            spacers: Spacers::Blank,
+            style_gallery: crate::docx::ooxml::StyleGallery::default(),
+            watermark: None,
+            numbering: NumberingConfig::default(),
+            notes: crate::docx::ooxml::NoteNumbering::default(),
+            string_overrides: HashMap::new(),
+            punctuation: None,
+            compression: crate::docx::Compression::default(),
+            image_limits: crate::docx::ImageLimits::default(),
+            memory_budget: None,
+            includes: None,
+            reproducible: false,
+            audit_rels: false,
+            files: None,
+            remote_includes: None,
+            compare_to: None,
+        }
+    }
+}
+
+impl DocumentConfig {
+    /// Resolver for the `includes` settings, reading from `files` when set
+    pub(crate) fn include_resolver(&self) -> Option<IncludeResolver> {
+        let mut resolver = IncludeResolver::new(self.includes.clone()?);
+        if let Some(files) = &self.files {
+            resolver = resolver.with_files(files.clone());
         }
+        if let Some(remote) = &self.remote_includes {
+            resolver = resolver.with_remote(remote.clone());
+        }
+        Some(resolver)
     }
 }
 
@@ -483,6 +887,23 @@ pub(crate) struct MediaFileMapping {
     pub media_file: crate::template::extract::header_footer::MediaFile,
 }
 
+/// Media files of the images in config-generated header or footer slots
+fn image_media(slots: [&[HeaderFooterField]; 3]) -> Vec<MediaFileMapping> {
+    crate::docx::ooxml::header_footer_images(slots)
+        .into_iter()
+        .map(|(rel_id, image)| MediaFileMapping {
+            original_rel_id: rel_id,
+            media_file: crate::template::extract::header_footer::MediaFile {
+                filename: image.filename.clone(),
+                data: image.data.clone(),
+                content_type: crate::template::extract::header_footer::guess_content_type(
+                    &image.filename,
+                ),
+            },
+        })
+        .collect()
+}
+
 /// Header or footer entry with associated media files
 #[derive(Debug)]
 pub(crate) struct HeaderFooterEntry {
@@ -509,6 +930,28 @@ pub(crate) struct BuildResult {
     pub toc_builder: Option<TocBuilder>,
 }
 
+impl BuildResult {
+    /// Whether the image with `rel_id` is drawn in a footnote
+    pub fn is_footnote_image(&self, rel_id: &str) -> bool {
+        self.footnotes.image_rel_ids.iter().any(|id| id == rel_id)
+    }
+
+    /// Relationships of the hyperlinks and images in footnotes, for
+    /// word/_rels/footnotes.xml.rels
+    pub fn footnote_rels(&self) -> crate::docx::ooxml::Relationships {
+        let mut rels = crate::docx::ooxml::Relationships::new();
+        for (rel_id, url) in &self.footnotes.hyperlinks {
+            rels.add_hyperlink_with_id(rel_id, url);
+        }
+        for image in &self.images.images {
+            if self.is_footnote_image(&image.rel_id) {
+                rels.add_image_with_id(&image.rel_id, &image.filename);
+            }
+        }
+        rels
+    }
+}
+
 /// Check if a block is a heading
 fn is_heading(block: &Block) -> bool {
     matches!(block, Block::Heading { .. })
@@ -545,25 +988,47 @@ pub(crate) fn build_document(
     rel_manager: &mut RelIdManager,
     table_template: Option<&TableTemplate>,
     image_template: Option<&crate::template::extract::image::ImageTemplate>,
+) -> crate::error::Result<BuildResult> {
+    // Chapters are built at least twice, which pays off from three threads on
+    #[cfg(feature = "parallel")]
+    let parallel_chapters = rayon::current_num_threads() > 2;
+    #[cfg(not(feature = "parallel"))]
+    let parallel_chapters = false;
+    build_document_with(
+        doc,
+        lang,
+        config,
+        rel_manager,
+        table_template,
+        image_template,
+        parallel_chapters,
+    )
+}
+
+/// [`build_document`], building chapters on all cores when `parallel_chapters` is set
+fn build_document_with(
+    doc: &ParsedDocument,
+    lang: Language,
+    config: &DocumentConfig,
+    rel_manager: &mut RelIdManager,
+    table_template: Option<&TableTemplate>,
+    image_template: Option<&crate::template::extract::image::ImageTemplate>,
+    parallel_chapters: bool,
 ) -> crate::error::Result<BuildResult> {
     let mut doc_xml = DocumentXml::new();
+    doc_xml.rtl = lang.is_rtl();
+    doc_xml.page_num_format = config.numbering.pages.clone();
+    doc_xml.notes = config.notes.clone();
     let mut image_ctx = ImageContext::new();
     // Set base path for image resolution if provided in config
     if let Some(ref base) = config.base_path {
         image_ctx.base_path = Some(base.clone());
     }
-    let mut hyperlink_ctx = HyperlinkContext::new();
-    let mut numbering_ctx = NumberingContext::new();
-
-
+    image_ctx.assets = config.memory_budget.clone().map(AssetStore::new);
+    image_ctx.files = config.files.clone();
 
     let mut footnotes = FootnotesXml::new();
-
-    // TOC builder for collecting headings
-    let mut toc_builder = TocBuilder::new();
-    let mut bookmark_id_counter: u32 = 10000 + config.id_offset;
-    let mut table_count: u32 = 0;
-    let mut figure_count: u32 = 0;
+    footnotes.separator = config.notes.separator.clone();
 
     // Calculate body width for tab stops (page width minus margins)
     let page_width = config.page.as_ref().and_then(|p| p.width).unwrap_or(11906);
@@ -571,17 +1036,11 @@ pub(crate) fn build_document(
     let margin_right = config.page.as_ref().and_then(|p| p.margin_right).unwrap_or(1440);
     let body_width_twips = page_width.saturating_sub(margin_left + margin_right);
 
-    // Cross-reference context for tracking anchors
-    let mut xref_ctx = CrossRefContext::new();
-
     // Track headers and footers
     let mut headers = Vec::new();
     let mut footers = Vec::new();
     let mut header_footer_refs = HeaderFooterRefs::default();
 
-    // Track previous block to insert blank lines before headings
-    let mut prev_block: Option<&Block> = None;
-
     // Find the first thematic break index (end of cover section)
     // Headings before this should not be in TOC, UNLESS process_all_headings is set
     let first_thematic_break_index = if config.process_all_headings {
@@ -592,106 +1051,138 @@ pub(crate) fn build_document(
             .position(|b| matches!(b, Block::ThematicBreak))
     };
 
+    // Section type of every `---` break
+    let chapter_break = match SECTION_BREAKS
+        .iter()
+        .find(|b| b.eq_ignore_ascii_case(&config.chapter_break))
+    {
+        Some(known) => *known,
+        None => {
+            crate::diagnostics::warn(
+                WarningCategory::Other,
+                format!("Unknown chapter break type: {}", config.chapter_break),
+            );
+            "nextPage"
+        }
+    };
+
     // Normalize renderer: "image" and "auto" both resolve to "rex" (pure-Rust ReX engine)
     let resolved_math_renderer = match config.math_renderer.as_str() {
         "image" | "auto" | "rex" => "rex".to_string(),
         other => other.to_string(), // "omml" or any unknown value
     };
 
-    // Process all blocks in the document
-    // Track the last list seen to support resuming lists across code blocks
-    let mut last_list_info: Option<(u32, bool, usize)> = None; // (num_id, is_ordered, block_index)
-
-    for (i, block) in doc.blocks.iter().enumerate() {
-        // Create build context
-        let mut ctx = BuildContext::new(BuildContextParams {
-            image_ctx: &mut image_ctx,
-            hyperlink_ctx: &mut hyperlink_ctx,
-            numbering_ctx: &mut numbering_ctx,
-            doc,
-            footnotes: &mut footnotes,
-            toc_builder: &mut toc_builder,
-            bookmark_id_counter: &mut bookmark_id_counter,
-            xref_ctx: &mut xref_ctx,
-            rel_manager,
-            table_template,
-            image_template,
-            table_count: &mut table_count,
-            figure_count: &mut figure_count,
-            lang,
-            font_override: None,
-            code_font: config.fonts.as_ref().and_then(|f| f.code.clone()),
-            code_size: config.fonts.as_ref().and_then(|f| f.code_size),
-            quote_level: 0,
-            mermaid_spacing: config.mermaid_spacing,
-            mermaid_output_format: config.mermaid_output_format.clone(),
-            mermaid_dpi: config.mermaid_dpi,
-            math_renderer: resolved_math_renderer.clone(),
-            math_font_size: config.math_font_size.clone(),
-            math_number_all: config.math_number_all,
-            body_width_twips,
-        });
+    // Render diagrams and equations and read image sizes up front on all
+    // cores; the loop below then assigns IDs and numbers in document order
+    #[cfg(feature = "parallel")]
+    {
+        image_ctx.probed_dimensions = crate::docx::prerender::prerender(
+            &doc.blocks,
+            &image_ctx,
+            (config.mermaid_output_format == "png").then(|| config.mermaid_dpi as f32 / 75.0),
+            (resolved_math_renderer == "rex").then_some(config.math_font_size.as_str()),
+        );
+    }
 
-        // Insert blank paragraph before heading if previous block was not a heading
-        if is_heading(block) {
-            if let Some(prev) = prev_block {
-                if !is_heading(prev) {
-                    doc_xml.add_element(DocElement::Paragraph(Box::default()));
-                }
-            }
-            // Heading breaks any list continuation
-            last_list_info = None;
-        }
+    let env = BodyEnv {
+        doc,
+        lang,
+        config,
+        table_template,
+        image_template,
+        strings: Strings::new(lang).with_overrides(config.string_overrides.clone()),
+        math_renderer: resolved_math_renderer,
+        body_width_twips,
+        chapter_break,
+        first_thematic_break_index,
+    };
+    let mut state = BodyState::new(image_ctx, footnotes, std::mem::take(rel_manager));
+
+    // Bounded builds expand each include only when it is reached and render
+    // its elements right away, so one chapter is held in memory at a time
+    let mut includes = config.include_resolver();
+    let mut fragments = config.memory_budget.clone().map(FragmentStore::new);
+
+    // Chapters are built on all cores, unless includes are expanded or
+    // elements rendered as the build reaches them
+    #[cfg(feature = "parallel")]
+    let built = parallel_chapters
+        && includes.is_none()
+        && fragments.is_none()
+        && crate::docx::chapters::build(&env, &mut state, &mut doc_xml.elements);
+    #[cfg(not(feature = "parallel"))]
+    let built = {
+        let _ = parallel_chapters;
+        false
+    };
 
-        // Determine if we should force continuation of the previous list
-        let mut forced_num_id = None;
-        if let Block::List { ordered, .. } = block {
-            if let Some((last_id, last_ordered, last_idx)) = last_list_info {
-                // If it's the same type of list and we only skipped 1 block (e.g. a code block)
-                // Resume the list numbering.
-                // i - last_idx == 1 means adjacent (normal case, usually handled by parser merging)
-                // i - last_idx == 2 means one block in between (e.g. List -> Code -> List)
-                if *ordered == last_ordered && (i - last_idx) <= 2 {
-                    forced_num_id = Some(last_id);
+    if !built {
+        for (i, block) in doc.blocks.iter().enumerate() {
+            let rendered_from = doc_xml.elements.len();
+            let expanded;
+            let block = match (block, includes.as_mut()) {
+                (
+                    Block::Include {
+                        path,
+                        resolved: None,
+                    },
+                    Some(resolver),
+                ) => {
+                    expanded = Block::Include {
+                        path: path.clone(),
+                        resolved: Some(resolver.resolve_include(path)?),
+                    };
+                    &expanded
                 }
-            }
-        } else if !matches!(
-            block,
-            Block::CodeBlock { .. } | Block::BlockQuote(_) | Block::Table { .. }
-        ) {
-            // If the block is NOT a list, and NOT something that might be inside a list (like code/quote/table)
-            // Then it definitely breaks the list.
-            // Text paragraphs usually break lists unless indented, but here we assume top-level paragraphs break lists.
-            last_list_info = None;
-        }
-
-        // Skip TOC for blocks before first thematic break (cover section)
-        let skip_toc = first_thematic_break_index.is_some_and(|idx| i < idx);
-
-        let elements = block_to_elements(block, 0, &mut ctx, forced_num_id, skip_toc);
+                // Code includes, and includes nested in other blocks, are
+                // expanded in place
+                (
+                    Block::CodeInclude { .. }
+                    | Block::BlockQuote(_)
+                    | Block::List { .. }
+                    | Block::LangGroup { .. },
+                    Some(resolver),
+                ) => match resolver.resolve_blocks(vec![block.clone()])?.pop() {
+                    Some(resolved) => {
+                        expanded = resolved;
+                        &expanded
+                    }
+                    None => block,
+                },
+                _ => block,
+            };
 
-        // If this block was a list, update tracking info
-        if let Block::List { ordered, .. } = block {
-            // Find the num_id used. If we forced it, we know it.
-            // If we generated it, we need to extract it from the generated paragraphs.
-            // But block_to_elements doesn't return the ID.
-            // However, if forced_num_id is None, block_to_elements called add_list,
-            // so it's the last added list.
-            let used_id = forced_num_id.unwrap_or_else(|| {
-                // If we didn't force it, it was just added.
-                // NumberingContext IDs are sequential (1, 2, 3...)
-                // access internal state? NumberingContext exposes lists vec.
-                numbering_ctx.lists.last().map(|l| l.num_id).unwrap_or(0)
-            });
+            state.build_block(&env, i, block, &mut doc_xml.elements);
 
-            last_list_info = Some((used_id, *ordered, i));
+            if let Some(store) = fragments.as_mut() {
+                doc_xml.render_from(rendered_from, store)?;
+            }
         }
+    }
 
-        for elem in elements {
-            doc_xml.add_element(elem);
+    let BodyState {
+        image_ctx,
+        hyperlink_ctx,
+        numbering_ctx,
+        footnotes,
+        toc_builder,
+        rel_manager: built_rel_manager,
+        section,
+        ..
+    } = state;
+    *rel_manager = built_rel_manager;
+
+    // Options for the last section go on the document's final sectPr
+    if chapter_break != "nextPage" {
+        doc_xml.final_section_break = Some(chapter_break.to_string());
+    }
+    if let Some(options) = section {
+        if options.section_break.is_some() {
+            doc_xml.final_section_break = options.section_break;
         }
-
-        prev_block = Some(block);
+        doc_xml.page_num_start = options.page_num_start.or(doc_xml.page_num_start);
+        doc_xml.final_page_num_format = options.page_num_format;
+        doc_xml.suppress_header_footer |= options.suppress_header_footer;
     }
 
     // Generate headers and footers
@@ -871,7 +1362,11 @@ pub(crate) fn build_document(
             headers.push(HeaderFooterEntry {
                 number: 1,
                 xml_bytes: xml,
-                media_files: Vec::new(),
+                media_files: image_media([
+                    &config.header.left,
+                    &config.header.center,
+                    &config.header.right,
+                ]),
             });
             // Relationship ID will be set in lib.rs
 
@@ -911,7 +1406,11 @@ pub(crate) fn build_document(
             footers.push(HeaderFooterEntry {
                 number: 1,
                 xml_bytes: xml,
-                media_files: Vec::new(),
+                media_files: image_media([
+                    &config.footer.left,
+                    &config.footer.center,
+                    &config.footer.right,
+                ]),
             });
             // Relationship ID will be set in lib.rs
 
@@ -943,6 +1442,25 @@ pub(crate) fn build_document(
         }
     }
 
+    // Watermark goes into the main and first-page headers; the suppression
+    // header (ID 3) used by cover/TOC sections stays empty.
+    if let Some(text) = config.watermark.as_deref().filter(|t| !t.trim().is_empty()) {
+        if !headers.iter().any(|h| h.number == 1) {
+            let header_xml = HeaderXml::new(HeaderConfig::empty(), "");
+            let xml = header_xml.to_xml().map_err(|e| {
+                crate::error::Error::Xml(format!("Failed to generate watermark header: {}", e))
+            })?;
+            headers.push(HeaderFooterEntry {
+                number: 1,
+                xml_bytes: xml,
+                media_files: Vec::new(),
+            });
+        }
+        for entry in headers.iter_mut().filter(|h| h.number <= 2) {
+            entry.xml_bytes = crate::docx::ooxml::add_watermark(&entry.xml_bytes, text);
+        }
+    }
+
     // Set header/footer refs on document
     doc_xml.header_footer_refs = header_footer_refs;
 
@@ -959,84 +1477,339 @@ pub(crate) fn build_document(
     })
 }
 
-/// Parameters for creating a BuildContext
-pub(crate) struct BuildContextParams<'a> {
-    pub image_ctx: &'a mut ImageContext,
-    pub hyperlink_ctx: &'a mut HyperlinkContext,
-    pub numbering_ctx: &'a mut NumberingContext,
+/// Settings every block of the body is built with
+pub(crate) struct BodyEnv<'a> {
     pub doc: &'a ParsedDocument,
-
-    pub footnotes: &'a mut FootnotesXml,
-    pub toc_builder: &'a mut TocBuilder,
-    pub bookmark_id_counter: &'a mut u32,
-    pub xref_ctx: &'a mut CrossRefContext,
-    pub rel_manager: &'a mut RelIdManager,
+    pub lang: Language,
+    pub config: &'a DocumentConfig,
     pub table_template: Option<&'a TableTemplate>,
     pub image_template: Option<&'a crate::template::extract::image::ImageTemplate>,
-    pub table_count: &'a mut u32,
-    pub figure_count: &'a mut u32,
-    pub lang: Language,
-    pub font_override: Option<String>,
-    pub code_font: Option<String>,
-    pub code_size: Option<u32>,
-    pub quote_level: usize,
-    pub mermaid_spacing: (u32, u32),
-    pub mermaid_output_format: String,
-    pub mermaid_dpi: u32,
+    pub strings: Strings,
+    /// Math renderer with "image" and "auto" resolved
     pub math_renderer: String,
-    pub math_font_size: String,
-    pub math_number_all: bool,
     pub body_width_twips: u32,
+    /// Section type of every `---` break
+    pub chapter_break: &'static str,
+    /// End of the cover section; headings before it stay out of the TOC
+    pub first_thematic_break_index: Option<usize>,
 }
 
-/// Context for building a document, holding all tracked state
-pub(crate) struct BuildContext<'a> {
-    pub image_ctx: &'a mut ImageContext,
-    pub hyperlink_ctx: &'a mut HyperlinkContext,
-    pub numbering_ctx: &'a mut NumberingContext,
-    pub doc: &'a ParsedDocument,
-
-    pub footnotes: &'a mut FootnotesXml,
-    pub toc_builder: &'a mut TocBuilder,
-    pub bookmark_id_counter: &'a mut u32,
-    pub xref_ctx: &'a mut CrossRefContext,
-    pub rel_manager: &'a mut RelIdManager,
-    pub table_template: Option<&'a TableTemplate>,
-    pub image_template: Option<&'a crate::template::extract::image::ImageTemplate>,
-    pub table_count: &'a mut u32,
-    pub figure_count: &'a mut u32,
-    pub lang: Language,
-    pub font_override: Option<String>,
-    pub code_font: Option<String>,
-    pub code_size: Option<u32>,
-    pub quote_level: usize,
-    pub mermaid_spacing: (u32, u32),
-    pub mermaid_output_format: String,
-    pub mermaid_dpi: u32,
-    pub math_renderer: String,
-    pub math_font_size: String,
-    pub math_number_all: bool,
-    pub body_width_twips: u32,
+/// State the body build carries from one block to the next
+pub(crate) struct BodyState {
+    pub image_ctx: ImageContext,
+    pub hyperlink_ctx: HyperlinkContext,
+    pub numbering_ctx: NumberingContext,
+    pub footnotes: FootnotesXml,
+    /// TOC builder for collecting headings
+    pub toc_builder: TocBuilder,
+    /// Cross-reference context for tracking anchors
+    pub xref_ctx: CrossRefContext,
+    pub rel_manager: RelIdManager,
+    pub table_count: u32,
+    pub figure_count: u32,
+    /// Whether the previous block was a heading, to insert blank lines before headings
+    pub prev_is_heading: Option<bool>,
+    /// Whether the next element starts a page, so an H1 there needs no page break
+    pub at_page_start: bool,
+    /// Whether the next paragraph gets the space of a left-out spacer paragraph
+    pub space_next: bool,
+    /// The last list seen, to resume lists across code blocks: (num_id, is_ordered, block_index)
+    pub last_list_info: Option<(u32, bool, usize)>,
+    /// Inside a `<!-- {toc:off} -->` region
+    pub toc_off: bool,
+    /// Options for the section being built, applied at its closing section break
+    pub section: Option<SectionOptions>,
+    /// Author and date from `<!-- {edited:...} -->`, written after the next heading
+    pub edited: Option<(String, String)>,
 }
 
-impl<'a> BuildContext<'a> {
-    pub fn new(params: BuildContextParams<'a>) -> Self {
+impl BodyState {
+    pub fn new(
+        image_ctx: ImageContext,
+        footnotes: FootnotesXml,
+        rel_manager: RelIdManager,
+    ) -> Self {
         Self {
-            image_ctx: params.image_ctx,
-            hyperlink_ctx: params.hyperlink_ctx,
-            numbering_ctx: params.numbering_ctx,
-            doc: params.doc,
-            footnotes: params.footnotes,
-            toc_builder: params.toc_builder,
-            bookmark_id_counter: params.bookmark_id_counter,
-            xref_ctx: params.xref_ctx,
-            rel_manager: params.rel_manager,
-            table_template: params.table_template,
-            image_template: params.image_template,
-            table_count: params.table_count,
-            figure_count: params.figure_count,
-            lang: params.lang,
-            font_override: params.font_override,
+            image_ctx,
+            hyperlink_ctx: HyperlinkContext::new(),
+            numbering_ctx: NumberingContext::new(),
+            footnotes,
+            toc_builder: TocBuilder::new(),
+            xref_ctx: CrossRefContext::new(),
+            rel_manager,
+            table_count: 0,
+            figure_count: 0,
+            prev_is_heading: None,
+            at_page_start: true,
+            space_next: false,
+            last_list_info: None,
+            toc_off: false,
+            section: None,
+            edited: None,
+        }
+    }
+
+    /// Build top-level block `i` of the document, appending its elements to `elements`
+    pub fn build_block(
+        &mut self,
+        env: &BodyEnv,
+        i: usize,
+        block: &Block,
+        elements: &mut Vec<DocElement>,
+    ) {
+        // Create build context
+        let mut ctx = BuildContext::new(BuildContextParams {
+            image_ctx: &mut self.image_ctx,
+            hyperlink_ctx: &mut self.hyperlink_ctx,
+            numbering_ctx: &mut self.numbering_ctx,
+            doc: env.doc,
+            footnotes: &mut self.footnotes,
+            toc_builder: &mut self.toc_builder,
+            xref_ctx: &mut self.xref_ctx,
+            rel_manager: &mut self.rel_manager,
+            table_template: env.table_template,
+            image_template: env.image_template,
+            table_count: &mut self.table_count,
+            figure_count: &mut self.figure_count,
+            lang: env.lang,
+            strings: &env.strings,
+            punctuation: env.config.punctuation.as_ref(),
+            font_override: None,
+            code_font: env.config.fonts.as_ref().and_then(|f| f.code.clone()),
+            code_size: env.config.fonts.as_ref().and_then(|f| f.code_size),
+            quote_level: 0,
+            mermaid_spacing: env.config.mermaid_spacing,
+            mermaid_output_format: env.config.mermaid_output_format.clone(),
+            mermaid_dpi: env.config.mermaid_dpi,
+            math_renderer: env.math_renderer.clone(),
+            math_font_size: env.config.math_font_size.clone(),
+            math_number_all: env.config.math_number_all,
+            body_width_twips: env.body_width_twips,
+            style_spacing: &env.config.style_spacing,
+            repeat_table_header: env.config.repeat_table_header,
+            table_rows_cant_split: env.config.table_rows_cant_split,
+            table_continuation_rows: env.config.table_continuation_rows,
+            code_continuation_lines: env.config.code_continuation_lines,
+            spacers: env.config.spacers,
+        });
+
+        // Insert blank paragraph before heading if previous block was not a heading
+        if is_heading(block) {
+            if self.prev_is_heading == Some(false) {
+                match env.config.spacers {
+                    Spacers::Blank => elements.push(DocElement::Paragraph(Box::default())),
+                    Spacers::Spacing => self.space_next = true,
+                    Spacers::None => {}
+                }
+            }
+            // Heading breaks any list continuation
+            self.last_list_info = None;
+        }
+
+        // Determine if we should force continuation of the previous list
+        let mut forced_num_id = None;
+        if let Block::List { ordered, .. } = block {
+            if let Some((last_id, last_ordered, last_idx)) = self.last_list_info {
+                // If it's the same type of list and we only skipped 1 block (e.g. a code block)
+                // Resume the list numbering.
+                // i - last_idx == 1 means adjacent (normal case, usually handled by parser merging)
+                // i - last_idx == 2 means one block in between (e.g. List -> Code -> List)
+                if *ordered == last_ordered && (i - last_idx) <= 2 {
+                    forced_num_id = Some(last_id);
+                }
+            }
+        } else if !matches!(
+            block,
+            Block::CodeBlock { .. } | Block::BlockQuote(_) | Block::Table { .. }
+        ) {
+            // If the block is NOT a list, and NOT something that might be inside a list (like code/quote/table)
+            // Then it definitely breaks the list.
+            // Text paragraphs usually break lists unless indented, but here we assume top-level paragraphs break lists.
+            self.last_list_info = None;
+        }
+
+        if let Block::Html(html) = block {
+            if let Some(cap) = TOC_DIRECTIVE.captures(html.trim()) {
+                self.toc_off = &cap[1] == "off";
+            } else if let Some(cap) = SECTION_DIRECTIVE.captures(html.trim()) {
+                self.section = Some(SectionOptions::parse(&cap[1]));
+            } else if let Some(cap) = EDITED_DIRECTIVE.captures(html.trim()) {
+                self.edited = Some((cap[1].trim().to_string(), cap[2].trim().to_string()));
+            }
+        }
+
+        // Skip TOC for blocks before first thematic break (cover section)
+        // and inside <!-- {toc:off} --> regions
+        let skip_toc = self.toc_off || env.first_thematic_break_index.is_some_and(|idx| i < idx);
+
+        let built = block_to_elements(block, 0, &mut ctx, forced_num_id, skip_toc);
+
+        // If this block was a list, update tracking info
+        if let Block::List { ordered, .. } = block {
+            // Find the num_id used. If we forced it, we know it.
+            // If we generated it, we need to extract it from the generated paragraphs.
+            // But block_to_elements doesn't return the ID.
+            // However, if forced_num_id is None, block_to_elements called add_list,
+            // so it's the last added list.
+            let used_id = forced_num_id.unwrap_or_else(|| {
+                // If we didn't force it, it was just added.
+                // numIds come from the shared RelIdManager
+                // access internal state? NumberingContext exposes lists vec.
+                self.numbering_ctx
+                    .lists
+                    .last()
+                    .map(|l| l.num_id)
+                    .unwrap_or(0)
+            });
+
+            self.last_list_info = Some((used_id, *ordered, i));
+        }
+
+        for mut elem in built {
+            if let DocElement::Paragraph(p) = &mut elem {
+                if p.is_section_break() && matches!(block, Block::ThematicBreak) {
+                    p.section_break = Some(env.chapter_break.to_string());
+                }
+                match p.style_id.as_deref() {
+                    Some("Heading1") if env.config.h1_page_break => {
+                        p.keep_with_next = true;
+                        p.page_break_before = !self.at_page_start;
+                    }
+                    Some("Heading2") if env.config.h2_keep_with_next => p.keep_with_next = true,
+                    _ => {}
+                }
+                if std::mem::take(&mut self.space_next) {
+                    p.spacing_before = Some(p.spacing_before.unwrap_or(0).max(SPACER_TWIPS));
+                }
+            }
+            if matches!(elem, DocElement::Table(_)) {
+                self.space_next = env.config.spacers == Spacers::Spacing;
+            }
+            if let (Some(options), DocElement::Paragraph(p)) = (&self.section, &mut elem) {
+                if p.is_section_break() {
+                    p.page_num_start = options.page_num_start.or(p.page_num_start);
+                    if options.page_num_format.is_some() {
+                        p.page_num_format = options.page_num_format.clone();
+                    }
+                    if options.section_break.is_some() {
+                        p.section_break = options.section_break.clone();
+                    }
+                    p.suppress_header_footer |= options.suppress_header_footer;
+                    self.section = None;
+                }
+            }
+            self.at_page_start = matches!(&elem, DocElement::Paragraph(p)
+                if p.section_break.as_deref().is_some_and(|b| b != "continuous"));
+            elements.push(elem);
+        }
+
+        if is_heading(block) {
+            if let Some((author, date)) = self.edited.take() {
+                for (style, text) in [("ChapterAuthor", author), ("ChapterDate", date)] {
+                    elements.push(DocElement::Paragraph(Box::new(
+                        Paragraph::with_style(style).add_run(Run::new(text)),
+                    )));
+                }
+            }
+        }
+
+        self.prev_is_heading = Some(is_heading(block));
+    }
+}
+
+/// Parameters for creating a BuildContext
+pub(crate) struct BuildContextParams<'a> {
+    pub image_ctx: &'a mut ImageContext,
+    pub hyperlink_ctx: &'a mut HyperlinkContext,
+    pub numbering_ctx: &'a mut NumberingContext,
+    pub doc: &'a ParsedDocument,
+
+    pub footnotes: &'a mut FootnotesXml,
+    pub toc_builder: &'a mut TocBuilder,
+    pub xref_ctx: &'a mut CrossRefContext,
+    pub rel_manager: &'a mut RelIdManager,
+    pub table_template: Option<&'a TableTemplate>,
+    pub image_template: Option<&'a crate::template::extract::image::ImageTemplate>,
+    pub table_count: &'a mut u32,
+    pub figure_count: &'a mut u32,
+    pub lang: Language,
+    pub strings: &'a Strings,
+    pub punctuation: Option<&'a Punctuation>,
+    pub font_override: Option<Arc<str>>,
+    pub code_font: Option<String>,
+    pub code_size: Option<u32>,
+    pub quote_level: usize,
+    pub mermaid_spacing: (u32, u32),
+    pub mermaid_output_format: String,
+    pub mermaid_dpi: u32,
+    pub math_renderer: String,
+    pub math_font_size: String,
+    pub math_number_all: bool,
+    pub body_width_twips: u32,
+    pub style_spacing: &'a HashMap<String, StyleSpacing>,
+    pub repeat_table_header: bool,
+    pub table_rows_cant_split: bool,
+    pub table_continuation_rows: Option<usize>,
+    pub code_continuation_lines: Option<usize>,
+    pub spacers: Spacers,
+}
+
+/// Context for building a document, holding all tracked state
+pub(crate) struct BuildContext<'a> {
+    pub image_ctx: &'a mut ImageContext,
+    pub hyperlink_ctx: &'a mut HyperlinkContext,
+    pub numbering_ctx: &'a mut NumberingContext,
+    pub doc: &'a ParsedDocument,
+
+    pub footnotes: &'a mut FootnotesXml,
+    pub toc_builder: &'a mut TocBuilder,
+    pub xref_ctx: &'a mut CrossRefContext,
+    pub rel_manager: &'a mut RelIdManager,
+    pub table_template: Option<&'a TableTemplate>,
+    pub image_template: Option<&'a crate::template::extract::image::ImageTemplate>,
+    pub table_count: &'a mut u32,
+    pub figure_count: &'a mut u32,
+    pub lang: Language,
+    pub strings: &'a Strings,
+    pub punctuation: Option<&'a Punctuation>,
+    pub font_override: Option<Arc<str>>,
+    pub code_font: Option<String>,
+    pub code_size: Option<u32>,
+    pub quote_level: usize,
+    pub mermaid_spacing: (u32, u32),
+    pub mermaid_output_format: String,
+    pub mermaid_dpi: u32,
+    pub math_renderer: String,
+    pub math_font_size: String,
+    pub math_number_all: bool,
+    pub body_width_twips: u32,
+    pub style_spacing: &'a HashMap<String, StyleSpacing>,
+    pub repeat_table_header: bool,
+    pub table_rows_cant_split: bool,
+    pub table_continuation_rows: Option<usize>,
+    pub code_continuation_lines: Option<usize>,
+    pub spacers: Spacers,
+}
+
+impl<'a> BuildContext<'a> {
+    pub fn new(params: BuildContextParams<'a>) -> Self {
+        Self {
+            image_ctx: params.image_ctx,
+            hyperlink_ctx: params.hyperlink_ctx,
+            numbering_ctx: params.numbering_ctx,
+            doc: params.doc,
+            footnotes: params.footnotes,
+            toc_builder: params.toc_builder,
+            xref_ctx: params.xref_ctx,
+            rel_manager: params.rel_manager,
+            table_template: params.table_template,
+            image_template: params.image_template,
+            table_count: params.table_count,
+            figure_count: params.figure_count,
+            lang: params.lang,
+            strings: params.strings,
+            punctuation: params.punctuation,
+            font_override: params.font_override,
             code_font: params.code_font,
             code_size: params.code_size,
             quote_level: params.quote_level,
@@ -1047,8 +1820,66 @@ impl<'a> BuildContext<'a> {
             math_font_size: params.math_font_size,
             math_number_all: params.math_number_all,
             body_width_twips: params.body_width_twips,
+            style_spacing: params.style_spacing,
+            repeat_table_header: params.repeat_table_header,
+            table_rows_cant_split: params.table_rows_cant_split,
+            table_continuation_rows: params.table_continuation_rows,
+            code_continuation_lines: params.code_continuation_lines,
+            spacers: params.spacers,
+        }
+    }
+
+    /// `[styles]` spacing configured for a paragraph style
+    fn style_spacing(&self, style: &str) -> StyleSpacing {
+        self.style_spacing.get(style).copied().unwrap_or_default()
+    }
+
+    /// Allocate the `w:id` of a new bookmark named `name`
+    fn next_bookmark_id(&mut self, name: &str) -> u32 {
+        self.rel_manager.next_bookmark_id(name)
+    }
+
+    /// Generated string in the active language
+    ///
+    /// Inside a language region the region's built-in strings are used;
+    /// `[i18n.strings]` overrides apply to the document language only.
+    pub fn label(&self, label: Label) -> &'a str {
+        if self.lang == self.strings.lang {
+            self.strings.get(label)
+        } else {
+            default_label(self.lang, label)
         }
     }
+
+    /// Switch to a language region, returning the state to restore
+    ///
+    /// Runs in the region take its language tag, and its default font unless
+    /// a font group is active. Unknown languages are warned about and ignored.
+    fn enter_lang_region(&mut self, lang: &str) -> (Language, Option<Arc<str>>) {
+        let saved = (self.lang, self.font_override.clone());
+        match Language::from_code(lang) {
+            Some(region) => {
+                if self.font_override.is_none() && region != self.strings.lang {
+                    let font = if region.is_complex_script() {
+                        region.default_cs_font()
+                    } else {
+                        region.default_ascii_font()
+                    };
+                    self.font_override = Some(font.into());
+                }
+                self.lang = region;
+            }
+            None => crate::diagnostics::warn(
+                WarningCategory::Other,
+                format!("Unknown language '{}' in lang region", lang),
+            ),
+        }
+        saved
+    }
+
+    fn leave_lang_region(&mut self, saved: (Language, Option<Arc<str>>)) {
+        (self.lang, self.font_override) = saved;
+    }
 }
 
 /// Convert a Block to one or more DocElements (Paragraph, Table, or Image)
@@ -1079,6 +1910,7 @@ fn block_to_elements(
             src,
             width,
             id,
+            caption,
             ..
         } => {
             // Register figure anchor if id is present
@@ -1167,12 +1999,19 @@ fn block_to_elements(
             // Build result elements
             let mut elements = vec![DocElement::Image(img)];
 
-            // Add caption paragraph if template and alt text exist
+            // Caption inlines, or the alt text when no caption is given
+            let caption_inlines = match caption {
+                Some(inlines) => inlines.clone(),
+                None if alt.is_empty() => Vec::new(),
+                None => vec![Inline::Text(alt.clone())],
+            };
+
+            // Add caption paragraph if template and caption exist
             if let Some(tmpl) = ctx.image_template {
-                if !alt.is_empty() {
+                if !caption_inlines.is_empty() {
                     // Use localized prefix if template has default "Figure"
                     let prefix = if tmpl.caption.prefix == "Figure" {
-                        ctx.lang.figure_caption_prefix().to_string()
+                        ctx.label(Label::Figure).to_string()
                     } else {
                         tmpl.caption.prefix.clone()
                     };
@@ -1182,48 +2021,50 @@ fn block_to_elements(
                         ctx.figure_count.to_string()
                     });
 
-                    let caption_text = format!("{} {}: {}", prefix, number_str, alt);
-
-                    let mut run = Run::new(&caption_text);
-                    run.font = Some(ctx.font_override.as_ref().unwrap_or(&tmpl.caption.font_family).clone());
-                    run.size = Some(tmpl.caption.font_size);
-                    run.color = Some(tmpl.caption.font_color.trim_start_matches('#').to_string());
-                    run.bold = tmpl.caption.bold;
-                    run.italic = tmpl.caption.italic;
+                    let label = format!("{} {}: ", prefix, number_str);
+                    let children = figure_caption_children(&label, &caption_inlines, ctx);
 
                     let mut caption_para = Paragraph::with_style("Caption")
-                        .add_run(run)
                         .spacing(tmpl.caption.spacing_before, tmpl.caption.spacing_after);
+                    caption_para.children = children;
+                    for child in &mut caption_para.children {
+                        if let ParagraphChild::Run(run) = child {
+                            run.font = Some(ctx.font_override.clone().unwrap_or_else(|| tmpl.caption.font_family.as_str().into()));
+                            run.size = Some(tmpl.caption.font_size);
+                            run.color = Some(tmpl.caption.font_color.trim_start_matches('#').to_string());
+                            // Keep bold and italic from the caption's markdown
+                            run.bold |= tmpl.caption.bold;
+                            run.italic |= tmpl.caption.italic;
+                        }
+                    }
 
                     // Align caption to match image alignment
                     caption_para = caption_para.align(&tmpl.alignment);
 
                     // Add bookmark if we have an ID
-                    if let Some(anchor) =
-                        id.as_ref().and_then(|fig_id| ctx.xref_ctx.resolve(fig_id))
+                    if let Some(name) = id
+                        .as_ref()
+                        .and_then(|fig_id| ctx.xref_ctx.resolve(fig_id))
+                        .map(|anchor| anchor.bookmark_name.clone())
                     {
-                        *ctx.bookmark_id_counter += 1;
-                        caption_para = caption_para
-                            .with_bookmark(*ctx.bookmark_id_counter, &anchor.bookmark_name);
+                        let bookmark_id = ctx.next_bookmark_id(&name);
+                        caption_para = caption_para.with_bookmark(bookmark_id, &name);
                     }
 
                     elements.push(DocElement::Paragraph(Box::new(caption_para)));
                 }
-            } else if !alt.is_empty() {
-                // No template — create a simple caption with alt text
-                let prefix = ctx.lang.figure_caption_prefix();
+            } else if !caption_inlines.is_empty() {
+                // No template — create a simple caption
+                let prefix = ctx.label(Label::Figure);
                 let number_str = figure_number.unwrap_or_else(|| {
                     *ctx.figure_count += 1;
                     ctx.figure_count.to_string()
                 });
-                let caption_text = format!("{} {}: {}", prefix, number_str, alt);
-                let mut run = Run::new(&caption_text);
-                if let Some(ref font) = ctx.font_override {
-                    run.font = Some(font.clone());
-                }
-                let caption_para = Paragraph::with_style("Caption")
-                    .add_run(run)
-                    .spacing(120, 120);
+                let label = format!("{} {}: ", prefix, number_str);
+                let mut caption_para = Paragraph::with_style("Caption")
+                    .spacing(120, 120)
+                    .style_spacing(&ctx.style_spacing("Caption"));
+                caption_para.children = figure_caption_children(&label, &caption_inlines, ctx);
                 elements.push(DocElement::Paragraph(Box::new(caption_para)));
             }
 
@@ -1241,7 +2082,10 @@ fn block_to_elements(
                 crate::mermaid::render_to_png(content, scale)
                     .map(|data| (data, true))
                     .or_else(|_png_err| {
-                        eprintln!("Warning: PNG rendering failed, falling back to SVG");
+                        crate::diagnostics::warn(
+                            WarningCategory::Diagram,
+                            "PNG rendering failed, falling back to SVG",
+                        );
                         crate::mermaid::render_to_svg(content)
                             .map(|svg| (svg.into_bytes(), false))
                     })
@@ -1319,7 +2163,7 @@ fn block_to_elements(
 
                             // Use localized prefix if template has default "Figure"
                             let prefix = if tmpl.caption.prefix == "Figure" {
-                                ctx.lang.figure_caption_prefix().to_string()
+                                ctx.label(Label::Figure).to_string()
                             } else {
                                 tmpl.caption.prefix.clone()
                             };
@@ -1332,7 +2176,7 @@ fn block_to_elements(
                             let caption_text = format!("{} {}", prefix, number_str);
 
                             let mut run = Run::new(&caption_text);
-                            run.font = Some(ctx.font_override.as_ref().unwrap_or(&tmpl.caption.font_family).clone());
+                            run.font = Some(ctx.font_override.clone().unwrap_or_else(|| tmpl.caption.font_family.as_str().into()));
                             run.size = Some(tmpl.caption.font_size);
                             run.color =
                                 Some(tmpl.caption.font_color.trim_start_matches('#').to_string());
@@ -1347,12 +2191,13 @@ fn block_to_elements(
                             caption_para = caption_para.align(&tmpl.alignment);
 
                             // Add bookmark if we have an ID
-                            if let Some(anchor) =
-                                id.as_ref().and_then(|fig_id| ctx.xref_ctx.resolve(fig_id))
+                            if let Some(name) = id
+                                .as_ref()
+                                .and_then(|fig_id| ctx.xref_ctx.resolve(fig_id))
+                                .map(|anchor| anchor.bookmark_name.clone())
                             {
-                                *ctx.bookmark_id_counter += 1;
-                                caption_para = caption_para
-                                    .with_bookmark(*ctx.bookmark_id_counter, &anchor.bookmark_name);
+                                let bookmark_id = ctx.next_bookmark_id(&name);
+                                caption_para = caption_para.with_bookmark(bookmark_id, &name);
                             }
 
                             elements.push(DocElement::Paragraph(Box::new(caption_para)));
@@ -1362,7 +2207,10 @@ fn block_to_elements(
                     elements
                 }
                 Err(e) => {
-                    eprintln!("Warning: Failed to render mermaid diagram: {}", e);
+                    crate::diagnostics::warn(
+                        WarningCategory::Diagram,
+                        format!("Failed to render mermaid diagram: {}", e),
+                    );
                     // Fallback to code block
                     block_to_paragraphs(block, list_level, ctx, skip_toc)
                         .into_iter()
@@ -1399,11 +2247,30 @@ fn block_to_elements(
                 Some(ctx.table_count.to_string())
             };
 
+            // Split long tables into page-sized parts when configured
+            let parts: Vec<&[Vec<ParserTableCell>]> = match ctx.table_continuation_rows {
+                Some(budget) if budget > 0 && rows.len() > budget => rows.chunks(budget).collect(),
+                _ => vec![rows.as_slice()],
+            };
+            let continued_caption = (parts.len() > 1).then(|| {
+                let prefix = match ctx.table_template {
+                    Some(template) if template.caption.prefix != "Table" => {
+                        template.caption.prefix.clone()
+                    }
+                    _ => ctx.label(Label::Table).to_string(),
+                };
+                let continued = ctx.label(Label::Continued);
+                match &table_number {
+                    Some(number) => format!("{} {} ({})", prefix, number, continued),
+                    None => format!("{} ({})", prefix, continued),
+                }
+            });
+
             // Add caption paragraph if template has caption style
             if let Some(template) = ctx.table_template {
                 // Use localized prefix if template has default "Table"
                 let prefix = if template.caption.prefix == "Table" {
-                    ctx.lang.table_caption_prefix().to_string()
+                    ctx.label(Label::Table).to_string()
                 } else {
                     template.caption.prefix.clone()
                 };
@@ -1420,43 +2287,46 @@ fn block_to_elements(
                     caption.as_deref().unwrap_or_default()
                 );
 
-                let mut run = Run::new(&caption_text);
-                run.font = Some(ctx.font_override.as_ref().unwrap_or(&template.caption.font_family).clone());
-                run.size = Some(template.caption.font_size);
-                run.color = Some(
-                    template
-                        .caption
-                        .font_color
-                        .trim_start_matches('#')
-                        .to_string(),
-                );
-                run.bold = template.caption.bold;
-                run.italic = template.caption.italic;
-
+                let run = table_caption_run(&caption_text, template, ctx);
                 let mut caption_para = Paragraph::with_style("Caption").add_run(run).spacing(
                     template.caption.spacing_before,
                     template.caption.spacing_after,
                 );
 
                 // Add bookmark if we have an ID
-                if let Some(anchor) = id
+                if let Some(name) = id
                     .as_ref()
                     .and_then(|table_id| ctx.xref_ctx.resolve(table_id))
+                    .map(|anchor| anchor.bookmark_name.clone())
                 {
-                    *ctx.bookmark_id_counter += 1;
-                    caption_para =
-                        caption_para.with_bookmark(*ctx.bookmark_id_counter, &anchor.bookmark_name);
+                    let bookmark_id = ctx.next_bookmark_id(&name);
+                    caption_para = caption_para.with_bookmark(bookmark_id, &name);
                 }
 
                 elements.push(DocElement::Paragraph(Box::new(caption_para)));
             }
 
-            let table = table_to_docx(headers, alignments, rows, ctx);
-            elements.push(DocElement::Table(table));
+            for (i, part) in parts.into_iter().enumerate() {
+                if let Some(text) = continued_caption.as_deref().filter(|_| i > 0) {
+                    // Each later part starts a page under a "(Continued)" caption
+                    let mut p = Paragraph::with_style("Caption")
+                        .page_break_before()
+                        .keep_with_next();
+                    p = match ctx.table_template {
+                        Some(template) => p.add_run(table_caption_run(text, template, ctx)),
+                        None => p.add_text(text),
+                    };
+                    elements.push(DocElement::Paragraph(Box::new(p)));
+                }
+                let table = table_to_docx(headers, alignments, part, ctx);
+                elements.push(DocElement::Table(table));
+            }
 
             // Add empty paragraph after table for spacing
-            let empty_para = Paragraph::default().spacing(0, 0).line_spacing(240, "auto");
-            elements.push(DocElement::Paragraph(Box::new(empty_para)));
+            if ctx.spacers == Spacers::Blank {
+                let empty_para = Paragraph::default().spacing(0, 0).line_spacing(240, "auto");
+                elements.push(DocElement::Paragraph(Box::new(empty_para)));
+            }
 
             elements
         }
@@ -1493,7 +2363,7 @@ fn block_to_elements(
 
         Block::FontGroup { font, blocks } => {
             let prev_override = ctx.font_override.clone();
-            ctx.font_override = Some(font.clone());
+            ctx.font_override = Some(font.as_str().into());
             let mut result = Vec::new();
             for block in blocks {
                 result.extend(block_to_elements(block, list_level, ctx, None, skip_toc));
@@ -1502,6 +2372,27 @@ fn block_to_elements(
             result
         }
 
+        Block::LangGroup { lang, blocks } => {
+            let saved = ctx.enter_lang_region(lang);
+            let mut result = Vec::new();
+            for block in blocks {
+                result.extend(block_to_elements(block, list_level, ctx, None, skip_toc));
+            }
+            ctx.leave_lang_region(saved);
+            result
+        }
+
+        Block::ParallelText { rows } => {
+            let table = parallel_text_to_table(rows, ctx, skip_toc);
+            vec![DocElement::Table(table)]
+        }
+
+        Block::FigureGroup {
+            figures,
+            caption,
+            id,
+        } => figure_group_to_elements(figures, caption.as_deref(), id.as_deref(), ctx),
+
         Block::Include { resolved, .. } => {
             if let Some(blocks) = resolved {
                 let mut result = Vec::new();
@@ -1520,7 +2411,7 @@ fn block_to_elements(
             items,
         } => {
             // Register this list and get a unique numId, or use forced one
-            let num_id = forced_num_id.unwrap_or_else(|| ctx.numbering_ctx.add_list(*ordered));
+            let num_id = forced_num_id.unwrap_or_else(|| ctx.numbering_ctx.add_list(*ordered, ctx.rel_manager));
 
             list_to_paragraphs_with_num_id(
                 *ordered, *start, items, list_level, num_id, ctx, skip_toc,
@@ -1577,10 +2468,9 @@ fn block_to_elements(
                                 .id(image_id);
                             img.position = math.position;
 
-                            let bookmark = bookmark_name.as_ref().map(|bk_name| {
-                                *ctx.bookmark_id_counter += 1;
-                                (*ctx.bookmark_id_counter, bk_name.clone())
-                            });
+                            let bookmark = bookmark_name
+                                .as_ref()
+                                .map(|bk_name| (ctx.next_bookmark_id(bk_name), bk_name.clone()));
                             let mut para = build_equation_paragraph(center_pos, right_pos, eq_number.as_deref(), bookmark);
                             // Insert inline image before the tab-to-right run (index 1)
                             para.children.insert(1, ParagraphChild::InlineImage(img));
@@ -1588,13 +2478,15 @@ fn block_to_elements(
                             return vec![DocElement::Paragraph(Box::new(para))];
                         }
                         Err(e) => {
-                            eprintln!("Warning: ReX rendering failed, falling back to OMML: {}", e);
+                            crate::diagnostics::warn(
+                                WarningCategory::Math,
+                                format!("ReX rendering failed, falling back to OMML: {}", e),
+                            );
                             let omml = crate::docx::math::latex_to_omml_paragraph(content);
 
-                            let bookmark = bookmark_name.as_ref().map(|bk_name| {
-                                *ctx.bookmark_id_counter += 1;
-                                (*ctx.bookmark_id_counter, bk_name.clone())
-                            });
+                            let bookmark = bookmark_name
+                                .as_ref()
+                                .map(|bk_name| (ctx.next_bookmark_id(bk_name), bk_name.clone()));
                             let mut para = build_equation_paragraph(center_pos, right_pos, eq_number.as_deref(), bookmark);
                             para.children.insert(1, ParagraphChild::OfficeMath(omml));
 
@@ -1604,10 +2496,9 @@ fn block_to_elements(
                 } else {
                     let omml = crate::docx::math::latex_to_omml_paragraph(content);
 
-                    let bookmark = bookmark_name.as_ref().map(|bk_name| {
-                        *ctx.bookmark_id_counter += 1;
-                        (*ctx.bookmark_id_counter, bk_name.clone())
-                    });
+                    let bookmark = bookmark_name
+                        .as_ref()
+                        .map(|bk_name| (ctx.next_bookmark_id(bk_name), bk_name.clone()));
                     let mut para = build_equation_paragraph(center_pos, right_pos, eq_number.as_deref(), bookmark);
                     para.children.insert(1, ParagraphChild::OfficeMath(omml));
 
@@ -1649,7 +2540,7 @@ fn block_to_paragraphs(
             // Register heading with TOC builder (unless in cover section)
             let bookmark_name = if skip_toc {
                 // Generate a bookmark name without adding to TOC
-                format!("_Heading_{}", *ctx.bookmark_id_counter + 1)
+                format!("_Heading_{}", ctx.rel_manager.upcoming_bookmark())
             } else {
                 ctx.toc_builder.add_heading(*level, &text, id.as_deref())
             };
@@ -1660,9 +2551,9 @@ fn block_to_paragraphs(
             }
 
             // Create paragraph with bookmark
-            *ctx.bookmark_id_counter += 1;
+            let bookmark_id = ctx.next_bookmark_id(&bookmark_name);
             let mut para = heading_to_paragraph(*level, content, ctx);
-            para = para.with_bookmark(*ctx.bookmark_id_counter, &bookmark_name);
+            para = para.with_bookmark(bookmark_id, &bookmark_name);
 
             vec![para]
         }
@@ -1677,15 +2568,47 @@ fn block_to_paragraphs(
             filename,
             highlight_lines,
             show_line_numbers,
-        } => code_block_to_paragraphs(
-            content,
-            lang.as_deref(),
-            filename.as_deref(),
-            highlight_lines,
-            *show_line_numbers,
-            ctx.code_font.as_deref(),
-            ctx.code_size,
-        ),
+            callouts,
+        } => {
+            let mut paragraphs = code_block_to_paragraphs(
+                content,
+                lang.as_deref(),
+                filename.as_deref(),
+                highlight_lines,
+                *show_line_numbers,
+                ctx.code_font.as_deref(),
+                ctx.code_size,
+            );
+            let first_line = usize::from(filename.is_some());
+            if matches!(lang.as_deref(), Some("diff" | "patch")) {
+                shade_diff_lines(&mut paragraphs[first_line..], content);
+            }
+            // Callout balloons go at the end of their lines
+            for &(line, number) in callouts {
+                if let Some(p) = paragraphs.get_mut(first_line + line as usize - 1) {
+                    let balloon = format!(" {}", callout_glyph(number));
+                    p.children.push(ParagraphChild::Run(
+                        Run::new(balloon).bold().preserve_space(true),
+                    ));
+                }
+            }
+            let paragraphs = continue_code_block(paragraphs, filename.as_deref(), ctx);
+            space_code_block(paragraphs, ctx)
+        }
+
+        Block::CodeCallouts(items) => items
+            .iter()
+            .map(|(number, text)| {
+                let mut p = Paragraph::with_style("ListParagraph").indent(240);
+                p.children.push(ParagraphChild::Run(
+                    Run::new(format!("{} ", callout_glyph(*number)))
+                        .bold()
+                        .preserve_space(true),
+                ));
+                p.children.extend(inlines_to_children(text, ctx));
+                p
+            })
+            .collect(),
 
         Block::BlockQuote(blocks) => {
             let mut paragraphs = Vec::new();
@@ -1713,7 +2636,7 @@ fn block_to_paragraphs(
             items,
         } => {
             // Register this list and get a unique numId
-            let num_id = ctx.numbering_ctx.add_list(*ordered);
+            let num_id = ctx.numbering_ctx.add_list(*ordered, ctx.rel_manager);
             list_to_paragraphs_with_num_id(
                 *ordered, *start, items, list_level, num_id, ctx, skip_toc,
             )
@@ -1733,7 +2656,7 @@ fn block_to_paragraphs(
 
         Block::FontGroup { font, blocks } => {
             let prev_override = ctx.font_override.clone();
-            ctx.font_override = Some(font.clone());
+            ctx.font_override = Some(font.as_str().into());
             let mut paragraphs = Vec::new();
             for block in blocks {
                 paragraphs.extend(block_to_paragraphs(block, list_level, ctx, skip_toc));
@@ -1742,6 +2665,27 @@ fn block_to_paragraphs(
             paragraphs
         }
 
+        Block::LangGroup { lang, blocks } => {
+            let saved = ctx.enter_lang_region(lang);
+            let mut paragraphs = Vec::new();
+            for block in blocks {
+                paragraphs.extend(block_to_paragraphs(block, list_level, ctx, skip_toc));
+            }
+            ctx.leave_lang_region(saved);
+            paragraphs
+        }
+
+        Block::ParallelText { rows } => {
+            // Without a table, the source and translation follow each other
+            let mut paragraphs = Vec::new();
+            for (source, translation) in rows {
+                for block in source.iter().chain(translation) {
+                    paragraphs.extend(block_to_paragraphs(block, list_level, ctx, skip_toc));
+                }
+            }
+            paragraphs
+        }
+
         Block::Mermaid { content, .. } => {
             // This is a fallback case if block_to_elements falls back to block_to_paragraphs
             code_block_to_paragraphs(content, Some("mermaid"), None, &Vec::new(), false, ctx.code_font.as_deref(), ctx.code_size)
@@ -1770,7 +2714,7 @@ fn block_to_paragraphs(
             vec![]
         }
 
-        Block::Image { .. } => {
+        Block::Image { .. } | Block::FigureGroup { .. } => {
             // Skip images for now - will be handled in Phase 3
             vec![]
         }
@@ -1817,22 +2761,23 @@ fn block_to_paragraphs(
                             .id(image_id);
                         img.position = math.position;
 
-                        let bookmark = bookmark_name.as_ref().map(|bk_name| {
-                            *ctx.bookmark_id_counter += 1;
-                            (*ctx.bookmark_id_counter, bk_name.clone())
-                        });
+                        let bookmark = bookmark_name
+                            .as_ref()
+                            .map(|bk_name| (ctx.next_bookmark_id(bk_name), bk_name.clone()));
                         let mut para = build_equation_paragraph(center_pos, right_pos, eq_number.as_deref(), bookmark);
                         para.children.insert(1, ParagraphChild::InlineImage(img));
 
                         return vec![para];
                     }
                     Err(e) => {
-                        eprintln!("Warning: ReX rendering failed, falling back to OMML: {}", e);
+                        crate::diagnostics::warn(
+                            WarningCategory::Math,
+                            format!("ReX rendering failed, falling back to OMML: {}", e),
+                        );
                         let omml = crate::docx::math::latex_to_omml_paragraph(content);
-                        let bookmark = bookmark_name.as_ref().map(|bk_name| {
-                            *ctx.bookmark_id_counter += 1;
-                            (*ctx.bookmark_id_counter, bk_name.clone())
-                        });
+                        let bookmark = bookmark_name
+                            .as_ref()
+                            .map(|bk_name| (ctx.next_bookmark_id(bk_name), bk_name.clone()));
                         let mut para = build_equation_paragraph(center_pos, right_pos, eq_number.as_deref(), bookmark);
                         para.children.insert(1, ParagraphChild::OfficeMath(omml));
 
@@ -1842,10 +2787,9 @@ fn block_to_paragraphs(
             }
 
             let omml = crate::docx::math::latex_to_omml_paragraph(content);
-            let bookmark = bookmark_name.as_ref().map(|bk_name| {
-                *ctx.bookmark_id_counter += 1;
-                (*ctx.bookmark_id_counter, bk_name.clone())
-            });
+            let bookmark = bookmark_name
+                .as_ref()
+                .map(|bk_name| (ctx.next_bookmark_id(bk_name), bk_name.clone()));
             let mut para = build_equation_paragraph(center_pos, right_pos, eq_number.as_deref(), bookmark);
             para.children.insert(1, ParagraphChild::OfficeMath(omml));
 
@@ -1931,17 +2875,13 @@ fn build_equation_paragraph(
 
 /// Convert a heading block to a paragraph
 fn heading_to_paragraph(level: u8, content: &[Inline], ctx: &mut BuildContext) -> Paragraph {
-    let style_id = match level {
-        1 => "Heading1",
-        2 => "Heading2",
-        3 => "Heading3",
-        _ => "Heading4", // level 4+ all use Heading4
-    };
+    let style_id = heading_style_id(level);
 
     let children = inlines_to_children(content, ctx);
     let mut p = Paragraph::with_style(style_id)
         .spacing(0, 0)
-        .line_spacing(240, "auto");
+        .line_spacing(240, "auto")
+        .style_spacing(&ctx.style_spacing(style_id));
     for child in children {
         p = match child {
             ParagraphChild::Run(r) => p.add_run(r),
@@ -1959,7 +2899,8 @@ fn paragraph_to_paragraph(inlines: &[Inline], ctx: &mut BuildContext) -> Paragra
     let children = inlines_to_children(inlines, ctx);
     let mut p = Paragraph::with_style("BodyText")
         .spacing(0, 0)
-        .line_spacing(240, "auto");
+        .line_spacing(240, "auto")
+        .style_spacing(&ctx.style_spacing("BodyText"));
     for child in children {
         p = match child {
             ParagraphChild::Run(r) => p.add_run(r),
@@ -1972,6 +2913,45 @@ fn paragraph_to_paragraph(inlines: &[Inline], ctx: &mut BuildContext) -> Paragra
     p
 }
 
+/// `Figure N: ` label run followed by the caption's formatted content
+fn figure_caption_children(
+    label: &str,
+    caption: &[Inline],
+    ctx: &mut BuildContext,
+) -> Vec<ParagraphChild> {
+    let mut children = vec![ParagraphChild::Run(Run::new(label))];
+    children.extend(inlines_to_children(caption, ctx));
+    if let Some(ref font) = ctx.font_override {
+        for child in &mut children {
+            if let ParagraphChild::Run(run) = child {
+                run.font = Some(font.clone());
+            }
+        }
+    }
+    children
+}
+
+/// Caption run formatted as the table template's caption
+fn table_caption_run(text: &str, template: &TableTemplate, ctx: &BuildContext) -> Run {
+    let mut run = Run::new(text);
+    run.font = Some(
+        ctx.font_override
+            .clone()
+            .unwrap_or_else(|| template.caption.font_family.as_str().into()),
+    );
+    run.size = Some(template.caption.font_size);
+    run.color = Some(
+        template
+            .caption
+            .font_color
+            .trim_start_matches('#')
+            .to_string(),
+    );
+    run.bold = template.caption.bold;
+    run.italic = template.caption.italic;
+    run
+}
+
 /// Convert parsed markdown table to DOCX Table
 ///
 /// # Arguments
@@ -1988,7 +2968,9 @@ fn table_to_docx(
     rows: &[Vec<ParserTableCell>],
     ctx: &mut BuildContext,
 ) -> Table {
-    let mut table = Table::new().with_header_row(true);
+    let mut table = Table::new()
+        .with_header_row(true)
+        .with_bidi_visual(ctx.lang.is_rtl());
 
     // Apply borders if template available
     if let Some(template) = ctx.table_template {
@@ -2022,8 +3004,12 @@ fn table_to_docx(
     let col_width = 9000 / col_count.max(1) as u32;
     table = table.with_column_widths(vec![col_width; col_count]);
 
-    // Add header row (row index 0)
-    let mut header_row = TableRow::new().header();
+    // Add header row (row index 0), repeated at the top of each page
+    let mut header_row = TableRow::new();
+    if ctx.repeat_table_header {
+        header_row = header_row.header();
+    }
+    header_row.cant_split = ctx.table_rows_cant_split;
     for (i, cell) in headers.iter().enumerate() {
         let alignment = alignments.get(i).copied().unwrap_or(ParserAlignment::None);
         let cell_elem = create_table_cell_with_template(
@@ -2046,6 +3032,7 @@ fn table_to_docx(
     for (row_idx, row) in rows.iter().enumerate() {
         let actual_row_idx = row_idx + 1; // +1 because header is row 0
         let mut data_row = TableRow::new();
+        data_row.cant_split = ctx.table_rows_cant_split;
         for (col_idx, cell) in row.iter().enumerate() {
             let alignment = alignments
                 .get(col_idx)
@@ -2071,9 +3058,185 @@ fn table_to_docx(
     table
 }
 
-/// Parameters for creating a table cell with template styling
-pub struct TableCellParams<'a, 'b> {
-    pub content: &'a [Inline],
+/// Build the two-column source/translation table for bilingual documents
+///
+/// Columns split the body width evenly and keep the blocks' own paragraph
+/// styles; only borders and cell margins come from the table template.
+fn parallel_text_to_table(
+    rows: &[(Vec<Block>, Vec<Block>)],
+    ctx: &mut BuildContext,
+    skip_toc: bool,
+) -> Table {
+    let col_width = ctx.body_width_twips / 2;
+    let mut table = Table::new()
+        .width(TableWidth::Pct(5000))
+        .with_column_widths(vec![col_width; 2])
+        .with_bidi_visual(ctx.lang.is_rtl());
+    if let Some(template) = ctx.table_template {
+        table = table.with_borders(template.borders.clone());
+        table = table.with_cell_margins(template.cell_margins.clone());
+    }
+
+    for (source, translation) in rows {
+        let mut row = TableRow::new();
+        for blocks in [source, translation] {
+            let mut cell = TableCellElement::new()
+                .width(TableWidth::Dxa(col_width))
+                .vertical_alignment("top");
+            for block in blocks {
+                for p in block_to_paragraphs(block, 0, ctx, skip_toc) {
+                    cell = cell.add_paragraph(p);
+                }
+            }
+            // Word requires at least one paragraph per cell
+            if cell.paragraphs.is_empty() {
+                cell = cell.add_paragraph(Paragraph::new());
+            }
+            row = row.add_cell(cell);
+        }
+        table = table.add_row(row);
+    }
+    table
+}
+
+/// Alt text, source, id and caption of an image in a figure group
+type SubFigure<'a> = (&'a String, &'a String, Option<&'a str>, Option<&'a [Inline]>);
+
+/// Convert a figure group to a borderless table of sub-figures and its caption
+///
+/// Each image gets a column holding the image and its `(a)`, `(b)`, ...
+/// sub-caption, scaled to fit the column. The shared `Figure N:` caption
+/// follows the table. The group is numbered through the cross-reference
+/// context when it or a sub-figure has an id, so `{ref:...}` to a sub-figure
+/// reads like "Figure 4.2(b)".
+fn figure_group_to_elements(
+    figures: &[Block],
+    caption: Option<&[Inline]>,
+    id: Option<&str>,
+    ctx: &mut BuildContext,
+) -> Vec<DocElement> {
+    let images: Vec<SubFigure> = figures
+        .iter()
+        .filter_map(|figure| match figure {
+            Block::Image {
+                alt,
+                src,
+                id,
+                caption,
+                ..
+            } => Some((alt, src, id.as_deref(), caption.as_deref())),
+            _ => None,
+        })
+        .collect();
+    if images.is_empty() {
+        return vec![];
+    }
+
+    let number = if id.is_some() || images.iter().any(|(_, _, sub_id, _)| sub_id.is_some()) {
+        let caption_text = caption.map(extract_inline_text).unwrap_or_default();
+        let subfigures = crate::docx::xref::subfigure_anchors(figures);
+        ctx.xref_ctx
+            .register_figure_group(id, &caption_text, &subfigures)
+    } else {
+        *ctx.figure_count += 1;
+        ctx.figure_count.to_string()
+    };
+
+    let col_width = ctx.body_width_twips / images.len() as u32;
+    // Leave room for the default cell margins (108 twips a side)
+    let max_width_emu = col_width.saturating_sub(216) as i64 * 635;
+    let mut table = Table::new()
+        .width(TableWidth::Pct(5000))
+        .with_column_widths(vec![col_width; images.len()])
+        .with_borders(crate::template::extract::table::BorderStyles::none())
+        .with_bidi_visual(ctx.lang.is_rtl());
+    let mut row = TableRow::new();
+    // Keep the images and their sub-captions on one page
+    row.cant_split = true;
+
+    for (i, (alt, src, sub_id, sub_caption)) in images.iter().enumerate() {
+        let image_para = Paragraph::new()
+            .spacing(0, 0)
+            .align("center")
+            .keep_with_next();
+        let image_para = match ctx
+            .image_ctx
+            .add_inline_image(src, max_width_emu, ctx.rel_manager)
+        {
+            Some(rel_id) => {
+                let (width_emu, height_emu) = ctx
+                    .image_ctx
+                    .images
+                    .last()
+                    .map(|img| (img.width_emu, img.height_emu))
+                    .unwrap_or_default();
+                let img = ImageElement::new(&rel_id, width_emu, height_emu)
+                    .alt_text(alt)
+                    .name(src)
+                    .id(ctx.rel_manager.next_image_id());
+                image_para.add_inline_image(img)
+            }
+            // Missing image: show its alt text in the cell instead
+            None => image_para.add_text(format!("[{}]", alt)),
+        };
+
+        let label = format!("({}) ", crate::docx::xref::subfigure_letter(i));
+        let text = match sub_caption {
+            Some(inlines) => inlines.to_vec(),
+            None => vec![Inline::Text(alt.to_string())],
+        };
+        let mut caption_para = Paragraph::with_style("Caption")
+            .spacing(60, 60)
+            .align("center");
+        caption_para.children = figure_caption_children(&label, &text, ctx);
+        if let Some(name) = sub_id
+            .and_then(|sub_id| ctx.xref_ctx.resolve(sub_id))
+            .map(|anchor| anchor.bookmark_name.clone())
+        {
+            let bookmark_id = ctx.next_bookmark_id(&name);
+            caption_para = caption_para.with_bookmark(bookmark_id, &name);
+        }
+
+        let cell = TableCellElement::new()
+            .width(TableWidth::Dxa(col_width))
+            .vertical_alignment("bottom")
+            .add_paragraph(image_para)
+            .add_paragraph(caption_para);
+        row = row.add_cell(cell);
+    }
+    table = table.add_row(row);
+
+    // Use the template's caption prefix, as for single images
+    let prefix = match ctx.image_template {
+        Some(tmpl) if tmpl.caption.prefix != "Figure" => tmpl.caption.prefix.clone(),
+        _ => ctx.label(Label::Figure).to_string(),
+    };
+    let (label, text) = match caption {
+        Some(inlines) => (format!("{} {}: ", prefix, number), inlines),
+        None => (format!("{} {}", prefix, number), &[][..]),
+    };
+    let mut caption_para = Paragraph::with_style("Caption")
+        .spacing(120, 120)
+        .style_spacing(&ctx.style_spacing("Caption"))
+        .align("center");
+    caption_para.children = figure_caption_children(&label, text, ctx);
+    if let Some(name) = id
+        .and_then(|id| ctx.xref_ctx.resolve(id))
+        .map(|anchor| anchor.bookmark_name.clone())
+    {
+        let bookmark_id = ctx.next_bookmark_id(&name);
+        caption_para = caption_para.with_bookmark(bookmark_id, &name);
+    }
+
+    vec![
+        DocElement::Table(table),
+        DocElement::Paragraph(Box::new(caption_para)),
+    ]
+}
+
+/// Parameters for creating a table cell with template styling
+pub struct TableCellParams<'a, 'b> {
+    pub content: &'a [Inline],
     pub alignment: ParserAlignment,
     pub is_header: bool,
     pub width: TableWidth,
@@ -2103,12 +3266,16 @@ fn create_table_cell_with_template(
         let row_style = tmpl.row_style_for_index(params.row_index);
         let col_style = tmpl.cell_style_for_column(params.col_index);
 
+        // Font names are shared by every run of the cell
+        let row_font: Arc<str> = row_style.font_family.as_str().into();
+        let col_font: Arc<str> = col_style.font_family.as_str().into();
+
         // Process children with template styling
         for child in children {
             p = match child {
                 ParagraphChild::Run(mut r) => {
                     // Row style provides font family, size, and color
-                    r.font = Some(row_style.font_family.clone());
+                    r.font = Some(row_font.clone());
                     r.size = Some(row_style.font_size);
                     r.color = Some(row_style.font_color.trim_start_matches('#').to_string());
 
@@ -2126,7 +3293,7 @@ fn create_table_cell_with_template(
 
                         // Column style can also override font properties if explicitly set
                         if col_style.font_family != "Calibri" {
-                            r.font = Some(col_style.font_family.clone());
+                            r.font = Some(col_font.clone());
                         }
                         if col_style.font_size != 22 {
                             r.size = Some(col_style.font_size);
@@ -2239,6 +3406,74 @@ fn get_row_shading(
     }
 }
 
+/// Split a long code block into page-sized parts when configured
+///
+/// Each later part starts a new page under a "(Continued)" line in the
+/// `CodeFilename` style, repeating the filename if the block has one.
+fn continue_code_block(
+    mut paragraphs: Vec<Paragraph>,
+    filename: Option<&str>,
+    ctx: &BuildContext,
+) -> Vec<Paragraph> {
+    let start = usize::from(filename.is_some());
+    let budget = match ctx.code_continuation_lines {
+        Some(budget) if budget > 0 && paragraphs.len() - start > budget => budget,
+        _ => return paragraphs,
+    };
+    let continued = ctx.label(Label::Continued);
+    let text = match filename {
+        Some(name) => format!("{} ({})", name, continued),
+        None => format!("({})", continued),
+    };
+
+    let lines = paragraphs.split_off(start);
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 && i % budget == 0 {
+            let mut run = Run::new(&text);
+            if let Some(font) = ctx.code_font.as_deref() {
+                run = run.font(font);
+            }
+            paragraphs.push(
+                Paragraph::with_style("CodeFilename")
+                    .add_run(run)
+                    .page_break_before()
+                    .keep_with_next(),
+            );
+        }
+        paragraphs.push(line);
+    }
+    paragraphs
+}
+
+/// Apply `[styles]` spacing to the paragraphs of a code block
+///
+/// `Code` line spacing applies to every line, but its space before and
+/// after only to the first and last line, so the block stays together.
+fn space_code_block(paragraphs: Vec<Paragraph>, ctx: &BuildContext) -> Vec<Paragraph> {
+    let code = ctx.style_spacing("Code");
+    let first = paragraphs
+        .iter()
+        .position(|p| p.style_id.as_deref() != Some("CodeFilename"))
+        .unwrap_or(0);
+    let last = paragraphs.len().saturating_sub(1);
+    paragraphs
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let spacing = if p.style_id.as_deref() == Some("CodeFilename") {
+                ctx.style_spacing("CodeFilename")
+            } else {
+                StyleSpacing {
+                    before: code.before.filter(|_| i == first),
+                    after: code.after.filter(|_| i == last),
+                    line: code.line,
+                }
+            };
+            p.style_spacing(&spacing)
+        })
+        .collect()
+}
+
 /// Convert a code block to paragraphs (one per line)
 fn code_block_to_paragraphs(
     content: &str,
@@ -2333,6 +3568,103 @@ fn code_block_to_paragraphs(
     paragraphs
 }
 
+/// Shade the lines of a unified diff: added green, removed red, hunks blue
+///
+/// A run of removed lines directly followed by added lines is compared
+/// line by line, and the part of each pair that changed is made bold.
+fn shade_diff_lines(paragraphs: &mut [Paragraph], content: &str) {
+    let lines: Vec<&str> = content.lines().collect();
+    let is_added = |line: &str| line.starts_with('+') && !line.starts_with("+++");
+    let is_removed = |line: &str| line.starts_with('-') && !line.starts_with("---");
+
+    for (p, line) in paragraphs.iter_mut().zip(&lines) {
+        if is_added(line) {
+            p.shading = Some("E6FFEC".to_string());
+        } else if is_removed(line) {
+            p.shading = Some("FFEBE9".to_string());
+        } else if line.starts_with("@@") {
+            p.shading = Some("DDF4FF".to_string());
+        }
+    }
+
+    let mut i = 0;
+    while i < lines.len() {
+        let removed = lines[i..].iter().take_while(|l| is_removed(l)).count();
+        let added = lines[i + removed..]
+            .iter()
+            .take_while(|l| is_added(l))
+            .count();
+        for j in 0..removed.min(added) {
+            let (old, new) = (lines[i + j], lines[i + removed + j]);
+            let old: Vec<char> = old.chars().skip(1).collect();
+            let new: Vec<char> = new.chars().skip(1).collect();
+            let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+            let suffix = old[prefix..]
+                .iter()
+                .rev()
+                .zip(new[prefix..].iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+            // Lines with nothing in common are shaded only
+            if prefix + suffix == 0 {
+                continue;
+            }
+            if let Some(p) = paragraphs.get_mut(i + j) {
+                embolden_code_span(p, 1 + prefix, 1 + old.len() - suffix, 1 + old.len());
+            }
+            if let Some(p) = paragraphs.get_mut(i + removed + j) {
+                embolden_code_span(p, 1 + prefix, 1 + new.len() - suffix, 1 + new.len());
+            }
+        }
+        i += (removed + added).max(1);
+    }
+}
+
+/// Make characters `start..end` of a code line `line_len` characters long
+/// bold, splitting its runs
+///
+/// Any line number run in front of the code shifts the positions.
+fn embolden_code_span(p: &mut Paragraph, start: usize, end: usize, line_len: usize) {
+    if start >= end {
+        return;
+    }
+    let len = |child: &ParagraphChild| match child {
+        ParagraphChild::Run(run) => run.text.chars().count(),
+        _ => 0,
+    };
+    let total: usize = p.children.iter().map(len).sum();
+    let offset = total.saturating_sub(line_len);
+    let (start, end) = (start + offset, end + offset);
+
+    let mut children = Vec::with_capacity(p.children.len() + 2);
+    let mut pos = 0;
+    for child in std::mem::take(&mut p.children) {
+        let n = len(&child);
+        let ParagraphChild::Run(run) = child else {
+            children.push(child);
+            continue;
+        };
+        let from = start.clamp(pos, pos + n) - pos;
+        let to = end.clamp(pos, pos + n) - pos;
+        pos += n;
+        if from == to {
+            children.push(ParagraphChild::Run(run));
+            continue;
+        }
+        let chars: Vec<char> = run.text.chars().collect();
+        for (range, bold) in [(0..from, false), (from..to, true), (to..n, false)] {
+            if range.is_empty() {
+                continue;
+            }
+            let mut part = run.clone();
+            part.text = chars[range].iter().collect();
+            part.bold |= bold;
+            children.push(ParagraphChild::Run(part));
+        }
+    }
+    p.children = children;
+}
+
 /// Convert a list to paragraphs with a specific numId (for unique list instances)
 fn list_to_paragraphs_with_num_id(
     _ordered: bool,
@@ -2394,20 +3726,178 @@ fn thematic_break_to_paragraph() -> Paragraph {
 /// # Returns
 /// A vector of paragraph children (runs or hyperlinks)
 fn inlines_to_children(inlines: &[Inline], ctx: &mut BuildContext) -> Vec<ParagraphChild> {
-    let mut children = Vec::new();
+    let smartened;
+    let inlines = match ctx.punctuation {
+        Some(rules) => {
+            smartened = smarten_inlines(inlines, &mut SmartPunctuation::new(rules));
+            &smartened[..]
+        }
+        None => inlines,
+    };
+
+    let mut children = Vec::with_capacity(inlines.len());
 
     for inline in inlines {
-        children.extend(inline_to_children(inline, false, false, false, ctx));
+        inline_to_children(inline, false, false, false, ctx, &mut children);
     }
 
+    let mut children = split_runs_by_script(children);
+    if ctx.lang != ctx.strings.lang {
+        apply_region_lang_to_children(&mut children, ctx.lang);
+    }
+    #[cfg(feature = "thai-segmentation")]
+    insert_thai_breaks_in_children(&mut children);
     apply_font_override_to_children(&mut children, &ctx.font_override);
     children
 }
 
+/// Paragraphs shorter than this (in characters) fit on one line, so they
+/// never need Thai break hints
+#[cfg(feature = "thai-segmentation")]
+const THAI_BREAK_MIN_CHARS: usize = 60;
+
+/// Insert zero-width spaces between Thai words in long paragraphs
+///
+/// Gives Word break opportunities inside Thai text so justified lines
+/// don't stretch the few real spaces into huge gaps.
+#[cfg(feature = "thai-segmentation")]
+fn insert_thai_breaks_in_children(children: &mut [ParagraphChild]) {
+    fn text_runs(child: &mut ParagraphChild) -> Vec<&mut Run> {
+        match child {
+            ParagraphChild::Run(run) => vec![run],
+            ParagraphChild::Hyperlink(hyperlink) => hyperlink.children.iter_mut().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    let mut runs: Vec<&mut Run> = children
+        .iter_mut()
+        .flat_map(text_runs)
+        .filter(|run| !run.instr_text && run.field_char.is_none())
+        .collect();
+    let length: usize = runs.iter().map(|run| run.text.chars().count()).sum();
+    if length < THAI_BREAK_MIN_CHARS {
+        return;
+    }
+    for run in runs.iter_mut() {
+        if crate::i18n::contains_thai(&run.text) {
+            run.text = crate::i18n::insert_thai_breaks(&run.text);
+        }
+    }
+}
+
+/// Apply smart punctuation to the text of a paragraph's inlines
+///
+/// Code and math are left as typed but still count as the preceding text,
+/// so a quote after inline code closes rather than opens.
+fn smarten_inlines(inlines: &[Inline], smart: &mut SmartPunctuation) -> Vec<Inline> {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => Inline::Text(smart.convert(text)),
+            Inline::Bold(inner) => Inline::Bold(smarten_inlines(inner, smart)),
+            Inline::Italic(inner) => Inline::Italic(smarten_inlines(inner, smart)),
+            Inline::BoldItalic(inner) => Inline::BoldItalic(smarten_inlines(inner, smart)),
+            Inline::Strikethrough(inner) => Inline::Strikethrough(smarten_inlines(inner, smart)),
+            Inline::Link { text, url, title } => Inline::Link {
+                text: smarten_inlines(text, smart),
+                url: url.clone(),
+                title: title.clone(),
+            },
+            Inline::Code(text) | Inline::InlineMath(text) | Inline::Kbd(text) => {
+                smart.skip(text);
+                inline.clone()
+            }
+            Inline::SoftBreak | Inline::HardBreak => {
+                smart.skip(" ");
+                inline.clone()
+            }
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// Split runs that mix scripts so each run carries its own `w:lang`
+///
+/// Word picks the spell-check language and complex-script font metrics per
+/// run, so a Thai word inside an English sentence needs a run of its own.
+fn split_runs_by_script(children: Vec<ParagraphChild>) -> Vec<ParagraphChild> {
+    let mut split = Vec::with_capacity(children.len());
+    for child in children {
+        match child {
+            ParagraphChild::Run(run) => {
+                split_run_by_script(run, |part| split.push(ParagraphChild::Run(part)));
+            }
+            ParagraphChild::Hyperlink(mut hyperlink) => {
+                let runs = std::mem::take(&mut hyperlink.children);
+                for run in runs {
+                    split_run_by_script(run, |part| hyperlink.children.push(part));
+                }
+                split.push(ParagraphChild::Hyperlink(hyperlink));
+            }
+            other => split.push(other),
+        }
+    }
+    split
+}
+
+/// Tag runs inside a language region with the region's language
+///
+/// Only runs written in the region's script family are retagged, so a Thai
+/// word inside an `en` region keeps `th-TH`.
+fn apply_region_lang_to_children(children: &mut [ParagraphChild], region: Language) {
+    let family = |lang: Language| (lang.is_complex_script(), lang.is_east_asian());
+    let retag = |run: &mut Run| {
+        let detected = run.lang.and_then(Language::from_code).unwrap_or_default();
+        let same_family = if region.is_complex_script() {
+            detected == region
+        } else {
+            family(detected) == family(region)
+        };
+        if run.field_char.is_none() && !run.instr_text && same_family {
+            run.lang = Some(region.lang_tag());
+            run.rtl = region.is_rtl();
+        }
+    };
+    for child in children.iter_mut() {
+        match child {
+            ParagraphChild::Run(run) => retag(run),
+            ParagraphChild::Hyperlink(hyperlink) => hyperlink.children.iter_mut().for_each(retag),
+            _ => {}
+        }
+    }
+}
+
+/// Split a text run at script changes, keeping its formatting
+///
+/// Each part is passed to `emit`; most runs are in one script and are
+/// passed on whole without copying their text.
+fn split_run_by_script(mut run: Run, mut emit: impl FnMut(Run)) {
+    if run.field_char.is_some()
+        || run.instr_text
+        || run.tab
+        || run.footnote_ref
+        || run.footnote_id.is_some()
+        || run.break_type.is_some()
+        || crate::i18n::is_single_script(&run.text)
+    {
+        emit(run);
+        return;
+    }
+    let text = std::mem::take(&mut run.text);
+    for (segment, lang) in crate::i18n::split_by_script(&text) {
+        let mut part = run.clone();
+        part.text = segment.to_string();
+        part.lang = Some(lang);
+        part.rtl = Language::from_code(lang).is_some_and(|l| l.is_rtl());
+        emit(part);
+    }
+}
+
 /// Apply font override to all runs within paragraph children
 fn apply_font_override_to_children(
     children: &mut [ParagraphChild],
-    font_override: &Option<String>,
+    font_override: &Option<Arc<str>>,
 ) {
     if let Some(ref font) = font_override {
         for child in children.iter_mut() {
@@ -2442,66 +3932,68 @@ fn apply_font_override_to_children(
 /// * `strike` - Current strikethrough state (from parent formatting)
 /// * `ctx` - Build context holding tracked state
 ///
-/// # Returns
-/// A vector of paragraph children (runs or hyperlinks)
+/// * `out` - Receives the paragraph children (runs or hyperlinks)
+///
+/// Text, formatting and breaks, which make up nearly every run, are pushed
+/// straight into `out`, so nesting doesn't build a vector per level.
 fn inline_to_children(
     inline: &Inline,
     bold: bool,
     italic: bool,
     strike: bool,
     ctx: &mut BuildContext,
-) -> Vec<ParagraphChild> {
-    match inline {
+    out: &mut Vec<ParagraphChild>,
+) {
+    let children = match inline {
         Inline::Text(text) => {
             let mut run = Run::new(text).preserve_space(true);
-            if bold {
-                run = run.bold();
-            }
-            if italic {
-                run = run.italic();
-            }
-            if strike {
-                run = run.strike();
-            }
-            vec![ParagraphChild::Run(run)]
+            run.bold = bold;
+            run.italic = italic;
+            run.strike = strike;
+            out.push(ParagraphChild::Run(run));
+            return;
         }
 
         Inline::Bold(content) => {
-            let mut children = Vec::new();
             for inner in content {
-                children.extend(inline_to_children(inner, true, italic, strike, ctx));
+                inline_to_children(inner, true, italic, strike, ctx, out);
             }
-            children
+            return;
         }
 
         Inline::Italic(content) => {
-            let mut children = Vec::new();
             for inner in content {
-                children.extend(inline_to_children(inner, bold, true, strike, ctx));
+                inline_to_children(inner, bold, true, strike, ctx, out);
             }
-            children
+            return;
         }
 
         Inline::BoldItalic(content) => {
-            let mut children = Vec::new();
             for inner in content {
-                children.extend(inline_to_children(inner, true, true, strike, ctx));
+                inline_to_children(inner, true, true, strike, ctx, out);
             }
-            children
+            return;
         }
 
         Inline::Code(text) => {
-            vec![ParagraphChild::Run(
+            out.push(ParagraphChild::Run(
                 Run::new(text).style("CodeChar").preserve_space(true),
-            )]
+            ));
+            return;
+        }
+
+        Inline::Kbd(key) => {
+            out.push(ParagraphChild::Run(
+                Run::new(key).style("Kbd").preserve_space(true),
+            ));
+            return;
         }
 
         Inline::Strikethrough(content) => {
-            let mut children = Vec::new();
             for inner in content {
-                children.extend(inline_to_children(inner, bold, italic, true, ctx));
+                inline_to_children(inner, bold, italic, true, ctx, out);
             }
-            children
+            return;
         }
 
         Inline::Link { text, url, .. } => {
@@ -2511,26 +4003,32 @@ fn inline_to_children(
                 if link_text.contains("{PAGENUM}") {
                     let bookmark = &url[1..]; // Strip the '#'
                     // Generate a PAGEREF field: begin + instrText + separate + placeholder + end
-                    let mut children = Vec::new();
-                    children.push(ParagraphChild::Run(
+                    out.push(ParagraphChild::Run(
                         Run::new("").with_field_char("begin"),
                     ));
-                    children.push(ParagraphChild::Run(
+                    out.push(ParagraphChild::Run(
                         Run::new(format!(" PAGEREF {} \\h ", bookmark)).with_instr_text(),
                     ));
-                    children.push(ParagraphChild::Run(
+                    out.push(ParagraphChild::Run(
                         Run::new("").with_field_char("separate"),
                     ));
-                    children.push(ParagraphChild::Run(Run::new("0"))); // Placeholder page number
-                    children.push(ParagraphChild::Run(
+                    out.push(ParagraphChild::Run(Run::new("0"))); // Placeholder page number
+                    out.push(ParagraphChild::Run(
                         Run::new("").with_field_char("end"),
                     ));
-                    return children;
+                    return;
                 }
             }
 
-            let rel_id = ctx.hyperlink_ctx.add_hyperlink(url, ctx.rel_manager);
-            let mut hyperlink = crate::docx::ooxml::Hyperlink::new(rel_id);
+            // `#id` links jump to a bookmark in the document; headings are
+            // bookmarked under their `{#id}`
+            let mut hyperlink = match url.strip_prefix('#').filter(|a| !a.is_empty()) {
+                Some(anchor) => crate::docx::ooxml::Hyperlink::to_anchor(anchor),
+                None => {
+                    let rel_id = ctx.hyperlink_ctx.add_hyperlink(url, ctx.rel_manager);
+                    crate::docx::ooxml::Hyperlink::new(rel_id)
+                }
+            };
 
             // Process nested text
             let children = inlines_to_children(text, ctx);
@@ -2545,38 +4043,54 @@ fn inline_to_children(
             vec![ParagraphChild::Hyperlink(hyperlink)]
         }
 
-        Inline::Image { .. } => {
-            // Skip inline images for now - will be handled in Phase 3
-            vec![]
+        Inline::Image { alt, src, .. } => {
+            let max_width_emu = ctx.body_width_twips as i64 * 635; // 635 EMU per twip
+            let Some(rel_id) = ctx
+                .image_ctx
+                .add_inline_image(src, max_width_emu, ctx.rel_manager)
+            else {
+                // Missing image: keep its alt text in the line as a placeholder
+                let mut run = Run::new(format!("[{}]", alt)).preserve_space(true);
+                run.bold = bold;
+                run.italic = italic;
+                run.strike = strike;
+                out.push(ParagraphChild::Run(run));
+                return;
+            };
+            let (width_emu, height_emu) = ctx
+                .image_ctx
+                .images
+                .last()
+                .map(|img| (img.width_emu, img.height_emu))
+                .unwrap_or_default();
+            let img = ImageElement::new(&rel_id, width_emu, height_emu)
+                .alt_text(alt)
+                .name(src)
+                .id(ctx.rel_manager.next_image_id());
+            vec![ParagraphChild::InlineImage(img)]
         }
 
         Inline::FootnoteRef(label) => {
             // Look up footnote definition
             if let Some(blocks) = ctx.doc.footnotes.get(label) {
                 // Convert blocks to paragraphs
-                // Use a temporary numbering context for footnote content
-                let mut footnote_numbering_ctx = NumberingContext::new();
+                // Lists, bookmarks and drawings share the document's IDs
                 let mut footnote_toc_builder = TocBuilder::new();
-                let mut footnote_bookmark_id: u32 = 0;
                 let mut footnote_xref_ctx = CrossRefContext::new();
-                // Create a temporary RelIdManager for footnote content to avoid affecting global state?
-                // Actually, images in footnotes should use the global manager to be valid in document.xml.rels
-                // BUT footnotes are in footnotes.xml, which has its own relationships file footnotes.xml.rels!
-                // Currently we don't support images in footnotes fully (relationships wise).
-                // For now, let's reuse the global manager but be aware that if we add images here,
-                // they need to be in footnotes.xml.rels, not document.xml.rels.
-                // TODO: Handle footnote relationships properly.
+                // Images and hyperlinks are registered as usual, then their
+                // relationships are moved to footnotes.xml.rels below
+                let first_link = ctx.hyperlink_ctx.hyperlinks.len();
+                let first_image = ctx.image_ctx.images.len();
 
                 let mut content = Vec::new();
                 for block in blocks {
                     let mut nested_ctx = BuildContext {
-                        image_ctx: &mut ImageContext::new(), // Temporary
-                        hyperlink_ctx: ctx.hyperlink_ctx, // Re-use? Hyperlinks in footnotes need relationships too
-                        numbering_ctx: &mut footnote_numbering_ctx,
+                        image_ctx: ctx.image_ctx,
+                        hyperlink_ctx: ctx.hyperlink_ctx,
+                        numbering_ctx: ctx.numbering_ctx,
                         doc: ctx.doc,
                         footnotes: ctx.footnotes,
                         toc_builder: &mut footnote_toc_builder,
-                        bookmark_id_counter: &mut footnote_bookmark_id,
                         xref_ctx: &mut footnote_xref_ctx,
                         rel_manager: ctx.rel_manager,
                         table_template: ctx.table_template,
@@ -2584,6 +4098,8 @@ fn inline_to_children(
                         table_count: &mut 0, // Footnotes don't typically have tables with captions, or they share numbering?
                         figure_count: &mut 0,
                         lang: ctx.lang,
+                        strings: ctx.strings,
+                        punctuation: ctx.punctuation,
                         font_override: ctx.font_override.clone(),
                         code_font: ctx.code_font.clone(),
                         code_size: ctx.code_size,
@@ -2595,6 +4111,12 @@ fn inline_to_children(
                         math_font_size: ctx.math_font_size.clone(),
                         math_number_all: ctx.math_number_all,
                         body_width_twips: ctx.body_width_twips,
+                        style_spacing: ctx.style_spacing,
+                        repeat_table_header: ctx.repeat_table_header,
+                        table_rows_cant_split: ctx.table_rows_cant_split,
+                        table_continuation_rows: ctx.table_continuation_rows,
+                        code_continuation_lines: ctx.code_continuation_lines,
+                        spacers: ctx.spacers,
                     };
                     let paragraphs = block_to_paragraphs(
                         block,
@@ -2605,6 +4127,15 @@ fn inline_to_children(
                     content.extend(paragraphs);
                 }
 
+                let links = ctx.hyperlink_ctx.hyperlinks.drain(first_link..);
+                ctx.footnotes
+                    .hyperlinks
+                    .extend(links.map(|link| (link.rel_id, link.url)));
+                let images = &ctx.image_ctx.images[first_image..];
+                ctx.footnotes
+                    .image_rel_ids
+                    .extend(images.iter().map(|image| image.rel_id.clone()));
+
                 if !content.is_empty() {
                     // Remove empty trailing paragraphs from footnote content
                     while content.len() > 1
@@ -2658,7 +4189,7 @@ fn inline_to_children(
             // Resolve the anchor to get bookmark info
             if let Some(anchor) = ctx.xref_ctx.resolve(target) {
                 let bookmark_name = anchor.bookmark_name.clone();
-                let display_text = ctx.xref_ctx.get_localized_display_text(target, ctx.lang);
+                let display_text = ctx.xref_ctx.get_localized_display_text(target, ctx.strings);
 
                 if *ref_type == RefType::Equation {
                     // Equation cross-refs use a dynamic REF field pointing to the bookmark
@@ -2695,7 +4226,11 @@ fn inline_to_children(
                 }
             } else {
                 // Unresolved reference — show as plain text
-                let display_text = ctx.xref_ctx.get_localized_display_text(target, ctx.lang);
+                crate::diagnostics::warn(
+                    WarningCategory::UnresolvedRef,
+                    format!("Unresolved reference: {}", target),
+                );
+                let display_text = ctx.xref_ctx.get_localized_display_text(target, ctx.strings);
                 let mut run = Run::new(&display_text);
                 run.color = Some("FF0000".to_string()); // Red to indicate missing ref
                 vec![ParagraphChild::Run(run)]
@@ -2706,15 +4241,17 @@ fn inline_to_children(
             // In blockquotes, soft break becomes a line break to preserve
             // the visual line structure. Outside blockquotes, it becomes a space.
             if ctx.quote_level > 0 {
-                vec![ParagraphChild::Run(create_break_run())]
+                out.push(ParagraphChild::Run(create_break_run()));
             } else {
-                vec![ParagraphChild::Run(Run::new(" "))]
+                out.push(ParagraphChild::Run(Run::new(" ")));
             }
+            return;
         }
 
         Inline::HardBreak => {
             // Hard break becomes a line break element
-            vec![ParagraphChild::Run(create_break_run())]
+            out.push(ParagraphChild::Run(create_break_run()));
+            return;
         }
 
         Inline::Html(_) => {
@@ -2751,7 +4288,13 @@ fn inline_to_children(
                         vec![ParagraphChild::InlineImage(img)]
                     }
                     Err(e) => {
-                        eprintln!("Warning: ReX rendering failed for inline math, falling back to OMML: {}", e);
+                        crate::diagnostics::warn(
+                            WarningCategory::Math,
+                            format!(
+                                "ReX rendering failed for inline math, falling back to OMML: {}",
+                                e
+                            ),
+                        );
                         let omml = crate::docx::math::latex_to_omml_inline(latex);
                         vec![ParagraphChild::OfficeMath(omml)]
                     }
@@ -2786,7 +4329,13 @@ fn inline_to_children(
                         vec![ParagraphChild::InlineImage(img)]
                     }
                     Err(e) => {
-                        eprintln!("Warning: ReX rendering failed for display math, falling back to OMML: {}", e);
+                        crate::diagnostics::warn(
+                            WarningCategory::Math,
+                            format!(
+                                "ReX rendering failed for display math, falling back to OMML: {}",
+                                e
+                            ),
+                        );
                         let omml = crate::docx::math::latex_to_omml_paragraph(latex);
                         vec![ParagraphChild::OfficeMath(omml)]
                     }
@@ -2797,7 +4346,8 @@ fn inline_to_children(
                 vec![ParagraphChild::OfficeMath(omml)]
             }
         }
-    }
+    };
+    out.extend(children);
 }
 
 /// Create a run with a line break element
@@ -2815,7 +4365,7 @@ fn estimate_inline_length(inlines: &[Inline]) -> usize {
     inlines
         .iter()
         .map(|i| match i {
-            Inline::Text(s) | Inline::Code(s) => s.chars().count(),
+            Inline::Text(s) | Inline::Code(s) | Inline::Kbd(s) => s.chars().count(),
             Inline::Bold(v) | Inline::Italic(v) | Inline::Strikethrough(v) => {
                 estimate_inline_length(v)
             }
@@ -2844,6 +4394,18 @@ mod tests {
         }
     }
 
+    /// Helper function to get a config whose file map holds stub images at `paths`
+    fn config_with_images(paths: &[&str]) -> DocumentConfig {
+        let mut files = FileMap::new();
+        for path in paths {
+            files.insert(path, b"not a real image".to_vec());
+        }
+        DocumentConfig {
+            files: Some(Arc::new(files)),
+            ..Default::default()
+        }
+    }
+
     /// Helper function to extract paragraphs from document elements
     fn get_paragraphs(doc: &DocumentXml) -> Vec<&Paragraph> {
         doc.elements
@@ -2890,6 +4452,65 @@ mod tests {
         assert!(!footnotes[0].content.is_empty());
     }
 
+    #[test]
+    fn test_mixed_script_runs_split() {
+        let md = "Use **ภาษาไทย and English** here";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &DocumentConfig::default(),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let paragraphs = get_paragraphs(&result.document);
+        let runs: Vec<(&str, Option<&str>, bool)> = paragraphs
+            .iter()
+            .flat_map(|p| p.iter_runs())
+            .map(|r| (r.text.as_str(), r.lang, r.bold))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                ("Use ", Some("en-US"), false),
+                ("ภาษาไทย ", Some("th-TH"), true),
+                ("and English", Some("en-US"), true),
+                (" here", Some("en-US"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_smart_punctuation() {
+        let md = "He said \"**stop**\" -- `\"raw\"`";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let config = DocumentConfig {
+            punctuation: Some(Punctuation::for_language(Language::Japanese)),
+            ..DocumentConfig::default()
+        };
+        let result = build_document(
+            &parsed,
+            Language::Japanese,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let text: String = get_paragraphs(&result.document)
+            .iter()
+            .flat_map(|p| p.iter_runs())
+            .map(|r| r.text.as_str())
+            .collect();
+        assert_eq!(text, "He said 「stop」 – \"raw\"");
+    }
+
     #[test]
     fn test_multiple_footnotes() {
         let md = "Text with two footnotes[^1][^2].\n\n[^1]: First footnote\n[^2]: Second footnote";
@@ -3161,45 +4782,257 @@ mod tests {
     }
 
     #[test]
-    fn test_cross_reference_in_document() {
-        let doc = ParsedDocument {
-            frontmatter: None,
-            blocks: vec![
-                Block::Heading {
-                    level: 1,
-                    content: vec![Inline::Text("Introduction".to_string())],
-                    id: Some("intro".to_string()),
-                },
-                Block::Paragraph(vec![
-                    Inline::Text("See ".to_string()),
-                    Inline::CrossRef {
-                        target: "intro".to_string(),
-                        ref_type: RefType::Chapter,
-                    },
-                    Inline::Text(" for more.".to_string()),
-                ]),
-            ],
-            footnotes: std::collections::HashMap::new(),
-        };
-
-        let config = no_toc_config(); // Disable TOC for this test
+    fn test_footnote_hyperlink_rels() {
+        let md =
+            "See [docs](https://example.com/a)[^1]\n\n[^1]: From [source](https://example.com/b)";
+        let parsed = parse_markdown_with_frontmatter(md);
         let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
         let result = build_document(
-            &doc,
+            &parsed,
             Language::English,
-            &config,
+            &DocumentConfig::default(),
             &mut rel_manager,
             None,
             None,
         )
         .unwrap();
 
-        // Verify the document was built successfully
-        assert!(!result.document.elements.is_empty());
+        // The footnote's link goes to footnotes.xml.rels, not document.xml.rels
+        let urls: Vec<&str> = result
+            .hyperlinks
+            .hyperlinks
+            .iter()
+            .map(|link| link.url.as_str())
+            .collect();
+        assert_eq!(urls, ["https://example.com/a"]);
+        assert_eq!(result.footnotes.hyperlinks.len(), 1);
+        let (rel_id, url) = &result.footnotes.hyperlinks[0];
+        assert_eq!(url, "https://example.com/b");
+
+        let rels = String::from_utf8(result.footnote_rels().to_xml().unwrap()).unwrap();
+        assert!(rels.contains(&format!("Id=\"{}\"", rel_id)));
+        assert!(rels.contains("Target=\"https://example.com/b\" TargetMode=\"External\""));
+        let xml = String::from_utf8(result.footnotes.to_xml().unwrap()).unwrap();
+        assert!(xml.contains(&format!("r:id=\"{}\"", rel_id)));
+    }
 
-        // Check that we have a heading and a paragraph
-        let paragraphs = get_paragraphs(&result.document);
-        assert!(paragraphs.len() >= 2);
+    #[test]
+    fn test_shared_rel_manager_ids() {
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let mut build = |md: &str| {
+            build_document(
+                &parse_markdown_with_frontmatter(md),
+                Language::English,
+                &DocumentConfig::default(),
+                &mut rel_manager,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        // Footnote lists join the document's numbering
+        let main = build("# Intro\n\n1. one\n\nText[^1]\n\n[^1]: Note\n\n    - a\n    - b");
+        let inside = build("# Cover\n\n- x");
+
+        let num_ids: Vec<u32> = main
+            .numbering
+            .lists
+            .iter()
+            .chain(&inside.numbering.lists)
+            .map(|list| list.num_id)
+            .collect();
+        assert_eq!(num_ids, [1, 2, 3]);
+
+        // A second build sharing the manager does not reuse bookmark IDs
+        let main_xml = String::from_utf8(main.document.to_xml().unwrap()).unwrap();
+        let inside_xml = String::from_utf8(inside.document.to_xml().unwrap()).unwrap();
+        let bookmark = |xml: &str| {
+            let start = xml.find("<w:bookmarkStart w:id=\"").unwrap() + 23;
+            xml[start..start + 5].to_string()
+        };
+        assert_ne!(bookmark(&main_xml), bookmark(&inside_xml));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_chapters_match_serial_build() {
+        let md = "\
+# Cover
+
+---
+
+# Start {#ch:start}
+
+Intro with a note[^a] and a [link](https://example.com/a).
+
+Table: Users {#tbl:users}
+| Name | Role |
+|------|------|
+| Ann | Admin |
+
+1. first
+2. second
+
+![Diagram](a.png){#fig:diagram}
+
+<!-- {toc:off} -->
+
+## Hidden from the TOC
+
+<!-- {section:page=1,bogus} -->
+
+---
+
+# Middle {#ch:middle}
+
+<!-- {toc:on} -->
+
+See {ref:tbl:users}, {ref:fig:diagram} and {ref:ch:start}.
+
+- bullet[^b]
+- [again](https://example.com/a)
+
+Table: Users again {#tbl:users}
+| Name |
+|------|
+| Bob |
+
+<!-- {edited:Ann, 2024-01-01} -->
+
+# End
+
+![Diagram](b.png) and [elsewhere](https://example.com/b)
+
+$$
+E = mc^2 \\label{eq:energy}
+$$
+
+See {ref:eq:energy} and {ref:tbl:users}.
+
+[^a]: First note with a [link](https://example.com/note).
+[^b]: Second note.
+";
+        let doc = parse_markdown_with_frontmatter(md);
+        assert!(crate::docx::chapters::split(&doc.blocks).len() > 3);
+
+        for content_ids in [false, true] {
+            let build = |parallel| {
+                let mut config = config_with_images(&["a.png", "b.png"]);
+                config.math_renderer = "omml".to_string();
+                config.h1_page_break = true;
+                let mut rel_manager = RelIdManager::new();
+                if content_ids {
+                    rel_manager.use_content_ids();
+                }
+                let (result, warnings) = crate::diagnostics::capture(|| {
+                    build_document_with(
+                        &doc,
+                        Language::English,
+                        &config,
+                        &mut rel_manager,
+                        None,
+                        None,
+                        parallel,
+                    )
+                    .unwrap()
+                });
+                let images: Vec<_> = result
+                    .images
+                    .images
+                    .iter()
+                    .map(|i| (i.rel_id.clone(), i.filename.clone()))
+                    .collect();
+                let links: Vec<_> = result
+                    .hyperlinks
+                    .hyperlinks
+                    .iter()
+                    .map(|h| (h.url.clone(), h.rel_id.clone()))
+                    .collect();
+                let lists: Vec<_> = result
+                    .numbering
+                    .lists
+                    .iter()
+                    .map(|l| (l.num_id, l.is_ordered))
+                    .collect();
+                let toc: Vec<_> = result
+                    .toc_builder
+                    .as_ref()
+                    .unwrap()
+                    .entries()
+                    .iter()
+                    .map(|e| (e.text.clone(), e.level, e.bookmark_id.clone()))
+                    .collect();
+                let messages: Vec<_> = warnings.into_iter().map(|w| w.message).collect();
+                (
+                    String::from_utf8(result.document.to_xml().unwrap()).unwrap(),
+                    String::from_utf8(result.footnotes.to_xml().unwrap()).unwrap(),
+                    result.footnotes.hyperlinks.clone(),
+                    images,
+                    links,
+                    lists,
+                    toc,
+                    messages,
+                    rel_manager,
+                )
+            };
+
+            let serial = build(false);
+            let parallel = build(true);
+            assert!(serial.0.contains("Table 2.1"));
+            assert!(!serial.7.is_empty());
+            assert_eq!(parallel.0, serial.0);
+            assert_eq!(parallel.1, serial.1);
+            assert_eq!(parallel.2, serial.2);
+            assert_eq!(parallel.3, serial.3);
+            assert_eq!(parallel.4, serial.4);
+            assert_eq!(parallel.5, serial.5);
+            assert_eq!(parallel.6, serial.6);
+            assert_eq!(parallel.7, serial.7);
+            assert_eq!(parallel.8, serial.8);
+        }
+    }
+
+    #[test]
+    fn test_cross_reference_in_document() {
+        let doc = ParsedDocument {
+            frontmatter: None,
+            blocks: vec![
+                Block::Heading {
+                    level: 1,
+                    content: vec![Inline::Text("Introduction".to_string())],
+                    id: Some("intro".to_string()),
+                },
+                Block::Paragraph(vec![
+                    Inline::Text("See ".to_string()),
+                    Inline::CrossRef {
+                        target: "intro".to_string(),
+                        ref_type: RefType::Chapter,
+                    },
+                    Inline::Text(" for more.".to_string()),
+                ]),
+            ],
+            footnotes: std::collections::HashMap::new(),
+        };
+
+        let config = no_toc_config(); // Disable TOC for this test
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &doc,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        // Verify the document was built successfully
+        assert!(!result.document.elements.is_empty());
+
+        // Check that we have a heading and a paragraph
+        let paragraphs = get_paragraphs(&result.document);
+        assert!(paragraphs.len() >= 2);
 
         // First paragraph should be the heading
         let heading = &paragraphs[0];
@@ -3269,6 +5102,7 @@ mod tests {
                     title: None,
                     width: None,
                     id: Some("fig:arch".to_string()),
+                    caption: None,
                 },
                 Block::Paragraph(vec![
                     Inline::Text("See ".to_string()),
@@ -3368,7 +5202,7 @@ mod tests {
 
     #[test]
     fn test_heading_levels() {
-        let md = "# H1\n\n## H2\n\n### H3\n\n#### H4\n\n##### H5";
+        let md = "# H1\n\n## H2\n\n### H3\n\n#### H4\n\n##### H5\n\n###### H6";
         let parsed = parse_markdown_with_frontmatter(md);
         let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
         let result = build_document(
@@ -3383,12 +5217,13 @@ mod tests {
         let docx = &result.document;
         let paragraphs = get_paragraphs(docx);
 
-        assert_eq!(paragraphs.len(), 5);
+        assert_eq!(paragraphs.len(), 6);
         assert_eq!(paragraphs[0].style_id, Some("Heading1".to_string()));
         assert_eq!(paragraphs[1].style_id, Some("Heading2".to_string()));
         assert_eq!(paragraphs[2].style_id, Some("Heading3".to_string()));
         assert_eq!(paragraphs[3].style_id, Some("Heading4".to_string()));
-        assert_eq!(paragraphs[4].style_id, Some("Heading4".to_string())); // H5 also uses Heading4
+        assert_eq!(paragraphs[4].style_id, Some("Heading5".to_string()));
+        assert_eq!(paragraphs[5].style_id, Some("Heading6".to_string()));
     }
 
     #[test]
@@ -3799,6 +5634,30 @@ End of document.
         assert!(runs.iter().any(|r| r.strike && r.text == "strike"));
     }
 
+    #[test]
+    fn test_kbd_runs() {
+        let md = "Press [[Ctrl]]+[[C]]";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &DocumentConfig::default(),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+        let paragraphs = get_paragraphs(&result.document);
+        let keys: Vec<_> = paragraphs[0]
+            .get_runs()
+            .into_iter()
+            .filter(|r| r.style.as_deref() == Some("Kbd"))
+            .map(|r| r.text.clone())
+            .collect();
+        assert_eq!(keys, ["Ctrl", "C"]);
+    }
+
     #[test]
     fn test_blockquote_with_formatting() {
         let md = "> This is a **bold** quote";
@@ -4206,6 +6065,256 @@ End of document.
         }
     }
 
+    #[test]
+    fn test_table_row_pagination() {
+        let md = "| Name | Age |\n|------|-----|\n| John | 30  |\n| Jane | 25  |";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            table_rows_cant_split: true,
+            repeat_table_header: false,
+            ..no_toc_config()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let table = result
+            .document
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                DocElement::Table(table) => Some(table),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(table.rows.len(), 3);
+        assert!(table.rows.iter().all(|r| r.cant_split && !r.is_header));
+        let xml = String::from_utf8(result.document.to_xml().unwrap()).unwrap();
+        assert!(xml.contains("<w:cantSplit/>"));
+        assert!(!xml.contains("<w:tblHeader/>"));
+    }
+
+    #[test]
+    fn test_table_continuation() {
+        let md = "| N |\n|---|\n| 1 |\n| 2 |\n| 3 |\n| 4 |\n| 5 |";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            table_continuation_rows: Some(2),
+            ..no_toc_config()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let row_counts: Vec<usize> = result
+            .document
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                DocElement::Table(table) => Some(table.rows.len()),
+                _ => None,
+            })
+            .collect();
+        // Every part repeats the header row
+        assert_eq!(row_counts, vec![3, 3, 2]);
+
+        let captions: Vec<_> = get_paragraphs(&result.document)
+            .into_iter()
+            .filter(|p| p.style_id.as_deref() == Some("Caption"))
+            .collect();
+        assert_eq!(captions.len(), 2);
+        assert!(captions.iter().all(|p| p.page_break_before));
+        let xml = String::from_utf8(result.document.to_xml().unwrap()).unwrap();
+        assert_eq!(xml.matches("Table 1 (Continued)").count(), 2);
+    }
+
+    #[test]
+    fn test_code_continuation() {
+        let md =
+            "```rust,filename=main.rs\nfn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\nfn e() {}\n```";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            code_continuation_lines: Some(2),
+            ..no_toc_config()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let paragraphs = get_paragraphs(&result.document);
+        let styles: Vec<_> = paragraphs
+            .iter()
+            .filter_map(|p| p.style_id.as_deref())
+            .filter(|s| s.starts_with("Code"))
+            .collect();
+        assert_eq!(
+            styles,
+            vec![
+                "CodeFilename",
+                "Code",
+                "Code",
+                "CodeFilename",
+                "Code",
+                "Code",
+                "CodeFilename",
+                "Code"
+            ]
+        );
+        let continued: Vec<_> = paragraphs
+            .iter()
+            .filter(|p| p.style_id.as_deref() == Some("CodeFilename"))
+            .skip(1)
+            .collect();
+        assert!(continued.iter().all(|p| p.page_break_before));
+        let xml = String::from_utf8(result.document.to_xml().unwrap()).unwrap();
+        assert_eq!(xml.matches("main.rs (Continued)").count(), 2);
+    }
+
+    #[test]
+    fn test_diff_code_block() {
+        let md = "```diff\n@@ -1,2 +1,2 @@\n-let x = 1;\n+let x = 2;\n context\n+added\n```";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &no_toc_config(),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let code: Vec<_> = get_paragraphs(&result.document)
+            .into_iter()
+            .filter(|p| p.style_id.as_deref() == Some("Code"))
+            .collect();
+        let shading: Vec<_> = code.iter().map(|p| p.shading.as_deref()).collect();
+        assert_eq!(
+            shading,
+            [
+                Some("DDF4FF"),
+                Some("FFEBE9"),
+                Some("E6FFEC"),
+                None,
+                Some("E6FFEC")
+            ]
+        );
+
+        // Only the changed digit is bold in the paired lines
+        for (p, digit) in [(code[1], "1"), (code[2], "2")] {
+            let bold: Vec<_> = p
+                .get_runs()
+                .into_iter()
+                .filter(|r| r.bold)
+                .map(|r| r.text.as_str())
+                .collect();
+            assert_eq!(bold, [digit]);
+            let text: String = p.get_runs().iter().map(|r| r.text.as_str()).collect();
+            assert!(text.ends_with(&format!("let x = {};", digit)));
+        }
+        assert!(!code[4].get_runs().iter().any(|r| r.bold));
+    }
+
+    #[test]
+    fn test_code_callouts() {
+        let md = "```rust\nuse std::fs; // <1>\nfn main() {}\n```\n\n<1> Bring `fs` into scope";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &no_toc_config(),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let paragraphs = get_paragraphs(&result.document);
+        let code: Vec<_> = paragraphs
+            .iter()
+            .filter(|p| p.style_id.as_deref() == Some("Code"))
+            .collect();
+        let last_run = code[0].get_runs().pop().unwrap();
+        assert_eq!(last_run.text, " \u{2776}");
+        assert!(last_run.bold);
+        assert!(!code[1]
+            .get_runs()
+            .iter()
+            .any(|r| r.text.contains('\u{2776}')));
+
+        let item = paragraphs
+            .iter()
+            .find(|p| p.style_id.as_deref() == Some("ListParagraph"))
+            .unwrap();
+        let runs = item.get_runs();
+        assert_eq!(runs[0].text, "\u{2776} ");
+        assert!(runs.iter().any(|r| r.style.as_deref() == Some("CodeChar")));
+    }
+
+    #[test]
+    fn test_spacers() {
+        let md = "Intro\n\n## Next\n\n| A |\n|---|\n| 1 |\n\nAfter";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let build = |spacers| {
+            let config = DocumentConfig {
+                spacers,
+                ..no_toc_config()
+            };
+            let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+            build_document(
+                &parsed,
+                Language::English,
+                &config,
+                &mut rel_manager,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        for (spacers, blanks, space_before) in [
+            (Spacers::Blank, 2, Some(0)),
+            (Spacers::Spacing, 0, Some(SPACER_TWIPS)),
+            (Spacers::None, 0, Some(0)),
+        ] {
+            let result = build(spacers);
+            let paragraphs = get_paragraphs(&result.document);
+            let empty = paragraphs.iter().filter(|p| p.children.is_empty()).count();
+            assert_eq!(empty, blanks, "{:?}", spacers);
+            let h2 = Some("Heading2".to_string());
+            let heading = paragraphs.iter().find(|p| p.style_id == h2).unwrap();
+            assert_eq!(heading.spacing_before, space_before, "{:?}", spacers);
+            let after = paragraphs.last().unwrap();
+            assert_eq!(after.spacing_before, space_before, "{:?}", spacers);
+        }
+        assert_eq!(Spacers::from_name("Spacing"), Some(Spacers::Spacing));
+        assert_eq!(Spacers::from_name("off"), Some(Spacers::None));
+        assert_eq!(Spacers::from_name("tight"), None);
+    }
+
     #[test]
     fn test_table_header_shading() {
         let md = "| H1 | H2 |\n|----|----|\n| D1 | D2 |";
@@ -4325,6 +6434,72 @@ End of document.
         assert_eq!(ctx.images[1].filename, "image_rId7.png");
     }
 
+    #[test]
+    fn test_image_context_dedupe_media() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("logo.png"), b"logo bytes").unwrap();
+        std::fs::write(dir.path().join("copy.png"), b"logo bytes").unwrap();
+        std::fs::write(dir.path().join("other.png"), b"other bytes").unwrap();
+
+        let mut ctx = ImageContext::new();
+        ctx.base_path = Some(dir.path().to_path_buf());
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        for src in [
+            "logo.png",
+            "other.png",
+            "logo.png",
+            "copy.png",
+            "missing.png",
+        ] {
+            ctx.add_image(src, None, &mut rel_manager);
+        }
+        ctx.add_image_data(
+            "diagram1.png",
+            b"logo bytes".to_vec(),
+            None,
+            &mut rel_manager,
+        );
+        ctx.add_image_data("diagram2.png", b"chart".to_vec(), None, &mut rel_manager);
+        ctx.dedupe_media();
+
+        let filenames: Vec<_> = ctx.images.iter().map(|i| i.filename.as_str()).collect();
+        assert_eq!(
+            filenames,
+            [
+                "image_rId6.png",
+                "image_rId7.png",
+                "image_rId6.png",
+                "image_rId6.png",
+                "image_rId10.png",
+                "image_rId6.png",
+                "diagram2.png",
+            ]
+        );
+        // Every use keeps its own relationship
+        assert_eq!(ctx.images[2].rel_id, "rId8");
+    }
+
+    #[test]
+    fn test_same_content_compares_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logo.png");
+        std::fs::write(&path, b"logo bytes").unwrap();
+        let image = |src: &str, data: Option<&[u8]>| ImageInfo {
+            filename: "image.png".to_string(),
+            rel_id: "rId1".to_string(),
+            src: src.to_string(),
+            data: data.map(Arc::from),
+            width_emu: 0,
+            height_emu: 0,
+        };
+        let file = image(&path.to_string_lossy(), None);
+
+        assert!(same_content(&file, &image("", Some(b"logo bytes"))));
+        assert!(!same_content(&file, &image("", Some(b"logo bytez"))));
+        assert!(!same_content(&file, &image("", Some(b"logo bytes!"))));
+        assert!(!same_content(&file, &image("missing.png", None)));
+    }
+
     #[test]
     fn test_image_context_dimensions_default() {
         let mut ctx = ImageContext::new();
@@ -4687,6 +6862,272 @@ End of document.
         assert_eq!(toc_elements.len(), 6);
     }
 
+    #[test]
+    fn test_toc_off_directive() {
+        let md = "<!-- {toc:off} -->\n\n# Preface\n\n<!-- {toc:on} -->\n\n# Chapter 1\n\n## Setup";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            process_all_headings: true,
+            ..Default::default()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let toc_builder = result.toc_builder.as_ref().unwrap();
+        let texts: Vec<_> = toc_builder
+            .entries()
+            .iter()
+            .map(|e| e.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["Chapter 1", "Setup"]);
+    }
+
+    #[test]
+    fn test_section_directive() {
+        let md = "# Preface\n\n---\n\n<!-- {section: restart=1, header=none} -->\n\n# Body\n\n---\n\n<!-- {section: restart=5} -->\n\n# Appendix";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig::default();
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let breaks: Vec<_> = get_paragraphs(&result.document)
+            .into_iter()
+            .filter(|p| p.is_section_break())
+            .collect();
+        assert_eq!(breaks.len(), 2);
+        assert_eq!(breaks[1].page_num_start, Some(1));
+        assert!(breaks[1].suppress_header_footer);
+        assert_eq!(result.document.page_num_start, Some(5));
+        assert!(!result.document.suppress_header_footer);
+    }
+
+    #[test]
+    fn test_section_directive_format() {
+        let md = "# Cover
+
+---
+
+<!-- {section: restart=1, format=lowerroman} -->
+
+# Preface
+
+---
+
+<!-- {section: restart=1} -->
+
+# Body
+
+---
+
+<!-- {section: format=upperRoman} -->
+
+# Glossary";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig::default();
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let breaks: Vec<_> = get_paragraphs(&result.document)
+            .into_iter()
+            .filter(|p| p.is_section_break())
+            .collect();
+        assert_eq!(breaks.len(), 3);
+        assert_eq!(breaks[1].page_num_format.as_deref(), Some("lowerRoman"));
+        assert_eq!(breaks[2].page_num_start, Some(1));
+        assert_eq!(breaks[2].page_num_format, None);
+        assert_eq!(
+            result.document.final_page_num_format.as_deref(),
+            Some("upperRoman")
+        );
+    }
+
+    #[test]
+    fn test_chapter_break() {
+        let md = "# One\n\n---\n\n# Two\n\n---\n\n<!-- {section: break=continuous} -->\n\n# Three";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            chapter_break: "oddpage".to_string(),
+            ..Default::default()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let breaks: Vec<_> = get_paragraphs(&result.document)
+            .into_iter()
+            .filter_map(|p| p.section_break.as_deref())
+            .collect();
+        assert_eq!(breaks, vec!["oddPage", "oddPage"]);
+        assert_eq!(
+            result.document.final_section_break.as_deref(),
+            Some("continuous")
+        );
+    }
+
+    #[test]
+    fn test_heading_page_breaks() {
+        let md = "# One
+
+Text.
+
+## Setup
+
+Text.
+
+# Two
+
+Text.
+
+---
+
+# Three
+
+Text.";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            h1_page_break: true,
+            h2_keep_with_next: true,
+            ..no_toc_config()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let paragraphs = get_paragraphs(&result.document);
+        let heading = |style: &str| -> Vec<(bool, bool)> {
+            paragraphs
+                .iter()
+                .filter(|p| p.style_id.as_deref() == Some(style))
+                .map(|p| (p.page_break_before, p.keep_with_next))
+                .collect()
+        };
+        // The first H1 starts the document and the third follows a section break
+        assert_eq!(
+            heading("Heading1"),
+            vec![(false, true), (true, true), (false, true)]
+        );
+        assert_eq!(heading("Heading2"), vec![(false, true)]);
+
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &no_toc_config(),
+            &mut crate::docx::rels_manager::RelIdManager::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(get_paragraphs(&result.document)
+            .iter()
+            .all(|p| !p.page_break_before && !p.keep_with_next));
+    }
+
+    #[test]
+    fn test_anchor_links() {
+        let md = "# Setup {#setup}\n\nSee [setup](#setup) or [the site](https://example.com).";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig::default();
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let links: Vec<_> = get_paragraphs(&result.document)
+            .into_iter()
+            .flat_map(|p| p.children.iter())
+            .filter_map(|child| match child {
+                ParagraphChild::Hyperlink(link) => Some(link.anchor.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(links, vec![Some("setup".to_string()), None]);
+        assert_eq!(result.hyperlinks.hyperlinks.len(), 1);
+    }
+
+    #[test]
+    fn test_edited_directive() {
+        let md = "<!-- {edited: Somchai Jaidee | 1 March 2025} -->\n\n# Setup\n\nText";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig::default();
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let paragraphs = get_paragraphs(&result.document);
+        let heading = paragraphs
+            .iter()
+            .position(|p| p.style_id.as_deref() == Some("Heading1"))
+            .unwrap();
+        let hidden: Vec<_> = paragraphs[heading + 1..heading + 3]
+            .iter()
+            .map(|p| {
+                let text = match &p.children[..] {
+                    [ParagraphChild::Run(r)] => r.text.as_str(),
+                    _ => "",
+                };
+                (p.style_id.as_deref().unwrap(), text)
+            })
+            .collect();
+        assert_eq!(
+            hidden,
+            [
+                ("ChapterAuthor", "Somchai Jaidee"),
+                ("ChapterDate", "1 March 2025")
+            ]
+        );
+    }
+
     #[test]
     fn test_toc_with_explicit_id() {
         let md = "# Introduction {#intro}\n\n## Getting Started {#start}";
@@ -4775,28 +7216,115 @@ End of document.
         }
     }
 
+    #[test]
+    fn test_build_document_image_caption_inlines() {
+        let md = "![Plot](plot.png \"Sales **by year**[^src]\")\n\n[^src]: Annual report\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &DocumentConfig::default(),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let caption = result
+            .document
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                DocElement::Paragraph(p) if p.style_id.as_deref() == Some("Caption") => Some(p),
+                _ => None,
+            })
+            .expect("caption paragraph");
+        let runs: Vec<&Run> = caption
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                ParagraphChild::Run(r) => Some(r),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(runs[0].text, "Figure 1: ");
+        assert!(runs.iter().any(|r| r.text == "by year" && r.bold));
+        assert!(runs
+            .iter()
+            .any(|r| r.style.as_deref() == Some("FootnoteReference")));
+        // Alt text stays the image description
+        if let Some(DocElement::Image(img)) = result.document.elements.first() {
+            assert_eq!(img.alt_text, "Plot");
+        } else {
+            panic!("Expected image element");
+        }
+    }
+
+    #[test]
+    fn test_figure_group() {
+        let md = "# Results {#ch:results}\n\n::: figures\n![Before](before.png){#fig:before}\n\n![After](after.png){#fig:after}\n\nFigure: Cleanup\n:::\n\nSee {ref:fig:after}.\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config_with_images(&["before.png", "after.png"]),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.images.images.len(), 2);
+
+        let table = result
+            .document
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                DocElement::Table(t) => Some(t),
+                _ => None,
+            })
+            .expect("layout table");
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.rows[0].cells.len(), 2);
+        assert!(table.rows[0].cant_split);
+        assert_eq!(
+            table.borders.as_ref().map(|b| b.inside_v.style.as_str()),
+            Some("none")
+        );
+        let sub_caption: String = table.rows[0].cells[1].paragraphs[1]
+            .iter_runs()
+            .map(|r| r.text.as_str())
+            .collect();
+        assert_eq!(sub_caption, "(b) After");
+
+        let text: String = get_paragraphs(&result.document)
+            .iter()
+            .flat_map(|p| p.iter_runs().map(|r| r.text.as_str()))
+            .collect();
+        assert!(text.contains("Figure 1.1: Cleanup"), "{}", text);
+        assert!(text.contains("Figure 1.1(b)"), "{}", text);
+    }
+
     #[test]
     fn test_build_document_image_in_blockquote() {
-        // Note: Parser creates BlockQuote with nested Paragraphs
         // Images in blockquotes are Inline::Image inside Paragraphs
-        // Block::Image is only created for standalone images (not yet implemented)
         let md = "> Quote with image\n> ![Image](img.png)";
         let parsed = parse_markdown_with_frontmatter(md);
         let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
         let result = build_document(
             &parsed,
             Language::English,
-            &DocumentConfig::default(),
+            &config_with_images(&["img.png"]),
             &mut rel_manager,
             None,
             None,
         )
         .unwrap();
 
-        // Parser creates BlockQuote with Paragraphs containing Inline::Image
-        // The builder doesn't extract Inline::Image to Block::Image yet
-        // So no images are tracked in ImageContext
-        assert_eq!(result.images.images.len(), 0);
+        // The inline image is still registered for packaging
+        assert_eq!(result.images.images.len(), 1);
 
         // Should have BlockQuote element
         let has_blockquote = result.document.elements.iter().any(
@@ -4805,6 +7333,100 @@ End of document.
         assert!(has_blockquote);
     }
 
+    #[test]
+    fn test_inline_images_in_tables_and_lists() {
+        let md = "| Status | Note |\n|---|---|\n| ![ok](ok.png) | done |\n\n- Click ![gear](gear.png) to open settings\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config_with_images(&["ok.png", "gear.png"]),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let images = &result.images.images;
+        let srcs: Vec<&str> = images.iter().map(|i| i.src.as_str()).collect();
+        assert_eq!(srcs, vec!["ok.png", "gear.png"]);
+
+        let inline_image = |p: &Paragraph| {
+            p.children.iter().find_map(|c| match c {
+                ParagraphChild::InlineImage(img) => Some(img.clone()),
+                _ => None,
+            })
+        };
+        let cell_image = result
+            .document
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                DocElement::Table(t) => t
+                    .rows
+                    .iter()
+                    .flat_map(|r| &r.cells)
+                    .flat_map(|c| &c.paragraphs)
+                    .find_map(inline_image),
+                _ => None,
+            })
+            .expect("image in table cell");
+        assert_eq!(cell_image.alt_text, "ok");
+        assert_eq!(cell_image.rel_id, images[0].rel_id);
+
+        let list_image = result
+            .document
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                DocElement::Paragraph(p) => inline_image(p),
+                _ => None,
+            })
+            .expect("image in list item");
+        assert_eq!(list_image.name, "gear.png");
+        // Unreadable images fall back to one line of text
+        assert_eq!(list_image.width_emu, 228600);
+        assert_eq!(list_image.height_emu, 228600);
+        assert_ne!(cell_image.id, list_image.id);
+    }
+
+    #[test]
+    fn test_missing_inline_image_keeps_alt_text() {
+        let md =
+            "| Status |\n|---|\n| ![ok](ok.png) |\n\n- Click ![gear](gear.png) to open settings\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let (result, warnings) = crate::diagnostics::capture(|| {
+            build_document(
+                &parsed,
+                Language::English,
+                &config_with_images(&["ok.png"]),
+                &mut rel_manager,
+                None,
+                None,
+            )
+            .unwrap()
+        });
+
+        // Only the image that exists is registered for packaging
+        let srcs: Vec<&str> = result
+            .images
+            .images
+            .iter()
+            .map(|i| i.src.as_str())
+            .collect();
+        assert_eq!(srcs, vec!["ok.png"]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].category, WarningCategory::MissingImage);
+
+        let text: String = get_paragraphs(&result.document)
+            .iter()
+            .flat_map(|p| p.iter_runs().map(|r| r.text.as_str()))
+            .collect();
+        assert_eq!(text, "Click [gear] to open settings");
+    }
+
     #[test]
     fn test_build_result_structure() {
         let md = "# Test\n\nSome text";
@@ -5098,6 +7720,77 @@ End of document.
         );
     }
 
+    #[test]
+    fn test_lang_region() {
+        let md = "ไทย\n\n::: {lang=en}\nTable: Totals\n| A |\n|---|\n| 1 |\n\nPlain words\n:::\n\n::: {lang=ja}\n漢字\n:::\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut config = DocumentConfig::default();
+        config.toc.enabled = false;
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let template = crate::template::extract::table::TableTemplate::default();
+        let result = build_document(
+            &parsed,
+            Language::Thai,
+            &config,
+            &mut rel_manager,
+            Some(&template),
+            None,
+        )
+        .unwrap();
+
+        let runs: Vec<&Run> = get_paragraphs(&result.document)
+            .into_iter()
+            .flat_map(|p| p.iter_runs())
+            .collect();
+        let run = |text: &str| {
+            *runs
+                .iter()
+                .find(|r| r.text == text)
+                .unwrap_or_else(|| panic!("run '{}' not found", text))
+        };
+        assert!(runs.iter().any(|r| r.text.starts_with("Table ")));
+        assert!(!runs.iter().any(|r| r.text.contains("ตารางที่")));
+        assert_eq!(run("Plain words").font.as_deref(), Some("Calibri"));
+        assert_eq!(run("Plain words").lang, Some("en-US"));
+        assert_eq!(run("漢字").lang, Some("ja-JP"));
+        assert_eq!(run("ไทย").font, None);
+    }
+
+    #[test]
+    fn test_parallel_text_table() {
+        let md = "::: translate\n## Terms\n\nThe parties agree.\n\n---\n\nคู่สัญญาตกลงกัน\n:::\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &DocumentConfig::default(),
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let table = result
+            .document
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                DocElement::Table(t) => Some(t),
+                _ => None,
+            })
+            .expect("parallel text should render as a table");
+        assert_eq!(table.rows.len(), 1);
+        let cells = &table.rows[0].cells;
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[0].paragraphs.len(), 2);
+        let translation: String = cells[1].paragraphs[0]
+            .iter_runs()
+            .map(|r| r.text.as_str())
+            .collect();
+        assert_eq!(translation, "คู่สัญญาตกลงกัน");
+    }
+
     #[test]
     fn test_mermaid_spacing_default_config() {
         // Default mermaid spacing should be (120, 120)
@@ -5194,6 +7887,61 @@ End of document.
         assert!(has_math, "Should produce OfficeMath children when renderer is omml");
     }
 
+    #[test]
+    fn test_style_spacing() {
+        let md = "Body text.\n\n```rust\nlet a = 1;\nlet b = 2;\n```\n";
+        let parsed = parse_markdown_with_frontmatter(md);
+        let config = DocumentConfig {
+            style_spacing: HashMap::from([
+                (
+                    "BodyText".to_string(),
+                    StyleSpacing {
+                        line: Some(360),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "Code".to_string(),
+                    StyleSpacing {
+                        before: Some(120),
+                        after: Some(240),
+                        line: Some(240),
+                    },
+                ),
+            ]),
+            ..no_toc_config()
+        };
+        let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+        let result = build_document(
+            &parsed,
+            Language::English,
+            &config,
+            &mut rel_manager,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let paragraphs = get_paragraphs(&result.document);
+        let body = paragraphs
+            .iter()
+            .find(|p| p.style_id.as_deref() == Some("BodyText"))
+            .unwrap();
+        assert_eq!(body.line, Some(360));
+        assert_eq!(body.line_rule.as_deref(), Some("auto"));
+
+        // Space before and after goes around the block, not between lines
+        let code: Vec<_> = paragraphs
+            .iter()
+            .filter(|p| p.style_id.as_deref() == Some("Code"))
+            .collect();
+        assert_eq!(code.len(), 2);
+        assert_eq!(code[0].spacing_before, Some(120));
+        assert_eq!(code[1].spacing_before, Some(0));
+        assert_eq!(code[1].spacing_after, Some(240));
+        assert!(code.iter().all(|p| p.line == Some(240)));
+    }
+
     #[test]
     fn test_document_config_mermaid_defaults() {
         let config = DocumentConfig::default();
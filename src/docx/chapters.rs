@@ -0,0 +1,267 @@
+//! Parallel building of the chapters of the body
+//!
+//! Every top-level H1 and every included file starts a chapter. Caption
+//! numbers, bookmark names, footnote, list and relationship IDs in a
+//! chapter all continue from the chapters before it, and references to
+//! earlier chapters are resolved as they are built. So rather than building
+//! chapters apart and renumbering them afterwards, each chapter is built
+//! from the exact state the serial build has when it gets there:
+//!
+//! 1. Every chapter is built on the rayon pool from the state before the
+//!    first one, recording the IDs and cross-references it asks for.
+//! 2. Replaying each chapter's requests on the state it should have
+//!    started from, and moving the other counters on by what it used,
+//!    gives the state the next chapter starts from.
+//! 3. Chapters built from a different state are built again from the new
+//!    one, on the pool, and step 2 is repeated until none is left. The
+//!    first chapter always starts right, so every round settles at least
+//!    one more; requests rarely depend on the state, so usually one
+//!    rebuild is enough.
+//!
+//! The merged elements, IDs and warnings are then those of a serial build.
+
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::diagnostics::Warning;
+use crate::docx::builder::{
+    BodyEnv, BodyState, HyperlinkContext, ImageContext, NumberingContext, SectionOptions,
+};
+use crate::docx::ooxml::{DocElement, FootnotesXml};
+use crate::docx::rels_manager::{RelIdManager, Request};
+use crate::docx::toc::TocBuilder;
+use crate::docx::xref::{CrossRefContext, Registration};
+use crate::parser::Block;
+
+/// What building a chapter passes on to the next one
+#[derive(Debug, Clone, PartialEq)]
+struct Carry {
+    rel_manager: RelIdManager,
+    xref_ctx: CrossRefContext,
+    toc_generated: u32,
+    next_footnote: i32,
+    table_count: u32,
+    figure_count: u32,
+    prev_is_heading: Option<bool>,
+    at_page_start: bool,
+    space_next: bool,
+    last_list_info: Option<(u32, bool, usize)>,
+    toc_off: bool,
+    section: Option<SectionOptions>,
+    edited: Option<(String, String)>,
+}
+
+impl Carry {
+    fn of(state: &BodyState) -> Self {
+        Self {
+            rel_manager: state.rel_manager.clone(),
+            xref_ctx: state.xref_ctx.clone(),
+            toc_generated: state.toc_builder.generated(),
+            next_footnote: state.footnotes.next_id(),
+            table_count: state.table_count,
+            figure_count: state.figure_count,
+            prev_is_heading: state.prev_is_heading,
+            at_page_start: state.at_page_start,
+            space_next: state.space_next,
+            last_list_info: state.last_list_info,
+            toc_off: state.toc_off,
+            section: state.section.clone(),
+            edited: state.edited.clone(),
+        }
+    }
+
+    /// The state after `chapter`, had it been built from this one
+    fn after(&self, chapter: &Chapter) -> Self {
+        let end = Carry::of(&chapter.state);
+        let start = &chapter.start;
+        let mut next = Self {
+            rel_manager: self.rel_manager.clone(),
+            xref_ctx: self.xref_ctx.clone(),
+            toc_generated: self.toc_generated + end.toc_generated - start.toc_generated,
+            next_footnote: self.next_footnote + end.next_footnote - start.next_footnote,
+            table_count: self.table_count + end.table_count - start.table_count,
+            figure_count: self.figure_count + end.figure_count - start.figure_count,
+            ..end
+        };
+        next.rel_manager.replay(&chapter.requests);
+        next.xref_ctx.replay(&chapter.registrations);
+        next
+    }
+
+    /// Fresh state for building a chapter from this one
+    fn resume(self, env: &BodyEnv, images: &ImageContext) -> BodyState {
+        let mut image_ctx = ImageContext::new();
+        image_ctx.base_path = images.base_path.clone();
+        image_ctx.probed_dimensions = images.probed_dimensions.clone();
+        image_ctx.files = images.files.clone();
+        let mut footnotes = FootnotesXml::starting_at(self.next_footnote);
+        footnotes.separator = env.config.notes.separator.clone();
+        let mut rel_manager = self.rel_manager;
+        rel_manager.start_recording();
+        let mut xref_ctx = self.xref_ctx;
+        xref_ctx.start_recording();
+        BodyState {
+            image_ctx,
+            hyperlink_ctx: HyperlinkContext::new(),
+            numbering_ctx: NumberingContext::new(),
+            footnotes,
+            toc_builder: TocBuilder::continuing(self.toc_generated),
+            xref_ctx,
+            rel_manager,
+            table_count: self.table_count,
+            figure_count: self.figure_count,
+            prev_is_heading: self.prev_is_heading,
+            at_page_start: self.at_page_start,
+            space_next: self.space_next,
+            last_list_info: self.last_list_info,
+            toc_off: self.toc_off,
+            section: self.section,
+            edited: self.edited,
+        }
+    }
+}
+
+/// A chapter built from `start`
+struct Chapter {
+    start: Carry,
+    state: BodyState,
+    elements: Vec<DocElement>,
+    warnings: Vec<Warning>,
+    requests: Vec<Request>,
+    registrations: Vec<Registration>,
+}
+
+fn build_chapter(
+    env: &BodyEnv,
+    images: &ImageContext,
+    blocks: Range<usize>,
+    start: Carry,
+) -> Chapter {
+    let mut state = start.clone().resume(env, images);
+    let mut elements = Vec::new();
+    let ((), warnings) = crate::diagnostics::capture_quietly(|| {
+        for i in blocks {
+            state.build_block(env, i, &env.doc.blocks[i], &mut elements);
+        }
+    });
+    let requests = state.rel_manager.take_recorded();
+    let registrations = state.xref_ctx.take_recorded();
+    Chapter {
+        start,
+        state,
+        elements,
+        warnings,
+        requests,
+        registrations,
+    }
+}
+
+/// Ranges of top-level blocks that make up the chapters of `blocks`
+pub(crate) fn split(blocks: &[Block]) -> Vec<Range<usize>> {
+    let starts: Vec<usize> = blocks
+        .iter()
+        .enumerate()
+        .filter(|(i, block)| {
+            *i == 0
+                || matches!(
+                    block,
+                    Block::Heading { level: 1, .. } | Block::Include { .. }
+                )
+        })
+        .map(|(i, _)| i)
+        .collect();
+    let ends = starts.iter().skip(1).copied().chain([blocks.len()]);
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| start..end)
+        .collect()
+}
+
+/// Build the body one chapter per task, appending its elements to `elements`
+///
+/// Leaves `state` as building every block in order would. Returns `false`,
+/// without building anything, for documents of fewer than two chapters.
+pub(crate) fn build(env: &BodyEnv, state: &mut BodyState, elements: &mut Vec<DocElement>) -> bool {
+    let ranges = split(&env.doc.blocks);
+    if ranges.len() < 2 {
+        return false;
+    }
+    let first = Carry::of(state);
+    let images = &state.image_ctx;
+
+    let mut chapters: Vec<Chapter> = ranges
+        .par_iter()
+        .map(|range| {
+            let start = Carry {
+                prev_is_heading: range
+                    .start
+                    .checked_sub(1)
+                    .map(|i| matches!(env.doc.blocks[i], Block::Heading { .. })),
+                ..first.clone()
+            };
+            build_chapter(env, images, range.clone(), start)
+        })
+        .collect();
+
+    loop {
+        let mut starts = vec![first.clone()];
+        for chapter in &chapters[..chapters.len() - 1] {
+            let next = starts[starts.len() - 1].after(chapter);
+            starts.push(next);
+        }
+        if chapters
+            .iter()
+            .zip(&starts)
+            .all(|(chapter, start)| chapter.start == *start)
+        {
+            break;
+        }
+        chapters = chapters
+            .into_par_iter()
+            .zip(starts)
+            .zip(ranges.par_iter())
+            .map(|((chapter, start), range)| {
+                if chapter.start == start {
+                    chapter
+                } else {
+                    build_chapter(env, images, range.clone(), start)
+                }
+            })
+            .collect();
+    }
+
+    for chapter in chapters {
+        crate::diagnostics::report_all(chapter.warnings);
+        elements.extend(chapter.elements);
+        absorb(state, chapter.state);
+    }
+    true
+}
+
+/// Continue `state` with a chapter built from where it ends
+fn absorb(state: &mut BodyState, mut chapter: BodyState) {
+    state.image_ctx.images.append(&mut chapter.image_ctx.images);
+    state
+        .hyperlink_ctx
+        .hyperlinks
+        .append(&mut chapter.hyperlink_ctx.hyperlinks);
+    state
+        .numbering_ctx
+        .lists
+        .append(&mut chapter.numbering_ctx.lists);
+    state.footnotes.append(chapter.footnotes);
+    state.toc_builder.append(chapter.toc_builder);
+    state.xref_ctx = chapter.xref_ctx;
+    state.rel_manager = chapter.rel_manager;
+    state.table_count = chapter.table_count;
+    state.figure_count = chapter.figure_count;
+    state.prev_is_heading = chapter.prev_is_heading;
+    state.at_page_start = chapter.at_page_start;
+    state.space_next = chapter.space_next;
+    state.last_list_info = chapter.last_list_info;
+    state.toc_off = chapter.toc_off;
+    state.section = chapter.section;
+    state.edited = chapter.edited;
+}
@@ -5,10 +5,20 @@
 //!
 //! Per ECMA-376, embedded fonts must be obfuscated by XOR-ing the first 32 bytes
 //! with a GUID-derived key. The fonts are stored as `.odttf` files in `word/fonts/`.
+//!
+//! A requested font whose license (OS/2 `fsType`) forbids embedding is an
+//! error. With `[fonts] subset = true` the embedded TrueType fonts are cut
+//! down to the glyphs the document uses ([`subset_font`]), which matters
+//! most for large Thai and CJK fonts.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::package::Package;
+use crate::diagnostics::{SourceLocation, WarningCategory};
 use crate::error::{Error, Result};
 
 /// Represents a font file to embed
@@ -30,6 +40,14 @@ pub struct EmbeddedFont {
     pub metrics: Option<FontMetrics>,
 }
 
+impl EmbeddedFont {
+    /// The font file as it was read, with the obfuscation undone
+    pub fn font_data(&self) -> Vec<u8> {
+        // XOR-ing with the same key again restores the original bytes
+        obfuscate_font_data(&self.data, &self.guid)
+    }
+}
+
 /// Font variant types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FontVariant {
@@ -54,7 +72,7 @@ impl FontVariant {
 /// Detects font variant from filename.
 /// Returns `None` for non-standard weights (Light, Medium, Thin, etc.)
 /// that don't map to the 4 OOXML embed slots.
-fn detect_variant(filename: &str) -> Option<FontVariant> {
+pub(crate) fn detect_variant(filename: &str) -> Option<FontVariant> {
     let lower = filename.to_lowercase();
 
     // Check for non-standard weights first — these have no OOXML embed slot
@@ -88,7 +106,7 @@ fn detect_variant(filename: &str) -> Option<FontVariant> {
 }
 
 /// Detects font family name from filename by removing variant suffixes and extension
-fn detect_font_name(filename: &str) -> String {
+pub(crate) fn detect_font_name(filename: &str) -> String {
     let stem = Path::new(filename)
         .file_stem()
         .and_then(|s| s.to_str())
@@ -426,6 +444,21 @@ pub fn check_embed_permission(path: &Path) -> Result<EmbedPermission> {
     }
 }
 
+/// Fail if a font's license (OS/2 `fsType`) forbids embedding it
+fn check_license(data: &[u8], font_name: &str) -> Result<()> {
+    let Some(fs_type) = read_fs_type(data) else {
+        return Ok(());
+    };
+    if classify_embed_permission(fs_type).is_embeddable() {
+        return Ok(());
+    }
+    Err(Error::Template(format!(
+        "Font '{}' does not allow embedding (OS/2 fsType 0x{:04X}); \
+         use a font whose license permits embedding or turn off fonts.embed",
+        font_name, fs_type
+    )))
+}
+
 /// Obfuscate font data per ECMA-376 §15.2.12
 ///
 /// The first 32 bytes of the font data are XOR'd with a key derived from the GUID.
@@ -453,7 +486,48 @@ fn obfuscate_font_data(data: &[u8], guid: &str) -> Vec<u8> {
 }
 
 /// Scan a directory for font files and group them by font family
-pub fn scan_font_dir(dir: &Path) -> Result<HashMap<String, Vec<(PathBuf, FontVariant)>>> {
+///
+/// Fonts whose license forbids embedding are skipped with a warning.
+/// Families are sorted by name and their files by path, so the order
+/// doesn't depend on the file system.
+pub fn scan_font_dir(dir: &Path) -> Result<BTreeMap<String, Vec<(PathBuf, FontVariant)>>> {
+    let mut families = scan_font_files(dir)?;
+    for variants in families.values_mut() {
+        variants.retain(|(path, _)| {
+            let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+            // Check font embedding permission via OS/2 fsType
+            match check_embed_permission(path) {
+                Ok(permission) if !permission.is_embeddable() => {
+                    crate::diagnostics::warn(
+                        WarningCategory::Font,
+                        format!(
+                            "Skipping restricted font (embedding not permitted): {}",
+                            filename
+                        ),
+                    );
+                    false
+                }
+                Ok(_) => true,
+                Err(e) => {
+                    crate::diagnostics::warn(
+                        WarningCategory::Font,
+                        format!(
+                            "Could not read embedding permission for {}: {}",
+                            filename, e
+                        ),
+                    );
+                    // Continue anyway — font may still be embeddable
+                    true
+                }
+            }
+        });
+    }
+    families.retain(|_, variants| !variants.is_empty());
+    Ok(families)
+}
+
+/// Font files of a directory by font family, whatever their license
+fn scan_font_files(dir: &Path) -> Result<BTreeMap<String, Vec<(PathBuf, FontVariant)>>> {
     if !dir.exists() || !dir.is_dir() {
         return Err(Error::Template(format!(
             "Font embed directory does not exist: {}",
@@ -461,7 +535,7 @@ pub fn scan_font_dir(dir: &Path) -> Result<HashMap<String, Vec<(PathBuf, FontVar
         )));
     }
 
-    let mut families: HashMap<String, Vec<(PathBuf, FontVariant)>> = HashMap::new();
+    let mut families: BTreeMap<String, Vec<(PathBuf, FontVariant)>> = BTreeMap::new();
 
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
@@ -490,39 +564,26 @@ pub fn scan_font_dir(dir: &Path) -> Result<HashMap<String, Vec<(PathBuf, FontVar
         let variant = match detect_variant(&filename) {
             Some(v) => v,
             None => {
-                eprintln!(
-                    "Skipping non-standard weight font: {} (OOXML only supports Regular/Bold/Italic/BoldItalic)",
-                    filename
+                crate::diagnostics::warn(
+                    WarningCategory::Font,
+                    format!(
+                        "Skipping non-standard weight font: {} (OOXML only supports Regular/Bold/Italic/BoldItalic)",
+                        filename
+                    ),
                 );
                 continue;
             }
         };
 
-        // Check font embedding permission via OS/2 fsType
-        match check_embed_permission(&path) {
-            Ok(permission) => {
-                if !permission.is_embeddable() {
-                    eprintln!(
-                        "Skipping restricted font (embedding not permitted): {}",
-                        filename
-                    );
-                    continue;
-                }
-            }
-            Err(e) => {
-                eprintln!(
-                    "Warning: Could not read embedding permission for {}: {}",
-                    filename, e
-                );
-                // Continue anyway — font may still be embeddable
-            }
-        }
-
         families
             .entry(font_name)
             .or_default()
             .push((path, variant));
     }
+    // `read_dir` order varies between file systems and runs
+    for variants in families.values_mut() {
+        variants.sort_by(|a, b| a.0.cmp(&b.0));
+    }
 
     Ok(families)
 }
@@ -530,34 +591,28 @@ pub fn scan_font_dir(dir: &Path) -> Result<HashMap<String, Vec<(PathBuf, FontVar
 /// Prepare embedded fonts from a directory
 ///
 /// Reads font files, obfuscates them, and returns `EmbeddedFont` entries
-/// ready to be added to the DOCX archive.
+/// ready to be added to the DOCX archive. A requested font whose license
+/// (OS/2 `fsType`) forbids embedding is an error rather than being left
+/// out silently.
 pub fn prepare_embedded_fonts(
     dir: &Path,
     font_names: &[&str],
 ) -> Result<Vec<EmbeddedFont>> {
-    let families = scan_font_dir(dir)?;
+    let families = scan_font_files(dir)?;
     let mut result = Vec::new();
     let mut font_counter = 1u32;
 
     for requested_name in font_names {
         // Find matching family (case-insensitive partial match)
-        let requested_lower = requested_name.to_lowercase();
         let matching: Vec<_> = families
             .iter()
-            .filter(|(name, _)| {
-                let name_lower = name.to_lowercase();
-                name_lower == requested_lower
-                    || name_lower.replace(' ', "") == requested_lower.replace(' ', "")
-                    || name_lower.contains(&requested_lower)
-                    || requested_lower.contains(&name_lower)
-            })
+            .filter(|(name, _)| font_name_matches(name, requested_name))
             .collect();
 
         if matching.is_empty() {
-            eprintln!(
-                "Warning: Font '{}' not found in {}",
-                requested_name,
-                dir.display()
+            crate::diagnostics::warn(
+                WarningCategory::Font,
+                format!("Font '{}' not found in {}", requested_name, dir.display()),
             );
             continue;
         }
@@ -565,6 +620,8 @@ pub fn prepare_embedded_fonts(
         for (family_name, variants) in &matching {
             for (path, variant) in *variants {
                 let raw_data = std::fs::read(path)?;
+                check_license(&raw_data, requested_name)
+                    .map_err(|e| e.at(SourceLocation::file(path.as_path())))?;
                 let metrics = read_font_metrics(&raw_data);
 
                 // Use font name from the 'name' table if available, else from filename
@@ -597,9 +654,86 @@ pub fn prepare_embedded_fonts(
     Ok(result)
 }
 
+/// Whether a font family found on disk satisfies a configured font name
+///
+/// Matching is case-insensitive and tolerant of spaces and partial names,
+/// so `"Noto Sans Thai"` matches files named `NotoSansThai-Regular.ttf`.
+pub fn font_name_matches(family: &str, requested: &str) -> bool {
+    let family = family.to_lowercase();
+    let requested = requested.to_lowercase();
+    family == requested
+        || family.replace(' ', "") == requested.replace(' ', "")
+        || family.contains(&requested)
+        || requested.contains(&family)
+}
+
+/// Unicode ranges checked by [`glyph_coverage`], grouped by script
+pub const COVERAGE_RANGES: [(&str, &[(char, char)]); 3] = [
+    ("Thai", &[('\u{0E01}', '\u{0E3A}'), ('\u{0E3F}', '\u{0E5B}')]),
+    ("Latin", &[('\u{0020}', '\u{007E}'), ('\u{00A0}', '\u{00FF}')]),
+    (
+        "Symbols",
+        &[
+            ('\u{2010}', '\u{2027}'), // General punctuation (dashes, quotes, bullets)
+            ('\u{2030}', '\u{205E}'),
+            ('\u{20A0}', '\u{20C0}'), // Currency
+            ('\u{2190}', '\u{21FF}'), // Arrows
+            ('\u{2200}', '\u{22FF}'), // Mathematical operators
+        ],
+    ),
+];
+
+/// Number of characters of one script a font has glyphs for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphCoverage {
+    /// Script name from [`COVERAGE_RANGES`]
+    pub script: &'static str,
+    /// Characters with a glyph in the font
+    pub covered: usize,
+    /// Characters checked
+    pub total: usize,
+}
+
+impl GlyphCoverage {
+    /// Coverage as a percentage (0-100)
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.covered as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// Report which of the [`COVERAGE_RANGES`] a font file has glyphs for
+///
+/// Returns `None` if the data is not a parseable TrueType/OpenType font.
+pub fn glyph_coverage(data: &[u8]) -> Option<Vec<GlyphCoverage>> {
+    let face = ttf_parser::Face::parse(data, 0).ok()?;
+    Some(
+        COVERAGE_RANGES
+            .iter()
+            .map(|(script, ranges)| {
+                let chars = ranges.iter().flat_map(|&(start, end)| start..=end);
+                let (covered, total) = chars.fold((0, 0), |(covered, total), c| {
+                    let has_glyph = face.glyph_index(c).is_some_and(|g| g.0 != 0);
+                    (covered + usize::from(has_glyph), total + 1)
+                });
+                GlyphCoverage {
+                    script,
+                    covered,
+                    total,
+                }
+            })
+            .collect(),
+    )
+}
+
 /// Grouping of embedded fonts by font name for fontTable.xml generation
-pub fn group_by_font_name(fonts: &[EmbeddedFont]) -> HashMap<String, Vec<&EmbeddedFont>> {
-    let mut groups: HashMap<String, Vec<&EmbeddedFont>> = HashMap::new();
+///
+/// Sorted by name, so fontTable.xml is the same from build to build.
+pub fn group_by_font_name(fonts: &[EmbeddedFont]) -> BTreeMap<String, Vec<&EmbeddedFont>> {
+    let mut groups: BTreeMap<String, Vec<&EmbeddedFont>> = BTreeMap::new();
     for font in fonts {
         groups
             .entry(font.font_name.clone())
@@ -609,6 +743,304 @@ pub fn group_by_font_name(fonts: &[EmbeddedFont]) -> HashMap<String, Vec<&Embedd
     groups
 }
 
+/// fsType bit 8: the font may only be embedded whole
+const FS_TYPE_NO_SUBSETTING: u16 = 0x0100;
+
+/// Characters whose glyphs are kept in every subset
+const ALWAYS_KEPT: (char, char) = ('\u{0020}', '\u{007E}');
+
+/// Thai block, kept whole when Word generates Thai page or list numbers
+const THAI_BLOCK: (char, char) = ('\u{0E01}', '\u{0E5B}');
+
+static FONT_EMBED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<w:embed(?:Regular|Bold|Italic|BoldItalic) r:id="([^"]+)" w:fontKey="\{([0-9A-Fa-f-]{36})\}""#)
+        .expect("Invalid regex")
+});
+
+/// Reduce a TrueType font to the glyphs needed to show `chars`
+///
+/// Outlines of glyphs that aren't needed are emptied; glyph ids, `cmap`
+/// and the layout tables stay as they are, so shaping still works. Glyphs
+/// no character maps to (ligatures, contextual forms such as the shifted
+/// Thai tone marks) are always kept, as are the parts of composite glyphs
+/// and printable ASCII.
+///
+/// Returns `None` when the font is left whole: CFF-based OpenType fonts,
+/// collections, fonts whose license forbids subsetting, and fonts where
+/// nothing would be removed.
+pub fn subset_font(data: &[u8], chars: &HashSet<char>) -> Option<Vec<u8>> {
+    if data.get(0..4) == Some(b"ttcf".as_slice())
+        || read_fs_type(data).is_some_and(|fs_type| fs_type & FS_TYPE_NO_SUBSETTING != 0)
+    {
+        return None;
+    }
+    let face = ttf_parser::Face::parse(data, 0).ok()?;
+    let num_glyphs = face.number_of_glyphs() as usize;
+    let (head_offset, _) = find_table(data, b"head")?;
+    let long_loca = read_u16(data, head_offset + 50)? != 0;
+    let glyf = table_data(data, b"glyf")?;
+    let loca = table_data(data, b"loca")?;
+
+    let offsets = (0..=num_glyphs)
+        .map(|i| {
+            if long_loca {
+                read_u32(loca, i * 4).map(|o| o as usize)
+            } else {
+                read_u16(loca, i * 2).map(|o| o as usize * 2)
+            }
+        })
+        .collect::<Option<Vec<usize>>>()?;
+    if offsets.windows(2).any(|w| w[0] > w[1]) || offsets[num_glyphs] > glyf.len() {
+        return None;
+    }
+
+    // Glyphs reached through a Unicode cmap are only kept when used
+    let mut keep = vec![true; num_glyphs];
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables.into_iter().filter(|s| s.is_unicode()) {
+            subtable.codepoints(|cp| {
+                if let Some(glyph) = subtable.glyph_index(cp) {
+                    if let Some(kept) = keep.get_mut(glyph.0 as usize) {
+                        *kept = false;
+                    }
+                }
+            });
+        }
+    }
+    let (first, last) = ALWAYS_KEPT;
+    for c in chars.iter().copied().chain(first..=last) {
+        if let Some(glyph) = face.glyph_index(c) {
+            if let Some(kept) = keep.get_mut(glyph.0 as usize) {
+                *kept = true;
+            }
+        }
+    }
+    if let Some(notdef) = keep.first_mut() {
+        *notdef = true;
+    }
+
+    let mut pending: Vec<usize> = (0..num_glyphs).filter(|&g| keep[g]).collect();
+    while let Some(glyph) = pending.pop() {
+        for component in composite_components(&glyf[offsets[glyph]..offsets[glyph + 1]]) {
+            if component < num_glyphs && !keep[component] {
+                keep[component] = true;
+                pending.push(component);
+            }
+        }
+    }
+
+    let mut new_glyf = Vec::with_capacity(glyf.len());
+    let mut new_loca = Vec::with_capacity(loca.len());
+    for glyph in 0..=num_glyphs {
+        // Short offsets stay even: every glyph in a short-loca font has an
+        // even length
+        if long_loca {
+            new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+        } else {
+            new_loca.extend_from_slice(&((new_glyf.len() / 2) as u16).to_be_bytes());
+        }
+        if glyph < num_glyphs && keep[glyph] {
+            new_glyf.extend_from_slice(&glyf[offsets[glyph]..offsets[glyph + 1]]);
+        }
+    }
+    if new_glyf.len() == glyf.len() {
+        return None;
+    }
+
+    let subset = rebuild_font(data, &[(*b"glyf", new_glyf), (*b"loca", new_loca)])?;
+    ttf_parser::Face::parse(&subset, 0).ok()?;
+    Some(subset)
+}
+
+/// Subset every embedded font of a finished DOCX to the characters its
+/// parts use
+///
+/// The fonts are found through `fontTable.xml`, de-obfuscated with their
+/// font keys, passed through [`subset_font`] and obfuscated again. The
+/// input is returned as is when no font gets smaller.
+pub(crate) fn subset_embedded_fonts(docx: Vec<u8>) -> Result<Vec<u8>> {
+    let mut package = Package::read(&docx)?;
+    let font_table = package.text("word/fontTable.xml").unwrap_or_default();
+    let rels = package
+        .text("word/_rels/fontTable.xml.rels")
+        .map(|xml| crate::reverse::parts::parse_relationships(&xml))
+        .unwrap_or_default();
+    let chars = used_chars(&package);
+
+    let mut changed = false;
+    for caps in FONT_EMBED.captures_iter(&font_table) {
+        let Some(rel) = rels.get(&caps[1]).filter(|r| !r.external) else {
+            continue;
+        };
+        let part = format!(
+            "word/{}",
+            rel.target
+                .trim_start_matches('/')
+                .trim_start_matches("word/")
+        );
+        let guid = &caps[2];
+        let Some(data) = package.get(&part) else {
+            continue;
+        };
+        if let Some(subset) = subset_font(&obfuscate_font_data(data, guid), &chars) {
+            package.set(&part, obfuscate_font_data(&subset, guid));
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return Ok(docx);
+    }
+    package.write()
+}
+
+/// Every character in the XML parts of `word/`
+///
+/// Page and list numbers in Thai formats are generated by Word and never
+/// appear in the XML, so the Thai block is added whole when one is used.
+fn used_chars(package: &Package) -> HashSet<char> {
+    let mut chars = HashSet::new();
+    let mut thai_numbers = false;
+    for (name, data) in &package.parts {
+        if !name.starts_with("word/") || !name.ends_with(".xml") || name == "word/fontTable.xml" {
+            continue;
+        }
+        let xml = String::from_utf8_lossy(data);
+        thai_numbers |= xml.contains("w:val=\"thai");
+        chars.extend(xml.chars());
+    }
+    if thai_numbers {
+        let (first, last) = THAI_BLOCK;
+        chars.extend(first..=last);
+    }
+    chars
+}
+
+/// Glyph ids used by a composite glyph
+fn composite_components(glyph: &[u8]) -> Vec<usize> {
+    const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    const WE_HAVE_A_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+    let mut components = Vec::new();
+    // A negative contour count marks a composite glyph
+    if read_u16(glyph, 0).is_none_or(|contours| (contours as i16) >= 0) {
+        return components;
+    }
+    let mut at = 10;
+    while let (Some(flags), Some(index)) = (read_u16(glyph, at), read_u16(glyph, at + 2)) {
+        components.push(index as usize);
+        at += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            8
+        } else {
+            6
+        };
+        at += if flags & WE_HAVE_A_SCALE != 0 {
+            2
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            4
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            8
+        } else {
+            0
+        };
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    components
+}
+
+/// Write a font file again with some tables replaced
+///
+/// Table checksums and `head.checkSumAdjustment` are recomputed, and the
+/// digital signature (`DSIG`), which no longer matches, is dropped.
+fn rebuild_font(data: &[u8], replaced: &[([u8; 4], Vec<u8>)]) -> Option<Vec<u8>> {
+    let num_tables = read_u16(data, 4)? as usize;
+    let mut tables: Vec<([u8; 4], Vec<u8>)> = Vec::with_capacity(num_tables);
+    for i in 0..num_tables {
+        let record = data.get(12 + i * 16..28 + i * 16)?;
+        let tag: [u8; 4] = record[0..4].try_into().ok()?;
+        if &tag == b"DSIG" {
+            continue;
+        }
+        let table = match replaced.iter().find(|(t, _)| *t == tag) {
+            Some((_, table)) => table.clone(),
+            None => {
+                let offset = read_u32(record, 8)? as usize;
+                let length = read_u32(record, 12)? as usize;
+                data.get(offset..offset + length)?.to_vec()
+            }
+        };
+        tables.push((tag, table));
+    }
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.max(1).leading_zeros() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+    let mut font = Vec::with_capacity(data.len());
+    font.extend_from_slice(&data[0..4]);
+    for value in [
+        count,
+        search_range,
+        entry_selector,
+        count * 16 - search_range,
+    ] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut offset = 12 + tables.len() * 16;
+    let mut head_offset = None;
+    for (tag, table) in &mut tables {
+        if *tag == *b"head" {
+            // The adjustment is left out of the checksums
+            table.get_mut(8..12)?.fill(0);
+            head_offset = Some(offset);
+        }
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(table).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in &tables {
+        font.extend_from_slice(table);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+    let head_offset = head_offset?;
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    Some(font)
+}
+
+/// Sum of a table as big-endian u32 words, zero-padded
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Bytes of a table of a TrueType/OpenType font
+fn table_data<'a>(data: &'a [u8], table_tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let (offset, length) = find_table(data, table_tag)?;
+    data.get(offset..offset + length)
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -738,4 +1170,84 @@ mod tests {
         let name = read_font_name(&data).expect("should read name");
         assert_eq!(name, "Srisakdi");
     }
+
+    #[test]
+    fn test_font_name_matches() {
+        assert!(font_name_matches("NotoSansThai", "Noto Sans Thai"));
+        assert!(font_name_matches("IBMPlexSansThai", "ibmplexsansthai"));
+        assert!(!font_name_matches("Consolas", "Noto Sans Thai"));
+    }
+
+    #[test]
+    fn test_glyph_coverage() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/fonts/NotoSansThai-Regular.ttf");
+        let data = std::fs::read(path).unwrap();
+        let coverage = glyph_coverage(&data).unwrap();
+
+        let thai = coverage.iter().find(|c| c.script == "Thai").unwrap();
+        assert_eq!(thai.total, 87);
+        assert!(thai.percent() > 90.0, "{:?}", thai);
+
+        assert!(glyph_coverage(b"not a font").is_none());
+    }
+
+    #[test]
+    fn test_subset_font() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/fonts/NotoSansThai-Regular.ttf");
+        let data = std::fs::read(path).unwrap();
+        let chars: HashSet<char> = "สวัสดี".chars().collect();
+        let subset = subset_font(&data, &chars).expect("font should be subset");
+        assert!(subset.len() < data.len());
+        assert_eq!(checksum(&subset), 0xB1B0_AFBA);
+
+        let face = ttf_parser::Face::parse(&subset, 0).unwrap();
+        let outline = |c: char| face.glyph_index(c).and_then(|g| face.glyph_bounding_box(g));
+        assert!(outline('ส').is_some());
+        assert!(outline('ฮ').is_none());
+
+        assert!(subset_font(b"not a font", &chars).is_none());
+    }
+
+    #[test]
+    fn test_subset_embedded_fonts() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/fonts");
+        let config = crate::DocumentConfig {
+            embedded_fonts: prepare_embedded_fonts(&dir, &["Noto Sans Thai"]).unwrap(),
+            subset_fonts: true,
+            ..Default::default()
+        };
+        let docx = crate::markdown_to_docx_with_config("# สวัสดี\n", crate::Language::Thai, &config)
+            .unwrap();
+
+        let package = Package::read(&docx).unwrap();
+        for font in &config.embedded_fonts {
+            let embedded = package
+                .get(&format!("word/fonts/{}", font.filename))
+                .unwrap();
+            assert!(embedded.len() < font.data.len(), "{}", font.filename);
+            let data = obfuscate_font_data(embedded, &font.guid);
+            assert!(ttf_parser::Face::parse(&data, 0).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_restricted_font_is_an_error() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/fonts/NotoSansThai-Regular.ttf");
+        let mut data = std::fs::read(path).unwrap();
+        let (os2, _) = find_table(&data, b"OS/2").unwrap();
+        data[os2 + 8..os2 + 10].copy_from_slice(&0x0002u16.to_be_bytes());
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Locked-Regular.ttf"), &data).unwrap();
+
+        let err = prepare_embedded_fonts(dir.path(), &["Locked"]).unwrap_err();
+        assert_eq!(err.code(), "template");
+        assert!(err.to_string().contains("fsType 0x0002"), "{}", err);
+        assert!(err.location().is_some());
+
+        // Scanning for other uses still just leaves it out
+        assert!(scan_font_dir(dir.path()).unwrap().is_empty());
+    }
 }
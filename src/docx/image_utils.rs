@@ -18,6 +18,34 @@ impl ImageDimensions {
     }
 }
 
+/// Bytes read from the start of an image file when looking for its size
+const HEADER_PREFIX_LEN: u64 = 64 * 1024;
+
+/// Read image dimensions from a file without loading the whole image
+///
+/// Only the first 64 KiB are read, which covers the header of every
+/// supported format except JPEGs with large embedded metadata; those fall
+/// back to reading the rest of the file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_image_dimensions_from_file(path: &std::path::Path) -> Option<ImageDimensions> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut data = Vec::new();
+    (&mut file)
+        .take(HEADER_PREFIX_LEN)
+        .read_to_end(&mut data)
+        .ok()?;
+    if let Some(dims) = read_image_dimensions(&data) {
+        return Some(dims);
+    }
+    if (data.len() as u64) < HEADER_PREFIX_LEN {
+        return None;
+    }
+    file.read_to_end(&mut data).ok()?;
+    read_image_dimensions(&data)
+}
+
 /// Read image dimensions from image data
 /// Supports PNG, JPEG, GIF, BMP, and SVG
 pub fn read_image_dimensions(data: &[u8]) -> Option<ImageDimensions> {
@@ -192,6 +220,23 @@ pub fn default_image_size_emu(dims: ImageDimensions) -> (i64, i64) {
     calculate_image_size_emu(dims, 96.0, 6.0, 9.0)
 }
 
+/// Size of an image placed inline with text
+/// - Natural size at 96 DPI, capped at `max_width_emu` and 9 inches height
+/// - A quarter inch square (about one line of text) if the size is unknown
+pub fn inline_image_size_emu(dims: Option<ImageDimensions>, max_width_emu: i64) -> (i64, i64) {
+    const EMU_PER_INCH: f64 = 914400.0;
+
+    match dims {
+        Some(dims) if dims.width > 0 && dims.height > 0 => {
+            calculate_image_size_emu(dims, 96.0, max_width_emu as f64 / EMU_PER_INCH, 9.0)
+        }
+        _ => {
+            let side = (0.25 * EMU_PER_INCH) as i64;
+            (side, side)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +255,31 @@ mod tests {
         assert_eq!(dims.height, 128);
     }
 
+    #[test]
+    fn test_inline_image_size() {
+        // 6 inch text column
+        let max_width = 6 * 914400;
+        let icon = ImageDimensions {
+            width: 24,
+            height: 24,
+        };
+        assert_eq!(
+            inline_image_size_emu(Some(icon), max_width),
+            (228600, 228600)
+        );
+
+        let banner = ImageDimensions {
+            width: 1152,
+            height: 96,
+        };
+        assert_eq!(
+            inline_image_size_emu(Some(banner), max_width),
+            (5486400, 457200)
+        );
+
+        assert_eq!(inline_image_size_emu(None, max_width), (228600, 228600));
+    }
+
     #[test]
     fn test_calculate_size() {
         let dims = ImageDimensions {
@@ -228,4 +298,25 @@ mod tests {
         let aspect = w as f64 / h as f64;
         assert!((aspect - 1920.0 / 1080.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_dimensions_from_file() {
+        let mut data = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x0D]);
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&[0x00, 0x00, 0x02, 0x00]); // Width: 512
+        data.extend_from_slice(&[0x00, 0x00, 0x01, 0x00]); // Height: 256
+        data.resize(200_000, 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.png");
+        std::fs::write(&path, &data).unwrap();
+        let dims = read_image_dimensions_from_file(&path).unwrap();
+        assert_eq!((dims.width, dims.height), (512, 256));
+
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, "not an image").unwrap();
+        assert!(read_image_dimensions_from_file(&text).is_none());
+        assert!(read_image_dimensions_from_file(&dir.path().join("missing.png")).is_none());
+    }
 }
@@ -15,7 +15,7 @@ use once_cell::sync::Lazy;
 use crate::error::Error;
 
 /// Embedded XITS Math font (OpenType math font)
-static MATH_FONT_DATA: &[u8] = include_bytes!("XITS_Math.otf");
+pub(crate) static MATH_FONT_DATA: &[u8] = include_bytes!("XITS_Math.otf");
 
 // ── SVG Backend ────────────────────────────────────────────────────────
 
@@ -320,9 +320,13 @@ fn is_math_font_supported(ch: char) -> bool {
 
 // ── Public API ─────────────────────────────────────────────────────────
 
-/// Cache for rendered math expressions
-static RENDER_CACHE: Lazy<Mutex<HashMap<String, MathSvgResult>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+/// SVG bytes the render cache holds at most; later renders are not cached,
+/// so equation-heavy documents don't keep every rendering alive
+const RENDER_CACHE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Cache for rendered math expressions, with the SVG bytes it holds
+static RENDER_CACHE: Lazy<Mutex<(HashMap<String, MathSvgResult>, usize)>> =
+    Lazy::new(|| Mutex::new((HashMap::new(), 0)));
 
 /// Rich metadata from a rendered math SVG, providing everything needed
 /// for correct sizing and vertical alignment without ad-hoc heuristics.
@@ -365,7 +369,7 @@ pub fn render_latex_to_svg(
     // Check cache
     let cache_key = format!("rex:{}:{}:{}", latex, display, font_size_str);
     if let Ok(cache) = RENDER_CACHE.lock() {
-        if let Some(cached) = cache.get(&cache_key) {
+        if let Some(cached) = cache.0.get(&cache_key) {
             return Ok(cached.clone());
         }
     }
@@ -498,7 +502,11 @@ pub fn render_latex_to_svg(
 
     // Cache the result
     if let Ok(mut cache) = RENDER_CACHE.lock() {
-        cache.insert(cache_key, result.clone());
+        let (entries, bytes) = &mut *cache;
+        if *bytes + result.svg_bytes.len() <= RENDER_CACHE_BYTES {
+            *bytes += result.svg_bytes.len();
+            entries.insert(cache_key, result.clone());
+        }
     }
 
     Ok(result)
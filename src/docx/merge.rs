@@ -0,0 +1,703 @@
+//! Merging of several DOCX files into one document
+//!
+//! The first document is used as the base: its styles, page setup, headers
+//! and footers apply to the merged result. The bodies of the following
+//! documents are appended after a page break, with everything that is
+//! numbered per package re-mapped so it stays unique:
+//!
+//! - relationships (images and hyperlinks), with media files copied over
+//! - list numbering definitions (`numbering.xml`)
+//! - footnotes and endnotes
+//! - bookmark ids, colliding bookmark names and drawing ids
+//!
+//! Styles missing from the base are copied from the appended document.
+//! A style whose id exists in both with a different definition is copied
+//! under a new id, and the appended content is pointed at the copy.
+
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::package::Package;
+use crate::error::{Error, Result};
+
+const REL_NUMBERING: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering";
+const REL_FOOTNOTES: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/footnotes";
+const REL_ENDNOTES: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/endnotes";
+
+const CT_NUMBERING: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml";
+const CT_FOOTNOTES: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml";
+const CT_ENDNOTES: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.endnotes+xml";
+
+const PAGE_BREAK: &str = r#"<w:p><w:r><w:br w:type="page"/></w:r></w:p>"#;
+
+pub(super) static RELATIONSHIP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<Relationship\s[^>]*?/?>").expect("Invalid regex"));
+static REL_ID_ATTR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\b(r:(?:id|embed|link|pict))="([^"]*)""#).expect("Invalid regex"));
+pub(super) static HEADER_FOOTER_REF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<w:(?:header|footer)Reference\b[^>]*/>").expect("Invalid regex"));
+pub(super) static ABSTRACT_NUM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<w:abstractNum\s.*?</w:abstractNum>").expect("Invalid regex"));
+static NUM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<w:num\s.*?</w:num>").expect("Invalid regex"));
+static NSID: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:nsid\b[^>]*/>").expect("Invalid regex"));
+static STYLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<w:style\s[^>]*/>|<w:style\s.*?</w:style>").expect("Invalid regex")
+});
+static STYLE_ID: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"w:styleId="([^"]*)""#).expect("Invalid regex"));
+static STYLE_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(<w:name\s+w:val=")([^"]*)""#).expect("Invalid regex"));
+static STYLE_DEFAULT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\s+w:default="(?:1|true|on)""#).expect("Invalid regex"));
+static STYLE_REF: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(<w:(?:pStyle|rStyle|tblStyle|basedOn|next|link)\b[^>]*?\sw:val=")([^"]*)""#)
+        .expect("Invalid regex")
+});
+static BOOKMARK_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<w:bookmarkStart\b[^>]*?w:name="([^"]*)""#).expect("Invalid regex"));
+
+/// Merge DOCX files into a single document
+///
+/// Documents are concatenated in order, each starting on a new page.
+/// Returns the bytes of the merged DOCX.
+pub fn merge_docx(documents: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let mut iter = documents.iter();
+    let first = iter
+        .next()
+        .ok_or_else(|| Error::Config("No documents to merge".to_string()))?;
+
+    let mut base = Package::read(first)?;
+    for (index, doc) in iter.enumerate() {
+        let appended = Package::read(doc)?;
+        base.append(&appended, index + 1)?;
+    }
+    base.write()
+}
+
+impl Package {
+    /// Append the body of `other` to this package
+    fn append(&mut self, other: &Package, index: usize) -> Result<()> {
+        let base_doc = self.text("word/document.xml").unwrap_or_default();
+        let other_doc = other.text("word/document.xml").unwrap_or_default();
+
+        let mut body = body_content(&other_doc)
+            .ok_or_else(|| Error::Parse("Appended DOCX has no <w:body>".to_string()))?
+            .to_string();
+
+        // Appended headers/footers are not carried over
+        body = HEADER_FOOTER_REF.replace_all(&body, "").into_owned();
+
+        let styles = self.merge_styles(other, Some(index));
+        body = rename_styles(&body, &styles);
+        body = self.merge_relationships(other, &body, index);
+        body = self.merge_numbering(other, &body);
+        body = self.merge_notes(other, &body, Notes::FOOTNOTES, &styles);
+        body = self.merge_notes(other, &body, Notes::ENDNOTES, &styles);
+        body = remap_bookmarks(&base_doc, &body, index);
+        let drawing_offset = id_offset(&base_doc, &body, "wp:docPr", "id", &[]);
+        body = offset_attr(&body, "wp:docPr", "id", drawing_offset, &[]);
+
+        let insert_at = final_sect_pr(&base_doc)
+            .or_else(|| base_doc.rfind("</w:body>"))
+            .ok_or_else(|| Error::Parse("DOCX has no <w:body>".to_string()))?;
+        let mut merged = String::with_capacity(base_doc.len() + body.len() + PAGE_BREAK.len());
+        merged.push_str(&base_doc[..insert_at]);
+        merged.push_str(PAGE_BREAK);
+        merged.push_str(&body);
+        merged.push_str(&base_doc[insert_at..]);
+        self.set_text("word/document.xml", merged);
+
+        Ok(())
+    }
+
+    /// Copy image and hyperlink relationships, returning the re-mapped body
+    fn merge_relationships(&mut self, other: &Package, body: &str, index: usize) -> String {
+        const RELS: &str = "word/_rels/document.xml.rels";
+        let other_rels = other.text(RELS).unwrap_or_default();
+        let mut base_rels = self.text(RELS).unwrap_or_default();
+
+        let mut id_map = HashMap::new();
+        let mut new_rels = String::new();
+        for rel in RELATIONSHIP.find_iter(&other_rels) {
+            let rel = rel.as_str();
+            let (Some(id), Some(rel_type), Some(target)) = (
+                xml_attr(rel, "Id"),
+                xml_attr(rel, "Type"),
+                xml_attr(rel, "Target"),
+            ) else {
+                continue;
+            };
+            let new_id = format!("rIdM{}_{}", index, id);
+            let external = xml_attr(rel, "TargetMode").as_deref() == Some("External");
+
+            if rel_type.ends_with("/hyperlink") {
+                let mode = if external {
+                    r#" TargetMode="External""#
+                } else {
+                    ""
+                };
+                new_rels.push_str(&format!(
+                    r#"<Relationship Id="{}" Type="{}" Target="{}"{}/>"#,
+                    new_id,
+                    rel_type,
+                    escape_attr(&target),
+                    mode
+                ));
+            } else if rel_type.ends_with("/image") && !external {
+                let source = match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("word/{}", target),
+                };
+                let Some(data) = other.get(&source) else {
+                    continue;
+                };
+                let file_name = source.rsplit('/').next().unwrap_or(&source);
+                let new_target = format!("media/m{}_{}", index, file_name);
+                self.set(&format!("word/{}", new_target), data.to_vec());
+                if let Some(ext) = file_name.rsplit_once('.').map(|(_, e)| e) {
+                    self.ensure_default_content_type(ext);
+                }
+                new_rels.push_str(&format!(
+                    r#"<Relationship Id="{}" Type="{}" Target="{}"/>"#,
+                    new_id, rel_type, new_target
+                ));
+            } else {
+                continue;
+            }
+            id_map.insert(id, new_id);
+        }
+
+        if let Some(pos) = base_rels.rfind("</Relationships>") {
+            base_rels.insert_str(pos, &new_rels);
+            self.set_text(RELS, base_rels);
+        }
+
+        REL_ID_ATTR
+            .replace_all(body, |caps: &Captures| match id_map.get(&caps[2]) {
+                Some(new_id) => format!(r#"{}="{}""#, &caps[1], new_id),
+                None => caps[0].to_string(),
+            })
+            .into_owned()
+    }
+
+    /// Append list definitions with offset ids, returning the re-mapped body
+    fn merge_numbering(&mut self, other: &Package, body: &str) -> String {
+        const PART: &str = "word/numbering.xml";
+        let Some(other_numbering) = other.text(PART) else {
+            return body.to_string();
+        };
+        let Some(mut base_numbering) = self.text(PART) else {
+            self.set_text(PART, other_numbering);
+            self.ensure_part_registered("numbering.xml", REL_NUMBERING, CT_NUMBERING);
+            return body.to_string();
+        };
+
+        let abstract_offset = id_offset(
+            &base_numbering,
+            &other_numbering,
+            "w:abstractNum",
+            "w:abstractNumId",
+            &[],
+        );
+        // numId 0 means "no numbering" and is never defined
+        let num_offset = id_offset(&base_numbering, &other_numbering, "w:num", "w:numId", &[0]);
+
+        let abstracts: String = ABSTRACT_NUM
+            .find_iter(&other_numbering)
+            .map(|m| {
+                let xml = NSID.replace_all(m.as_str(), "");
+                offset_attr(
+                    &xml,
+                    "w:abstractNum",
+                    "w:abstractNumId",
+                    abstract_offset,
+                    &[],
+                )
+            })
+            .collect();
+        let nums: String = NUM
+            .find_iter(&other_numbering)
+            .map(|m| {
+                let xml = offset_attr(m.as_str(), "w:num", "w:numId", num_offset, &[0]);
+                offset_attr(&xml, "w:abstractNumId", "w:val", abstract_offset, &[])
+            })
+            .collect();
+
+        // Schema order: all abstractNum elements precede all num elements
+        let abstract_pos = base_numbering
+            .find("<w:num ")
+            .or_else(|| base_numbering.rfind("</w:numbering>"));
+        if let Some(pos) = abstract_pos {
+            base_numbering.insert_str(pos, &abstracts);
+        }
+        if let Some(pos) = base_numbering.rfind("</w:numbering>") {
+            base_numbering.insert_str(pos, &nums);
+        }
+        self.set_text(PART, base_numbering);
+
+        offset_attr(body, "w:numId", "w:val", num_offset, &[0])
+    }
+
+    /// Append footnotes or endnotes with offset ids, returning the re-mapped body
+    fn merge_notes(
+        &mut self,
+        other: &Package,
+        body: &str,
+        notes: Notes,
+        styles: &HashMap<String, String>,
+    ) -> String {
+        let part = format!("word/{}", notes.file);
+        let Some(other_xml) = other.text(&part) else {
+            return body.to_string();
+        };
+        let other_xml = rename_styles(&other_xml, styles);
+        let Some(mut base_xml) = self.text(&part) else {
+            self.set_text(&part, other_xml);
+            self.ensure_part_registered(notes.file, notes.rel_type, notes.content_type);
+            return body.to_string();
+        };
+
+        // Ids -1 and 0 belong to the separator notes
+        let offset = id_offset(&base_xml, &other_xml, notes.element, "w:id", &[0]);
+        let pattern =
+            Regex::new(&format!(r"(?s)<{0}\s.*?</{0}>", notes.element)).expect("Invalid regex");
+        let appended: String = pattern
+            .find_iter(&other_xml)
+            .map(|m| m.as_str())
+            // Separators are shared with the base document
+            .filter(|xml| !xml[..xml.find('>').unwrap_or(0)].contains("w:type="))
+            .map(|xml| offset_attr(xml, notes.element, "w:id", offset, &[0]))
+            .collect();
+
+        let closing = format!("</{}s>", notes.element);
+        if let Some(pos) = base_xml.rfind(&closing) {
+            base_xml.insert_str(pos, &appended);
+            self.set_text(&part, base_xml);
+        }
+
+        offset_attr(body, notes.reference, "w:id", offset, &[0])
+    }
+
+    /// Copy styles of `other` into this package
+    ///
+    /// Styles the base lacks are copied as they are. A style whose id exists
+    /// in the base with a different definition keeps the base definition,
+    /// unless `index` is set: then it is copied under a new id, and the
+    /// returned map (old id -> new id) is used to re-point references.
+    pub(super) fn merge_styles(
+        &mut self,
+        other: &Package,
+        index: Option<usize>,
+    ) -> HashMap<String, String> {
+        const PART: &str = "word/styles.xml";
+        let (Some(mut base_styles), Some(other_styles)) = (self.text(PART), other.text(PART))
+        else {
+            return HashMap::new();
+        };
+
+        let existing: HashMap<String, &str> = STYLE
+            .find_iter(&base_styles)
+            .filter_map(|m| {
+                let id = STYLE_ID.captures(m.as_str())?[1].to_string();
+                Some((id, m.as_str()))
+            })
+            .collect();
+
+        let mut renamed = HashMap::new();
+        let mut copied = Vec::new();
+        for style in STYLE.find_iter(&other_styles).map(|m| m.as_str()) {
+            let Some(id) = STYLE_ID.captures(style).map(|c| c[1].to_string()) else {
+                continue;
+            };
+            match existing.get(&id) {
+                None => copied.push(style.to_string()),
+                Some(base) if *base == style => {}
+                Some(_) => {
+                    let Some(index) = index else {
+                        continue;
+                    };
+                    let new_id = format!("{}_m{}", id, index);
+                    // A second default style of the same type would be ambiguous
+                    let style = STYLE_DEFAULT.replace(style, "");
+                    let style = STYLE_NAME.replace(&style, |caps: &Captures| {
+                        format!("{}{}_m{}\"", &caps[1], &caps[2], index)
+                    });
+                    copied.push(style.into_owned());
+                    renamed.insert(id, new_id);
+                }
+            }
+        }
+
+        // Copied styles may be based on or linked to renamed ones
+        let missing: String = copied
+            .iter()
+            .map(|style| {
+                let style = STYLE_ID.replace(style, |caps: &Captures| {
+                    let id = renamed.get(&caps[1]).map_or(&caps[1], String::as_str);
+                    format!("w:styleId=\"{}\"", id)
+                });
+                rename_styles(&style, &renamed)
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            if let Some(pos) = base_styles.rfind("</w:styles>") {
+                base_styles.insert_str(pos, &missing);
+                self.set_text(PART, base_styles);
+            }
+        }
+        renamed
+    }
+
+    /// Register a part in the document relationships and content types
+    fn ensure_part_registered(&mut self, file: &str, rel_type: &str, content_type: &str) {
+        const RELS: &str = "word/_rels/document.xml.rels";
+        if let Some(mut rels) = self.text(RELS) {
+            if !rels.contains(rel_type) {
+                if let Some(pos) = rels.rfind("</Relationships>") {
+                    rels.insert_str(
+                        pos,
+                        &format!(
+                            r#"<Relationship Id="rIdM{}" Type="{}" Target="{}"/>"#,
+                            file.trim_end_matches(".xml"),
+                            rel_type,
+                            file
+                        ),
+                    );
+                    self.set_text(RELS, rels);
+                }
+            }
+        }
+
+        self.update_content_types(&format!(r#"PartName="/word/{}""#, file), || {
+            format!(
+                r#"<Override PartName="/word/{}" ContentType="{}"/>"#,
+                file, content_type
+            )
+        });
+    }
+
+    /// Make sure media with this extension has a default content type
+    pub(super) fn ensure_default_content_type(&mut self, ext: &str) {
+        let ext = ext.to_ascii_lowercase();
+        let content_type = match ext.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "bmp" => "image/bmp",
+            "svg" => "image/svg+xml",
+            "emf" => "image/x-emf",
+            "wmf" => "image/x-wmf",
+            "tif" | "tiff" => "image/tiff",
+            _ => "application/octet-stream",
+        };
+        self.update_content_types(&format!(r#"Extension="{}""#, ext), || {
+            format!(
+                r#"<Default Extension="{}" ContentType="{}"/>"#,
+                ext, content_type
+            )
+        });
+    }
+
+    pub(super) fn update_content_types(&mut self, marker: &str, entry: impl FnOnce() -> String) {
+        const PART: &str = "[Content_Types].xml";
+        if let Some(mut types) = self.text(PART) {
+            if !types.contains(marker) {
+                if let Some(pos) = types.rfind("</Types>") {
+                    types.insert_str(pos, &entry());
+                    self.set_text(PART, types);
+                }
+            }
+        }
+    }
+}
+
+/// Element and part names for footnotes / endnotes
+#[derive(Clone, Copy)]
+struct Notes {
+    file: &'static str,
+    element: &'static str,
+    reference: &'static str,
+    rel_type: &'static str,
+    content_type: &'static str,
+}
+
+impl Notes {
+    const FOOTNOTES: Notes = Notes {
+        file: "footnotes.xml",
+        element: "w:footnote",
+        reference: "w:footnoteReference",
+        rel_type: REL_FOOTNOTES,
+        content_type: CT_FOOTNOTES,
+    };
+    const ENDNOTES: Notes = Notes {
+        file: "endnotes.xml",
+        element: "w:endnote",
+        reference: "w:endnoteReference",
+        rel_type: REL_ENDNOTES,
+        content_type: CT_ENDNOTES,
+    };
+}
+
+/// Inner content of `<w:body>` without the final section properties
+fn body_content(document: &str) -> Option<&str> {
+    let open = document.find("<w:body>")? + "<w:body>".len();
+    let close = document.rfind("</w:body>")?;
+    let inner = &document[open..close];
+    match final_sect_pr(inner) {
+        Some(pos) => Some(&inner[..pos]),
+        None => Some(inner),
+    }
+}
+
+/// Byte offset of a trailing `<w:sectPr>` that closes the body, if any
+fn final_sect_pr(xml: &str) -> Option<usize> {
+    let pos = xml.rfind("<w:sectPr")?;
+    let after = &xml[pos..];
+    let end = after.find("</w:sectPr>").map(|e| e + "</w:sectPr>".len())?;
+    let rest = after[end..].trim();
+    (rest.is_empty() || rest == "</w:body>" || rest.starts_with("</w:body>")).then_some(pos)
+}
+
+/// Point style references at renamed styles
+fn rename_styles(xml: &str, renamed: &HashMap<String, String>) -> String {
+    if renamed.is_empty() {
+        return xml.to_string();
+    }
+    STYLE_REF
+        .replace_all(xml, |caps: &Captures| match renamed.get(&caps[2]) {
+            Some(new_id) => format!("{}{}\"", &caps[1], new_id),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Give appended bookmarks unique ids and rename colliding names
+fn remap_bookmarks(base: &str, body: &str, index: usize) -> String {
+    let offset = id_offset(base, body, "w:bookmarkStart", "w:id", &[]);
+    let mut body = offset_attr(body, "w:bookmarkStart", "w:id", offset, &[]);
+    body = offset_attr(&body, "w:bookmarkEnd", "w:id", offset, &[]);
+
+    let base_names: HashSet<&str> = BOOKMARK_NAME
+        .captures_iter(base)
+        .filter_map(|c| c.get(1).map(|m| m.as_str()))
+        .collect();
+    let collisions: Vec<String> = BOOKMARK_NAME
+        .captures_iter(&body)
+        .map(|c| c[1].to_string())
+        .filter(|name| base_names.contains(name.as_str()))
+        .collect();
+
+    for name in collisions {
+        let renamed = format!("{}_m{}", name, index);
+        let escaped = regex::escape(&name);
+        let pattern = Regex::new(&format!(
+            r#"(w:name="|w:anchor="|PAGEREF |REF ){}(["\s])"#,
+            escaped
+        ))
+        .expect("Invalid regex");
+        body = pattern
+            .replace_all(&body, |caps: &Captures| {
+                format!("{}{}{}", &caps[1], renamed, &caps[2])
+            })
+            .into_owned();
+    }
+    body
+}
+
+/// Shift that moves the ids of `appended` past the largest id in `base`
+///
+/// Only non-negative values outside `reserved` take part; 0 when either
+/// side has no such ids.
+fn id_offset(base: &str, appended: &str, element: &str, attr: &str, reserved: &[i64]) -> i64 {
+    let ids = |xml: &str| -> Vec<i64> {
+        attr_pattern(element, attr)
+            .captures_iter(xml)
+            .filter_map(|c| c[2].parse::<i64>().ok())
+            .filter(|value| *value >= 0 && !reserved.contains(value))
+            .collect()
+    };
+    match (ids(base).into_iter().max(), ids(appended).into_iter().min()) {
+        (Some(max), Some(min)) => (max + 1 - min).max(0),
+        _ => 0,
+    }
+}
+
+/// Add `offset` to every non-negative numeric `attr` on `element`
+///
+/// Negative values and those in `reserved` are left alone (e.g. `numId` 0
+/// removes numbering, footnote ids -1/0 are the separators).
+fn offset_attr(xml: &str, element: &str, attr: &str, offset: i64, reserved: &[i64]) -> String {
+    if offset == 0 {
+        return xml.to_string();
+    }
+    attr_pattern(element, attr)
+        .replace_all(xml, |caps: &Captures| match caps[2].parse::<i64>() {
+            Ok(value) if value >= 0 && !reserved.contains(&value) => {
+                format!("{}{}\"", &caps[1], value + offset)
+            }
+            _ => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+fn attr_pattern(element: &str, attr: &str) -> Regex {
+    Regex::new(&format!(
+        r#"(<{}\b[^>]*?\s{}=")(-?\d+)""#,
+        regex::escape(element),
+        regex::escape(attr)
+    ))
+    .expect("Invalid regex")
+}
+
+/// Read an attribute from a single XML start tag
+pub(super) fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let needle = format!(" {}=\"", name);
+    let start = tag.find(&needle)? + needle.len();
+    let end = tag[start..].find('"')? + start;
+    Some(
+        tag[start..end]
+            .replace("&quot;", "\"")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown_to_docx;
+
+    fn part(docx: &[u8], name: &str) -> String {
+        Package::read(docx).unwrap().text(name).unwrap()
+    }
+
+    #[test]
+    fn test_merge_concatenates_bodies() {
+        let a = markdown_to_docx("# First\n\nAlpha text.").unwrap();
+        let b = markdown_to_docx("# Second\n\nBeta text.").unwrap();
+
+        let sections = |docx: &[u8]| part(docx, "word/document.xml").matches("<w:sectPr").count();
+        let expected_sections = sections(&a) + sections(&b) - 1;
+
+        let merged = merge_docx(&[a, b]).unwrap();
+        let doc = part(&merged, "word/document.xml");
+
+        let alpha = doc.find("Alpha text.").unwrap();
+        let beta = doc.find("Beta text.").unwrap();
+        assert!(alpha < beta);
+        assert!(doc.contains(r#"<w:br w:type="page"/>"#));
+        // The appended document's final section is dropped
+        assert_eq!(doc.matches("<w:sectPr").count(), expected_sections);
+    }
+
+    #[test]
+    fn test_merge_remaps_numbering_and_footnotes() {
+        let a = markdown_to_docx("- a\n- b\n\nNote[^1].\n\n[^1]: First note.").unwrap();
+        let b = markdown_to_docx("1. x\n2. y\n\nNote[^1].\n\n[^1]: Second note.").unwrap();
+
+        let merged = merge_docx(&[a.clone(), b]).unwrap();
+
+        let numbering = part(&merged, "word/numbering.xml");
+        let base_nums = part(&a, "word/numbering.xml").matches("<w:num ").count();
+        assert!(numbering.matches("<w:num ").count() > base_nums);
+        let num_ids: Vec<i64> = attr_pattern("w:num", "w:numId")
+            .captures_iter(&numbering)
+            .map(|c| c[2].parse().unwrap())
+            .collect();
+        let unique: HashSet<_> = num_ids.iter().collect();
+        assert_eq!(unique.len(), num_ids.len());
+
+        let footnotes = part(&merged, "word/footnotes.xml");
+        assert!(footnotes.contains("First note."));
+        assert!(footnotes.contains("Second note."));
+        let doc = part(&merged, "word/document.xml");
+        assert!(doc.contains(r#"<w:footnoteReference w:id="2"/>"#));
+    }
+
+    #[test]
+    fn test_remap_bookmarks_renames_collisions() {
+        let base = r#"<w:bookmarkStart w:id="3" w:name="_Toc1_Intro"/>"#;
+        let body = r#"<w:hyperlink w:anchor="_Toc1_Intro"/><w:bookmarkStart w:id="1" w:name="_Toc1_Intro"/><w:bookmarkEnd w:id="1"/>"#;
+        let result = remap_bookmarks(base, body, 2);
+        assert!(result.contains(r#"w:anchor="_Toc1_Intro_m2""#));
+        assert!(result.contains(r#"<w:bookmarkStart w:id="4" w:name="_Toc1_Intro_m2"/>"#));
+        assert!(result.contains(r#"<w:bookmarkEnd w:id="4"/>"#));
+    }
+
+    #[test]
+    fn test_offset_attr_shifts_zero_ids() {
+        let base = r#"<w:bookmarkStart w:id="0" w:name="a"/><w:bookmarkEnd w:id="0"/>"#;
+        let body = r#"<w:bookmarkStart w:id="0" w:name="b"/><w:bookmarkEnd w:id="0"/>"#;
+        let result = remap_bookmarks(base, body, 1);
+        assert!(result.contains(r#"<w:bookmarkStart w:id="1" w:name="b"/>"#));
+        assert!(result.contains(r#"<w:bookmarkEnd w:id="1"/>"#));
+
+        let refs = r#"<w:numId w:val="0"/><w:numId w:val="2"/>"#;
+        assert_eq!(
+            offset_attr(refs, "w:numId", "w:val", 5, &[0]),
+            r#"<w:numId w:val="0"/><w:numId w:val="7"/>"#
+        );
+    }
+
+    #[test]
+    fn test_merge_styles_renames_collisions() {
+        let styles = |body: &str| Package {
+            parts: vec![(
+                "word/styles.xml".to_string(),
+                format!("<w:styles>{}</w:styles>", body).into_bytes(),
+            )],
+        };
+        let same =
+            r#"<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/></w:style>"#;
+        let mut base = styles(&format!(
+            r#"{}<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:rPr><w:sz w:val="22"/></w:rPr></w:style>"#,
+            same
+        ));
+        let other = styles(&format!(
+            r#"{}<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:rPr><w:sz w:val="32"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="Note"><w:name w:val="Note"/><w:basedOn w:val="Normal"/></w:style>"#,
+            same
+        ));
+
+        let renamed = base.merge_styles(&other, Some(1));
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed["Normal"], "Normal_m1");
+
+        let merged = base.text("word/styles.xml").unwrap();
+        assert_eq!(merged.matches(r#"w:styleId="Quote""#).count(), 1);
+        assert!(merged.contains(
+            r#"<w:style w:type="paragraph" w:styleId="Normal_m1"><w:name w:val="Normal_m1"/><w:rPr><w:sz w:val="32"/>"#
+        ));
+        assert!(merged.contains(r#"<w:basedOn w:val="Normal_m1"/>"#));
+
+        let body =
+            r#"<w:pPr><w:pStyle w:val="Normal"/></w:pPr><w:pPr><w:pStyle w:val="Quote"/></w:pPr>"#;
+        assert_eq!(
+            rename_styles(body, &renamed),
+            r#"<w:pPr><w:pStyle w:val="Normal_m1"/></w:pPr><w:pPr><w:pStyle w:val="Quote"/></w:pPr>"#
+        );
+    }
+
+    #[test]
+    fn test_merge_requires_documents() {
+        assert!(merge_docx(&[]).is_err());
+    }
+}
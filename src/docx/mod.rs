@@ -1,14 +1,32 @@
 pub(crate) mod builder;
+#[cfg(feature = "parallel")]
+pub(crate) mod chapters;
+#[cfg(feature = "parallel")]
+pub(crate) mod prerender;
 pub mod font_embed;
 pub(crate) mod highlight;
 pub mod image_utils;
 pub(crate) mod math;
 pub(crate) mod math_rex;
+pub(crate) mod merge;
 pub(crate) mod ooxml;
+pub(crate) mod optimize;
+pub(crate) mod package;
 pub(crate) mod packager;
 pub(crate) mod rels_manager;
+pub(crate) mod restyle;
+pub(crate) mod spill;
 pub(crate) mod toc;
+pub(crate) mod validate;
 pub(crate) mod xref;
 
-pub use builder::{parse_length_to_twips, DocumentConfig, DocumentMeta, PageConfig};
-pub use ooxml::{FontConfig, Language, Paragraph, Run};
+pub use builder::{parse_length_to_twips, DocumentConfig, DocumentMeta, PageConfig, Spacers};
+pub use merge::merge_docx;
+pub use optimize::{optimize_images, ImageLimits, OptimizeOptions, OptimizeStats};
+pub use packager::Compression;
+pub use rels_manager::RelIdManager;
+pub use restyle::restyle_docx;
+pub use spill::MemoryBudget;
+pub use ooxml::{FontConfig, Language, Paragraph, ParagraphPreset, Run, StyleSpacing};
+pub use ooxml::xml_writer::XmlWriter;
+pub use validate::{validate_docx, ValidationCategory, ValidationIssue};
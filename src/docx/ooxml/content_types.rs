@@ -97,6 +97,12 @@ impl ContentTypes {
         ));
     }
 
+    /// Order extensions and overrides by name instead of insertion
+    pub fn sort(&mut self) {
+        self.extensions.sort();
+        self.overrides.sort();
+    }
+
     /// Generate XML content
     pub fn to_xml(&self) -> Result<Vec<u8>> {
         let mut writer = Writer::new(Cursor::new(Vec::new()));
@@ -57,32 +57,14 @@ impl CoreProperties {
     }
 
     /// Get current time in ISO 8601 format (W3CDTF)
-    fn current_iso_time() -> String {
-        // Use a fixed format that's compatible with Word
-        // In production, this would use the system time
-        // For now, use a reasonable default
+    pub(crate) fn current_iso_time() -> String {
         #[cfg(not(target_arch = "wasm32"))]
         {
             use std::time::SystemTime;
             let now = SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .unwrap_or_default();
-            // Convert to ISO 8601 format manually (simplified)
-            let secs = now.as_secs();
-            // Calculate date components (simplified - assumes UTC)
-            let days = secs / 86400;
-            let years_since_1970 = days / 365;
-            let year = 1970 + years_since_1970;
-            let day_of_year = days % 365;
-            let month = (day_of_year / 30).min(11) + 1;
-            let day = (day_of_year % 30) + 1;
-            let hour = (secs % 86400) / 3600;
-            let minute = (secs % 3600) / 60;
-            let second = secs % 60;
-            format!(
-                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
-                year, month, day, hour, minute, second
-            )
+            iso_time(now.as_secs())
         }
         #[cfg(target_arch = "wasm32")]
         {
@@ -91,6 +73,16 @@ impl CoreProperties {
         }
     }
 
+    /// Replace the build time with a fixed date, for reproducible output
+    ///
+    /// Uses 1980-01-01, the earliest time a ZIP entry can carry, so the
+    /// package and its metadata agree.
+    pub fn fix_timestamps(&mut self) {
+        let epoch = "1980-01-01T00:00:00Z".to_string();
+        self.created = Some(epoch.clone());
+        self.modified = Some(epoch);
+    }
+
     /// Set document title
     #[allow(dead_code)]
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
@@ -392,6 +384,41 @@ impl AppProperties {
     }
 }
 
+/// Format seconds since the Unix epoch as a UTC W3CDTF timestamp
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn iso_time(secs: u64) -> String {
+    let (year, month, day) = civil_from_days(secs / 86400);
+    let hour = (secs % 86400) / 3600;
+    let minute = (secs % 3600) / 60;
+    let second = secs % 60;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Gregorian (year, month, day) of a day count since 1970-01-01
+///
+/// Counts in 400-year eras starting on March 1st, so leap days fall at
+/// the end of each year.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -409,6 +436,16 @@ mod tests {
         assert!(xml_str.contains("dcterms:modified"));
     }
 
+    #[test]
+    fn test_iso_time_is_a_calendar_date() {
+        assert_eq!(iso_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso_time(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(iso_time(1_709_251_199), "2024-02-29T23:59:59Z");
+        assert_eq!(iso_time(1_709_251_200), "2024-03-01T00:00:00Z");
+        assert_eq!(iso_time(1_792_238_400), "2026-10-17T12:00:00Z");
+        assert_eq!(iso_time(1_798_761_599), "2026-12-31T23:59:59Z");
+    }
+
     #[test]
     fn test_core_properties_with_title() {
         let core = CoreProperties::new().with_title("My Document");
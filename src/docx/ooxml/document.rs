@@ -1,8 +1,13 @@
 //! Generate word/document.xml for DOCX
 
-use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use std::io::Cursor;
+use std::sync::Arc;
+
+use super::footnotes::NoteNumbering;
+use super::styles::StyleSpacing;
+use super::xml_writer::{fragment, XmlWriter};
 
 use crate::error::Result;
 use crate::i18n::detection::{contains_thai, detect_language};
@@ -36,7 +41,7 @@ pub struct Run {
     pub underline: bool,
     pub strike: bool,
     pub style: Option<String>,     // Character style
-    pub font: Option<String>,      // Specific font override
+    pub font: Option<Arc<str>>,    // Specific font override, shared between runs
     pub size: Option<u32>,         // Size in half-points
     pub color: Option<String>,     // Hex color (without #)
     pub highlight: Option<String>, // Highlight color
@@ -47,15 +52,18 @@ pub struct Run {
     pub field_char: Option<String>, // Field character type: "begin", "separate", "end"
     pub instr_text: bool,         // If true, this is instruction text for a field
     pub tab: bool,                // If true, this run contains a tab character
-    pub lang: Option<String>,     // Language for spell-check (auto-detected from text)
+    pub lang: Option<&'static str>, // Language for spell-check (auto-detected from text)
+    pub rtl: bool,                // Right-to-left run (Arabic/Hebrew, auto-detected from text)
     pub break_type: Option<String>, // "page", "column", "textWrapping"
+    pub(crate) revision: Option<Revision>, // Tracked insertion or deletion
 }
 
 impl Run {
     pub fn new(text: impl Into<String>) -> Self {
         let text_str = text.into();
         // Auto-detect language from text content for proper spell-checking
-        let lang = Some(detect_language(&text_str).to_string());
+        let detected = detect_language(&text_str);
+        let rtl = crate::Language::from_code(detected).is_some_and(|l| l.is_rtl());
         Self {
             text: text_str,
             bold: false,
@@ -74,8 +82,10 @@ impl Run {
             field_char: None,
             instr_text: false,
             tab: false,
-            lang,
+            lang: Some(detected),
+            rtl,
             break_type: None,
+            revision: None,
         }
     }
 
@@ -111,7 +121,7 @@ impl Run {
 
     /// Set font override
     pub fn font(mut self, font: &str) -> Self {
-        self.font = Some(font.to_string());
+        self.font = Some(font.into());
         self
     }
 
@@ -163,12 +173,29 @@ impl Run {
         self
     }
 
+    /// Set right-to-left direction for this run
+    pub fn rtl(mut self, rtl: bool) -> Self {
+        self.rtl = rtl;
+        self
+    }
+
     /// Write run XML to a writer
     pub fn write_xml<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        if let Some(revision) = &self.revision {
+            writer.write_event(Event::Start(revision.to_element()))?;
+        }
+        // Deleted runs keep their text in w:delText / w:delInstrText
+        let deleted = self
+            .revision
+            .as_ref()
+            .is_some_and(|r| r.kind == RevisionKind::Delete);
+
         writer.write_event(Event::Start(BytesStart::new("w:r")))?;
 
-        // Detect if text contains Thai characters for Complex Script handling
-        let is_complex_script = contains_thai(&self.text);
+        // Complex Script handling for Thai text and complex-script language runs
+        let primary_lang = self.lang.unwrap_or("en-US");
+        let run_lang = crate::Language::from_code(primary_lang).unwrap_or_default();
+        let is_complex_script = contains_thai(&self.text) || run_lang.is_complex_script();
 
         // Run properties
         if self.bold
@@ -182,6 +209,7 @@ impl Run {
             || self.highlight.is_some()
             || self.superscript
             || self.footnote_id.is_some()
+            || self.rtl
             || is_complex_script
         {
             writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
@@ -210,9 +238,9 @@ impl Run {
             // 2. Font
             if let Some(font) = &self.font {
                 let mut fonts = BytesStart::new("w:rFonts");
-                fonts.push_attribute(("w:ascii", font.as_str()));
-                fonts.push_attribute(("w:hAnsi", font.as_str()));
-                fonts.push_attribute(("w:cs", font.as_str()));
+                fonts.push_attribute(("w:ascii", &**font));
+                fonts.push_attribute(("w:hAnsi", &**font));
+                fonts.push_attribute(("w:cs", &**font));
                 if is_complex_script {
                     fonts.push_attribute(("w:hint", "cs"));
                 }
@@ -289,19 +317,19 @@ impl Run {
                 writer.write_event(Event::Empty(va))?;
             }
 
+            // 10c. Right-to-left text direction
+            if self.rtl {
+                writer.write_event(Event::Empty(BytesStart::new("w:rtl")))?;
+            }
+
             // 11. Language setting - use auto-detected language for proper spell-checking
             let mut lang_elem = BytesStart::new("w:lang");
-            let primary_lang = self.lang.as_deref().unwrap_or("en-US");
             lang_elem.push_attribute(("w:val", primary_lang));
-            // For Thai text, set eastAsia and bidi to Thai; for others, keep Thai as fallback
-            if primary_lang == "th-TH" {
-                lang_elem.push_attribute(("w:eastAsia", "th-TH"));
-                lang_elem.push_attribute(("w:bidi", "th-TH"));
-            } else {
-                // Keep Thai as eastAsia/bidi for mixed content support
-                lang_elem.push_attribute(("w:eastAsia", "th-TH"));
-                lang_elem.push_attribute(("w:bidi", "th-TH"));
-            }
+            // Complex-script text (Thai, Lao, Khmer, Myanmar, Arabic, Hebrew) sets bidi and
+            // CJK text sets eastAsia to its language; others keep Thai as fallback for mixed
+            // content
+            lang_elem.push_attribute(("w:eastAsia", run_lang.east_asia_lang_tag()));
+            lang_elem.push_attribute(("w:bidi", run_lang.cs_lang_tag()));
             writer.write_event(Event::Empty(lang_elem))?;
 
             // 12. Ligatures (Thai ligature support)
@@ -321,11 +349,16 @@ impl Run {
 
         // Instruction text (for field codes)
         if self.instr_text && !self.text.is_empty() {
-            let mut it = BytesStart::new("w:instrText");
+            let name = if deleted {
+                "w:delInstrText"
+            } else {
+                "w:instrText"
+            };
+            let mut it = BytesStart::new(name);
             it.push_attribute(("xml:space", "preserve"));
             writer.write_event(Event::Start(it))?;
             writer.write_event(Event::Text(BytesText::new(&self.text)))?;
-            writer.write_event(Event::End(BytesEnd::new("w:instrText")))?;
+            writer.write_event(Event::End(BytesEnd::new(name)))?;
         }
 
         // Tab character
@@ -356,16 +389,20 @@ impl Run {
 
         // Text (only if not instruction text and not empty)
         if !self.instr_text && !self.text.is_empty() {
-            let mut t = BytesStart::new("w:t");
+            let name = if deleted { "w:delText" } else { "w:t" };
+            let mut t = BytesStart::new(name);
             if self.preserve_space {
                 t.push_attribute(("xml:space", "preserve"));
             }
             writer.write_event(Event::Start(t))?;
             writer.write_event(Event::Text(BytesText::new(&self.text)))?;
-            writer.write_event(Event::End(BytesEnd::new("w:t")))?;
+            writer.write_event(Event::End(BytesEnd::new(name)))?;
         }
 
         writer.write_event(Event::End(BytesEnd::new("w:r")))?;
+        if let Some(revision) = &self.revision {
+            writer.write_event(Event::End(BytesEnd::new(revision.name())))?;
+        }
         Ok(())
     }
 }
@@ -376,6 +413,42 @@ impl Default for Run {
     }
 }
 
+/// Kind of tracked change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RevisionKind {
+    Insert,
+    Delete,
+}
+
+/// Tracked change on a run or paragraph mark, shown by Word as a redline
+#[derive(Debug, Clone)]
+pub(crate) struct Revision {
+    pub kind: RevisionKind,
+    pub id: u32,                // Unique among the document's revisions
+    pub author: &'static str,   // Reviewer name shown in Word
+    pub date: Option<Arc<str>>, // ISO 8601 timestamp, omitted when unknown
+}
+
+impl Revision {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            RevisionKind::Insert => "w:ins",
+            RevisionKind::Delete => "w:del",
+        }
+    }
+
+    /// `w:ins` or `w:del` with the revision attributes
+    fn to_element(&self) -> BytesStart<'static> {
+        let mut elem = BytesStart::new(self.name());
+        elem.push_attribute(("w:id", self.id.to_string().as_str()));
+        elem.push_attribute(("w:author", self.author));
+        if let Some(date) = &self.date {
+            elem.push_attribute(("w:date", date.as_ref()));
+        }
+        elem
+    }
+}
+
 /// Bookmark start element
 #[derive(Debug, Clone)]
 pub(crate) struct BookmarkStart {
@@ -386,14 +459,25 @@ pub(crate) struct BookmarkStart {
 /// Hyperlink element for paragraphs
 #[derive(Debug, Clone)]
 pub(crate) struct Hyperlink {
-    pub id: String,         // Relationship ID (rId...)
-    pub children: Vec<Run>, // Hyperlinks usually contain runs
+    pub id: String,             // Relationship ID (rId...)
+    pub anchor: Option<String>, // Bookmark inside the document, instead of a relationship
+    pub children: Vec<Run>,     // Hyperlinks usually contain runs
 }
 
 impl Hyperlink {
     pub fn new(id: impl Into<String>) -> Self {
         Self {
             id: id.into(),
+            anchor: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Link to a bookmark in the document (`[text](#install)`)
+    pub fn to_anchor(anchor: impl Into<String>) -> Self {
+        Self {
+            id: String::new(),
+            anchor: Some(anchor.into()),
             children: Vec::new(),
         }
     }
@@ -419,6 +503,9 @@ pub(crate) enum ParagraphChild {
     BookmarkEnd { id: u32 },
 }
 
+/// Section break types: where the section after the break starts
+pub const SECTION_BREAKS: [&str; 4] = ["nextPage", "oddPage", "evenPage", "continuous"];
+
 /// Paragraph with style and children (runs or hyperlinks)
 #[derive(Debug, Clone)]
 pub struct Paragraph {
@@ -434,10 +521,12 @@ pub struct Paragraph {
     pub line_rule: Option<String>,   // "auto", "exact", "atLeast"
     pub keep_with_next: bool,
     pub page_break_before: bool,
-    pub shading: Option<String>,       // Fill color (hex without #)
-    pub section_break: Option<String>, // "nextPage", "continuous", "evenPage", "oddPage"
-    pub page_num_start: Option<u32>,   // Page number to restart at for section break
-    pub suppress_header_footer: bool,  // Suppress header/footer references in sectPr
+    pub shading: Option<String>,         // Fill color (hex without #)
+    pub section_break: Option<String>,   // "nextPage", "continuous", "evenPage", "oddPage"
+    pub page_num_start: Option<u32>,     // Page number to restart at for section break
+    pub suppress_header_footer: bool,    // Suppress header/footer references in sectPr
+    pub section_rtl: bool,               // Right-to-left section with gutter on the right
+    pub page_num_format: Option<String>, // Page number format ("thaiNumbers", ...)
     pub(crate) empty_header_footer_refs: Option<HeaderFooterRefs>, // Empty header/footer refs to use when suppressing
     pub(crate) bookmark_start: Option<BookmarkStart>,              // Bookmark start element
     pub(crate) bookmark_end: bool, // If true, close bookmark after content
@@ -452,6 +541,8 @@ pub struct Paragraph {
     pub sect_margin_header: Option<u32>, // Header margin for sectPr
     pub sect_margin_footer: Option<u32>, // Footer margin for sectPr
     pub sect_margin_gutter: Option<u32>, // Gutter margin for sectPr
+    /// Tracked insertion or deletion of the paragraph mark
+    pub(crate) mark_revision: Option<Revision>,
 }
 
 impl Paragraph {
@@ -473,6 +564,8 @@ impl Paragraph {
             section_break: None,
             page_num_start: None,
             suppress_header_footer: false,
+            section_rtl: false,
+            page_num_format: None,
             empty_header_footer_refs: None,
             bookmark_start: None,
             bookmark_end: false,
@@ -486,6 +579,7 @@ impl Paragraph {
             sect_margin_header: None,
             sect_margin_footer: None,
             sect_margin_gutter: None,
+            mark_revision: None,
         }
     }
 
@@ -577,6 +671,17 @@ impl Paragraph {
         self
     }
 
+    /// Override spacing with the values set in a style's `[styles]` spacing
+    pub fn style_spacing(mut self, spacing: &StyleSpacing) -> Self {
+        self.spacing_before = spacing.before.or(self.spacing_before);
+        self.spacing_after = spacing.after.or(self.spacing_after);
+        if let Some(line) = spacing.line {
+            self.line = Some(line as i32);
+            self.line_rule = Some("auto".to_string());
+        }
+        self
+    }
+
     /// Keep paragraph with next paragraph
     pub fn keep_with_next(mut self) -> Self {
         self.keep_with_next = true;
@@ -678,6 +783,7 @@ impl Paragraph {
             || self.page_break_before
             || self.shading.is_some()
             || self.section_break.is_some()
+            || self.mark_revision.is_some()
         {
             writer.write_event(Event::Start(BytesStart::new("w:pPr")))?;
 
@@ -790,6 +896,10 @@ impl Paragraph {
 
             // 12. Paragraph-level run properties with ligatures
             writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
+            // A tracked paragraph mark comes first in the paragraph's rPr
+            if let Some(revision) = &self.mark_revision {
+                writer.write_event(Event::Empty(revision.to_element()))?;
+            }
             // Add ligatures for Thai support
             let mut ligatures = BytesStart::new("w14:ligatures");
             ligatures.push_attribute(("w14:val", "all"));
@@ -850,10 +960,15 @@ impl Paragraph {
                 type_elem.push_attribute(("w:val", break_type.as_str()));
                 writer.write_event(Event::Empty(type_elem))?;
 
-                // Page numbering restart
-                if let Some(start) = self.page_num_start {
+                // Page numbering format and restart
+                if self.page_num_start.is_some() || self.page_num_format.is_some() {
                     let mut pg_num_type = BytesStart::new("w:pgNumType");
-                    pg_num_type.push_attribute(("w:start", start.to_string().as_str()));
+                    if let Some(format) = &self.page_num_format {
+                        pg_num_type.push_attribute(("w:fmt", format.as_str()));
+                    }
+                    if let Some(start) = self.page_num_start {
+                        pg_num_type.push_attribute(("w:start", start.to_string().as_str()));
+                    }
                     writer.write_event(Event::Empty(pg_num_type))?;
                 }
 
@@ -915,6 +1030,12 @@ impl Paragraph {
                     }
                 }
 
+                // Right-to-left section layout with mirrored gutter
+                if self.section_rtl {
+                    writer.write_event(Event::Empty(BytesStart::new("w:bidi")))?;
+                    writer.write_event(Event::Empty(BytesStart::new("w:rtlGutter")))?;
+                }
+
                 // Document grid (for Asian text)
                 let mut doc_grid = BytesStart::new("w:docGrid");
                 doc_grid.push_attribute(("w:linePitch", "360"));
@@ -941,9 +1062,15 @@ impl Paragraph {
                     run.write_xml(writer)?;
                 }
                 ParagraphChild::Hyperlink(hyperlink) => {
-                    // Write <w:hyperlink r:id="...">
+                    // Write <w:hyperlink r:id="..."> or <w:hyperlink w:anchor="...">
                     let mut link_elem = BytesStart::new("w:hyperlink");
-                    link_elem.push_attribute(("r:id", hyperlink.id.as_str()));
+                    match &hyperlink.anchor {
+                        Some(anchor) => {
+                            link_elem.push_attribute(("w:anchor", anchor.as_str()));
+                            link_elem.push_attribute(("w:history", "1"));
+                        }
+                        None => link_elem.push_attribute(("r:id", hyperlink.id.as_str())),
+                    }
                     writer.write_event(Event::Start(link_elem))?;
 
                     // Write hyperlink children (runs)
@@ -1363,6 +1490,8 @@ pub(crate) enum DocElement {
     /// Display math block (raw OMML paragraph XML)
     #[allow(dead_code)]
     MathBlock(String),
+    /// Body content already rendered to XML (bounded-memory builds)
+    Fragment(crate::docx::spill::Fragment),
 }
 
 /// Table width type
@@ -1384,6 +1513,7 @@ pub struct Table {
     pub(crate) width: TableWidth,
     pub borders: Option<BorderStyles>, // Template borders
     pub cell_margins: Option<CellMargins>,
+    pub bidi_visual: bool, // Lay out columns right-to-left
 }
 
 impl Table {
@@ -1395,6 +1525,7 @@ impl Table {
             width: TableWidth::Auto,
             borders: None,
             cell_margins: None,
+            bidi_visual: false,
         }
     }
 
@@ -1433,6 +1564,12 @@ impl Table {
         self.width = width;
         self
     }
+
+    /// Set right-to-left column ordering (first column on the right)
+    pub fn with_bidi_visual(mut self, rtl: bool) -> Self {
+        self.bidi_visual = rtl;
+        self
+    }
 }
 
 /// Table row
@@ -1440,6 +1577,7 @@ impl Table {
 pub(crate) struct TableRow {
     pub cells: Vec<TableCellElement>,
     pub is_header: bool,
+    pub cant_split: bool, // Keep the row on one page
 }
 
 /// Table cell
@@ -1457,6 +1595,7 @@ impl TableRow {
         Self {
             cells: Vec::new(),
             is_header: false,
+            cant_split: false,
         }
     }
 
@@ -1552,8 +1691,98 @@ pub(crate) struct DocumentXml {
     pub empty_header_id: Option<String>,      // ID for empty header
     pub empty_footer_id: Option<String>,      // ID for empty footer
     pub page_num_start: Option<u32>,          // Page number start for the final section
+    pub suppress_header_footer: bool,         // Final section uses the empty header/footer
+    pub rtl: bool,                            // Right-to-left sections with gutter on the right
+    pub page_num_format: Option<String>,      // Page number format for every section
+    pub final_page_num_format: Option<String>, // Page number format of the final section only
+    pub final_section_break: Option<String>,  // How the final section starts ("oddPage", ...)
+    pub notes: NoteNumbering,                 // Footnote/endnote numbering for settings.xml
 }
 
+/// Namespaces declared on `w:document`
+const DOCUMENT_NAMESPACES: [(&str, &str); 23] = [
+    (
+        "xmlns:w",
+        "http://schemas.openxmlformats.org/wordprocessingml/2006/main",
+    ),
+    (
+        "xmlns:m",
+        "http://schemas.openxmlformats.org/officeDocument/2006/math",
+    ),
+    (
+        "xmlns:r",
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships",
+    ),
+    (
+        "xmlns:wp",
+        "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing",
+    ),
+    (
+        "xmlns:wp14",
+        "http://schemas.microsoft.com/office/word/2010/wordprocessingDrawing",
+    ),
+    (
+        "xmlns:a",
+        "http://schemas.openxmlformats.org/drawingml/2006/main",
+    ),
+    (
+        "xmlns:pic",
+        "http://schemas.openxmlformats.org/drawingml/2006/picture",
+    ),
+    (
+        "xmlns:mc",
+        "http://schemas.openxmlformats.org/markup-compatibility/2006",
+    ),
+    (
+        "xmlns:w14",
+        "http://schemas.microsoft.com/office/word/2010/wordml",
+    ),
+    (
+        "xmlns:w15",
+        "http://schemas.microsoft.com/office/word/2012/wordml",
+    ),
+    (
+        "xmlns:w16",
+        "http://schemas.microsoft.com/office/word/2018/wordml",
+    ),
+    (
+        "xmlns:w16cex",
+        "http://schemas.microsoft.com/office/word/2018/wordml/cex",
+    ),
+    (
+        "xmlns:w16cid",
+        "http://schemas.microsoft.com/office/word/2016/wordml/cid",
+    ),
+    (
+        "xmlns:w16se",
+        "http://schemas.microsoft.com/office/word/2015/wordml/symex",
+    ),
+    (
+        "xmlns:wpc",
+        "http://schemas.microsoft.com/office/word/2010/wordprocessingCanvas",
+    ),
+    (
+        "xmlns:wpg",
+        "http://schemas.microsoft.com/office/word/2010/wordprocessingGroup",
+    ),
+    (
+        "xmlns:wpi",
+        "http://schemas.microsoft.com/office/word/2010/wordprocessingInk",
+    ),
+    (
+        "xmlns:wne",
+        "http://schemas.microsoft.com/office/word/2006/wordml",
+    ),
+    (
+        "xmlns:wps",
+        "http://schemas.microsoft.com/office/word/2010/wordprocessingShape",
+    ),
+    ("xmlns:o", "urn:schemas-microsoft-com:office:office"),
+    ("xmlns:v", "urn:schemas-microsoft-com:vml"),
+    ("xmlns:w10", "urn:schemas-microsoft-com:office:word"),
+    ("mc:Ignorable", "w14 w15 w16se w16cid w16 w16cex wp14"),
+];
+
 impl Default for DocumentXml {
     fn default() -> Self {
         Self::new()
@@ -1576,6 +1805,12 @@ impl DocumentXml {
             empty_header_id: None,
             empty_footer_id: None,
             page_num_start: None,
+            suppress_header_footer: false,
+            rtl: false,
+            page_num_format: None,
+            final_page_num_format: None,
+            final_section_break: None,
+            notes: NoteNumbering::default(),
         }
     }
 
@@ -1596,11 +1831,6 @@ impl DocumentXml {
         self.elements.push(DocElement::Image(image));
     }
 
-    /// Add a document element (paragraph, table, or image)
-    pub fn add_element(&mut self, element: DocElement) {
-        self.elements.push(element);
-    }
-
     /// Set page size (in twips)
     #[allow(dead_code)]
     pub fn page_size(mut self, width: u32, height: u32) -> Self {
@@ -1637,107 +1867,93 @@ impl DocumentXml {
     }
 
     /// Generate XML content for word/document.xml
+    #[allow(dead_code)]
     pub fn to_xml(&self) -> Result<Vec<u8>> {
-        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
-
-        // XML declaration
-        writer.write_event(Event::Decl(BytesDecl::new(
-            "1.0",
-            Some("UTF-8"),
-            Some("yes"),
-        )))?;
+        Ok(self.write_to(Cursor::new(Vec::new()))?.into_inner())
+    }
 
+    /// Write word/document.xml straight into `out` (e.g. a ZIP entry)
+    /// without building it in memory first
+    pub fn write_to<W: std::io::Write>(&self, out: W) -> Result<W> {
+        let mut xml = XmlWriter::indented(out);
+        xml.declaration()?;
         // Root element with all Word namespaces (including 2016+ extensions to prevent compatibility mode)
-        let mut doc = BytesStart::new("w:document");
-        doc.push_attribute((
-            "xmlns:w",
-            "http://schemas.openxmlformats.org/wordprocessingml/2006/main",
-        ));
-        doc.push_attribute((
-            "xmlns:m",
-            "http://schemas.openxmlformats.org/officeDocument/2006/math",
-        ));
-        doc.push_attribute((
-            "xmlns:r",
-            "http://schemas.openxmlformats.org/officeDocument/2006/relationships",
-        ));
-        doc.push_attribute((
-            "xmlns:wp",
-            "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing",
-        ));
-        doc.push_attribute((
-            "xmlns:wp14",
-            "http://schemas.microsoft.com/office/word/2010/wordprocessingDrawing",
-        ));
-        doc.push_attribute((
-            "xmlns:a",
-            "http://schemas.openxmlformats.org/drawingml/2006/main",
-        ));
-        doc.push_attribute((
-            "xmlns:pic",
-            "http://schemas.openxmlformats.org/drawingml/2006/picture",
-        ));
-        doc.push_attribute((
-            "xmlns:mc",
-            "http://schemas.openxmlformats.org/markup-compatibility/2006",
-        ));
-        doc.push_attribute((
-            "xmlns:w14",
-            "http://schemas.microsoft.com/office/word/2010/wordml",
-        ));
-        doc.push_attribute((
-            "xmlns:w15",
-            "http://schemas.microsoft.com/office/word/2012/wordml",
-        ));
-        doc.push_attribute((
-            "xmlns:w16",
-            "http://schemas.microsoft.com/office/word/2018/wordml",
-        ));
-        doc.push_attribute((
-            "xmlns:w16cex",
-            "http://schemas.microsoft.com/office/word/2018/wordml/cex",
-        ));
-        doc.push_attribute((
-            "xmlns:w16cid",
-            "http://schemas.microsoft.com/office/word/2016/wordml/cid",
-        ));
-        doc.push_attribute((
-            "xmlns:w16se",
-            "http://schemas.microsoft.com/office/word/2015/wordml/symex",
-        ));
-        doc.push_attribute((
-            "xmlns:wpc",
-            "http://schemas.microsoft.com/office/word/2010/wordprocessingCanvas",
-        ));
-        doc.push_attribute((
-            "xmlns:wpg",
-            "http://schemas.microsoft.com/office/word/2010/wordprocessingGroup",
-        ));
-        doc.push_attribute((
-            "xmlns:wpi",
-            "http://schemas.microsoft.com/office/word/2010/wordprocessingInk",
-        ));
-        doc.push_attribute((
-            "xmlns:wne",
-            "http://schemas.microsoft.com/office/word/2006/wordml",
-        ));
-        doc.push_attribute((
-            "xmlns:wps",
-            "http://schemas.microsoft.com/office/word/2010/wordprocessingShape",
-        ));
-        doc.push_attribute(("xmlns:o", "urn:schemas-microsoft-com:office:office"));
-        doc.push_attribute(("xmlns:v", "urn:schemas-microsoft-com:vml"));
-        doc.push_attribute(("xmlns:w10", "urn:schemas-microsoft-com:office:word"));
-        doc.push_attribute(("mc:Ignorable", "w14 w15 w16se w16cid w16 w16cex wp14"));
+        xml.start("w:document", &DOCUMENT_NAMESPACES)?;
+        xml.start("w:body", &[])?;
+
+        let writer = xml.inner();
+        self.write_elements(&self.elements, writer)?;
+
+        // Section properties (Page size/margins)
+        self.write_sect_pr(writer)?;
+
+        xml.end("w:body")?;
+        xml.end("w:document")?;
+        Ok(xml.into_inner())
+    }
+
+    /// Body content without the final section properties, for splicing
+    /// into another document (e.g. the `{{inside}}` slot of a cover)
+    pub(crate) fn body_xml(&self) -> Result<String> {
+        fragment(|w| self.write_elements(&self.elements, w.inner()))
+    }
+
+    /// Render the elements from `start` on into fragments held by `store`
+    ///
+    /// Section breaks and chapter headings stay as elements: their
+    /// properties are completed after the build (header references, page
+    /// numbering restarts), and the TOC and cover passes look for them.
+    pub(crate) fn render_from(
+        &mut self,
+        start: usize,
+        store: &mut crate::docx::spill::FragmentStore,
+    ) -> Result<()> {
+        let mut pending = Vec::new();
+        for element in self.elements.split_off(start) {
+            let keep = match &element {
+                DocElement::Paragraph(p) => {
+                    p.is_section_break() || p.style_id.as_deref() == Some("Heading1")
+                }
+                DocElement::Fragment(_) => true,
+                _ => false,
+            };
+            if keep {
+                self.push_fragment(&mut pending, store)?;
+                self.elements.push(element);
+            } else {
+                pending.push(element);
+            }
+        }
+        self.push_fragment(&mut pending, store)
+    }
 
-        writer.write_event(Event::Start(doc))?;
-        writer.write_event(Event::Start(BytesStart::new("w:body")))?;
+    /// Render `pending` as one fragment at the end of the body
+    fn push_fragment(
+        &mut self,
+        pending: &mut Vec<DocElement>,
+        store: &mut crate::docx::spill::FragmentStore,
+    ) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut writer = Writer::new(Vec::new());
+        self.write_elements(pending, &mut writer)?;
+        pending.clear();
+        let fragment = store.store(writer.into_inner())?;
+        self.elements.push(DocElement::Fragment(fragment));
+        Ok(())
+    }
 
-        // Write elements (paragraphs, tables, images, and raw XML)
-        for element in &self.elements {
+    /// Write paragraphs, tables, images, and raw XML
+    fn write_elements<W: std::io::Write>(
+        &self,
+        elements: &[DocElement],
+        writer: &mut Writer<W>,
+    ) -> Result<()> {
+        for element in elements {
             match element {
-                DocElement::Paragraph(p) => self.write_paragraph(&mut writer, p)?,
-                DocElement::Table(table) => self.write_table(&mut writer, table)?,
+                DocElement::Paragraph(p) => self.write_paragraph(writer, p)?,
+                DocElement::Table(table) => self.write_table(writer, table)?,
                 DocElement::Image(image) => {
                     // Images need to be wrapped in a paragraph and run
                     writer.write_event(Event::Start(BytesStart::new("w:p")))?;
@@ -1765,12 +1981,12 @@ impl DocumentXml {
                     writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
 
                     writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-                    self.write_drawing(&mut writer, image)?;
+                    self.write_drawing(writer, image)?;
                     writer.write_event(Event::End(BytesEnd::new("w:r")))?;
                     writer.write_event(Event::End(BytesEnd::new("w:p")))?;
                 }
                 DocElement::RawXml(xml) => {
-                    self.write_raw_xml(&mut writer, xml)?;
+                    self.write_raw_xml(writer, xml)?;
                 }
                 DocElement::MathBlock(xml) => {
                     // Display math: wrap in w:p containing m:oMathPara
@@ -1781,19 +1997,14 @@ impl DocumentXml {
                     jc.push_attribute(("w:val", "center"));
                     writer.write_event(Event::Empty(jc))?;
                     writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
-                    self.write_raw_xml_inline(&mut writer, xml)?;
+                    self.write_raw_xml_inline(writer, xml)?;
                     writer.write_event(Event::End(BytesEnd::new("w:p")))?;
                 }
+                DocElement::Fragment(fragment) => fragment.copy_to(writer.get_mut())?,
             }
         }
 
-        // Section properties (Page size/margins)
-        self.write_sect_pr(&mut writer)?;
-
-        writer.write_event(Event::End(BytesEnd::new("w:body")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:document")))?;
-
-        Ok(writer.into_inner().into_inner())
+        Ok(())
     }
 
     /// Write raw XML content (e.g. from cover template)
@@ -1884,6 +2095,20 @@ impl DocumentXml {
         writer: &mut Writer<W>,
         p: &Paragraph,
     ) -> Result<()> {
+        // Section breaks (cover, TOC, chapters) share the document's direction
+        // and page number format
+        if p.is_section_break()
+            && ((self.rtl && !p.section_rtl)
+                || (p.page_num_format.is_none() && self.page_num_format.is_some()))
+        {
+            let mut inherited = p.clone();
+            inherited.section_rtl |= self.rtl;
+            if inherited.page_num_format.is_none() {
+                inherited.page_num_format = self.page_num_format.clone();
+            }
+            return self.write_paragraph(writer, &inherited);
+        }
+
         // Case 1: Section break with header/footer suppression
         // For cover/TOC sections, we want NO headers/footers at all.
         // If empty header/footer IDs are set, use them to explicitly prevent inheritance.
@@ -1924,8 +2149,20 @@ impl DocumentXml {
     fn write_sect_pr<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
         writer.write_event(Event::Start(BytesStart::new("w:sectPr")))?;
 
+        let suppressed;
+        let refs = if self.suppress_header_footer {
+            suppressed = HeaderFooterRefs {
+                default_header_id: self.empty_header_id.clone(),
+                default_footer_id: self.empty_footer_id.clone(),
+                ..Default::default()
+            };
+            &suppressed
+        } else {
+            &self.header_footer_refs
+        };
+
         // Default header
-        if let Some(ref id) = self.header_footer_refs.default_header_id {
+        if let Some(ref id) = refs.default_header_id {
             let mut header_ref = BytesStart::new("w:headerReference");
             header_ref.push_attribute(("w:type", "default"));
             header_ref.push_attribute(("r:id", id.as_str()));
@@ -1933,8 +2170,8 @@ impl DocumentXml {
         }
 
         // First page header (if different first page enabled)
-        if self.header_footer_refs.different_first_page {
-            if let Some(ref id) = self.header_footer_refs.first_header_id {
+        if refs.different_first_page {
+            if let Some(ref id) = refs.first_header_id {
                 let mut header_ref = BytesStart::new("w:headerReference");
                 header_ref.push_attribute(("w:type", "first"));
                 header_ref.push_attribute(("r:id", id.as_str()));
@@ -1943,7 +2180,7 @@ impl DocumentXml {
         }
 
         // Default footer
-        if let Some(ref id) = self.header_footer_refs.default_footer_id {
+        if let Some(ref id) = refs.default_footer_id {
             let mut footer_ref = BytesStart::new("w:footerReference");
             footer_ref.push_attribute(("w:type", "default"));
             footer_ref.push_attribute(("r:id", id.as_str()));
@@ -1951,8 +2188,8 @@ impl DocumentXml {
         }
 
         // First page footer (if different first page enabled)
-        if self.header_footer_refs.different_first_page {
-            if let Some(ref id) = self.header_footer_refs.first_footer_id {
+        if refs.different_first_page {
+            if let Some(ref id) = refs.first_footer_id {
                 let mut footer_ref = BytesStart::new("w:footerReference");
                 footer_ref.push_attribute(("w:type", "first"));
                 footer_ref.push_attribute(("r:id", id.as_str()));
@@ -1960,10 +2197,25 @@ impl DocumentXml {
             }
         }
 
-        // Page numbering (restart at specific number if set)
-        if let Some(start) = self.page_num_start {
+        if let Some(break_type) = &self.final_section_break {
+            let mut type_elem = BytesStart::new("w:type");
+            type_elem.push_attribute(("w:val", break_type.as_str()));
+            writer.write_event(Event::Empty(type_elem))?;
+        }
+
+        // Page numbering (format and restart at specific number if set)
+        let page_num_format = self
+            .final_page_num_format
+            .as_ref()
+            .or(self.page_num_format.as_ref());
+        if self.page_num_start.is_some() || page_num_format.is_some() {
             let mut pg_num = BytesStart::new("w:pgNumType");
-            pg_num.push_attribute(("w:start", start.to_string().as_str()));
+            if let Some(format) = page_num_format {
+                pg_num.push_attribute(("w:fmt", format.as_str()));
+            }
+            if let Some(start) = self.page_num_start {
+                pg_num.push_attribute(("w:start", start.to_string().as_str()));
+            }
             writer.write_event(Event::Empty(pg_num))?;
         }
 
@@ -1994,6 +2246,12 @@ impl DocumentXml {
             writer.write_event(Event::Empty(BytesStart::new("w:titlePg")))?;
         }
 
+        // Right-to-left section layout with mirrored gutter
+        if self.rtl {
+            writer.write_event(Event::Empty(BytesStart::new("w:bidi")))?;
+            writer.write_event(Event::Empty(BytesStart::new("w:rtlGutter")))?;
+        }
+
         // Document grid (for Asian text)
         let mut doc_grid = BytesStart::new("w:docGrid");
         doc_grid.push_attribute(("w:linePitch", "360"));
@@ -2032,6 +2290,11 @@ impl DocumentXml {
         tbl_style.push_attribute(("w:val", "TableGrid"));
         writer.write_event(Event::Empty(tbl_style))?;
 
+        // Right-to-left column ordering
+        if table.bidi_visual {
+            writer.write_event(Event::Empty(BytesStart::new("w:bidiVisual")))?;
+        }
+
         // Table width
         let mut tbl_w = BytesStart::new("w:tblW");
         match table.width {
@@ -2182,9 +2445,14 @@ impl DocumentXml {
         writer.write_event(Event::Start(BytesStart::new("w:tr")))?;
 
         // Row properties (optional)
-        if row.is_header {
+        if row.is_header || row.cant_split {
             writer.write_event(Event::Start(BytesStart::new("w:trPr")))?;
-            writer.write_event(Event::Empty(BytesStart::new("w:tblHeader")))?;
+            if row.cant_split {
+                writer.write_event(Event::Empty(BytesStart::new("w:cantSplit")))?;
+            }
+            if row.is_header {
+                writer.write_event(Event::Empty(BytesStart::new("w:tblHeader")))?;
+            }
             writer.write_event(Event::End(BytesEnd::new("w:trPr")))?;
         }
 
@@ -2530,4 +2798,48 @@ mod tests {
         assert!(xml.contains("blurRad=\"190500\""));
         assert!(xml.contains("<a:alpha val=\"30000\""));
     }
+
+    #[test]
+    fn test_rtl_document() {
+        let run = Run::new("مرحبا بالعالم");
+        assert!(run.rtl);
+        assert!(!Run::new("Hello").rtl);
+
+        let mut doc = DocumentXml::new();
+        doc.rtl = true;
+        doc.add_paragraph(Paragraph::new().add_run(run));
+        doc.add_paragraph(Paragraph::new().section_break("nextPage"));
+        doc.add_table(
+            Table::new().with_bidi_visual(true).add_row(
+                TableRow::new().add_cell(
+                    TableCellElement::new().add_paragraph(Paragraph::new().add_text("A1")),
+                ),
+            ),
+        );
+        let xml: String = String::from_utf8(doc.to_xml().unwrap())
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(xml.contains("<w:rtl/>"));
+        assert!(xml.contains("w:bidi=\"ar-SA\""));
+        assert!(xml.contains("<w:tblStyle w:val=\"TableGrid\"/> <w:bidiVisual/>"));
+        // Both the section break and the final sectPr are right-to-left
+        assert_eq!(xml.matches("<w:bidi/> <w:rtlGutter/>").count(), 2);
+    }
+
+    #[test]
+    fn test_page_number_format() {
+        let mut doc = DocumentXml::new();
+        doc.page_num_format = Some("thaiNumbers".to_string());
+        doc.page_num_start = Some(1);
+        doc.add_paragraph(Paragraph::new().section_break("nextPage"));
+        let mut roman = Paragraph::new().section_break("nextPage");
+        roman.page_num_format = Some("lowerRoman".to_string());
+        doc.add_paragraph(roman);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        assert!(xml.contains("<w:pgNumType w:fmt=\"thaiNumbers\"/>"));
+        assert!(xml.contains("<w:pgNumType w:fmt=\"lowerRoman\"/>"));
+        assert!(xml.contains("<w:pgNumType w:fmt=\"thaiNumbers\" w:start=\"1\"/>"));
+    }
 }
@@ -8,6 +8,7 @@ use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::Writer;
 use std::io::Cursor;
 
+use super::footnotes::write_separator_paragraph;
 use crate::error::Result;
 
 /// Endnotes XML generator
@@ -15,13 +16,11 @@ use crate::error::Result;
 pub struct EndnotesXml {
     // We don't store user endnotes yet - just generate the separators
     // Future: Add endnote storage similar to FootnotesXml
+    /// Text replacing the separator lines, as for footnotes
+    pub separator: Option<String>,
 }
 
 impl EndnotesXml {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
     /// Generate XML content for word/endnotes.xml
     /// This includes the required separator entries (IDs -1 and 0)
     pub fn to_xml(&self) -> Result<Vec<u8>> {
@@ -60,17 +59,7 @@ impl EndnotesXml {
         en.push_attribute(("w:type", type_));
         en.push_attribute(("w:id", id.to_string().as_str()));
         writer.write_event(Event::Start(en))?;
-
-        writer.write_event(Event::Start(BytesStart::new("w:p")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        if id == -1 {
-            writer.write_event(Event::Empty(BytesStart::new("w:separator")))?;
-        } else {
-            writer.write_event(Event::Empty(BytesStart::new("w:continuationSeparator")))?;
-        }
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:p")))?;
-
+        write_separator_paragraph(writer, id, self.separator.as_deref())?;
         writer.write_event(Event::End(BytesEnd::new("w:endnote")))?;
         Ok(())
     }
@@ -82,7 +71,7 @@ mod tests {
 
     #[test]
     fn test_endnotes_xml_new() {
-        let endnotes = EndnotesXml::new();
+        let endnotes = EndnotesXml::default();
         let xml = endnotes.to_xml().unwrap();
         let xml_str = String::from_utf8(xml).unwrap();
 
@@ -94,7 +83,7 @@ mod tests {
 
     #[test]
     fn test_endnotes_xml_has_separators() {
-        let endnotes = EndnotesXml::new();
+        let endnotes = EndnotesXml::default();
         let xml = endnotes.to_xml().unwrap();
         let xml_str = String::from_utf8(xml).unwrap();
 
@@ -107,7 +96,7 @@ mod tests {
 
     #[test]
     fn test_endnotes_xml_structure() {
-        let endnotes = EndnotesXml::new();
+        let endnotes = EndnotesXml::default();
         let xml = endnotes.to_xml().unwrap();
         let xml_str = String::from_utf8(xml).unwrap();
 
@@ -6,12 +6,10 @@
 //! - Total pages (NUMPAGES field)
 //! - Chapter names (STYLEREF field)
 //! - Document title
+//! - Pictures such as a company logo
 
-use crate::docx::ooxml::header::HeaderFooterField;
+use crate::docx::ooxml::header::{write_header_footer_part, HeaderFooterField};
 use crate::error::Result;
-use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use quick_xml::Writer;
-use std::io::Cursor;
 
 /// Footer configuration
 #[derive(Debug, Clone)]
@@ -73,220 +71,11 @@ impl FooterXml {
     ///
     /// Returns the complete footer XML as a byte vector
     pub fn to_xml(&self) -> Result<Vec<u8>> {
-        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
-
-        // XML declaration
-        writer.write_event(Event::Decl(BytesDecl::new(
-            "1.0",
-            Some("UTF-8"),
-            Some("yes"),
-        )))?;
-
-        // Root element with namespaces
-        let mut ftr = BytesStart::new("w:ftr");
-        ftr.push_attribute((
-            "xmlns:w",
-            "http://schemas.openxmlformats.org/wordprocessingml/2006/main",
-        ));
-        ftr.push_attribute((
-            "xmlns:r",
-            "http://schemas.openxmlformats.org/officeDocument/2006/relationships",
-        ));
-        writer.write_event(Event::Start(ftr))?;
-
-        // Create a paragraph with three tab stops (left, center, right)
-        self.write_footer_paragraph(&mut writer)?;
-
-        writer.write_event(Event::End(BytesEnd::new("w:ftr")))?;
-
-        Ok(writer.into_inner().into_inner())
-    }
-
-    /// Write the footer paragraph with tab stops and content
-    fn write_footer_paragraph<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        writer.write_event(Event::Start(BytesStart::new("w:p")))?;
-
-        // Paragraph properties with tab stops (only if there's actual content)
-        let has_content = !self.config.left.is_empty()
-            || !self.config.center.is_empty()
-            || !self.config.right.is_empty();
-
-        writer.write_event(Event::Start(BytesStart::new("w:pPr")))?;
-
-        if has_content {
-            // Tab stops: only add when there's content to position
-            let mut tabs = Vec::new();
-            if !self.config.center.is_empty() {
-                tabs.push(("center", "4513"));
-            }
-            if !self.config.right.is_empty() {
-                tabs.push(("right", "9026"));
-            }
-            if !tabs.is_empty() {
-                writer.write_event(Event::Start(BytesStart::new("w:tabs")))?;
-                for (val, pos) in &tabs {
-                    let mut tab = BytesStart::new("w:tab");
-                    tab.push_attribute(("w:val", *val));
-                    tab.push_attribute(("w:pos", *pos));
-                    writer.write_event(Event::Empty(tab))?;
-                }
-                writer.write_event(Event::End(BytesEnd::new("w:tabs")))?;
-            }
-        }
-
-        writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
-
-        // Left content
-        for field in &self.config.left {
-            self.write_field(writer, field)?;
-        }
-
-        // Tab to center (if center content exists)
-        if !self.config.center.is_empty() {
-            self.write_tab(writer)?;
-            for field in &self.config.center {
-                self.write_field(writer, field)?;
-            }
-        }
-
-        // Tab to right (if right content exists)
-        if !self.config.right.is_empty() {
-            self.write_tab(writer)?;
-            for field in &self.config.right {
-                self.write_field(writer, field)?;
-            }
-        }
-
-        writer.write_event(Event::End(BytesEnd::new("w:p")))?;
-        Ok(())
-    }
-
-    /// Write a tab character
-    fn write_tab<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        writer.write_event(Event::Empty(BytesStart::new("w:tab")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-        Ok(())
-    }
-
-    /// Write a header/footer field
-    fn write_field<W: std::io::Write>(
-        &self,
-        writer: &mut Writer<W>,
-        field: &HeaderFooterField,
-    ) -> Result<()> {
-        match field {
-            HeaderFooterField::Text(text) => {
-                writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-                let mut t = BytesStart::new("w:t");
-                t.push_attribute(("xml:space", "preserve"));
-                writer.write_event(Event::Start(t))?;
-                writer.write_event(Event::Text(BytesText::new(text)))?;
-                writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-                writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-            }
-            HeaderFooterField::DocumentTitle => {
-                // Just output the title as static text
-                writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-                let mut t = BytesStart::new("w:t");
-                t.push_attribute(("xml:space", "preserve"));
-                writer.write_event(Event::Start(t))?;
-                writer.write_event(Event::Text(BytesText::new(&self.document_title)))?;
-                writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-                writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-            }
-            HeaderFooterField::PageNumber => {
-                self.write_page_field(writer, "PAGE")?;
-            }
-            HeaderFooterField::TotalPages => {
-                self.write_page_field(writer, "NUMPAGES")?;
-            }
-            HeaderFooterField::ChapterName => {
-                self.write_styleref_field(writer)?;
-            }
-        }
-        Ok(())
-    }
-
-    /// Write a PAGE or NUMPAGES field
-    ///
-    /// Word fields use the structure:
-    /// - fldChar begin
-    /// - instrText (field instruction)
-    /// - fldChar separate
-    /// - placeholder text
-    /// - fldChar end
-    fn write_page_field<W: std::io::Write>(
-        &self,
-        writer: &mut Writer<W>,
-        field_type: &str,
-    ) -> Result<()> {
-        // Field begin
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        let mut fld_char = BytesStart::new("w:fldChar");
-        fld_char.push_attribute(("w:fldCharType", "begin"));
-        writer.write_event(Event::Empty(fld_char))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Field instruction
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:instrText")))?;
-        writer.write_event(Event::Text(BytesText::new(&format!(" {} ", field_type))))?;
-        writer.write_event(Event::End(BytesEnd::new("w:instrText")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Field separate
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        let mut fld_char = BytesStart::new("w:fldChar");
-        fld_char.push_attribute(("w:fldCharType", "separate"));
-        writer.write_event(Event::Empty(fld_char))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Placeholder value
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:t")))?;
-        writer.write_event(Event::Text(BytesText::new("1")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Field end
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        let mut fld_char = BytesStart::new("w:fldChar");
-        fld_char.push_attribute(("w:fldCharType", "end"));
-        writer.write_event(Event::Empty(fld_char))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        Ok(())
-    }
-
-    /// Write STYLEREF field for chapter name (references Heading 1)
-    ///
-    /// The STYLEREF field automatically extracts text from the most recent
-    /// paragraph with the specified style (Heading 1 for chapter titles).
-    /// Uses w:fldSimple for simpler field structure.
-    ///
-    /// IMPORTANT: w:fldSimple is a direct child of w:p, NOT wrapped in w:r.
-    fn write_styleref_field<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        // w:fldSimple with STYLEREF instruction - direct child of paragraph, NOT inside a run
-        let mut fld_simple = BytesStart::new("w:fldSimple");
-        // Use &quot; for double quotes in XML attribute
-        fld_simple.push_attribute(("w:instr", "STYLEREF \"Heading 1\" \\* MERGEFORMAT"));
-        writer.write_event(Event::Start(fld_simple))?;
-
-        // Placeholder run with cached value (Word will update this)
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        // Add w:noProof to prevent spell-checking the field result
-        writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
-        writer.write_event(Event::Empty(BytesStart::new("w:noProof")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:t")))?;
-        writer.write_event(Event::Text(BytesText::new("Chapter")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        writer.write_event(Event::End(BytesEnd::new("w:fldSimple")))?;
-
-        Ok(())
+        write_header_footer_part(
+            "w:ftr",
+            [&self.config.left, &self.config.center, &self.config.right],
+            &self.document_title,
+        )
     }
 }
 
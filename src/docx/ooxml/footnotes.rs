@@ -1,6 +1,6 @@
 //! Generate word/footnotes.xml for DOCX
 
-use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use std::io::Cursor;
 
@@ -12,6 +12,12 @@ use crate::error::Result;
 pub struct FootnotesXml {
     footnotes: Vec<Footnote>,
     next_id: i32,
+    /// Text replacing the separator lines above the notes (see [`NoteNumbering::separator`])
+    pub separator: Option<String>,
+    /// Hyperlinks in footnotes as (relationship ID, URL), for footnotes.xml.rels
+    pub(crate) hyperlinks: Vec<(String, String)>,
+    /// Relationship IDs of images in footnotes, for footnotes.xml.rels
+    pub(crate) image_rel_ids: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -25,6 +31,9 @@ impl FootnotesXml {
         Self {
             footnotes: Vec::new(),
             next_id: 1, // IDs start at 1 (0 and -1 are reserved)
+            separator: None,
+            hyperlinks: Vec::new(),
+            image_rel_ids: Vec::new(),
         }
     }
 
@@ -36,6 +45,30 @@ impl FootnotesXml {
         id
     }
 
+    /// No footnotes yet, the first to get ID `next_id`
+    #[cfg(feature = "parallel")]
+    pub(crate) fn starting_at(next_id: i32) -> Self {
+        Self {
+            next_id,
+            ..Self::new()
+        }
+    }
+
+    /// ID the next footnote gets
+    #[cfg(feature = "parallel")]
+    pub(crate) fn next_id(&self) -> i32 {
+        self.next_id
+    }
+
+    /// Add the footnotes `rest` numbered on from these
+    #[cfg(feature = "parallel")]
+    pub(crate) fn append(&mut self, rest: FootnotesXml) {
+        self.footnotes.extend(rest.footnotes);
+        self.next_id = rest.next_id;
+        self.hyperlinks.extend(rest.hyperlinks);
+        self.image_rel_ids.extend(rest.image_rel_ids);
+    }
+
     /// Get the number of footnotes
     pub fn len(&self) -> usize {
         self.footnotes.len()
@@ -70,6 +103,23 @@ impl FootnotesXml {
             "xmlns:w14",
             "http://schemas.microsoft.com/office/word/2010/wordml",
         ));
+        // Hyperlinks and images in footnotes refer to footnotes.xml.rels
+        root.push_attribute((
+            "xmlns:r",
+            "http://schemas.openxmlformats.org/officeDocument/2006/relationships",
+        ));
+        root.push_attribute((
+            "xmlns:wp",
+            "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing",
+        ));
+        root.push_attribute((
+            "xmlns:a",
+            "http://schemas.openxmlformats.org/drawingml/2006/main",
+        ));
+        root.push_attribute((
+            "xmlns:pic",
+            "http://schemas.openxmlformats.org/drawingml/2006/picture",
+        ));
         writer.write_event(Event::Start(root))?;
 
         // Add separator (id -1)
@@ -105,19 +155,93 @@ impl FootnotesXml {
         ft.push_attribute(("w:type", type_));
         ft.push_attribute(("w:id", id.to_string().as_str()));
         writer.write_event(Event::Start(ft))?;
+        write_separator_paragraph(writer, id, self.separator.as_deref())?;
+        writer.write_event(Event::End(BytesEnd::new("w:footnote")))?;
+        Ok(())
+    }
+}
 
-        writer.write_event(Event::Start(BytesStart::new("w:p")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        if id == -1 {
-            writer.write_event(Event::Empty(BytesStart::new("w:separator")))?;
-        } else {
-            writer.write_event(Event::Empty(BytesStart::new("w:continuationSeparator")))?;
+/// Write the paragraph of a separator (`id` -1) or continuation separator
+///
+/// With a custom `separator` the paragraph holds that text, or nothing when
+/// it is empty, instead of Word's separator line.
+pub(crate) fn write_separator_paragraph<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    id: i32,
+    separator: Option<&str>,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("w:p")))?;
+    match separator {
+        Some("") => {}
+        Some(text) => {
+            writer.write_event(Event::Start(BytesStart::new("w:r")))?;
+            let mut t = BytesStart::new("w:t");
+            t.push_attribute(("xml:space", "preserve"));
+            writer.write_event(Event::Start(t))?;
+            writer.write_event(Event::Text(BytesText::new(text)))?;
+            writer.write_event(Event::End(BytesEnd::new("w:t")))?;
+            writer.write_event(Event::End(BytesEnd::new("w:r")))?;
         }
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:p")))?;
+        None => {
+            writer.write_event(Event::Start(BytesStart::new("w:r")))?;
+            if id == -1 {
+                writer.write_event(Event::Empty(BytesStart::new("w:separator")))?;
+            } else {
+                writer.write_event(Event::Empty(BytesStart::new("w:continuationSeparator")))?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("w:r")))?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new("w:p")))?;
+    Ok(())
+}
 
-        writer.write_event(Event::End(BytesEnd::new("w:footnote")))?;
-        Ok(())
+/// Footnote and endnote numbering, written to `w:footnotePr` and
+/// `w:endnotePr` in settings.xml
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NoteNumbering {
+    /// Word number format of footnote marks, e.g. "lowerRoman" or "chicago"
+    /// (*, †, ‡, §); decimal when unset
+    pub footnotes: Option<String>,
+    /// Word number format of endnote marks; Word's lowerRoman when unset
+    pub endnotes: Option<String>,
+    /// Where note numbers start again from 1
+    pub restart: NoteRestart,
+    /// Text drawn instead of the separator line above the notes, or no
+    /// separator when empty
+    pub separator: Option<String>,
+}
+
+/// When footnote and endnote numbering starts again (`w:numRestart`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoteRestart {
+    /// Numbered through the whole document
+    #[default]
+    Continuous,
+    /// From 1 in each section, e.g. each chapter
+    EachSection,
+    /// From 1 on each page; endnotes number continuously
+    EachPage,
+}
+
+impl NoteRestart {
+    /// Parse a restart name: continuous, section or page
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "continuous" => Some(NoteRestart::Continuous),
+            "section" => Some(NoteRestart::EachSection),
+            "page" => Some(NoteRestart::EachPage),
+            _ => None,
+        }
+    }
+
+    /// `w:numRestart` value for footnotes, or endnotes with `endnote`
+    pub(crate) fn value(self, endnote: bool) -> &'static str {
+        match self {
+            NoteRestart::EachSection => "eachSect",
+            NoteRestart::EachPage if !endnote => "eachPage",
+            _ => "continuous",
+        }
     }
 }
 
@@ -199,6 +323,30 @@ mod tests {
         assert!(xml_str.contains("<w:footnote w:type=\"continuationSeparator\" w:id=\"0\""));
     }
 
+    #[test]
+    fn test_footnotes_xml_custom_separator() {
+        let mut footnotes = FootnotesXml::new();
+        footnotes.separator = Some("* * *".to_string());
+        let xml = String::from_utf8(footnotes.to_xml().unwrap()).unwrap();
+        assert!(xml.contains("<w:t xml:space=\"preserve\">* * *</w:t>"));
+        assert!(!xml.contains("<w:separator/>"));
+
+        footnotes.separator = Some(String::new());
+        let xml = String::from_utf8(footnotes.to_xml().unwrap()).unwrap();
+        assert!(xml.contains("<w:footnote w:type=\"separator\" w:id=\"-1\"><w:p></w:p>"));
+    }
+
+    #[test]
+    fn test_note_restart() {
+        assert_eq!(
+            NoteRestart::from_name("Section"),
+            Some(NoteRestart::EachSection)
+        );
+        assert_eq!(NoteRestart::from_name("chapter"), None);
+        assert_eq!(NoteRestart::EachPage.value(false), "eachPage");
+        assert_eq!(NoteRestart::EachPage.value(true), "continuous");
+    }
+
     #[test]
     fn test_footnotes_xml_multiple_footnotes() {
         let mut footnotes = FootnotesXml::new();
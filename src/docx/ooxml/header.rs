@@ -5,12 +5,16 @@
 //! - Page numbers (PAGE field)
 //! - Total pages (NUMPAGES field)
 //! - Chapter names (STYLEREF field)
+//! - Chapter authors and edit dates (STYLEREF fields)
 //! - Document title
+//! - Pictures such as a company logo
 
+use super::xml_writer::{escape, XmlWriter};
 use crate::error::Result;
-use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use quick_xml::Writer;
-use std::io::Cursor;
+use crate::i18n::{Label, Strings};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::sync::Arc;
 
 /// Field types for dynamic header/footer content
 #[derive(Debug, Clone)]
@@ -23,8 +27,158 @@ pub enum HeaderFooterField {
     TotalPages,
     /// Chapter name field (STYLEREF "Heading 1")
     ChapterName,
+    /// Last author of the chapter's source file (STYLEREF "Chapter Author")
+    ChapterAuthor,
+    /// Last edit date of the chapter's source file (STYLEREF "Chapter Date")
+    ChapterDate,
+    /// Nearest heading of a level, 1-6, before the page (STYLEREF "Heading 2")
+    StyleRef(u8),
     /// Document title (static text from config)
     DocumentTitle,
+    /// Inline picture, such as a logo
+    Image(HeaderFooterImage),
+}
+
+/// Picture shown in a header or footer
+#[derive(Debug, Clone)]
+pub struct HeaderFooterImage {
+    /// File name in the package's media folder, e.g. "logo.png"
+    pub filename: String,
+    /// Image bytes
+    pub data: Arc<[u8]>,
+    /// Width in EMUs
+    pub width_emu: i64,
+    /// Height in EMUs
+    pub height_emu: i64,
+}
+
+/// EMUs per twip
+const EMU_PER_TWIP: i64 = 635;
+
+/// Height of an image without a configured size (1cm)
+const DEFAULT_IMAGE_HEIGHT_TWIPS: u32 = 567;
+
+impl HeaderFooterImage {
+    /// Image sized to `width` and/or `height` in twips
+    ///
+    /// When only one is given the other follows the image's aspect ratio;
+    /// without either the image is 1cm tall. Returns `None` when the data is
+    /// not a PNG, JPEG, GIF, BMP or SVG image.
+    pub fn new(
+        filename: &str,
+        data: impl Into<Arc<[u8]>>,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Option<Self> {
+        let data = data.into();
+        let ratio = crate::docx::image_utils::read_image_dimensions(&data)?.aspect_ratio();
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width as f64, height as f64),
+            (Some(width), None) => (width as f64, width as f64 / ratio),
+            (None, height) => {
+                let height = height.unwrap_or(DEFAULT_IMAGE_HEIGHT_TWIPS) as f64;
+                (height * ratio, height)
+            }
+        };
+        Some(Self {
+            filename: filename.to_string(),
+            data,
+            width_emu: (width * EMU_PER_TWIP as f64).round() as i64,
+            height_emu: (height * EMU_PER_TWIP as f64).round() as i64,
+        })
+    }
+}
+
+/// Relationship ID of the `n`th image (from 1) in a header or footer part
+fn image_rel_id(n: usize) -> String {
+    format!("rIdImage{}", n)
+}
+
+/// Images of a header or footer part with their relationship IDs, in the
+/// order [`write_header_footer_part`] writes them
+pub(crate) fn header_footer_images(
+    slots: [&[HeaderFooterField]; 3],
+) -> Vec<(String, &HeaderFooterImage)> {
+    slots
+        .into_iter()
+        .flatten()
+        .filter_map(|field| match field {
+            HeaderFooterField::Image(image) => Some(image),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(i, image)| (image_rel_id(i + 1), image))
+        .collect()
+}
+
+/// Localized "Page X of Y" in header and footer text
+pub const PAGE_OF_TOTAL_TOKEN: &str = "{page_of_total}";
+
+/// Field tokens recognized by [`HeaderFooterField::parse_template`]
+static FIELD_TOKEN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\{\{(page|total|chapter(?:_author|_date)?|heading:[1-6])\}\}|\{(page|pages|chapter(?:_author|_date)?|heading:[1-6])\}",
+    )
+    .expect("Invalid regex")
+});
+
+impl HeaderFooterField {
+    /// Split header/footer text into static text and field runs
+    ///
+    /// `{page}`, `{pages}` and `{chapter}` (or `{{page}}`, `{{total}}` and
+    /// `{{chapter}}`) become page number, page count and chapter name
+    /// fields, and `{chapter_author}` / `{chapter_date}` the chapter's last
+    /// author and edit date, and `{heading:2}` the current level 2 heading;
+    /// everything else is kept as text.
+    pub fn parse_template(text: &str) -> Vec<HeaderFooterField> {
+        Self::parse_localized(text, None)
+    }
+
+    /// Like [`parse_template`](Self::parse_template), with `{page_of_total}`
+    /// replaced by the localized "Page {page} of {pages}" wording
+    pub fn parse_template_localized(text: &str, strings: &Strings) -> Vec<HeaderFooterField> {
+        Self::parse_localized(text, Some(strings))
+    }
+
+    fn parse_localized(text: &str, strings: Option<&Strings>) -> Vec<HeaderFooterField> {
+        let expanded;
+        let text = match strings {
+            Some(strings) if text.contains(PAGE_OF_TOTAL_TOKEN) => {
+                expanded = text.replace(PAGE_OF_TOTAL_TOKEN, strings.get(Label::PageOfTotal));
+                expanded.as_str()
+            }
+            _ => text,
+        };
+        let mut fields = Vec::new();
+        let mut last = 0;
+        for cap in FIELD_TOKEN.captures_iter(text) {
+            let token = cap.get(0).expect("Invalid regex");
+            if token.start() > last {
+                fields.push(HeaderFooterField::Text(
+                    text[last..token.start()].to_string(),
+                ));
+            }
+            let name = cap.get(1).or_else(|| cap.get(2)).map_or("", |m| m.as_str());
+            fields.push(match name {
+                "page" => HeaderFooterField::PageNumber,
+                "chapter" => HeaderFooterField::ChapterName,
+                "chapter_author" => HeaderFooterField::ChapterAuthor,
+                "chapter_date" => HeaderFooterField::ChapterDate,
+                "pages" | "total" => HeaderFooterField::TotalPages,
+                level => HeaderFooterField::StyleRef(
+                    level
+                        .trim_start_matches("heading:")
+                        .parse()
+                        .expect("Invalid regex"),
+                ),
+            });
+            last = token.end();
+        }
+        if last < text.len() {
+            fields.push(HeaderFooterField::Text(text[last..].to_string()));
+        }
+        fields
+    }
 }
 
 /// Header configuration
@@ -87,221 +241,291 @@ impl HeaderXml {
     ///
     /// Returns the complete header XML as a byte vector
     pub fn to_xml(&self) -> Result<Vec<u8>> {
-        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
-
-        // XML declaration
-        writer.write_event(Event::Decl(BytesDecl::new(
-            "1.0",
-            Some("UTF-8"),
-            Some("yes"),
-        )))?;
-
-        // Root element with namespaces
-        let mut hdr = BytesStart::new("w:hdr");
-        hdr.push_attribute((
+        write_header_footer_part(
+            "w:hdr",
+            [&self.config.left, &self.config.center, &self.config.right],
+            &self.document_title,
+        )
+    }
+}
+
+/// Write a header (`w:hdr`) or footer (`w:ftr`) part
+///
+/// The part holds one paragraph with the left, center and right aligned
+/// fields separated by tabs.
+pub(crate) fn write_header_footer_part(
+    root: &str,
+    [left, center, right]: [&[HeaderFooterField]; 3],
+    document_title: &str,
+) -> Result<Vec<u8>> {
+    let mut w = XmlWriter::indented(Vec::new());
+    w.declaration()?;
+    let mut namespaces = vec![
+        (
             "xmlns:w",
             "http://schemas.openxmlformats.org/wordprocessingml/2006/main",
-        ));
-        hdr.push_attribute((
+        ),
+        (
             "xmlns:r",
             "http://schemas.openxmlformats.org/officeDocument/2006/relationships",
+        ),
+    ];
+    if !header_footer_images([left, center, right]).is_empty() {
+        namespaces.push((
+            "xmlns:wp",
+            "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing",
         ));
-        writer.write_event(Event::Start(hdr))?;
-
-        // Create a paragraph with three tab stops (left, center, right)
-        self.write_header_paragraph(&mut writer)?;
-
-        writer.write_event(Event::End(BytesEnd::new("w:hdr")))?;
-
-        Ok(writer.into_inner().into_inner())
     }
+    w.start(root, &namespaces)?;
+
+    w.start("w:p", &[])?;
+    w.start("w:pPr", &[])?;
+    // Tab stops: only add when there's content to position
+    if !center.is_empty() || !right.is_empty() {
+        w.start("w:tabs", &[])?;
+        if !center.is_empty() {
+            w.empty("w:tab", &[("w:val", "center"), ("w:pos", "4513")])?;
+        }
+        if !right.is_empty() {
+            w.empty("w:tab", &[("w:val", "right"), ("w:pos", "9026")])?;
+        }
+        w.end("w:tabs")?;
+    }
+    w.end("w:pPr")?;
 
-    /// Write the header paragraph with tab stops and content
-    fn write_header_paragraph<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        writer.write_event(Event::Start(BytesStart::new("w:p")))?;
-
-        // Paragraph properties with tab stops (only if there's actual content)
-        let has_content = !self.config.left.is_empty()
-            || !self.config.center.is_empty()
-            || !self.config.right.is_empty();
-
-        writer.write_event(Event::Start(BytesStart::new("w:pPr")))?;
-
-        if has_content {
-            // Tab stops: only add when there's content to position
-            let mut tabs = Vec::new();
-            if !self.config.center.is_empty() {
-                tabs.push(("center", "4513"));
-            }
-            if !self.config.right.is_empty() {
-                tabs.push(("right", "9026"));
-            }
-            if !tabs.is_empty() {
-                writer.write_event(Event::Start(BytesStart::new("w:tabs")))?;
-                for (val, pos) in &tabs {
-                    let mut tab = BytesStart::new("w:tab");
-                    tab.push_attribute(("w:val", *val));
-                    tab.push_attribute(("w:pos", *pos));
-                    writer.write_event(Event::Empty(tab))?;
-                }
-                writer.write_event(Event::End(BytesEnd::new("w:tabs")))?;
+    let mut images = 0;
+    for field in left {
+        write_field(&mut w, field, document_title, &mut images)?;
+    }
+    for fields in [center, right] {
+        if !fields.is_empty() {
+            // Tab to the next stop
+            w.start("w:r", &[])?;
+            w.empty("w:tab", &[])?;
+            w.end("w:r")?;
+            for field in fields {
+                write_field(&mut w, field, document_title, &mut images)?;
             }
         }
+    }
+    w.end("w:p")?;
 
-        writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
+    w.end(root)?;
+    Ok(w.into_inner())
+}
 
-        // Left content
-        for field in &self.config.left {
-            self.write_field(writer, field)?;
+/// Write a header/footer field
+///
+/// `images` counts the images written so far, numbering their relationship IDs.
+fn write_field<W: std::io::Write>(
+    w: &mut XmlWriter<W>,
+    field: &HeaderFooterField,
+    document_title: &str,
+    images: &mut usize,
+) -> Result<()> {
+    match field {
+        HeaderFooterField::Text(text) => write_text_run(w, text),
+        HeaderFooterField::DocumentTitle => write_text_run(w, document_title),
+        HeaderFooterField::PageNumber => write_complex_field(w, "PAGE"),
+        HeaderFooterField::TotalPages => write_complex_field(w, "NUMPAGES"),
+        HeaderFooterField::ChapterName => write_simple_field(w, CHAPTER_FIELD, "Chapter", true),
+        HeaderFooterField::ChapterAuthor => {
+            write_simple_field(w, CHAPTER_AUTHOR_FIELD, "Author", true)
         }
-
-        // Tab to center (if center content exists)
-        if !self.config.center.is_empty() {
-            self.write_tab(writer)?;
-            for field in &self.config.center {
-                self.write_field(writer, field)?;
-            }
+        HeaderFooterField::ChapterDate => write_simple_field(w, CHAPTER_DATE_FIELD, "Date", true),
+        HeaderFooterField::StyleRef(level) => {
+            write_simple_field(w, &heading_field(*level), "Heading", true)
         }
-
-        // Tab to right (if right content exists)
-        if !self.config.right.is_empty() {
-            self.write_tab(writer)?;
-            for field in &self.config.right {
-                self.write_field(writer, field)?;
-            }
+        HeaderFooterField::Image(image) => {
+            *images += 1;
+            write_image_run(w, image, *images)
         }
-
-        writer.write_event(Event::End(BytesEnd::new("w:p")))?;
-        Ok(())
     }
+}
+
+/// First `wp:docPr` ID of header/footer images, clear of the body's drawings
+const IMAGE_DOC_PR_BASE: usize = 90000;
+
+/// Write the `n`th image of the part as an inline drawing run
+fn write_image_run<W: std::io::Write>(
+    w: &mut XmlWriter<W>,
+    image: &HeaderFooterImage,
+    n: usize,
+) -> Result<()> {
+    const DRAWINGML: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+    const PICTURE: &str = "http://schemas.openxmlformats.org/drawingml/2006/picture";
+    let (cx, cy) = (image.width_emu.to_string(), image.height_emu.to_string());
+    let id = (IMAGE_DOC_PR_BASE + n).to_string();
+    let name = format!("Picture {}", n);
+
+    w.start("w:r", &[])?;
+    w.start("w:drawing", &[])?;
+    w.start(
+        "wp:inline",
+        &[
+            ("distT", "0"),
+            ("distB", "0"),
+            ("distL", "0"),
+            ("distR", "0"),
+        ],
+    )?;
+    w.empty("wp:extent", &[("cx", &cx), ("cy", &cy)])?;
+    w.empty("wp:docPr", &[("id", &id), ("name", &name)])?;
+    w.start("a:graphic", &[("xmlns:a", DRAWINGML)])?;
+    w.start("a:graphicData", &[("uri", PICTURE)])?;
+    w.start("pic:pic", &[("xmlns:pic", PICTURE)])?;
+    w.start("pic:nvPicPr", &[])?;
+    w.empty("pic:cNvPr", &[("id", "0"), ("name", &image.filename)])?;
+    w.empty("pic:cNvPicPr", &[])?;
+    w.end("pic:nvPicPr")?;
+    w.start("pic:blipFill", &[])?;
+    w.empty("a:blip", &[("r:embed", &image_rel_id(n))])?;
+    w.start("a:stretch", &[])?;
+    w.empty("a:fillRect", &[])?;
+    w.end("a:stretch")?;
+    w.end("pic:blipFill")?;
+    w.start("pic:spPr", &[])?;
+    w.start("a:xfrm", &[])?;
+    w.empty("a:off", &[("x", "0"), ("y", "0")])?;
+    w.empty("a:ext", &[("cx", &cx), ("cy", &cy)])?;
+    w.end("a:xfrm")?;
+    w.start("a:prstGeom", &[("prst", "rect")])?;
+    w.empty("a:avLst", &[])?;
+    w.end("a:prstGeom")?;
+    w.end("pic:spPr")?;
+    w.end("pic:pic")?;
+    w.end("a:graphicData")?;
+    w.end("a:graphic")?;
+    w.end("wp:inline")?;
+    w.end("w:drawing")?;
+    w.end("w:r")
+}
+
+fn write_text_run<W: std::io::Write>(w: &mut XmlWriter<W>, text: &str) -> Result<()> {
+    w.start("w:r", &[])?;
+    w.element("w:t", &[("xml:space", "preserve")], text)?;
+    w.end("w:r")
+}
+
+/// STYLEREF instruction giving the current chapter (Heading 1) title
+pub(crate) const CHAPTER_FIELD: &str = "STYLEREF \"Heading 1\" \\* MERGEFORMAT";
+
+/// STYLEREF instruction giving the current heading of a level
+pub(crate) fn heading_field(level: u8) -> String {
+    format!("STYLEREF \"Heading {}\" \\* MERGEFORMAT", level.clamp(1, 6))
+}
+
+/// STYLEREF instruction giving the current chapter's last author
+///
+/// Refers to the hidden paragraph the builder adds after a chapter heading
+/// from `<!-- {edited:...} -->`.
+pub(crate) const CHAPTER_AUTHOR_FIELD: &str = "STYLEREF \"Chapter Author\" \\* MERGEFORMAT";
+
+/// STYLEREF instruction giving the current chapter's last edit date
+pub(crate) const CHAPTER_DATE_FIELD: &str = "STYLEREF \"Chapter Date\" \\* MERGEFORMAT";
+
+/// Write a PAGE or NUMPAGES field
+///
+/// Word fields use the structure:
+/// - fldChar begin
+/// - instrText (field instruction)
+/// - fldChar separate
+/// - placeholder text
+/// - fldChar end
+fn write_complex_field<W: std::io::Write>(w: &mut XmlWriter<W>, field_type: &str) -> Result<()> {
+    let fld_char = |w: &mut XmlWriter<W>, kind: &str| -> Result<()> {
+        w.start("w:r", &[])?;
+        w.empty("w:fldChar", &[("w:fldCharType", kind)])?;
+        w.end("w:r")
+    };
+
+    fld_char(w, "begin")?;
+    w.start("w:r", &[])?;
+    w.element("w:instrText", &[], &format!(" {} ", field_type))?;
+    w.end("w:r")?;
+    fld_char(w, "separate")?;
+    w.start("w:r", &[])?;
+    w.element("w:t", &[], "1")?;
+    w.end("w:r")?;
+    fld_char(w, "end")
+}
 
-    /// Write a tab character
-    fn write_tab<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        writer.write_event(Event::Empty(BytesStart::new("w:tab")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-        Ok(())
+/// Write a `w:fldSimple` field with a cached `placeholder` result
+///
+/// IMPORTANT: w:fldSimple is a direct child of w:p, NOT wrapped in w:r.
+/// `no_proof` keeps Word from spell-checking the field result.
+pub(crate) fn write_simple_field<W: std::io::Write>(
+    w: &mut XmlWriter<W>,
+    instr: &str,
+    placeholder: &str,
+    no_proof: bool,
+) -> Result<()> {
+    w.start("w:fldSimple", &[("w:instr", instr)])?;
+    w.start("w:r", &[])?;
+    if no_proof {
+        w.start("w:rPr", &[])?;
+        w.empty("w:noProof", &[])?;
+        w.end("w:rPr")?;
     }
+    w.element("w:t", &[], placeholder)?;
+    w.end("w:r")?;
+    w.end("w:fldSimple")
+}
 
-    /// Write a header/footer field
-    fn write_field<W: std::io::Write>(
-        &self,
-        writer: &mut Writer<W>,
-        field: &HeaderFooterField,
-    ) -> Result<()> {
-        match field {
-            HeaderFooterField::Text(text) => {
-                writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-                let mut t = BytesStart::new("w:t");
-                t.push_attribute(("xml:space", "preserve"));
-                writer.write_event(Event::Start(t))?;
-                writer.write_event(Event::Text(BytesText::new(text)))?;
-                writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-                writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-            }
-            HeaderFooterField::DocumentTitle => {
-                // Just output the title as static text
-                writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-                let mut t = BytesStart::new("w:t");
-                t.push_attribute(("xml:space", "preserve"));
-                writer.write_event(Event::Start(t))?;
-                writer.write_event(Event::Text(BytesText::new(&self.document_title)))?;
-                writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-                writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-            }
-            HeaderFooterField::PageNumber => {
-                self.write_page_field(writer, "PAGE")?;
-            }
-            HeaderFooterField::TotalPages => {
-                self.write_page_field(writer, "NUMPAGES")?;
-            }
-            HeaderFooterField::ChapterName => {
-                self.write_styleref_field(writer)?;
-            }
+/// VML namespaces required by the watermark shape
+const WATERMARK_NAMESPACES: [(&str, &str); 2] = [
+    ("xmlns:v", "urn:schemas-microsoft-com:vml"),
+    ("xmlns:o", "urn:schemas-microsoft-com:office:office"),
+];
+
+/// Add a diagonal text watermark (like Word's "DRAFT" watermark) to a header part
+///
+/// The watermark is a VML WordArt shape anchored in the header, so it shows
+/// behind the body text on every page that uses the header. The shape run is
+/// appended to the header's first paragraph; a paragraph is created when the
+/// header has none.
+pub fn add_watermark(header_xml: &[u8], text: &str) -> Vec<u8> {
+    let mut xml = String::from_utf8_lossy(header_xml).into_owned();
+
+    // Declare the VML namespaces on the root element if missing
+    if let Some(start) = xml.find("<w:hdr") {
+        if let Some(end) = xml[start..].find('>').map(|i| start + i) {
+            let root = xml[start..end].to_string();
+            let missing: String = WATERMARK_NAMESPACES
+                .iter()
+                .filter(|(prefix, _)| !root.contains(&format!("{}=", prefix)))
+                .map(|(prefix, uri)| format!(" {}=\"{}\"", prefix, uri))
+                .collect();
+            let insert_at = if root.ends_with('/') { end - 1 } else { end };
+            xml.insert_str(insert_at, &missing);
         }
-        Ok(())
     }
 
-    /// Write a PAGE or NUMPAGES field
-    ///
-    /// Word fields use the structure:
-    /// - fldChar begin
-    /// - instrText (field instruction)
-    /// - fldChar separate
-    /// - placeholder text
-    /// - fldChar end
-    fn write_page_field<W: std::io::Write>(
-        &self,
-        writer: &mut Writer<W>,
-        field_type: &str,
-    ) -> Result<()> {
-        // Field begin
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        let mut fld_char = BytesStart::new("w:fldChar");
-        fld_char.push_attribute(("w:fldCharType", "begin"));
-        writer.write_event(Event::Empty(fld_char))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Field instruction
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:instrText")))?;
-        writer.write_event(Event::Text(BytesText::new(&format!(" {} ", field_type))))?;
-        writer.write_event(Event::End(BytesEnd::new("w:instrText")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Field separate
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        let mut fld_char = BytesStart::new("w:fldChar");
-        fld_char.push_attribute(("w:fldCharType", "separate"));
-        writer.write_event(Event::Empty(fld_char))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Placeholder value
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:t")))?;
-        writer.write_event(Event::Text(BytesText::new("1")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        // Field end
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        let mut fld_char = BytesStart::new("w:fldChar");
-        fld_char.push_attribute(("w:fldCharType", "end"));
-        writer.write_event(Event::Empty(fld_char))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        Ok(())
+    let escaped = escape(text);
+    let run = format!(
+        concat!(
+            r##"<w:r><w:rPr><w:noProof/></w:rPr><w:pict>"##,
+            r##"<v:shapetype id="_x0000_t136" coordsize="21600,21600" o:spt="136" adj="10800" path="m@7,l@8,m@5,21600l@6,21600e">"##,
+            r##"<v:formulas><v:f eqn="sum #0 0 10800"/><v:f eqn="prod #0 2 1"/><v:f eqn="sum 21600 0 @1"/><v:f eqn="sum 0 0 @2"/><v:f eqn="sum 21600 0 @3"/><v:f eqn="if @0 @3 0"/><v:f eqn="if @0 21600 @1"/><v:f eqn="if @0 0 @2"/><v:f eqn="if @0 @4 21600"/><v:f eqn="mid @5 @6"/><v:f eqn="mid @8 @5"/><v:f eqn="mid @7 @8"/><v:f eqn="mid @6 @7"/><v:f eqn="sum @6 0 @5"/></v:formulas>"##,
+            r##"<v:path textpathok="t" o:connecttype="custom" o:connectlocs="@9,0;@10,10800;@11,21600;@12,10800" o:connectangles="270,180,90,0"/>"##,
+            r##"<v:textpath on="t" fitshape="t"/><o:lock v:ext="edit" text="t" shapetype="t"/></v:shapetype>"##,
+            r##"<v:shape id="PowerPlusWaterMarkObject" o:spid="_x0000_s2049" type="#_x0000_t136" "##,
+            r##"style="position:absolute;margin-left:0;margin-top:0;width:468pt;height:117pt;rotation:315;z-index:-251657216;"##,
+            r##"mso-position-horizontal:center;mso-position-horizontal-relative:margin;mso-position-vertical:center;mso-position-vertical-relative:margin" "##,
+            r##"o:allowincell="f" fillcolor="silver" stroked="f">"##,
+            r##"<v:fill opacity=".5"/><v:textpath style="font-family:&quot;Calibri&quot;;font-size:1pt" string="{}"/>"##,
+            r##"</v:shape></w:pict></w:r>"##
+        ),
+        escaped
+    );
+
+    if let Some(pos) = xml.find("</w:p>") {
+        xml.insert_str(pos, &run);
+    } else if let Some(pos) = xml.find("</w:hdr>") {
+        xml.insert_str(pos, &format!("<w:p>{}</w:p>", run));
     }
 
-    /// Write STYLEREF field for chapter name (references Heading 1)
-    ///
-    /// The STYLEREF field automatically extracts text from the most recent
-    /// paragraph with the specified style (Heading 1 for chapter titles).
-    /// Uses w:fldSimple for simpler field structure.
-    ///
-    /// IMPORTANT: w:fldSimple is a direct child of w:p, NOT wrapped in w:r.
-    fn write_styleref_field<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        // w:fldSimple with STYLEREF instruction - direct child of paragraph, NOT inside a run
-        let mut fld_simple = BytesStart::new("w:fldSimple");
-        // Use &quot; for double quotes in XML attribute
-        fld_simple.push_attribute(("w:instr", "STYLEREF \"Heading 1\" \\* MERGEFORMAT"));
-        writer.write_event(Event::Start(fld_simple))?;
-
-        // Placeholder run with cached value (Word will update this)
-        writer.write_event(Event::Start(BytesStart::new("w:r")))?;
-        // Add w:noProof to prevent spell-checking the field result
-        writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
-        writer.write_event(Event::Empty(BytesStart::new("w:noProof")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
-        writer.write_event(Event::Start(BytesStart::new("w:t")))?;
-        writer.write_event(Event::Text(BytesText::new("Chapter")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-        writer.write_event(Event::End(BytesEnd::new("w:r")))?;
-
-        writer.write_event(Event::End(BytesEnd::new("w:fldSimple")))?;
-
-        Ok(())
-    }
+    xml.into_bytes()
 }
 
 #[cfg(test)]
@@ -354,6 +578,17 @@ mod tests {
         assert!(xml_str.contains("Heading 1"));
     }
 
+    #[test]
+    fn test_header_xml_with_style_ref() {
+        let config = HeaderConfig {
+            left: vec![HeaderFooterField::ChapterName],
+            center: vec![],
+            right: vec![HeaderFooterField::StyleRef(2)],
+        };
+        let xml = String::from_utf8(HeaderXml::new(config, "").to_xml().unwrap()).unwrap();
+        assert!(xml.contains("w:instr=\"STYLEREF &quot;Heading 2&quot; \\* MERGEFORMAT\""));
+    }
+
     #[test]
     fn test_header_xml_page_of_total() {
         let config = HeaderConfig {
@@ -376,6 +611,37 @@ mod tests {
         assert!(xml_str.contains("NUMPAGES"));
     }
 
+    #[test]
+    fn test_header_xml_with_image() {
+        // PNG header of a 2x1 image
+        let png: &[u8] = &[
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00,
+            0x00,
+        ];
+        let logo = HeaderFooterImage::new("logo.png", png, None, Some(1440)).unwrap();
+        assert_eq!((logo.width_emu, logo.height_emu), (1828800, 914400));
+        assert!(HeaderFooterImage::new("logo.png", &b"not an image"[..], None, None).is_none());
+
+        let config = HeaderConfig {
+            left: vec![HeaderFooterField::Image(logo)],
+            center: vec![],
+            right: vec![HeaderFooterField::ChapterName],
+        };
+        let images = header_footer_images([&config.left, &config.center, &config.right]);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].0, "rIdImage1");
+
+        let xml = String::from_utf8(HeaderXml::new(config, "").to_xml().unwrap()).unwrap();
+        assert!(xml.contains("xmlns:wp="));
+        assert!(xml.contains("<wp:extent cx=\"1828800\" cy=\"914400\"/>"));
+        assert!(xml.contains("r:embed=\"rIdImage1\""));
+        assert!(!HeaderXml::new(HeaderConfig::default(), "")
+            .to_xml()
+            .map(|xml| String::from_utf8(xml).unwrap().contains("xmlns:wp="))
+            .unwrap());
+    }
+
     #[test]
     fn test_header_xml_empty_config() {
         let config = HeaderConfig::empty();
@@ -412,4 +678,68 @@ mod tests {
         assert!(xml_str.contains("STYLEREF"));
         assert!(xml_str.contains("Heading 1"));
     }
+
+    #[test]
+    fn test_add_watermark() {
+        let header = HeaderXml::new(HeaderConfig::empty(), "");
+        let xml = add_watermark(&header.to_xml().unwrap(), "DRAFT & <review>");
+        let xml_str = String::from_utf8(xml).unwrap();
+
+        assert!(xml_str.contains(r#"xmlns:v="urn:schemas-microsoft-com:vml""#));
+        assert!(xml_str.contains(r#"string="DRAFT &amp; &lt;review&gt;""#));
+        // The shape run goes inside the existing paragraph
+        assert_eq!(xml_str.matches("<w:p>").count(), 1);
+        assert!(xml_str.find("<w:pict>").unwrap() < xml_str.find("</w:p>").unwrap());
+
+        // Parts without paragraphs get one
+        let bare = add_watermark(br#"<w:hdr xmlns:w="w"></w:hdr>"#, "DRAFT");
+        let bare_str = String::from_utf8(bare).unwrap();
+        assert!(bare_str.contains("<w:p><w:r>"));
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let fields = HeaderFooterField::parse_template("Page {page} of {{total}} - {chapter}");
+        assert_eq!(fields.len(), 6);
+        assert!(matches!(&fields[0], HeaderFooterField::Text(t) if t == "Page "));
+        assert!(matches!(fields[1], HeaderFooterField::PageNumber));
+        assert!(matches!(&fields[2], HeaderFooterField::Text(t) if t == " of "));
+        assert!(matches!(fields[3], HeaderFooterField::TotalPages));
+        assert!(matches!(&fields[4], HeaderFooterField::Text(t) if t == " - "));
+        assert!(matches!(fields[5], HeaderFooterField::ChapterName));
+
+        let fields = HeaderFooterField::parse_template("{chapter_author}, {{chapter_date}}");
+        assert!(matches!(fields[0], HeaderFooterField::ChapterAuthor));
+        assert!(matches!(fields[2], HeaderFooterField::ChapterDate));
+
+        let fields = HeaderFooterField::parse_template("{chapter} / {heading:2}");
+        assert!(matches!(fields[2], HeaderFooterField::StyleRef(2)));
+        let fields = HeaderFooterField::parse_template("{heading:7}");
+        assert!(matches!(&fields[..], [HeaderFooterField::Text(_)]));
+
+        // Other placeholders are left as text
+        let fields = HeaderFooterField::parse_template("{{title}}");
+        assert!(matches!(&fields[..], [HeaderFooterField::Text(t)] if t == "{{title}}"));
+        assert!(HeaderFooterField::parse_template("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_template_localized() {
+        let strings = Strings::new(crate::Language::Thai);
+        let fields = HeaderFooterField::parse_template_localized("{page_of_total}", &strings);
+        assert_eq!(fields.len(), 4);
+        assert!(matches!(&fields[0], HeaderFooterField::Text(t) if t == "หน้า "));
+        assert!(matches!(fields[1], HeaderFooterField::PageNumber));
+        assert!(matches!(&fields[2], HeaderFooterField::Text(t) if t == " จาก "));
+        assert!(matches!(fields[3], HeaderFooterField::TotalPages));
+
+        // Overridden wording, and no expansion without strings
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert(Label::PageOfTotal, "{page}/{pages}".to_string());
+        let strings = Strings::new(crate::Language::English).with_overrides(overrides);
+        let fields = HeaderFooterField::parse_template_localized("{page_of_total}", &strings);
+        assert!(matches!(&fields[1], HeaderFooterField::Text(t) if t == "/"));
+        let fields = HeaderFooterField::parse_template("{page_of_total}");
+        assert!(matches!(&fields[..], [HeaderFooterField::Text(t)] if t == "{page_of_total}"));
+    }
 }
@@ -9,14 +9,15 @@ mod latent_styles;
 pub(crate) mod numbering;
 mod rels;
 mod styles;
+pub(crate) mod xml_writer;
 
 // Re-export types for internal use within the crate
 pub(crate) use content_types::ContentTypes;
 pub(crate) use doc_props::*;
 pub(crate) use document::{
     DocElement, DocumentXml, HeaderFooterRefs, Hyperlink, ImageBorderEffect, ImageEffectExtent,
-    ImageElement, ImageShadowEffect, PageLayout, ParagraphChild, Table, TableCellElement, TableRow,
-    TableWidth,
+    ImageElement, ImageShadowEffect, PageLayout, ParagraphChild, Revision, RevisionKind, Table,
+    TableCellElement, TableRow, TableWidth, SECTION_BREAKS,
 };
 pub(crate) use endnotes::*;
 pub(crate) use footer::*;
@@ -24,12 +25,13 @@ pub(crate) use header::*;
 pub(crate) use rels::Relationships;
 pub(crate) use styles::{
     generate_font_table_xml, generate_settings_xml, generate_theme_xml, generate_web_settings_xml,
-    StylesDocument,
+    heading_style_id, Style, StyleType, StylesDocument,
 };
 
 // Public API exports
 pub use document::{Paragraph, Run, TabStop};
 pub use footer::FooterConfig;
-pub use footnotes::FootnotesXml;
-pub use header::{HeaderConfig, HeaderFooterField};
-pub use styles::{FontConfig, Language};
+pub use footnotes::{FootnotesXml, NoteNumbering, NoteRestart};
+pub use header::{HeaderConfig, HeaderFooterField, HeaderFooterImage};
+pub use numbering::NumberingConfig;
+pub use styles::{FontConfig, Language, ParagraphPreset, StyleGallery, StyleSpacing};
@@ -9,10 +9,68 @@ use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::Writer;
 use std::io::Cursor;
 
+/// Word number formats accepted for ordered-list levels
+pub(crate) const ORDERED_FORMATS: &[&str] = &[
+    "decimal",
+    "lowerLetter",
+    "upperLetter",
+    "lowerRoman",
+    "upperRoman",
+    "thaiLetters",
+    "thaiNumbers",
+    "thaiCounting",
+];
+
+/// Default bullets per level, with fonts available on all Windows systems:
+/// - Level 0,3,6: Symbol font char \xF0B7 (solid bullet)
+/// - Level 1,4,7: Courier New "o" (open bullet)
+/// - Level 2,5,8: Wingdings char \xF0A7 (solid square)
+const DEFAULT_BULLETS: [(&str, &str); 3] = [
+    ("\u{F0B7}", "Symbol"),
+    ("o", "Courier New"),
+    ("\u{F0A7}", "Wingdings"),
+];
+
+/// List formats per nesting level
+///
+/// Levels beyond the end of a list reuse it from the start, so
+/// `["decimal", "lowerLetter"]` alternates 1, a, 1, a... Empty lists keep
+/// the built-in formats.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumberingConfig {
+    /// Word number formats for ordered lists (see [`ORDERED_FORMATS`])
+    pub ordered: Vec<String>,
+    /// Bullet glyphs for unordered lists, drawn in the paragraph font
+    pub bullets: Vec<String>,
+    /// Word number format for page numbers (`w:pgNumType w:fmt`), e.g. "thaiNumbers"
+    pub pages: Option<String>,
+}
+
+impl NumberingConfig {
+    /// Number format for ordered-list level `ilvl`
+    pub(crate) fn ordered_format(&self, ilvl: u32) -> &str {
+        if self.ordered.is_empty() {
+            "decimal"
+        } else {
+            &self.ordered[ilvl as usize % self.ordered.len()]
+        }
+    }
+
+    /// Bullet glyph and its font (if any) for unordered-list level `ilvl`
+    fn bullet(&self, ilvl: u32) -> (&str, Option<&str>) {
+        if self.bullets.is_empty() {
+            let (glyph, font) = DEFAULT_BULLETS[ilvl as usize % DEFAULT_BULLETS.len()];
+            (glyph, Some(font))
+        } else {
+            (&self.bullets[ilvl as usize % self.bullets.len()], None)
+        }
+    }
+}
+
 /// Generate numbering.xml content with dynamic list instances
 ///
 /// This creates:
-/// - abstractNumId 1: Ordered list (1, 2, 3...)
+/// - abstractNumId 1: Ordered list (1, 2, 3... unless `config` says otherwise)
 /// - abstractNumId 2: Unordered list (bullet •)
 ///
 /// Each list in the document gets its own unique numId that references
@@ -20,6 +78,7 @@ use std::io::Cursor;
 /// This ensures each list restarts numbering independently.
 pub(crate) fn generate_numbering_xml_with_context(
     numbering_ctx: &NumberingContext,
+    config: &NumberingConfig,
 ) -> Result<Vec<u8>> {
     let buffer = Cursor::new(Vec::new());
     let mut writer = Writer::new(buffer);
@@ -43,11 +102,11 @@ pub(crate) fn generate_numbering_xml_with_context(
     ));
     writer.write_event(Event::Start(root))?;
 
-    // Abstract numbering 1: Ordered list (decimal)
-    write_abstract_num_ordered(&mut writer, 1)?;
+    // Abstract numbering 1: Ordered list
+    write_abstract_num_ordered(&mut writer, 1, config)?;
 
     // Abstract numbering 2: Unordered list (bullet)
-    write_abstract_num_bullet(&mut writer, 2)?;
+    write_abstract_num_bullet(&mut writer, 2, config)?;
 
     // Generate a <w:num> for each list in the document
     // Each numId references abstractNumId 1 (ordered) or 2 (unordered)
@@ -94,10 +153,10 @@ pub fn generate_numbering_xml() -> Result<Vec<u8>> {
     writer.write_event(Event::Start(root))?;
 
     // Abstract numbering 1: Ordered list (decimal)
-    write_abstract_num_ordered(&mut writer, 1)?;
+    write_abstract_num_ordered(&mut writer, 1, &NumberingConfig::default())?;
 
     // Abstract numbering 2: Unordered list (bullet)
-    write_abstract_num_bullet(&mut writer, 2)?;
+    write_abstract_num_bullet(&mut writer, 2, &NumberingConfig::default())?;
 
     // Num 1 references abstract 1 (ordered)
     write_num(&mut writer, 1, 1)?;
@@ -111,7 +170,11 @@ pub fn generate_numbering_xml() -> Result<Vec<u8>> {
 }
 
 /// Write abstract numbering definition for ordered lists
-fn write_abstract_num_ordered<W: std::io::Write>(writer: &mut Writer<W>, id: u32) -> Result<()> {
+fn write_abstract_num_ordered<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    id: u32,
+    config: &NumberingConfig,
+) -> Result<()> {
     let mut elem = BytesStart::new("w:abstractNum");
     elem.push_attribute(("w:abstractNumId", id.to_string().as_str()));
     writer.write_event(Event::Start(elem))?;
@@ -139,7 +202,7 @@ fn write_abstract_num_ordered<W: std::io::Write>(writer: &mut Writer<W>, id: u32
 
     // Define levels 0-8 for nesting
     for ilvl in 0..9u32 {
-        write_ordered_level(writer, ilvl)?;
+        write_ordered_level(writer, ilvl, config.ordered_format(ilvl))?;
     }
 
     writer.write_event(Event::End(BytesEnd::new("w:abstractNum")))?;
@@ -147,7 +210,11 @@ fn write_abstract_num_ordered<W: std::io::Write>(writer: &mut Writer<W>, id: u32
 }
 
 /// Write a single level for ordered list
-fn write_ordered_level<W: std::io::Write>(writer: &mut Writer<W>, ilvl: u32) -> Result<()> {
+fn write_ordered_level<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    ilvl: u32,
+    num_fmt: &str,
+) -> Result<()> {
     let mut lvl = BytesStart::new("w:lvl");
     lvl.push_attribute(("w:ilvl", ilvl.to_string().as_str()));
     writer.write_event(Event::Start(lvl))?;
@@ -157,9 +224,9 @@ fn write_ordered_level<W: std::io::Write>(writer: &mut Writer<W>, ilvl: u32) ->
     start.push_attribute(("w:val", "1"));
     writer.write_event(Event::Empty(start))?;
 
-    // Number format: decimal
+    // Number format: decimal, lowerLetter, thaiLetters, ...
     let mut fmt = BytesStart::new("w:numFmt");
-    fmt.push_attribute(("w:val", "decimal"));
+    fmt.push_attribute(("w:val", num_fmt));
     writer.write_event(Event::Empty(fmt))?;
 
     // Level text: "%1" for level 0, "%2" for level 1, etc. (without the dot since we add suffix)
@@ -196,7 +263,11 @@ fn write_ordered_level<W: std::io::Write>(writer: &mut Writer<W>, ilvl: u32) ->
 }
 
 /// Write abstract numbering definition for bullet lists
-fn write_abstract_num_bullet<W: std::io::Write>(writer: &mut Writer<W>, id: u32) -> Result<()> {
+fn write_abstract_num_bullet<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    id: u32,
+    config: &NumberingConfig,
+) -> Result<()> {
     let mut elem = BytesStart::new("w:abstractNum");
     elem.push_attribute(("w:abstractNumId", id.to_string().as_str()));
     writer.write_event(Event::Start(elem))?;
@@ -223,23 +294,8 @@ fn write_abstract_num_bullet<W: std::io::Write>(writer: &mut Writer<W>, id: u32)
     writer.write_event(Event::Empty(tmpl))?;
 
     // Define levels 0-8 for nesting
-    // Use Word's standard bullet characters with fonts available on all Windows systems:
-    // - Level 0,3,6: Symbol font char \xF0B7 (solid bullet)
-    // - Level 1,4,7: Courier New "o" (open bullet)
-    // - Level 2,5,8: Wingdings char \xF0A7 (solid square)
-    let bullets: [(&str, &str); 9] = [
-        ("\u{F0B7}", "Symbol"),
-        ("o", "Courier New"),
-        ("\u{F0A7}", "Wingdings"),
-        ("\u{F0B7}", "Symbol"),
-        ("o", "Courier New"),
-        ("\u{F0A7}", "Wingdings"),
-        ("\u{F0B7}", "Symbol"),
-        ("o", "Courier New"),
-        ("\u{F0A7}", "Wingdings"),
-    ];
     for ilvl in 0..9u32 {
-        let (bullet_char, bullet_font) = bullets[ilvl as usize];
+        let (bullet_char, bullet_font) = config.bullet(ilvl);
         write_bullet_level(writer, ilvl, bullet_char, bullet_font)?;
     }
 
@@ -252,7 +308,7 @@ fn write_bullet_level<W: std::io::Write>(
     writer: &mut Writer<W>,
     ilvl: u32,
     bullet: &str,
-    font: &str,
+    font: Option<&str>,
 ) -> Result<()> {
     let mut lvl = BytesStart::new("w:lvl");
     lvl.push_attribute(("w:ilvl", ilvl.to_string().as_str()));
@@ -297,17 +353,19 @@ fn write_bullet_level<W: std::io::Write>(
     writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
 
     // Run properties (font for bullet character)
-    writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
-
-    // Use the appropriate font for each bullet style
-    // Symbol, Courier New, and Wingdings are all built-in on Windows
-    let mut fonts = BytesStart::new("w:rFonts");
-    fonts.push_attribute(("w:ascii", font));
-    fonts.push_attribute(("w:hAnsi", font));
-    fonts.push_attribute(("w:hint", "default"));
-    writer.write_event(Event::Empty(fonts))?;
-
-    writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
+    // Symbol, Courier New, and Wingdings are all built-in on Windows;
+    // custom glyphs use the paragraph font
+    if let Some(font) = font {
+        writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
+
+        let mut fonts = BytesStart::new("w:rFonts");
+        fonts.push_attribute(("w:ascii", font));
+        fonts.push_attribute(("w:hAnsi", font));
+        fonts.push_attribute(("w:hint", "default"));
+        writer.write_event(Event::Empty(fonts))?;
+
+        writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
+    }
 
     writer.write_event(Event::End(BytesEnd::new("w:lvl")))?;
     Ok(())
@@ -374,4 +432,28 @@ mod tests {
         // Check bullet format
         assert!(xml_str.contains("w:val=\"bullet\""));
     }
+
+    #[test]
+    fn test_numbering_config_formats() {
+        let mut ctx = NumberingContext::new();
+        ctx.add_list(true, &mut crate::docx::rels_manager::RelIdManager::new());
+        let config = NumberingConfig {
+            ordered: vec!["thaiLetters".to_string(), "thaiNumbers".to_string()],
+            bullets: vec!["\u{2022}".to_string()],
+            pages: None,
+        };
+        let xml = generate_numbering_xml_with_context(&ctx, &config).unwrap();
+        let xml_str = String::from_utf8(xml).unwrap();
+
+        assert!(xml_str.contains(
+            "<w:lvl w:ilvl=\"0\"><w:start w:val=\"1\"/><w:numFmt w:val=\"thaiLetters\"/>"
+        ));
+        assert!(xml_str.contains(
+            "<w:lvl w:ilvl=\"2\"><w:start w:val=\"1\"/><w:numFmt w:val=\"thaiLetters\"/>"
+        ));
+        assert!(xml_str.contains("<w:numFmt w:val=\"thaiNumbers\"/>"));
+        assert!(!xml_str.contains("w:val=\"decimal\""));
+        assert!(xml_str.contains("<w:lvlText w:val=\"\u{2022}\"/>"));
+        assert!(!xml_str.contains("Wingdings"));
+    }
 }
@@ -293,6 +293,16 @@ impl Relationships {
         });
     }
 
+    /// Whether there are no relationships
+    pub fn is_empty(&self) -> bool {
+        self.rels.is_empty()
+    }
+
+    /// Order relationships by ID instead of insertion
+    pub fn sort(&mut self) {
+        self.rels.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+
     /// Generate XML content
     pub fn to_xml(&self) -> Result<Vec<u8>> {
         let mut writer = Writer::new(Cursor::new(Vec::new()));
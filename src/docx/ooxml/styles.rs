@@ -2,9 +2,12 @@
 
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::Writer;
+use std::collections::HashMap;
 use std::io::Cursor;
 
+use super::footnotes::{NoteNumbering, NoteRestart};
 use crate::error::Result;
+use crate::i18n::strings::{default_label, Label};
 
 /// kompleks script size in half-points (14pt = 28)
 #[allow(dead_code)]
@@ -23,20 +26,174 @@ pub struct FontConfig {
     pub code_size: Option<u32>,
 }
 
+/// Paragraph spacing of one style, set with `[styles.<StyleId>]`
+///
+/// Unset values keep the built-in spacing of the style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StyleSpacing {
+    /// Space before the paragraph, in twips
+    pub before: Option<u32>,
+    /// Space after the paragraph, in twips
+    pub after: Option<u32>,
+    /// Line spacing in 240ths of a line (240 = single, 360 = 1.5 lines)
+    pub line: Option<u32>,
+}
+
+/// Body text layout preset, set with `[document] paragraph_preset`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParagraphPreset {
+    /// Built-in BodyText: 0.5" first-line indent, 12pt after, left aligned
+    #[default]
+    Default,
+    /// Thai government format: 1.25cm first-line indent, no space between
+    /// paragraphs and Thai distributed justification
+    ThaiOfficial,
+}
+
+impl ParagraphPreset {
+    /// Preset from its config name (`default` or `thai-official`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "" | "default" => Some(Self::Default),
+            "thai-official" => Some(Self::ThaiOfficial),
+            _ => None,
+        }
+    }
+}
+
 /// Language setting for default fonts
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Language {
     #[default]
     English,
     Thai,
+    Lao,
+    Khmer,
+    Myanmar,
+    Vietnamese,
+    Chinese,
+    Japanese,
+    Korean,
+    Arabic,
+    Hebrew,
 }
 
 impl Language {
+    /// Parse a language code or name such as "th", "thai", "km-KH" or "Khmer"
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+        let primary = code.split(['-', '_']).next().unwrap_or_default();
+        match primary {
+            "en" | "english" => Some(Language::English),
+            "th" | "thai" => Some(Language::Thai),
+            "lo" | "lao" => Some(Language::Lao),
+            "km" | "khmer" => Some(Language::Khmer),
+            "my" | "myanmar" | "burmese" => Some(Language::Myanmar),
+            "vi" | "vietnamese" => Some(Language::Vietnamese),
+            "zh" | "chinese" => Some(Language::Chinese),
+            "ja" | "japanese" => Some(Language::Japanese),
+            "ko" | "korean" => Some(Language::Korean),
+            "ar" | "arabic" => Some(Language::Arabic),
+            "he" | "iw" | "hebrew" => Some(Language::Hebrew),
+            _ => None,
+        }
+    }
+
+    /// Language tag written to `w:lang`
+    pub fn lang_tag(&self) -> &'static str {
+        match self {
+            Language::English => "en-US",
+            Language::Thai => "th-TH",
+            Language::Lao => "lo-LA",
+            Language::Khmer => "km-KH",
+            Language::Myanmar => "my-MM",
+            Language::Vietnamese => "vi-VN",
+            Language::Chinese => "zh-CN",
+            Language::Japanese => "ja-JP",
+            Language::Korean => "ko-KR",
+            Language::Arabic => "ar-SA",
+            Language::Hebrew => "he-IL",
+        }
+    }
+
+    /// Whether text in this language is written in a complex script
+    pub fn is_complex_script(&self) -> bool {
+        matches!(
+            self,
+            Language::Thai
+                | Language::Lao
+                | Language::Khmer
+                | Language::Myanmar
+                | Language::Arabic
+                | Language::Hebrew
+        )
+    }
+
+    /// Whether this language is written right-to-left
+    pub fn is_rtl(&self) -> bool {
+        matches!(self, Language::Arabic | Language::Hebrew)
+    }
+
+    /// Whether this is a CJK language, set in the `w:eastAsia` slots
+    pub fn is_east_asian(&self) -> bool {
+        matches!(
+            self,
+            Language::Chinese | Language::Japanese | Language::Korean
+        )
+    }
+
+    /// Language tag for Latin text (`w:val`)
+    ///
+    /// Complex-script and CJK languages mix in English, so their Latin slot
+    /// stays en-US.
+    pub fn latin_lang_tag(&self) -> &'static str {
+        if self.is_complex_script() || self.is_east_asian() {
+            "en-US"
+        } else {
+            self.lang_tag()
+        }
+    }
+
+    /// Language tag for the complex-script (`w:bidi`) slot
+    ///
+    /// Latin-script languages keep Thai so mixed Thai text still shapes correctly.
+    pub fn cs_lang_tag(&self) -> &'static str {
+        if self.is_complex_script() {
+            self.lang_tag()
+        } else {
+            "th-TH"
+        }
+    }
+
+    /// Language tag for the East Asian (`w:eastAsia`) slot
+    pub fn east_asia_lang_tag(&self) -> &'static str {
+        if self.is_east_asian() {
+            self.lang_tag()
+        } else {
+            self.cs_lang_tag()
+        }
+    }
+
+    /// Get default East Asian font (`w:rFonts w:eastAsia`) for CJK languages
+    pub fn default_east_asia_font(&self) -> Option<&'static str> {
+        match self {
+            Language::Chinese => Some("Microsoft YaHei"),
+            Language::Japanese => Some("Yu Gothic"),
+            Language::Korean => Some("Malgun Gothic"),
+            _ => None,
+        }
+    }
+
     /// Get default ASCII font for this language
     pub fn default_ascii_font(&self) -> &'static str {
         match self {
-            Language::English => "Calibri",
+            Language::English | Language::Vietnamese => "Calibri",
+            Language::Chinese | Language::Japanese | Language::Korean => "Calibri",
+            Language::Arabic | Language::Hebrew => "Calibri",
             Language::Thai => "TH Sarabun New",
+            Language::Lao => "Phetsarath OT",
+            Language::Khmer => "Khmer OS",
+            Language::Myanmar => "Myanmar Text",
         }
     }
 
@@ -45,40 +202,88 @@ impl Language {
         match self {
             // Use TH Sarabun New for CS font even in English mode
             // This ensures mixed Thai text in English documents renders correctly
-            Language::English => "TH Sarabun New",
+            Language::English | Language::Vietnamese => "TH Sarabun New",
+            Language::Chinese | Language::Japanese | Language::Korean => "TH Sarabun New",
             Language::Thai => "TH Sarabun New",
+            Language::Lao => "Phetsarath OT",
+            Language::Khmer => "Khmer OS",
+            Language::Myanmar => "Myanmar Text",
+            Language::Arabic => "Arial",
+            Language::Hebrew => "David",
         }
     }
 
     /// Get default font size in half-points
     pub fn default_font_size(&self) -> u32 {
         match self {
-            Language::English => 22, // 11pt
-            Language::Thai => 28,    // 14pt
+            Language::English => 22,                      // 11pt
+            Language::Thai => 28,                         // 14pt
+            Language::Lao | Language::Vietnamese => 24,   // 12pt
+            Language::Khmer | Language::Myanmar => 22,    // 11pt
+            Language::Chinese | Language::Japanese => 21, // 10.5pt
+            Language::Korean => 20,                       // 10pt
+            Language::Arabic => 24,                       // 12pt
+            Language::Hebrew => 22,                       // 11pt
         }
     }
 
     /// Get default complex script size in half-points
     pub fn default_cs_size(&self) -> u32 {
         match self {
-            Language::English => 22, // 11pt
-            Language::Thai => 28,    // 14pt
+            Language::English => 22,                      // 11pt
+            Language::Thai => 28,                         // 14pt
+            Language::Lao | Language::Vietnamese => 24,   // 12pt
+            Language::Khmer | Language::Myanmar => 22,    // 11pt
+            Language::Chinese | Language::Japanese => 21, // 10.5pt
+            Language::Korean => 20,                       // 10pt
+            Language::Arabic => 24,                       // 12pt
+            Language::Hebrew => 22,                       // 11pt
         }
     }
 
     /// Get localized table caption prefix
     pub fn table_caption_prefix(&self) -> &'static str {
-        match self {
-            Language::English => "Table",
-            Language::Thai => "ตารางที่",
-        }
+        default_label(*self, Label::Table)
     }
 
     /// Get localized figure/image caption prefix
     pub fn figure_caption_prefix(&self) -> &'static str {
+        default_label(*self, Label::Figure)
+    }
+
+    /// Get localized chapter label for cross-references ("Chapter 2", "第2章")
+    pub fn chapter_label(&self, num: &str) -> String {
+        default_label(*self, Label::Chapter).replace("{n}", num)
+    }
+
+    /// Get localized appendix prefix for cross-references
+    pub fn appendix_prefix(&self) -> &'static str {
+        default_label(*self, Label::Appendix)
+    }
+
+    /// Get localized table of contents title
+    pub fn toc_title(&self) -> &'static str {
+        default_label(*self, Label::TableOfContents)
+    }
+
+    /// Characters that must not end (`after`) or start (`before`) a line
+    ///
+    /// Word's kinsoku rules for CJK text; `None` for other languages.
+    pub fn kinsoku(&self) -> Option<(&'static str, &'static str)> {
         match self {
-            Language::English => "Figure",
-            Language::Thai => "รูปที่",
+            Language::Chinese => Some((
+                "([{·‘“〈《「『【〔〖（．［｛￡￥",
+                "!%),.:;>?]}¢¨°·ˇˉ―‖’”…‰′″›℃∶、。〃〉》」』】〕〗〞︶︺︾﹀﹄﹚﹜﹞！＂％＇），．：；？］｀｜｝～￠",
+            )),
+            Language::Japanese => Some((
+                "$([\\{£¥‘“〈《「『【〔＄（［｛｢￡￥",
+                "!%),.:;?]}¢°’”‰′″℃、。々〉》」』】〕゛゜ゝゞ・ヽヾ！％），．：；？］｝｡｣､･ﾞﾟ￠",
+            )),
+            Language::Korean => Some((
+                "$([\\{£¥‘“〈《「『【〔＄（［｛￦",
+                "!%),.:;?]}¢°’”′″℃〉》」』】〕！％），．：；？］｝￠",
+            )),
+            _ => None,
         }
     }
 }
@@ -105,6 +310,48 @@ impl StyleType {
     }
 }
 
+/// Which styles Word offers to people editing the document, set with
+/// `[gallery]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleGallery {
+    /// Styles shown in the Quick Styles gallery, by id ("BodyText") or name
+    /// ("Body Text"); every defined style when `None`
+    pub styles: Option<Vec<String>>,
+    /// Hide Word's other built-in styles from the gallery and Styles pane
+    /// until they are used
+    pub hide_builtin: bool,
+    /// Lock Word's other built-in styles, so they can't be applied while
+    /// formatting restrictions are on
+    pub lock_builtin: bool,
+}
+
+impl StyleGallery {
+    /// Whether `name` refers to `style` by id or name
+    fn names(name: &str, style: &Style) -> bool {
+        name.replace(' ', "").eq_ignore_ascii_case(&style.id)
+            || name.trim().eq_ignore_ascii_case(&style.name)
+    }
+
+    /// Whether a defined style is shown in the Quick Styles gallery
+    fn shows(&self, style: &Style) -> bool {
+        self.styles
+            .as_ref()
+            .is_none_or(|names| names.iter().any(|name| Self::names(name, style)))
+    }
+}
+
+/// Style id of a heading level: Heading1-Heading6, deeper levels use Heading6
+pub(crate) fn heading_style_id(level: u8) -> &'static str {
+    match level {
+        0 | 1 => "Heading1",
+        2 => "Heading2",
+        3 => "Heading3",
+        4 => "Heading4",
+        5 => "Heading5",
+        _ => "Heading6",
+    }
+}
+
 /// Tab stop definition
 #[derive(Debug, Clone)]
 pub struct TabStop {
@@ -147,11 +394,15 @@ pub struct Style {
     pub spacing_after: Option<u32>,
     pub indent_left: Option<u32>,       // In twips
     pub indent_first_line: Option<u32>, // First-line indent in twips
-    pub contextual_spacing: bool, // Ignore spacing between same styles
+    pub contextual_spacing: bool,       // Ignore spacing between same styles
+    pub bidi: Option<bool>,             // Paragraph direction override (true = right-to-left)
+    pub vanish: bool,                   // Hidden text (w:vanish), not shown or printed
     pub hidden: bool,
     pub semi_hidden: bool,
     pub unhide_when_used: bool,
-    pub tabs: Vec<TabStop>, // Tab stops for paragraph styles
+    pub line_spacing: Option<u32>, // In 240ths of a line (lineRule="auto")
+    pub tabs: Vec<TabStop>,        // Tab stops for paragraph styles
+    pub justification: Option<String>, // Paragraph alignment (w:jc)
 }
 
 impl Style {
@@ -175,13 +426,17 @@ impl Style {
             outline_level: None,
             spacing_before: None,
             spacing_after: None,
+            line_spacing: None,
             indent_left: None,
             indent_first_line: None,
             contextual_spacing: false,
+            bidi: None,
+            vanish: false,
             hidden: false,
             semi_hidden: false,
             unhide_when_used: false,
             tabs: Vec::new(),
+            justification: None,
         }
     }
 
@@ -279,6 +534,18 @@ impl Style {
         self
     }
 
+    /// Set paragraph direction (right-to-left when enabled)
+    pub fn bidi(mut self, enabled: bool) -> Self {
+        self.bidi = Some(enabled);
+        self
+    }
+
+    /// Hide the text when viewing and printing
+    pub fn vanish(mut self) -> Self {
+        self.vanish = true;
+        self
+    }
+
     /// Set as hidden
     #[allow(dead_code)]
     pub fn hidden(mut self) -> Self {
@@ -322,6 +589,10 @@ pub(crate) struct StylesDocument {
     header_template_tabs: Option<Vec<(u32, String)>>,
     /// Tab stops from header-footer.docx template's Footer style (overrides computed)
     footer_template_tabs: Option<Vec<(u32, String)>>,
+    /// Widow/orphan control for all paragraphs (w:widowControl in pPrDefault)
+    widow_control: bool,
+    /// Quick Styles gallery and built-in style visibility
+    gallery: StyleGallery,
 }
 
 impl StylesDocument {
@@ -335,6 +606,8 @@ impl StylesDocument {
             margin_right: None,
             header_template_tabs: None,
             footer_template_tabs: None,
+            widow_control: true,
+            gallery: StyleGallery::default(),
         };
         doc.add_default_styles();
         doc
@@ -357,11 +630,38 @@ impl StylesDocument {
             margin_right,
             header_template_tabs: None,
             footer_template_tabs: None,
+            widow_control: true,
+            gallery: StyleGallery::default(),
         };
         doc.add_default_styles();
         doc
     }
 
+    /// The paragraph, character and table styles, in definition order
+    pub(crate) fn styles(&self) -> &[Style] {
+        &self.styles
+    }
+
+    /// Turn widow/orphan control for all paragraphs on or off (on by default)
+    pub fn set_widow_control(&mut self, enabled: bool) {
+        self.widow_control = enabled;
+    }
+
+    /// Limit the Quick Styles gallery and hide or lock Word's built-in styles
+    ///
+    /// Returns the gallery entries that name no style.
+    pub fn set_gallery(&mut self, gallery: StyleGallery) -> Vec<String> {
+        let unknown = gallery
+            .styles
+            .iter()
+            .flatten()
+            .filter(|name| !self.styles.iter().any(|s| StyleGallery::names(name, s)))
+            .cloned()
+            .collect();
+        self.gallery = gallery;
+        unknown
+    }
+
     /// Set the tab stops from a header-footer.docx template's styles.
     /// When set, these override the computed tab positions for Header/Footer styles.
     pub fn set_template_tabs(
@@ -380,6 +680,85 @@ impl StylesDocument {
         self.add_default_styles();
     }
 
+    /// Adjust the TOC1..TOCn styles to the `[toc]` layout options
+    ///
+    /// Adds styles for levels beyond TOC3 up to `depth`, applies per-level
+    /// indents and sets the page number tab stop (or removes it when page
+    /// numbers are not right-aligned).
+    pub fn apply_toc_layout(&mut self, toc: &crate::docx::toc::TocConfig) {
+        use crate::docx::toc::TocPageNumbers;
+
+        for level in 4..=toc.depth.min(9) {
+            let id = format!("TOC{}", level);
+            if self.styles.iter().any(|s| s.id == id) {
+                continue;
+            }
+            if let Some(mut style) = self.styles.iter().find(|s| s.id == "TOC3").cloned() {
+                style.id = id;
+                style.name = format!("toc {}", level);
+                style.indent_left = Some(440 * (level as u32 - 1));
+                self.styles.push(style);
+            }
+        }
+
+        for style in self.styles.iter_mut() {
+            let Some(level) = style
+                .id
+                .strip_prefix("TOC")
+                .and_then(|n| n.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            if let Some(&Some(indent)) = toc.indents.get(level - 1) {
+                style.indent_left = Some(indent);
+            }
+            if toc.page_numbers != TocPageNumbers::Right {
+                style.tabs.clear();
+            } else if !toc.dot_leader {
+                for tab in &mut style.tabs {
+                    tab.leader = None;
+                }
+            }
+        }
+    }
+
+    /// Lay out the BodyText style as `preset` asks
+    ///
+    /// Runs before [`apply_spacing`](Self::apply_spacing), so `[styles.BodyText]`
+    /// spacing still wins over the preset's.
+    pub fn apply_paragraph_preset(&mut self, preset: ParagraphPreset) {
+        if preset == ParagraphPreset::Default {
+            return;
+        }
+        if let Some(style) = self.styles.iter_mut().find(|s| s.id == "BodyText") {
+            style.spacing_before = Some(0);
+            style.spacing_after = Some(0);
+            style.indent_first_line = Some(709); // 1.25cm
+            style.justification = Some("thaiDistribute".to_string());
+        }
+    }
+
+    /// Apply `[styles]` spacing to the paragraph styles it names by id
+    ///
+    /// Returns the ids that match no style, sorted.
+    pub fn apply_spacing<'s>(
+        &mut self,
+        spacing: &'s HashMap<String, StyleSpacing>,
+    ) -> Vec<&'s str> {
+        let mut unknown = Vec::new();
+        for (id, spacing) in spacing {
+            let Some(style) = self.styles.iter_mut().find(|s| &s.id == id) else {
+                unknown.push(id.as_str());
+                continue;
+            };
+            style.spacing_before = spacing.before.or(style.spacing_before);
+            style.spacing_after = spacing.after.or(style.spacing_after);
+            style.line_spacing = spacing.line.or(style.line_spacing);
+        }
+        unknown.sort_unstable();
+        unknown
+    }
+
     /// Compute the text area width in twips (page_width - left_margin - right_margin)
     /// Falls back to A4 defaults: 11906 - 1440 - 1440 = 9026
     fn text_area_width(&self) -> u32 {
@@ -443,8 +822,11 @@ impl StylesDocument {
             .as_ref()
             .and_then(|c| c.caption_size)
             .unwrap_or(match self.lang {
-                Language::English => 18, // 9pt
-                Language::Thai => 24,    // 12pt
+                Language::English | Language::Khmer | Language::Myanmar => 18, // 9pt
+                Language::Chinese | Language::Japanese | Language::Korean => 18, // 9pt
+                Language::Lao | Language::Vietnamese => 20,                    // 10pt
+                Language::Arabic | Language::Hebrew => 20,                     // 10pt
+                Language::Thai => 24,                                          // 12pt
             })
     }
 
@@ -508,12 +890,18 @@ impl StylesDocument {
 
         // Title style (cover page title)
         let (title_font, title_size, title_cs_size) = match self.lang {
-            Language::English => (
+            Language::English
+            | Language::Vietnamese
+            | Language::Chinese
+            | Language::Japanese
+            | Language::Korean
+            | Language::Arabic
+            | Language::Hebrew => (
                 format!("{} Light", ascii_font),
                 normal_size * 2 + 14,
                 normal_size * 2 + 14,
             ),
-            Language::Thai => (
+            Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => (
                 cs_font.clone(),
                 normal_size + 44, // Relative to normal (e.g. 28 + 44 = 72)
                 normal_size + 44,
@@ -545,12 +933,18 @@ impl StylesDocument {
 
         // Heading1 style
         let (h1_font, h1_size, h1_cs_size) = match self.lang {
-            Language::English => (
+            Language::English
+            | Language::Vietnamese
+            | Language::Chinese
+            | Language::Japanese
+            | Language::Korean
+            | Language::Arabic
+            | Language::Hebrew => (
                 format!("{} Light", ascii_font),
                 normal_size + 10,
                 normal_size + 18,
             ),
-            Language::Thai => (
+            Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => (
                 cs_font.clone(),
                 normal_size + 12, // Relative: 28 + 12 = 40 (20pt)
                 normal_size + 12,
@@ -572,12 +966,18 @@ impl StylesDocument {
 
         // Heading2 style
         let (h2_font, h2_size, h2_cs_size) = match self.lang {
-            Language::English => (
+            Language::English
+            | Language::Vietnamese
+            | Language::Chinese
+            | Language::Japanese
+            | Language::Korean
+            | Language::Arabic
+            | Language::Hebrew => (
                 format!("{} Light", ascii_font),
                 normal_size + 4,
                 normal_size + 10,
             ),
-            Language::Thai => (
+            Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => (
                 cs_font.clone(),
                 normal_size + 4, // Relative: 28 + 4 = 32 (16pt)
                 normal_size + 4,
@@ -599,12 +999,18 @@ impl StylesDocument {
 
         // Heading3 style
         let (h3_font, h3_size, h3_cs_size) = match self.lang {
-            Language::English => (
+            Language::English
+            | Language::Vietnamese
+            | Language::Chinese
+            | Language::Japanese
+            | Language::Korean
+            | Language::Arabic
+            | Language::Hebrew => (
                 format!("{} Light", ascii_font),
                 normal_size + 2,
                 normal_size + 6,
             ),
-            Language::Thai => (
+            Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => (
                 cs_font.clone(),
                 normal_size, // Relative: 28 (14pt)
                 normal_size,
@@ -626,8 +1032,14 @@ impl StylesDocument {
 
         // Heading4 style
         let (h4_size, h4_cs_size) = match self.lang {
-            Language::English => (normal_size, normal_size + 6),
-            Language::Thai => (
+            Language::English
+            | Language::Vietnamese
+            | Language::Chinese
+            | Language::Japanese
+            | Language::Korean
+            | Language::Arabic
+            | Language::Hebrew => (normal_size, normal_size + 6),
+            Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => (
                 if normal_size > 2 {
                     normal_size - 2
                 } else {
@@ -654,18 +1066,43 @@ impl StylesDocument {
                 .spacing(200, 80), // 10pt before, 4pt after
         );
 
-        // Code style (code blocks)
+        // Heading5 and Heading6 styles (same size as Heading4)
         self.add_style(
-            Style::new("Code", "Code", StyleType::Paragraph)
-                .ui_priority(99)
-                .based_on("Normal")
-                .font(&code_font, &code_font, &code_font)
-                .size(code_size)
-                .size_cs(code_size)
-                .spacing(120, 120) // 6pt before/after
-                .contextual_spacing(true) // Merge spacing between code lines
-                .indent(240), // Left indent for the block
+            Style::new("Heading5", "Heading 5", StyleType::Paragraph)
+                .ui_priority(9)
+                .based_on("Heading4")
+                .next("Normal")
+                .color(&h1_color)
+                .outline_level(4)
+                .spacing(160, 60), // 8pt before, 3pt after
         );
+        self.add_style(
+            Style::new("Heading6", "Heading 6", StyleType::Paragraph)
+                .ui_priority(9)
+                .based_on("Heading5")
+                .next("Normal")
+                .color(&h1_color)
+                .outline_level(5)
+                .spacing(120, 60), // 6pt before, 3pt after
+        );
+
+        // Code style (code blocks)
+        let code_style = Style::new("Code", "Code", StyleType::Paragraph)
+            .ui_priority(99)
+            .based_on("Normal")
+            .font(&code_font, &code_font, &code_font)
+            .size(code_size)
+            .size_cs(code_size)
+            .spacing(120, 120) // 6pt before/after
+            .contextual_spacing(true) // Merge spacing between code lines
+            .indent(240); // Left indent for the block
+
+        // Code stays left-to-right even in RTL documents
+        self.add_style(if self.lang.is_rtl() {
+            code_style.bidi(false)
+        } else {
+            code_style
+        });
 
         // CodeChar style (inline code)
         self.add_style(
@@ -677,6 +1114,14 @@ impl StylesDocument {
                 .color("D63384"), // Pinkish for code
         );
 
+        // Kbd style (keyboard keys and UI elements, drawn as a small box)
+        self.add_style(
+            Style::new("Kbd", "Keyboard", StyleType::Character)
+                .ui_priority(99)
+                .size(normal_size.saturating_sub(2))
+                .size_cs(normal_size_cs.saturating_sub(2)),
+        );
+
         // Quote style (blockquotes)
         self.add_style(
             Style::new("Quote", "Quote", StyleType::Paragraph)
@@ -775,6 +1220,16 @@ impl StylesDocument {
                 .spacing(60, 60),
         );
 
+        // FootnoteReference style (character; the runs set superscript themselves)
+        self.add_style(
+            Style::new(
+                "FootnoteReference",
+                "footnote reference",
+                StyleType::Character,
+            )
+            .ui_priority(99),
+        );
+
         // Hyperlink style (character)
         self.add_style(
             Style::new("Hyperlink", "Hyperlink", StyleType::Character)
@@ -863,6 +1318,23 @@ impl StylesDocument {
                 .spacing(120, 0) // 6pt before
                 .indent(240),
         );
+
+        // Hidden chapter author/date paragraphs, read by {chapter_author} and
+        // {chapter_date} STYLEREF fields in headers and footers
+        for (id, name) in [
+            ("ChapterAuthor", "Chapter Author"),
+            ("ChapterDate", "Chapter Date"),
+        ] {
+            self.add_style(
+                Style::new(id, name, StyleType::Paragraph)
+                    .ui_priority(99)
+                    .based_on("Normal")
+                    .vanish(),
+            );
+        }
+
+        // TableGrid style (referenced by every table; borders are set per table)
+        self.add_style(Style::new("TableGrid", "Table Grid", StyleType::Table).ui_priority(39));
     }
 
     pub fn to_xml(&self) -> Result<Vec<u8>> {
@@ -943,6 +1415,9 @@ impl StylesDocument {
         fonts.push_attribute(("w:ascii", ascii_font.as_str()));
         fonts.push_attribute(("w:hAnsi", ascii_font.as_str()));
         fonts.push_attribute(("w:cs", cs_font.as_str()));
+        if let Some(east_asia_font) = self.lang.default_east_asia_font() {
+            fonts.push_attribute(("w:eastAsia", east_asia_font));
+        }
         writer.write_event(Event::Empty(fonts))?;
 
         // 2. Default size
@@ -963,11 +1438,11 @@ impl StylesDocument {
         color.push_attribute(("w:val", normal_color.as_str()));
         writer.write_event(Event::Empty(color))?;
 
-        // 4. Language setting for Thai support
+        // 4. Language setting for Thai (or other complex script) support
         let mut lang = BytesStart::new("w:lang");
-        lang.push_attribute(("w:val", "en-US"));
-        lang.push_attribute(("w:eastAsia", "th-TH"));
-        lang.push_attribute(("w:bidi", "th-TH"));
+        lang.push_attribute(("w:val", self.lang.latin_lang_tag()));
+        lang.push_attribute(("w:eastAsia", self.lang.east_asia_lang_tag()));
+        lang.push_attribute(("w:bidi", self.lang.cs_lang_tag()));
         writer.write_event(Event::Empty(lang))?;
 
         // 5. Ligatures (Thai ligature support)
@@ -978,6 +1453,22 @@ impl StylesDocument {
         writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
         writer.write_event(Event::End(BytesEnd::new("w:rPrDefault")))?;
 
+        // Paragraph properties default: widow/orphan control, and RTL
+        // languages start paragraphs on the right, so start-aligned styles
+        // render right-aligned
+        if self.widow_control || self.lang.is_rtl() {
+            writer.write_event(Event::Start(BytesStart::new("w:pPrDefault")))?;
+            writer.write_event(Event::Start(BytesStart::new("w:pPr")))?;
+            if self.widow_control {
+                writer.write_event(Event::Empty(BytesStart::new("w:widowControl")))?;
+            }
+            if self.lang.is_rtl() {
+                writer.write_event(Event::Empty(BytesStart::new("w:bidi")))?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
+            writer.write_event(Event::End(BytesEnd::new("w:pPrDefault")))?;
+        }
+
         writer.write_event(Event::End(BytesEnd::new("w:docDefaults")))?;
 
         Ok(())
@@ -990,28 +1481,40 @@ impl StylesDocument {
         latent: &super::latent_styles::LatentStyles,
     ) -> Result<()> {
         // Start latentStyles element with attributes
+        // `hide_builtin` and `lock_builtin` apply to every latent style
+        let hide = self.gallery.hide_builtin;
+        let locked = latent.def_locked_state || self.gallery.lock_builtin;
+
         let mut elem = BytesStart::new("w:latentStyles");
-        elem.push_attribute((
-            "w:defLockedState",
-            if latent.def_locked_state { "1" } else { "0" },
-        ));
+        elem.push_attribute(("w:defLockedState", if locked { "1" } else { "0" }));
         elem.push_attribute((
             "w:defUIPriority",
             latent.def_ui_priority.to_string().as_str(),
         ));
         elem.push_attribute((
             "w:defSemiHidden",
-            if latent.def_semi_hidden { "1" } else { "0" },
+            if latent.def_semi_hidden || hide {
+                "1"
+            } else {
+                "0"
+            },
         ));
         elem.push_attribute((
             "w:defUnhideWhenUsed",
-            if latent.def_unhide_when_used {
+            if latent.def_unhide_when_used || hide {
+                "1"
+            } else {
+                "0"
+            },
+        ));
+        elem.push_attribute((
+            "w:defQFormat",
+            if latent.def_q_format && !hide {
                 "1"
             } else {
                 "0"
             },
         ));
-        elem.push_attribute(("w:defQFormat", if latent.def_q_format { "1" } else { "0" }));
         elem.push_attribute(("w:count", latent.count.to_string().as_str()));
         writer.write_event(Event::Start(elem))?;
 
@@ -1030,7 +1533,7 @@ impl StylesDocument {
             if exc.unhide_when_used {
                 exc_elem.push_attribute(("w:unhideWhenUsed", "1"));
             }
-            if exc.q_format {
+            if exc.q_format && !hide {
                 exc_elem.push_attribute(("w:qFormat", "1"));
             }
 
@@ -1086,7 +1589,9 @@ impl StylesDocument {
         writer.write_event(Event::Empty(BytesStart::new("w:autoRedefine")))?;
 
         // Quick format (show in Quick Styles gallery)
-        writer.write_event(Event::Empty(BytesStart::new("w:qFormat")))?;
+        if self.gallery.shows(style) {
+            writer.write_event(Event::Empty(BytesStart::new("w:qFormat")))?;
+        }
 
         // Hidden flags
         if style.hidden {
@@ -1113,7 +1618,7 @@ impl StylesDocument {
             // 7. w:tabs
             // 8. w:spacing
             // 9. w:ind (indentation)
-            // 10. w:jc (justification - not used in styles)
+            // 10. w:jc (justification)
             // 11. w:outlineLvl (for headings)
             // 12. w:rPr (paragraph-level run properties)
             // 13. w:sectPr (not in styles, only in document paragraphs)
@@ -1164,8 +1669,22 @@ impl StylesDocument {
                 writer.write_event(Event::End(BytesEnd::new("w:tabs")))?;
             }
 
+            // Paragraph direction (between tabs and spacing per ECMA-376)
+            match style.bidi {
+                Some(true) => writer.write_event(Event::Empty(BytesStart::new("w:bidi")))?,
+                Some(false) => {
+                    let mut bidi = BytesStart::new("w:bidi");
+                    bidi.push_attribute(("w:val", "0"));
+                    writer.write_event(Event::Empty(bidi))?;
+                }
+                None => {}
+            }
+
             // 8. Spacing
-            if style.spacing_before.is_some() || style.spacing_after.is_some() {
+            if style.spacing_before.is_some()
+                || style.spacing_after.is_some()
+                || style.line_spacing.is_some()
+            {
                 let mut spacing = BytesStart::new("w:spacing");
                 if let Some(before) = style.spacing_before {
                     spacing.push_attribute(("w:before", before.to_string().as_str()));
@@ -1173,6 +1692,10 @@ impl StylesDocument {
                 if let Some(after) = style.spacing_after {
                     spacing.push_attribute(("w:after", after.to_string().as_str()));
                 }
+                if let Some(line) = style.line_spacing {
+                    spacing.push_attribute(("w:line", line.to_string().as_str()));
+                    spacing.push_attribute(("w:lineRule", "auto"));
+                }
                 writer.write_event(Event::Empty(spacing))?;
             }
 
@@ -1188,6 +1711,13 @@ impl StylesDocument {
                 writer.write_event(Event::Empty(indent_elem))?;
             }
 
+            // 10. Justification
+            if let Some(ref jc) = style.justification {
+                let mut jc_elem = BytesStart::new("w:jc");
+                jc_elem.push_attribute(("w:val", jc.as_str()));
+                writer.write_event(Event::Empty(jc_elem))?;
+            }
+
             // 11. Outline level (for headings)
             if let Some(level) = style.outline_level {
                 let mut outline = BytesStart::new("w:outlineLvl");
@@ -1239,6 +1769,11 @@ impl StylesDocument {
             writer.write_event(Event::Empty(BytesStart::new("w:iCs")))?;
         }
 
+        // Hidden text
+        if style.vanish {
+            writer.write_event(Event::Empty(BytesStart::new("w:vanish")))?;
+        }
+
         // 4. Underline
         if style.underline {
             let mut underline = BytesStart::new("w:u");
@@ -1267,11 +1802,27 @@ impl StylesDocument {
             writer.write_event(Event::Empty(color_elem))?;
         }
 
-        // 8. Language setting for Thai support (in all styles)
+        // Box border and shading (for Kbd style)
+        if style.id == "Kbd" {
+            let mut bdr = BytesStart::new("w:bdr");
+            bdr.push_attribute(("w:val", "single"));
+            bdr.push_attribute(("w:sz", "4")); // 1/2 pt
+            bdr.push_attribute(("w:space", "0"));
+            bdr.push_attribute(("w:color", "A0A0A0")); // Gray border
+            writer.write_event(Event::Empty(bdr))?;
+
+            let mut shd = BytesStart::new("w:shd");
+            shd.push_attribute(("w:val", "clear"));
+            shd.push_attribute(("w:color", "auto"));
+            shd.push_attribute(("w:fill", "F5F5F5")); // Light gray background
+            writer.write_event(Event::Empty(shd))?;
+        }
+
+        // 8. Language setting for Thai (or other complex script) support (in all styles)
         let mut lang = BytesStart::new("w:lang");
-        lang.push_attribute(("w:val", "en-US"));
-        lang.push_attribute(("w:eastAsia", "th-TH"));
-        lang.push_attribute(("w:bidi", "th-TH"));
+        lang.push_attribute(("w:val", self.lang.latin_lang_tag()));
+        lang.push_attribute(("w:eastAsia", self.lang.east_asia_lang_tag()));
+        lang.push_attribute(("w:bidi", self.lang.cs_lang_tag()));
         writer.write_event(Event::Empty(lang))?;
 
         // 9. Ligatures (Thai ligature support)
@@ -1289,7 +1840,9 @@ impl StylesDocument {
 }
 
 /// Generate word/settings.xml with full Word 2013+ compatibility
-pub fn generate_settings_xml() -> Result<Vec<u8>> {
+///
+/// `notes` sets the footnote and endnote number format and restart.
+pub fn generate_settings_xml(lang: Language, notes: &NoteNumbering) -> Result<Vec<u8>> {
     let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
 
     // XML declaration with standalone="yes" (required by Word)
@@ -1372,25 +1925,40 @@ pub fn generate_settings_xml() -> Result<Vec<u8>> {
     char_spacing.push_attribute(("w:val", "doNotCompress"));
     writer.write_event(Event::Empty(char_spacing))?;
 
-    // Footnote properties (required for proper document structure)
-    writer.write_event(Event::Start(BytesStart::new("w:footnotePr")))?;
-    let mut fn_sep = BytesStart::new("w:footnote");
-    fn_sep.push_attribute(("w:id", "-1"));
-    writer.write_event(Event::Empty(fn_sep))?;
-    let mut fn_cont = BytesStart::new("w:footnote");
-    fn_cont.push_attribute(("w:id", "0"));
-    writer.write_event(Event::Empty(fn_cont))?;
-    writer.write_event(Event::End(BytesEnd::new("w:footnotePr")))?;
-
-    // Endnote properties
-    writer.write_event(Event::Start(BytesStart::new("w:endnotePr")))?;
-    let mut en_sep = BytesStart::new("w:endnote");
-    en_sep.push_attribute(("w:id", "-1"));
-    writer.write_event(Event::Empty(en_sep))?;
-    let mut en_cont = BytesStart::new("w:endnote");
-    en_cont.push_attribute(("w:id", "0"));
-    writer.write_event(Event::Empty(en_cont))?;
-    writer.write_event(Event::End(BytesEnd::new("w:endnotePr")))?;
+    // Kinsoku: CJK punctuation that may not end or start a line
+    if let Some((after, before)) = lang.kinsoku() {
+        let mut no_break_after = BytesStart::new("w:noLineBreaksAfter");
+        no_break_after.push_attribute(("w:lang", lang.lang_tag()));
+        no_break_after.push_attribute(("w:val", after));
+        writer.write_event(Event::Empty(no_break_after))?;
+
+        let mut no_break_before = BytesStart::new("w:noLineBreaksBefore");
+        no_break_before.push_attribute(("w:lang", lang.lang_tag()));
+        no_break_before.push_attribute(("w:val", before));
+        writer.write_event(Event::Empty(no_break_before))?;
+    }
+
+    // Footnote and endnote properties (required for proper document structure)
+    for (note, format) in [("footnote", &notes.footnotes), ("endnote", &notes.endnotes)] {
+        let props = format!("w:{}Pr", note);
+        writer.write_event(Event::Start(BytesStart::new(props.as_str())))?;
+        if let Some(format) = format {
+            let mut num_fmt = BytesStart::new("w:numFmt");
+            num_fmt.push_attribute(("w:val", format.as_str()));
+            writer.write_event(Event::Empty(num_fmt))?;
+        }
+        if notes.restart != NoteRestart::Continuous {
+            let mut restart = BytesStart::new("w:numRestart");
+            restart.push_attribute(("w:val", notes.restart.value(note == "endnote")));
+            writer.write_event(Event::Empty(restart))?;
+        }
+        for id in ["-1", "0"] {
+            let mut separator = BytesStart::new(format!("w:{}", note));
+            separator.push_attribute(("w:id", id));
+            writer.write_event(Event::Empty(separator))?;
+        }
+        writer.write_event(Event::End(BytesEnd::new(props.as_str())))?;
+    }
 
     // Compatibility settings for Word 2013+ and Thai/OpenType features
     writer.write_event(Event::Start(BytesStart::new("w:compat")))?;
@@ -1492,9 +2060,9 @@ pub fn generate_settings_xml() -> Result<Vec<u8>> {
 
     // Theme font languages
     let mut theme_font_lang = BytesStart::new("w:themeFontLang");
-    theme_font_lang.push_attribute(("w:val", "en-US"));
-    theme_font_lang.push_attribute(("w:eastAsia", "th-TH"));
-    theme_font_lang.push_attribute(("w:bidi", "th-TH"));
+    theme_font_lang.push_attribute(("w:val", lang.latin_lang_tag()));
+    theme_font_lang.push_attribute(("w:eastAsia", lang.east_asia_lang_tag()));
+    theme_font_lang.push_attribute(("w:bidi", lang.cs_lang_tag()));
     writer.write_event(Event::Empty(theme_font_lang))?;
 
     // Color scheme mapping (theme colors)
@@ -1944,7 +2512,7 @@ mod tests {
     #[test]
     fn test_styles_document_english() {
         let doc = StylesDocument::new(Language::English, None);
-        assert_eq!(doc.styles.len(), 22); // All required styles (including TOCHeading, BodyText, CodeFilename, Header, and Footer)
+        assert_eq!(doc.styles.len(), 29); // All required styles (including Heading5-6, TOCHeading, BodyText, CodeFilename, Kbd, Header, Footer, TableGrid and the chapter author/date styles)
 
         // Check Normal style
         let normal = doc.styles.iter().find(|s| s.id == "Normal").unwrap();
@@ -2006,7 +2574,7 @@ mod tests {
     #[test]
     fn test_styles_document_thai() {
         let doc = StylesDocument::new(Language::Thai, None);
-        assert_eq!(doc.styles.len(), 22); // All required styles (including TOCHeading, BodyText, CodeFilename, Header, and Footer)
+        assert_eq!(doc.styles.len(), 29); // All required styles (including Heading5-6, TOCHeading, BodyText, CodeFilename, Kbd, Header, Footer, TableGrid and the chapter author/date styles)
 
         // Check Normal style uses Thai font
         let normal = doc.styles.iter().find(|s| s.id == "Normal").unwrap();
@@ -2089,8 +2657,11 @@ mod tests {
             "Heading2",
             "Heading3",
             "Heading4",
+            "Heading5",
+            "Heading6",
             "Code",
             "CodeChar",
+            "Kbd",
             "Quote",
             "Caption",
             "TOCHeading",
@@ -2100,6 +2671,8 @@ mod tests {
             "FootnoteText",
             "Hyperlink",
             "ListParagraph",
+            "ChapterAuthor",
+            "ChapterDate",
         ];
 
         for style_id in &required_styles {
@@ -2126,9 +2699,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_style_gallery() {
+        let mut doc = StylesDocument::new(Language::English, None);
+        let unknown = doc.set_gallery(StyleGallery {
+            styles: Some(vec![
+                "Heading1".to_string(),
+                "Body Text".to_string(),
+                "Fancy".to_string(),
+            ]),
+            hide_builtin: true,
+            lock_builtin: true,
+        });
+        assert_eq!(unknown, ["Fancy"]);
+
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        // Only the listed styles are in the gallery
+        assert_eq!(xml.matches("<w:qFormat/>").count(), 2);
+        assert!(xml.contains("w:defLockedState=\"1\""));
+        assert!(xml.contains("w:defSemiHidden=\"1\""));
+        assert!(!xml.contains("w:qFormat=\"1\""));
+
+        let xml = String::from_utf8(
+            StylesDocument::new(Language::English, None)
+                .to_xml()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(xml.contains("w:defLockedState=\"0\""));
+        assert!(xml.contains("w:qFormat=\"1\""));
+    }
+
     #[test]
     fn test_generate_settings_xml() {
-        let xml = generate_settings_xml().unwrap();
+        let xml = generate_settings_xml(Language::English, &NoteNumbering::default()).unwrap();
         assert!(!xml.is_empty());
 
         let xml_str = String::from_utf8(xml).unwrap();
@@ -2142,6 +2746,20 @@ mod tests {
         assert!(xml_str.contains("<w:themeFontLang"));
         assert!(xml_str.contains("th-TH"));
         assert!(xml_str.contains("<w:updateFields w:val=\"true\"/>"));
+        assert!(xml_str.contains("<w:footnotePr>\n    <w:footnote w:id=\"-1\"/>"));
+
+        let notes = NoteNumbering {
+            footnotes: Some("chicago".to_string()),
+            restart: NoteRestart::EachPage,
+            ..Default::default()
+        };
+        let xml =
+            String::from_utf8(generate_settings_xml(Language::Thai, &notes).unwrap()).unwrap();
+        assert!(xml.contains(concat!(
+            "<w:footnotePr>\n    <w:numFmt w:val=\"chicago\"/>\n",
+            "    <w:numRestart w:val=\"eachPage\"/>"
+        )));
+        assert!(xml.contains("<w:endnotePr>\n    <w:numRestart w:val=\"continuous\"/>"));
     }
 
     #[test]
@@ -2164,6 +2782,142 @@ mod tests {
         assert_eq!(Language::Thai.default_font_size(), 28); // 14pt
     }
 
+    #[test]
+    fn test_language_from_code() {
+        assert_eq!(Language::from_code("TH"), Some(Language::Thai));
+        assert_eq!(Language::from_code("lao"), Some(Language::Lao));
+        assert_eq!(Language::from_code("km-KH"), Some(Language::Khmer));
+        assert_eq!(Language::from_code("Burmese"), Some(Language::Myanmar));
+        assert_eq!(Language::from_code("vi"), Some(Language::Vietnamese));
+        assert_eq!(Language::from_code("fr"), None);
+        assert_eq!(Language::Myanmar.lang_tag(), "my-MM");
+        assert_eq!(Language::Vietnamese.cs_lang_tag(), "th-TH");
+        assert_eq!(Language::from_code("ja-JP"), Some(Language::Japanese));
+        assert_eq!(Language::Korean.east_asia_lang_tag(), "ko-KR");
+        assert_eq!(Language::Thai.east_asia_lang_tag(), "th-TH");
+        assert_eq!(Language::Chinese.chapter_label("2"), "第2章");
+        assert_eq!(Language::Japanese.toc_title(), "目次");
+    }
+
+    #[test]
+    fn test_cjk_east_asia_font_and_kinsoku() {
+        let doc = StylesDocument::new(Language::Japanese, None);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        assert!(xml.contains("w:eastAsia=\"Yu Gothic\""));
+        assert!(xml.contains("w:val=\"en-US\" w:eastAsia=\"ja-JP\" w:bidi=\"th-TH\""));
+
+        let settings = String::from_utf8(
+            generate_settings_xml(Language::Japanese, &NoteNumbering::default()).unwrap(),
+        )
+        .unwrap();
+        assert!(settings.contains("<w:noLineBreaksAfter w:lang=\"ja-JP\""));
+        assert!(settings.contains("<w:noLineBreaksBefore w:lang=\"ja-JP\""));
+        assert!(settings.contains("w:eastAsia=\"ja-JP\""));
+
+        let settings = String::from_utf8(
+            generate_settings_xml(Language::English, &NoteNumbering::default()).unwrap(),
+        )
+        .unwrap();
+        assert!(!settings.contains("noLineBreaks"));
+    }
+
+    #[test]
+    fn test_styles_document_arabic_rtl() {
+        assert_eq!(Language::from_code("he-IL"), Some(Language::Hebrew));
+        assert!(Language::Arabic.is_rtl());
+        assert!(!Language::Thai.is_rtl());
+
+        let doc = StylesDocument::new(Language::Arabic, None);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        let defaults =
+            &xml[xml.find("<w:pPrDefault>").unwrap()..xml.find("</w:pPrDefault>").unwrap()];
+        assert!(defaults.contains("<w:bidi/>"));
+        assert!(xml.contains("w:val=\"en-US\" w:eastAsia=\"ar-SA\" w:bidi=\"ar-SA\""));
+        assert!(xml.contains("<w:bidi w:val=\"0\"/>"));
+
+        let mut doc = StylesDocument::new(Language::English, None);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        assert!(!xml.contains("<w:bidi"));
+        let defaults =
+            &xml[xml.find("<w:pPrDefault>").unwrap()..xml.find("</w:pPrDefault>").unwrap()];
+        assert!(defaults.contains("<w:widowControl/>"));
+
+        doc.set_widow_control(false);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        assert!(!xml.contains("<w:pPrDefault>"));
+    }
+
+    #[test]
+    fn test_styles_document_khmer() {
+        let doc = StylesDocument::new(Language::Khmer, None);
+        let normal = doc.styles.iter().find(|s| s.id == "Normal").unwrap();
+        assert_eq!(normal.font_cs, Some("Khmer OS".to_string()));
+
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        assert!(xml.contains("w:val=\"en-US\" w:eastAsia=\"km-KH\" w:bidi=\"km-KH\""));
+        assert!(!xml.contains("th-TH"));
+    }
+
+    #[test]
+    fn test_apply_spacing() {
+        let mut doc = StylesDocument::new(Language::English, None);
+        let spacing = HashMap::from([
+            (
+                "BodyText".to_string(),
+                StyleSpacing {
+                    line: Some(360),
+                    ..Default::default()
+                },
+            ),
+            (
+                "Heading1".to_string(),
+                StyleSpacing {
+                    before: Some(480),
+                    after: Some(0),
+                    line: None,
+                },
+            ),
+            ("Nope".to_string(), StyleSpacing::default()),
+        ]);
+        assert_eq!(doc.apply_spacing(&spacing), vec!["Nope"]);
+
+        let heading = doc.styles.iter().find(|s| s.id == "Heading1").unwrap();
+        assert_eq!(heading.spacing_before, Some(480));
+        assert_eq!(heading.spacing_after, Some(0));
+        assert_eq!(heading.line_spacing, None);
+
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        let body = &xml[xml.find("w:styleId=\"BodyText\"").unwrap()..];
+        let body = &body[..body.find("</w:style>").unwrap()];
+        assert!(body.contains("w:line=\"360\" w:lineRule=\"auto\""));
+    }
+
+    #[test]
+    fn test_thai_official_paragraph_preset() {
+        assert_eq!(
+            ParagraphPreset::from_name("Thai-Official"),
+            Some(ParagraphPreset::ThaiOfficial)
+        );
+        assert_eq!(
+            ParagraphPreset::from_name(""),
+            Some(ParagraphPreset::Default)
+        );
+        assert_eq!(ParagraphPreset::from_name("memo"), None);
+
+        let mut doc = StylesDocument::new(Language::Thai, None);
+        doc.apply_paragraph_preset(ParagraphPreset::ThaiOfficial);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        let body = &xml[xml.find("w:styleId=\"BodyText\"").unwrap()..];
+        let body = &body[..body.find("</w:style>").unwrap()];
+        assert!(body.contains("<w:spacing w:before=\"0\" w:after=\"0\"/>"));
+        assert!(body.contains("<w:ind w:firstLine=\"709\"/>"));
+        assert!(body.contains("<w:jc w:val=\"thaiDistribute\"/>"));
+
+        let doc = StylesDocument::new(Language::Thai, None);
+        let xml = String::from_utf8(doc.to_xml().unwrap()).unwrap();
+        assert!(!xml.contains("<w:jc "));
+    }
+
     #[test]
     fn test_style_type_as_str() {
         assert_eq!(StyleType::Paragraph.as_str(), "paragraph");
@@ -2176,6 +2930,7 @@ mod tests {
     fn test_language_table_caption_prefix() {
         assert_eq!(Language::English.table_caption_prefix(), "Table");
         assert_eq!(Language::Thai.table_caption_prefix(), "ตารางที่");
+        assert_eq!(Language::Vietnamese.table_caption_prefix(), "Bảng");
     }
 
     #[test]
@@ -2183,4 +2938,33 @@ mod tests {
         assert_eq!(Language::English.figure_caption_prefix(), "Figure");
         assert_eq!(Language::Thai.figure_caption_prefix(), "รูปที่");
     }
+
+    #[test]
+    fn test_apply_toc_layout() {
+        use crate::docx::toc::{TocConfig, TocPageNumbers};
+
+        let mut doc = StylesDocument::new(Language::English, None);
+        doc.apply_toc_layout(&TocConfig {
+            depth: 4,
+            indents: vec![None, Some(300)],
+            dot_leader: false,
+            ..Default::default()
+        });
+        let style = |id: &str| doc.styles.iter().find(|s| s.id == id).unwrap();
+        assert_eq!(style("TOC1").indent_left, None);
+        assert_eq!(style("TOC2").indent_left, Some(300));
+        assert_eq!(style("TOC4").name, "toc 4");
+        assert_eq!(style("TOC4").indent_left, Some(1320));
+        assert_eq!(style("TOC1").tabs[0].leader, None);
+
+        doc.apply_toc_layout(&TocConfig {
+            page_numbers: TocPageNumbers::Inline,
+            ..Default::default()
+        });
+        assert!(doc
+            .styles
+            .iter()
+            .filter(|s| s.id.starts_with("TOC"))
+            .all(|s| s.tabs.is_empty()));
+    }
 }
@@ -0,0 +1,147 @@
+//! Shared XML writer for OOXML parts
+//!
+//! A thin layer over `quick_xml::Writer` that takes element names and
+//! attribute lists directly, so parts are written without building
+//! `BytesStart` values by hand or concatenating strings. Text and attribute
+//! values are always escaped; only [`XmlWriter::raw`] writes markup as is.
+
+use std::borrow::Cow;
+use std::io::Write;
+
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+
+use crate::error::Result;
+
+/// Element attributes as `(name, value)` pairs, values unescaped
+pub type Attrs<'a> = &'a [(&'a str, &'a str)];
+
+/// Streaming XML writer for document, header and footer parts
+pub struct XmlWriter<W: Write> {
+    inner: Writer<W>,
+}
+
+impl<W: Write> XmlWriter<W> {
+    /// Writer producing compact output
+    pub fn new(out: W) -> Self {
+        Self {
+            inner: Writer::new(out),
+        }
+    }
+
+    /// Writer indenting nested elements by two spaces
+    pub fn indented(out: W) -> Self {
+        Self {
+            inner: Writer::new_with_indent(out, b' ', 2),
+        }
+    }
+
+    /// `<?xml version="1.0" encoding="UTF-8" standalone="yes"?>`
+    pub fn declaration(&mut self) -> Result<()> {
+        self.inner.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+        Ok(())
+    }
+
+    pub fn start(&mut self, name: &str, attrs: Attrs) -> Result<()> {
+        self.inner.write_event(Event::Start(tag(name, attrs)))?;
+        Ok(())
+    }
+
+    pub fn end(&mut self, name: &str) -> Result<()> {
+        self.inner.write_event(Event::End(BytesEnd::new(name)))?;
+        Ok(())
+    }
+
+    /// Self-closing element
+    pub fn empty(&mut self, name: &str, attrs: Attrs) -> Result<()> {
+        self.inner.write_event(Event::Empty(tag(name, attrs)))?;
+        Ok(())
+    }
+
+    /// Escaped character data
+    pub fn text(&mut self, text: &str) -> Result<()> {
+        self.inner.write_event(Event::Text(BytesText::new(text)))?;
+        Ok(())
+    }
+
+    /// Element holding only text: `<name attrs>text</name>`
+    pub fn element(&mut self, name: &str, attrs: Attrs, text: &str) -> Result<()> {
+        self.start(name, attrs)?;
+        self.text(text)?;
+        self.end(name)
+    }
+
+    /// Markup written verbatim, for fragments that are already valid XML
+    pub fn raw(&mut self, xml: &str) -> Result<()> {
+        self.inner.get_mut().write_all(xml.as_bytes())?;
+        Ok(())
+    }
+
+    /// The underlying writer, for code still written against `quick_xml`
+    pub fn inner(&mut self) -> &mut Writer<W> {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+fn tag<'a>(name: &'a str, attrs: Attrs<'a>) -> BytesStart<'a> {
+    let mut start = BytesStart::new(name);
+    for &attr in attrs {
+        start.push_attribute(attr);
+    }
+    start
+}
+
+/// Write a markup fragment (no declaration) and return it as a string
+pub fn fragment(f: impl FnOnce(&mut XmlWriter<Vec<u8>>) -> Result<()>) -> Result<String> {
+    let mut writer = XmlWriter::new(Vec::new());
+    f(&mut writer)?;
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Escape text for use in XML content or attribute values
+pub fn escape(text: &str) -> Cow<'_, str> {
+    quick_xml::escape::escape(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_escapes() {
+        let xml = fragment(|w| {
+            w.start("w:r", &[])?;
+            w.empty("w:fldChar", &[("w:fldCharType", "begin")])?;
+            w.element("w:t", &[("xml:space", "preserve")], "R&D <\"draft\">")?;
+            w.end("w:r")
+        })
+        .unwrap();
+        assert_eq!(
+            xml,
+            r#"<w:r><w:fldChar w:fldCharType="begin"/><w:t xml:space="preserve">R&amp;D &lt;&quot;draft&quot;&gt;</w:t></w:r>"#
+        );
+    }
+
+    #[test]
+    fn test_attribute_escaping_and_raw() {
+        let xml = fragment(|w| {
+            w.start("w:fldSimple", &[("w:instr", r#"STYLEREF "Heading 1""#)])?;
+            w.raw("<w:r/>")?;
+            w.end("w:fldSimple")
+        })
+        .unwrap();
+        assert_eq!(
+            xml,
+            r#"<w:fldSimple w:instr="STYLEREF &quot;Heading 1&quot;"><w:r/></w:fldSimple>"#
+        );
+        assert_eq!(escape("a < b & 'c'"), "a &lt; b &amp; &apos;c&apos;");
+    }
+}
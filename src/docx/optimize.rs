@@ -0,0 +1,350 @@
+//! Downscaling of embedded images in a finished DOCX
+//!
+//! Images are often much larger than the size they are shown at. For each
+//! PNG/JPEG in `word/media/` used by the main document, the largest
+//! displayed width (from `<wp:extent>`) is converted to pixels at the target
+//! DPI; images wider than that are resized and re-encoded. A media file is
+//! only replaced when the result is smaller.
+//!
+//! Resizing needs the `images` feature.
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::package::Package;
+use crate::error::{Error, Result};
+
+const EMU_PER_INCH: f64 = 914_400.0;
+
+static DRAWING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<w:drawing>.*?</w:drawing>").expect("Invalid regex"));
+static EXTENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<wp:extent cx="(\d+)""#).expect("Invalid regex"));
+static BLIP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<a:blip r:embed="([^"]+)""#).expect("Invalid regex"));
+
+/// Settings for [`optimize_images`]
+#[derive(Debug, Clone, Copy)]
+pub struct OptimizeOptions {
+    /// Target resolution at the displayed size
+    pub dpi: u32,
+    /// JPEG quality (1-100) used when re-encoding
+    pub jpeg_quality: u8,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 150,
+            jpeg_quality: 85,
+        }
+    }
+}
+
+/// Limits applied to images as they are embedded during a build
+///
+/// Set from `[images]` in md2docx.toml. An image is resampled when it is
+/// wider than `max_dpi` at its displayed size, or wider than `max_width`
+/// pixels; with neither set images are embedded as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// Highest resolution kept at the displayed size
+    pub max_dpi: Option<u32>,
+    /// Widest image kept, in pixels
+    pub max_width: Option<u32>,
+    /// JPEG quality (1-100) used when re-encoding
+    pub jpeg_quality: u8,
+}
+
+impl Default for ImageLimits {
+    fn default() -> Self {
+        Self {
+            max_dpi: None,
+            max_width: None,
+            jpeg_quality: OptimizeOptions::default().jpeg_quality,
+        }
+    }
+}
+
+impl ImageLimits {
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.max_dpi.is_some() || self.max_width.is_some()
+    }
+
+    /// Widest pixel width to embed for an image shown `width_emu` wide
+    pub fn max_pixels(&self, width_emu: i64) -> Option<u32> {
+        let by_dpi = self.max_dpi.map(|dpi| {
+            (width_emu as f64 / EMU_PER_INCH * dpi as f64)
+                .ceil()
+                .max(1.0) as u32
+        });
+        match (by_dpi, self.max_width) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// What [`optimize_images`] changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptimizeStats {
+    /// Media files that were replaced
+    pub resized: usize,
+    /// Total size of the replaced media files before and after
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Downscale embedded images to `options.dpi` at their displayed size
+///
+/// Returns the new DOCX bytes and a summary. Images that are already small
+/// enough, used outside the main document, or in other formats are left
+/// untouched, and the input is returned as is if the result isn't smaller.
+pub fn optimize_images(docx: &[u8], options: &OptimizeOptions) -> Result<(Vec<u8>, OptimizeStats)> {
+    if !cfg!(all(feature = "images", not(target_arch = "wasm32"))) {
+        return Err(Error::NotImplemented(
+            "Image optimization requires the 'images' feature".to_string(),
+        ));
+    }
+
+    let mut package = Package::read(docx)?;
+    let mut stats = OptimizeStats::default();
+
+    for (media, max_width) in media_widths(&package, options.dpi) {
+        let Some(data) = package.get(&media) else {
+            continue;
+        };
+        let Some(smaller) = resize(data, max_width, options.jpeg_quality)? else {
+            continue;
+        };
+        if smaller.len() < data.len() {
+            stats.resized += 1;
+            stats.bytes_before += data.len() as u64;
+            stats.bytes_after += smaller.len() as u64;
+            package.set(&media, smaller);
+        }
+    }
+
+    // Re-encoded images can compress worse inside the zip than the originals
+    let optimized = package.write()?;
+    if optimized.len() >= docx.len() {
+        return Ok((docx.to_vec(), OptimizeStats::default()));
+    }
+    Ok((optimized, stats))
+}
+
+/// Map each media part used by `document.xml` to its maximum pixel width
+fn media_widths(package: &Package, dpi: u32) -> HashMap<String, u32> {
+    let document = package.text("word/document.xml").unwrap_or_default();
+    let rels = package
+        .text("word/_rels/document.xml.rels")
+        .map(|xml| crate::reverse::parts::parse_relationships(&xml))
+        .unwrap_or_default();
+
+    // Media referenced from other parts (headers, footers) is displayed at
+    // sizes we don't track here
+    let shared: Vec<String> = package
+        .parts
+        .iter()
+        .filter(|(name, _)| name.ends_with(".rels") && name != "word/_rels/document.xml.rels")
+        .map(|(_, data)| String::from_utf8_lossy(data).into_owned())
+        .collect();
+
+    let mut widths: HashMap<String, u32> = HashMap::new();
+    for drawing in DRAWING.find_iter(&document) {
+        let drawing = drawing.as_str();
+        let (Some(extent), Some(blip)) = (EXTENT.captures(drawing), BLIP.captures(drawing)) else {
+            continue;
+        };
+        let Some(rel) = rels.get(&blip[1]).filter(|r| !r.external) else {
+            continue;
+        };
+        let quoted = format!("\"{}\"", rel.target);
+        if shared.iter().any(|xml| xml.contains(&quoted)) {
+            continue;
+        }
+        let cx: f64 = extent[1].parse().unwrap_or(0.0);
+        let pixels = (cx / EMU_PER_INCH * dpi as f64).ceil().max(1.0) as u32;
+        let media = format!(
+            "word/{}",
+            rel.target
+                .trim_start_matches('/')
+                .trim_start_matches("word/")
+        );
+        let width = widths.entry(media).or_insert(0);
+        *width = (*width).max(pixels);
+    }
+    widths
+}
+
+/// Downscale an image being packaged to at most `max_width` pixels wide
+///
+/// Only the image header is read for images that already fit. Returns
+/// `None` when the image is kept as is: narrow enough, not a PNG/JPEG, not
+/// smaller once re-encoded, or unreadable.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn downscale_image(
+    image: &crate::docx::builder::ImageInfo,
+    max_width: u32,
+    jpeg_quality: u8,
+) -> Option<Vec<u8>> {
+    use crate::docx::image_utils::{read_image_dimensions, read_image_dimensions_from_file};
+
+    let dims = match &image.data {
+        Some(data) => read_image_dimensions(data),
+        None => read_image_dimensions_from_file(std::path::Path::new(&image.src)),
+    }?;
+    if dims.width <= max_width {
+        return None;
+    }
+
+    let read;
+    let data = match &image.data {
+        Some(data) => &data[..],
+        None => {
+            read = std::fs::read(&image.src).ok()?;
+            &read[..]
+        }
+    };
+    match resize(data, max_width, jpeg_quality) {
+        Ok(Some(smaller)) if smaller.len() < data.len() => Some(smaller),
+        Ok(_) => None,
+        Err(e) => {
+            crate::diagnostics::warn(
+                crate::diagnostics::WarningCategory::Other,
+                format!("Image {} kept at full size: {}", image.src, e),
+            );
+            None
+        }
+    }
+}
+
+/// Resize an image to at most `max_width` pixels wide
+///
+/// Returns `None` if the image is already narrow enough or not a PNG/JPEG.
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
+fn resize(data: &[u8], max_width: u32, jpeg_quality: u8) -> Result<Option<Vec<u8>>> {
+    use image::codecs::jpeg::JpegEncoder;
+    use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+    use image::imageops::FilterType;
+    use image::ImageFormat;
+
+    let format = match image::guess_format(data) {
+        Ok(format @ (ImageFormat::Png | ImageFormat::Jpeg)) => format,
+        _ => return Ok(None),
+    };
+    let img = image::load_from_memory_with_format(data, format)
+        .map_err(|e| Error::Image(format!("Failed to decode image: {}", e)))?;
+    if img.width() <= max_width {
+        return Ok(None);
+    }
+
+    let height = (img.height() as u64 * max_width as u64 / img.width() as u64).max(1) as u32;
+    let resized = img.resize_exact(max_width, height, FilterType::Lanczos3);
+
+    let mut out = Vec::new();
+    let encoded = match format {
+        ImageFormat::Jpeg => resized
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, jpeg_quality)),
+        _ => resized.write_with_encoder(PngEncoder::new_with_quality(
+            &mut out,
+            CompressionType::Best,
+            PngFilter::Adaptive,
+        )),
+    };
+    encoded.map_err(|e| Error::Image(format!("Failed to encode image: {}", e)))?;
+    Ok(Some(out))
+}
+
+#[cfg(not(all(feature = "images", not(target_arch = "wasm32"))))]
+fn resize(_data: &[u8], _max_width: u32, _jpeg_quality: u8) -> Result<Option<Vec<u8>>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_widths_from_extent() {
+        let md = "# Images\n";
+        let docx = crate::markdown_to_docx(md).unwrap();
+        let mut package = Package::read(&docx).unwrap();
+
+        // One image shown 2 inches wide, referenced through rIdImg
+        let drawing = r#"<w:p><w:r><w:drawing><wp:inline><wp:extent cx="1828800" cy="914400"/><a:graphic><a:blip r:embed="rIdImg"/></a:graphic></wp:inline></w:drawing></w:r></w:p>"#;
+        let doc = package.text("word/document.xml").unwrap();
+        package.set_text(
+            "word/document.xml",
+            doc.replacen("<w:body>", &format!("<w:body>{}", drawing), 1),
+        );
+        let rels = package.text("word/_rels/document.xml.rels").unwrap();
+        package.set_text(
+            "word/_rels/document.xml.rels",
+            rels.replace(
+                "</Relationships>",
+                r#"<Relationship Id="rIdImg" Type="image" Target="media/photo.png"/></Relationships>"#,
+            ),
+        );
+
+        let widths = media_widths(&package, 150);
+        assert_eq!(widths.get("word/media/photo.png"), Some(&300));
+    }
+
+    #[test]
+    fn test_image_limits_max_pixels() {
+        let two_inches = 1_828_800;
+        assert_eq!(ImageLimits::default().max_pixels(two_inches), None);
+        let limits = ImageLimits {
+            max_dpi: Some(220),
+            ..ImageLimits::default()
+        };
+        assert_eq!(limits.max_pixels(two_inches), Some(440));
+        let limits = ImageLimits {
+            max_width: Some(400),
+            ..limits
+        };
+        assert_eq!(limits.max_pixels(two_inches), Some(400));
+        assert_eq!(limits.max_pixels(914_400), Some(220));
+    }
+
+    #[cfg(all(feature = "images", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_resize_png() {
+        let img = image::RgbImage::from_fn(400, 200, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let resized = resize(&png, 100, 85).unwrap().unwrap();
+        let dims = crate::docx::image_utils::read_image_dimensions(&resized).unwrap();
+        assert_eq!((dims.width, dims.height), (100, 50));
+        assert!(resize(&png, 800, 85).unwrap().is_none());
+    }
+
+    #[cfg(all(feature = "images", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_downscale_image() {
+        let img = image::RgbImage::from_fn(400, 200, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let info = crate::docx::builder::ImageInfo {
+            filename: "image_rId6.png".to_string(),
+            rel_id: "rId6".to_string(),
+            src: "photo.png".to_string(),
+            data: Some(png.into()),
+            width_emu: 914_400,
+            height_emu: 457_200,
+        };
+
+        let smaller = downscale_image(&info, 100, 85).unwrap();
+        let dims = crate::docx::image_utils::read_image_dimensions(&smaller).unwrap();
+        assert_eq!((dims.width, dims.height), (100, 50));
+        assert!(downscale_image(&info, 400, 85).is_none());
+    }
+}
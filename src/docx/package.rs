@@ -0,0 +1,73 @@
+//! In-memory access to the parts of an existing DOCX package
+//!
+//! Used by the tools that post-process generated documents (merging,
+//! image optimization, validation).
+
+use std::io::{Cursor, Read, Write};
+
+use zip::write::{FileOptions, ZipWriter};
+use zip::ZipArchive;
+
+use crate::error::{Error, Result};
+
+/// An unpacked DOCX: part name -> bytes, in original order
+pub(crate) struct Package {
+    pub(crate) parts: Vec<(String, Vec<u8>)>,
+}
+
+impl Package {
+    /// Unpack a DOCX, failing if it has no main document part
+    pub(crate) fn read(bytes: &[u8]) -> Result<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        let mut parts = Vec::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            parts.push((file.name().to_string(), data));
+        }
+        let package = Self { parts };
+        if package.get("word/document.xml").is_none() {
+            return Err(Error::Parse("DOCX has no word/document.xml".to_string()));
+        }
+        Ok(package)
+    }
+
+    /// Pack the parts back into a DOCX
+    pub(crate) fn write(&self) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options: FileOptions<'static, ()> =
+            FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in &self.parts {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(data)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&[u8]> {
+        self.parts
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, d)| d.as_slice())
+    }
+
+    pub(crate) fn text(&self, name: &str) -> Option<String> {
+        self.get(name)
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+    }
+
+    pub(crate) fn set(&mut self, name: &str, data: Vec<u8>) {
+        match self.parts.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = data,
+            None => self.parts.push((name.to_string(), data)),
+        }
+    }
+
+    pub(crate) fn set_text(&mut self, name: &str, text: String) {
+        self.set(name, text.into_bytes());
+    }
+}
@@ -1,6 +1,6 @@
 //! ZIP packager for DOCX
 
-use std::io::{Seek, Write};
+use std::io::{Read, Seek, Write};
 use zip::write::{FileOptions, ZipWriter};
 
 use crate::docx::ooxml::{
@@ -16,6 +16,20 @@ use crate::error::Result;
 pub(crate) struct Packager<W: Write + Seek> {
     writer: ZipWriter<W>,
     added_files: std::collections::HashSet<String>,
+    compression: Compression,
+}
+
+/// ZIP compression settings for the DOCX archive
+///
+/// Lower levels build faster at the cost of a larger file. PNG and JPEG
+/// data is already compressed, so storing it as is saves time on image-heavy
+/// documents while barely changing the output size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Compression {
+    /// Deflate level from 0 (stored, fastest) to 9 (smallest); `None` uses the default of 6
+    pub level: Option<u32>,
+    /// Store PNG and JPEG media uncompressed instead of deflating it again
+    pub store_media: bool,
 }
 
 
@@ -34,17 +48,33 @@ pub(crate) struct RelContext<'a> {
 impl<W: Write + Seek> Packager<W> {
     /// Create a new packager with the given writer
     pub fn new(writer: W) -> Self {
+        Self::with_compression(writer, Compression::default())
+    }
+
+    /// Create a new packager with custom compression settings
+    pub fn with_compression(writer: W, compression: Compression) -> Self {
         Self {
             writer: ZipWriter::new(writer),
             added_files: std::collections::HashSet::new(),
+            compression,
         }
     }
 
-    /// Get file options for writing
-    fn get_file_options() -> FileOptions<'static, ()> {
-        FileOptions::default()
-            .compression_method(zip::CompressionMethod::Deflated)
+    /// Get file options for writing `path`
+    fn get_file_options(&self, path: &str) -> FileOptions<'static, ()> {
+        // A fixed timestamp keeps the archive identical across rebuilds
+        let options = FileOptions::default()
             .unix_permissions(0o644)
+            .last_modified_time(zip::DateTime::default());
+        // Deflate has no level 0; "no compression" means storing the entry
+        let stored = self.compression.level == Some(0)
+            || (self.compression.store_media && is_compressed_media(path));
+        if stored {
+            return options.compression_method(zip::CompressionMethod::Stored);
+        }
+        options
+            .compression_method(zip::CompressionMethod::Deflated)
+            .compression_level(self.compression.level.map(|level| i64::from(level.min(9))))
     }
 
     /// Package all DOCX components into the ZIP archive
@@ -111,14 +141,20 @@ impl<W: Write + Seek> Packager<W> {
         // 4. docProps/app.xml - Application properties (creator app, version)
         self.write_file("docProps/app.xml", &props.app.to_xml()?)?;
 
-        // 5. word/document.xml - Main document content
-        self.write_file("word/document.xml", &document.to_xml()?)?;
+        // 5. word/document.xml - Main document content, streamed as it is
+        // generated since it is by far the largest XML part
+        if self.start_file("word/document.xml")? {
+            document.write_to(&mut self.writer)?;
+        }
 
         // 6. word/styles.xml - Style definitions
         self.write_file("word/styles.xml", &styles.to_xml()?)?;
 
         // 7. word/settings.xml - Document settings
-        self.write_file("word/settings.xml", &generate_settings_xml()?)?;
+        self.write_file(
+            "word/settings.xml",
+            &generate_settings_xml(lang, &document.notes)?,
+        )?;
 
         // 8. word/fontTable.xml - Font table (with optional embedded font references)
         self.write_file(
@@ -138,14 +174,32 @@ impl<W: Write + Seek> Packager<W> {
         Ok(())
     }
 
+    /// Start a new entry in the ZIP archive
+    ///
+    /// Returns `false` (and starts nothing) if the path was already added.
+    /// Bytes written to `self.writer` afterwards go into the entry.
+    fn start_file(&mut self, path: &str) -> Result<bool> {
+        if !self.added_files.insert(path.to_string()) {
+            return Ok(false);
+        }
+        let options = self.get_file_options(path);
+        self.writer.start_file(path, options)?;
+        Ok(true)
+    }
+
     /// Write a file to the ZIP archive
     fn write_file(&mut self, path: &str, content: &[u8]) -> Result<()> {
-        if self.added_files.contains(path) {
-            return Ok(());
+        if self.start_file(path)? {
+            self.writer.write_all(content)?;
+        }
+        Ok(())
+    }
+
+    /// Copy a file into the ZIP archive from a reader, without buffering it
+    fn copy_file(&mut self, path: &str, mut reader: impl Read) -> Result<()> {
+        if self.start_file(path)? {
+            std::io::copy(&mut reader, &mut self.writer)?;
         }
-        self.writer.start_file(path, Self::get_file_options())?;
-        self.writer.write_all(content)?;
-        self.added_files.insert(path.to_string());
         Ok(())
     }
 
@@ -158,6 +212,15 @@ impl<W: Write + Seek> Packager<W> {
         Ok(())
     }
 
+    /// Stream an image into the archive from a reader (e.g. an open file)
+    ///
+    /// The image is copied in chunks rather than read into memory, which
+    /// keeps peak memory low for documents with large photos.
+    pub fn add_image_reader(&mut self, filename: &str, reader: impl Read) -> Result<()> {
+        let path = format!("word/media/{}", filename);
+        self.copy_file(&path, reader)
+    }
+
     /// Add a header file to the archive
     pub fn add_header(&mut self, header_num: u32, content: &[u8]) -> Result<()> {
         let path = format!("word/header{}.xml", header_num);
@@ -198,6 +261,12 @@ impl<W: Write + Seek> Packager<W> {
         Ok(())
     }
 
+    /// Add the footnotes relationships file to the archive
+    pub fn add_footnotes_rels(&mut self, content: &[u8]) -> Result<()> {
+        self.write_file("word/_rels/footnotes.xml.rels", content)?;
+        Ok(())
+    }
+
     /// Add an endnotes file to the archive
     pub fn add_endnotes(&mut self, content: &[u8]) -> Result<()> {
         self.write_file("word/endnotes.xml", content)?;
@@ -227,6 +296,15 @@ impl<W: Write + Seek> Packager<W> {
     }
 }
 
+/// Whether `path` is media whose format is already compressed
+fn is_compressed_media(path: &str) -> bool {
+    let Some(name) = path.strip_prefix("word/media/") else {
+        return false;
+    };
+    let ext = name.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    matches!(ext.as_str(), "png" | "jpg" | "jpeg")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -587,4 +665,88 @@ mod tests {
         assert!(!zip_data.is_empty());
         assert_eq!(&zip_data[0..4], b"PK\x03\x04");
     }
+
+    #[test]
+    fn test_packager_streams_parts() {
+        use crate::docx::ooxml::Paragraph;
+        use std::io::Read;
+
+        let mut document = DocumentXml::new();
+        document.add_paragraph(Paragraph::with_style("Normal").add_text("Streamed"));
+        let styles = StylesDocument::new(Language::English, None);
+        let content_types = ContentTypes::new();
+        let rels = Relationships::root_rels();
+        let doc_rels = Relationships::document_rels();
+
+        let mut packager = Packager::new(Cursor::new(Vec::new()));
+        packager
+            .package(
+                &document,
+                &styles,
+                &content_types,
+                &rels,
+                &doc_rels,
+                Language::English,
+            )
+            .unwrap();
+        let image_data = vec![7u8; 200_000];
+        packager
+            .add_image_reader("large.png", Cursor::new(&image_data))
+            .unwrap();
+        // Duplicate media is written once
+        packager.add_image("large.png", b"other").unwrap();
+        let zip_data = packager.finish().unwrap().into_inner();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        let mut media = Vec::new();
+        archive
+            .by_name("word/media/large.png")
+            .unwrap()
+            .read_to_end(&mut media)
+            .unwrap();
+        assert_eq!(media, image_data);
+
+        let mut xml = Vec::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_end(&mut xml)
+            .unwrap();
+        assert_eq!(xml, document.to_xml().unwrap());
+    }
+
+    #[test]
+    fn test_packager_compression() {
+        let image_data = vec![7u8; 10_000];
+        let package = |compression: Compression| {
+            let mut packager = Packager::with_compression(Cursor::new(Vec::new()), compression);
+            packager.add_image("photo.JPG", &image_data).unwrap();
+            packager.add_numbering(&image_data).unwrap();
+            zip::ZipArchive::new(packager.finish().unwrap()).unwrap()
+        };
+
+        let mut archive = package(Compression::default());
+        let media = archive.by_name("word/media/photo.JPG").unwrap();
+        assert_eq!(media.compression(), zip::CompressionMethod::Deflated);
+        drop(media);
+
+        let mut archive = package(Compression {
+            level: Some(1),
+            store_media: true,
+        });
+        let media = archive.by_name("word/media/photo.JPG").unwrap();
+        assert_eq!(media.compression(), zip::CompressionMethod::Stored);
+        assert_eq!(media.compressed_size(), image_data.len() as u64);
+        drop(media);
+        let numbering = archive.by_name("word/numbering.xml").unwrap();
+        assert_eq!(numbering.compression(), zip::CompressionMethod::Deflated);
+        drop(numbering);
+
+        let mut archive = package(Compression {
+            level: Some(0),
+            store_media: false,
+        });
+        let numbering = archive.by_name("word/numbering.xml").unwrap();
+        assert_eq!(numbering.compression(), zip::CompressionMethod::Stored);
+    }
 }
@@ -0,0 +1,183 @@
+//! Parallel rendering of diagrams, math and image sizes
+//!
+//! Mermaid diagrams, rendered math and reading image headers dominate build
+//! time for large books. Before the document build, every such job in the
+//! document is collected in document order and run on the rayon pool. The
+//! build picks the results up as it reaches them, so a chapter built twice
+//! (see [`crate::docx::chapters`]) doesn't render its diagrams twice.
+
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
+
+use crate::docx::builder::ImageContext;
+use crate::docx::image_utils::{read_image_dimensions_from_file, ImageDimensions};
+use crate::parser::{Block, Inline};
+
+/// Work done ahead of the build, borrowed from the document
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Job<'a> {
+    Mermaid(&'a str),
+    /// LaTeX source and whether it is display math
+    Math(&'a str, bool),
+    /// Image source as written in the markdown
+    ImageSize(&'a str),
+}
+
+/// Run every diagram, equation and image-size job in the document in parallel
+///
+/// `mermaid_png_scale` is the scale diagrams are rasterized at, or `None`
+/// for SVG output. `math_font_size` is set when math is rendered to images.
+/// Rendered diagrams and equations are handed to their renderers' caches;
+/// image sizes are returned keyed by resolved path, for
+/// [`ImageContext::probed_dimensions`].
+pub(crate) fn prerender(
+    blocks: &[Block],
+    image_ctx: &ImageContext,
+    mermaid_png_scale: Option<f32>,
+    math_font_size: Option<&str>,
+) -> HashMap<String, Option<ImageDimensions>> {
+    let mut jobs = Vec::new();
+    collect_blocks(blocks, &mut jobs);
+    // Each job runs once, in order of first appearance
+    let mut seen = HashSet::new();
+    jobs.retain(|job| seen.insert(job.clone()));
+
+    let sizes: Vec<(String, Option<ImageDimensions>)> = jobs
+        .par_iter()
+        .filter_map(|job| match *job {
+            Job::Mermaid(content) => {
+                crate::mermaid::prerender(content, mermaid_png_scale);
+                None
+            }
+            Job::Math(latex, display) => {
+                if let Some(size) = math_font_size {
+                    // Fills the renderer's cache; errors are reported by the build
+                    let _ = crate::docx::math_rex::render_latex_to_svg(latex, display, size);
+                }
+                None
+            }
+            // Images in a file map are sized from memory while building
+            Job::ImageSize(_) if image_ctx.files.is_some() => None,
+            Job::ImageSize(src) => {
+                let path = image_ctx.resolve_image_path(src);
+                let dims = read_image_dimensions_from_file(std::path::Path::new(&path));
+                Some((path, dims))
+            }
+        })
+        .collect();
+    sizes.into_iter().collect()
+}
+
+fn collect_blocks<'a>(blocks: &'a [Block], jobs: &mut Vec<Job<'a>>) {
+    for block in blocks {
+        match block {
+            Block::Heading { content, .. } | Block::Paragraph(content) => {
+                collect_inlines(content, jobs)
+            }
+            Block::Image { src, .. } => push_image(src, jobs),
+            Block::Mermaid { content, .. } => jobs.push(Job::Mermaid(content)),
+            Block::MathBlock { content, .. } => jobs.push(Job::Math(content, true)),
+            Block::BlockQuote(blocks)
+            | Block::FontGroup { blocks, .. }
+            | Block::LangGroup { blocks, .. }
+            | Block::FigureGroup {
+                figures: blocks, ..
+            } => collect_blocks(blocks, jobs),
+            Block::Include {
+                resolved: Some(blocks),
+                ..
+            } => collect_blocks(blocks, jobs),
+            Block::List { items, .. } => {
+                for item in items {
+                    collect_blocks(&item.content, jobs);
+                }
+            }
+            Block::Table { headers, rows, .. } => {
+                for cell in headers.iter().chain(rows.iter().flatten()) {
+                    collect_inlines(&cell.content, jobs);
+                }
+            }
+            Block::ParallelText { rows } => {
+                for (source, translation) in rows {
+                    collect_blocks(source, jobs);
+                    collect_blocks(translation, jobs);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_inlines<'a>(inlines: &'a [Inline], jobs: &mut Vec<Job<'a>>) {
+    for inline in inlines {
+        match inline {
+            Inline::InlineMath(latex) => jobs.push(Job::Math(latex, false)),
+            Inline::DisplayMath(latex) => jobs.push(Job::Math(latex, true)),
+            Inline::Image { src, .. } => push_image(src, jobs),
+            Inline::Bold(children)
+            | Inline::Italic(children)
+            | Inline::BoldItalic(children)
+            | Inline::Strikethrough(children) => collect_inlines(children, jobs),
+            Inline::Link { text, .. } => collect_inlines(text, jobs),
+            _ => {}
+        }
+    }
+}
+
+/// Queue a size probe for local image files
+fn push_image<'a>(src: &'a str, jobs: &mut Vec<Job<'a>>) {
+    let remote = ["http://", "https://", "data:"]
+        .iter()
+        .any(|scheme| src.starts_with(scheme));
+    if !remote {
+        jobs.push(Job::ImageSize(src));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_markdown_with_frontmatter;
+
+    #[test]
+    fn test_collect_jobs() {
+        let doc = parse_markdown_with_frontmatter(
+            "# Intro\n\nInline $x^2$ math.\n\n```mermaid\ngraph TD; A-->B\n```\n\n> **Bold $y$**\n\n![Logo](logo.png)\n\n![Remote](https://example.com/a.png)\n\n$$\nE = mc^2\n$$\n",
+        );
+        let mut jobs = Vec::new();
+        collect_blocks(&doc.blocks, &mut jobs);
+        assert_eq!(
+            jobs,
+            vec![
+                Job::Math("x^2", false),
+                Job::Mermaid("graph TD; A-->B\n"),
+                Job::Math("y", false),
+                Job::ImageSize("logo.png"),
+                Job::Math("\nE = mc^2\n", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prerender_image_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut png = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        png.extend_from_slice(&[0x00, 0x00, 0x00, 0x0D]);
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&[0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x20]);
+        std::fs::write(dir.path().join("a.png"), &png).unwrap();
+
+        let doc = parse_markdown_with_frontmatter(
+            "# One\n\n![A](a.png)\n\n# Two\n\nSee ![A](a.png) and ![B](missing.png)\n",
+        );
+        let mut image_ctx = ImageContext::new();
+        image_ctx.base_path = Some(dir.path().to_path_buf());
+        let sizes = prerender(&doc.blocks, &image_ctx, None, None);
+
+        assert_eq!(sizes.len(), 2);
+        let a = sizes[&image_ctx.resolve_image_path("a.png")].unwrap();
+        assert_eq!((a.width, a.height), (64, 32));
+        assert!(sizes[&image_ctx.resolve_image_path("missing.png")].is_none());
+    }
+}
@@ -1,11 +1,21 @@
 use std::collections::{HashMap, HashSet};
 
+use super::package::Package;
+use super::validate::{check_references, ValidationIssue};
+use crate::error::Result;
+
 /// Manages Relationship IDs (rId) for the document.
 ///
 /// This ensures all relationships in `word/_rels/document.xml.rels` have unique IDs,
 /// even when combining content from multiple sources (main document, cover template, etc.).
-#[derive(Debug, Clone)]
-pub(crate) struct RelIdManager {
+/// Bookmark IDs, drawing IDs and list numIds are allocated here too, so every
+/// build that shares a manager (cover inside-content, footnotes) stays
+/// collision-free without per-build offsets.
+///
+/// [`audit`](Self::audit) checks a finished package for references to
+/// relationships that were never written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelIdManager {
     next_id: usize,
     reserved_ids: HashSet<String>,
     // Mapping from (scope, original_id) -> new_id
@@ -13,8 +23,30 @@ pub(crate) struct RelIdManager {
     mappings: HashMap<(String, String), String>,
     /// Unique ID counter for images/drawings (wp:docPr id)
     image_id_counter: u32,
+    /// Sequential ID of the last bookmark handed out
+    bookmark_counter: u32,
+    /// Next list instance ID (w:numId)
+    num_id_counter: u32,
+    /// Derive content rIds and bookmark IDs from hashes (reproducible builds)
+    content_ids: bool,
+    /// Bookmark IDs handed out while `content_ids` is set
+    bookmark_ids: HashSet<u32>,
+    /// Requests made while recording, to replay on another manager
+    recorded: Option<Vec<Request>>,
 }
 
+/// A call that handed out an ID, with its arguments
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+pub(crate) enum Request {
+    Reserve(String),
+    Id,
+    Target(String),
+    Bookmark(String),
+    Mapped(String, String),
+    Image,
+    Num,
+}
 
 impl RelIdManager {
     /// Create a new RelIdManager with standard IDs reserved
@@ -24,6 +56,11 @@ impl RelIdManager {
             reserved_ids: HashSet::new(),
             mappings: HashMap::new(),
             image_id_counter: 10000,
+            bookmark_counter: 10000,
+            num_id_counter: 1,
+            content_ids: false,
+            bookmark_ids: HashSet::new(),
+            recorded: None,
         };
 
         // Reserve standard IDs used in Relationships::document_rels()
@@ -43,6 +80,7 @@ impl RelIdManager {
 
     /// Reserve a specific ID (e.g. if a template uses a specific ID)
     pub fn reserve(&mut self, id: &str) {
+        self.record(|| Request::Reserve(id.into()));
         self.reserved_ids.insert(id.to_string());
 
         // Update next_id if reserved id is higher or equal
@@ -57,6 +95,11 @@ impl RelIdManager {
 
     /// Generate a new unique rId
     pub fn next_id(&mut self) -> String {
+        self.record(|| Request::Id);
+        self.allocate()
+    }
+
+    fn allocate(&mut self) -> String {
         loop {
             let id = format!("rId{}", self.next_id);
             self.next_id += 1;
@@ -67,33 +110,181 @@ impl RelIdManager {
         }
     }
 
+    /// Derive IDs from content instead of allocation order
+    ///
+    /// An edit then only changes the IDs of what was edited, so rebuilt
+    /// documents diff cleanly against earlier builds.
+    pub fn use_content_ids(&mut self) {
+        self.content_ids = true;
+    }
+
+    /// rId for a relationship to `target` (an image path or hyperlink URL)
+    ///
+    /// With content IDs the rId is a hash of the target; repeated targets
+    /// get the next free hash. Otherwise the next sequential rId is used.
+    pub fn id_for(&mut self, target: &str) -> String {
+        self.record(|| Request::Target(target.into()));
+        if !self.content_ids {
+            return self.allocate();
+        }
+        (0u32..)
+            .map(|n| format!("rId{:08x}", content_hash(target, n) as u32))
+            .find(|id| self.reserved_ids.insert(id.clone()))
+            .expect("unbounded search finds a free ID")
+    }
+
+    /// Allocate the `w:id` of a new bookmark named `name`
+    ///
+    /// With content IDs the ID is a hash of the name, kept within the
+    /// positive `ST_DecimalNumber` range.
+    pub fn next_bookmark_id(&mut self, name: &str) -> u32 {
+        self.record(|| Request::Bookmark(name.into()));
+        self.bookmark_counter += 1;
+        if !self.content_ids {
+            return self.bookmark_counter;
+        }
+        (0u32..)
+            .map(|n| content_hash(name, n) as u32 & 0x7fff_ffff)
+            .find(|id| self.bookmark_ids.insert(*id))
+            .expect("unbounded search finds a free ID")
+    }
+
     /// Get a mapped ID for a template resource, creating one if needed.
     ///
     /// # Arguments
     /// * `scope` - A namespace for the source (e.g., "cover", "header1")
     /// * `original_id` - The rId in the source document (e.g., "rId7")
     pub fn get_mapped_id(&mut self, scope: &str, original_id: &str) -> String {
+        self.record(|| Request::Mapped(scope.into(), original_id.into()));
         let key = (scope.to_string(), original_id.to_string());
         if let Some(new_id) = self.mappings.get(&key) {
             return new_id.clone();
         }
 
-        let new_id = self.next_id();
+        let new_id = self.allocate();
         self.mappings.insert(key, new_id.clone());
         new_id
     }
 
     /// Get the next unique image/drawing ID
     pub fn next_image_id(&mut self) -> u32 {
+        self.record(|| Request::Image);
         let id = self.image_id_counter;
         self.image_id_counter += 1;
         id
     }
 
-    /// Set an offset for image IDs to avoid collisions
-    #[allow(dead_code)]
-    pub fn set_image_id_offset(&mut self, offset: u32) {
-        self.image_id_counter = 10000 + offset;
+    /// Sequential ID the next bookmark will get
+    ///
+    /// Used to name generated bookmarks such as `_Heading_10001`.
+    pub fn upcoming_bookmark(&self) -> u32 {
+        self.bookmark_counter + 1
+    }
+
+    /// Get the next unique list instance ID (`w:numId`)
+    pub fn next_num_id(&mut self) -> u32 {
+        self.record(|| Request::Num);
+        let id = self.num_id_counter;
+        self.num_id_counter += 1;
+        id
+    }
+
+    /// Find relationship references in `docx` that don't resolve
+    ///
+    /// Every `r:id`, `r:embed`, `r:link` and `r:pict` in an XML part must
+    /// name a relationship in that part's `.rels` part. An empty list means
+    /// all of them do.
+    ///
+    /// ```
+    /// use md2docx::RelIdManager;
+    ///
+    /// let docx = md2docx::markdown_to_docx("See [docs](https://example.com)").unwrap();
+    /// assert!(RelIdManager::audit(&docx).unwrap().is_empty());
+    /// ```
+    pub fn audit(docx: &[u8]) -> Result<Vec<ValidationIssue>> {
+        let package = Package::read(docx)?;
+        let mut issues = Vec::new();
+        check_references(&package, &mut issues);
+        Ok(issues)
+    }
+
+    /// Audit a package the pipeline just generated
+    ///
+    /// With `enforce` (`DocumentConfig::audit_rels`) dangling references
+    /// fail with [`crate::Error::Validation`]. Otherwise debug builds report
+    /// them as warnings and release builds skip the audit.
+    pub(crate) fn audit_output(docx: Vec<u8>, enforce: bool) -> Result<Vec<u8>> {
+        if !enforce && !cfg!(debug_assertions) {
+            return Ok(docx);
+        }
+        let issues = Self::audit(&docx)?;
+        if issues.is_empty() {
+            return Ok(docx);
+        }
+        if !enforce {
+            for issue in &issues {
+                crate::diagnostics::warn(
+                    crate::diagnostics::WarningCategory::Other,
+                    format!("Dangling relationship: {}", issue),
+                );
+            }
+            return Ok(docx);
+        }
+        let lines: Vec<String> = issues.iter().map(|i| format!("  {}", i)).collect();
+        Err(crate::Error::Validation(format!(
+            "generated document references {} missing relationship(s):\n{}",
+            issues.len(),
+            lines.join("\n")
+        )))
+    }
+
+    /// Start keeping the requests made from now on
+    #[cfg(feature = "parallel")]
+    pub(crate) fn start_recording(&mut self) {
+        self.recorded = Some(Vec::new());
+    }
+
+    /// Stop recording and return the requests made since [`start_recording`](Self::start_recording)
+    #[cfg(feature = "parallel")]
+    pub(crate) fn take_recorded(&mut self) -> Vec<Request> {
+        self.recorded.take().unwrap_or_default()
+    }
+
+    /// Make the requests recorded on another manager, in order
+    ///
+    /// The IDs handed out are those this manager gives, which differ from
+    /// the recorded ones when the two managers started out differently.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn replay(&mut self, requests: &[Request]) {
+        for request in requests {
+            match request {
+                Request::Reserve(id) => self.reserve(id),
+                Request::Id => {
+                    self.next_id();
+                }
+                Request::Target(target) => {
+                    self.id_for(target);
+                }
+                Request::Bookmark(name) => {
+                    self.next_bookmark_id(name);
+                }
+                Request::Mapped(scope, original_id) => {
+                    self.get_mapped_id(scope, original_id);
+                }
+                Request::Image => {
+                    self.next_image_id();
+                }
+                Request::Num => {
+                    self.next_num_id();
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, request: impl FnOnce() -> Request) {
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.push(request());
+        }
     }
 
     /// Reset the manager (clearing mappings but keeping reserved IDs)
@@ -106,6 +297,21 @@ impl RelIdManager {
     }
 }
 
+/// Hash of `content`, salted with `attempt` to step past collisions
+///
+/// 64-bit FNV-1a, so IDs stay the same across Rust releases and platforms.
+pub(crate) fn content_hash(content: &str, attempt: u32) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    content
+        .as_bytes()
+        .iter()
+        .chain(&attempt.to_le_bytes())
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
 impl Default for RelIdManager {
     fn default() -> Self {
         Self::new()
@@ -162,4 +368,88 @@ mod tests {
         let new_id3 = mgr.get_mapped_id("other", "rId1"); // Different scope
         assert_ne!(new_id1, new_id3);
     }
+
+    #[test]
+    fn test_content_ids() {
+        let ids = |targets: &[&str]| {
+            let mut mgr = RelIdManager::new();
+            mgr.use_content_ids();
+            targets.iter().map(|t| mgr.id_for(t)).collect::<Vec<_>>()
+        };
+
+        // The same target keeps its rId when other relationships come first
+        let a = ids(&["logo.png", "https://example.com"]);
+        let b = ids(&["intro.png", "logo.png", "https://example.com"]);
+        assert_eq!(a[0], b[1]);
+        assert_eq!(a[1], b[2]);
+
+        // Repeated targets still get distinct rIds
+        let c = ids(&["logo.png", "logo.png"]);
+        assert_ne!(c[0], c[1]);
+
+        let mut mgr = RelIdManager::new();
+        assert_eq!(mgr.next_bookmark_id("_Toc1"), 10001);
+        mgr.use_content_ids();
+        let id = mgr.next_bookmark_id("_Toc1");
+        assert!(id <= i32::MAX as u32);
+        assert_ne!(mgr.next_bookmark_id("_Toc1"), id);
+    }
+
+    #[test]
+    fn test_shared_id_counters() {
+        let mut mgr = RelIdManager::new();
+
+        // Builds sharing a manager continue where the previous one stopped
+        assert_eq!(mgr.upcoming_bookmark(), 10001);
+        assert_eq!(mgr.next_bookmark_id("_Toc1"), 10001);
+        assert_eq!(mgr.next_bookmark_id("_Toc1"), 10002);
+        assert_eq!(mgr.upcoming_bookmark(), 10003);
+
+        assert_eq!(mgr.next_num_id(), 1);
+        assert_eq!(mgr.next_num_id(), 2);
+        assert_eq!(mgr.next_image_id(), 10000);
+        assert_eq!(mgr.next_image_id(), 10001);
+    }
+
+    #[test]
+    fn test_content_ids_are_pinned() {
+        // Reproducible output depends on these never changing
+        let mut mgr = RelIdManager::new();
+        mgr.use_content_ids();
+        assert_eq!(mgr.id_for("logo.png"), "rId441bad2b");
+        assert_eq!(mgr.next_bookmark_id("_Toc1"), 1_343_047_011);
+        assert_eq!(content_hash("logo.png", 0), 0xda4a_5042_441b_ad2b);
+    }
+
+    #[test]
+    fn test_audit() {
+        let docx = crate::markdown_to_docx("See [docs](https://example.com)\n").unwrap();
+        assert!(RelIdManager::audit(&docx).unwrap().is_empty());
+        assert_eq!(
+            RelIdManager::audit_output(docx.clone(), true).unwrap(),
+            docx
+        );
+
+        let mut package = Package::read(&docx).unwrap();
+        let xml = package.text("word/document.xml").unwrap();
+        let xml = xml.replacen("<w:body>", r#"<w:body><w:hyperlink r:id="rId999"/>"#, 1);
+        package.set_text("word/document.xml", xml);
+        let broken = package.write().unwrap();
+
+        let issues = RelIdManager::audit(&broken).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].part, "word/document.xml");
+        assert!(issues[0].message.contains("rId999"));
+
+        // Reported, not fatal, unless enforced
+        let (result, warnings) =
+            crate::diagnostics::capture(|| RelIdManager::audit_output(broken.clone(), false));
+        assert_eq!(result.unwrap(), broken);
+        if cfg!(debug_assertions) {
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].message.contains("rId999"));
+        }
+        let err = RelIdManager::audit_output(broken, true).unwrap_err();
+        assert!(matches!(err, crate::Error::Validation(_)), "{}", err);
+    }
 }
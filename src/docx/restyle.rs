@@ -0,0 +1,424 @@
+//! Re-styling of an existing DOCX with a template directory (`md2docx restyle`)
+//!
+//! The content of the document is kept as it is; from the template it takes:
+//!
+//! - `styles.xml`, keeping document styles the template doesn't define
+//! - the theme (`theme/theme1.xml`)
+//! - list formatting: each list definition gets the levels of the template's
+//!   first bullet or numbered list
+//! - the headers and footers of `header-footer.docx`, with `{{title}}`,
+//!   `{{subtitle}}`, `{{author}}` and `{{date}}` filled in from the
+//!   document properties
+//!
+//! Styles, theme and numbering come from the first template file present
+//! (see [`TemplateDir::style_source`]).
+
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::merge::{xml_attr, ABSTRACT_NUM, HEADER_FOOTER_REF, RELATIONSHIP};
+use super::package::Package;
+use crate::error::Result;
+use crate::template::extract::header_footer::HeaderFooterContent;
+use crate::template::render::header_footer::{
+    generate_header_footer_rels_xml_with_prefix, render_header_footer, HeaderFooterContext,
+};
+use crate::template::{HeaderFooterTemplate, TemplateDir};
+
+const RELS: &str = "word/_rels/document.xml.rels";
+const CONTENT_TYPES: &str = "[Content_Types].xml";
+const STYLES: &str = "word/styles.xml";
+const THEME: &str = "word/theme/theme1.xml";
+const NUMBERING: &str = "word/numbering.xml";
+
+const REL_HEADER: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/header";
+const REL_FOOTER: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/footer";
+const CT_HEADER: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml";
+const CT_FOOTER: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.footer+xml";
+
+static LVL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<w:lvl\s.*?</w:lvl>").expect("Invalid regex"));
+static SECT_PR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<w:sectPr(?:\s[^>]*[^/])?>.*?</w:sectPr>").expect("Invalid regex")
+});
+static OVERRIDE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<Override\s[^>]*?PartName="/([^"]*)"[^>]*/>"#).expect("Invalid regex")
+});
+static TITLE_PG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<w:titlePg\b[^>]*/>").expect("Invalid regex"));
+/// `w:sectPr` children that come after `w:titlePg`
+static AFTER_TITLE_PG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"<w:(?:textDirection|bidi|rtlGutter|docGrid|printerSettings|sectPrChange)\b|</w:sectPr>",
+    )
+    .expect("Invalid regex")
+});
+
+/// Re-apply the styles, theme, numbering and headers/footers of `template`
+///
+/// Returns the bytes of the re-styled DOCX.
+pub fn restyle_docx(document: &[u8], template: &TemplateDir) -> Result<Vec<u8>> {
+    let mut package = Package::read(document)?;
+
+    if let Some(source) = template.style_source()? {
+        let source = Package::read(&source)?;
+        package.restyle_styles(&source);
+        if let (true, Some(theme)) = (package.get(THEME).is_some(), source.get(THEME)) {
+            package.set(THEME, theme.to_vec());
+        }
+        if let (Some(numbering), Some(template_numbering)) =
+            (package.text(NUMBERING), source.text(NUMBERING))
+        {
+            package.set_text(
+                NUMBERING,
+                restyle_numbering(&numbering, &template_numbering),
+            );
+        }
+    }
+
+    if let Some(header_footer) = template.extract_header_footer()? {
+        if !header_footer.is_empty() {
+            package.replace_headers_footers(&header_footer)?;
+        }
+    }
+
+    package.write()
+}
+
+impl Package {
+    /// Use the template's styles, adding back the document's own styles
+    fn restyle_styles(&mut self, source: &Package) {
+        let (Some(original), Some(styles)) = (self.get(STYLES), source.get(STYLES)) else {
+            return;
+        };
+        let original = Package {
+            parts: vec![(STYLES.to_string(), original.to_vec())],
+        };
+        self.set(STYLES, styles.to_vec());
+        self.merge_styles(&original, None);
+    }
+
+    /// Swap the headers and footers for the rendered template ones
+    fn replace_headers_footers(&mut self, template: &HeaderFooterTemplate) -> Result<()> {
+        let ctx = self.header_footer_context();
+        let mut rels = self.text(RELS).unwrap_or_default();
+
+        // Drop the existing header and footer parts
+        let mut removed = HashSet::new();
+        for rel in RELATIONSHIP.find_iter(&rels) {
+            let rel = rel.as_str();
+            let is_header_footer =
+                xml_attr(rel, "Type").is_some_and(|t| t == REL_HEADER || t == REL_FOOTER);
+            if let (true, Some(target)) = (is_header_footer, xml_attr(rel, "Target")) {
+                let part = format!("word/{}", target.trim_start_matches('/'));
+                removed.insert(format!("word/_rels/{}.rels", &part["word/".len()..]));
+                removed.insert(part);
+            }
+        }
+        rels = RELATIONSHIP
+            .replace_all(&rels, |caps: &Captures| {
+                let is_header_footer =
+                    xml_attr(&caps[0], "Type").is_some_and(|t| t == REL_HEADER || t == REL_FOOTER);
+                if is_header_footer {
+                    String::new()
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .into_owned();
+        self.parts.retain(|(name, _)| !removed.contains(name));
+        if let Some(types) = self.text(CONTENT_TYPES) {
+            let types = OVERRIDE.replace_all(&types, |caps: &Captures| {
+                if removed.contains(&caps[1]) {
+                    String::new()
+                } else {
+                    caps[0].to_string()
+                }
+            });
+            self.set_text(CONTENT_TYPES, types.into_owned());
+        }
+
+        let slots: [(&str, &str, &Option<HeaderFooterContent>); 4] = [
+            ("header", "default", &template.default_header),
+            ("header", "first", &template.first_page_header),
+            ("footer", "default", &template.default_footer),
+            ("footer", "first", &template.first_page_footer),
+        ];
+        let mut references = String::new();
+        for (kind, ref_type, content) in slots {
+            let Some(content) = content else {
+                continue;
+            };
+            let rendered = render_header_footer(content, &ctx, 1, &template.media)?;
+            let file = (1..)
+                .map(|n| format!("{}{}.xml", kind, n))
+                .find(|file| self.get(&format!("word/{}", file)).is_none())
+                .expect("unbounded search finds a free name");
+
+            self.set(&format!("word/{}", file), rendered.xml);
+            if !rendered.media.is_empty() {
+                let rels_xml =
+                    generate_header_footer_rels_xml_with_prefix(&rendered.media, "header_")?;
+                self.set(&format!("word/_rels/{}.rels", file), rels_xml);
+                for (_, media) in &rendered.media {
+                    self.set(
+                        &format!("word/media/header_{}", media.filename),
+                        media.data.to_vec(),
+                    );
+                    if let Some((_, ext)) = media.filename.rsplit_once('.') {
+                        self.ensure_default_content_type(ext);
+                    }
+                }
+            }
+
+            let (rel_type, content_type) = match kind {
+                "header" => (REL_HEADER, CT_HEADER),
+                _ => (REL_FOOTER, CT_FOOTER),
+            };
+            let rel_id = format!("rIdRestyle{}", file.trim_end_matches(".xml"));
+            if let Some(pos) = rels.rfind("</Relationships>") {
+                rels.insert_str(
+                    pos,
+                    &format!(
+                        r#"<Relationship Id="{}" Type="{}" Target="{}"/>"#,
+                        rel_id, rel_type, file
+                    ),
+                );
+            }
+            self.update_content_types(&format!(r#"PartName="/word/{}""#, file), || {
+                format!(
+                    r#"<Override PartName="/word/{}" ContentType="{}"/>"#,
+                    file, content_type
+                )
+            });
+            references.push_str(&format!(
+                r#"<w:{}Reference w:type="{}" r:id="{}"/>"#,
+                kind, ref_type, rel_id
+            ));
+        }
+        self.set_text(RELS, rels);
+
+        let document = self.text("word/document.xml").unwrap_or_default();
+        let document = apply_references(&document, &references, template.different_first_page);
+        self.set_text("word/document.xml", document);
+        Ok(())
+    }
+
+    /// Placeholder values from `docProps/core.xml`
+    fn header_footer_context(&self) -> HeaderFooterContext {
+        let core = self.text("docProps/core.xml").unwrap_or_default();
+        HeaderFooterContext {
+            title: core_property(&core, "dc:title"),
+            subtitle: core_property(&core, "dc:subject"),
+            author: core_property(&core, "dc:creator"),
+            date: core_property(&core, "dcterms:created")
+                .chars()
+                .take(10)
+                .collect(),
+        }
+    }
+}
+
+/// Put `references` into the sections that had headers or footers
+///
+/// A document without any gets them on its final section.
+fn apply_references(document: &str, references: &str, title_page: bool) -> String {
+    let sections: Vec<_> = SECT_PR.find_iter(document).collect();
+    let had_any = sections
+        .iter()
+        .any(|s| HEADER_FOOTER_REF.is_match(s.as_str()));
+    let last = sections.len().saturating_sub(1);
+
+    let mut result = String::with_capacity(document.len() + references.len());
+    let mut pos = 0;
+    for (index, section) in sections.iter().enumerate() {
+        result.push_str(&document[pos..section.start()]);
+        pos = section.end();
+
+        let xml = section.as_str();
+        let apply = HEADER_FOOTER_REF.is_match(xml) || (!had_any && index == last);
+        let xml = HEADER_FOOTER_REF.replace_all(xml, "");
+        if !apply {
+            result.push_str(&xml);
+            continue;
+        }
+        let open = xml.find('>').map_or(0, |p| p + 1);
+        let mut xml = format!("{}{}{}", &xml[..open], references, &xml[open..]);
+        if title_page && !TITLE_PG.is_match(&xml) {
+            if let Some(at) = AFTER_TITLE_PG.find(&xml).map(|m| m.start()) {
+                xml.insert_str(at, "<w:titlePg/>");
+            }
+        }
+        result.push_str(&xml);
+    }
+    result.push_str(&document[pos..]);
+    result
+}
+
+/// Give each list definition in `numbering` the levels of the template's
+/// first list of the same kind (bullet or numbered)
+fn restyle_numbering(numbering: &str, template: &str) -> String {
+    let template_levels = |bullet: bool| {
+        ABSTRACT_NUM
+            .find_iter(template)
+            .map(|m| m.as_str())
+            .find(|xml| is_bullet(xml) == bullet)
+            .map(|xml| LVL.find_iter(xml).map(|m| m.as_str()).collect::<String>())
+    };
+    let bullets = template_levels(true);
+    let numbers = template_levels(false);
+
+    ABSTRACT_NUM
+        .replace_all(numbering, |caps: &Captures| {
+            let xml = &caps[0];
+            let levels = if is_bullet(xml) { &bullets } else { &numbers };
+            let (Some(levels), Some(first), Some(last)) =
+                (levels, LVL.find(xml), LVL.find_iter(xml).last())
+            else {
+                return xml.to_string();
+            };
+            format!("{}{}{}", &xml[..first.start()], levels, &xml[last.end()..])
+        })
+        .into_owned()
+}
+
+/// Whether the first level of an abstract list definition is a bullet
+fn is_bullet(abstract_num: &str) -> bool {
+    LVL.find(abstract_num)
+        .is_some_and(|lvl| lvl.as_str().contains(r#"<w:numFmt w:val="bullet"/>"#))
+}
+
+/// Text of `element` in `docProps/core.xml`, unescaped
+fn core_property(core: &str, element: &str) -> String {
+    let pattern = Regex::new(&format!(
+        r"(?s)<{0}\b[^>]*>(.*?)</{0}>",
+        regex::escape(element)
+    ))
+    .expect("Invalid regex");
+    pattern
+        .captures(core)
+        .map(|c| {
+            quick_xml::escape::unescape(&c[1])
+                .map(|text| text.into_owned())
+                .unwrap_or_else(|_| c[1].to_string())
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::FileMap;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    fn part(docx: &[u8], name: &str) -> String {
+        Package::read(docx).unwrap().text(name).unwrap()
+    }
+
+    #[test]
+    fn test_restyle_applies_template() {
+        let document = crate::markdown_to_docx("# Intro\n\n- a\n- b\n").unwrap();
+        let mut package = Package::read(&document).unwrap();
+        let styles = package.text(STYLES).unwrap().replace(
+            "</w:styles>",
+            r#"<w:style w:type="paragraph" w:styleId="DocOnly"/></w:styles>"#,
+        );
+        package.set_text(STYLES, styles);
+        let core = package.text("docProps/core.xml").unwrap().replace(
+            "</cp:coreProperties>",
+            "<dc:title>Report &amp; Co</dc:title></cp:coreProperties>",
+        );
+        package.set_text("docProps/core.xml", core);
+        let document = package.write().unwrap();
+
+        // A template whose header reads "ACME {{title}}"
+        let mut template = Package::read(&crate::markdown_to_docx("# T\n").unwrap()).unwrap();
+        let styles = template.text(STYLES).unwrap().replace(
+            "</w:styles>",
+            r#"<w:style w:type="paragraph" w:styleId="TemplateOnly"/></w:styles>"#,
+        );
+        template.set_text(STYLES, styles);
+        for (name, data) in &mut template.parts {
+            if name.starts_with("word/header") {
+                let xml = String::from_utf8_lossy(data).replace(
+                    "</w:hdr>",
+                    "<w:p><w:r><w:t>ACME {{title}}</w:t></w:r></w:p></w:hdr>",
+                );
+                *data = xml.into_bytes();
+            }
+        }
+        let mut files = FileMap::new();
+        files.insert("tpl/header-footer.docx", template.write().unwrap());
+        let template = TemplateDir::from_files(Path::new("tpl"), Arc::new(files));
+
+        let restyled = restyle_docx(&document, &template).unwrap();
+
+        let styles = part(&restyled, STYLES);
+        assert!(styles.contains(r#"w:styleId="TemplateOnly""#));
+        assert!(styles.contains(r#"w:styleId="DocOnly""#));
+
+        let package = Package::read(&restyled).unwrap();
+        let headers: Vec<String> = package
+            .parts
+            .iter()
+            .filter(|(name, _)| name.starts_with("word/header"))
+            .map(|(_, data)| String::from_utf8_lossy(data).into_owned())
+            .collect();
+        assert!(headers
+            .iter()
+            .any(|xml| xml.contains("ACME Report &amp; Co")));
+        assert!(part(&restyled, "word/document.xml").contains("Intro"));
+
+        let issues = crate::docx::validate_docx(&restyled).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_restyle_numbering() {
+        let numbering = concat!(
+            r#"<w:numbering><w:abstractNum w:abstractNumId="1"><w:multiLevelType w:val="hybridMultilevel"/>"#,
+            r#"<w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl></w:abstractNum>"#,
+            r#"<w:abstractNum w:abstractNumId="2">"#,
+            r#"<w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/><w:lvlText w:val="-"/></w:lvl></w:abstractNum>"#,
+            r#"<w:num w:numId="1"><w:abstractNumId w:val="1"/></w:num></w:numbering>"#,
+        );
+        let template = concat!(
+            r#"<w:numbering><w:abstractNum w:abstractNumId="7">"#,
+            r#"<w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/><w:lvlText w:val="■"/></w:lvl></w:abstractNum>"#,
+            r#"<w:abstractNum w:abstractNumId="8">"#,
+            r#"<w:lvl w:ilvl="0"><w:numFmt w:val="thaiNumbers"/><w:lvlText w:val="%1)"/></w:lvl></w:abstractNum>"#,
+            r#"</w:numbering>"#,
+        );
+
+        let result = restyle_numbering(numbering, template);
+        assert!(result.contains(
+            r#"<w:abstractNum w:abstractNumId="1"><w:multiLevelType w:val="hybridMultilevel"/><w:lvl w:ilvl="0"><w:numFmt w:val="thaiNumbers"/>"#
+        ));
+        assert!(result.contains(r#"<w:lvlText w:val="■"/>"#));
+        assert!(!result.contains(r#"<w:lvlText w:val="-"/>"#));
+        assert!(result.contains(r#"<w:num w:numId="1"><w:abstractNumId w:val="1"/></w:num>"#));
+    }
+
+    #[test]
+    fn test_apply_references() {
+        let document = concat!(
+            r#"<w:body><w:p><w:pPr><w:sectPr><w:pgSz/></w:sectPr></w:pPr></w:p>"#,
+            r#"<w:sectPr><w:headerReference w:type="default" r:id="rId9"/><w:pgSz/><w:docGrid/></w:sectPr></w:body>"#,
+        );
+        let refs = r#"<w:headerReference w:type="first" r:id="rIdNew"/>"#;
+        let result = apply_references(document, refs, true);
+
+        assert!(!result.contains("rId9"));
+        assert_eq!(result.matches("rIdNew").count(), 1);
+        assert!(result.contains(
+            r#"<w:sectPr><w:headerReference w:type="first" r:id="rIdNew"/><w:pgSz/><w:titlePg/><w:docGrid/></w:sectPr>"#
+        ));
+        // Sections that had no header keep none
+        assert!(result.contains(r#"<w:sectPr><w:pgSz/></w:sectPr>"#));
+    }
+}
@@ -0,0 +1,238 @@
+//! Bounded-memory rendering of document bodies
+//!
+//! With a [`MemoryBudget`], the builder renders each top-level block to
+//! body XML as soon as it is built and drops its elements. Rendered
+//! fragments stay in memory until the budget is used up; later ones are
+//! appended to a spill file and copied back into `word/document.xml` when
+//! the DOCX is packaged.
+//!
+//! Rendered images (equations, diagrams) are capped the same way by an
+//! [`AssetStore`]: past the budget they are written to files that the
+//! packager streams from.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::error::Result;
+
+/// Memory limit for building very large documents
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    /// Bytes of rendered body XML kept in memory before spilling to disk
+    pub bytes: usize,
+    /// Directory for the spill file (default: the system temp directory)
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl MemoryBudget {
+    /// Budget of `bytes`, spilling to the system temp directory
+    pub fn new(bytes: usize) -> Self {
+        Self {
+            bytes,
+            spill_dir: None,
+        }
+    }
+
+    /// Spill to `dir` instead of the system temp directory
+    pub fn spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = Some(dir.into());
+        self
+    }
+}
+
+/// Rendered body XML, held in memory or as a range of the spill file
+#[derive(Debug, Clone)]
+pub(crate) enum Fragment {
+    Memory(Vec<u8>),
+    Spilled {
+        file: Arc<SpillFile>,
+        offset: u64,
+        len: u64,
+    },
+}
+
+impl Fragment {
+    /// Copy the fragment's XML into `out`
+    pub fn copy_to(&self, out: &mut impl Write) -> Result<()> {
+        match self {
+            Fragment::Memory(xml) => out.write_all(xml)?,
+            Fragment::Spilled { file, offset, len } => {
+                let mut reader = File::open(&file.path)?;
+                reader.seek(SeekFrom::Start(*offset))?;
+                io::copy(&mut reader.take(*len), out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spill file, removed once no fragment refers to it
+#[derive(Debug)]
+pub(crate) struct SpillFile {
+    path: PathBuf,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Keeps rendered fragments within a [`MemoryBudget`]
+pub(crate) struct FragmentStore {
+    budget: MemoryBudget,
+    in_memory: usize,
+    /// Open spill file and the offset its next fragment is written at
+    spill: Option<(Arc<SpillFile>, File, u64)>,
+}
+
+impl FragmentStore {
+    pub fn new(budget: MemoryBudget) -> Self {
+        Self {
+            budget,
+            in_memory: 0,
+            spill: None,
+        }
+    }
+
+    /// Keep `xml` in memory if it fits the budget, otherwise spill it
+    pub fn store(&mut self, xml: Vec<u8>) -> Result<Fragment> {
+        if self.in_memory + xml.len() <= self.budget.bytes {
+            self.in_memory += xml.len();
+            return Ok(Fragment::Memory(xml));
+        }
+
+        if self.spill.is_none() {
+            self.spill = Some(self.open_spill_file()?);
+        }
+        let (file, writer, end) = self.spill.as_mut().expect("spill file opened above");
+        writer.write_all(&xml)?;
+        let fragment = Fragment::Spilled {
+            file: Arc::clone(file),
+            offset: *end,
+            len: xml.len() as u64,
+        };
+        *end += xml.len() as u64;
+        Ok(fragment)
+    }
+
+    fn open_spill_file(&self) -> Result<(Arc<SpillFile>, File, u64)> {
+        let path = spill_path(&self.budget, "spill")?;
+        let writer = File::create(&path)?;
+        log::debug!("Spilling rendered body XML to {}", path.display());
+        Ok((Arc::new(SpillFile { path }), writer, 0))
+    }
+}
+
+/// Keeps rendered image bytes within a [`MemoryBudget`]
+///
+/// Spilled files are removed when the store is dropped, after packaging.
+#[derive(Debug)]
+pub(crate) struct AssetStore {
+    budget: MemoryBudget,
+    in_memory: usize,
+    files: Vec<PathBuf>,
+}
+
+impl AssetStore {
+    pub fn new(budget: MemoryBudget) -> Self {
+        Self {
+            budget,
+            in_memory: 0,
+            files: Vec::new(),
+        }
+    }
+
+    /// Write `data` to disk if it doesn't fit the budget
+    ///
+    /// Returns the file it was written to, or `None` if it should stay in
+    /// memory.
+    pub fn spill(&mut self, filename: &str, data: &[u8]) -> Result<Option<PathBuf>> {
+        if self.in_memory + data.len() <= self.budget.bytes {
+            self.in_memory += data.len();
+            return Ok(None);
+        }
+        let path = spill_path(&self.budget, filename)?;
+        fs::write(&path, data)?;
+        self.files.push(path.clone());
+        Ok(Some(path))
+    }
+}
+
+impl Drop for AssetStore {
+    fn drop(&mut self) {
+        for path in &self.files {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Path for a new spill file in the budget's directory, ending in `suffix`
+fn spill_path(budget: &MemoryBudget, suffix: &str) -> Result<PathBuf> {
+    // Unique per file, so parallel builds in one process don't collide
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = budget.spill_dir.clone().unwrap_or_else(std::env::temp_dir);
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(format!(
+        "md2docx-{}-{}.{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        suffix
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(fragment: &Fragment) -> String {
+        let mut out = Vec::new();
+        fragment.copy_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_fragments_within_budget_stay_in_memory() {
+        let mut store = FragmentStore::new(MemoryBudget::new(16));
+        let fragment = store.store(b"<w:p/>".to_vec()).unwrap();
+        assert!(matches!(fragment, Fragment::Memory(_)));
+        assert_eq!(contents(&fragment), "<w:p/>");
+    }
+
+    #[test]
+    fn test_fragments_over_budget_are_spilled() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut store = FragmentStore::new(MemoryBudget::new(8).spill_dir(dir.path()));
+        let first = store.store(b"<w:p/>".to_vec()).unwrap();
+        let second = store.store(b"<w:p>a</w:p>".to_vec()).unwrap();
+        let third = store.store(b"<w:p>b</w:p>".to_vec()).unwrap();
+
+        assert!(matches!(first, Fragment::Memory(_)));
+        assert!(matches!(second, Fragment::Spilled { .. }));
+        assert_eq!(contents(&second), "<w:p>a</w:p>");
+        assert_eq!(contents(&third), "<w:p>b</w:p>");
+
+        // The spill file goes away with the last fragment using it
+        drop(store);
+        drop((second, third));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_assets_over_budget_are_spilled() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut store = AssetStore::new(MemoryBudget::new(8).spill_dir(dir.path()));
+        assert_eq!(store.spill("a.svg", b"<svg/>").unwrap(), None);
+
+        let path = store.spill("b.svg", b"<svg></svg>").unwrap().unwrap();
+        assert!(path.to_string_lossy().ends_with("b.svg"));
+        assert_eq!(fs::read(&path).unwrap(), b"<svg></svg>");
+
+        drop(store);
+        assert!(!path.exists());
+    }
+}
@@ -2,6 +2,18 @@
 
 use crate::docx::ooxml::{DocElement, Paragraph, Run};
 
+/// Where TOC entries show their page number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TocPageNumbers {
+    /// Right-aligned at the text margin (default)
+    #[default]
+    Right,
+    /// Directly after the entry text
+    Inline,
+    /// No page numbers
+    None,
+}
+
 /// TOC configuration
 #[derive(Debug, Clone)]
 pub struct TocConfig {
@@ -9,6 +21,18 @@ pub struct TocConfig {
     pub depth: u8,         // 1-6, how many heading levels to include (default 2)
     pub title: String,     // "Table of Contents" or localized
     pub after_cover: bool, // If true, TOC comes after cover content
+    /// Extra paragraph styles listed in the TOC, with the TOC level to use
+    pub include_styles: Vec<(String, u8)>,
+    /// Heading styles left out of the TOC (e.g. "Heading 3")
+    pub exclude_styles: Vec<String>,
+    /// Left indent per TOC level in twips (index 0 is level 1); `None`
+    /// keeps the style's default indent
+    pub indents: Vec<Option<u32>>,
+    /// Dotted leader between entry text and right-aligned page number
+    pub dot_leader: bool,
+    pub page_numbers: TocPageNumbers,
+    /// Make entries hyperlinks to their headings
+    pub hyperlinks: bool,
 }
 
 impl Default for TocConfig {
@@ -18,10 +42,64 @@ impl Default for TocConfig {
             depth: 2,
             title: "Table of Contents".to_string(),
             after_cover: true,
+            include_styles: Vec::new(),
+            exclude_styles: Vec::new(),
+            indents: Vec::new(),
+            dot_leader: true,
+            page_numbers: TocPageNumbers::Right,
+            hyperlinks: true,
         }
     }
 }
 
+impl TocConfig {
+    /// Whether headings of `level` are listed
+    pub fn includes_level(&self, level: u8) -> bool {
+        // Accept both the style name ("Heading 2") and its ID ("Heading2")
+        let style = format!("Heading{}", level);
+        level <= self.depth
+            && !self
+                .exclude_styles
+                .iter()
+                .any(|s| s.replace(' ', "").eq_ignore_ascii_case(&style))
+    }
+
+    /// Build the TOC field instruction
+    ///
+    /// Headings are selected with `\o` unless some heading style is
+    /// excluded, in which case the included heading styles are listed with
+    /// `\t` together with `include_styles`.
+    pub fn field_instruction(&self) -> String {
+        let mut instr = String::from(" TOC");
+        let excluded = (1..=self.depth).any(|level| !self.includes_level(level));
+        let mut styles: Vec<String> = Vec::new();
+        if excluded {
+            for level in (1..=self.depth).filter(|&level| self.includes_level(level)) {
+                styles.push(format!("Heading {},{}", level, level));
+            }
+        } else {
+            instr.push_str(&format!(" \\o \"1-{}\"", self.depth));
+        }
+        for (style, level) in &self.include_styles {
+            styles.push(format!("{},{}", style, level));
+        }
+        if !styles.is_empty() {
+            instr.push_str(&format!(" \\t \"{}\"", styles.join(",")));
+        }
+        if self.hyperlinks {
+            instr.push_str(" \\h");
+        }
+        instr.push_str(" \\z \\u");
+        match self.page_numbers {
+            TocPageNumbers::Right => {}
+            TocPageNumbers::Inline => instr.push_str(" \\p \" \""),
+            TocPageNumbers::None => instr.push_str(" \\n"),
+        }
+        instr.push(' ');
+        instr
+    }
+}
+
 /// A collected heading for TOC
 #[derive(Debug, Clone)]
 pub(crate) struct TocEntry {
@@ -74,6 +152,28 @@ impl TocBuilder {
         self.entries.is_empty()
     }
 
+    /// Empty builder for headings after `generated` earlier bookmark names
+    #[cfg(feature = "parallel")]
+    pub fn continuing(generated: u32) -> Self {
+        Self {
+            entries: Vec::new(),
+            next_id: generated,
+        }
+    }
+
+    /// Number of bookmark names generated so far
+    #[cfg(feature = "parallel")]
+    pub fn generated(&self) -> u32 {
+        self.next_id
+    }
+
+    /// Add the headings `rest` collected after the ones collected here
+    #[cfg(feature = "parallel")]
+    pub fn append(&mut self, rest: TocBuilder) {
+        self.entries.extend(rest.entries);
+        self.next_id = rest.next_id;
+    }
+
     /// Generate a sanitized bookmark ID from text
     fn generate_bookmark_id(&mut self, text: &str) -> String {
         self.next_id += 1;
@@ -104,37 +204,47 @@ impl TocBuilder {
         elements.push(DocElement::Paragraph(Box::new(title_para)));
 
         // 2. TOC Field begin - Word will auto-generate entries with page numbers
-        // The field code: TOC \o "1-2" \h \z \u
+        // The default field code: TOC \o "1-2" \h \z \u
         // \o "1-2" = outline levels 1-2
-        // \h = hyperlink entries
-        // \z = preserve tab leader
-        // \u = use paragraph styles
+        // \t = extra styles, \h = hyperlink entries, \z = preserve tab leader,
+        // \u = use paragraph styles, \n / \p = no / inline page numbers
         let toc_field_begin = Paragraph::new()
             .spacing(0, 0)
             .line_spacing(240, "auto")
             .add_run(Run::new("").with_field_char("begin"))
-            .add_run(
-                Run::new(format!(" TOC \\o \"1-{}\" \\h \\z \\u ", config.depth)).with_instr_text(),
-            )
+            .add_run(Run::new(config.field_instruction()).with_instr_text())
             .add_run(Run::new("").with_field_char("separate"));
         elements.push(DocElement::Paragraph(Box::new(toc_field_begin)));
 
         // 3. Static placeholder entries (Word updates these when field is updated)
         // Each entry has: text, tab, and PAGEREF field for page number
-        for entry in self.entries.iter().filter(|e| e.level <= config.depth) {
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| config.includes_level(e.level))
+        {
             let style = format!("TOC{}", entry.level);
 
             // Create TOC entry with tab and page reference
-            let toc_para = Paragraph::with_style(&style)
+            let mut toc_para = Paragraph::with_style(&style)
                 .spacing(0, 0)
                 .line_spacing(240, "auto")
-                .add_run(Run::new(&entry.text))
-                .add_run(Run::new("").with_tab())
-                .add_run(Run::new("").with_field_char("begin"))
-                .add_run(Run::new(format!(" PAGEREF {} \\h ", entry.bookmark_id)).with_instr_text())
-                .add_run(Run::new("").with_field_char("separate"))
-                .add_run(Run::new("1")) // Placeholder page number
-                .add_run(Run::new("").with_field_char("end"));
+                .add_run(Run::new(&entry.text));
+            toc_para = match config.page_numbers {
+                TocPageNumbers::Right => toc_para.add_run(Run::new("").with_tab()),
+                TocPageNumbers::Inline => toc_para.add_run(Run::new(" ")),
+                TocPageNumbers::None => toc_para,
+            };
+            if config.page_numbers != TocPageNumbers::None {
+                toc_para = toc_para
+                    .add_run(Run::new("").with_field_char("begin"))
+                    .add_run(
+                        Run::new(format!(" PAGEREF {} \\h ", entry.bookmark_id)).with_instr_text(),
+                    )
+                    .add_run(Run::new("").with_field_char("separate"))
+                    .add_run(Run::new("1")) // Placeholder page number
+                    .add_run(Run::new("").with_field_char("end"));
+            }
 
             elements.push(DocElement::Paragraph(Box::new(toc_para)));
         }
@@ -249,6 +359,7 @@ mod tests {
             depth: 2,
             title: "TOC".to_string(),
             after_cover: true,
+            ..Default::default()
         };
         let elements = builder.generate_toc(&config);
 
@@ -289,6 +400,7 @@ mod tests {
             depth: 2,
             title: "Contents".to_string(),
             after_cover: true,
+            ..Default::default()
         };
         let elements = builder.generate_toc(&config);
 
@@ -346,4 +458,50 @@ mod tests {
         let text_part = id.split('_').last().unwrap_or("");
         assert!(text_part.len() <= 40);
     }
+
+    #[test]
+    fn test_toc_field_instruction() {
+        let config = TocConfig::default();
+        assert_eq!(config.field_instruction(), " TOC \\o \"1-2\" \\h \\z \\u ");
+
+        let config = TocConfig {
+            depth: 3,
+            exclude_styles: vec!["Heading 2".to_string()],
+            include_styles: vec![("Title".to_string(), 1)],
+            hyperlinks: false,
+            page_numbers: TocPageNumbers::None,
+            ..Default::default()
+        };
+        assert!(!config.includes_level(2));
+        assert_eq!(
+            config.field_instruction(),
+            " TOC \\t \"Heading 1,1,Heading 3,3,Title,1\" \\z \\u \\n "
+        );
+    }
+
+    #[test]
+    fn test_toc_excluded_style_and_inline_numbers() {
+        let mut builder = TocBuilder::new();
+        builder.add_heading(1, "H1", None);
+        builder.add_heading(2, "H2", None);
+
+        let config = TocConfig {
+            exclude_styles: vec!["Heading2".to_string()],
+            page_numbers: TocPageNumbers::Inline,
+            ..Default::default()
+        };
+        let elements = builder.generate_toc(&config);
+
+        // title + field begin + 1 entry + field end + section break
+        assert_eq!(elements.len(), 5);
+        match &elements[2] {
+            DocElement::Paragraph(p) => {
+                assert_eq!(p.style_id, Some("TOC1".to_string()));
+                let xml = format!("{:?}", p.children);
+                assert!(!xml.contains("tab: true"));
+                assert!(xml.contains("PAGEREF"));
+            }
+            _ => panic!("Expected paragraph"),
+        }
+    }
 }
@@ -0,0 +1,516 @@
+//! Sanity checks for a finished DOCX package (`md2docx validate-output`)
+//!
+//! Re-opens a generated document and looks for the problems that make Word
+//! report "unreadable content":
+//!
+//! - parts without a content type in `[Content_Types].xml`
+//! - relationships pointing to parts that don't exist, and `r:id`
+//!   references with no matching relationship
+//! - XML parts that are not well-formed
+//! - paragraph, run and table style references missing from `styles.xml`
+//! - `w:sectPr` anywhere but the end of the body or the properties of a
+//!   body paragraph
+//! - bookmark starts and ends that don't pair up
+//!
+//! With the `self-check` feature every generated document goes through
+//! these checks before it is returned (see [`self_check`]).
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use once_cell::sync::Lazy;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use regex::Regex;
+
+use super::package::Package;
+use crate::error::Result;
+use crate::reverse::parts::parse_relationships;
+
+const CONTENT_TYPES: &str = "[Content_Types].xml";
+
+static DEFAULT_TYPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<Default\s[^>]*?Extension="([^"]*)""#).expect("Invalid regex"));
+static OVERRIDE_TYPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<Override\s[^>]*?PartName="([^"]*)""#).expect("Invalid regex"));
+static REL_ID_ATTR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\br:(?:id|embed|link|pict)="([^"]*)""#).expect("Invalid regex"));
+static STYLE_ID: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<w:style\b[^>]*?w:styleId="([^"]*)""#).expect("Invalid regex"));
+static STYLE_REF: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<w:(pStyle|rStyle|tblStyle)\s+w:val="([^"]*)""#).expect("Invalid regex")
+});
+static BOOKMARK_START: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<w:bookmarkStart\b[^>]*?\bw:id="([^"]*)""#).expect("Invalid regex"));
+static BOOKMARK_END: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<w:bookmarkEnd\b[^>]*?\bw:id="([^"]*)""#).expect("Invalid regex"));
+
+/// Kind of problem found by [`validate_docx`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationCategory {
+    /// A part has no content type
+    ContentType,
+    /// A relationship target or `r:id` reference does not resolve
+    Relationship,
+    /// An XML part cannot be parsed
+    MalformedXml,
+    /// A style id is used but not defined in `styles.xml`
+    UndefinedStyle,
+    /// A `w:sectPr` is somewhere Word doesn't accept it
+    SectionProperties,
+    /// A bookmark start without an end, or the other way round
+    Bookmark,
+}
+
+impl ValidationCategory {
+    /// Short machine-friendly name of the category
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationCategory::ContentType => "content-type",
+            ValidationCategory::Relationship => "relationship",
+            ValidationCategory::MalformedXml => "malformed-xml",
+            ValidationCategory::UndefinedStyle => "undefined-style",
+            ValidationCategory::SectionProperties => "section-properties",
+            ValidationCategory::Bookmark => "bookmark",
+        }
+    }
+}
+
+impl fmt::Display for ValidationCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single problem found in a DOCX package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Package part containing the problem
+    pub part: String,
+    /// Problem category
+    pub category: ValidationCategory,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: [{}] {}", self.part, self.category, self.message)
+    }
+}
+
+/// Check a DOCX package for structural problems
+///
+/// Returns an error only if the file is not a readable DOCX at all; an empty
+/// list means no problems were found.
+pub fn validate_docx(docx: &[u8]) -> Result<Vec<ValidationIssue>> {
+    let package = Package::read(docx)?;
+    let mut issues = Vec::new();
+
+    check_content_types(&package, &mut issues);
+    check_well_formed(&package, &mut issues);
+    check_relationships(&package, &mut issues);
+    check_styles(&package, &mut issues);
+    check_sections(&package, &mut issues);
+    check_bookmarks(&package, &mut issues);
+
+    Ok(issues)
+}
+
+/// Fail if a generated DOCX breaks any of the rules checked here
+///
+/// A no-op unless the `self-check` feature is enabled. The error lists
+/// each problem with its part, so a generator bug surfaces at build time
+/// instead of as Word's offer to recover the document.
+#[cfg(feature = "self-check")]
+pub(crate) fn self_check(docx: Vec<u8>) -> Result<Vec<u8>> {
+    let issues = validate_docx(&docx)?;
+    if issues.is_empty() {
+        return Ok(docx);
+    }
+    let lines: Vec<String> = issues.iter().map(|i| format!("  {}", i)).collect();
+    Err(crate::Error::Validation(format!(
+        "generated document has {} problem(s):\n{}",
+        issues.len(),
+        lines.join("\n")
+    )))
+}
+
+#[cfg(not(feature = "self-check"))]
+pub(crate) fn self_check(docx: Vec<u8>) -> Result<Vec<u8>> {
+    Ok(docx)
+}
+
+fn issue(part: &str, category: ValidationCategory, message: String) -> ValidationIssue {
+    ValidationIssue {
+        part: part.to_string(),
+        category,
+        message,
+    }
+}
+
+fn check_content_types(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    let Some(types) = package.text(CONTENT_TYPES) else {
+        issues.push(issue(
+            CONTENT_TYPES,
+            ValidationCategory::ContentType,
+            "missing".to_string(),
+        ));
+        return;
+    };
+
+    let defaults: HashSet<String> = DEFAULT_TYPE
+        .captures_iter(&types)
+        .map(|c| c[1].to_ascii_lowercase())
+        .collect();
+    let overrides: HashSet<String> = OVERRIDE_TYPE
+        .captures_iter(&types)
+        .map(|c| c[1].trim_start_matches('/').to_string())
+        .collect();
+
+    for (name, _) in &package.parts {
+        if name == CONTENT_TYPES || overrides.contains(name) {
+            continue;
+        }
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        if !defaults.contains(&extension) {
+            issues.push(issue(
+                name,
+                ValidationCategory::ContentType,
+                "part has no content type".to_string(),
+            ));
+        }
+    }
+}
+
+fn is_xml_part(name: &str) -> bool {
+    name.ends_with(".xml") || name.ends_with(".rels")
+}
+
+fn check_well_formed(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    for (name, data) in &package.parts {
+        if !is_xml_part(name) {
+            continue;
+        }
+        let mut reader = Reader::from_reader(data.as_slice());
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Eof) => break,
+                Ok(_) => buf.clear(),
+                Err(e) => {
+                    issues.push(issue(
+                        name,
+                        ValidationCategory::MalformedXml,
+                        format!("at byte {}: {}", reader.error_position(), e),
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Source part of a `.rels` part (`word/_rels/document.xml.rels` -> `word/document.xml`)
+fn rels_source(rels_name: &str) -> Option<String> {
+    let (dir, file) = rels_name.rsplit_once("_rels/")?;
+    Some(format!("{}{}", dir, file.strip_suffix(".rels")?))
+}
+
+/// Resolve a relationship target against the folder of its source part
+fn resolve_target(source: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = source.split('/').collect();
+    segments.pop();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn check_relationships(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    let parts: HashSet<&str> = package.parts.iter().map(|(n, _)| n.as_str()).collect();
+
+    for (name, data) in &package.parts {
+        let Some(source) = name.ends_with(".rels").then(|| rels_source(name)).flatten() else {
+            continue;
+        };
+        let rels = parse_relationships(&String::from_utf8_lossy(data));
+        let mut ids: Vec<_> = rels.iter().filter(|(_, r)| !r.external).collect();
+        ids.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, rel) in ids {
+            let target = resolve_target(&source, &rel.target);
+            if !parts.contains(target.as_str()) {
+                issues.push(issue(
+                    name,
+                    ValidationCategory::Relationship,
+                    format!("{} points to missing part {}", id, target),
+                ));
+            }
+        }
+    }
+
+    check_references(package, issues);
+}
+
+/// `r:id` style references without a relationship in the part's `.rels`
+///
+/// The check behind [`RelIdManager::audit`](super::RelIdManager::audit).
+pub(crate) fn check_references(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    let rels_by_source: HashMap<_, _> = package
+        .parts
+        .iter()
+        .filter(|(name, _)| name.ends_with(".rels"))
+        .filter_map(|(name, data)| {
+            let rels = parse_relationships(&String::from_utf8_lossy(data));
+            Some((rels_source(name)?, rels))
+        })
+        .collect();
+
+    for (name, data) in &package.parts {
+        if !name.ends_with(".xml") || name == CONTENT_TYPES {
+            continue;
+        }
+        let xml = String::from_utf8_lossy(data);
+        let rels = rels_by_source.get(name.as_str());
+        let mut reported = HashSet::new();
+        for caps in REL_ID_ATTR.captures_iter(&xml) {
+            let id = &caps[1];
+            if rels.is_some_and(|r| r.contains_key(id)) || !reported.insert(id.to_string()) {
+                continue;
+            }
+            issues.push(issue(
+                name,
+                ValidationCategory::Relationship,
+                format!("reference to undefined relationship {}", id),
+            ));
+        }
+    }
+}
+
+/// Parts of `word/` with content: the document, headers, footers, notes...
+fn is_content_part(name: &str) -> bool {
+    name.starts_with("word/")
+        && name.ends_with(".xml")
+        && name != "word/styles.xml"
+        && !name.contains("/_rels/")
+}
+
+fn check_styles(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    let defined: HashSet<String> = package
+        .text("word/styles.xml")
+        .map(|xml| {
+            STYLE_ID
+                .captures_iter(&xml)
+                .map(|c| c[1].to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for (name, data) in &package.parts {
+        if !is_content_part(name) {
+            continue;
+        }
+        let xml = String::from_utf8_lossy(data);
+        let mut reported = HashSet::new();
+        for caps in STYLE_REF.captures_iter(&xml) {
+            let id = &caps[2];
+            if defined.contains(id) || !reported.insert(id.to_string()) {
+                continue;
+            }
+            issues.push(issue(
+                name,
+                ValidationCategory::UndefinedStyle,
+                format!("w:{} \"{}\" is not defined in styles.xml", &caps[1], id),
+            ));
+        }
+    }
+}
+
+fn check_sections(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    const PART: &str = "word/document.xml";
+    let Some(xml) = package.text(PART) else {
+        return;
+    };
+    let mut report = |message: String| {
+        issues.push(issue(PART, ValidationCategory::SectionProperties, message));
+    };
+
+    let mut reader = Reader::from_str(&xml);
+    let mut stack: Vec<Vec<u8>> = Vec::new();
+    let mut body_section_seen = false;
+    loop {
+        let (name, empty) = match reader.read_event() {
+            Ok(Event::Start(e)) => (e.name().as_ref().to_vec(), false),
+            Ok(Event::Empty(e)) => (e.name().as_ref().to_vec(), true),
+            Ok(Event::End(_)) => {
+                stack.pop();
+                continue;
+            }
+            // Malformed XML is reported by check_well_formed
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        let depth = stack.len();
+        let parent = stack.last().map(Vec::as_slice);
+
+        if body_section_seen && parent == Some(b"w:body".as_slice()) {
+            report(format!(
+                "<{}> follows the body's w:sectPr, which must come last",
+                String::from_utf8_lossy(&name)
+            ));
+            body_section_seen = false;
+        }
+        if name == b"w:sectPr" {
+            match parent {
+                Some(b"w:body") => body_section_seen = true,
+                // A section break belongs to a paragraph directly in the body
+                Some(b"w:pPr") => {
+                    let paragraph = depth
+                        .checked_sub(3)
+                        .map(|i| (stack[i].as_slice(), stack[i + 1].as_slice()));
+                    if paragraph != Some((b"w:body".as_slice(), b"w:p".as_slice())) {
+                        report("w:sectPr in a paragraph that is not directly in w:body".into());
+                    }
+                }
+                _ => report(format!(
+                    "w:sectPr inside <{}>",
+                    parent.map(String::from_utf8_lossy).unwrap_or_default()
+                )),
+            }
+        }
+        if !empty {
+            stack.push(name);
+        }
+    }
+}
+
+fn check_bookmarks(package: &Package, issues: &mut Vec<ValidationIssue>) {
+    for (name, data) in &package.parts {
+        if !is_content_part(name) {
+            continue;
+        }
+        let xml = String::from_utf8_lossy(data);
+        let mut starts = BTreeSet::new();
+        for caps in BOOKMARK_START.captures_iter(&xml) {
+            if !starts.insert(caps[1].to_string()) {
+                issues.push(issue(
+                    name,
+                    ValidationCategory::Bookmark,
+                    format!(
+                        "bookmark id {} is used by more than one w:bookmarkStart",
+                        &caps[1]
+                    ),
+                ));
+            }
+        }
+        let ends: BTreeSet<String> = BOOKMARK_END
+            .captures_iter(&xml)
+            .map(|c| c[1].to_string())
+            .collect();
+        for id in starts.difference(&ends) {
+            issues.push(issue(
+                name,
+                ValidationCategory::Bookmark,
+                format!("w:bookmarkStart {} has no w:bookmarkEnd", id),
+            ));
+        }
+        for id in ends.difference(&starts) {
+            issues.push(issue(
+                name,
+                ValidationCategory::Bookmark,
+                format!("w:bookmarkEnd {} has no w:bookmarkStart", id),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_document_is_valid() {
+        let md = "# Title\n\nSome **bold** text with a [link](https://example.com).\n\n\
+                  | A | B |\n|---|---|\n| 1 | 2 |\n\n> quote\n\n```rust\nfn main() {}\n```\n";
+        let docx = crate::markdown_to_docx(md).unwrap();
+        let issues = validate_docx(&docx).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_detects_broken_package() {
+        let docx = crate::markdown_to_docx("# Title\n\nText\n").unwrap();
+        let mut package = Package::read(&docx).unwrap();
+
+        let doc = package.text("word/document.xml").unwrap();
+        let doc = doc.replacen(
+            "<w:body>",
+            r#"<w:body><w:p><w:pPr><w:pStyle w:val="NoSuchStyle"/></w:pPr><w:hyperlink r:id="rId999"/></w:p><w:bookmarkStart w:id="900" w:name="dangling"/>"#,
+            1,
+        );
+        // Body content after the closing section properties
+        let doc = doc.replacen("</w:body>", "<w:p/></w:body>", 1);
+        package.set_text("word/document.xml", doc);
+        package.set("word/media/orphan.xyz", vec![0]);
+        package.set_text("word/broken.xml", "<a><b></a>".to_string());
+        let rels = package.text("word/_rels/document.xml.rels").unwrap();
+        package.set_text(
+            "word/_rels/document.xml.rels",
+            rels.replace(
+                "</Relationships>",
+                r#"<Relationship Id="rIdGone" Type="image" Target="media/gone.png"/></Relationships>"#,
+            ),
+        );
+
+        let issues = validate_docx(&package.write().unwrap()).unwrap();
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.part.as_str(), i.category))
+            .collect();
+        for expected in [
+            ("word/media/orphan.xyz", ValidationCategory::ContentType),
+            ("word/broken.xml", ValidationCategory::MalformedXml),
+            (
+                "word/_rels/document.xml.rels",
+                ValidationCategory::Relationship,
+            ),
+            ("word/document.xml", ValidationCategory::Relationship),
+            ("word/document.xml", ValidationCategory::UndefinedStyle),
+            ("word/document.xml", ValidationCategory::SectionProperties),
+            ("word/document.xml", ValidationCategory::Bookmark),
+        ] {
+            assert!(
+                found.contains(&expected),
+                "{:?} not in {:?}",
+                expected,
+                issues
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        assert_eq!(
+            resolve_target("word/document.xml", "media/a.png"),
+            "word/media/a.png"
+        );
+        assert_eq!(
+            resolve_target("word/document.xml", "../customXml/item1.xml"),
+            "customXml/item1.xml"
+        );
+        assert_eq!(resolve_target("", "word/document.xml"), "word/document.xml");
+        assert_eq!(rels_source("_rels/.rels").as_deref(), Some(""));
+    }
+}
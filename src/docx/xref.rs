@@ -1,10 +1,12 @@
 //! Cross-reference context for tracking anchors and resolving references
 
-use crate::parser::RefType;
+use crate::i18n::{Label, Strings};
+use crate::parser::{Block, RefType};
+use crate::Language;
 use std::collections::HashMap;
 
 /// Information about a registered anchor/bookmark
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct AnchorInfo {
     #[allow(dead_code)]
     pub id: String, // User-defined ID (e.g., "intro", "arch")
@@ -15,7 +17,7 @@ pub(crate) struct AnchorInfo {
 }
 
 /// Context for tracking cross-references during document build
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub(crate) struct CrossRefContext {
     /// Map from anchor ID to anchor info
     anchors: HashMap<String, AnchorInfo>,
@@ -26,6 +28,21 @@ pub(crate) struct CrossRefContext {
     figure_num: u32,
     table_num: u32,
     equation_num: u32,
+    /// Registrations made while recording, to replay on another context
+    recorded: Option<Vec<Registration>>,
+}
+
+/// A call that changed a [`CrossRefContext`], with its arguments
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+pub(crate) enum Registration {
+    Heading(String, u8, String),
+    Figure(String, String),
+    FigureGroup(Option<String>, String, Vec<(Option<String>, String)>),
+    Table(String, String),
+    Equation(String),
+    EquationNumber,
+    Anchor(String, RefType, String),
 }
 
 impl CrossRefContext {
@@ -36,6 +53,7 @@ impl CrossRefContext {
     /// Register a heading anchor
     /// Called when processing Block::Heading with an id
     pub fn register_heading(&mut self, id: &str, level: u8, text: &str) -> String {
+        self.record(|| Registration::Heading(id.into(), level, text.into()));
         self.next_bookmark_id += 1;
         let bookmark_name = format!("_Ref_{}", sanitize_bookmark_name(id));
 
@@ -66,6 +84,7 @@ impl CrossRefContext {
 
     /// Register a figure anchor
     pub fn register_figure(&mut self, id: &str, alt_text: &str) -> String {
+        self.record(|| Registration::Figure(id.into(), alt_text.into()));
         self.next_bookmark_id += 1;
         self.figure_num += 1;
 
@@ -90,8 +109,75 @@ impl CrossRefContext {
         bookmark_name
     }
 
+    /// Number a group of sub-figures and register the anchors in it
+    ///
+    /// The group takes the next figure number, and sub-figures are numbered
+    /// after it with a letter, like "4.2(b)". `subfigures` holds the id (if
+    /// any) and text of each sub-figure in order. Returns the group number.
+    pub fn register_figure_group(
+        &mut self,
+        id: Option<&str>,
+        caption: &str,
+        subfigures: &[(Option<&str>, &str)],
+    ) -> String {
+        self.record(|| {
+            let subfigures = subfigures
+                .iter()
+                .map(|(id, text)| (id.map(Into::into), (*text).into()));
+            Registration::FigureGroup(id.map(Into::into), caption.into(), subfigures.collect())
+        });
+        self.figure_num += 1;
+        let number = if self.chapter_num > 0 {
+            format!("{}.{}", self.chapter_num, self.figure_num)
+        } else {
+            self.figure_num.to_string()
+        };
+
+        let mut anchors = Vec::new();
+        if let Some(id) = id {
+            anchors.push((id, caption, number.clone()));
+        }
+        for (i, (sub_id, text)) in subfigures.iter().enumerate() {
+            if let Some(sub_id) = sub_id {
+                let sub_number = format!("{}({})", number, subfigure_letter(i));
+                anchors.push((*sub_id, *text, sub_number));
+            }
+        }
+        for (anchor_id, text, anchor_number) in anchors {
+            self.next_bookmark_id += 1;
+            self.anchors.insert(
+                anchor_id.to_string(),
+                AnchorInfo {
+                    id: anchor_id.to_string(),
+                    bookmark_name: format!("_Ref_{}", sanitize_bookmark_name(anchor_id)),
+                    ref_type: RefType::Figure,
+                    display_text: text.to_string(),
+                    number: Some(anchor_number),
+                },
+            );
+        }
+
+        number
+    }
+
+    /// Number of a registered figure group, found through the group's id or
+    /// one of its sub-figure ids
+    pub fn figure_group_number<'a>(
+        &self,
+        ids: impl IntoIterator<Item = &'a str>,
+    ) -> Option<String> {
+        let number = ids
+            .into_iter()
+            .find_map(|id| self.resolve(id))?
+            .number
+            .as_deref()?;
+        // Sub-figures are numbered "4.2(b)"
+        Some(number.split('(').next().unwrap_or(number).to_string())
+    }
+
     /// Register a table anchor
     pub fn register_table(&mut self, id: &str, caption: &str) -> String {
+        self.record(|| Registration::Table(id.into(), caption.into()));
         self.next_bookmark_id += 1;
         self.table_num += 1;
 
@@ -118,6 +204,7 @@ impl CrossRefContext {
 
     /// Register an equation anchor
     pub fn register_equation(&mut self, id: &str) -> String {
+        self.record(|| Registration::Equation(id.into()));
         self.next_bookmark_id += 1;
         self.equation_num += 1;
 
@@ -144,6 +231,7 @@ impl CrossRefContext {
 
     /// Get current equation number (for display equations without an explicit id)
     pub fn next_equation_number(&mut self) -> String {
+        self.record(|| Registration::EquationNumber);
         self.equation_num += 1;
         if self.chapter_num > 0 {
             format!("{}.{}", self.chapter_num, self.equation_num)
@@ -155,6 +243,7 @@ impl CrossRefContext {
     /// Register a generic anchor (for future extensibility)
     #[allow(dead_code)]
     pub fn register_anchor(&mut self, id: &str, ref_type: RefType, text: &str) -> String {
+        self.record(|| Registration::Anchor(id.into(), ref_type, text.into()));
         self.next_bookmark_id += 1;
         let bookmark_name = format!("_Ref_{}", sanitize_bookmark_name(id));
 
@@ -174,45 +263,47 @@ impl CrossRefContext {
 
     /// Resolve a cross-reference by target ID
     /// Returns the anchor info if found
+    ///
+    /// `{ref:fig:map}` refers to the target `map`, so an anchor declared
+    /// with its prefix (`{#fig:map}`) is found under the bare id too.
     pub fn resolve(&self, target: &str) -> Option<&AnchorInfo> {
-        self.anchors.get(target)
+        self.anchors.get(target).or_else(|| {
+            self.anchors
+                .iter()
+                .filter(|(id, _)| id.split_once(':').is_some_and(|(_, rest)| rest == target))
+                .min_by_key(|(id, _)| id.as_str())
+                .map(|(_, anchor)| anchor)
+        })
     }
 
     /// Get display text for a reference
     /// Returns formatted text like "Figure 1.2" or just the title
     #[allow(dead_code)]
     pub fn get_display_text(&self, target: &str, _ref_type: RefType) -> String {
-        self.get_localized_display_text(target, crate::docx::ooxml::Language::English)
+        self.get_localized_display_text(target, &Strings::new(Language::English))
     }
 
     /// Get localized display text for a reference
-    pub fn get_localized_display_text(
-        &self,
-        target: &str,
-        lang: crate::docx::ooxml::Language,
-    ) -> String {
-        if let Some(anchor) = self.anchors.get(target) {
+    pub fn get_localized_display_text(&self, target: &str, strings: &Strings) -> String {
+        if let Some(anchor) = self.resolve(target) {
             match anchor.ref_type {
                 RefType::Figure => {
                     if let Some(num) = &anchor.number {
-                        format!("{} {}", lang.figure_caption_prefix(), num)
+                        format!("{} {}", strings.get(Label::Figure), num)
                     } else {
                         anchor.display_text.clone()
                     }
                 }
                 RefType::Table => {
                     if let Some(num) = &anchor.number {
-                        format!("{} {}", lang.table_caption_prefix(), num)
+                        format!("{} {}", strings.get(Label::Table), num)
                     } else {
                         anchor.display_text.clone()
                     }
                 }
                 RefType::Chapter => {
                     if let Some(num) = &anchor.number {
-                        match lang {
-                            crate::docx::ooxml::Language::Thai => format!("บทที่ {}", num),
-                            _ => format!("Chapter {}", num),
-                        }
+                        strings.chapter(num)
                     } else {
                         anchor.display_text.clone()
                     }
@@ -227,10 +318,7 @@ impl CrossRefContext {
                 }
                 RefType::Appendix => {
                     if let Some(num) = &anchor.number {
-                        match lang {
-                            crate::docx::ooxml::Language::Thai => format!("ภาคผนวก {}", num),
-                            _ => format!("Appendix {}", num),
-                        }
+                        format!("{} {}", strings.get(Label::Appendix), num)
                     } else {
                         anchor.display_text.clone()
                     }
@@ -243,6 +331,58 @@ impl CrossRefContext {
         }
     }
 
+    /// Start keeping the registrations made from now on
+    #[cfg(feature = "parallel")]
+    pub fn start_recording(&mut self) {
+        self.recorded = Some(Vec::new());
+    }
+
+    /// Stop recording and return the registrations made since [`start_recording`](Self::start_recording)
+    #[cfg(feature = "parallel")]
+    pub fn take_recorded(&mut self) -> Vec<Registration> {
+        self.recorded.take().unwrap_or_default()
+    }
+
+    /// Make the registrations recorded on another context, in order
+    #[cfg(feature = "parallel")]
+    pub fn replay(&mut self, registrations: &[Registration]) {
+        for registration in registrations {
+            match registration {
+                Registration::Heading(id, level, text) => {
+                    self.register_heading(id, *level, text);
+                }
+                Registration::Figure(id, alt_text) => {
+                    self.register_figure(id, alt_text);
+                }
+                Registration::FigureGroup(id, caption, subfigures) => {
+                    let subfigures: Vec<(Option<&str>, &str)> = subfigures
+                        .iter()
+                        .map(|(id, text)| (id.as_deref(), text.as_str()))
+                        .collect();
+                    self.register_figure_group(id.as_deref(), caption, &subfigures);
+                }
+                Registration::Table(id, caption) => {
+                    self.register_table(id, caption);
+                }
+                Registration::Equation(id) => {
+                    self.register_equation(id);
+                }
+                Registration::EquationNumber => {
+                    self.next_equation_number();
+                }
+                Registration::Anchor(id, ref_type, text) => {
+                    self.register_anchor(id, *ref_type, text);
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, registration: impl FnOnce() -> Registration) {
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.push(registration());
+        }
+    }
+
     /// Check if an anchor exists
     #[allow(dead_code)]
     pub fn has_anchor(&self, id: &str) -> bool {
@@ -256,6 +396,23 @@ impl CrossRefContext {
     }
 }
 
+/// Letter of the `index`th sub-figure: a, b, c, ...
+pub(crate) fn subfigure_letter(index: usize) -> char {
+    (b'a' + (index % 26) as u8) as char
+}
+
+/// Id and alt text of each image in a figure group, as passed to
+/// [`CrossRefContext::register_figure_group`]
+pub(crate) fn subfigure_anchors(figures: &[Block]) -> Vec<(Option<&str>, &str)> {
+    figures
+        .iter()
+        .filter_map(|figure| match figure {
+            Block::Image { alt, id, .. } => Some((id.as_deref(), alt.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
 /// Sanitize a string for use as a bookmark name
 /// Keeps only alphanumeric and underscores
 fn sanitize_bookmark_name(s: &str) -> String {
@@ -329,35 +486,24 @@ mod tests {
         ctx.register_table("users", "User List");
         ctx.register_figure("diagram", "Overview Diagram");
 
-        use crate::docx::ooxml::Language;
+        let english = Strings::new(Language::English);
+        let thai = Strings::new(Language::Thai);
 
         // English
+        assert_eq!(ctx.get_localized_display_text("ch1", &english), "Chapter 1");
         assert_eq!(
-            ctx.get_localized_display_text("ch1", Language::English),
-            "Chapter 1"
-        );
-        assert_eq!(
-            ctx.get_localized_display_text("users", Language::English),
+            ctx.get_localized_display_text("users", &english),
             "Table 1.1"
         );
         assert_eq!(
-            ctx.get_localized_display_text("diagram", Language::English),
+            ctx.get_localized_display_text("diagram", &english),
             "Figure 1.1"
         );
 
         // Thai
-        assert_eq!(
-            ctx.get_localized_display_text("ch1", Language::Thai),
-            "บทที่ 1"
-        );
-        assert_eq!(
-            ctx.get_localized_display_text("users", Language::Thai),
-            "ตารางที่ 1.1"
-        );
-        assert_eq!(
-            ctx.get_localized_display_text("diagram", Language::Thai),
-            "รูปที่ 1.1"
-        );
+        assert_eq!(ctx.get_localized_display_text("ch1", &thai), "บทที่ 1");
+        assert_eq!(ctx.get_localized_display_text("users", &thai), "ตารางที่ 1.1");
+        assert_eq!(ctx.get_localized_display_text("diagram", &thai), "รูปที่ 1.1");
     }
 
     #[test]
@@ -376,6 +522,53 @@ mod tests {
         assert_eq!(fig2.number, Some("2.1".to_string()));
     }
 
+    #[test]
+    fn test_register_figure_group() {
+        let mut ctx = CrossRefContext::new();
+        for chapter in 1..=4 {
+            ctx.register_heading(&format!("ch{}", chapter), 1, "Chapter");
+        }
+        ctx.register_figure("first", "First");
+        let number = ctx.register_figure_group(
+            None,
+            "Before and after",
+            &[
+                (Some("before"), "Before"),
+                (None, "Middle"),
+                (Some("after"), "After"),
+            ],
+        );
+
+        assert_eq!(number, "4.2");
+        assert_eq!(
+            ctx.get_display_text("before", RefType::Figure),
+            "Figure 4.2(a)"
+        );
+        assert_eq!(
+            ctx.get_display_text("after", RefType::Figure),
+            "Figure 4.2(c)"
+        );
+        assert_eq!(
+            ctx.figure_group_number(["missing", "after"]),
+            Some("4.2".to_string())
+        );
+        // The next figure follows the group
+        ctx.register_figure("next", "Next");
+        assert_eq!(ctx.resolve("next").unwrap().number, Some("4.3".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_prefixed_ids() {
+        let mut ctx = CrossRefContext::new();
+        ctx.register_figure("fig:map", "Map");
+        ctx.register_table("tbl:map", "Map");
+
+        assert_eq!(ctx.resolve("map").unwrap().id, "fig:map");
+        assert_eq!(ctx.get_display_text("map", RefType::Figure), "Figure 1");
+        assert_eq!(ctx.resolve("tbl:map").unwrap().ref_type, RefType::Table);
+        assert!(ctx.resolve("missing").is_none());
+    }
+
     #[test]
     fn test_sanitize_bookmark_name() {
         assert_eq!(sanitize_bookmark_name("hello-world"), "helloworld");
@@ -0,0 +1,578 @@
+//! EPUB 3 output (`md2docx build --epub`)
+//!
+//! Each chapter is rendered by the HTML renderer as an XHTML document of its
+//! own and listed in the spine in order. Images are stored next to the
+//! chapters, mermaid diagrams and equations stay inline SVG, and the fonts
+//! configured for embedding are added with `@font-face` rules. The
+//! navigation document is built from the chapters' headings.
+
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use zip::write::{FileOptions, ZipWriter};
+
+use crate::diagnostics::{self, WarningCategory};
+use crate::docx::font_embed::{font_name_matches, scan_font_dir, FontVariant};
+use crate::docx::ooxml::xml_writer::XmlWriter;
+use crate::docx::ooxml::CoreProperties;
+use crate::docx::rels_manager::content_hash;
+use crate::html::{self, escape, Book, TocEntry};
+use crate::parser::{extract_inline_text, Block};
+use crate::{DocumentConfig, Language, ParsedDocument, Result};
+
+const MIMETYPE: &str = "application/epub+zip";
+
+/// A font file stored in the package
+struct Font {
+    family: String,
+    variant: FontVariant,
+    path: String,
+    data: Vec<u8>,
+}
+
+/// Write parsed chapters as an EPUB package
+pub(crate) fn write(
+    docs: &[ParsedDocument],
+    lang: Language,
+    config: &DocumentConfig,
+) -> Result<Vec<u8>> {
+    let files: Vec<String> = (1..=docs.len())
+        .map(|i| format!("chapter-{}.xhtml", i))
+        .collect();
+    let book = html::render_book(docs, &files, lang, config)?;
+    let title = docs
+        .first()
+        .map(|doc| html::page_title(doc, config))
+        .unwrap_or_else(|| "Document".to_string());
+    let fonts = embedded_fonts(config);
+
+    let mut css = html::stylesheet(lang, config.fonts.as_ref());
+    for font in &fonts {
+        let (weight, style) = match font.variant {
+            FontVariant::Regular => ("normal", "normal"),
+            FontVariant::Bold => ("bold", "normal"),
+            FontVariant::Italic => ("normal", "italic"),
+            FontVariant::BoldItalic => ("bold", "italic"),
+        };
+        let _ = writeln!(
+            css,
+            "@font-face {{ font-family: \"{}\"; font-weight: {}; font-style: {}; src: url(\"{}\"); }}",
+            font.family.replace(['"', '\\'], ""),
+            weight,
+            style,
+            font.path
+        );
+    }
+
+    let mut package = Package::new(config.compression);
+    package.add("META-INF/container.xml", &container_xml()?)?;
+    package.add(
+        "OEBPS/content.opf",
+        &content_opf(&book, &files, &fonts, &title, lang, config)?,
+    )?;
+    package.add(
+        "OEBPS/nav.xhtml",
+        nav_xhtml(&book, docs, &files, lang, config).as_bytes(),
+    )?;
+    package.add("OEBPS/style.css", css.as_bytes())?;
+    for (i, body) in book.chapters.iter().enumerate() {
+        let chapter_title = chapter_title(&docs[i]).unwrap_or_else(|| title.clone());
+        package.add(
+            &format!("OEBPS/{}", files[i]),
+            xhtml_page(&chapter_title, body, lang).as_bytes(),
+        )?;
+    }
+    for media in &book.media {
+        package.add(&format!("OEBPS/{}", media.path), &media.data)?;
+    }
+    for font in &fonts {
+        package.add(&format!("OEBPS/{}", font.path), &font.data)?;
+    }
+    package.finish()
+}
+
+/// ZIP archive with the `mimetype` entry first and uncompressed, as EPUB
+/// requires
+struct Package {
+    writer: ZipWriter<Cursor<Vec<u8>>>,
+    compression: crate::docx::Compression,
+    started: bool,
+}
+
+impl Package {
+    fn new(compression: crate::docx::Compression) -> Self {
+        Self {
+            writer: ZipWriter::new(Cursor::new(Vec::new())),
+            compression,
+            started: false,
+        }
+    }
+
+    fn file_options(&self, path: &str) -> FileOptions<'static, ()> {
+        // A fixed timestamp keeps the archive identical across rebuilds
+        let options = FileOptions::default()
+            .unix_permissions(0o644)
+            .last_modified_time(zip::DateTime::default());
+        let media = path.starts_with("OEBPS/images/") || path.starts_with("OEBPS/fonts/");
+        if path == "mimetype"
+            || self.compression.level == Some(0)
+            || (media && self.compression.store_media)
+        {
+            return options.compression_method(zip::CompressionMethod::Stored);
+        }
+        options
+            .compression_method(zip::CompressionMethod::Deflated)
+            .compression_level(self.compression.level.map(|level| i64::from(level.min(9))))
+    }
+
+    fn add(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        if !self.started {
+            self.started = true;
+            self.writer
+                .start_file("mimetype", self.file_options("mimetype"))?;
+            self.writer.write_all(MIMETYPE.as_bytes())?;
+        }
+        self.writer.start_file(path, self.file_options(path))?;
+        self.writer.write_all(data)?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Vec<u8>> {
+        Ok(self.writer.finish()?.into_inner())
+    }
+}
+
+/// `META-INF/container.xml`, pointing at the package document
+fn container_xml() -> Result<Vec<u8>> {
+    let mut xml = XmlWriter::new(Vec::new());
+    xml.declaration()?;
+    xml.start(
+        "container",
+        &[
+            ("version", "1.0"),
+            ("xmlns", "urn:oasis:names:tc:opendocument:xmlns:container"),
+        ],
+    )?;
+    xml.start("rootfiles", &[])?;
+    xml.empty(
+        "rootfile",
+        &[
+            ("full-path", "OEBPS/content.opf"),
+            ("media-type", "application/oebps-package+xml"),
+        ],
+    )?;
+    xml.end("rootfiles")?;
+    xml.end("container")?;
+    Ok(xml.into_inner())
+}
+
+/// `content.opf`: metadata, every file of the book and the reading order
+fn content_opf(
+    book: &Book,
+    files: &[String],
+    fonts: &[Font],
+    title: &str,
+    lang: Language,
+    config: &DocumentConfig,
+) -> Result<Vec<u8>> {
+    let author = config
+        .document_meta
+        .as_ref()
+        .map(|meta| meta.author.as_str())
+        .filter(|author| !author.is_empty());
+    // Derived from the title and author, so rebuilds keep the identifier
+    // readers know the book by
+    let key = [title, author.unwrap_or_default(), lang.lang_tag()].join("\0");
+    let identifier = format!("urn:md2docx:{:016x}", content_hash(&key, 0));
+    let modified = if config.reproducible {
+        "1980-01-01T00:00:00Z".to_string()
+    } else {
+        CoreProperties::current_iso_time()
+    };
+
+    let mut xml = XmlWriter::new(Vec::new());
+    xml.declaration()?;
+    xml.start(
+        "package",
+        &[
+            ("xmlns", "http://www.idpf.org/2007/opf"),
+            ("version", "3.0"),
+            ("unique-identifier", "book-id"),
+            ("xml:lang", lang.lang_tag()),
+        ],
+    )?;
+    xml.start(
+        "metadata",
+        &[("xmlns:dc", "http://purl.org/dc/elements/1.1/")],
+    )?;
+    xml.element("dc:identifier", &[("id", "book-id")], &identifier)?;
+    xml.element("dc:title", &[], title)?;
+    xml.element("dc:language", &[], lang.lang_tag())?;
+    if let Some(author) = author {
+        xml.element("dc:creator", &[], author)?;
+    }
+    xml.element("meta", &[("property", "dcterms:modified")], &modified)?;
+    xml.end("metadata")?;
+
+    xml.start("manifest", &[])?;
+    xml.empty(
+        "item",
+        &[
+            ("id", "nav"),
+            ("href", "nav.xhtml"),
+            ("media-type", "application/xhtml+xml"),
+            ("properties", "nav"),
+        ],
+    )?;
+    xml.empty(
+        "item",
+        &[
+            ("id", "css"),
+            ("href", "style.css"),
+            ("media-type", "text/css"),
+        ],
+    )?;
+    for (i, (file, body)) in files.iter().zip(&book.chapters).enumerate() {
+        let id = format!("chapter-{}", i + 1);
+        let mut attrs = vec![
+            ("id", id.as_str()),
+            ("href", file.as_str()),
+            ("media-type", "application/xhtml+xml"),
+        ];
+        if body.contains("<svg") {
+            attrs.push(("properties", "svg"));
+        }
+        xml.empty("item", &attrs)?;
+    }
+    for (i, media) in book.media.iter().enumerate() {
+        let id = format!("image-{}", i + 1);
+        xml.empty(
+            "item",
+            &[
+                ("id", id.as_str()),
+                ("href", media.path.as_str()),
+                ("media-type", media.media_type),
+            ],
+        )?;
+    }
+    for (i, font) in fonts.iter().enumerate() {
+        let id = format!("font-{}", i + 1);
+        let media_type = if font.path.ends_with(".otf") {
+            "font/otf"
+        } else {
+            "font/ttf"
+        };
+        xml.empty(
+            "item",
+            &[
+                ("id", id.as_str()),
+                ("href", font.path.as_str()),
+                ("media-type", media_type),
+            ],
+        )?;
+    }
+    xml.end("manifest")?;
+
+    // The contents come first, or after the cover with `toc.after_cover`
+    let mut spine: Vec<String> = (1..=files.len())
+        .map(|i| format!("chapter-{}", i))
+        .collect();
+    if config.toc.enabled {
+        let at = if config.toc.after_cover && book.has_cover {
+            1
+        } else {
+            0
+        };
+        spine.insert(at.min(spine.len()), "nav".to_string());
+    }
+    xml.start("spine", &[])?;
+    for idref in &spine {
+        xml.empty("itemref", &[("idref", idref.as_str())])?;
+    }
+    xml.end("spine")?;
+    xml.end("package")?;
+    Ok(xml.into_inner())
+}
+
+/// The navigation document, listing the headings as nested lists
+///
+/// Without headings to list, each chapter is listed by its title, since the
+/// list may not be empty.
+fn nav_xhtml(
+    book: &Book,
+    docs: &[ParsedDocument],
+    files: &[String],
+    lang: Language,
+    config: &DocumentConfig,
+) -> String {
+    let toc = &config.toc;
+    let mut entries: Vec<TocEntry> = book
+        .headings
+        .iter()
+        .filter(|entry| toc.includes_level(entry.level))
+        .cloned()
+        .collect();
+    if entries.is_empty() {
+        entries = docs
+            .iter()
+            .zip(files)
+            .enumerate()
+            .map(|(i, (doc, file))| TocEntry {
+                level: 1,
+                id: String::new(),
+                text: chapter_title(doc).unwrap_or_else(|| format!("{}", i + 1)),
+                file: file.clone(),
+            })
+            .collect();
+    }
+
+    let mut body = String::from("<nav epub:type=\"toc\" id=\"toc\" class=\"toc\">\n");
+    let _ = writeln!(body, "<h1>{}</h1>", escape(&toc.title));
+    body.push_str("<ol>\n");
+    // Levels of the open items, and whether each has a nested list open
+    let mut open: Vec<(u8, bool)> = Vec::new();
+    for entry in &entries {
+        while let Some(&(level, nested)) = open.last() {
+            if level < entry.level {
+                break;
+            }
+            open.pop();
+            body.push_str(if nested { "</ol>\n</li>\n" } else { "</li>\n" });
+        }
+        if let Some(parent) = open.last_mut() {
+            if !parent.1 {
+                parent.1 = true;
+                body.push_str("\n<ol>\n");
+            }
+        }
+        let href = if entry.id.is_empty() {
+            entry.file.clone()
+        } else {
+            format!("{}#{}", entry.file, entry.id)
+        };
+        let _ = write!(
+            body,
+            "<li><a href=\"{}\">{}</a>",
+            escape(&href),
+            escape(&entry.text)
+        );
+        open.push((entry.level, false));
+    }
+    while let Some((_, nested)) = open.pop() {
+        body.push_str(if nested { "</ol>\n</li>\n" } else { "</li>\n" });
+    }
+    body.push_str("</ol>\n</nav>\n");
+    xhtml_page(&toc.title, &body, lang)
+}
+
+/// A complete XHTML document around a chapter body
+fn xhtml_page(title: &str, body: &str, lang: Language) -> String {
+    let mut page = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n");
+    let _ = writeln!(
+        page,
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{0}\" xml:lang=\"{0}\">",
+        lang.lang_tag()
+    );
+    page.push_str("<head>\n<meta charset=\"utf-8\"/>\n");
+    let _ = writeln!(page, "<title>{}</title>", escape(title));
+    page.push_str("<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n");
+    page.push_str("</head>\n<body>\n<main>\n");
+    page.push_str(body);
+    page.push_str("</main>\n</body>\n</html>\n");
+    page
+}
+
+/// Text of a chapter's first heading
+fn chapter_title(doc: &ParsedDocument) -> Option<String> {
+    doc.blocks.iter().find_map(|block| match block {
+        Block::Heading { content, .. } => Some(extract_inline_text(content)),
+        _ => None,
+    })
+}
+
+/// Font files for the configured default and code fonts: those already
+/// prepared for DOCX embedding, or else those found in `embed_dir`
+fn embedded_fonts(config: &DocumentConfig) -> Vec<Font> {
+    // The stylesheet refers to fonts by their configured names
+    let names: Vec<&str> = config
+        .fonts
+        .iter()
+        .flat_map(|fonts| [fonts.default.as_deref(), fonts.code.as_deref()])
+        .flatten()
+        .filter(|name| !name.is_empty())
+        .collect();
+    if !config.embedded_fonts.is_empty() {
+        return config
+            .embedded_fonts
+            .iter()
+            .enumerate()
+            .map(|(i, font)| {
+                let data = font.font_data();
+                let extension = if data.starts_with(b"OTTO") {
+                    "otf"
+                } else {
+                    "ttf"
+                };
+                Font {
+                    family: names
+                        .iter()
+                        .find(|name| font_name_matches(&font.font_name, name))
+                        .map_or_else(|| font.font_name.clone(), |name| name.to_string()),
+                    variant: font.variant,
+                    path: format!("fonts/font{}.{}", i + 1, extension),
+                    data,
+                }
+            })
+            .collect();
+    }
+    let Some(dir) = &config.embed_dir else {
+        return Vec::new();
+    };
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let families = match scan_font_dir(dir) {
+        Ok(families) => families,
+        Err(e) => {
+            diagnostics::warn(WarningCategory::Font, e.to_string());
+            return Vec::new();
+        }
+    };
+
+    let mut stored: Vec<PathBuf> = Vec::new();
+    let mut fonts = Vec::new();
+    for name in names {
+        let mut found = false;
+        for (family, variants) in &families {
+            if !font_name_matches(family, name) {
+                continue;
+            }
+            found = true;
+            for (path, variant) in variants {
+                if stored.contains(path) {
+                    continue;
+                }
+                let data = match std::fs::read(path) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        diagnostics::warn(
+                            WarningCategory::Font,
+                            format!("Could not read {}: {}", path.display(), e),
+                        );
+                        continue;
+                    }
+                };
+                let extension = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("ttf")
+                    .to_ascii_lowercase();
+                stored.push(path.clone());
+                fonts.push(Font {
+                    family: name.to_string(),
+                    variant: *variant,
+                    path: format!("fonts/font{}.{}", stored.len(), extension),
+                    data,
+                });
+            }
+        }
+        if !found {
+            diagnostics::warn(
+                WarningCategory::Font,
+                format!("Font '{}' not found in {}", name, dir.display()),
+            );
+        }
+    }
+    fonts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileMap;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    fn read(archive: &mut zip::ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut text = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn test_epub_package() {
+        let mut files = FileMap::new();
+        files.insert("book/logo.png", b"\x89PNG\r\n\x1a\n".to_vec());
+        let mut config = DocumentConfig {
+            title: "Handbook".to_string(),
+            base_path: Some(PathBuf::from("book")),
+            files: Some(Arc::new(files)),
+            ..Default::default()
+        };
+        config.toc.enabled = true;
+        let chapters = [
+            "# Cover\n",
+            "# One {#one}\n\nLine<br>break and a note[^1].\n\n![Logo](logo.png)\n\n[^1]: Noted.\n",
+            "# Two\n\n## Part\n\nSee {ref:one}.\n",
+        ];
+        let epub = crate::markdown_to_epub(&chapters, Language::English, &config).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(epub)).unwrap();
+        let mimetype = archive.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), zip::CompressionMethod::Stored);
+        drop(mimetype);
+
+        let opf = read(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Handbook</dc:title>"));
+        // The identifier must not change between toolchains
+        assert!(opf.contains(">urn:md2docx:5da015e824b9d8e1</dc:identifier>"));
+        assert!(opf.contains(r#"href="images/image1.png" media-type="image/png""#));
+        let spine = &opf[opf.find("<spine>").unwrap()..];
+        assert!(spine.find("chapter-1").unwrap() < spine.find("\"nav\"").unwrap());
+        assert!(spine.find("\"nav\"").unwrap() < spine.find("chapter-2").unwrap());
+
+        let one = read(&mut archive, "OEBPS/chapter-2.xhtml");
+        assert!(one.starts_with("<?xml"));
+        assert!(one.contains("<img src=\"images/image1.png\" alt=\"Logo\"/>"));
+        assert!(one.contains("<li id=\"fn-1\">"));
+        assert!(!one.contains("<br>"));
+        let two = read(&mut archive, "OEBPS/chapter-3.xhtml");
+        assert!(two.contains("href=\"chapter-2.xhtml#one\""));
+
+        let nav = read(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains("<a href=\"chapter-2.xhtml#one\">One</a>"));
+        assert!(nav.contains("<ol>\n<li><a href=\"chapter-3.xhtml#section-3\">Part</a>"));
+        assert!(!nav.contains(">Cover</a>"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    fn test_epub_modified_date() {
+        let modified = |config: &DocumentConfig| {
+            let epub = crate::markdown_to_epub(&["# One\n"], Language::English, config).unwrap();
+            let mut archive = zip::ZipArchive::new(Cursor::new(epub)).unwrap();
+            let opf = read(&mut archive, "OEBPS/content.opf");
+            let tag = r#"<meta property="dcterms:modified">"#;
+            let start = opf.find(tag).unwrap() + tag.len();
+            opf[start..start + 20].to_string()
+        };
+        let mut config = DocumentConfig::default();
+
+        let today = || chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let before = today();
+        let date = modified(&config);
+        let after = today();
+        let parsed = chrono::NaiveDateTime::parse_from_str(&date, "%Y-%m-%dT%H:%M:%SZ").unwrap();
+        let day = parsed.format("%Y-%m-%d").to_string();
+        assert!(day == before || day == after, "{date} is not today");
+
+        config.reproducible = true;
+        assert_eq!(modified(&config), "1980-01-01T00:00:00Z");
+    }
+}
@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::diagnostics::{Diagnostic, SourceLocation};
+
 /// Main error type for md2docx operations
 #[derive(Debug, Error)]
 pub enum Error {
@@ -49,6 +51,14 @@ pub enum Error {
     #[error("Include error: {0}")]
     Include(String),
 
+    /// Generated document failed the `self-check` validation
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    /// Cross-reference or link that points nowhere (strict builds)
+    #[error("Reference error: {0}")]
+    Reference(String),
+
     /// Regex compilation error
     #[error("Regex error: {0}")]
     Regex(String),
@@ -64,6 +74,66 @@ pub enum Error {
     /// Feature not implemented yet
     #[error("Not implemented: {0}")]
     NotImplemented(String),
+
+    /// Error about a place in a source file
+    #[error("{location}: {source}")]
+    Located {
+        location: SourceLocation,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Attach the source location the error is about
+    ///
+    /// An error that already has a location keeps it, since the innermost
+    /// one is the most precise.
+    pub fn at(self, location: SourceLocation) -> Self {
+        match self {
+            Error::Located { .. } => self,
+            error => Error::Located {
+                location,
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// Source location the error is about, when known
+    pub fn location(&self) -> Option<&SourceLocation> {
+        match self {
+            Error::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Short machine-friendly name of the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Parse(_) => "parse",
+            Error::Io(_) => "io",
+            Error::Xml(_) => "xml",
+            Error::Zip(_) => "zip",
+            Error::Config(_) => "config",
+            Error::Template(_) => "template",
+            Error::Image(_) => "image",
+            Error::Mermaid(_) => "mermaid",
+            Error::Math(_) => "math",
+            Error::Git(_) => "git",
+            Error::Include(_) => "include",
+            Error::Reference(_) => "reference",
+            Error::Validation(_) => "validation",
+            Error::Regex(_) => "regex",
+            Error::Utf8(_) => "utf8",
+            Error::TemplateParse(_) => "template-parse",
+            Error::NotImplemented(_) => "not-implemented",
+            Error::Located { source, .. } => source.code(),
+        }
+    }
+
+    /// The error as a diagnostic with its code and location
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::from(self)
+    }
 }
 
 /// Result type alias for md2docx operations
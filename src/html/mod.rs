@@ -0,0 +1,1393 @@
+//! HTML output (`md2docx build --html`)
+//!
+//! Renders the same parsed document as the DOCX into one self-contained
+//! page: the DOCX styles are approximated with CSS, local images are
+//! embedded as data URIs, and mermaid diagrams and equations are inlined as
+//! SVG. Figures, tables and equations are numbered as in the DOCX, and
+//! the table of contents links to the headings. Page layout, headers,
+//! footers and watermarks have no HTML equivalent and are left out.
+//!
+//! [`render_book`] renders the chapters of an EPUB the same way, as XHTML
+//! with the images collected as files of their own.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use base64::Engine as _;
+
+use crate::diagnostics::{self, WarningCategory};
+use crate::docx::highlight::highlight_code;
+use crate::docx::math_rex::render_latex_to_svg;
+use crate::docx::xref::{subfigure_anchors, subfigure_letter, CrossRefContext};
+use crate::docx::FontConfig;
+use crate::i18n::{Label, Strings};
+use crate::parser::{
+    callout_glyph, code_with_callouts, extract_inline_text, Alignment, Block, Inline, ListItem,
+    TableCell,
+};
+use crate::{DocumentConfig, Language, ParsedDocument, Result};
+
+/// Render a parsed document to a standalone HTML page
+pub fn render(doc: &ParsedDocument, lang: Language, config: &DocumentConfig) -> Result<String> {
+    let blocks = match config.include_resolver() {
+        Some(mut resolver) => resolver.resolve_blocks(doc.blocks.clone())?,
+        None => doc.blocks.clone(),
+    };
+
+    let mut renderer = Renderer::new(lang, config);
+    renderer.register_anchors(&blocks);
+    renderer.in_cover =
+        !config.process_all_headings && blocks.iter().any(|b| matches!(b, Block::ThematicBreak));
+    renderer.blocks(&blocks);
+    renderer.notes(&doc.footnotes);
+
+    let mut body = std::mem::take(&mut renderer.out);
+    if let Some(contents) = renderer.contents() {
+        let at = if config.toc.after_cover {
+            renderer.cover_end.unwrap_or(0)
+        } else {
+            0
+        };
+        body.insert_str(at, &contents);
+    }
+
+    let title = page_title(doc, config);
+    let mut page = String::new();
+    page.push_str("<!DOCTYPE html>\n");
+    let _ = writeln!(page, "<html lang=\"{}\">", lang.lang_tag());
+    page.push_str("<head>\n<meta charset=\"utf-8\">\n");
+    page.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    let _ = writeln!(page, "<title>{}</title>", escape(&title));
+    if let Some(meta) = &config.document_meta {
+        if !meta.author.is_empty() {
+            let _ = writeln!(
+                page,
+                "<meta name=\"author\" content=\"{}\">",
+                escape(&meta.author)
+            );
+        }
+        if !meta.subtitle.is_empty() {
+            let _ = writeln!(
+                page,
+                "<meta name=\"description\" content=\"{}\">",
+                escape(&meta.subtitle)
+            );
+        }
+    }
+    page.push_str("<meta name=\"generator\" content=\"md2docx\">\n");
+    let _ = writeln!(
+        page,
+        "<style>\n{}</style>",
+        stylesheet(lang, config.fonts.as_ref())
+    );
+    page.push_str("</head>\n<body>\n<main>\n");
+    page.push_str(&body);
+    page.push_str("</main>\n</body>\n</html>\n");
+    Ok(page)
+}
+
+/// Chapters rendered as XHTML for an EPUB
+pub(crate) struct Book {
+    /// Body of each chapter, to be placed in `<main>`
+    pub chapters: Vec<String>,
+    /// Headings for the table of contents
+    pub headings: Vec<TocEntry>,
+    /// Local images the chapters refer to
+    pub media: Vec<Media>,
+    /// Whether the first chapter is a cover, left out of the contents
+    pub has_cover: bool,
+}
+
+/// An image stored in the EPUB next to the chapters
+pub(crate) struct Media {
+    source: PathBuf,
+    /// Path relative to the chapters
+    pub path: String,
+    pub media_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Render chapters as XHTML for an EPUB, `files` naming the file each goes
+/// into
+///
+/// Numbering runs on across chapters and cross-references link to the
+/// chapter their target is in. As in the combined document, the first
+/// chapter is a cover unless `process_all_headings` is set; each chapter's
+/// footnotes are listed at its end.
+pub(crate) fn render_book(
+    docs: &[ParsedDocument],
+    files: &[String],
+    lang: Language,
+    config: &DocumentConfig,
+) -> Result<Book> {
+    let mut chapters = Vec::with_capacity(docs.len());
+    for doc in docs {
+        chapters.push(match config.include_resolver() {
+            Some(mut resolver) => resolver.resolve_blocks(doc.blocks.clone())?,
+            None => doc.blocks.clone(),
+        });
+    }
+
+    let mut renderer = Renderer::new(lang, config);
+    renderer.xhtml = true;
+    renderer.media = Some(Vec::new());
+    for (blocks, file) in chapters.iter().zip(files) {
+        renderer.file = file.clone();
+        renderer.register_anchors(blocks);
+    }
+    let has_cover = !config.process_all_headings
+        && (chapters.len() > 1
+            || chapters
+                .first()
+                .is_some_and(|blocks| blocks.iter().any(|b| matches!(b, Block::ThematicBreak))));
+    renderer.in_cover = has_cover;
+
+    let mut bodies = Vec::with_capacity(chapters.len());
+    for (i, (blocks, file)) in chapters.iter().zip(files).enumerate() {
+        renderer.file = file.clone();
+        // A new chapter ends the cover like a thematic break
+        if i > 0 {
+            renderer.in_cover = false;
+        }
+        renderer.blocks(blocks);
+        renderer.notes(&docs[i].footnotes);
+        bodies.push(std::mem::take(&mut renderer.out));
+    }
+
+    Ok(Book {
+        chapters: bodies,
+        headings: renderer.headings,
+        media: renderer.media.unwrap_or_default(),
+        has_cover,
+    })
+}
+
+/// Title of the page: the configured title, then the metadata's, then the
+/// frontmatter's
+pub(crate) fn page_title(doc: &ParsedDocument, config: &DocumentConfig) -> String {
+    [
+        Some(config.title.as_str()),
+        config
+            .document_meta
+            .as_ref()
+            .map(|meta| meta.title.as_str()),
+        doc.frontmatter.as_ref().and_then(|fm| fm.title.as_deref()),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|title| !title.trim().is_empty())
+    .unwrap_or("Document")
+    .to_string()
+}
+
+/// A heading listed in the table of contents
+#[derive(Clone)]
+pub(crate) struct TocEntry {
+    pub level: u8,
+    pub id: String,
+    pub text: String,
+    /// File the heading is in, empty for a single page
+    pub file: String,
+}
+
+struct Renderer<'a> {
+    out: String,
+    config: &'a DocumentConfig,
+    strings: Strings,
+    xref: CrossRefContext,
+    /// Footnote labels in the order they are first referenced
+    footnotes: Vec<String>,
+    figure_count: u32,
+    table_count: u32,
+    /// Headings without an id get `section-N`
+    section_count: u32,
+    /// Before the first thematic break, whose headings are left out of the
+    /// contents
+    in_cover: bool,
+    /// Where the body continues after the cover
+    cover_end: Option<usize>,
+    toc_off: bool,
+    headings: Vec<TocEntry>,
+    /// Footnotes already listed, by an earlier chapter
+    notes_written: usize,
+    /// Write XHTML: void elements closed and raw HTML that isn't
+    /// well-formed left out
+    xhtml: bool,
+    /// Local images collected as files instead of data URIs
+    media: Option<Vec<Media>>,
+    /// File each anchor is in, when the output is split into several
+    anchor_files: HashMap<String, String>,
+    /// File being written
+    file: String,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(lang: Language, config: &'a DocumentConfig) -> Self {
+        Self {
+            out: String::new(),
+            config,
+            strings: Strings::new(lang).with_overrides(config.string_overrides.clone()),
+            xref: CrossRefContext::new(),
+            footnotes: Vec::new(),
+            figure_count: 0,
+            table_count: 0,
+            section_count: 0,
+            in_cover: false,
+            cover_end: None,
+            toc_off: false,
+            headings: Vec::new(),
+            notes_written: 0,
+            xhtml: false,
+            media: None,
+            anchor_files: HashMap::new(),
+            file: String::new(),
+        }
+    }
+
+    /// Number headings, figures, tables and equations with ids up front,
+    /// so references to later targets resolve too
+    fn register_anchors(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            if let Some(id) = anchor_id(block) {
+                self.anchor_files.insert(id.to_string(), self.file.clone());
+            }
+            match block {
+                Block::Heading {
+                    level,
+                    content,
+                    id: Some(id),
+                } => {
+                    self.xref
+                        .register_heading(id, *level, &extract_inline_text(content));
+                }
+                Block::Image {
+                    alt, id: Some(id), ..
+                } => {
+                    self.xref.register_figure(id, alt);
+                }
+                Block::Mermaid { id: Some(id), .. } => {
+                    self.xref.register_figure(id, "Mermaid Diagram");
+                }
+                Block::Table {
+                    caption,
+                    id: Some(id),
+                    ..
+                } => {
+                    self.xref
+                        .register_table(id, caption.as_deref().unwrap_or(""));
+                }
+                Block::MathBlock { id: Some(id), .. } => {
+                    self.xref.register_equation(id);
+                }
+                Block::BlockQuote(blocks)
+                | Block::FontGroup { blocks, .. }
+                | Block::LangGroup { blocks, .. }
+                | Block::Include {
+                    resolved: Some(blocks),
+                    ..
+                } => self.register_anchors(blocks),
+                Block::List { items, .. } => {
+                    for item in items {
+                        self.register_anchors(&item.content);
+                    }
+                }
+                Block::ParallelText { rows } => {
+                    for (source, translation) in rows {
+                        self.register_anchors(source);
+                        self.register_anchors(translation);
+                    }
+                }
+                Block::FigureGroup {
+                    figures,
+                    caption,
+                    id,
+                } => {
+                    let subfigures = subfigure_anchors(figures);
+                    if id.is_none() && subfigures.iter().all(|(sub_id, _)| sub_id.is_none()) {
+                        continue;
+                    }
+                    for sub_id in subfigures.iter().filter_map(|(sub_id, _)| *sub_id) {
+                        self.anchor_files
+                            .insert(sub_id.to_string(), self.file.clone());
+                    }
+                    let caption = caption.as_deref().map(extract_inline_text);
+                    self.xref.register_figure_group(
+                        id.as_deref(),
+                        caption.as_deref().unwrap_or(""),
+                        &subfigures,
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Heading { level, content, id } => self.heading(*level, content, id.as_deref()),
+            Block::Paragraph(content) => {
+                self.out.push_str("<p>");
+                self.inlines(content);
+                self.out.push_str("</p>\n");
+            }
+            Block::CodeBlock {
+                lang,
+                content,
+                filename,
+                highlight_lines,
+                show_line_numbers,
+                callouts,
+            } => self.code_block(
+                &code_with_callouts(content, callouts),
+                lang.as_deref(),
+                filename.as_deref(),
+                highlight_lines,
+                *show_line_numbers,
+            ),
+            Block::CodeCallouts(items) => {
+                self.out.push_str("<dl class=\"callouts\">\n");
+                for (number, text) in items {
+                    let _ = write!(self.out, "<dt>{}</dt><dd>", callout_glyph(*number));
+                    self.inlines(text);
+                    self.out.push_str("</dd>\n");
+                }
+                self.out.push_str("</dl>\n");
+            }
+            Block::BlockQuote(blocks) => {
+                self.out.push_str("<blockquote>\n");
+                self.blocks(blocks);
+                self.out.push_str("</blockquote>\n");
+            }
+            Block::List {
+                ordered,
+                start,
+                items,
+            } => self.list(*ordered, *start, items),
+            Block::Table {
+                headers,
+                alignments,
+                rows,
+                caption,
+                id,
+            } => self.table(headers, alignments, rows, caption.as_deref(), id.as_deref()),
+            Block::Image {
+                alt,
+                src,
+                title,
+                width,
+                id,
+                caption,
+            } => self.image_block(
+                alt,
+                src,
+                title.as_deref(),
+                width.as_deref(),
+                id.as_deref(),
+                caption.as_deref(),
+            ),
+            Block::ThematicBreak => {
+                let _ = writeln!(self.out, "<hr{}", self.void());
+                if self.in_cover {
+                    self.in_cover = false;
+                    self.cover_end = Some(self.out.len());
+                }
+            }
+            Block::Mermaid { content, id } => self.mermaid(content, id.as_deref()),
+            Block::Html(html) => self.html(html),
+            Block::MathBlock { content, id } => self.math_block(content, id.as_deref()),
+            Block::Include {
+                resolved: Some(blocks),
+                ..
+            } => self.blocks(blocks),
+            // Left unresolved when no include configuration is given
+            Block::Include { .. } | Block::CodeInclude { .. } => {}
+            Block::FontGroup { font, blocks } => {
+                let _ = writeln!(
+                    self.out,
+                    "<div style=\"font-family: {}\">",
+                    escape(&font_family(font))
+                );
+                self.blocks(blocks);
+                self.out.push_str("</div>\n");
+            }
+            Block::LangGroup { lang, blocks } => {
+                let _ = writeln!(self.out, "<div lang=\"{}\">", escape(lang));
+                self.blocks(blocks);
+                self.out.push_str("</div>\n");
+            }
+            Block::FigureGroup {
+                figures,
+                caption,
+                id,
+            } => self.figure_group(figures, caption.as_deref(), id.as_deref()),
+            Block::ParallelText { rows } => {
+                self.out.push_str("<div class=\"parallel\">\n");
+                for (source, translation) in rows {
+                    self.out.push_str("<div>\n");
+                    self.blocks(source);
+                    self.out.push_str("</div>\n<div>\n");
+                    self.blocks(translation);
+                    self.out.push_str("</div>\n");
+                }
+                self.out.push_str("</div>\n");
+            }
+        }
+    }
+
+    fn heading(&mut self, level: u8, content: &[Inline], id: Option<&str>) {
+        let level = level.clamp(1, 6);
+        let id = match id {
+            Some(id) => id.to_string(),
+            None => {
+                self.section_count += 1;
+                format!("section-{}", self.section_count)
+            }
+        };
+        if !self.in_cover && !self.toc_off {
+            self.headings.push(TocEntry {
+                level,
+                id: id.clone(),
+                text: extract_inline_text(content),
+                file: self.file.clone(),
+            });
+        }
+        let _ = write!(self.out, "<h{} id=\"{}\">", level, escape(&id));
+        self.inlines(content);
+        let _ = writeln!(self.out, "</h{}>", level);
+    }
+
+    fn code_block(
+        &mut self,
+        content: &str,
+        lang: Option<&str>,
+        filename: Option<&str>,
+        highlight_lines: &[u32],
+        show_line_numbers: bool,
+    ) {
+        self.out.push_str("<figure class=\"code\">\n");
+        if let Some(name) = filename {
+            let _ = writeln!(self.out, "<figcaption>{}</figcaption>", escape(name));
+        }
+        match lang {
+            Some(lang) => {
+                let _ = write!(self.out, "<pre><code class=\"language-{}\">", escape(lang));
+            }
+            None => self.out.push_str("<pre><code>"),
+        }
+        let lines = highlight_code(content.trim_end_matches('\n'), lang);
+        for (i, tokens) in lines.iter().enumerate() {
+            let number = i as u32 + 1;
+            if i > 0 {
+                self.out.push('\n');
+            }
+            let highlighted = highlight_lines.contains(&number);
+            if highlighted {
+                self.out.push_str("<mark>");
+            }
+            if show_line_numbers {
+                let _ = write!(self.out, "<span class=\"line-number\">{}</span>", number);
+            }
+            for (text, color) in tokens {
+                let text = escape(text.trim_end_matches(['\r', '\n']));
+                match color {
+                    Some(hex) => {
+                        let _ = write!(self.out, "<span style=\"color: #{}\">{}</span>", hex, text);
+                    }
+                    None => self.out.push_str(&text),
+                }
+            }
+            if highlighted {
+                self.out.push_str("</mark>");
+            }
+        }
+        self.out.push_str("</code></pre>\n</figure>\n");
+    }
+
+    fn list(&mut self, ordered: bool, start: Option<u32>, items: &[ListItem]) {
+        match (ordered, start) {
+            (true, Some(start)) if start != 1 => {
+                let _ = writeln!(self.out, "<ol start=\"{}\">", start);
+            }
+            (true, _) => self.out.push_str("<ol>\n"),
+            (false, _) => self.out.push_str("<ul>\n"),
+        }
+        for item in items {
+            match item.checked {
+                Some(checked) => {
+                    let (disabled, checked) = match (self.xhtml, checked) {
+                        (true, true) => (" disabled=\"disabled\"", " checked=\"checked\""),
+                        (true, false) => (" disabled=\"disabled\"", ""),
+                        (false, true) => (" disabled", " checked"),
+                        (false, false) => (" disabled", ""),
+                    };
+                    let _ = write!(
+                        self.out,
+                        "<li class=\"task\"><input type=\"checkbox\"{}{}{}",
+                        disabled,
+                        checked,
+                        self.void()
+                    );
+                }
+                None => self.out.push_str("<li>"),
+            }
+            // A single paragraph stays on the marker's line
+            match item.content.as_slice() {
+                [Block::Paragraph(content)] => self.inlines(content),
+                content => {
+                    self.out.push('\n');
+                    self.blocks(content);
+                }
+            }
+            self.out.push_str("</li>\n");
+        }
+        self.out
+            .push_str(if ordered { "</ol>\n" } else { "</ul>\n" });
+    }
+
+    fn table(
+        &mut self,
+        headers: &[TableCell],
+        alignments: &[Alignment],
+        rows: &[Vec<TableCell>],
+        caption: Option<&str>,
+        id: Option<&str>,
+    ) {
+        let number = match id {
+            Some(id) => self.anchor_number(id),
+            None => {
+                self.table_count += 1;
+                self.table_count.to_string()
+            }
+        };
+        match id {
+            Some(id) => {
+                let _ = writeln!(self.out, "<table id=\"{}\">", escape(id));
+            }
+            None => self.out.push_str("<table>\n"),
+        }
+        if let Some(caption) = caption {
+            let _ = writeln!(
+                self.out,
+                "<caption>{} {}: {}</caption>",
+                escape(self.strings.get(Label::Table)),
+                number,
+                escape(caption)
+            );
+        }
+        if !headers.is_empty() {
+            self.out.push_str("<thead>\n");
+            self.row(headers, alignments, "th");
+            self.out.push_str("</thead>\n");
+        }
+        self.out.push_str("<tbody>\n");
+        for row in rows {
+            self.row(row, alignments, "td");
+        }
+        self.out.push_str("</tbody>\n</table>\n");
+    }
+
+    fn row(&mut self, cells: &[TableCell], alignments: &[Alignment], tag: &str) {
+        self.out.push_str("<tr>");
+        for (i, cell) in cells.iter().enumerate() {
+            let align = match alignments.get(i).copied().unwrap_or_default() {
+                Alignment::Left => " style=\"text-align: left\"",
+                Alignment::Center => " style=\"text-align: center\"",
+                Alignment::Right => " style=\"text-align: right\"",
+                Alignment::None => "",
+            };
+            let _ = write!(self.out, "<{}{}>", tag, align);
+            self.inlines(&cell.content);
+            let _ = write!(self.out, "</{}>", tag);
+        }
+        self.out.push_str("</tr>\n");
+    }
+
+    fn image_block(
+        &mut self,
+        alt: &str,
+        src: &str,
+        title: Option<&str>,
+        width: Option<&str>,
+        id: Option<&str>,
+        caption: Option<&[Inline]>,
+    ) {
+        let number = match id {
+            Some(id) => self.anchor_number(id),
+            None => {
+                self.figure_count += 1;
+                self.figure_count.to_string()
+            }
+        };
+        match id {
+            Some(id) => {
+                let _ = writeln!(self.out, "<figure id=\"{}\">", escape(id));
+            }
+            None => self.out.push_str("<figure>\n"),
+        }
+        match self.image_src(src) {
+            Some(url) => {
+                let _ = write!(self.out, "<img src=\"{}\" alt=\"{}\"", url, escape(alt));
+                if let Some(title) = title {
+                    let _ = write!(self.out, " title=\"{}\"", escape(title));
+                }
+                if let Some(width) = width.and_then(css_length) {
+                    let _ = write!(self.out, " style=\"width: {}\"", width);
+                }
+                let _ = writeln!(self.out, "{}", self.void());
+            }
+            None => {
+                let _ = writeln!(self.out, "<p><em>{}</em></p>", escape(alt));
+            }
+        }
+        if let Some(caption) = caption {
+            let _ = write!(
+                self.out,
+                "<figcaption>{} {}: ",
+                escape(self.strings.get(Label::Figure)),
+                number
+            );
+            self.inlines(caption);
+            self.out.push_str("</figcaption>\n");
+        } else if !alt.is_empty() {
+            let _ = writeln!(
+                self.out,
+                "<figcaption>{} {}: {}</figcaption>",
+                escape(self.strings.get(Label::Figure)),
+                number,
+                escape(alt)
+            );
+        }
+        self.out.push_str("</figure>\n");
+    }
+
+    /// Sub-figures side by side, each with an `(a)` caption, then the
+    /// shared caption
+    fn figure_group(&mut self, figures: &[Block], caption: Option<&[Inline]>, id: Option<&str>) {
+        let sub_ids = subfigure_anchors(figures)
+            .into_iter()
+            .filter_map(|(sub_id, _)| sub_id);
+        let number = match self.xref.figure_group_number(id.into_iter().chain(sub_ids)) {
+            Some(number) => number,
+            None => {
+                self.figure_count += 1;
+                self.figure_count.to_string()
+            }
+        };
+        match id {
+            Some(id) => {
+                let _ = writeln!(self.out, "<figure class=\"group\" id=\"{}\">", escape(id));
+            }
+            None => self.out.push_str("<figure class=\"group\">\n"),
+        }
+        self.out.push_str("<div class=\"subfigures\">\n");
+        let images = figures.iter().filter_map(|figure| match figure {
+            Block::Image {
+                alt,
+                src,
+                width,
+                id,
+                caption,
+                ..
+            } => Some((alt, src, width, id, caption)),
+            _ => None,
+        });
+        for (i, (alt, src, width, sub_id, sub_caption)) in images.enumerate() {
+            match sub_id {
+                Some(sub_id) => {
+                    let _ = writeln!(self.out, "<figure id=\"{}\">", escape(sub_id));
+                }
+                None => self.out.push_str("<figure>\n"),
+            }
+            match self.image_src(src) {
+                Some(url) => {
+                    let _ = write!(self.out, "<img src=\"{}\" alt=\"{}\"", url, escape(alt));
+                    if let Some(width) = width.as_deref().and_then(css_length) {
+                        let _ = write!(self.out, " style=\"width: {}\"", width);
+                    }
+                    let _ = writeln!(self.out, "{}", self.void());
+                }
+                None => {
+                    let _ = writeln!(self.out, "<p><em>{}</em></p>", escape(alt));
+                }
+            }
+            let _ = write!(self.out, "<figcaption>({}) ", subfigure_letter(i));
+            match sub_caption {
+                Some(inlines) => self.inlines(inlines),
+                None => self.out.push_str(&escape(alt)),
+            }
+            self.out.push_str("</figcaption>\n</figure>\n");
+        }
+        self.out.push_str("</div>\n");
+        let _ = write!(
+            self.out,
+            "<figcaption>{} {}",
+            escape(self.strings.get(Label::Figure)),
+            number
+        );
+        if let Some(caption) = caption {
+            self.out.push_str(": ");
+            self.inlines(caption);
+        }
+        self.out.push_str("</figcaption>\n</figure>\n");
+    }
+
+    fn mermaid(&mut self, content: &str, id: Option<&str>) {
+        match crate::mermaid::render_to_svg(content) {
+            Ok(svg) => {
+                match id {
+                    Some(id) => {
+                        let _ =
+                            writeln!(self.out, "<figure class=\"diagram\" id=\"{}\">", escape(id));
+                    }
+                    None => self.out.push_str("<figure class=\"diagram\">\n"),
+                }
+                self.out.push_str(inline_svg(&svg));
+                self.out.push_str("\n</figure>\n");
+            }
+            Err(e) => {
+                diagnostics::warn(
+                    WarningCategory::Diagram,
+                    format!("Mermaid diagram shown as source in the HTML: {}", e),
+                );
+                self.code_block(content, None, None, &[], false);
+            }
+        }
+    }
+
+    fn math_block(&mut self, content: &str, id: Option<&str>) {
+        let Some(svg) = self.math(content, true) else {
+            self.code_block(content, None, None, &[], false);
+            return;
+        };
+        match id {
+            Some(id) => {
+                let _ = writeln!(
+                    self.out,
+                    "<div class=\"equation\" id=\"{}\">{}<span class=\"equation-number\">({})</span></div>",
+                    escape(id),
+                    svg,
+                    self.anchor_number(id)
+                );
+            }
+            None => {
+                let _ = writeln!(self.out, "<div class=\"equation\">{}</div>", svg);
+            }
+        }
+    }
+
+    /// An equation as inline SVG sitting on the baseline, `None` if it fails
+    /// to render
+    fn math(&mut self, latex: &str, display: bool) -> Option<String> {
+        match render_latex_to_svg(latex, display, &self.config.math_font_size) {
+            Ok(result) => {
+                let svg = String::from_utf8_lossy(&result.svg_bytes);
+                let descent = result.descent_emu as f32 / EMU_PER_PT;
+                Some(format!(
+                    "<span class=\"math\" style=\"vertical-align: -{:.1}pt\">{}</span>",
+                    descent,
+                    inline_svg(&svg)
+                ))
+            }
+            Err(e) => {
+                diagnostics::warn(
+                    WarningCategory::Math,
+                    format!("Equation shown as source in the HTML: {}", e),
+                );
+                None
+            }
+        }
+    }
+
+    /// Raw HTML is passed through; `<!-- {toc:off} -->` and
+    /// `<!-- {toc:on} -->` keep headings out of the contents
+    fn html(&mut self, html: &str) {
+        let compact: String = html.chars().filter(|c| !c.is_whitespace()).collect();
+        match compact.as_str() {
+            "<!--{toc:off}-->" => self.toc_off = true,
+            "<!--{toc:on}-->" => self.toc_off = false,
+            _ => {}
+        }
+        if !self.passes_through(html) {
+            return;
+        }
+        self.out.push_str(html);
+        if !html.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            self.inline(inline);
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Text(text) => self.out.push_str(&escape(text)),
+            Inline::Bold(inner) => self.wrap("strong", inner),
+            Inline::Italic(inner) => self.wrap("em", inner),
+            Inline::BoldItalic(inner) => {
+                self.out.push_str("<strong>");
+                self.wrap("em", inner);
+                self.out.push_str("</strong>");
+            }
+            Inline::Strikethrough(inner) => self.wrap("del", inner),
+            Inline::Code(code) => {
+                let _ = write!(self.out, "<code>{}</code>", escape(code));
+            }
+            Inline::Kbd(key) => {
+                let _ = write!(self.out, "<kbd>{}</kbd>", escape(key));
+            }
+            Inline::Link { text, url, title } => {
+                let _ = write!(self.out, "<a href=\"{}\"", escape(url));
+                if let Some(title) = title {
+                    let _ = write!(self.out, " title=\"{}\"", escape(title));
+                }
+                self.out.push('>');
+                self.inlines(text);
+                self.out.push_str("</a>");
+            }
+            Inline::Image { alt, src, title } => match self.image_src(src) {
+                Some(url) => {
+                    let _ = write!(self.out, "<img src=\"{}\" alt=\"{}\"", url, escape(alt));
+                    if let Some(title) = title {
+                        let _ = write!(self.out, " title=\"{}\"", escape(title));
+                    }
+                    self.out.push_str(self.void());
+                }
+                None => self.out.push_str(&escape(alt)),
+            },
+            Inline::FootnoteRef(label) => {
+                let number = match self.footnotes.iter().position(|l| l == label) {
+                    Some(i) => i + 1,
+                    None => {
+                        self.footnotes.push(label.clone());
+                        self.footnotes.len()
+                    }
+                };
+                let _ = write!(
+                    self.out,
+                    "<sup class=\"footnote-ref\"><a href=\"{}\">{}</a></sup>",
+                    escape(&self.href(&format!("fn-{}", number))),
+                    number
+                );
+            }
+            Inline::CrossRef { target, .. } => {
+                let text = self.xref.get_localized_display_text(target, &self.strings);
+                if self.xref.has_anchor(target) {
+                    let _ = write!(
+                        self.out,
+                        "<a href=\"{}\">{}</a>",
+                        escape(&self.href(target)),
+                        escape(&text)
+                    );
+                } else {
+                    self.out.push_str(&escape(&text));
+                }
+            }
+            Inline::SoftBreak => self.out.push('\n'),
+            Inline::HardBreak => {
+                let _ = writeln!(self.out, "<br{}", self.void());
+            }
+            Inline::Html(html) => {
+                if self.passes_through(html) {
+                    self.out.push_str(html);
+                }
+            }
+            Inline::IndexMarker(_) => {}
+            Inline::InlineMath(latex) | Inline::DisplayMath(latex) => {
+                let display = matches!(inline, Inline::DisplayMath(_));
+                match self.math(latex, display) {
+                    Some(svg) => self.out.push_str(&svg),
+                    None => {
+                        let _ = write!(self.out, "<em>{}</em>", escape(latex));
+                    }
+                }
+            }
+        }
+    }
+
+    fn wrap(&mut self, tag: &str, inner: &[Inline]) {
+        let _ = write!(self.out, "<{}>", tag);
+        self.inlines(inner);
+        let _ = write!(self.out, "</{}>", tag);
+    }
+
+    /// Footnotes as a numbered list at the end, in order of first reference;
+    /// only those not listed yet when the output is split into chapters
+    fn notes(&mut self, notes: &HashMap<String, Vec<Block>>) {
+        if self.footnotes.len() == self.notes_written {
+            return;
+        }
+        let _ = write!(
+            self.out,
+            "<section class=\"footnotes\">\n<hr{}\n",
+            self.void()
+        );
+        match self.notes_written {
+            0 => self.out.push_str("<ol>\n"),
+            written => {
+                let _ = writeln!(self.out, "<ol start=\"{}\">", written + 1);
+            }
+        }
+        // Notes may reference further notes, which are appended as they go
+        let mut i = self.notes_written;
+        while i < self.footnotes.len() {
+            let label = self.footnotes[i].clone();
+            i += 1;
+            let id = format!("fn-{}", i);
+            self.anchor_files.insert(id.clone(), self.file.clone());
+            let _ = writeln!(self.out, "<li id=\"{}\">", id);
+            if let Some(blocks) = notes.get(&label) {
+                self.blocks(blocks);
+            }
+            self.out.push_str("</li>\n");
+        }
+        self.notes_written = i;
+        self.out.push_str("</ol>\n</section>\n");
+    }
+
+    /// The table of contents, linking to the headings it lists; `None` when
+    /// it is disabled or would be empty
+    fn contents(&self) -> Option<String> {
+        let toc = &self.config.toc;
+        let entries: Vec<&TocEntry> = self
+            .headings
+            .iter()
+            .filter(|entry| toc.includes_level(entry.level))
+            .collect();
+        if !toc.enabled || entries.is_empty() {
+            return None;
+        }
+        let mut html = String::from("<nav class=\"toc\">\n");
+        let _ = writeln!(html, "<h2>{}</h2>", escape(&toc.title));
+        html.push_str("<ul>\n");
+        for entry in entries {
+            let _ = writeln!(
+                html,
+                "<li class=\"toc-level-{}\"><a href=\"#{}\">{}</a></li>",
+                entry.level,
+                escape(&entry.id),
+                escape(&entry.text)
+            );
+        }
+        html.push_str("</ul>\n</nav>\n");
+        Some(html)
+    }
+
+    fn anchor_number(&self, id: &str) -> String {
+        self.xref
+            .resolve(id)
+            .and_then(|anchor| anchor.number.clone())
+            .unwrap_or_default()
+    }
+
+    /// End of a void element such as `<br`
+    fn void(&self) -> &'static str {
+        if self.xhtml {
+            "/>"
+        } else {
+            ">"
+        }
+    }
+
+    /// Link to an anchor, through the file it is in when that isn't the
+    /// one being written
+    fn href(&self, id: &str) -> String {
+        match self.anchor_files.get(id) {
+            Some(file) if !file.is_empty() && *file != self.file => format!("{}#{}", file, id),
+            _ => format!("#{}", id),
+        }
+    }
+
+    /// Whether raw HTML can be written as is; in XHTML it has to be
+    /// well-formed, and is otherwise left out with a warning
+    fn passes_through(&self, html: &str) -> bool {
+        if !self.xhtml || well_formed(html) {
+            return true;
+        }
+        diagnostics::warn(
+            WarningCategory::Other,
+            format!(
+                "Raw HTML left out of the EPUB as it isn't well-formed XHTML: {}",
+                html.trim()
+            ),
+        );
+        false
+    }
+
+    /// URL of an image: remote and data URLs as they are, local files
+    /// embedded as data URIs, `None` with a warning if the file is missing
+    ///
+    /// When collecting media, local files are stored once each and linked
+    /// by path, and remote images, which an EPUB can't show, are left out.
+    fn image_src(&mut self, src: &str) -> Option<String> {
+        if src.starts_with("data:") {
+            return Some(escape(src));
+        }
+        if src.starts_with("http://") || src.starts_with("https://") {
+            if self.media.is_some() {
+                diagnostics::warn(
+                    WarningCategory::MissingImage,
+                    format!("Remote image left out of the EPUB: {}", src),
+                );
+                return None;
+            }
+            return Some(escape(src));
+        }
+        let path = match &self.config.base_path {
+            Some(base) if Path::new(src).is_relative() => base.join(src),
+            _ => PathBuf::from(src),
+        };
+        let data = match &self.config.files {
+            Some(files) => files.get(&path).map(|data| data.to_vec()),
+            None => std::fs::read(&path).ok(),
+        };
+        let Some(data) = data else {
+            diagnostics::warn(
+                WarningCategory::MissingImage,
+                format!("Image not found: {}", path.display()),
+            );
+            return None;
+        };
+        if let Some(media) = &mut self.media {
+            if let Some(stored) = media.iter().find(|m| m.source == path) {
+                return Some(escape(&stored.path));
+            }
+            let media_type = image_mime(&path, &data);
+            let extension = match media_type {
+                "image/svg+xml" => "svg",
+                "image/jpeg" => "jpg",
+                other => other.trim_start_matches("image/"),
+            };
+            let stored = format!("images/image{}.{}", media.len() + 1, extension);
+            media.push(Media {
+                source: path,
+                path: stored.clone(),
+                media_type,
+                data,
+            });
+            return Some(escape(&stored));
+        }
+        Some(format!(
+            "data:{};base64,{}",
+            image_mime(&path, &data),
+            base64::engine::general_purpose::STANDARD.encode(&data)
+        ))
+    }
+}
+
+const EMU_PER_PT: f32 = 12_700.0;
+
+/// The id a block is the target of, if any
+fn anchor_id(block: &Block) -> Option<&str> {
+    match block {
+        Block::Heading { id, .. }
+        | Block::Image { id, .. }
+        | Block::Mermaid { id, .. }
+        | Block::Table { id, .. }
+        | Block::MathBlock { id, .. }
+        | Block::FigureGroup { id, .. } => id.as_deref(),
+        _ => None,
+    }
+}
+
+/// Whether raw HTML is well-formed XML with every element closed
+fn well_formed(html: &str) -> bool {
+    let mut reader = quick_xml::Reader::from_str(html);
+    let mut depth = 0usize;
+    loop {
+        match reader.read_event() {
+            Ok(quick_xml::events::Event::Start(_)) => depth += 1,
+            Ok(quick_xml::events::Event::End(_)) => match depth.checked_sub(1) {
+                Some(open) => depth = open,
+                None => return false,
+            },
+            Ok(quick_xml::events::Event::Eof) => return depth == 0,
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+    }
+}
+
+/// CSS approximating the DOCX styles for `lang` and `fonts`
+pub(crate) fn stylesheet(lang: Language, fonts: Option<&FontConfig>) -> String {
+    let normal = fonts
+        .and_then(|f| f.normal_size)
+        .unwrap_or(lang.default_font_size());
+    let heading_sizes = match lang {
+        Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => [
+            normal + 12,
+            normal + 4,
+            normal,
+            normal.saturating_sub(2).max(1),
+        ],
+        _ => [normal + 10, normal + 4, normal + 2, normal],
+    };
+    let caption_size = fonts.and_then(|f| f.caption_size).unwrap_or(match lang {
+        Language::Thai => 24,
+        Language::Lao | Language::Vietnamese | Language::Arabic | Language::Hebrew => 20,
+        _ => 18,
+    });
+    let code_size = fonts.and_then(|f| f.code_size).unwrap_or(20);
+    let color = |value: Option<&String>, default: &str| {
+        format!(
+            "#{}",
+            value.map_or(default, |hex| hex.trim_start_matches('#'))
+        )
+    };
+
+    let mut families = vec![fonts
+        .and_then(|f| f.default.clone())
+        .unwrap_or_else(|| lang.default_ascii_font().to_string())];
+    families.push(lang.default_cs_font().to_string());
+    families.extend(lang.default_east_asia_font().map(str::to_string));
+    families.dedup();
+    let body_font = families
+        .iter()
+        .map(|family| font_family(family))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let code_font = font_family(
+        &fonts
+            .and_then(|f| f.code.clone())
+            .unwrap_or_else(|| "Consolas".to_string()),
+    );
+    let pt = |half_points: u32| half_points as f32 / 2.0;
+
+    let mut css = String::new();
+    let _ = writeln!(
+        css,
+        "body {{ font-family: {}, sans-serif; font-size: {}pt; color: {}; line-height: 1.5; margin: 0; }}",
+        body_font,
+        pt(normal),
+        color(fonts.and_then(|f| f.normal_color.as_ref()), "000000")
+    );
+    css.push_str("main { max-width: 46em; margin: 0 auto; padding: 2em 1.5em; }\n");
+    let heading_color = color(fonts.and_then(|f| f.h1_color.as_ref()), "2F5496");
+    for (i, size) in heading_sizes.iter().enumerate() {
+        let _ = writeln!(
+            css,
+            "h{} {{ font-size: {}pt; color: {}; }}",
+            i + 1,
+            pt(*size),
+            heading_color
+        );
+    }
+    let _ = writeln!(
+        css,
+        "h5, h6 {{ font-size: {}pt; color: {}; }}",
+        pt(heading_sizes[3]),
+        heading_color
+    );
+    css.push_str("h4, h5, h6 { font-style: italic; }\n");
+    css.push_str("main > p { text-indent: 0.5in; text-align: justify; }\n");
+    let _ = writeln!(
+        css,
+        "code, pre {{ font-family: {}, monospace; font-size: {}pt; }}",
+        code_font,
+        pt(code_size)
+    );
+    css.push_str("p code, li code, td code { color: #C7254E; }\n");
+    css.push_str(
+        "dl.callouts { display: grid; grid-template-columns: auto 1fr; gap: 0.2em 0.5em; }\n",
+    );
+    css.push_str("dl.callouts dd { margin: 0; }\n");
+    css.push_str(
+        "kbd { font-size: 90%; padding: 0 0.3em; border: 1px solid #A0A0A0; border-radius: 3px; }\n",
+    );
+    css.push_str("pre { background: #F5F5F5; padding: 0.5em 1em; overflow-x: auto; }\n");
+    css.push_str("pre mark { display: inline-block; width: 100%; background: #FFF3B0; }\n");
+    css.push_str(
+        ".line-number { display: inline-block; width: 3em; color: #999999; user-select: none; }\n",
+    );
+    css.push_str("figure { margin: 1em 0; text-align: center; }\n");
+    css.push_str("figure.code { text-align: left; }\n");
+    css.push_str("figure.code figcaption { font-weight: bold; text-align: left; }\n");
+    css.push_str("img, svg { max-width: 100%; height: auto; }\n");
+    let _ = writeln!(
+        css,
+        "figcaption, caption {{ font-size: {}pt; color: {}; padding: 0.3em 0; }}",
+        pt(caption_size),
+        color(fonts.and_then(|f| f.caption_color.as_ref()), "000000")
+    );
+    css.push_str("table { border-collapse: collapse; margin: 1em auto; }\n");
+    css.push_str("th, td { border: 1px solid #000000; padding: 0.2em 0.5em; }\n");
+    css.push_str("th { background: #D9E2F3; font-weight: bold; }\n");
+    css.push_str("blockquote { margin: 1em 0; padding-left: 1em; border-left: 3px solid #CCCCCC; font-style: italic; }\n");
+    css.push_str("li.task { list-style: none; }\n");
+    css.push_str("li.task input { margin-left: -1.4em; margin-right: 0.4em; }\n");
+    css.push_str(".equation { display: flex; justify-content: center; align-items: center; position: relative; margin: 1em 0; }\n");
+    css.push_str(".equation-number { position: absolute; right: 0; }\n");
+    css.push_str(
+        ".parallel { display: grid; grid-template-columns: 1fr 1fr; column-gap: 1.5em; }\n",
+    );
+    css.push_str(
+        ".subfigures { display: flex; justify-content: center; align-items: flex-end; gap: 1em; }\n",
+    );
+    css.push_str(".subfigures figure { flex: 1; margin: 0; }\n");
+    css.push_str(".toc ul { list-style: none; padding-left: 0; }\n");
+    for level in 2..=6 {
+        let _ = writeln!(
+            css,
+            ".toc-level-{} {{ padding-left: {}em; }}",
+            level,
+            (level - 1) * 2
+        );
+    }
+    css.push_str(".footnotes { font-size: 0.85em; }\n");
+    css
+}
+
+/// A font name quoted for CSS
+fn font_family(name: &str) -> String {
+    format!("\"{}\"", name.replace(['"', '\\'], ""))
+}
+
+/// A width from image attributes (`50%`, `300px`, `5cm`) as CSS; bare
+/// numbers are pixels
+fn css_length(width: &str) -> Option<String> {
+    let width = width.trim();
+    let digits = width
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(width.len());
+    let (value, unit) = width.split_at(digits);
+    if value.parse::<f32>().is_err() {
+        return None;
+    }
+    match unit {
+        "" => Some(format!("{}px", value)),
+        "%" | "px" | "pt" | "cm" | "mm" | "in" | "em" => Some(width.to_string()),
+        _ => None,
+    }
+}
+
+/// MIME type of an image, from its contents or else its extension
+fn image_mime(path: &Path, data: &[u8]) -> &'static str {
+    if data.starts_with(b"\x89PNG") {
+        return "image/png";
+    }
+    if data.starts_with(&[0xFF, 0xD8]) {
+        return "image/jpeg";
+    }
+    if data.starts_with(b"GIF8") {
+        return "image/gif";
+    }
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        return "image/webp";
+    }
+    if data.starts_with(b"BM") {
+        return "image/bmp";
+    }
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "svg" => "image/svg+xml",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/png",
+    }
+}
+
+/// The `<svg>` element of an SVG document, without an XML declaration or
+/// doctype before it
+fn inline_svg(svg: &str) -> &str {
+    svg.find("<svg").map_or(svg, |start| &svg[start..])
+}
+
+/// Text escaped for HTML content and attribute values
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_markdown_with_frontmatter, FileMap};
+    use std::sync::Arc;
+
+    fn to_html(markdown: &str, config: &DocumentConfig) -> String {
+        let doc = parse_markdown_with_frontmatter(markdown);
+        render(&doc, Language::English, config).unwrap()
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let html = to_html(
+            "# Intro & Scope\n\nSome **bold** and `code`.\n\n\
+             - [x] done\n- [ ] todo\n\n| A | B |\n|:--|--:|\n| 1 | 2 |\n",
+            &DocumentConfig::default(),
+        );
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1 id=\"section-1\">Intro &amp; Scope</h1>"));
+        assert!(html.contains("<p>Some <strong>bold</strong> and <code>code</code>.</p>"));
+        assert!(html.contains("<input type=\"checkbox\" disabled checked>done"));
+        assert!(html.contains("<th style=\"text-align: left\">A</th>"));
+        assert!(html.contains("<td style=\"text-align: right\">2</td>"));
+    }
+
+    #[test]
+    fn test_contents_after_cover() {
+        let mut config = DocumentConfig::default();
+        config.toc.enabled = true;
+        let html = to_html(
+            "# Cover\n\n---\n\n# One\n\n## Two\n\n<!-- {toc:off} -->\n\n# Hidden\n",
+            &config,
+        );
+        let toc = html.find("<nav class=\"toc\">").unwrap();
+        assert!(toc > html.find("<hr>").unwrap());
+        assert!(html.contains("<li class=\"toc-level-1\"><a href=\"#section-2\">One</a></li>"));
+        assert!(html.contains("<li class=\"toc-level-2\"><a href=\"#section-3\">Two</a></li>"));
+        assert!(!html[toc..].contains(">Cover</a>"));
+        assert!(!html.contains(">Hidden</a>"));
+    }
+
+    #[test]
+    fn test_images_embedded() {
+        let mut files = FileMap::new();
+        files.insert("docs/logo.png", b"\x89PNG\r\n\x1a\n".to_vec());
+        let config = DocumentConfig {
+            base_path: Some(PathBuf::from("docs")),
+            files: Some(Arc::new(files)),
+            ..Default::default()
+        };
+        let html = to_html(
+            "![Logo](logo.png)\n\n![Remote](https://example.com/a.png)\n",
+            &config,
+        );
+        assert!(html.contains("<img src=\"data:image/png;base64,iVBORw0KGgo=\" alt=\"Logo\">"));
+        assert!(html.contains("<figcaption>Figure 1: Logo</figcaption>"));
+        assert!(html.contains("src=\"https://example.com/a.png\""));
+    }
+
+    #[test]
+    fn test_css_length() {
+        assert_eq!(css_length("50%").as_deref(), Some("50%"));
+        assert_eq!(css_length("300").as_deref(), Some("300px"));
+        assert_eq!(css_length("5cm").as_deref(), Some("5cm"));
+        assert_eq!(css_length("wide"), None);
+        assert_eq!(css_length("1px;color:red"), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Localized date rendering
+//!
+//! Formats ISO dates (`YYYY-MM-DD`) for covers and headers using a
+//! per-language format string. Thai dates use Thai month names and the
+//! Buddhist Era (พ.ศ.), which is the Gregorian year plus 543.
+//!
+//! Format strings use strftime-style specifiers:
+//!
+//! | Specifier | Meaning |
+//! |-----------|---------|
+//! | `%d` | Day, zero-padded (`05`) |
+//! | `%-d`, `%e` | Day without padding (`5`) |
+//! | `%m` | Month, zero-padded (`01`) |
+//! | `%-m` | Month without padding (`1`) |
+//! | `%B` | Full month name (`January`, `มกราคม`) |
+//! | `%b` | Abbreviated month name (`Jan`, `ม.ค.`) |
+//! | `%Y` | Year in the language's era (Buddhist Era for Thai) |
+//! | `%G` | Gregorian year |
+//! | `%%` | Literal `%` |
+
+use crate::Language;
+
+/// Offset between Buddhist Era and Gregorian years
+pub const BUDDHIST_ERA_OFFSET: i32 = 543;
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const THAI_MONTHS: [&str; 12] = [
+    "มกราคม",
+    "กุมภาพันธ์",
+    "มีนาคม",
+    "เมษายน",
+    "พฤษภาคม",
+    "มิถุนายน",
+    "กรกฎาคม",
+    "สิงหาคม",
+    "กันยายน",
+    "ตุลาคม",
+    "พฤศจิกายน",
+    "ธันวาคม",
+];
+
+const THAI_MONTHS_SHORT: [&str; 12] = [
+    "ม.ค.",
+    "ก.พ.",
+    "มี.ค.",
+    "เม.ย.",
+    "พ.ค.",
+    "มิ.ย.",
+    "ก.ค.",
+    "ส.ค.",
+    "ก.ย.",
+    "ต.ค.",
+    "พ.ย.",
+    "ธ.ค.",
+];
+
+/// Default date format string for a language
+///
+/// English keeps ISO dates so existing documents render unchanged.
+pub fn date_format(lang: Language) -> &'static str {
+    match lang {
+        Language::Thai => "%-d %B พ.ศ. %Y",
+        Language::Chinese | Language::Japanese => "%Y年%-m月%-d日",
+        Language::Korean => "%Y년 %-m월 %-d일",
+        Language::Vietnamese => "%d/%m/%Y",
+        _ => "%Y-%m-%d",
+    }
+}
+
+/// Year in the calendar era used by a language
+pub fn era_year(year: i32, lang: Language) -> i32 {
+    match lang {
+        Language::Thai => year + BUDDHIST_ERA_OFFSET,
+        _ => year,
+    }
+}
+
+/// Localized full month name (1-based month)
+pub fn month_name(month: u32, lang: Language) -> &'static str {
+    let index = (month.clamp(1, 12) - 1) as usize;
+    match lang {
+        Language::Thai => THAI_MONTHS[index],
+        _ => ENGLISH_MONTHS[index],
+    }
+}
+
+/// Localized abbreviated month name (1-based month)
+pub fn month_abbrev(month: u32, lang: Language) -> &'static str {
+    let index = (month.clamp(1, 12) - 1) as usize;
+    match lang {
+        Language::Thai => THAI_MONTHS_SHORT[index],
+        _ => &ENGLISH_MONTHS[index][..3],
+    }
+}
+
+/// Parse an ISO `YYYY-MM-DD` date into (year, month, day)
+pub fn parse_iso_date(date: &str) -> Option<(i32, u32, u32)> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year = parts.next()?.parse::<i32>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+/// Number of days in a month of the Gregorian calendar (1-based month)
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Render an ISO date in a language's format
+///
+/// `format` overrides the language default from [`date_format`]. Dates that
+/// are not `YYYY-MM-DD` (e.g. "Spring 2025") are returned unchanged.
+pub fn format_date(date: &str, lang: Language, format: Option<&str>) -> String {
+    let Some((year, month, day)) = parse_iso_date(date) else {
+        return date.to_string();
+    };
+    let format = format.unwrap_or_else(|| date_format(lang));
+
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let no_pad = chars.peek() == Some(&'-');
+        if no_pad {
+            chars.next();
+        }
+        match chars.next() {
+            Some('d') if no_pad => out.push_str(&day.to_string()),
+            Some('d') => out.push_str(&format!("{:02}", day)),
+            Some('e') => out.push_str(&day.to_string()),
+            Some('m') if no_pad => out.push_str(&month.to_string()),
+            Some('m') => out.push_str(&format!("{:02}", month)),
+            Some('B') => out.push_str(month_name(month, lang)),
+            Some('b') => out.push_str(month_abbrev(month, lang)),
+            Some('Y') => out.push_str(&era_year(year, lang).to_string()),
+            Some('G') => out.push_str(&year.to_string()),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                if no_pad {
+                    out.push('-');
+                }
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date_thai_buddhist_era() {
+        assert_eq!(
+            format_date("2025-01-28", Language::Thai, None),
+            "28 มกราคม พ.ศ. 2568"
+        );
+        assert_eq!(
+            format_date("2025-03-05", Language::Thai, Some("%e %b %Y")),
+            "5 มี.ค. 2568"
+        );
+        assert_eq!(
+            format_date("2025-03-05", Language::Thai, Some("%d/%m/%G")),
+            "05/03/2025"
+        );
+    }
+
+    #[test]
+    fn test_format_date_other_languages() {
+        assert_eq!(
+            format_date("2025-01-28", Language::English, None),
+            "2025-01-28"
+        );
+        assert_eq!(
+            format_date("2025-01-28", Language::English, Some("%B %-d, %Y")),
+            "January 28, 2025"
+        );
+        assert_eq!(
+            format_date("2025-01-08", Language::Japanese, None),
+            "2025年1月8日"
+        );
+        assert_eq!(
+            format_date("2025-01-08", Language::Korean, None),
+            "2025년 1월 8일"
+        );
+    }
+
+    #[test]
+    fn test_format_date_passthrough() {
+        assert_eq!(
+            format_date("Spring 2025", Language::Thai, None),
+            "Spring 2025"
+        );
+        assert_eq!(format_date("", Language::Thai, None), "");
+        assert_eq!(
+            format_date("2025-13-01", Language::Thai, None),
+            "2025-13-01"
+        );
+    }
+
+    #[test]
+    fn test_parse_iso_date_month_lengths() {
+        assert_eq!(parse_iso_date("2026-01-31"), Some((2026, 1, 31)));
+        assert_eq!(parse_iso_date("2026-02-31"), None);
+        assert_eq!(parse_iso_date("2026-02-29"), None);
+        assert_eq!(parse_iso_date("2024-02-29"), Some((2024, 2, 29)));
+        assert_eq!(parse_iso_date("2000-02-29"), Some((2000, 2, 29)));
+        assert_eq!(parse_iso_date("1900-02-29"), None);
+        assert_eq!(parse_iso_date("2026-04-31"), None);
+        assert_eq!(parse_iso_date("2026-04-30"), Some((2026, 4, 30)));
+        assert_eq!(
+            format_date("2026-02-31", Language::Thai, None),
+            "2026-02-31"
+        );
+    }
+}
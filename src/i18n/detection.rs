@@ -1,7 +1,9 @@
 //! Script detection for Thai/English text
 //!
 //! This module provides functions to detect the script of text,
-//! primarily for distinguishing Thai from Latin/English text.
+//! primarily for distinguishing Thai from Latin/English text. Lao, Khmer,
+//! Myanmar, Vietnamese, Chinese, Japanese, Korean, Arabic and Hebrew are
+//! recognized for spell-check language tags.
 
 /// Thai Unicode range: U+0E00 to U+0E7F
 const THAI_START: char = '\u{0E00}';
@@ -39,16 +41,134 @@ pub fn is_predominantly_thai(text: &str) -> bool {
     thai_count * 2 > letter_count // More than 50% Thai
 }
 
+/// Language tag of a complex-script or CJK character
+///
+/// Han characters map to Chinese; kana to Japanese and Hangul to Korean.
+fn script_language(c: char) -> Option<&'static str> {
+    match c {
+        THAI_START..=THAI_END => Some("th-TH"),
+        '\u{0E80}'..='\u{0EFF}' => Some("lo-LA"),
+        '\u{1780}'..='\u{17FF}' | '\u{19E0}'..='\u{19FF}' => Some("km-KH"),
+        '\u{1000}'..='\u{109F}' => Some("my-MM"),
+        '\u{0600}'..='\u{06FF}'
+        | '\u{0750}'..='\u{077F}'
+        | '\u{08A0}'..='\u{08FF}'
+        | '\u{FB50}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}' => Some("ar-SA"),
+        '\u{0590}'..='\u{05FF}' | '\u{FB1D}'..='\u{FB4F}' => Some("he-IL"),
+        '\u{3040}'..='\u{30FF}' => Some("ja-JP"),
+        '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+            Some("ko-KR")
+        }
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' => Some("zh-CN"),
+        _ => None,
+    }
+}
+
+/// Every tag [`script_language`] returns, for counting letters per language
+const SCRIPT_LANGUAGES: [&str; 9] = [
+    "th-TH", "lo-LA", "km-KH", "my-MM", "ar-SA", "he-IL", "ja-JP", "ko-KR", "zh-CN",
+];
+
+/// Check if a character only occurs in Vietnamese Latin text (ă, đ, ơ, ư, ạ, ế...)
+fn is_vietnamese_char(c: char) -> bool {
+    matches!(
+        c,
+        'Ă' | 'ă' | 'Đ' | 'đ' | 'Ơ' | 'ơ' | 'Ư' | 'ư' | '\u{1EA0}'..='\u{1EF9}'
+    )
+}
+
 /// Determine the primary language of a text for spell-check purposes
-/// Returns "th-TH" for Thai, "en-US" for English/Latin
+/// Returns "th-TH" for Thai, "lo-LA", "km-KH" or "my-MM" for Lao, Khmer or
+/// Myanmar, "ar-SA" or "he-IL" for Arabic or Hebrew, "zh-CN", "ja-JP" or "ko-KR" for CJK, "vi-VN" for Vietnamese and
+/// "en-US" for other Latin text
 pub fn detect_language(text: &str) -> &'static str {
-    if is_predominantly_thai(text) {
-        "th-TH"
+    let mut letter_count = 0;
+    // Called for every run, so letters are counted without allocating
+    let mut counts = [0usize; SCRIPT_LANGUAGES.len()];
+    let index = |tag: &str| SCRIPT_LANGUAGES.iter().position(|l| *l == tag);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letter_count += 1;
+        if let Some(i) = script_language(c).and_then(index) {
+            counts[i] += 1;
+        }
+    }
+
+    // More than 50% of the letters in one complex script
+    if let Some(i) = counts.iter().position(|n| n * 2 > letter_count) {
+        return SCRIPT_LANGUAGES[i];
+    }
+
+    // Japanese and Korean mix in Han characters, so any kana or Hangul decides
+    let count = |tag: &str| index(tag).map_or(0, |i| counts[i]);
+    if (count("zh-CN") + count("ja-JP") + count("ko-KR")) * 2 > letter_count {
+        return if count("ja-JP") > 0 {
+            "ja-JP"
+        } else if count("ko-KR") > 0 {
+            "ko-KR"
+        } else {
+            "zh-CN"
+        };
+    }
+    if text.chars().any(is_vietnamese_char) {
+        "vi-VN"
     } else {
         "en-US"
     }
 }
 
+/// Script a letter belongs to for splitting; Han, kana and Hangul are one
+fn script_family(c: char) -> &'static str {
+    match script_language(c) {
+        Some("zh-CN" | "ja-JP" | "ko-KR") => "cjk",
+        Some(lang) => lang,
+        None => "latin",
+    }
+}
+
+/// Whether all letters of `text` share a script, so [`split_by_script`]
+/// would return it whole
+pub fn is_single_script(text: &str) -> bool {
+    let mut scripts = text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .map(script_family);
+    match scripts.next() {
+        Some(first) => scripts.all(|script| script == first),
+        None => true,
+    }
+}
+
+/// Split text where letters change script
+///
+/// Spaces, digits and punctuation stay with the preceding letters (or the
+/// following ones at the start), so "Hello สวัสดี!" splits into "Hello " and
+/// "สวัสดี!". Han, kana and Hangul count as one script so Japanese and Korean
+/// text stays whole. Each segment comes with its detected language tag.
+pub fn split_by_script(text: &str) -> Vec<(&str, &'static str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut current: Option<&str> = None;
+    for (i, c) in text.char_indices().filter(|(_, c)| c.is_alphabetic()) {
+        let next = script_family(c);
+        match current {
+            Some(prev) if prev != next => {
+                segments.push(&text[start..i]);
+                start = i;
+            }
+            _ => {}
+        }
+        current = Some(next);
+    }
+    segments.push(&text[start..]);
+
+    segments
+        .into_iter()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| (segment, detect_language(segment)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,5 +215,45 @@ mod tests {
         assert_eq!(detect_language("Hello World"), "en-US");
         assert_eq!(detect_language("สวัสดี Hello"), "th-TH"); // Thai-heavy
         assert_eq!(detect_language("Hello สวัสดี World"), "en-US"); // English-heavy
+        assert_eq!(detect_language("ສະບາຍດີ"), "lo-LA");
+        assert_eq!(detect_language("សួស្តី"), "km-KH");
+        assert_eq!(detect_language("မင်္ဂလာပါ"), "my-MM");
+        assert_eq!(detect_language("Xin chào thế giới"), "vi-VN");
+        assert_eq!(detect_language("你好世界"), "zh-CN");
+        assert_eq!(detect_language("日本語のテキスト"), "ja-JP");
+        assert_eq!(detect_language("안녕하세요"), "ko-KR");
+        assert_eq!(detect_language("مرحبا بالعالم"), "ar-SA");
+        assert_eq!(detect_language("שלום עולם"), "he-IL");
+    }
+
+    #[test]
+    fn test_split_by_script() {
+        assert_eq!(
+            split_by_script("Hello สวัสดี, world!"),
+            vec![
+                ("Hello ", "en-US"),
+                ("สวัสดี, ", "th-TH"),
+                ("world!", "en-US")
+            ]
+        );
+        assert_eq!(
+            split_by_script("(1) ภาษาไทย"),
+            vec![("(1) ภาษาไทย", "th-TH")]
+        );
+        assert_eq!(
+            split_by_script("日本語のテキスト"),
+            vec![("日本語のテキスト", "ja-JP")]
+        );
+        assert_eq!(split_by_script("123"), vec![("123", "en-US")]);
+        assert!(split_by_script("").is_empty());
+    }
+
+    #[test]
+    fn test_is_single_script() {
+        assert!(is_single_script("Hello, world!"));
+        assert!(is_single_script("(1) ภาษาไทย"));
+        assert!(is_single_script("日本語のテキスト"));
+        assert!(is_single_script("123"));
+        assert!(!is_single_script("Hello สวัสดี"));
     }
 }
@@ -1,6 +1,18 @@
+pub mod date;
 pub mod detection;
 mod fonts;
+pub mod punctuation;
+#[cfg(feature = "thai-segmentation")]
+pub mod segment;
+pub mod strings;
+mod words;
 
+pub use date::format_date;
 pub use detection::*;
 #[allow(unused_imports)]
 pub use fonts::*;
+pub use punctuation::{Punctuation, PunctuationSpacing, SmartPunctuation};
+#[cfg(feature = "thai-segmentation")]
+pub use segment::insert_thai_breaks;
+pub use strings::{default_label, Label, Strings};
+pub use words::count_words;
@@ -0,0 +1,248 @@
+//! Localized smart punctuation
+//!
+//! Turns straight quotes, `--`, `---` and `...` into typographic
+//! punctuation. Quote characters and spacing follow the document language
+//! (English and Thai “ ”, Japanese 「 」, Arabic « ») and can be overridden
+//! under `[i18n]`, e.g. French guillemets with narrow no-break spaces:
+//!
+//! ```toml
+//! [i18n]
+//! smart_punctuation = true
+//! quotes = "«»‹›"
+//! punctuation_spacing = "narrow"
+//! ```
+
+use crate::Language;
+
+/// Space inserted inside quotes and before `; : ! ?` with narrow spacing
+pub const NARROW_NBSP: char = '\u{202F}';
+
+/// Spacing around quotes and high punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PunctuationSpacing {
+    /// Punctuation hugs the text (English, Thai)
+    #[default]
+    None,
+    /// Narrow no-break space inside quotes and before `; : ! ?` (French)
+    Narrow,
+}
+
+impl PunctuationSpacing {
+    /// Parse a `punctuation_spacing` value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "none" => Some(PunctuationSpacing::None),
+            "narrow" | "french" => Some(PunctuationSpacing::Narrow),
+            _ => None,
+        }
+    }
+}
+
+/// Punctuation rules for a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Punctuation {
+    /// Primary open/close, then inner open/close quotes
+    pub quotes: [char; 4],
+    pub spacing: PunctuationSpacing,
+}
+
+impl Punctuation {
+    /// Default rules for a language
+    pub fn for_language(lang: Language) -> Self {
+        let quotes = match lang {
+            Language::Japanese => ['「', '」', '『', '』'],
+            Language::Khmer | Language::Arabic => ['«', '»', '‹', '›'],
+            _ => ['“', '”', '‘', '’'],
+        };
+        Self {
+            quotes,
+            spacing: PunctuationSpacing::None,
+        }
+    }
+
+    /// Replace the quote characters
+    ///
+    /// Takes two characters (primary pair) or four (primary and inner
+    /// pairs). Returns `None` for any other length.
+    pub fn with_quotes(mut self, quotes: &str) -> Option<Self> {
+        let chars: Vec<char> = quotes.chars().filter(|c| !c.is_whitespace()).collect();
+        match chars.len() {
+            2 => self.quotes[..2].copy_from_slice(&chars),
+            4 => self.quotes.copy_from_slice(&chars),
+            _ => return None,
+        }
+        Some(self)
+    }
+
+    pub fn with_spacing(mut self, spacing: PunctuationSpacing) -> Self {
+        self.spacing = spacing;
+        self
+    }
+}
+
+/// Converts text one piece at a time, remembering the preceding character
+/// so quotes split across formatting runs still pair up
+#[derive(Debug)]
+pub struct SmartPunctuation<'a> {
+    rules: &'a Punctuation,
+    prev: Option<char>,
+}
+
+impl<'a> SmartPunctuation<'a> {
+    pub fn new(rules: &'a Punctuation) -> Self {
+        Self { rules, prev: None }
+    }
+
+    /// Note text that is output verbatim (code, math)
+    pub fn skip(&mut self, text: &str) {
+        if let Some(c) = text.chars().last() {
+            self.prev = Some(c);
+        }
+    }
+
+    /// Convert a piece of text
+    pub fn convert(&mut self, text: &str) -> String {
+        let narrow = self.rules.spacing == PunctuationSpacing::Narrow;
+        let [open, close, inner_open, inner_close] = self.rules.quotes;
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            let prev = out.chars().last().or(self.prev);
+            let opening = prev.is_none_or(|p| opens_quote(p, &self.rules.quotes));
+
+            match c {
+                '"' if opening => {
+                    out.push(open);
+                    if narrow {
+                        out.push(NARROW_NBSP);
+                        if next == Some(' ') {
+                            i += 1;
+                        }
+                    }
+                }
+                '"' => {
+                    if narrow {
+                        if out.ends_with(' ') {
+                            out.pop();
+                        }
+                        out.push(NARROW_NBSP);
+                    }
+                    out.push(close);
+                }
+                '\'' if prev.is_some_and(char::is_alphanumeric)
+                    && next.is_some_and(char::is_alphanumeric) =>
+                {
+                    // Apostrophe, whatever the quote style
+                    out.push('’');
+                }
+                '\'' if opening => out.push(inner_open),
+                '\'' => out.push(inner_close),
+                '-' if next == Some('-') => {
+                    if chars.get(i + 2) == Some(&'-') {
+                        out.push('—');
+                        i += 1;
+                    } else {
+                        out.push('–');
+                    }
+                    i += 1;
+                }
+                '.' if next == Some('.') && chars.get(i + 2) == Some(&'.') => {
+                    out.push('…');
+                    i += 2;
+                }
+                ';' | ':' | '!' | '?'
+                    if narrow && prev.is_some() && next.is_none_or(char::is_whitespace) =>
+                {
+                    if out.ends_with(' ') {
+                        out.pop();
+                    }
+                    if prev != Some(NARROW_NBSP) {
+                        out.push(NARROW_NBSP);
+                    }
+                    out.push(c);
+                }
+                _ => out.push(c),
+            }
+            i += 1;
+        }
+
+        self.skip(&out);
+        out
+    }
+}
+
+/// Whether a quote after `prev` opens rather than closes
+fn opens_quote(prev: char, quotes: &[char; 4]) -> bool {
+    prev.is_whitespace()
+        || matches!(prev, '(' | '[' | '{' | '—' | '–')
+        || prev == quotes[0]
+        || prev == quotes[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smarten(text: &str, rules: &Punctuation) -> String {
+        SmartPunctuation::new(rules).convert(text)
+    }
+
+    #[test]
+    fn test_english_quotes_and_dashes() {
+        let rules = Punctuation::for_language(Language::English);
+        assert_eq!(
+            smarten("\"It's 'fine'\" -- really --- ok...", &rules),
+            "“It’s ‘fine’” – really — ok…"
+        );
+    }
+
+    #[test]
+    fn test_language_quotes() {
+        let rules = Punctuation::for_language(Language::Japanese);
+        assert_eq!(smarten("\"ことば\"", &rules), "「ことば」");
+
+        let rules = Punctuation::for_language(Language::Thai);
+        assert_eq!(smarten("คำว่า \"ไทย\" ครับ", &rules), "คำว่า “ไทย” ครับ");
+    }
+
+    #[test]
+    fn test_french_spacing() {
+        let rules = Punctuation::for_language(Language::English)
+            .with_quotes("«»‹›")
+            .unwrap()
+            .with_spacing(PunctuationSpacing::Narrow);
+        assert_eq!(
+            smarten("Il a dit \"bonjour\" ! Vraiment ?", &rules),
+            "Il a dit «\u{202F}bonjour\u{202F}»\u{202F}! Vraiment\u{202F}?"
+        );
+        // Times and URLs keep their colons tight
+        assert_eq!(smarten("à 10:30", &rules), "à 10:30");
+    }
+
+    #[test]
+    fn test_quotes_across_pieces() {
+        let rules = Punctuation::for_language(Language::English);
+        let mut smart = SmartPunctuation::new(&rules);
+        assert_eq!(smart.convert("say \""), "say “");
+        assert_eq!(smart.convert("bold"), "bold");
+        assert_eq!(smart.convert("\" now"), "” now");
+    }
+
+    #[test]
+    fn test_with_quotes() {
+        let rules = Punctuation::for_language(Language::English);
+        assert_eq!(
+            rules.with_quotes("„“").unwrap().quotes,
+            ['„', '“', '‘', '’']
+        );
+        assert!(rules.with_quotes("\"").is_none());
+        assert_eq!(
+            PunctuationSpacing::from_name("French"),
+            Some(PunctuationSpacing::Narrow)
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Thai word segmentation
+//!
+//! Thai is written without spaces between words. Thai runs are segmented
+//! with the ICU dictionary segmenter, which both the word count and the
+//! justified-text break hints use.
+//!
+//! Word can only break a justified Thai line at the few spaces there are
+//! and stretches them into huge gaps, so a zero-width space (U+200B) is
+//! inserted between words, giving Word a break opportunity at every word
+//! boundary without changing how the text looks.
+
+use icu_segmenter::WordSegmenter;
+
+use super::detection::is_thai_char;
+
+/// Invisible break opportunity inserted between Thai words
+pub const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+thread_local! {
+    // The segmenter's data is not `Sync`, so each thread builds its own
+    static SEGMENTER: WordSegmenter = WordSegmenter::new_auto();
+}
+
+/// Insert zero-width spaces between the words of every Thai run in `text`
+///
+/// Non-Thai text is left untouched.
+pub fn insert_thai_breaks(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 8);
+    let mut thai = String::new();
+    for c in text.chars() {
+        if is_thai_char(c) {
+            thai.push(c);
+        } else {
+            if !thai.is_empty() {
+                push_segmented(&mut out, &thai);
+                thai.clear();
+            }
+            out.push(c);
+        }
+    }
+    if !thai.is_empty() {
+        push_segmented(&mut out, &thai);
+    }
+    out
+}
+
+/// Split a run of Thai text into its dictionary segments
+///
+/// Each segment is paired with whether it is word-like, as opposed to
+/// punctuation or whitespace.
+pub(crate) fn segment_thai(run: &str) -> Vec<(&str, bool)> {
+    SEGMENTER.with(|segmenter| {
+        let mut segments = Vec::new();
+        let mut breaks = segmenter.segment_str(run);
+        let mut last = 0;
+        while let Some(brk) = breaks.next() {
+            if brk > last {
+                segments.push((&run[last..brk], breaks.is_word_like()));
+                last = brk;
+            }
+        }
+        segments
+    })
+}
+
+/// Append a Thai run with zero-width spaces at its interior word boundaries
+fn push_segmented(out: &mut String, run: &str) {
+    for (i, (segment, _)) in segment_thai(run).into_iter().enumerate() {
+        if i > 0 {
+            out.push(ZERO_WIDTH_SPACE);
+        }
+        out.push_str(segment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_thai_breaks() {
+        let text = insert_thai_breaks("ภาษาไทยง่ายนิดเดียว (Thai)");
+        assert!(text.contains(ZERO_WIDTH_SPACE));
+        assert_eq!(
+            text.replace(ZERO_WIDTH_SPACE, ""),
+            "ภาษาไทยง่ายนิดเดียว (Thai)"
+        );
+        assert!(!text.starts_with(ZERO_WIDTH_SPACE));
+        assert!(text.ends_with("เดียว (Thai)"));
+        assert_eq!(insert_thai_breaks("Hello world"), "Hello world");
+    }
+}
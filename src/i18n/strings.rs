@@ -0,0 +1,482 @@
+//! Generated document strings
+//!
+//! Per-language tables for the text md2docx writes into documents on its
+//! own: the TOC title, caption prefixes and cross-reference labels.
+//! `[i18n.strings]` in md2docx.toml overrides individual entries.
+
+use std::collections::HashMap;
+
+use crate::Language;
+
+/// A generated string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Label {
+    TableOfContents,
+    Figure,
+    Table,
+    /// Chapter reference, with `{n}` standing for the chapter number
+    Chapter,
+    Appendix,
+    Index,
+    References,
+    Continued,
+    /// Title of the revision history appendix
+    RevisionHistory,
+    /// Revision history column headings
+    RevisionDate,
+    RevisionVersion,
+    RevisionAuthor,
+    RevisionDescription,
+    /// Heading of the chapter column in the last-edited table
+    RevisionChapter,
+    /// Part divider label, with `{n}` standing for the part number
+    Part,
+    /// Heading of the summary column in the `{!chapterlist}` table
+    Summary,
+    /// Column headings of the `{!signatures}` table
+    PreparedBy,
+    ReviewedBy,
+    ApprovedBy,
+    /// Row labels of the `{!signatures}` table
+    Signature,
+    SignerName,
+    SignerPosition,
+    /// `{page_of_total}` in headers and footers, with `{page}` and `{pages}`
+    /// standing for the page number and page count
+    PageOfTotal,
+}
+
+impl Label {
+    /// All labels, in table order
+    pub const ALL: [Label; 23] = [
+        Label::TableOfContents,
+        Label::Figure,
+        Label::Table,
+        Label::Chapter,
+        Label::Appendix,
+        Label::Index,
+        Label::References,
+        Label::Continued,
+        Label::RevisionHistory,
+        Label::RevisionDate,
+        Label::RevisionVersion,
+        Label::RevisionAuthor,
+        Label::RevisionDescription,
+        Label::RevisionChapter,
+        Label::Part,
+        Label::Summary,
+        Label::PreparedBy,
+        Label::ReviewedBy,
+        Label::ApprovedBy,
+        Label::Signature,
+        Label::SignerName,
+        Label::SignerPosition,
+        Label::PageOfTotal,
+    ];
+
+    /// Key used in `[i18n.strings]`
+    pub fn key(&self) -> &'static str {
+        match self {
+            Label::TableOfContents => "toc",
+            Label::Figure => "figure",
+            Label::Table => "table",
+            Label::Chapter => "chapter",
+            Label::Appendix => "appendix",
+            Label::Index => "index",
+            Label::References => "references",
+            Label::Continued => "continued",
+            Label::RevisionHistory => "revision_history",
+            Label::RevisionDate => "revision_date",
+            Label::RevisionVersion => "revision_version",
+            Label::RevisionAuthor => "revision_author",
+            Label::RevisionDescription => "revision_description",
+            Label::RevisionChapter => "revision_chapter",
+            Label::Part => "part",
+            Label::Summary => "summary",
+            Label::PreparedBy => "prepared_by",
+            Label::ReviewedBy => "reviewed_by",
+            Label::ApprovedBy => "approved_by",
+            Label::Signature => "signature",
+            Label::SignerName => "signer_name",
+            Label::SignerPosition => "signer_position",
+            Label::PageOfTotal => "page_of_total",
+        }
+    }
+
+    /// Parse an `[i18n.strings]` key
+    pub fn from_key(key: &str) -> Option<Self> {
+        Label::ALL
+            .into_iter()
+            .find(|label| label.key().eq_ignore_ascii_case(key.trim()))
+    }
+}
+
+/// Built-in strings for a language, indexed like [`Label::ALL`]
+fn table(lang: Language) -> [&'static str; 23] {
+    match lang {
+        Language::English => [
+            "Table of Contents",
+            "Figure",
+            "Table",
+            "Chapter {n}",
+            "Appendix",
+            "Index",
+            "References",
+            "Continued",
+            "Revision History",
+            "Date",
+            "Version",
+            "Author",
+            "Description",
+            "Chapter",
+            "Part {n}",
+            "Summary",
+            "Prepared by",
+            "Reviewed by",
+            "Approved by",
+            "Signature",
+            "Name",
+            "Position",
+            "Page {page} of {pages}",
+        ],
+        Language::Thai => [
+            "สารบัญ",
+            "รูปที่",
+            "ตารางที่",
+            "บทที่ {n}",
+            "ภาคผนวก",
+            "ดัชนี",
+            "เอกสารอ้างอิง",
+            "ต่อ",
+            "ประวัติการแก้ไข",
+            "วันที่",
+            "เวอร์ชัน",
+            "ผู้แก้ไข",
+            "รายละเอียด",
+            "บท",
+            "ภาคที่ {n}",
+            "สรุป",
+            "ผู้จัดทำ",
+            "ผู้ตรวจสอบ",
+            "ผู้อนุมัติ",
+            "ลงชื่อ",
+            "ชื่อ",
+            "ตำแหน่ง",
+            "หน้า {page} จาก {pages}",
+        ],
+        Language::Lao => [
+            "ສາລະບານ",
+            "ຮູບທີ",
+            "ຕາຕະລາງທີ",
+            "ບົດທີ {n}",
+            "ພາກຜະໜວກ",
+            "ດັດຊະນີ",
+            "ເອກະສານອ້າງອີງ",
+            "ຕໍ່",
+            "ປະຫວັດການແກ້ໄຂ",
+            "ວັນທີ",
+            "ສະບັບ",
+            "ຜູ້ແກ້ໄຂ",
+            "ລາຍລະອຽດ",
+            "ບົດ",
+            "ພາກທີ {n}",
+            "ສະຫຼຸບ",
+            "ຜູ້ຈັດທຳ",
+            "ຜູ້ກວດສອບ",
+            "ຜູ້ອະນຸມັດ",
+            "ລົງຊື່",
+            "ຊື່",
+            "ຕຳແໜ່ງ",
+            "ໜ້າ {page} ຈາກ {pages}",
+        ],
+        Language::Khmer => [
+            "មាតិកា",
+            "រូបភាពទី",
+            "តារាងទី",
+            "ជំពូកទី {n}",
+            "ឧបសម្ព័ន្ធ",
+            "សន្ទស្សន៍",
+            "ឯកសារយោង",
+            "បន្ត",
+            "ប្រវត្តិកែសម្រួល",
+            "កាលបរិច្ឆេទ",
+            "កំណែ",
+            "អ្នកនិពន្ធ",
+            "ការពិពណ៌នា",
+            "ជំពូក",
+            "ផ្នែកទី {n}",
+            "សេចក្តីសង្ខេប",
+            "រៀបចំដោយ",
+            "ពិនិត្យដោយ",
+            "អនុម័តដោយ",
+            "ហត្ថលេខា",
+            "ឈ្មោះ",
+            "មុខតំណែង",
+            "ទំព័រ {page} នៃ {pages}",
+        ],
+        Language::Myanmar => [
+            "မာတိကာ",
+            "ပုံ",
+            "ဇယား",
+            "အခန်း {n}",
+            "နောက်ဆက်တွဲ",
+            "အညွှန်း",
+            "ကိုးကားချက်များ",
+            "ဆက်လက်",
+            "ပြင်ဆင်မှုမှတ်တမ်း",
+            "ရက်စွဲ",
+            "ဗားရှင်း",
+            "ရေးသားသူ",
+            "ဖော်ပြချက်",
+            "အခန်း",
+            "အပိုင်း {n}",
+            "အကျဉ်းချုပ်",
+            "ပြင်ဆင်သူ",
+            "စစ်ဆေးသူ",
+            "အတည်ပြုသူ",
+            "လက်မှတ်",
+            "အမည်",
+            "ရာထူး",
+            "စာမျက်နှာ {page} / {pages}",
+        ],
+        Language::Vietnamese => [
+            "Mục lục",
+            "Hình",
+            "Bảng",
+            "Chương {n}",
+            "Phụ lục",
+            "Chỉ mục",
+            "Tài liệu tham khảo",
+            "Tiếp theo",
+            "Lịch sử sửa đổi",
+            "Ngày",
+            "Phiên bản",
+            "Tác giả",
+            "Mô tả",
+            "Chương",
+            "Phần {n}",
+            "Tóm tắt",
+            "Người lập",
+            "Người kiểm tra",
+            "Người phê duyệt",
+            "Chữ ký",
+            "Họ tên",
+            "Chức vụ",
+            "Trang {page} / {pages}",
+        ],
+        Language::Chinese => [
+            "目录",
+            "图",
+            "表",
+            "第{n}章",
+            "附录",
+            "索引",
+            "参考文献",
+            "续",
+            "修订历史",
+            "日期",
+            "版本",
+            "作者",
+            "说明",
+            "章节",
+            "第{n}部分",
+            "摘要",
+            "编制",
+            "审核",
+            "批准",
+            "签名",
+            "姓名",
+            "职务",
+            "第 {page} 页，共 {pages} 页",
+        ],
+        Language::Japanese => [
+            "目次",
+            "図",
+            "表",
+            "第{n}章",
+            "付録",
+            "索引",
+            "参考文献",
+            "続き",
+            "改訂履歴",
+            "日付",
+            "バージョン",
+            "作成者",
+            "内容",
+            "章",
+            "第{n}部",
+            "概要",
+            "作成",
+            "確認",
+            "承認",
+            "署名",
+            "氏名",
+            "役職",
+            "{page} / {pages} ページ",
+        ],
+        Language::Korean => [
+            "목차",
+            "그림",
+            "표",
+            "제{n}장",
+            "부록",
+            "색인",
+            "참고 문헌",
+            "계속",
+            "개정 이력",
+            "날짜",
+            "버전",
+            "작성자",
+            "설명",
+            "장",
+            "제{n}부",
+            "요약",
+            "작성",
+            "검토",
+            "승인",
+            "서명",
+            "성명",
+            "직위",
+            "{page} / {pages} 페이지",
+        ],
+        Language::Arabic => [
+            "جدول المحتويات",
+            "شكل",
+            "جدول",
+            "الفصل {n}",
+            "ملحق",
+            "الفهرس",
+            "المراجع",
+            "تابع",
+            "سجل المراجعات",
+            "التاريخ",
+            "الإصدار",
+            "المؤلف",
+            "الوصف",
+            "الفصل",
+            "الجزء {n}",
+            "ملخص",
+            "أعده",
+            "راجعه",
+            "اعتمده",
+            "التوقيع",
+            "الاسم",
+            "المنصب",
+            "صفحة {page} من {pages}",
+        ],
+        Language::Hebrew => [
+            "תוכן עניינים",
+            "איור",
+            "טבלה",
+            "פרק {n}",
+            "נספח",
+            "אינדקס",
+            "מקורות",
+            "המשך",
+            "היסטוריית גרסאות",
+            "תאריך",
+            "גרסה",
+            "מחבר",
+            "תיאור",
+            "פרק",
+            "חלק {n}",
+            "תקציר",
+            "הוכן על ידי",
+            "נבדק על ידי",
+            "אושר על ידי",
+            "חתימה",
+            "שם",
+            "תפקיד",
+            "עמוד {page} מתוך {pages}",
+        ],
+    }
+}
+
+/// Built-in string for a label in a language
+pub fn default_label(lang: Language, label: Label) -> &'static str {
+    table(lang)[label as usize]
+}
+
+/// Localized strings for one document, with user overrides
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Strings {
+    pub lang: Language,
+    pub overrides: HashMap<Label, String>,
+}
+
+impl Strings {
+    pub fn new(lang: Language) -> Self {
+        Self {
+            lang,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Replace built-in strings with user-provided ones
+    pub fn with_overrides(mut self, overrides: HashMap<Label, String>) -> Self {
+        self.overrides.extend(overrides);
+        self
+    }
+
+    /// String for a label, preferring the override
+    pub fn get(&self, label: Label) -> &str {
+        self.overrides
+            .get(&label)
+            .map(String::as_str)
+            .unwrap_or_else(|| default_label(self.lang, label))
+    }
+
+    /// Chapter label for a number ("Chapter 2", "บทที่ 2", "第2章")
+    pub fn chapter(&self, num: &str) -> String {
+        self.get(Label::Chapter).replace("{n}", num)
+    }
+
+    /// Part label for a number ("Part II", "ภาคที่ 2", "第2部")
+    pub fn part(&self, num: &str) -> String {
+        self.get(Label::Part).replace("{n}", num)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_labels() {
+        assert_eq!(
+            default_label(Language::English, Label::TableOfContents),
+            "Table of Contents"
+        );
+        assert_eq!(
+            default_label(Language::Thai, Label::References),
+            "เอกสารอ้างอิง"
+        );
+        assert_eq!(Strings::new(Language::Japanese).chapter("3"), "第3章");
+        assert_eq!(Strings::new(Language::English).part("II"), "Part II");
+        assert_eq!(Label::from_key("TOC"), Some(Label::TableOfContents));
+        assert_eq!(Label::from_key("glossary"), None);
+        assert_eq!(
+            default_label(Language::Thai, Label::RevisionHistory),
+            "ประวัติการแก้ไข"
+        );
+        assert_eq!(
+            Label::from_key("revision_author"),
+            Some(Label::RevisionAuthor)
+        );
+        assert_eq!(
+            default_label(Language::Thai, Label::PageOfTotal),
+            "หน้า {page} จาก {pages}"
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let strings = Strings::new(Language::Thai).with_overrides(HashMap::from([
+            (Label::Figure, "ภาพที่".to_string()),
+            (Label::Chapter, "บท {n}".to_string()),
+        ]));
+        assert_eq!(strings.get(Label::Figure), "ภาพที่");
+        assert_eq!(strings.get(Label::Table), "ตารางที่");
+        assert_eq!(strings.chapter("2"), "บท 2");
+    }
+}
@@ -0,0 +1,97 @@
+//! Word counting for mixed Thai/English text
+//!
+//! Thai is written without spaces between words, so whitespace splitting
+//! would count a whole Thai sentence as one word. Thai runs are segmented
+//! with the shared ICU dictionary segmenter when the `thai-segmentation`
+//! feature is enabled, and estimated from the number of base characters
+//! otherwise.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::detection::is_thai_char;
+
+/// Average number of base characters per Thai word, used when no
+/// dictionary segmenter is available
+#[cfg(not(feature = "thai-segmentation"))]
+const THAI_CHARS_PER_WORD: usize = 4;
+
+/// Count the words in a piece of text
+///
+/// Non-Thai text is counted using Unicode word boundaries (UAX #29).
+/// Thai runs are counted separately, see the module documentation.
+pub fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut other = String::with_capacity(text.len());
+    let mut thai = String::new();
+
+    for c in text.chars() {
+        if is_thai_char(c) {
+            thai.push(c);
+            other.push(' ');
+        } else {
+            if !thai.is_empty() {
+                count += count_thai_words(&thai);
+                thai.clear();
+            }
+            other.push(c);
+        }
+    }
+    if !thai.is_empty() {
+        count += count_thai_words(&thai);
+    }
+
+    count + other.unicode_words().count()
+}
+
+/// Count words in a run of Thai characters using dictionary segmentation
+#[cfg(feature = "thai-segmentation")]
+fn count_thai_words(run: &str) -> usize {
+    super::segment::segment_thai(run)
+        .iter()
+        .filter(|(_, word_like)| *word_like)
+        .count()
+}
+
+/// Estimate words in a run of Thai characters from its base characters
+#[cfg(not(feature = "thai-segmentation"))]
+fn count_thai_words(run: &str) -> usize {
+    let base_chars = run
+        .chars()
+        .filter(|&c| is_thai_base_char(c) && c.is_alphabetic())
+        .count();
+    if base_chars == 0 {
+        0
+    } else {
+        base_chars.div_ceil(THAI_CHARS_PER_WORD)
+    }
+}
+
+/// Thai characters that occupy their own cell (not above/below vowels or tone marks)
+#[cfg(not(feature = "thai-segmentation"))]
+fn is_thai_base_char(c: char) -> bool {
+    !matches!(c, '\u{0E31}' | '\u{0E34}'..='\u{0E3A}' | '\u{0E47}'..='\u{0E4E}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_english_words() {
+        assert_eq!(count_words("Hello, world! It's a test."), 5);
+        assert_eq!(count_words(""), 0);
+    }
+
+    #[test]
+    fn test_count_thai_is_not_whitespace_based() {
+        // "สวัสดีครับ ยินดีต้อนรับ" is four words but only one space
+        let count = count_words("สวัสดีครับ ยินดีต้อนรับ");
+        assert!(count >= 3, "got {}", count);
+    }
+
+    #[test]
+    fn test_count_mixed_text() {
+        let count = count_words("ใช้ md2docx สร้างเอกสาร");
+        assert!(count >= 3, "got {}", count);
+    }
+}
@@ -14,20 +14,33 @@
 
 pub mod config;
 pub mod discovery;
+pub mod diagnostics;
+pub mod diff;
 pub mod docx;
 pub mod error;
+pub mod html;
 pub mod i18n;
 pub mod parser;
+pub mod reverse;
 pub mod template;
+pub mod verify;
+pub mod vfs;
 
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+pub mod logging;
 #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
 pub mod project;
 
-pub use docx::ooxml::{FooterConfig, HeaderConfig, HeaderFooterField};
-pub use docx::toc::TocConfig;
-pub use docx::{DocumentConfig, DocumentMeta};
+pub use diagnostics::{BuildReport, Diagnostic, Severity, SourceLocation};
+pub use docx::ooxml::{
+    FooterConfig, HeaderConfig, HeaderFooterField, HeaderFooterImage, NoteNumbering, NoteRestart,
+    NumberingConfig, ParagraphPreset, StyleGallery, StyleSpacing,
+};
+pub use docx::toc::{TocConfig, TocPageNumbers};
+pub use docx::{DocumentConfig, DocumentMeta, MemoryBudget, RelIdManager, Spacers};
 pub use parser::{IncludeConfig, IncludeResolver, ParsedDocument};
 pub use template::{PlaceholderContext, TemplateDir, TemplateSet};
+pub use vfs::FileMap;
 
 // Re-export template extraction types for use in examples
 pub use template::extract::{CoverTemplate, HeaderFooterTemplate, ImageTemplate, TableTemplate};
@@ -35,22 +48,35 @@ pub use template::extract::{CoverTemplate, HeaderFooterTemplate, ImageTemplate,
 // Re-export helper function for finding image paths
 pub use template::extract::cover::find_image_path_from_rel_id;
 
+pub(crate) mod epub;
 pub mod mermaid;
+pub(crate) mod odt;
 
-#[cfg(feature = "git")]
-pub mod diff;
-
-#[cfg(feature = "wasm")]
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 pub mod wasm;
 
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
+
+#[cfg(all(feature = "pdf", not(target_arch = "wasm32")))]
+pub mod pdf;
+
+#[cfg(any(
+    all(feature = "wasm", target_arch = "wasm32"),
+    all(feature = "server", not(target_arch = "wasm32"))
+))]
+pub(crate) mod options;
+
 pub use docx::ooxml::{FootnotesXml, Language, Paragraph, Run};
 pub use error::{Error, Result};
 
+use diagnostics::WarningCategory;
 use docx::builder::build_document;
 use docx::ooxml::numbering::generate_numbering_xml_with_context;
-use docx::ooxml::{ContentTypes, DocumentXml, Relationships, StylesDocument};
+use docx::ooxml::{heading_style_id, ContentTypes, DocumentXml, Relationships, StylesDocument};
 use docx::packager::Packager;
 use parser::parse_markdown_with_frontmatter;
+use std::collections::HashMap;
 use std::io::Cursor;
 
 /// High-level Document builder for creating DOCX files
@@ -60,6 +86,8 @@ pub struct Document {
     doc_xml: DocumentXml,
     /// Language for styles/fonts
     lang: Language,
+    /// Spacing overrides per paragraph style id
+    style_spacing: HashMap<String, StyleSpacing>,
 }
 
 impl Document {
@@ -68,6 +96,7 @@ impl Document {
         Self {
             doc_xml: DocumentXml::new(),
             lang: Language::English,
+            style_spacing: HashMap::new(),
         }
     }
 
@@ -76,6 +105,7 @@ impl Document {
         Self {
             doc_xml: DocumentXml::new(),
             lang,
+            style_spacing: HashMap::new(),
         }
     }
 
@@ -85,47 +115,49 @@ impl Document {
         self
     }
 
-    /// Add a heading (level 1-4)
-    pub fn add_heading(mut self, level: u8, text: &str) -> Self {
-        let style_id = match level {
-            1 => "Heading1",
-            2 => "Heading2",
-            3 => "Heading3",
-            _ => "Heading4",
-        };
-        let p = Paragraph::with_style(style_id)
-            .add_text(text)
+    /// Set the spacing of a paragraph style (e.g. "BodyText", "Heading1")
+    ///
+    /// Applies to the style and to the paragraphs added with it afterwards.
+    pub fn style_spacing(mut self, style: &str, spacing: StyleSpacing) -> Self {
+        self.style_spacing.insert(style.to_string(), spacing);
+        self
+    }
+
+    /// Paragraph in `style` with the document's spacing for it
+    fn styled(&self, style: &str) -> Paragraph {
+        let mut p = Paragraph::with_style(style)
             .spacing(0, 0)
             .line_spacing(240, "auto");
+        if let Some(spacing) = self.style_spacing.get(style) {
+            p = p.style_spacing(spacing);
+        }
+        p
+    }
+
+    /// Add a heading (level 1-6)
+    pub fn add_heading(mut self, level: u8, text: &str) -> Self {
+        let p = self.styled(heading_style_id(level)).add_text(text);
         self.doc_xml.add_paragraph(p);
         self
     }
 
     /// Add a paragraph with optional style
     pub fn add_paragraph(mut self, text: &str) -> Self {
-        let p = Paragraph::with_style("Normal")
-            .add_text(text)
-            .spacing(0, 0)
-            .line_spacing(240, "auto");
+        let p = self.styled("Normal").add_text(text);
         self.doc_xml.add_paragraph(p);
         self
     }
 
     /// Add a styled paragraph
     pub fn add_styled_paragraph(mut self, style: &str, text: &str) -> Self {
-        let p = Paragraph::with_style(style)
-            .add_text(text)
-            .spacing(0, 0)
-            .line_spacing(240, "auto");
+        let p = self.styled(style).add_text(text);
         self.doc_xml.add_paragraph(p);
         self
     }
 
     /// Add a paragraph with a Run (for fine-grained control)
     pub fn add_paragraph_with_runs(mut self, style: &str, runs: Vec<Run>) -> Self {
-        let mut p = Paragraph::with_style(style)
-            .spacing(0, 0)
-            .line_spacing(240, "auto");
+        let mut p = self.styled(style);
         for run in runs {
             p = p.add_run(run);
         }
@@ -135,12 +167,20 @@ impl Document {
 
     /// Add a code block
     pub fn add_code_block(mut self, code: &str) -> Self {
-        // Split by lines and add each as a Code paragraph
-        for line in code.lines() {
+        // Split by lines and add each as a Code paragraph; space before and
+        // after the block goes to its first and last line
+        let spacing = self.style_spacing.get("Code").copied().unwrap_or_default();
+        let last = code.lines().count().saturating_sub(1);
+        for (i, line) in code.lines().enumerate() {
             let p = Paragraph::with_style("Code")
                 .add_text(line)
                 .spacing(0, 0)
-                .line_spacing(240, "auto");
+                .line_spacing(240, "auto")
+                .style_spacing(&StyleSpacing {
+                    before: spacing.before.filter(|_| i == 0),
+                    after: spacing.after.filter(|_| i == last),
+                    line: spacing.line,
+                });
             self.doc_xml.add_paragraph(p);
         }
         self
@@ -168,7 +208,8 @@ impl Document {
         let content_types = ContentTypes::new();
         let rels = Relationships::root_rels();
         let doc_rels = Relationships::document_rels();
-        let styles = StylesDocument::new(self.lang, None);
+        let mut styles = StylesDocument::new(self.lang, None);
+        styles.apply_spacing(&self.style_spacing);
 
         // Package
         packager.package(
@@ -181,7 +222,7 @@ impl Document {
         )?;
 
         let cursor = packager.finish()?;
-        Ok(cursor.into_inner())
+        crate::docx::validate::self_check(cursor.into_inner())
     }
 
     /// Write document to a file (only available when not targeting WASM)
@@ -255,6 +296,137 @@ pub fn markdown_to_docx_with_config(
     markdown_to_docx_with_templates(markdown, lang, config, None, &PlaceholderContext::default())
 }
 
+/// Convert markdown string to DOCX bytes, returning the warnings too
+///
+/// Same as [`markdown_to_docx_with_config`], but the warnings reported
+/// during the build are collected in the [`BuildReport`] (with their source
+/// locations where known) instead of only being logged.
+///
+/// # Example
+/// ```rust,no_run
+/// use md2docx::{markdown_to_docx_with_report, DocumentConfig, Language};
+///
+/// let md = "# Hello\n\n![Logo](missing.png)";
+/// let report =
+///     markdown_to_docx_with_report(md, Language::English, &DocumentConfig::default()).unwrap();
+/// for diagnostic in report.diagnostics() {
+///     eprintln!("{}", diagnostic.render(Some(md)));
+/// }
+/// std::fs::write("output.docx", &report.bytes).unwrap();
+/// ```
+pub fn markdown_to_docx_with_report(
+    markdown: &str,
+    lang: Language,
+    config: &DocumentConfig,
+) -> Result<BuildReport> {
+    BuildReport::capture(|| markdown_to_docx_with_config(markdown, lang, config))
+}
+
+/// Convert markdown string to a standalone HTML page
+///
+/// The page is rendered from the same parsed document as the DOCX, with
+/// the styles approximated in CSS, local images embedded and mermaid
+/// diagrams and equations inlined as SVG.
+///
+/// # Example
+/// ```rust,no_run
+/// use md2docx::{markdown_to_html, DocumentConfig, Language};
+///
+/// let md = "# Hello World\n\nThis is **bold** text.";
+/// let html = markdown_to_html(md, Language::English, &DocumentConfig::default()).unwrap();
+/// std::fs::write("output.html", html).unwrap();
+/// ```
+pub fn markdown_to_html(markdown: &str, lang: Language, config: &DocumentConfig) -> Result<String> {
+    let parsed = parse_markdown_with_frontmatter(markdown);
+    html::render(&parsed, lang, config)
+}
+
+/// Convert markdown chapters to EPUB 3 bytes
+///
+/// Each chapter becomes one XHTML document of the spine, rendered as for
+/// [`markdown_to_html`]. Local images and the fonts of `embed_dir` are
+/// stored in the package, and mermaid diagrams and equations are inlined
+/// as SVG. Unless `process_all_headings` is set, the first of several
+/// chapters is treated as the cover and left out of the contents.
+///
+/// # Example
+/// ```rust,no_run
+/// use md2docx::{markdown_to_epub, DocumentConfig, Language};
+///
+/// let chapters = ["# Preface\n\nWelcome.", "# Chapter 1\n\nThis is **bold** text."];
+/// let epub_bytes = markdown_to_epub(&chapters, Language::English, &DocumentConfig::default()).unwrap();
+/// std::fs::write("output.epub", epub_bytes).unwrap();
+/// ```
+pub fn markdown_to_epub(
+    chapters: &[&str],
+    lang: Language,
+    config: &DocumentConfig,
+) -> Result<Vec<u8>> {
+    let parsed: Vec<ParsedDocument> = chapters
+        .iter()
+        .map(|markdown| parse_markdown_with_frontmatter(markdown))
+        .collect();
+    epub::write(&parsed, lang, config)
+}
+
+/// Convert markdown string to PDF bytes
+///
+/// The PDF is laid out by md2docx itself, so no office suite is needed.
+/// It uses the fonts, page setup, numbering and table of contents settings
+/// of `config`; templates, headers and watermarks apply to DOCX only.
+///
+/// # Example
+/// ```rust,no_run
+/// use md2docx::{markdown_to_pdf, DocumentConfig, Language};
+///
+/// let md = "# สวัสดี\n\nThis is **bold** text.";
+/// let pdf_bytes = markdown_to_pdf(md, Language::Thai, &DocumentConfig::default()).unwrap();
+/// std::fs::write("output.pdf", pdf_bytes).unwrap();
+/// ```
+#[cfg(all(feature = "pdf", not(target_arch = "wasm32")))]
+pub fn markdown_to_pdf(markdown: &str, lang: Language, config: &DocumentConfig) -> Result<Vec<u8>> {
+    let parsed = parse_markdown_with_frontmatter(markdown);
+    pdf::render(&parsed, lang, config)
+}
+
+/// Convert markdown string to OpenDocument text (ODT) bytes
+///
+/// The document is built as for DOCX and written as ODF, keeping the style
+/// names, numbering, table of contents, header and footer of `config`.
+/// Templates, watermarks and tracked changes apply to DOCX only, and
+/// equations are embedded as images even when `math_renderer` is `"omml"`.
+///
+/// # Example
+/// ```rust,no_run
+/// use md2docx::{markdown_to_odt, DocumentConfig, Language};
+///
+/// let md = "# Hello World\n\nThis is **bold** text.";
+/// let odt_bytes = markdown_to_odt(md, Language::English, &DocumentConfig::default()).unwrap();
+/// std::fs::write("output.odt", odt_bytes).unwrap();
+/// ```
+pub fn markdown_to_odt(markdown: &str, lang: Language, config: &DocumentConfig) -> Result<Vec<u8>> {
+    let parsed = parse_markdown_with_frontmatter(markdown);
+
+    // The whole body is needed in memory, and OMML has no ODF equivalent
+    let mut config = config.clone();
+    config.memory_budget = None;
+    if config.math_renderer == "omml" {
+        config.math_renderer = "image".to_string();
+    }
+
+    let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+    let mut build_result = build_document(&parsed, lang, &config, &mut rel_manager, None, None)?;
+    if let Some(toc_builder) = build_result.toc_builder.take() {
+        if config.toc.enabled && !toc_builder.is_empty() {
+            let toc_elements = toc_builder.generate_toc(&config.toc);
+            for (i, elem) in toc_elements.into_iter().enumerate() {
+                build_result.document.elements.insert(i, elem);
+            }
+        }
+    }
+    odt::write(&mut build_result, lang, &config)
+}
+
 /// Convert markdown to DOCX with template support
 ///
 /// This function extends `markdown_to_docx_with_config` by adding support for
@@ -325,10 +497,37 @@ pub fn markdown_to_docx_with_templates(
     doc_config: &DocumentConfig,
     templates: Option<&crate::template::TemplateSet>,
     placeholder_ctx: &crate::template::PlaceholderContext,
+) -> Result<Vec<u8>> {
+    markdown_to_docx_with_stages(
+        markdown,
+        lang,
+        doc_config,
+        templates,
+        placeholder_ctx,
+        &mut |_| {},
+    )
+}
+
+/// Convert markdown to DOCX with templates, reporting each finished stage
+///
+/// Works like [`markdown_to_docx_with_templates`], calling `stage_done`
+/// with `"parse"`, `"build"` and `"package"` as each stage completes, so
+/// callers can time them (the build report does).
+pub fn markdown_to_docx_with_stages(
+    markdown: &str,
+    lang: Language,
+    doc_config: &DocumentConfig,
+    templates: Option<&crate::template::TemplateSet>,
+    placeholder_ctx: &crate::template::PlaceholderContext,
+    stage_done: &mut dyn FnMut(&'static str),
 ) -> Result<Vec<u8>> {
     let parsed = parse_markdown_with_frontmatter(markdown);
+    stage_done("parse");
 
     let mut rel_manager = crate::docx::rels_manager::RelIdManager::new();
+    if doc_config.reproducible {
+        rel_manager.use_content_ids();
+    }
     let table_template = templates.and_then(|t| t.table.as_ref());
     let image_template = templates.and_then(|t| t.image.as_ref());
     let mut build_result = build_document(
@@ -340,6 +539,11 @@ pub fn markdown_to_docx_with_templates(
         image_template,
     )?;
 
+    // Show the differences from an earlier version as tracked changes
+    if let Some(baseline) = &doc_config.compare_to {
+        diff::redline(baseline, lang, doc_config, &mut build_result.document.elements)?;
+    }
+
     // Apply templates if provided
     if let Some(template_set) = templates {
         // Apply cover template
@@ -449,7 +653,7 @@ pub fn markdown_to_docx_with_templates(
             {
                 if p.is_section_break() {
                     // Found the section break that ends Chapter 1 (and defines its properties)
-                    p.page_num_start = Some(1);
+                    p.page_num_start.get_or_insert(1);
                     found_next_break = true;
                     break;
                 }
@@ -459,7 +663,7 @@ pub fn markdown_to_docx_with_templates(
         // If no section break found after Chapter 1, it means Chapter 1 is the last section.
         // Its properties are defined in the document's final sectPr.
         if !found_next_break {
-            build_result.document.page_num_start = Some(1);
+            build_result.document.page_num_start.get_or_insert(1);
         }
 
         // Also check if there's a section break *before* Chapter 1 (e.g. from TOC).
@@ -472,9 +676,10 @@ pub fn markdown_to_docx_with_templates(
     // Note: Table and image templates would be applied during block processing
     // This requires modifying the builder to use template styles
     // For now, we just load and extract the templates
+    stage_done("build");
 
     let buffer = Cursor::new(Vec::new());
-    let mut packager = Packager::new(buffer);
+    let mut packager = Packager::with_compression(buffer, doc_config.compression);
 
     let mut content_types = ContentTypes::new();
     let rels = Relationships::root_rels();
@@ -500,8 +705,38 @@ pub fn markdown_to_docx_with_templates(
         }
     }
 
+    styles.apply_toc_layout(&doc_config.toc);
+    styles.apply_paragraph_preset(doc_config.paragraph_preset);
+    styles.set_widow_control(doc_config.widow_control);
+    for name in styles.set_gallery(doc_config.style_gallery.clone()) {
+        crate::diagnostics::warn(
+            WarningCategory::Other,
+            format!("[gallery] style '{}' is not defined; ignored", name),
+        );
+    }
+    for id in styles.apply_spacing(&doc_config.style_spacing) {
+        crate::diagnostics::warn(
+            WarningCategory::Other,
+            format!("[styles.{}] does not name a paragraph style; ignored", id),
+        );
+    }
+
     // Process images from build_result (includes cover template images and markdown images)
     // Header/footer images are handled separately with header_ prefix
+    build_result.images.dedupe_media();
+    let image_limits = doc_config.image_limits;
+    if image_limits.is_enabled() && !cfg!(feature = "images") {
+        diagnostics::warn(
+            WarningCategory::Other,
+            "[images] limits need the 'images' feature; images are embedded at full size",
+        );
+    }
+    // Widest size each media file is shown at, across all its uses
+    let mut display_widths: std::collections::HashMap<&str, i64> = std::collections::HashMap::new();
+    for image in &build_result.images.images {
+        let width = display_widths.entry(&image.filename).or_insert(0);
+        *width = (*width).max(image.width_emu);
+    }
     for image in &build_result.images.images {
         let ext = std::path::Path::new(&image.filename)
             .extension()
@@ -518,14 +753,52 @@ pub fn markdown_to_docx_with_templates(
             _ => "application/octet-stream",
         };
         content_types.add_image_extension(ext, content_type);
-        doc_rels.add_image_with_id(&image.rel_id, &image.filename);
+        if !build_result.is_footnote_image(&image.rel_id) {
+            doc_rels.add_image_with_id(&image.rel_id, &image.filename);
+        }
 
         #[cfg(not(target_arch = "wasm32"))]
         {
+            // Media shared by several images is packaged on its first use
+            let Some(display_width) = display_widths.remove(image.filename.as_str()) else {
+                continue;
+            };
+            // A file map stands in for the file system: images missing from
+            // it are not read from disk
+            let on_disk = image.data.is_none() && doc_config.files.is_none();
+            let downscaled = image_limits
+                .max_pixels(display_width)
+                .filter(|_| cfg!(feature = "images") && (image.data.is_some() || on_disk))
+                .and_then(|max_width| {
+                    docx::optimize::downscale_image(image, max_width, image_limits.jpeg_quality)
+                });
+            if let Some(smaller) = downscaled {
+                packager.add_image(&image.filename, &smaller)?;
+            } else if let Some(ref data) = image.data {
+                packager.add_image(&image.filename, data)?;
+            } else if let Some(file) = on_disk
+                .then(|| std::fs::File::open(&image.src).ok())
+                .flatten()
+            {
+                packager.add_image_reader(&image.filename, std::io::BufReader::new(file))?;
+            } else {
+                diagnostics::warn(
+                    WarningCategory::MissingImage,
+                    format!("Image not found: {}", image.src),
+                );
+            }
+        }
+        // Without a file system, only images held in memory (rendered or
+        // registered in a file map) can be embedded
+        #[cfg(target_arch = "wasm32")]
+        if display_widths.remove(image.filename.as_str()).is_some() {
             if let Some(ref data) = image.data {
                 packager.add_image(&image.filename, data)?;
-            } else if let Ok(data) = std::fs::read(&image.src) {
-                packager.add_image(&image.filename, &data)?;
+            } else {
+                diagnostics::warn(
+                    WarningCategory::MissingImage,
+                    format!("Image not found: {}", image.src),
+                );
             }
         }
     }
@@ -536,13 +809,19 @@ pub fn markdown_to_docx_with_templates(
     doc_rels.add_footnotes_with_id(&footnotes_rel_id);
     let footnotes_xml = build_result.footnotes.to_xml()?;
     packager.add_footnotes(&footnotes_xml)?;
+    let footnote_rels = build_result.footnote_rels();
+    if !footnote_rels.is_empty() {
+        packager.add_footnotes_rels(&footnote_rels.to_xml()?)?;
+    }
 
     // Add endnotes
     use crate::docx::ooxml::EndnotesXml;
     content_types.add_endnotes();
     let endnotes_rel_id = rel_manager.next_id();
     doc_rels.add_endnotes_with_id(&endnotes_rel_id);
-    let endnotes = EndnotesXml::new();
+    let endnotes = EndnotesXml {
+        separator: build_result.document.notes.separator.clone(),
+    };
     let endnotes_xml = endnotes.to_xml()?;
     packager.add_endnotes(&endnotes_xml)?;
 
@@ -555,7 +834,8 @@ pub fn markdown_to_docx_with_templates(
     content_types.add_numbering();
     let numbering_rel_id = rel_manager.next_id();
     doc_rels.add_numbering_with_id(&numbering_rel_id);
-    let numbering_xml = generate_numbering_xml_with_context(&build_result.numbering)?;
+    let numbering_xml =
+        generate_numbering_xml_with_context(&build_result.numbering, &doc_config.numbering)?;
     packager.add_numbering(&numbering_xml)?;
 
     // Process headers
@@ -655,20 +935,23 @@ pub fn markdown_to_docx_with_templates(
 
             // If no specific fonts configured, skip — only embed used fonts
             if font_names.is_empty() {
-                eprintln!(
-                    "Warning: Font embedding enabled but no fonts configured (fonts.default / fonts.code). Skipping embed."
+                crate::diagnostics::warn(
+                    WarningCategory::Font,
+                    "Font embedding enabled but no fonts configured (fonts.default / fonts.code). Skipping embed.",
                 );
                 auto_embedded_fonts = Vec::new();
             } else {
                 let name_refs: Vec<&str> = font_names.iter().map(|s| s.as_str()).collect();
                 auto_embedded_fonts = crate::docx::font_embed::prepare_embedded_fonts(
                     embed_dir, &name_refs,
-                )
-                .unwrap_or_default();
+                )?;
             }
             &auto_embedded_fonts
         } else {
-            eprintln!("Warning: Font embed directory not found: {}", embed_dir.display());
+            crate::diagnostics::warn(
+                WarningCategory::Font,
+                format!("Font embed directory not found: {}", embed_dir.display()),
+            );
             auto_embedded_fonts = Vec::new();
             &auto_embedded_fonts
         }
@@ -677,6 +960,7 @@ pub fn markdown_to_docx_with_templates(
         &auto_embedded_fonts
     };
 
+    let subset_fonts = doc_config.subset_fonts && !effective_embedded.is_empty();
     let embedded_fonts_ref = if !effective_embedded.is_empty() {
         // Add font content type
         content_types.add_font_extension();
@@ -698,8 +982,13 @@ pub fn markdown_to_docx_with_templates(
         None
     };
 
-    let core_props = crate::docx::ooxml::CoreProperties::new();
+    let mut core_props = crate::docx::ooxml::CoreProperties::new();
     let app_props = crate::docx::ooxml::AppProperties::new();
+    if doc_config.reproducible {
+        core_props.fix_timestamps();
+        content_types.sort();
+        doc_rels.sort();
+    }
     packager.package_with_props(
         &build_result.document,
         &styles,
@@ -760,7 +1049,7 @@ pub fn markdown_to_docx_with_templates(
                 crate::template::render::header_footer::generate_header_footer_rels_xml_with_prefix(
                     &media_tuples,
                     "header_",
-                );
+                )?;
             packager.add_header_rels(entry.number, &rels_xml)?;
         }
     }
@@ -806,13 +1095,18 @@ pub fn markdown_to_docx_with_templates(
                 crate::template::render::header_footer::generate_header_footer_rels_xml_with_prefix(
                     &media_tuples,
                     "header_",
-                );
+                )?;
             packager.add_footer_rels(entry.number, &rels_xml)?;
         }
     }
 
-    let cursor = packager.finish()?;
-    Ok(cursor.into_inner())
+    let mut docx = packager.finish()?.into_inner();
+    if subset_fonts {
+        docx = crate::docx::font_embed::subset_embedded_fonts(docx)?;
+    }
+    let docx = RelIdManager::audit_output(docx, doc_config.audit_rels)?;
+    stage_done("package");
+    crate::docx::validate::self_check(docx)
 }
 
 /// Context for applying cover template to a document
@@ -882,6 +1176,12 @@ fn apply_cover_template(ctx: CoverTemplateContext<'_>) -> Result<()> {
                 .hyperlinks
                 .hyperlinks
                 .extend(inside_result.hyperlinks.hyperlinks);
+            // numIds and bookmark IDs came from the shared rel_manager, so the
+            // inside lists can join the main numbering.xml as they are
+            ctx.build_result
+                .numbering
+                .lists
+                .extend(inside_result.numbering.lists);
 
             // Generate XML string for the inside content
             // We use a temporary DocumentXml to serialize just these elements
@@ -895,22 +1195,7 @@ fn apply_cover_template(ctx: CoverTemplateContext<'_>) -> Result<()> {
                 }
                 temp_doc.elements.push(elem);
             }
-            // Use to_xml to get the XML string, but we need to extract body content
-            let full_xml = String::from_utf8(temp_doc.to_xml()?).unwrap_or_default();
-
-            // Extract content between <w:body> and </w:body> (excluding sectPr)
-            if let Some(start) = full_xml.find("<w:body>") {
-                if let Some(end) = full_xml.rfind("<w:sectPr") {
-                    // Find last sectPr
-                    full_xml[start + 8..end].to_string()
-                } else if let Some(end) = full_xml.find("</w:body>") {
-                    full_xml[start + 8..end].to_string()
-                } else {
-                    String::new()
-                }
-            } else {
-                String::new()
-            }
+            temp_doc.body_xml()?
         } else {
             String::new()
         };
@@ -1247,23 +1532,22 @@ pub fn markdown_to_docx_with_includes(
     resolve_includes(&mut parsed, include_config)?;
 
     // Determine language from frontmatter, default to English
-    let lang = if let Some(ref fm) = parsed.frontmatter {
-        if let Some(ref l) = fm.language {
-            match l.to_lowercase().as_str() {
-                "th" | "thai" => Language::Thai,
-                _ => Language::English,
-            }
-        } else {
-            Language::English
-        }
-    } else {
-        Language::English
+    let lang = parsed
+        .frontmatter
+        .as_ref()
+        .and_then(|fm| fm.language.as_deref())
+        .and_then(Language::from_code)
+        .unwrap_or_default();
+
+    // Images in the document itself are relative to the include base path
+    let config = DocumentConfig {
+        base_path: Some(include_config.base_path.clone()),
+        ..Default::default()
     };
-
     let mut build_result = build_document(
         &parsed,
         lang,
-        &DocumentConfig::default(),
+        &config,
         &mut crate::docx::rels_manager::RelIdManager::new(),
         None,
         None,
@@ -1290,6 +1574,7 @@ pub fn markdown_to_docx_with_includes(
     let styles = StylesDocument::new(lang, None);
 
     // Process images
+    build_result.images.dedupe_media();
     for image in &build_result.images.images {
         let ext = std::path::Path::new(&image.filename)
             .extension()
@@ -1306,7 +1591,9 @@ pub fn markdown_to_docx_with_includes(
         };
         content_types.add_image_extension(ext, content_type);
 
-        doc_rels.add_image_with_id(&image.rel_id, &image.filename);
+        if !build_result.is_footnote_image(&image.rel_id) {
+            doc_rels.add_image_with_id(&image.rel_id, &image.filename);
+        }
 
         if image.rel_id.starts_with("rId") {
             // Valid relationship ID
@@ -1316,8 +1603,13 @@ pub fn markdown_to_docx_with_includes(
         {
             if let Some(ref data) = image.data {
                 packager.add_image(&image.filename, data)?;
-            } else if let Ok(data) = std::fs::read(&image.src) {
-                packager.add_image(&image.filename, &data)?;
+            } else if let Ok(file) = std::fs::File::open(&image.src) {
+                packager.add_image_reader(&image.filename, std::io::BufReader::new(file))?;
+            } else {
+                diagnostics::warn(
+                    WarningCategory::MissingImage,
+                    format!("Image not found: {}", image.src),
+                );
             }
         }
     }
@@ -1327,12 +1619,18 @@ pub fn markdown_to_docx_with_includes(
     doc_rels.add_footnotes();
     let footnotes_xml = build_result.footnotes.to_xml()?;
     packager.add_footnotes(&footnotes_xml)?;
+    let footnote_rels = build_result.footnote_rels();
+    if !footnote_rels.is_empty() {
+        packager.add_footnotes_rels(&footnote_rels.to_xml()?)?;
+    }
 
     // Always add endnotes.xml (settings.xml references endnote IDs -1 and 0)
     use crate::docx::ooxml::EndnotesXml;
     content_types.add_endnotes();
     doc_rels.add_endnotes();
-    let endnotes = EndnotesXml::new();
+    let endnotes = EndnotesXml {
+        separator: build_result.document.notes.separator.clone(),
+    };
     let endnotes_xml = endnotes.to_xml()?;
     packager.add_endnotes(&endnotes_xml)?;
 
@@ -1344,7 +1642,8 @@ pub fn markdown_to_docx_with_includes(
     // Always add numbering.xml for list support
     content_types.add_numbering();
     doc_rels.add_numbering();
-    let numbering_xml = generate_numbering_xml_with_context(&build_result.numbering)?;
+    let numbering_xml =
+        generate_numbering_xml_with_context(&build_result.numbering, &NumberingConfig::default())?;
     packager.add_numbering(&numbering_xml)?;
 
     // Process headers and capture returned relationship IDs
@@ -1440,7 +1739,7 @@ pub fn markdown_to_docx_with_includes(
                 crate::template::render::header_footer::generate_header_footer_rels_xml_with_prefix(
                     &media_tuples,
                     "header_",
-                );
+                )?;
             packager.add_header_rels(entry.number, &rels_xml)?;
         }
     }
@@ -1486,13 +1785,57 @@ pub fn markdown_to_docx_with_includes(
                 crate::template::render::header_footer::generate_header_footer_rels_xml_with_prefix(
                     &media_tuples,
                     "header_",
-                );
+                )?;
             packager.add_footer_rels(entry.number, &rels_xml)?;
         }
     }
 
     let cursor = packager.finish()?;
-    Ok(cursor.into_inner())
+    crate::docx::validate::self_check(cursor.into_inner())
+}
+
+/// Convert markdown with includes to DOCX within a memory budget
+///
+/// Like [`markdown_to_docx_with_includes`], but each top-level
+/// `{!include:...}` is read, built and rendered to XML only when the build
+/// reaches it. Rendered body XML beyond `budget` is spilled to disk and
+/// copied into the package at the end, so books assembled from many large
+/// chapter files build without holding every chapter in memory.
+///
+/// # Example
+/// ```rust,no_run
+/// use md2docx::{markdown_to_docx_bounded, IncludeConfig, MemoryBudget};
+/// use std::path::PathBuf;
+///
+/// let md = "{!include:ch01.md}\n\n---\n\n{!include:ch02.md}";
+/// let include_config = IncludeConfig {
+///     base_path: PathBuf::from("./book"),
+///     source_root: PathBuf::from("./src"),
+///     max_depth: 10,
+/// };
+///
+/// let budget = MemoryBudget::new(64 * 1024 * 1024);
+/// let docx_bytes = markdown_to_docx_bounded(md, &include_config, budget).unwrap();
+/// std::fs::write("book.docx", docx_bytes).unwrap();
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn markdown_to_docx_bounded(
+    markdown: &str,
+    include_config: &IncludeConfig,
+    budget: MemoryBudget,
+) -> Result<Vec<u8>> {
+    let lang = parser::parse_frontmatter(markdown)
+        .0
+        .and_then(|fm| fm.language)
+        .and_then(|code| Language::from_code(&code))
+        .unwrap_or_default();
+    let config = DocumentConfig {
+        base_path: Some(include_config.base_path.clone()),
+        memory_budget: Some(budget),
+        includes: Some(include_config.clone()),
+        ..Default::default()
+    };
+    markdown_to_docx_with_config(markdown, lang, &config)
 }
 
 #[cfg(test)]
@@ -1673,15 +2016,19 @@ mod tests {
     }
 
     #[test]
-    fn test_heading_level_4() {
+    fn test_heading_levels_4_to_6() {
         let doc = Document::new()
             .add_heading(4, "Level 4")
-            .add_heading(5, "Level 5"); // Should also use Heading4
+            .add_heading(5, "Level 5")
+            .add_heading(6, "Level 6")
+            .add_heading(7, "Level 7"); // Deeper levels use Heading6
 
         let paragraphs = get_paragraphs(&doc);
-        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(paragraphs.len(), 4);
         assert_eq!(paragraphs[0].style_id, Some("Heading4".to_string()));
-        assert_eq!(paragraphs[1].style_id, Some("Heading4".to_string()));
+        assert_eq!(paragraphs[1].style_id, Some("Heading5".to_string()));
+        assert_eq!(paragraphs[2].style_id, Some("Heading6".to_string()));
+        assert_eq!(paragraphs[3].style_id, Some("Heading6".to_string()));
     }
 
     #[test]
@@ -2017,4 +2364,95 @@ mod tests {
         };
         assert_eq!(doc.blocks.len(), 1);
     }
+
+    #[test]
+    fn test_reproducible_output() {
+        let config = DocumentConfig {
+            reproducible: true,
+            ..Default::default()
+        };
+        let build =
+            |md: &str| markdown_to_docx_with_config(md, Language::English, &config).unwrap();
+        let part = |docx: &[u8], name: &str| {
+            let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+            let mut xml = String::new();
+            std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut xml).unwrap();
+            xml
+        };
+
+        let md = "# Setup\n\nSee [the site](https://example.com).";
+        let first = build(md);
+        assert_eq!(first, build(md));
+        assert!(part(&first, "docProps/core.xml").contains("1980-01-01T00:00:00Z"));
+
+        // Content added before the link leaves its relationship ID alone
+        let link_id = |docx: &[u8]| {
+            let rels = part(docx, "word/_rels/document.xml.rels");
+            let target = rels.find("Target=\"https://example.com\"").unwrap();
+            let start = rels[..target].rfind("Id=\"").unwrap() + 4;
+            let len = rels[start..].find('"').unwrap();
+            rels[start..start + len].to_string()
+        };
+        let edited = build(&format!(
+            "# Intro\n\nSee [docs](https://docs.example.com).\n\n{md}"
+        ));
+        assert_eq!(link_id(&first), link_id(&edited));
+
+        // Embedded fonts are scanned, numbered and listed in a fixed order
+        let config = DocumentConfig {
+            embed_dir: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("docs/fonts")),
+            fonts: Some(crate::docx::ooxml::FontConfig {
+                default: Some("Noto Sans Thai".to_string()),
+                code: Some("IBM Plex Sans Thai".to_string()),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let build = |md: &str| markdown_to_docx_with_config(md, Language::Thai, &config).unwrap();
+        let first = build(md);
+        assert_eq!(first, build(md));
+        assert!(part(&first, "word/fontTable.xml").contains("w:embedRegular"));
+    }
+
+    #[test]
+    fn test_build_from_file_map() {
+        // 1x1 PNG header is enough for the dimension reader
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 3]);
+        let mut files = FileMap::new();
+        files.insert("book/ch01.md", b"# Setup\n\n![Logo](img/logo.png)".to_vec());
+        files.insert("book/img/logo.png", png.clone());
+        files.insert("src/main.rs", b"fn main() {}".to_vec());
+
+        let config = DocumentConfig {
+            base_path: Some(PathBuf::from("book")),
+            includes: Some(IncludeConfig {
+                base_path: PathBuf::from("book"),
+                source_root: PathBuf::from("src"),
+                ..Default::default()
+            }),
+            files: Some(std::sync::Arc::new(files)),
+            ..Default::default()
+        };
+        let md = "{!include:ch01.md}\n\n{!code:main.rs}";
+        let docx = markdown_to_docx_with_config(md, Language::English, &config).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+        let mut document = String::new();
+        let mut part = archive.by_name("word/document.xml").unwrap();
+        std::io::Read::read_to_string(&mut part, &mut document).unwrap();
+        drop(part);
+        assert!(document.contains("Setup"));
+        assert!(document.contains("main"));
+
+        let media: Vec<String> = archive
+            .file_names()
+            .filter(|name| name.starts_with("word/media/"))
+            .map(String::from)
+            .collect();
+        assert_eq!(media.len(), 1);
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut archive.by_name(&media[0]).unwrap(), &mut data).unwrap();
+        assert_eq!(data, png);
+    }
 }
@@ -0,0 +1,162 @@
+//! Command-line logger
+//!
+//! The library reports progress and warnings through the [`log`] facade.
+//! [`init`] installs a logger that writes them to stderr, filtered by the
+//! `--verbose` / `--quiet` flags, and optionally to a log file. The log file
+//! always receives debug output with timestamps so it can be attached to CI
+//! runs.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::Result;
+
+/// Logger writing to stderr and an optional log file
+struct CliLogger {
+    /// Most detailed level shown on stderr
+    stderr_level: LevelFilter,
+    /// Log file, written at debug level or finer
+    file: Option<Mutex<File>>,
+}
+
+impl CliLogger {
+    fn file_level(&self) -> LevelFilter {
+        if self.file.is_some() {
+            self.stderr_level.max(LevelFilter::Debug)
+        } else {
+            LevelFilter::Off
+        }
+    }
+}
+
+impl Log for CliLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.stderr_level || metadata.level() <= self.file_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= self.stderr_level {
+            eprintln!(
+                "{}",
+                format_stderr(record, self.stderr_level >= LevelFilter::Debug)
+            );
+        }
+
+        if record.level() <= self.file_level() {
+            if let Some(file) = &self.file {
+                if let Ok(mut file) = file.lock() {
+                    let _ = writeln!(
+                        file,
+                        "{} {:<5} {}: {}",
+                        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+                        record.level(),
+                        record.target(),
+                        record.args()
+                    );
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// Format a record for the terminal
+///
+/// Normal runs keep the familiar `Warning: ...` / `Error: ...` prefixes.
+/// Verbose runs add a timestamp, the level and the emitting module.
+fn format_stderr(record: &Record, verbose: bool) -> String {
+    if verbose {
+        return format!(
+            "[{} {:<5} {}] {}",
+            chrono::Local::now().format("%H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+    match record.level() {
+        Level::Error => format!("Error: {}", record.args()),
+        Level::Warn => format!("Warning: {}", record.args()),
+        _ => record.args().to_string(),
+    }
+}
+
+/// Map `--verbose` / `--quiet` flags to the stderr log level
+///
+/// The default shows progress messages (info); each `-v` adds one level of
+/// detail and `--quiet` keeps only errors.
+pub fn level_from_flags(verbose: u8, quiet: bool) -> LevelFilter {
+    if quiet {
+        return LevelFilter::Error;
+    }
+    match verbose {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Install the command-line logger
+///
+/// `log_file` is created (or truncated) and receives every message at
+/// debug level or above, regardless of `level`.
+pub fn init(level: LevelFilter, log_file: Option<&Path>) -> Result<()> {
+    let file = log_file.map(File::create).transpose()?.map(Mutex::new);
+    let logger = CliLogger {
+        stderr_level: level,
+        file,
+    };
+    let max_level = level.max(logger.file_level());
+
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|e| crate::Error::Config(format!("Failed to install logger: {}", e)))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_from_flags() {
+        assert_eq!(level_from_flags(0, false), LevelFilter::Info);
+        assert_eq!(level_from_flags(1, false), LevelFilter::Debug);
+        assert_eq!(level_from_flags(3, false), LevelFilter::Trace);
+        assert_eq!(level_from_flags(2, true), LevelFilter::Error);
+    }
+
+    #[test]
+    fn test_format_stderr() {
+        let warning = format_stderr(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("md2docx::mermaid")
+                .args(format_args!("diagram simplified"))
+                .build(),
+            false,
+        );
+        assert_eq!(warning, "Warning: diagram simplified");
+
+        let verbose = format_stderr(
+            &Record::builder()
+                .level(Level::Debug)
+                .target("md2docx::mermaid")
+                .args(format_args!("rendering"))
+                .build(),
+            true,
+        );
+        assert!(verbose.ends_with("DEBUG md2docx::mermaid] rendering"));
+    }
+}
@@ -1,20 +1,49 @@
 //! md2docx CLI entry point
 
 #[cfg(feature = "cli")]
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+#[cfg(feature = "cli")]
+use md2docx::diagnostics::WarningCategory;
 use std::path::PathBuf;
 
+/// Exit status when a build succeeds but warnings are treated as errors
+#[cfg(feature = "cli")]
+const EXIT_WARNINGS_AS_ERRORS: i32 = 3;
+
+#[cfg(feature = "cli")]
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Error: build failed, or check/validation found problems
+  2  Invalid command-line arguments
+  3  Build written, but warnings were treated as errors (--strict, --warnings-as-errors)";
+
 #[cfg(feature = "cli")]
 #[derive(Parser)]
 #[command(name = "md2docx")]
 #[command(author, version, about = "Convert Markdown to DOCX", long_about = None)]
+#[command(after_help = EXIT_CODES_HELP)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Show more detail (-v for debug, -vv for trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only print errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also write a timestamped debug log to this file
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
 }
 
 #[cfg(feature = "cli")]
 #[derive(Subcommand)]
+// Parsed once per run, so the size of `Build` doesn't matter
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Build DOCX from markdown files
     Build {
@@ -22,14 +51,34 @@ enum Commands {
         #[arg(short, long)]
         input: Option<PathBuf>,
 
-        /// Input directory with chapter files
+        /// Translation of --input, rendered beside it as a bilingual parallel table
+        #[arg(long, requires = "input")]
+        translation: Option<PathBuf>,
+
+        /// Input directory with chapter files (repeat to build several projects)
         #[arg(short, long)]
-        dir: Option<PathBuf>,
+        dir: Vec<PathBuf>,
+
+        /// Build every project listed in the workspace file
+        #[arg(long)]
+        all: bool,
+
+        /// Workspace file used by --all
+        #[arg(long, default_value = md2docx::project::WORKSPACE_FILE)]
+        workspace: PathBuf,
+
+        /// Number of projects to build in parallel (0 = all cores)
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
 
         /// Output DOCX file
         #[arg(short, long)]
         output: Option<PathBuf>,
 
+        /// Directory for output files; relative output paths are placed inside it
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+
         /// Template DOCX file
         #[arg(long)]
         template: Option<PathBuf>,
@@ -41,84 +90,1190 @@ enum Commands {
         /// Math renderer: "rex" (default, pure Rust) or "omml" (Word native)
         #[arg(long, default_value = "rex")]
         math_renderer: String,
+
+        /// Build profile from md2docx.toml (e.g. "draft" for [profile.draft])
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Set a placeholder variable, overriding [document] values (repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+
+        /// Only include chapters whose frontmatter has one of these tags
+        #[arg(long, value_delimiter = ',', value_name = "TAG")]
+        only_tags: Vec<String>,
+
+        /// Keep images at full size, ignoring [images] max_dpi and max_width
+        #[arg(long)]
+        no_optimize: bool,
+
+        /// Byte-identical output for identical input (fixed timestamps, content-derived IDs)
+        #[arg(long)]
+        reproducible: bool,
+
+        /// Only use cached copies of {!include:https://...} files, never download
+        #[arg(long)]
+        offline: bool,
+
+        /// Result format printed to stdout
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Check the generated DOCX for structural problems after building
+        #[arg(long)]
+        validate: bool,
+
+        /// Also write a PDF next to each DOCX (needs the 'pdf' feature)
+        #[arg(long)]
+        pdf: bool,
+
+        /// Also write a standalone HTML page next to each DOCX
+        #[arg(long)]
+        html: bool,
+
+        /// Also write an OpenDocument text file (.odt) next to each DOCX
+        #[arg(long)]
+        odt: bool,
+
+        /// Also write an EPUB e-book next to each DOCX, one chapter per file
+        #[arg(long)]
+        epub: bool,
+
+        /// Treat every warning as an error (exit code 3)
+        #[arg(long)]
+        strict: bool,
+
+        /// Treat warnings in these categories as errors: missing-image, diagram,
+        /// math, unresolved-ref, font, other
+        #[arg(long, value_delimiter = ',', value_name = "CATEGORY")]
+        warnings_as_errors: Vec<WarningCategory>,
+    },
+
+    /// Check a project for problems without building it
+    Check {
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+    },
+
+    /// Show word counts and page estimates for a project
+    Stats {
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Print statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Convert a DOCX file back to markdown
+    Extract {
+        /// Input DOCX file
+        input: PathBuf,
+
+        /// Output markdown file (defaults to the input name with .md)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Folder for extracted images, relative to the output file
+        #[arg(long, default_value = "media")]
+        media_dir: String,
+    },
+
+    /// Check a DOCX file for problems that make Word report unreadable content
+    ValidateOutput {
+        /// DOCX file to check
+        input: PathBuf,
+    },
+
+    /// Combine several DOCX files into one document
+    Merge {
+        /// DOCX files to merge, in order
+        #[arg(required = true, num_args = 2..)]
+        inputs: Vec<PathBuf>,
+
+        /// Output DOCX file
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Re-apply a template's styles, numbering and headers/footers to a DOCX file
+    Restyle {
+        /// DOCX file to re-style
+        input: PathBuf,
+
+        /// Template directory (header-footer.docx, cover.docx, ...)
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Output DOCX file (defaults to the input name with -restyled)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show the changes between two versions of a document as tracked changes
+    Diff {
+        /// Earlier and later version: markdown files or project directories
+        /// (or their md2docx.toml), or one markdown file with --from
+        #[arg(required = true, num_args = 1..=2)]
+        inputs: Vec<PathBuf>,
+
+        /// Git revision of the earlier version of the file (needs the 'git' feature)
+        #[arg(long, value_name = "REV")]
+        from: Option<String>,
+
+        /// Git revision of the later version (defaults to the working tree)
+        #[arg(long, value_name = "REV", requires = "from")]
+        to: Option<String>,
+
+        /// Output DOCX file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Leave dates off the tracked changes, for byte-identical output
+        #[arg(long)]
+        reproducible: bool,
+    },
+
+    /// Run md2docx as an HTTP conversion service
+    Serve {
+        /// Serve the conversion API (POST /convert)
+        #[arg(long)]
+        api: bool,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: std::net::SocketAddr,
+
+        /// Largest accepted request, assets included, in MB
+        #[arg(long, default_value_t = 32)]
+        max_body: usize,
+
+        /// Conversions allowed to run at the same time
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+        max_concurrent: u16,
+
+        /// Seconds a request may wait and convert before it fails
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+
+    /// Create new project files
+    New {
+        #[command(subcommand)]
+        kind: NewKind,
+    },
+
+    /// Inspect the fonts available for embedding
+    Fonts {
+        #[command(subcommand)]
+        action: FontsAction,
+    },
+
+    /// List referenced images or shrink the images in a DOCX
+    Images {
+        #[command(subcommand)]
+        action: ImagesAction,
+    },
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand)]
+enum FontsAction {
+    /// List font files in the embed directory with their glyph coverage
+    List {
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+    },
+
+    /// Verify that fonts.default and fonts.code resolve to font files
+    Check {
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+    },
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand)]
+enum ImagesAction {
+    /// List images referenced by the project, flagging missing or oversized files
+    List {
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Size in KB above which an image is reported as oversized
+        #[arg(long, default_value_t = md2docx::project::DEFAULT_MAX_IMAGE_BYTES / 1024)]
+        max_size: u64,
+    },
+
+    /// Downscale embedded images to a target DPI at their displayed size
+    Optimize {
+        /// DOCX file to optimize
+        input: PathBuf,
+
+        /// Output DOCX file (defaults to overwriting the input)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Target resolution in dots per inch
+        #[arg(long, default_value_t = 150)]
+        dpi: u32,
+
+        /// JPEG quality (1-100) for re-encoded photos
+        #[arg(long, default_value_t = 85, value_parser = clap::value_parser!(u8).range(1..=100))]
+        quality: u8,
     },
 }
 
 #[cfg(feature = "cli")]
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable messages
+    Text,
+    /// One JSON document with output path, warnings, image count and timings
+    Json,
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand)]
+enum NewKind {
+    /// Create the next numbered chapter file
+    Chapter {
+        /// Chapter title
+        title: String,
+
+        /// Project directory
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Name used in the filename (derived from the title by default)
+        #[arg(long)]
+        slug: Option<String>,
+
+        /// Chapter number (defaults to the next free number)
+        #[arg(long)]
+        number: Option<u32>,
+    },
+}
+
+#[cfg(feature = "cli")]
+fn main() {
+    if let Err(e) = run() {
+        match e.downcast_ref::<md2docx::Error>() {
+            Some(error) => {
+                let source = error
+                    .location()
+                    .and_then(|location| std::fs::read_to_string(&location.file).ok());
+                eprintln!("{}", error.diagnostic().render(source.as_deref()));
+            }
+            None => eprintln!("error: {}", e),
+        }
+        std::process::exit(1);
+    }
+}
+
+#[cfg(feature = "cli")]
+fn run() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    md2docx::logging::init(
+        md2docx::logging::level_from_flags(cli.verbose, cli.quiet),
+        cli.log_file.as_deref(),
+    )?;
 
     match cli.command {
         Commands::Build {
             input,
-            dir,
+            translation,
+            mut dir,
+            all,
+            workspace,
+            jobs,
             output,
+            output_dir,
             template: _,
             toc,
             math_renderer,
+            profile,
+            vars,
+            only_tags,
+            no_optimize,
+            reproducible,
+            offline,
+            format,
+            validate,
+            pdf,
+            html,
+            odt,
+            epub,
+            strict,
+            warnings_as_errors,
         } => {
-            use md2docx::project::ProjectBuilder;
+            use md2docx::diagnostics::WarningPolicy;
+            use md2docx::project::{
+                build_all, BatchOptions, BuildReport, ProjectBuilder, StageTiming, Workspace,
+            };
             use md2docx::{
-                markdown_to_docx_with_templates, DocumentConfig, Language, PlaceholderContext,
+                markdown_to_docx_with_stages, DocumentConfig, Language, PlaceholderContext,
             };
+            use std::time::Instant;
 
-            if let Some(ref input_dir) = dir {
-                let mut builder = ProjectBuilder::from_directory(input_dir)?;
+            let json = format == OutputFormat::Json;
+            let policy = WarningPolicy {
+                strict,
+                as_errors: warnings_as_errors,
+            };
+            // Located warnings are shown with their source line after the build
+            if !json {
+                md2docx::diagnostics::log_located_warnings(false);
+            }
 
-                // Apply CLI overrides
-                if toc {
-                    builder = builder.with_toc(true);
+            if all {
+                dir.extend(Workspace::load_projects(&workspace)?);
+            }
+
+            if all || dir.len() > 1 {
+                if output.is_some() {
+                    log::error!("--output cannot be used when building several projects");
+                    std::process::exit(1);
+                }
+                if pdf || html || odt || epub {
+                    log::error!(
+                        "--pdf, --html, --odt and --epub cannot be used when building several projects"
+                    );
+                    std::process::exit(1);
+                }
+
+                let results = build_all(
+                    &dir,
+                    &BatchOptions {
+                        toc,
+                        jobs,
+                        profile: profile.clone(),
+                        vars: vars.clone(),
+                        only_tags: only_tags.clone(),
+                        no_optimize,
+                        reproducible,
+                        offline,
+                        output_dir: output_dir.clone(),
+                    },
+                )?;
+                let failures = results.iter().filter(|r| r.outcome.is_err()).count();
+
+                if json {
+                    let entries: Vec<_> = results
+                        .iter()
+                        .map(|r| {
+                            let mut entry = report_json(&r.outcome);
+                            entry["project"] = serde_json::json!(r.dir);
+                            entry
+                        })
+                        .collect();
+                    println!("{}", serde_json::to_string_pretty(&entries)?);
+                    if failures > 0 {
+                        std::process::exit(1);
+                    }
+                    if validate {
+                        validate_reports(&results)?;
+                    }
+                    enforce_warning_policy(
+                        &policy,
+                        results.iter().filter_map(|r| r.outcome.as_ref().ok()),
+                    );
+                    return Ok(());
+                }
+
+                for report in results.iter().filter_map(|r| r.outcome.as_ref().ok()) {
+                    print_diagnostics(&report.warnings);
+                }
+                println!("{:<30} {:<8} {:>8}  Output", "Project", "Status", "Time");
+                for result in &results {
+                    let (status, detail) = match &result.outcome {
+                        Ok(report) => ("ok", report.output.display().to_string()),
+                        Err(e) => ("FAILED", e.clone()),
+                    };
+                    println!(
+                        "{:<30} {:<8} {:>7.2}s  {}",
+                        result.dir.display(),
+                        status,
+                        result.duration.as_secs_f64(),
+                        detail
+                    );
+                }
+                println!("{} built, {} failed", results.len() - failures, failures);
+
+                if failures > 0 {
+                    std::process::exit(1);
                 }
-                if let Some(ref out) = output {
-                    builder = builder.with_output(out.clone());
+                if validate {
+                    validate_reports(&results)?;
                 }
+                enforce_warning_policy(
+                    &policy,
+                    results.iter().filter_map(|r| r.outcome.as_ref().ok()),
+                );
+            } else if let Some(input_dir) = dir.first() {
+                let builders =
+                    ProjectBuilder::targets_from_directory(input_dir, profile.as_deref())?;
+                if output.is_some() && builders.len() > 1 {
+                    log::error!(
+                        "--output cannot be used with a project that has {} [[output]] targets",
+                        builders.len()
+                    );
+                    std::process::exit(1);
+                }
+
+                let mut outcomes = Vec::new();
+                for mut builder in builders {
+                    // Apply CLI overrides
+                    if toc {
+                        builder = builder.with_toc(true);
+                    }
+                    if let Some(ref out) = output {
+                        builder = builder.with_output(out.clone());
+                    }
+                    if let Some(ref dir) = output_dir {
+                        builder = builder.with_output_dir(dir.clone());
+                    }
+                    for (key, value) in &vars {
+                        builder = builder.with_var(key, value);
+                    }
+                    if !only_tags.is_empty() {
+                        builder = builder.with_only_tags(&only_tags);
+                    }
+                    if no_optimize {
+                        builder = builder.without_image_limits();
+                    }
+                    if reproducible {
+                        builder = builder.reproducible();
+                    }
+                    if offline {
+                        builder = builder.offline();
+                    }
+
+                    // Other formats are rendered before the builder is consumed
+                    let pdf_bytes = if pdf {
+                        Some(build_project_pdf(&builder)?)
+                    } else {
+                        None
+                    };
+                    let html_page = if html {
+                        Some(builder.build_html()?)
+                    } else {
+                        None
+                    };
+                    let odt_bytes = if odt {
+                        Some(builder.build_odt()?)
+                    } else {
+                        None
+                    };
+                    let epub_bytes = if epub {
+                        Some(builder.build_epub()?)
+                    } else {
+                        None
+                    };
 
-                // Build and write
-                let output_path = builder.build_to_file()?;
-                println!("Successfully created: {}", output_path.display());
+                    // Build and write
+                    let outcome = if json {
+                        builder
+                            .build_to_file_with_report()
+                            .map_err(|e| e.to_string())
+                    } else {
+                        let report = builder.build_to_file_with_report()?;
+                        print_diagnostics(&report.warnings);
+                        log::info!("Successfully created: {}", report.output.display());
+                        Ok(report)
+                    };
+                    if let (Some(bytes), Ok(report)) = (&pdf_bytes, &outcome) {
+                        let pdf_path = report.output.with_extension("pdf");
+                        std::fs::write(&pdf_path, bytes)?;
+                        if !json {
+                            log::info!("Successfully created: {}", pdf_path.display());
+                        }
+                    }
+                    if let (Some(page), Ok(report)) = (&html_page, &outcome) {
+                        let html_path = report.output.with_extension("html");
+                        std::fs::write(&html_path, page)?;
+                        if !json {
+                            log::info!("Successfully created: {}", html_path.display());
+                        }
+                    }
+                    if let (Some(bytes), Ok(report)) = (&odt_bytes, &outcome) {
+                        let odt_path = report.output.with_extension("odt");
+                        std::fs::write(&odt_path, bytes)?;
+                        if !json {
+                            log::info!("Successfully created: {}", odt_path.display());
+                        }
+                    }
+                    if let (Some(bytes), Ok(report)) = (&epub_bytes, &outcome) {
+                        let epub_path = report.output.with_extension("epub");
+                        std::fs::write(&epub_path, bytes)?;
+                        if !json {
+                            log::info!("Successfully created: {}", epub_path.display());
+                        }
+                    }
+                    outcomes.push(outcome);
+                }
+                if json {
+                    print_reports(&outcomes)?;
+                }
+
+                let reports: Vec<_> = outcomes.into_iter().flatten().collect();
+                let mut clean = true;
+                if validate {
+                    for report in &reports {
+                        clean &= validate_output(&report.output)?;
+                    }
+                }
+                if !clean {
+                    std::process::exit(1);
+                }
+                enforce_warning_policy(&policy, &reports);
             } else if let Some(ref input_file) = input {
+                if profile.is_some() {
+                    log::error!("--profile requires a project directory (--dir)");
+                    std::process::exit(1);
+                }
+                if !only_tags.is_empty() {
+                    log::error!("--only-tags requires a project directory (--dir)");
+                    std::process::exit(1);
+                }
+
                 // Simple single file conversion
-                println!("Reading input file: {}", input_file.display());
+                log::info!("Reading input file: {}", input_file.display());
+                let mut timings = Vec::new();
+                let start = Instant::now();
                 let raw_content = std::fs::read_to_string(input_file)?;
 
                 // Rewrite relative image paths
-                let content = resolve_image_paths(&raw_content, input_file);
+                let mut content = resolve_image_paths(&raw_content, input_file);
+
+                // Pair the input with its translation block by block
+                if let Some(ref translation_file) = translation {
+                    let translation = std::fs::read_to_string(translation_file)?;
+                    let translation = resolve_image_paths(&translation, translation_file);
+                    content = md2docx::parser::pair_translation(&content, &translation);
+                }
+
+                // Language from frontmatter `language:`, default to English
+                let lang = md2docx::parser::parse_frontmatter(&raw_content)
+                    .0
+                    .and_then(|fm| fm.language)
+                    .and_then(|l| Language::from_code(&l))
+                    .unwrap_or_default();
 
                 // For single file, we use default config but can enable TOC if requested
                 let mut doc_config = DocumentConfig::default();
                 if toc {
                     doc_config.toc.enabled = true;
                 }
+                doc_config.toc.title = lang.toc_title().to_string();
                 doc_config.math_renderer = math_renderer.clone();
+                doc_config.reproducible = reproducible;
+                let mut placeholders = PlaceholderContext::default();
+                for (key, value) in &vars {
+                    placeholders.set(key, value);
+                }
+                timings.push(StageTiming::new("load", start.elapsed()));
 
-                let docx_bytes = markdown_to_docx_with_templates(
-                    &content,
-                    Language::English,
-                    &doc_config,
-                    None,
-                    &PlaceholderContext::default(),
-                )?;
+                let mut start = Instant::now();
+                let (docx_bytes, warnings) = md2docx::diagnostics::capture(|| {
+                    markdown_to_docx_with_stages(
+                        &content,
+                        lang,
+                        &doc_config,
+                        None,
+                        &placeholders,
+                        &mut |stage| {
+                            timings.push(StageTiming::new(stage, start.elapsed()));
+                            start = Instant::now();
+                        },
+                    )
+                });
+                let docx_bytes = docx_bytes?;
 
-                let final_output = if let Some(ref out) = output {
-                    out.clone()
-                } else {
-                    let mut out = input_file.clone();
-                    out.set_extension("docx");
-                    out
+                let final_output = match (&output, &output_dir) {
+                    (Some(out), Some(dir)) => dir.join(out),
+                    (Some(out), None) => out.clone(),
+                    (None, Some(dir)) => dir
+                        .join(input_file.file_name().unwrap_or_default())
+                        .with_extension("docx"),
+                    (None, None) => input_file.with_extension("docx"),
                 };
 
-                std::fs::write(&final_output, docx_bytes)?;
-                println!("Successfully created: {}", final_output.display());
+                let start = Instant::now();
+                if let Some(parent) = final_output.parent() {
+                    if !parent.exists() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                }
+                std::fs::write(&final_output, &docx_bytes)?;
+                timings.push(StageTiming::new("write", start.elapsed()));
+                if pdf {
+                    let pdf_path = final_output.with_extension("pdf");
+                    std::fs::write(&pdf_path, render_pdf(&content, lang, &doc_config)?)?;
+                    if !json {
+                        log::info!("Successfully created: {}", pdf_path.display());
+                    }
+                }
+                if html {
+                    let html_path = final_output.with_extension("html");
+                    let page = md2docx::markdown_to_html(&content, lang, &doc_config)?;
+                    std::fs::write(&html_path, page)?;
+                    if !json {
+                        log::info!("Successfully created: {}", html_path.display());
+                    }
+                }
+                if odt {
+                    let odt_path = final_output.with_extension("odt");
+                    let bytes = md2docx::markdown_to_odt(&content, lang, &doc_config)?;
+                    std::fs::write(&odt_path, bytes)?;
+                    if !json {
+                        log::info!("Successfully created: {}", odt_path.display());
+                    }
+                }
+                if epub {
+                    let epub_path = final_output.with_extension("epub");
+                    let bytes = md2docx::markdown_to_epub(&[content.as_str()], lang, &doc_config)?;
+                    std::fs::write(&epub_path, bytes)?;
+                    if !json {
+                        log::info!("Successfully created: {}", epub_path.display());
+                    }
+                }
+
+                let report = BuildReport::new(final_output, &docx_bytes, warnings, timings);
+                if json {
+                    print_reports(&[Ok(report.clone())])?;
+                } else {
+                    print_diagnostics(&report.warnings);
+                    log::info!("Successfully created: {}", report.output.display());
+                }
+                if validate && !validate_output(&report.output)? {
+                    std::process::exit(1);
+                }
+                enforce_warning_policy(&policy, [&report]);
+            } else {
+                log::error!("Either --input or --dir must be specified");
+                std::process::exit(1);
+            }
+        }
+        Commands::Check { dir } => {
+            use md2docx::project::{check_project, load_project_config};
+
+            let config = load_project_config(&dir)?;
+            let issues = check_project(&dir, &config)?;
+            for issue in &issues {
+                println!("{}", issue);
+            }
+
+            if issues.is_empty() {
+                log::info!("No problems found");
+            } else {
+                log::error!("Found {} problem(s)", issues.len());
+                std::process::exit(1);
+            }
+        }
+        Commands::Stats { dir, json } => {
+            use md2docx::project::{load_project_config, project_stats};
+
+            let config = load_project_config(&dir)?;
+            let stats = project_stats(&dir, &config)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
             } else {
-                eprintln!("Error: Either --input or --dir must be specified");
+                println!(
+                    "{:<40} {:>8} {:>6} {:>6} {:>6} {:>7} {:>7}",
+                    "Chapter", "Words", "Figs", "Tables", "Eqs", "Pages", "Minutes"
+                );
+                for file in stats.files.iter().chain(std::iter::once(&stats.total)) {
+                    let name = if std::ptr::eq(file, &stats.total) {
+                        "Total".to_string()
+                    } else {
+                        file.path
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default()
+                    };
+                    println!(
+                        "{:<40} {:>8} {:>6} {:>6} {:>6} {:>7.1} {:>7.1}",
+                        name,
+                        file.words,
+                        file.figures,
+                        file.tables,
+                        file.equations,
+                        file.pages,
+                        file.reading_minutes
+                    );
+                }
+            }
+        }
+        Commands::Extract {
+            input,
+            output,
+            media_dir,
+        } => {
+            use md2docx::reverse::{extract_to_file, ExtractOptions};
+
+            let output = output.unwrap_or_else(|| input.with_extension("md"));
+            let path = extract_to_file(&input, &output, &ExtractOptions { media_dir })?;
+            log::info!("Successfully created: {}", path.display());
+        }
+        Commands::ValidateOutput { input } => {
+            if !validate_output(&input)? {
+                std::process::exit(1);
+            }
+        }
+        Commands::Merge { inputs, output } => {
+            let documents = inputs
+                .iter()
+                .map(std::fs::read)
+                .collect::<Result<Vec<_>, _>>()?;
+            let merged = md2docx::docx::merge_docx(&documents)?;
+            std::fs::write(&output, merged)?;
+            log::info!("Successfully created: {}", output.display());
+        }
+        Commands::Restyle {
+            input,
+            template,
+            output,
+        } => {
+            let template = md2docx::TemplateDir::load(&template)?;
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+                input.with_file_name(format!("{}-restyled.docx", stem))
+            });
+            let restyled = md2docx::docx::restyle_docx(&std::fs::read(&input)?, &template)?;
+            std::fs::write(&output, restyled)?;
+            log::info!("Successfully created: {}", output.display());
+        }
+        Commands::Diff {
+            inputs,
+            from,
+            to,
+            output,
+            reproducible,
+        } => {
+            use md2docx::diff::{compare, Version};
+
+            let (old, mut new) = match (inputs.as_slice(), &from) {
+                ([old, new], None) => (Version::from_path(old)?, Version::from_path(new)?),
+                ([dir], Some(_)) if dir.is_dir() => {
+                    log::error!(
+                        "--from compares one markdown file; to compare projects, give two project directories"
+                    );
+                    std::process::exit(1);
+                }
+                ([file], Some(from)) => {
+                    let new = match &to {
+                        Some(to) => version_at(file, to)?,
+                        None => Version::from_file(file)?,
+                    };
+                    (version_at(file, from)?, new)
+                }
+                _ => {
+                    log::error!(
+                        "Give two markdown files or project directories, or one file with --from <REV>"
+                    );
+                    std::process::exit(1);
+                }
+            };
+            // Projects bring their own TOC title from md2docx.toml
+            if new.lang.is_none() {
+                new.config.toc.title = new.language().toc_title().to_string();
+            }
+            new.config.reproducible = reproducible;
+
+            std::fs::write(&output, compare(&old, &new)?)?;
+            log::info!("Successfully created: {}", output.display());
+        }
+        Commands::Serve {
+            api,
+            addr,
+            max_body,
+            max_concurrent,
+            timeout,
+        } => {
+            if !api {
+                log::error!("Nothing to serve: pass --api to run the conversion service");
                 std::process::exit(1);
             }
+            serve(ServeOptions {
+                addr,
+                max_body_bytes: max_body * 1024 * 1024,
+                max_concurrent: usize::from(max_concurrent),
+                timeout: std::time::Duration::from_secs(timeout),
+            })?;
         }
+        Commands::New { kind } => match kind {
+            NewKind::Chapter {
+                title,
+                dir,
+                slug,
+                number,
+            } => {
+                use md2docx::project::{load_project_config, new_chapter, NewChapter};
+
+                let config = load_project_config(&dir)?;
+                let path = new_chapter(
+                    &dir,
+                    &config,
+                    &NewChapter {
+                        title,
+                        slug,
+                        number,
+                    },
+                )?;
+                log::info!("Created chapter: {}", path.display());
+            }
+        },
+        Commands::Fonts { action } => {
+            use md2docx::project::{inspect_fonts, load_project_config};
+
+            let dir = match &action {
+                FontsAction::List { dir } | FontsAction::Check { dir } => dir,
+            };
+            let config = load_project_config(dir)?;
+            let report = inspect_fonts(dir, &config)?;
+
+            match action {
+                FontsAction::List { .. } => {
+                    if report.files.is_empty() {
+                        log::info!("No font files in {}", report.dir.display());
+                        return Ok(());
+                    }
+                    println!(
+                        "{:<36} {:<24} {:<11} {:<6} {:>6} {:>6} {:>8}",
+                        "File", "Family", "Variant", "Embed", "Thai", "Latin", "Symbols"
+                    );
+                    for file in &report.files {
+                        let name = file
+                            .path
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        let variant = file
+                            .variant
+                            .map(|v| format!("{:?}", v))
+                            .unwrap_or_else(|| "unsupported".to_string());
+                        let embed = match file.embeddable {
+                            Some(true) => "yes",
+                            Some(false) => "no",
+                            None => "?",
+                        };
+                        let coverage: Vec<String> = file
+                            .coverage
+                            .iter()
+                            .map(|c| format!("{:.0}%", c.percent()))
+                            .collect();
+                        println!(
+                            "{:<36} {:<24} {:<11} {:<6} {:>6} {:>6} {:>8}",
+                            name,
+                            file.family,
+                            variant,
+                            embed,
+                            coverage.first().map_or("-", String::as_str),
+                            coverage.get(1).map_or("-", String::as_str),
+                            coverage.get(2).map_or("-", String::as_str)
+                        );
+                    }
+                }
+                FontsAction::Check { .. } => {
+                    if report.configured.is_empty() {
+                        log::info!("No fonts configured (fonts.default / fonts.code)");
+                        return Ok(());
+                    }
+                    for font in &report.configured {
+                        if font.files.is_empty() {
+                            println!(
+                                "fonts.{} = \"{}\": not found in {}",
+                                font.role,
+                                font.name,
+                                report.dir.display()
+                            );
+                        } else {
+                            let files: Vec<String> =
+                                font.files.iter().map(|p| p.display().to_string()).collect();
+                            println!(
+                                "fonts.{} = \"{}\": {}",
+                                font.role,
+                                font.name,
+                                files.join(", ")
+                            );
+                        }
+                    }
+                    let missing = report.missing().count();
+                    if missing > 0 {
+                        log::error!("{} configured font(s) not found", missing);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Commands::Images { action } => match action {
+            ImagesAction::List { dir, max_size } => {
+                use md2docx::project::{list_images, load_project_config, ImageStatus};
+
+                let config = load_project_config(&dir)?;
+                let images = list_images(&dir, &config, max_size * 1024)?;
+                if images.is_empty() {
+                    log::info!("No images referenced in {}", dir.display());
+                    return Ok(());
+                }
+                println!(
+                    "{:<32} {:<40} {:>9} {:>11} {:<9}",
+                    "Location", "Image", "Size", "Pixels", "Status"
+                );
+                let base = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+                for image in &images {
+                    let file = image.file.strip_prefix(&base).unwrap_or(&image.file);
+                    let location = format!("{}:{}", file.display(), image.line);
+                    let path = image
+                        .resolved
+                        .as_ref()
+                        .map(|p| p.strip_prefix(&base).unwrap_or(p).display().to_string())
+                        .unwrap_or_else(|| image.src.clone());
+                    let size = image
+                        .size_bytes
+                        .map(|b| format!("{} KB", b.div_ceil(1024)))
+                        .unwrap_or_else(|| "-".to_string());
+                    let pixels = image
+                        .dimensions
+                        .map(|d| format!("{}x{}", d.width, d.height))
+                        .unwrap_or_else(|| "-".to_string());
+                    println!(
+                        "{:<32} {:<40} {:>9} {:>11} {:<9}",
+                        location, path, size, pixels, image.status
+                    );
+                }
+
+                let count = |status| images.iter().filter(|i| i.status == status).count();
+                let (missing, oversized) =
+                    (count(ImageStatus::Missing), count(ImageStatus::Oversized));
+                if missing + oversized > 0 {
+                    log::error!(
+                        "{} missing, {} oversized (limit {} KB)",
+                        missing,
+                        oversized,
+                        max_size
+                    );
+                    std::process::exit(1);
+                }
+            }
+            ImagesAction::Optimize {
+                input,
+                output,
+                dpi,
+                quality,
+            } => {
+                use md2docx::docx::{optimize_images, OptimizeOptions};
+
+                let docx = std::fs::read(&input)?;
+                let (optimized, stats) = optimize_images(
+                    &docx,
+                    &OptimizeOptions {
+                        dpi,
+                        jpeg_quality: quality,
+                    },
+                )?;
+                let output = output.unwrap_or(input);
+                std::fs::write(&output, &optimized)?;
+                log::info!(
+                    "Resized {} image(s): {} KB -> {} KB ({} KB -> {} KB total)",
+                    stats.resized,
+                    stats.bytes_before.div_ceil(1024),
+                    stats.bytes_after.div_ceil(1024),
+                    (docx.len() as u64).div_ceil(1024),
+                    (optimized.len() as u64).div_ceil(1024)
+                );
+                log::info!("Successfully created: {}", output.display());
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// JSON object for one build: the report fields plus `status`, or the error
+#[cfg(feature = "cli")]
+fn report_json(outcome: &Result<md2docx::project::BuildReport, String>) -> serde_json::Value {
+    match outcome {
+        Ok(report) => {
+            let mut value = serde_json::to_value(report).unwrap_or_default();
+            value["status"] = "ok".into();
+            value
+        }
+        Err(e) => serde_json::json!({ "status": "failed", "error": e }),
     }
+}
 
+/// Print build results as JSON, exiting with status 1 on failure
+///
+/// A single result is printed as an object, several (one per `[[output]]`
+/// target) as an array.
+#[cfg(feature = "cli")]
+fn print_reports(
+    outcomes: &[Result<md2docx::project::BuildReport, String>],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let value = match outcomes {
+        [outcome] => report_json(outcome),
+        _ => outcomes.iter().map(report_json).collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&value)?);
+    if outcomes.iter().any(|o| o.is_err()) {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
+/// Print located warnings to stderr with the source line they point at
+#[cfg(feature = "cli")]
+fn print_diagnostics(warnings: &[md2docx::diagnostics::Warning]) {
+    for warning in warnings {
+        if let Some(location) = &warning.location {
+            let source = std::fs::read_to_string(&location.file).ok();
+            let diagnostic = md2docx::Diagnostic::from(warning);
+            eprintln!("{}\n", diagnostic.render(source.as_deref()));
+        }
+    }
+}
+
+/// Check a generated DOCX, printing any problems; returns whether it is clean
+#[cfg(feature = "cli")]
+fn validate_output(path: &std::path::Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let issues = md2docx::docx::validate_docx(&std::fs::read(path)?)?;
+    for issue in &issues {
+        println!("{}: {}", path.display(), issue);
+    }
+    if issues.is_empty() {
+        log::info!("{}: no problems found", path.display());
+    } else {
+        log::error!("{}: found {} problem(s)", path.display(), issues.len());
+    }
+    Ok(issues.is_empty())
+}
+
+/// Validate every successfully built project, exiting with status 1 on problems
+#[cfg(feature = "cli")]
+fn validate_reports(
+    results: &[md2docx::project::BatchResult],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut clean = true;
+    for report in results.iter().filter_map(|r| r.outcome.as_ref().ok()) {
+        clean &= validate_output(&report.output)?;
+    }
+    if !clean {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Exit with [`EXIT_WARNINGS_AS_ERRORS`] if any build reported a warning the policy rejects
+#[cfg(feature = "cli")]
+fn enforce_warning_policy<'a>(
+    policy: &md2docx::diagnostics::WarningPolicy,
+    reports: impl IntoIterator<Item = &'a md2docx::project::BuildReport>,
+) {
+    let mut errors = 0;
+    for report in reports {
+        for warning in policy.errors(&report.warnings) {
+            log::error!("{}: {}", report.output.display(), warning);
+            errors += 1;
+        }
+    }
+    if errors > 0 {
+        log::error!("{} warning(s) treated as errors", errors);
+        std::process::exit(EXIT_WARNINGS_AS_ERRORS);
+    }
+}
+
+/// A markdown file as it was at a git revision
+#[cfg(all(feature = "cli", feature = "git"))]
+fn version_at(path: &std::path::Path, rev: &str) -> md2docx::Result<md2docx::diff::Version> {
+    md2docx::diff::Version::at_revision(path, rev)
+}
+
+#[cfg(all(feature = "cli", not(feature = "git")))]
+fn version_at(_path: &std::path::Path, _rev: &str) -> md2docx::Result<md2docx::diff::Version> {
+    Err(md2docx::Error::Git(
+        "comparing revisions needs md2docx built with the 'git' feature".to_string(),
+    ))
+}
+
+#[cfg(all(feature = "cli", feature = "pdf"))]
+fn render_pdf(
+    markdown: &str,
+    lang: md2docx::Language,
+    config: &md2docx::DocumentConfig,
+) -> md2docx::Result<Vec<u8>> {
+    md2docx::markdown_to_pdf(markdown, lang, config)
+}
+
+#[cfg(all(feature = "cli", feature = "pdf"))]
+fn build_project_pdf(builder: &md2docx::project::ProjectBuilder) -> md2docx::Result<Vec<u8>> {
+    builder.build_pdf()
+}
+
+#[cfg(all(feature = "cli", not(feature = "pdf")))]
+fn render_pdf(
+    _markdown: &str,
+    _lang: md2docx::Language,
+    _config: &md2docx::DocumentConfig,
+) -> md2docx::Result<Vec<u8>> {
+    Err(pdf_unavailable())
+}
+
+#[cfg(all(feature = "cli", not(feature = "pdf")))]
+fn build_project_pdf(_builder: &md2docx::project::ProjectBuilder) -> md2docx::Result<Vec<u8>> {
+    Err(pdf_unavailable())
+}
+
+#[cfg(all(feature = "cli", not(feature = "pdf")))]
+fn pdf_unavailable() -> md2docx::Error {
+    md2docx::Error::Config("PDF output needs md2docx built with the 'pdf' feature".to_string())
+}
+
+/// Settings of `md2docx serve`
+#[cfg(feature = "cli")]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+struct ServeOptions {
+    addr: std::net::SocketAddr,
+    max_body_bytes: usize,
+    max_concurrent: usize,
+    timeout: std::time::Duration,
+}
+
+#[cfg(all(feature = "cli", feature = "server"))]
+fn serve(options: ServeOptions) -> md2docx::Result<()> {
+    md2docx::server::run(md2docx::server::ServerConfig {
+        addr: options.addr,
+        max_body_bytes: options.max_body_bytes,
+        max_concurrent: options.max_concurrent,
+        timeout: options.timeout,
+    })
+}
+
+#[cfg(all(feature = "cli", not(feature = "server")))]
+fn serve(_options: ServeOptions) -> md2docx::Result<()> {
+    Err(md2docx::Error::Config(
+        "the HTTP service needs md2docx built with the 'server' feature".to_string(),
+    ))
+}
+
+/// Parse a `--var` argument of the form `key=value`
+#[cfg(feature = "cli")]
+fn parse_var(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
+}
+
 /// Rewrite image paths in markdown content to be relative to the markdown file's directory.
 fn resolve_image_paths(content: &str, file_path: &std::path::Path) -> String {
     md2docx::project::resolve_image_paths(content, file_path)
@@ -11,13 +11,42 @@
 pub mod config;
 pub use config::MermaidConfig;
 
+use crate::diagnostics::WarningCategory;
 use crate::error::Error;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Mutex;
 
 /// Padding factor for SVG canvas (1.0 = no extra padding)
 const SVG_PADDING_FACTOR: f64 = 1.0;
 
+/// Family of the embedded font diagram text falls back to in browser builds
+#[cfg(all(feature = "mermaid-png", target_arch = "wasm32"))]
+const FALLBACK_FONT_FAMILY: &str = "XITS Math";
+
+/// Fonts for turning diagram text into paths and for PDF output, loaded
+/// once per process
+///
+/// Browser builds cannot scan system fonts, so there every generic family
+/// (`sans-serif`, `monospace`, ...) maps to the embedded XITS font instead.
+#[cfg(feature = "mermaid-png")]
+pub(crate) static FONT_DB: Lazy<std::sync::Arc<usvg::fontdb::Database>> = Lazy::new(|| {
+    let mut font_db = usvg::fontdb::Database::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    font_db.load_system_fonts();
+    #[cfg(target_arch = "wasm32")]
+    {
+        font_db.load_font_data(crate::docx::math_rex::MATH_FONT_DATA.to_vec());
+        font_db.set_serif_family(FALLBACK_FONT_FAMILY);
+        font_db.set_sans_serif_family(FALLBACK_FONT_FAMILY);
+        font_db.set_monospace_family(FALLBACK_FONT_FAMILY);
+        font_db.set_cursive_family(FALLBACK_FONT_FAMILY);
+        font_db.set_fantasy_family(FALLBACK_FONT_FAMILY);
+    }
+    std::sync::Arc::new(font_db)
+});
+
 use std::panic;
 
 /// Static regex for pipe-separated edge labels: -->|label| or --|label|->, etc.
@@ -56,6 +85,18 @@ static HEIGHT_RE: Lazy<Regex> =
 /// # Errors
 /// Returns Error if rendering fails even after fallback
 pub fn render_to_svg(content: &str) -> Result<String, Error> {
+    let (svg, simplified) = match take_prerendered(content, None) {
+        Some(diagram) => (String::from_utf8(diagram.data)?, diagram.simplified),
+        None => render_svg(content)?,
+    };
+    if simplified {
+        warn_simplified();
+    }
+    Ok(svg)
+}
+
+/// Render to SVG, returning whether edge labels had to be stripped
+fn render_svg(content: &str) -> Result<(String, bool), Error> {
     // v0.2.0 of mermaid-rs-renderer supports all 23 diagram types natively:
     // flowchart, sequence, class, state, ER, pie, gantt, journey, timeline,
     // mindmap, gitGraph, xychart, quadrant, sankey, kanban, C4, block,
@@ -63,13 +104,13 @@ pub fn render_to_svg(content: &str) -> Result<String, Error> {
 
     // Try normal rendering first
     match try_render_to_svg(content) {
-        Ok(svg) => Ok(svg),
+        Ok(svg) => Ok((svg, false)),
         Err(e) => {
+            log::debug!("Mermaid rendering failed: {}", e);
             // If normal rendering fails, try stripping edge labels
             let simplified = strip_edge_labels(content);
             if simplified != content {
-                eprintln!("Warning: Mermaid diagram contains unsupported features (edge labels). Rendering simplified version without labels.");
-                try_render_to_svg(&simplified)
+                try_render_to_svg(&simplified).map(|svg| (svg, true))
             } else {
                 Err(e)
             }
@@ -77,6 +118,61 @@ pub fn render_to_svg(content: &str) -> Result<String, Error> {
     }
 }
 
+fn warn_simplified() {
+    crate::diagnostics::warn(
+        WarningCategory::Diagram,
+        "Mermaid diagram contains unsupported features (edge labels). Rendering simplified version without labels.",
+    );
+}
+
+/// A diagram rendered ahead of the document build
+struct Prerendered {
+    /// PNG bytes, or SVG text for SVG output
+    data: Vec<u8>,
+    simplified: bool,
+}
+
+/// Diagram source and PNG scale bits (`None` for SVG output)
+type PrerenderKey = (String, Option<u32>);
+
+/// Diagrams rendered by [`prerender`]. Each entry is taken by the render
+/// call that needs it, so finished images are not kept after the build.
+static PRERENDERED: Lazy<Mutex<HashMap<PrerenderKey, Prerendered>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn take_prerendered(content: &str, scale: Option<f32>) -> Option<Prerendered> {
+    let key = (content.to_string(), scale.map(f32::to_bits));
+    PRERENDERED.lock().ok()?.remove(&key)
+}
+
+/// Render a diagram ahead of time
+///
+/// A later [`render_to_png`] (with `png_scale`) or [`render_to_svg`] (with
+/// `None`) call for the same source returns the finished image at once.
+/// Failures are not stored: the later call renders again and reports them.
+#[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+pub(crate) fn prerender(content: &str, png_scale: Option<f32>) {
+    let Ok((svg, simplified)) = render_svg(content) else {
+        return;
+    };
+    let data = match png_scale {
+        #[cfg(feature = "mermaid-png")]
+        Some(scale) => match svg_to_png(&svg, scale) {
+            Ok(png) => png,
+            Err(_) => return,
+        },
+        #[cfg(not(feature = "mermaid-png"))]
+        Some(_) => return,
+        None => svg.into_bytes(),
+    };
+    if let Ok(mut prerendered) = PRERENDERED.lock() {
+        prerendered.insert(
+            (content.to_string(), png_scale.map(f32::to_bits)),
+            Prerendered { data, simplified },
+        );
+    }
+}
+
 /// Sanitize SVG output from mermaid-rs-renderer for usvg compatibility.
 ///
 /// mermaid-rs-renderer v0.2.0 may produce `font-family` attributes with unescaped
@@ -197,15 +293,13 @@ fn strip_edge_labels(content: &str) -> String {
 /// This ensures the SVG renders identically everywhere regardless of font availability.
 #[cfg(feature = "mermaid-png")]
 fn convert_text_to_paths(svg: &str) -> Result<String, Error> {
-    use usvg::{fontdb, Options, Tree, WriteOptions};
+    use usvg::{Options, Tree, WriteOptions};
 
-    // Create options with font database
-    let mut opt = Options::default();
-
-    // Load system fonts for text-to-path conversion
-    let mut font_db = fontdb::Database::new();
-    font_db.load_system_fonts();
-    opt.fontdb = std::sync::Arc::new(font_db);
+    // Create options with the font database for text-to-path conversion
+    let opt = Options {
+        fontdb: FONT_DB.clone(),
+        ..Options::default()
+    };
 
     // Parse SVG
     let tree =
@@ -299,8 +393,16 @@ fn format_dimension(value: f64, original: &str) -> String {
 /// Returns Error if rendering fails
 #[cfg(feature = "mermaid-png")]
 pub fn render_to_png(content: &str, scale: f32) -> Result<Vec<u8>, Error> {
+    if let Some(diagram) = take_prerendered(content, Some(scale)) {
+        if diagram.simplified {
+            warn_simplified();
+        }
+        return Ok(diagram.data);
+    }
+
     // First get the SVG with padding and text converted to paths
     let svg = render_to_svg(content)?;
+    log::debug!("Converting mermaid SVG to PNG at {}x scale", scale);
 
     // Convert SVG to PNG
     svg_to_png(&svg, scale)
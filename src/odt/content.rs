@@ -0,0 +1,829 @@
+//! `content.xml`: the document body
+//!
+//! Formatting set directly on paragraphs, runs, tables and cells becomes
+//! automatic styles derived from the named ones, shared by every element
+//! with the same formatting. Fields show their result, as in DOCX until
+//! they are updated. OMML equations and raw WordprocessingML (cover
+//! templates) have no ODF equivalent and are left out with a warning.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::diagnostics::{self, WarningCategory};
+use crate::docx::builder::{BuildResult, ImageInfo};
+use crate::docx::ooxml::xml_writer::XmlWriter;
+use crate::docx::ooxml::{DocElement, ImageElement, ParagraphChild, Table};
+use crate::{Paragraph, Result, Run};
+
+use super::styles::{BULLET_LIST, NUMBER_LIST};
+use super::{emu_cm, half_pt, pt, set_font, text_align, write_style, Props, NAMESPACES};
+
+/// An image file to store in the package
+pub(super) struct Picture {
+    pub path: String,
+    pub media_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// `content.xml` and the images it refers to
+pub(super) struct Content {
+    pub xml: Vec<u8>,
+    pub pictures: Vec<Picture>,
+}
+
+/// A style generated for directly formatted elements
+struct AutoStyle {
+    name: String,
+    family: &'static str,
+    parent: Option<String>,
+    /// Restart page numbering on the standard master page
+    page_number: Option<u32>,
+    props: Props,
+}
+
+pub(super) fn write(build: &BuildResult) -> Result<Content> {
+    let elements = &build.document.elements;
+
+    // A section's page number restart is set on its last paragraph in DOCX
+    // and on its first one in ODF
+    let mut restarts = HashMap::new();
+    let mut section_start = 0;
+    for (index, element) in elements.iter().enumerate() {
+        if let DocElement::Paragraph(p) = element {
+            if p.section_break.is_some() {
+                if let Some(start) = p.page_num_start {
+                    restarts.insert(section_start, start);
+                }
+                section_start = index + 1;
+            }
+        }
+    }
+    if let Some(start) = build.document.page_num_start {
+        restarts.insert(section_start, start);
+    }
+
+    let mut body = Body::new(build);
+    for (index, element) in elements.iter().enumerate() {
+        body.restart = restarts.get(&index).copied();
+        body.element(element)?;
+    }
+    body.close_lists()?;
+
+    if !body.skipped.is_empty() {
+        let skipped: Vec<_> = body.skipped.iter().copied().collect();
+        diagnostics::warn(
+            WarningCategory::Other,
+            format!("ODT output leaves out {}", skipped.join(", ")),
+        );
+    }
+
+    let mut xml = XmlWriter::new(Vec::new());
+    xml.declaration()?;
+    xml.start("office:document-content", NAMESPACES)?;
+    xml.start("office:automatic-styles", &[])?;
+    for style in &body.styles {
+        let page_number = style.page_number.map(|n| n.to_string());
+        let mut attrs = vec![
+            ("style:name", style.name.as_str()),
+            ("style:family", style.family),
+        ];
+        if let Some(parent) = &style.parent {
+            attrs.push(("style:parent-style-name", parent.as_str()));
+        }
+        if page_number.is_some() {
+            attrs.push(("style:master-page-name", "Standard"));
+        }
+        let mut props = style.props.clone();
+        if let Some(n) = page_number {
+            props.paragraph("style:page-number", n);
+        }
+        write_style(&mut xml, &attrs, &props)?;
+    }
+    xml.end("office:automatic-styles")?;
+    xml.start("office:body", &[])?;
+    xml.start("office:text", &[])?;
+    let text =
+        String::from_utf8(body.xml.into_inner()).map_err(|e| crate::Error::Utf8(e.to_string()))?;
+    xml.raw(&text)?;
+    xml.end("office:text")?;
+    xml.end("office:body")?;
+    xml.end("office:document-content")?;
+
+    Ok(Content {
+        xml: xml.into_inner(),
+        pictures: body.pictures,
+    })
+}
+
+/// Writes the body elements, collecting automatic styles and images
+struct Body<'a> {
+    xml: XmlWriter<Vec<u8>>,
+    build: &'a BuildResult,
+    styles: Vec<AutoStyle>,
+    style_names: HashMap<String, String>,
+    pictures: Vec<Picture>,
+    /// Numbering ID of the open list and how many levels are open
+    list: Option<u32>,
+    list_depth: usize,
+    /// Numbering ID of the list closed last, continued if it comes back
+    last_list: Option<u32>,
+    /// Open fields; `true` once past the instruction, into the result
+    fields: Vec<bool>,
+    /// Whether the last character written was a space (or the paragraph
+    /// start), after which ODF would collapse another space
+    after_space: bool,
+    /// Name of the bookmark started on the current paragraph
+    bookmarks: HashMap<u32, String>,
+    /// Alignment of the table cell being written
+    cell_align: Option<String>,
+    /// Page number to restart at on the next top-level paragraph
+    restart: Option<u32>,
+    tables: usize,
+    notes: usize,
+    skipped: BTreeSet<&'static str>,
+}
+
+impl<'a> Body<'a> {
+    fn new(build: &'a BuildResult) -> Self {
+        Self {
+            xml: XmlWriter::new(Vec::new()),
+            build,
+            styles: Vec::new(),
+            style_names: HashMap::new(),
+            pictures: Vec::new(),
+            list: None,
+            list_depth: 0,
+            last_list: None,
+            fields: Vec::new(),
+            after_space: true,
+            bookmarks: HashMap::new(),
+            cell_align: None,
+            restart: None,
+            tables: 0,
+            notes: 0,
+            skipped: BTreeSet::new(),
+        }
+    }
+
+    fn element(&mut self, element: &DocElement) -> Result<()> {
+        match element {
+            DocElement::Paragraph(p) => self.paragraph(p),
+            DocElement::Table(table) => {
+                self.close_lists()?;
+                self.table(table)
+            }
+            DocElement::Image(image) => {
+                self.close_lists()?;
+                self.image_paragraph(image)
+            }
+            DocElement::RawXml(_) => {
+                self.skipped.insert("cover template content");
+                Ok(())
+            }
+            DocElement::MathBlock(_) => {
+                self.skipped.insert("OMML equations");
+                Ok(())
+            }
+            DocElement::Fragment(_) => {
+                self.skipped
+                    .insert("content rendered past the memory budget");
+                Ok(())
+            }
+        }
+    }
+
+    /// The name of an automatic style with these properties, added on
+    /// first use; the parent itself when there is nothing to add
+    fn auto_style(
+        &mut self,
+        family: &'static str,
+        parent: Option<&str>,
+        props: Props,
+    ) -> Option<String> {
+        let page_number = if family == "paragraph" {
+            self.restart.take()
+        } else {
+            None
+        };
+        if props.is_empty() && page_number.is_none() {
+            return parent.map(str::to_string);
+        }
+        let key = format!("{}|{:?}|{:?}|{}", family, parent, page_number, props.key());
+        if let Some(name) = self.style_names.get(&key) {
+            return Some(name.clone());
+        }
+        let prefix = match family {
+            "paragraph" => "P",
+            "text" => "T",
+            "table" => "Table",
+            "table-column" => "Column",
+            "table-row" => "Row",
+            "table-cell" => "Cell",
+            _ => "S",
+        };
+        let count = self.styles.iter().filter(|s| s.family == family).count();
+        let name = format!("{}{}", prefix, count + 1);
+        self.styles.push(AutoStyle {
+            name: name.clone(),
+            family,
+            parent: parent.map(str::to_string),
+            page_number,
+            props,
+        });
+        self.style_names.insert(key, name.clone());
+        Some(name)
+    }
+
+    // ---- Lists ----
+
+    /// Open list levels down to `depth` (1-based) of list `num_id`
+    fn enter_list(&mut self, num_id: u32, depth: usize) -> Result<()> {
+        if self.list != Some(num_id) {
+            self.close_lists()?;
+        }
+        if self.list_depth == 0 {
+            let ordered = self
+                .build
+                .numbering
+                .lists
+                .iter()
+                .find(|l| l.num_id == num_id)
+                .is_some_and(|l| l.is_ordered);
+            let mut attrs = vec![(
+                "text:style-name",
+                if ordered { NUMBER_LIST } else { BULLET_LIST },
+            )];
+            if self.last_list == Some(num_id) {
+                attrs.push(("text:continue-numbering", "true"));
+            }
+            self.xml.start("text:list", &attrs)?;
+            self.xml.start("text:list-item", &[])?;
+            self.list = Some(num_id);
+            self.list_depth = 1;
+        } else if depth <= self.list_depth {
+            while self.list_depth > depth {
+                self.xml.end("text:list-item")?;
+                self.xml.end("text:list")?;
+                self.list_depth -= 1;
+            }
+            self.xml.end("text:list-item")?;
+            self.xml.start("text:list-item", &[])?;
+        }
+        while self.list_depth < depth {
+            self.xml.start("text:list", &[])?;
+            self.xml.start("text:list-item", &[])?;
+            self.list_depth += 1;
+        }
+        Ok(())
+    }
+
+    fn close_lists(&mut self) -> Result<()> {
+        while self.list_depth > 0 {
+            self.xml.end("text:list-item")?;
+            self.xml.end("text:list")?;
+            self.list_depth -= 1;
+        }
+        if let Some(list) = self.list.take() {
+            self.last_list = Some(list);
+        }
+        Ok(())
+    }
+
+    // ---- Paragraphs ----
+
+    fn paragraph(&mut self, p: &Paragraph) -> Result<()> {
+        match p.numbering_id {
+            Some(num_id) => {
+                let depth = p.numbering_level.unwrap_or(0) as usize + 1;
+                self.enter_list(num_id, depth)?;
+            }
+            None => self.close_lists()?,
+        }
+        self.paragraph_element(p)
+    }
+
+    /// A paragraph, without the list it may belong to
+    fn paragraph_element(&mut self, p: &Paragraph) -> Result<()> {
+        let mut props = Props::default();
+        if let Some(align) = p.align.as_deref().or(self.cell_align.as_deref()) {
+            props.paragraph("fo:text-align", text_align(align));
+        }
+        if let Some(before) = p.spacing_before {
+            props.paragraph("fo:margin-top", pt(before));
+        }
+        if let Some(after) = p.spacing_after {
+            props.paragraph("fo:margin-bottom", pt(after));
+        }
+        if let Some(indent) = p.indent_left {
+            props.paragraph("fo:margin-left", pt(indent));
+        }
+        if let (Some(line), Some(rule)) = (p.line, p.line_rule.as_deref()) {
+            let line = line.max(0) as u32;
+            match rule {
+                "exact" => props.paragraph("fo:line-height", pt(line)),
+                "atLeast" => props.paragraph("style:line-height-at-least", pt(line)),
+                _ => props.paragraph("fo:line-height", format!("{}%", line * 100 / 240)),
+            }
+        }
+        if p.keep_with_next {
+            props.paragraph("fo:keep-with-next", "always");
+        }
+        if p.page_break_before {
+            props.paragraph("fo:break-before", "page");
+        }
+        if let Some(fill) = &p.shading {
+            props.paragraph("fo:background-color", format!("#{}", fill));
+        }
+        let page_break = p.children.iter().any(|child| {
+            matches!(child, ParagraphChild::Run(run) if run.break_type.as_deref() == Some("page"))
+        });
+        if page_break
+            || p.section_break
+                .as_deref()
+                .is_some_and(|kind| kind != "continuous")
+        {
+            props.paragraph("fo:break-after", "page");
+        }
+        for tab in &p.tabs {
+            let kind = match tab.val.as_str() {
+                "center" => "center",
+                "right" | "end" => "right",
+                _ => "left",
+            };
+            props.tab(
+                kind,
+                tab.pos.saturating_sub(p.indent_left.unwrap_or(0)),
+                None,
+            );
+        }
+
+        let parent = p.style_id.as_deref().unwrap_or("Normal");
+        let style = self.auto_style("paragraph", Some(parent), props);
+        let style = style.as_deref().unwrap_or(parent);
+        let level = parent
+            .strip_prefix("Heading")
+            .and_then(|n| n.parse::<u8>().ok())
+            .map(|n| n.to_string());
+        let element = if level.is_some() { "text:h" } else { "text:p" };
+        let mut attrs = vec![("text:style-name", style)];
+        if let Some(level) = &level {
+            attrs.push(("text:outline-level", level.as_str()));
+        }
+
+        self.xml.start(element, &attrs)?;
+        self.after_space = true;
+        let paragraph_bookmark = p.bookmark_start.as_ref().map(|b| b.name.clone());
+        if let Some(name) = &paragraph_bookmark {
+            self.xml
+                .empty("text:bookmark-start", &[("text:name", name)])?;
+        }
+        for child in &p.children {
+            self.child(child)?;
+        }
+        if let (true, Some(name)) = (p.bookmark_end, &paragraph_bookmark) {
+            self.xml
+                .empty("text:bookmark-end", &[("text:name", name)])?;
+        }
+        self.xml.end(element)
+    }
+
+    fn child(&mut self, child: &ParagraphChild) -> Result<()> {
+        match child {
+            ParagraphChild::Run(run) => self.run(run),
+            ParagraphChild::Hyperlink(link) => {
+                let url = match &link.anchor {
+                    Some(anchor) => Some(format!("#{}", anchor)),
+                    None => self
+                        .build
+                        .hyperlinks
+                        .hyperlinks
+                        .iter()
+                        .find(|h| h.rel_id == link.id)
+                        .map(|h| h.url.clone()),
+                };
+                let Some(url) = url.filter(|_| self.visible()) else {
+                    return link.children.iter().try_for_each(|run| self.run(run));
+                };
+                self.xml
+                    .start("text:a", &[("xlink:type", "simple"), ("xlink:href", &url)])?;
+                for run in &link.children {
+                    self.run(run)?;
+                }
+                self.xml.end("text:a")
+            }
+            ParagraphChild::InlineImage(image) => self.frame(image),
+            ParagraphChild::OfficeMath(_) => {
+                self.skipped.insert("OMML equations");
+                Ok(())
+            }
+            ParagraphChild::BookmarkStart { id, name } => {
+                self.bookmarks.insert(*id, name.clone());
+                self.xml
+                    .empty("text:bookmark-start", &[("text:name", name)])
+            }
+            ParagraphChild::BookmarkEnd { id } => match self.bookmarks.remove(id) {
+                Some(name) => self.xml.empty("text:bookmark-end", &[("text:name", &name)]),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Whether content at this point is shown: outside fields or in a
+    /// field's result
+    fn visible(&self) -> bool {
+        self.fields.iter().all(|&result| result)
+    }
+
+    fn run(&mut self, run: &Run) -> Result<()> {
+        match run.field_char.as_deref() {
+            Some("begin") => self.fields.push(false),
+            Some("separate") => {
+                if let Some(result) = self.fields.last_mut() {
+                    *result = true;
+                }
+            }
+            Some("end") => {
+                self.fields.pop();
+            }
+            _ => {}
+        }
+        // The note body carries its own number
+        if run.field_char.is_some() || run.instr_text || run.footnote_ref || !self.visible() {
+            return Ok(());
+        }
+        if let Some(id) = run.footnote_id {
+            return self.note(id);
+        }
+
+        let mut props = Props::default();
+        if run.bold {
+            props.text_all(
+                "fo:font-weight",
+                "style:font-weight-asian",
+                "style:font-weight-complex",
+                "bold",
+            );
+        }
+        if run.italic {
+            props.text_all(
+                "fo:font-style",
+                "style:font-style-asian",
+                "style:font-style-complex",
+                "italic",
+            );
+        }
+        if run.underline {
+            props.text("style:text-underline-style", "solid");
+            props.text("style:text-underline-width", "auto");
+            props.text("style:text-underline-color", "font-color");
+        }
+        if run.strike {
+            props.text("style:text-line-through-style", "solid");
+        }
+        if let Some(font) = &run.font {
+            set_font(&mut props, font, font);
+        }
+        if let Some(size) = run.size {
+            props.text_all(
+                "fo:font-size",
+                "style:font-size-asian",
+                "style:font-size-complex",
+                &half_pt(size),
+            );
+        }
+        if let Some(color) = &run.color {
+            props.text("fo:color", format!("#{}", color));
+        }
+        if let Some(color) = run.highlight.as_deref().and_then(highlight_color) {
+            props.text("fo:background-color", color);
+        }
+        if run.superscript {
+            props.text("style:text-position", "super 58%");
+        }
+
+        let style = self.auto_style("text", run.style.as_deref(), props);
+        if let Some(style) = &style {
+            self.xml.start("text:span", &[("text:style-name", style)])?;
+        }
+        if run.tab {
+            self.xml.empty("text:tab", &[])?;
+            self.after_space = false;
+        }
+        if run.break_type.as_deref() == Some("textWrapping") {
+            self.xml.empty("text:line-break", &[])?;
+            self.after_space = true;
+        }
+        self.text(&run.text)?;
+        if style.is_some() {
+            self.xml.end("text:span")?;
+        }
+        Ok(())
+    }
+
+    /// Text with tabs, line breaks and runs of spaces written as elements,
+    /// since ODF collapses white space in character data
+    fn text(&mut self, text: &str) -> Result<()> {
+        let mut pending = String::new();
+        let mut spaces = 0;
+        for c in text.chars() {
+            if c == ' ' {
+                if self.after_space {
+                    spaces += 1;
+                } else {
+                    pending.push(' ');
+                    self.after_space = true;
+                }
+                continue;
+            }
+            if spaces > 0 {
+                self.flush(&mut pending)?;
+                self.spaces(spaces)?;
+                spaces = 0;
+            }
+            match c {
+                '\t' => {
+                    self.flush(&mut pending)?;
+                    self.xml.empty("text:tab", &[])?;
+                }
+                '\n' => {
+                    self.flush(&mut pending)?;
+                    self.xml.empty("text:line-break", &[])?;
+                    self.after_space = true;
+                    continue;
+                }
+                _ => pending.push(c),
+            }
+            self.after_space = false;
+        }
+        self.flush(&mut pending)?;
+        if spaces > 0 {
+            self.spaces(spaces)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, pending: &mut String) -> Result<()> {
+        if !pending.is_empty() {
+            self.xml.text(pending)?;
+            pending.clear();
+        }
+        Ok(())
+    }
+
+    fn spaces(&mut self, count: usize) -> Result<()> {
+        if count == 1 {
+            self.xml.empty("text:s", &[])
+        } else {
+            self.xml.empty("text:s", &[("text:c", &count.to_string())])
+        }
+    }
+
+    fn note(&mut self, id: i32) -> Result<()> {
+        let Some(footnote) = self
+            .build
+            .footnotes
+            .get_footnotes()
+            .iter()
+            .find(|f| f.id == id)
+        else {
+            return Ok(());
+        };
+        self.notes += 1;
+        let note_id = format!("ftn{}", id);
+        self.xml.start(
+            "text:note",
+            &[("text:id", &note_id), ("text:note-class", "footnote")],
+        )?;
+        self.xml
+            .element("text:note-citation", &[], &self.notes.to_string())?;
+        self.xml.start("text:note-body", &[])?;
+        let after_space = self.after_space;
+        let fields = std::mem::take(&mut self.fields);
+        for p in &footnote.content {
+            self.paragraph_element(p)?;
+        }
+        self.fields = fields;
+        self.after_space = after_space;
+        self.xml.end("text:note-body")?;
+        self.xml.end("text:note")
+    }
+
+    // ---- Images ----
+
+    fn image_paragraph(&mut self, image: &ImageElement) -> Result<()> {
+        let mut props = Props::default();
+        props.paragraph(
+            "fo:text-align",
+            text_align(image.alignment.as_deref().unwrap_or("center")),
+        );
+        props.paragraph("fo:margin-top", pt(image.spacing_before.unwrap_or(0)));
+        props.paragraph("fo:margin-bottom", pt(image.spacing_after.unwrap_or(0)));
+        let style = self.auto_style("paragraph", Some("Normal"), props);
+        let style = style.as_deref().unwrap_or("Normal");
+        self.xml.start("text:p", &[("text:style-name", style)])?;
+        self.frame(image)?;
+        self.xml.end("text:p")
+    }
+
+    /// An image anchored as a character
+    fn frame(&mut self, image: &ImageElement) -> Result<()> {
+        let Some(info) = self
+            .build
+            .images
+            .images
+            .iter()
+            .find(|i| i.rel_id == image.rel_id)
+        else {
+            return Ok(());
+        };
+        let path = format!("Pictures/{}", info.filename);
+        if !self.pictures.iter().any(|p| p.path == path) {
+            match read_image(info) {
+                Some(data) => self.pictures.push(Picture {
+                    path: path.clone(),
+                    media_type: media_type(&info.filename),
+                    data,
+                }),
+                None => {
+                    diagnostics::warn(
+                        WarningCategory::MissingImage,
+                        format!("Image not found: {}", info.src),
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        let name = format!("Image{}", image.id);
+        let (width, height) = (emu_cm(image.width_emu), emu_cm(image.height_emu));
+        self.xml.start(
+            "draw:frame",
+            &[
+                ("draw:name", &name),
+                ("text:anchor-type", "as-char"),
+                ("svg:width", &width),
+                ("svg:height", &height),
+            ],
+        )?;
+        self.xml.empty(
+            "draw:image",
+            &[
+                ("xlink:href", &path),
+                ("xlink:type", "simple"),
+                ("xlink:show", "embed"),
+                ("xlink:actuate", "onLoad"),
+            ],
+        )?;
+        if !image.alt_text.is_empty() {
+            self.xml.element("svg:desc", &[], &image.alt_text)?;
+        }
+        self.xml.end("draw:frame")?;
+        self.after_space = false;
+        Ok(())
+    }
+
+    // ---- Tables ----
+
+    fn table(&mut self, table: &Table) -> Result<()> {
+        self.tables += 1;
+        let mut props = Props::default();
+        let width: u32 = table.column_widths.iter().sum();
+        if width > 0 {
+            props.set("table-properties", "style:width", pt(width));
+            props.set("table-properties", "table:align", "left");
+        } else {
+            props.set("table-properties", "table:align", "margins");
+        }
+        if table.bidi_visual {
+            props.set("table-properties", "style:writing-mode", "rl-tb");
+        }
+        let style = self.auto_style("table", None, props);
+        let name = format!("Table{}", self.tables);
+        let mut attrs = vec![("table:name", name.as_str())];
+        if let Some(style) = &style {
+            attrs.push(("table:style-name", style.as_str()));
+        }
+        self.xml.start("table:table", &attrs)?;
+
+        if table.column_widths.is_empty() {
+            let columns = table.rows.iter().map(|r| r.cells.len()).max().unwrap_or(1);
+            self.xml.empty(
+                "table:table-column",
+                &[("table:number-columns-repeated", &columns.to_string())],
+            )?;
+        }
+        for &column_width in &table.column_widths {
+            let mut props = Props::default();
+            props.set(
+                "table-column-properties",
+                "style:column-width",
+                pt(column_width),
+            );
+            let style = self.auto_style("table-column", None, props);
+            let style = style.as_deref().unwrap_or_default();
+            self.xml
+                .empty("table:table-column", &[("table:style-name", style)])?;
+        }
+
+        let headers = table.rows.iter().take_while(|r| r.is_header).count();
+        for (index, row) in table.rows.iter().enumerate() {
+            if index == 0 && headers > 0 {
+                self.xml.start("table:table-header-rows", &[])?;
+            }
+            let mut props = Props::default();
+            if row.cant_split {
+                props.set("table-row-properties", "fo:keep-together", "always");
+            }
+            match self.auto_style("table-row", None, props) {
+                Some(style) => self
+                    .xml
+                    .start("table:table-row", &[("table:style-name", style.as_str())])?,
+                None => self.xml.start("table:table-row", &[])?,
+            }
+            for cell in &row.cells {
+                let mut props = Props::default();
+                let group = "table-cell-properties";
+                props.set(group, "fo:border", "0.5pt solid #000000");
+                props.set(group, "fo:padding", "0.05in");
+                if let Some(fill) = &cell.shading {
+                    props.set(group, "fo:background-color", format!("#{}", fill));
+                }
+                if let Some(align) = &cell.vertical_alignment {
+                    let align = if align == "center" {
+                        "middle"
+                    } else {
+                        align.as_str()
+                    };
+                    props.set(group, "style:vertical-align", align);
+                }
+                let style = self.auto_style("table-cell", None, props);
+                let style = style.as_deref().unwrap_or_default();
+                self.xml.start(
+                    "table:table-cell",
+                    &[("table:style-name", style), ("office:value-type", "string")],
+                )?;
+                self.cell_align = cell.alignment.clone();
+                if cell.paragraphs.is_empty() {
+                    self.xml.empty("text:p", &[("text:style-name", "Normal")])?;
+                }
+                for p in &cell.paragraphs {
+                    self.paragraph(p)?;
+                }
+                self.close_lists()?;
+                self.cell_align = None;
+                self.xml.end("table:table-cell")?;
+            }
+            self.xml.end("table:table-row")?;
+            if index + 1 == headers {
+                self.xml.end("table:table-header-rows")?;
+            }
+        }
+        self.xml.end("table:table")
+    }
+}
+
+/// Bytes of an image, from memory or from its source file
+fn read_image(info: &ImageInfo) -> Option<Vec<u8>> {
+    match &info.data {
+        Some(data) => Some(data.to_vec()),
+        None => std::fs::read(&info.src).ok(),
+    }
+}
+
+fn media_type(filename: &str) -> &'static str {
+    let ext = std::path::Path::new(filename)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("png");
+    match ext.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "emf" => "image/x-emf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Word highlight color names as RGB
+fn highlight_color(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "yellow" => "#FFFF00",
+        "green" => "#00FF00",
+        "cyan" => "#00FFFF",
+        "magenta" => "#FF00FF",
+        "blue" => "#0000FF",
+        "red" => "#FF0000",
+        "darkBlue" => "#000080",
+        "darkCyan" => "#008080",
+        "darkGreen" => "#008000",
+        "darkMagenta" => "#800080",
+        "darkRed" => "#800000",
+        "darkYellow" => "#808000",
+        "darkGray" => "#808080",
+        "lightGray" => "#C0C0C0",
+        "black" => "#000000",
+        "white" => "#FFFFFF",
+        _ => return None,
+    })
+}
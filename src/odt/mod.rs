@@ -0,0 +1,294 @@
+//! OpenDocument text output (`md2docx build --odt`)
+//!
+//! The document goes through the same builder as DOCX and its element model
+//! is written as ODF instead of WordprocessingML: `styles` turns the DOCX
+//! styles, page setup, header and footer into `styles.xml`, `content`
+//! writes the body to `content.xml` and `packager` assembles the archive.
+//! Cover, table and image templates, watermarks and tracked changes only
+//! apply to DOCX output, and equations are always embedded as images.
+
+mod content;
+mod packager;
+mod styles;
+
+use std::io::Cursor;
+
+use crate::docx::builder::BuildResult;
+use crate::docx::ooxml::xml_writer::XmlWriter;
+use crate::{DocumentConfig, Language, Result};
+
+use packager::OdtPackager;
+
+/// Namespaces declared on the root of every part
+const NAMESPACES: &[(&str, &str)] = &[
+    (
+        "xmlns:office",
+        "urn:oasis:names:tc:opendocument:xmlns:office:1.0",
+    ),
+    (
+        "xmlns:style",
+        "urn:oasis:names:tc:opendocument:xmlns:style:1.0",
+    ),
+    (
+        "xmlns:text",
+        "urn:oasis:names:tc:opendocument:xmlns:text:1.0",
+    ),
+    (
+        "xmlns:table",
+        "urn:oasis:names:tc:opendocument:xmlns:table:1.0",
+    ),
+    (
+        "xmlns:draw",
+        "urn:oasis:names:tc:opendocument:xmlns:drawing:1.0",
+    ),
+    (
+        "xmlns:fo",
+        "urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0",
+    ),
+    (
+        "xmlns:svg",
+        "urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0",
+    ),
+    ("xmlns:xlink", "http://www.w3.org/1999/xlink"),
+    ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+    (
+        "xmlns:meta",
+        "urn:oasis:names:tc:opendocument:xmlns:meta:1.0",
+    ),
+    ("office:version", "1.3"),
+];
+
+/// Write a built document as an ODT package
+pub(crate) fn write(
+    build: &mut BuildResult,
+    lang: Language,
+    config: &DocumentConfig,
+) -> Result<Vec<u8>> {
+    build.images.dedupe_media();
+    let body = content::write(build)?;
+    let styles = styles::write(lang, config)?;
+
+    let mut packager = OdtPackager::new(Cursor::new(Vec::new()), config.compression);
+    packager.add_file("content.xml", &body.xml)?;
+    packager.add_file("styles.xml", &styles)?;
+    packager.add_file("meta.xml", &meta_xml(lang, config)?)?;
+    for picture in &body.pictures {
+        packager.add_picture(&picture.path, picture.media_type, &picture.data)?;
+    }
+    Ok(packager.finish()?.into_inner())
+}
+
+/// `meta.xml`: title, author and language
+fn meta_xml(lang: Language, config: &DocumentConfig) -> Result<Vec<u8>> {
+    let mut xml = XmlWriter::new(Vec::new());
+    xml.declaration()?;
+    xml.start("office:document-meta", NAMESPACES)?;
+    xml.start("office:meta", &[])?;
+    xml.element(
+        "meta:generator",
+        &[],
+        concat!("md2docx/", env!("CARGO_PKG_VERSION")),
+    )?;
+    if !config.title.is_empty() {
+        xml.element("dc:title", &[], &config.title)?;
+    }
+    if let Some(author) = config.document_meta.as_ref().map(|m| m.author.as_str()) {
+        if !author.is_empty() {
+            xml.element("meta:initial-creator", &[], author)?;
+        }
+    }
+    xml.element("dc:language", &[], lang.lang_tag())?;
+    xml.end("office:meta")?;
+    xml.end("office:document-meta")?;
+    Ok(xml.into_inner())
+}
+
+/// Formatting properties of a style, grouped by the properties element
+/// they belong to (`paragraph-properties`, `text-properties`, ...)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct Props {
+    groups: Vec<(&'static str, Vec<(&'static str, String)>)>,
+    /// Tab stops as (type, position, leader), written inside
+    /// `paragraph-properties`
+    tabs: Vec<(&'static str, String, Option<&'static str>)>,
+}
+
+impl Props {
+    pub fn set(&mut self, group: &'static str, name: &'static str, value: impl Into<String>) {
+        let value = value.into();
+        let index = match self.groups.iter().position(|(g, _)| *g == group) {
+            Some(index) => index,
+            None => {
+                self.groups.push((group, Vec::new()));
+                self.groups.len() - 1
+            }
+        };
+        let attrs = &mut self.groups[index].1;
+        match attrs.iter_mut().find(|(n, _)| *n == name) {
+            Some(attr) => attr.1 = value,
+            None => attrs.push((name, value)),
+        }
+    }
+
+    pub fn paragraph(&mut self, name: &'static str, value: impl Into<String>) {
+        self.set("paragraph-properties", name, value);
+    }
+
+    pub fn text(&mut self, name: &'static str, value: impl Into<String>) {
+        self.set("text-properties", name, value);
+    }
+
+    /// Set a property for Latin, Asian and complex script text alike
+    pub fn text_all(
+        &mut self,
+        name: &'static str,
+        asian: &'static str,
+        complex: &'static str,
+        value: &str,
+    ) {
+        self.text(name, value);
+        self.text(asian, value);
+        self.text(complex, value);
+    }
+
+    pub fn tab(&mut self, kind: &'static str, position: u32, leader: Option<&'static str>) {
+        self.paragraph_group();
+        self.tabs.push((kind, pt(position), leader));
+    }
+
+    fn paragraph_group(&mut self) {
+        if !self
+            .groups
+            .iter()
+            .any(|(g, _)| *g == "paragraph-properties")
+        {
+            self.groups.push(("paragraph-properties", Vec::new()));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// A key identifying these properties, for sharing automatic styles
+    pub fn key(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    /// Write the properties elements
+    pub fn write(&self, xml: &mut XmlWriter<Vec<u8>>) -> Result<()> {
+        for (group, attrs) in &self.groups {
+            let name = format!("style:{}", group);
+            let attrs: Vec<(&str, &str)> = attrs.iter().map(|(n, v)| (*n, v.as_str())).collect();
+            if *group == "paragraph-properties" && !self.tabs.is_empty() {
+                xml.start(&name, &attrs)?;
+                xml.start("style:tab-stops", &[])?;
+                for (kind, position, leader) in &self.tabs {
+                    let mut attrs =
+                        vec![("style:type", *kind), ("style:position", position.as_str())];
+                    if let Some(leader) = leader {
+                        attrs.push(("style:leader-style", "dotted"));
+                        attrs.push(("style:leader-text", *leader));
+                    }
+                    xml.empty("style:tab-stop", &attrs)?;
+                }
+                xml.end("style:tab-stops")?;
+                xml.end(&name)?;
+            } else {
+                xml.empty(&name, &attrs)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a `<style:style>` element
+pub(super) fn write_style(
+    xml: &mut XmlWriter<Vec<u8>>,
+    attrs: &[(&str, &str)],
+    props: &Props,
+) -> Result<()> {
+    xml.start("style:style", attrs)?;
+    props.write(xml)?;
+    xml.end("style:style")
+}
+
+/// Twips as points
+pub(super) fn pt(twips: u32) -> String {
+    format!("{}pt", twips as f32 / 20.0)
+}
+
+/// Half-points as points
+pub(super) fn half_pt(size: u32) -> String {
+    format!("{}pt", size as f32 / 2.0)
+}
+
+/// EMUs as centimeters
+pub(super) fn emu_cm(emu: i64) -> String {
+    format!("{:.3}cm", emu as f64 / 360_000.0)
+}
+
+/// DOCX paragraph alignment as `fo:text-align`
+pub(super) fn text_align(align: &str) -> &'static str {
+    match align {
+        "center" => "center",
+        "right" | "end" => "end",
+        "both" | "distribute" | "thaiDistribute" => "justify",
+        _ => "start",
+    }
+}
+
+/// Set the Latin, Asian and complex script fonts
+pub(super) fn set_font(props: &mut Props, latin: &str, complex: &str) {
+    let quote = |font: &str| format!("'{}'", font.replace('\'', ""));
+    props.text("fo:font-family", quote(latin));
+    props.text("style:font-family-asian", quote(latin));
+    props.text("style:font-family-complex", quote(complex));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_odt_package() {
+        let markdown = "# บทนำ\n\nSome **bold** text[^1].\n\n1. One\n2. Two\n\n| A | B |\n|---|---|\n| 1 | 2 |\n\n[^1]: A note.\n";
+        let config = DocumentConfig {
+            title: "Report".to_string(),
+            ..Default::default()
+        };
+        let odt = crate::markdown_to_odt(markdown, Language::Thai, &config).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(odt)).unwrap();
+        let mut mimetype = archive.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), zip::CompressionMethod::Stored);
+        let mut value = String::new();
+        mimetype.read_to_string(&mut value).unwrap();
+        assert_eq!(value, "application/vnd.oasis.opendocument.text");
+        drop(mimetype);
+
+        let mut content = String::new();
+        archive
+            .by_name("content.xml")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert!(content.contains(r#"text:outline-level="1""#));
+        assert!(content.contains("บทนำ"));
+        assert!(content.contains("<text:note "));
+        assert!(content.contains("<text:list "));
+        assert!(content.contains("<table:table "));
+
+        let mut styles = String::new();
+        archive
+            .by_name("styles.xml")
+            .unwrap()
+            .read_to_string(&mut styles)
+            .unwrap();
+        assert!(styles.contains(r#"style:name="Heading1""#));
+        assert!(styles.contains("<text:page-number"));
+        assert!(archive.by_name("META-INF/manifest.xml").is_ok());
+    }
+}
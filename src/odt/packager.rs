@@ -0,0 +1,118 @@
+//! ZIP packager for ODT
+
+use std::io::{Seek, Write};
+use zip::write::{FileOptions, ZipWriter};
+
+use crate::docx::ooxml::xml_writer::XmlWriter;
+use crate::docx::Compression;
+use crate::error::Result;
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+
+/// ODT Packager
+///
+/// Writes the `mimetype` entry first and uncompressed, as ODF requires,
+/// then the parts added to it, and lists them all in
+/// `META-INF/manifest.xml` when finished.
+pub(crate) struct OdtPackager<W: Write + Seek> {
+    writer: ZipWriter<W>,
+    /// (path, media type) of every part, for the manifest
+    entries: Vec<(String, String)>,
+    compression: Compression,
+    started: bool,
+}
+
+impl<W: Write + Seek> OdtPackager<W> {
+    pub fn new(writer: W, compression: Compression) -> Self {
+        Self {
+            writer: ZipWriter::new(writer),
+            entries: Vec::new(),
+            compression,
+            started: false,
+        }
+    }
+
+    /// Get file options for writing `path`
+    fn file_options(&self, path: &str) -> FileOptions<'static, ()> {
+        // A fixed timestamp keeps the archive identical across rebuilds
+        let options = FileOptions::default()
+            .unix_permissions(0o644)
+            .last_modified_time(zip::DateTime::default());
+        let media = path.starts_with("Pictures/");
+        if path == "mimetype"
+            || self.compression.level == Some(0)
+            || (media && self.compression.store_media)
+        {
+            return options.compression_method(zip::CompressionMethod::Stored);
+        }
+        options
+            .compression_method(zip::CompressionMethod::Deflated)
+            .compression_level(self.compression.level.map(|level| i64::from(level.min(9))))
+    }
+
+    fn write_entry(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        if !self.started {
+            self.started = true;
+            self.writer
+                .start_file("mimetype", self.file_options("mimetype"))?;
+            self.writer.write_all(MIMETYPE.as_bytes())?;
+        }
+        self.writer.start_file(path, self.file_options(path))?;
+        self.writer.write_all(data)?;
+        Ok(())
+    }
+
+    /// Add an XML part (`content.xml`, `styles.xml`, ...)
+    pub fn add_file(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        self.write_entry(path, data)?;
+        self.entries
+            .push((path.to_string(), "text/xml".to_string()));
+        Ok(())
+    }
+
+    /// Add an image under `Pictures/`
+    pub fn add_picture(&mut self, path: &str, media_type: &str, data: &[u8]) -> Result<()> {
+        self.write_entry(path, data)?;
+        self.entries
+            .push((path.to_string(), media_type.to_string()));
+        Ok(())
+    }
+
+    /// Write the manifest and finish the archive
+    pub fn finish(mut self) -> Result<W> {
+        let mut xml = XmlWriter::new(Vec::new());
+        xml.declaration()?;
+        xml.start(
+            "manifest:manifest",
+            &[
+                (
+                    "xmlns:manifest",
+                    "urn:oasis:names:tc:opendocument:xmlns:manifest:1.0",
+                ),
+                ("manifest:version", "1.3"),
+            ],
+        )?;
+        xml.empty(
+            "manifest:file-entry",
+            &[
+                ("manifest:full-path", "/"),
+                ("manifest:version", "1.3"),
+                ("manifest:media-type", MIMETYPE),
+            ],
+        )?;
+        for (path, media_type) in &self.entries {
+            xml.empty(
+                "manifest:file-entry",
+                &[
+                    ("manifest:full-path", path),
+                    ("manifest:media-type", media_type),
+                ],
+            )?;
+        }
+        xml.end("manifest:manifest")?;
+
+        let manifest = xml.into_inner();
+        self.write_entry("META-INF/manifest.xml", &manifest)?;
+        Ok(self.writer.finish()?)
+    }
+}
@@ -0,0 +1,441 @@
+//! `styles.xml`: named styles, list styles, page layout and master page
+//!
+//! The named styles are the DOCX ones, converted property by property, so
+//! paragraphs keep their style names (`Heading1`, `Code`, `Caption`...) and
+//! can be restyled in LibreOffice the same way as in Word.
+
+use crate::docx::ooxml::xml_writer::XmlWriter;
+use crate::docx::ooxml::{
+    FooterConfig, HeaderConfig, HeaderFooterField, NumberingConfig, Style, StyleType,
+    StylesDocument,
+};
+use crate::{DocumentConfig, Language, Result};
+
+use super::{half_pt, pt, set_font, text_align, write_style, Props, NAMESPACES};
+
+/// List style of bullet lists
+pub(super) const BULLET_LIST: &str = "List_Bullet";
+/// List style of numbered lists
+pub(super) const NUMBER_LIST: &str = "List_Number";
+
+/// Bullets used when `[numbering] bullets` is not set; the DOCX defaults
+/// rely on Symbol and Wingdings glyphs
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+pub(super) fn write(lang: Language, config: &DocumentConfig) -> Result<Vec<u8>> {
+    let page = config.page.clone().unwrap_or_default();
+    let mut styles = StylesDocument::with_page_layout(
+        lang,
+        config.fonts.clone(),
+        page.width,
+        page.margin_left,
+        page.margin_right,
+    );
+    styles.apply_toc_layout(&config.toc);
+    styles.apply_paragraph_preset(config.paragraph_preset);
+    styles.apply_spacing(&config.style_spacing);
+
+    let mut xml = XmlWriter::new(Vec::new());
+    xml.declaration()?;
+    xml.start("office:document-styles", NAMESPACES)?;
+
+    xml.start("office:styles", &[])?;
+    let (language, country) = lang
+        .lang_tag()
+        .split_once('-')
+        .unwrap_or((lang.lang_tag(), ""));
+    let mut defaults = Props::default();
+    defaults.text("fo:language", language);
+    defaults.text("fo:country", country);
+    defaults.text("style:language-complex", language);
+    defaults.text("style:country-complex", country);
+    if config.widow_control {
+        defaults.paragraph("fo:widows", "2");
+        defaults.paragraph("fo:orphans", "2");
+    }
+    if lang.is_rtl() {
+        defaults.paragraph("style:writing-mode", "rl-tb");
+    }
+    xml.start("style:default-style", &[("style:family", "paragraph")])?;
+    defaults.write(&mut xml)?;
+    xml.end("style:default-style")?;
+
+    for style in styles.styles() {
+        write_named_style(&mut xml, style)?;
+    }
+    write_list_style(&mut xml, BULLET_LIST, false, &config.numbering)?;
+    write_list_style(&mut xml, NUMBER_LIST, true, &config.numbering)?;
+    xml.end("office:styles")?;
+
+    let has_header = !is_empty(
+        &config.header.left,
+        &config.header.center,
+        &config.header.right,
+    );
+    let has_footer = !is_empty(
+        &config.footer.left,
+        &config.footer.center,
+        &config.footer.right,
+    );
+    xml.start("office:automatic-styles", &[])?;
+    write_page_layout(&mut xml, &page, lang, has_header, has_footer)?;
+    xml.end("office:automatic-styles")?;
+
+    xml.start("office:master-styles", &[])?;
+    xml.start(
+        "style:master-page",
+        &[
+            ("style:name", "Standard"),
+            ("style:page-layout-name", "pm1"),
+        ],
+    )?;
+    if has_header {
+        write_header(&mut xml, &config.header, config)?;
+    }
+    if has_footer {
+        write_footer(&mut xml, &config.footer, config)?;
+    }
+    xml.end("style:master-page")?;
+    xml.end("office:master-styles")?;
+
+    xml.end("office:document-styles")?;
+    Ok(xml.into_inner())
+}
+
+/// A DOCX paragraph or character style as a named ODF style
+fn write_named_style(xml: &mut XmlWriter<Vec<u8>>, style: &Style) -> Result<()> {
+    let family = match style.style_type {
+        StyleType::Paragraph => "paragraph",
+        StyleType::Character => "text",
+        StyleType::Table | StyleType::Numbering => return Ok(()),
+    };
+
+    let mut props = Props::default();
+    if style.style_type == StyleType::Paragraph {
+        if let Some(before) = style.spacing_before {
+            props.paragraph("fo:margin-top", pt(before));
+        }
+        if let Some(after) = style.spacing_after {
+            props.paragraph("fo:margin-bottom", pt(after));
+        }
+        if let Some(indent) = style.indent_left {
+            props.paragraph("fo:margin-left", pt(indent));
+        }
+        if let Some(indent) = style.indent_first_line {
+            props.paragraph("fo:text-indent", pt(indent));
+        }
+        if let Some(jc) = style.justification.as_deref() {
+            props.paragraph("fo:text-align", text_align(jc));
+        }
+        if style.contextual_spacing {
+            props.paragraph("style:contextual-spacing", "true");
+        }
+        if let Some(bidi) = style.bidi {
+            props.paragraph("style:writing-mode", if bidi { "rl-tb" } else { "lr-tb" });
+        }
+        // DOCX tab stops are measured from the margin, ODF ones from the indent
+        let indent = style.indent_left.unwrap_or(0);
+        for tab in &style.tabs {
+            let kind = match tab.alignment.as_str() {
+                "center" => "center",
+                "right" | "end" => "right",
+                _ => "left",
+            };
+            let leader = match tab.leader.as_deref() {
+                Some("dot") => Some("."),
+                Some("hyphen") => Some("-"),
+                Some("underscore") => Some("_"),
+                _ => None,
+            };
+            props.tab(kind, tab.position.saturating_sub(indent), leader);
+        }
+    }
+
+    if let Some(latin) = style.font_ascii.as_deref() {
+        set_font(&mut props, latin, style.font_cs.as_deref().unwrap_or(latin));
+    }
+    if let Some(size) = style.size {
+        props.text("fo:font-size", half_pt(size));
+        props.text("style:font-size-asian", half_pt(size));
+    }
+    if let Some(size) = style.size_cs.or(style.size) {
+        props.text("style:font-size-complex", half_pt(size));
+    }
+    if style.bold {
+        props.text_all(
+            "fo:font-weight",
+            "style:font-weight-asian",
+            "style:font-weight-complex",
+            "bold",
+        );
+    }
+    if style.italic {
+        props.text_all(
+            "fo:font-style",
+            "style:font-style-asian",
+            "style:font-style-complex",
+            "italic",
+        );
+    }
+    if style.underline {
+        props.text("style:text-underline-style", "solid");
+        props.text("style:text-underline-width", "auto");
+        props.text("style:text-underline-color", "font-color");
+    }
+    if let Some(color) = &style.color {
+        props.text("fo:color", format!("#{}", color));
+    }
+    if style.vanish {
+        props.text("text:display", "none");
+    }
+
+    let outline = style.outline_level.map(|level| (level + 1).to_string());
+    let mut attrs = vec![
+        ("style:name", style.id.as_str()),
+        ("style:display-name", style.name.as_str()),
+        ("style:family", family),
+    ];
+    if let Some(parent) = &style.based_on {
+        attrs.push(("style:parent-style-name", parent.as_str()));
+    }
+    if family == "paragraph" {
+        if let Some(next) = &style.next {
+            attrs.push(("style:next-style-name", next.as_str()));
+        }
+    }
+    if let Some(level) = &outline {
+        attrs.push(("style:default-outline-level", level.as_str()));
+    }
+    write_style(xml, &attrs, &props)
+}
+
+/// A list style with nine levels, indented like the DOCX numbering
+fn write_list_style(
+    xml: &mut XmlWriter<Vec<u8>>,
+    name: &str,
+    ordered: bool,
+    numbering: &NumberingConfig,
+) -> Result<()> {
+    xml.start("text:list-style", &[("style:name", name)])?;
+    for ilvl in 0..9u32 {
+        let level = (ilvl + 1).to_string();
+        let (element, attrs) = if ordered {
+            let format = match numbering.ordered_format(ilvl) {
+                "lowerLetter" => "a",
+                "upperLetter" => "A",
+                "lowerRoman" => "i",
+                "upperRoman" => "I",
+                "thaiLetters" => "ก, ข, ค, ...",
+                "thaiNumbers" => "๑, ๒, ๓, ...",
+                _ => "1",
+            };
+            (
+                "text:list-level-style-number",
+                vec![("text:level", level.as_str()), ("style:num-format", format)],
+            )
+        } else {
+            let bullet = if numbering.bullets.is_empty() {
+                BULLETS[ilvl as usize % BULLETS.len()]
+            } else {
+                &numbering.bullets[ilvl as usize % numbering.bullets.len()]
+            };
+            (
+                "text:list-level-style-bullet",
+                vec![("text:level", level.as_str()), ("text:bullet-char", bullet)],
+            )
+        };
+        // Same as DOCX: 0.5in per level with a 0.25in hanging indent
+        let margin = pt((ilvl + 1) * 720);
+        xml.start(element, &attrs)?;
+        xml.start(
+            "style:list-level-properties",
+            &[("text:list-level-position-and-space-mode", "label-alignment")],
+        )?;
+        xml.empty(
+            "style:list-level-label-alignment",
+            &[
+                ("text:label-followed-by", "listtab"),
+                ("text:list-tab-stop-position", &margin),
+                ("fo:text-indent", "-18pt"),
+                ("fo:margin-left", &margin),
+            ],
+        )?;
+        xml.end("style:list-level-properties")?;
+        xml.end(element)?;
+    }
+    xml.end("text:list-style")
+}
+
+/// The page size and margins of `[page]`, with the same A4 defaults as DOCX
+fn write_page_layout(
+    xml: &mut XmlWriter<Vec<u8>>,
+    page: &crate::docx::PageConfig,
+    lang: Language,
+    has_header: bool,
+    has_footer: bool,
+) -> Result<()> {
+    let top = page.margin_top.unwrap_or(1440);
+    let bottom = page.margin_bottom.unwrap_or(1440);
+    let header = page.margin_header.unwrap_or(708).min(top);
+    let footer = page.margin_footer.unwrap_or(708).min(bottom);
+
+    // ODF measures the page margins to the header and footer, which then
+    // keep their distance from the body
+    let mut props = Props::default();
+    let group = "page-layout-properties";
+    props.set(group, "fo:page-width", pt(page.width.unwrap_or(11906)));
+    props.set(group, "fo:page-height", pt(page.height.unwrap_or(16838)));
+    props.set(
+        group,
+        "fo:margin-top",
+        pt(if has_header { header } else { top }),
+    );
+    props.set(
+        group,
+        "fo:margin-bottom",
+        pt(if has_footer { footer } else { bottom }),
+    );
+    let gutter = page.margin_gutter.unwrap_or(0);
+    props.set(
+        group,
+        "fo:margin-left",
+        pt(page.margin_left.unwrap_or(1440) + gutter),
+    );
+    props.set(
+        group,
+        "fo:margin-right",
+        pt(page.margin_right.unwrap_or(1440)),
+    );
+    props.set(
+        group,
+        "style:writing-mode",
+        if lang.is_rtl() { "rl-tb" } else { "lr-tb" },
+    );
+
+    xml.start("style:page-layout", &[("style:name", "pm1")])?;
+    props.write(xml)?;
+    if has_header {
+        let spacing = pt(top - header);
+        xml.start("style:header-style", &[])?;
+        xml.empty(
+            "style:header-footer-properties",
+            &[("fo:min-height", "0pt"), ("fo:margin-bottom", &spacing)],
+        )?;
+        xml.end("style:header-style")?;
+    }
+    if has_footer {
+        let spacing = pt(bottom - footer);
+        xml.start("style:footer-style", &[])?;
+        xml.empty(
+            "style:header-footer-properties",
+            &[("fo:min-height", "0pt"), ("fo:margin-top", &spacing)],
+        )?;
+        xml.end("style:footer-style")?;
+    }
+    xml.end("style:page-layout")
+}
+
+fn write_header(
+    xml: &mut XmlWriter<Vec<u8>>,
+    header: &HeaderConfig,
+    config: &DocumentConfig,
+) -> Result<()> {
+    xml.start("style:header", &[])?;
+    write_line(
+        xml,
+        "Header",
+        [&header.left, &header.center, &header.right],
+        &config.title,
+    )?;
+    xml.end("style:header")?;
+    if config.different_first_page {
+        xml.start("style:header-first", &[])?;
+        xml.empty("text:p", &[("text:style-name", "Header")])?;
+        xml.end("style:header-first")?;
+    }
+    Ok(())
+}
+
+fn write_footer(
+    xml: &mut XmlWriter<Vec<u8>>,
+    footer: &FooterConfig,
+    config: &DocumentConfig,
+) -> Result<()> {
+    xml.start("style:footer", &[])?;
+    write_line(
+        xml,
+        "Footer",
+        [&footer.left, &footer.center, &footer.right],
+        &config.title,
+    )?;
+    xml.end("style:footer")?;
+    if config.different_first_page {
+        xml.start("style:footer-first", &[])?;
+        xml.empty("text:p", &[("text:style-name", "Footer")])?;
+        xml.end("style:footer-first")?;
+    }
+    Ok(())
+}
+
+/// A header or footer paragraph: left, center and right parts separated
+/// by the tab stops of the `Header` / `Footer` style
+fn write_line(
+    xml: &mut XmlWriter<Vec<u8>>,
+    style: &str,
+    [left, center, right]: [&Vec<HeaderFooterField>; 3],
+    title: &str,
+) -> Result<()> {
+    xml.start("text:p", &[("text:style-name", style)])?;
+    write_fields(xml, left, title)?;
+    if !center.is_empty() || !right.is_empty() {
+        xml.empty("text:tab", &[])?;
+        write_fields(xml, center, title)?;
+    }
+    if !right.is_empty() {
+        xml.empty("text:tab", &[])?;
+        write_fields(xml, right, title)?;
+    }
+    xml.end("text:p")
+}
+
+fn write_fields(
+    xml: &mut XmlWriter<Vec<u8>>,
+    fields: &[HeaderFooterField],
+    title: &str,
+) -> Result<()> {
+    for field in fields {
+        match field {
+            HeaderFooterField::Text(text) => xml.text(text)?,
+            HeaderFooterField::DocumentTitle => xml.text(title)?,
+            HeaderFooterField::PageNumber => {
+                xml.element("text:page-number", &[("text:select-page", "current")], "1")?
+            }
+            HeaderFooterField::TotalPages => xml.element("text:page-count", &[], "1")?,
+            HeaderFooterField::ChapterName => xml.empty(
+                "text:chapter",
+                &[("text:display", "name"), ("text:outline-level", "1")],
+            )?,
+            HeaderFooterField::StyleRef(level) => xml.empty(
+                "text:chapter",
+                &[
+                    ("text:display", "name"),
+                    ("text:outline-level", &level.to_string()),
+                ],
+            )?,
+            // Read from hidden paragraphs of the chapter in DOCX; ODF has
+            // no field for them
+            HeaderFooterField::ChapterAuthor | HeaderFooterField::ChapterDate => {}
+            // Header and footer pictures are DOCX-only for now
+            HeaderFooterField::Image(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn is_empty(
+    left: &[HeaderFooterField],
+    center: &[HeaderFooterField],
+    right: &[HeaderFooterField],
+) -> bool {
+    left.is_empty() && center.is_empty() && right.is_empty()
+}
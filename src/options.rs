@@ -0,0 +1,216 @@
+//! Conversion options shared by the WASM and HTTP APIs
+//!
+//! Mirrors the parts of [`DocumentConfig`] and [`PlaceholderContext`] a
+//! conversion without a project directory can use. Field names are
+//! camelCase, matching the JavaScript `ConvertOptions` object and the JSON
+//! `options` of the HTTP service.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::docx::ooxml::FontConfig;
+use crate::docx::{parse_length_to_twips, PageConfig};
+use crate::i18n::{Label, Strings};
+use crate::template::{PlaceholderContext, TemplateDir};
+use crate::{
+    DocumentConfig, Error, FileMap, IncludeConfig, Language, Result, TocConfig, TocPageNumbers,
+};
+
+/// Conversion options, deserialized from a camelCase `ConvertOptions` object
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct ConvertOptions {
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub base_path: PathBuf,
+    pub source_root: PathBuf,
+    pub template_dir: Option<PathBuf>,
+    pub toc: Option<TocOptions>,
+    pub fonts: Option<FontOptions>,
+    pub page: Option<PageOptions>,
+    pub placeholders: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct TocOptions {
+    pub enabled: Option<bool>,
+    pub depth: Option<u8>,
+    pub title: Option<String>,
+    pub after_cover: Option<bool>,
+    pub dot_leader: Option<bool>,
+    pub page_numbers: Option<String>,
+    pub hyperlinks: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct FontOptions {
+    pub default: Option<String>,
+    pub code: Option<String>,
+    pub normal_size: Option<u32>,
+    pub caption_size: Option<u32>,
+    pub code_size: Option<u32>,
+    pub normal_color: Option<String>,
+    pub h1_color: Option<String>,
+    pub caption_color: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct PageOptions {
+    pub width: Option<String>,
+    pub height: Option<String>,
+    pub margin_top: Option<String>,
+    pub margin_right: Option<String>,
+    pub margin_bottom: Option<String>,
+    pub margin_left: Option<String>,
+}
+
+impl ConvertOptions {
+    /// Language from the options, else the document's frontmatter
+    pub fn language(&self, markdown: &str) -> Result<Language> {
+        match &self.language {
+            Some(code) => Language::from_code(code)
+                .ok_or_else(|| Error::Config(format!("Unknown language '{}'", code))),
+            None => Ok(crate::parser::parse_frontmatter(markdown)
+                .0
+                .and_then(|fm| fm.language)
+                .and_then(|code| Language::from_code(&code))
+                .unwrap_or_default()),
+        }
+    }
+
+    /// Document configuration for everything but files and templates
+    pub fn document_config(&self, lang: Language) -> Result<DocumentConfig> {
+        Ok(DocumentConfig {
+            title: self.title.clone().unwrap_or_default(),
+            toc: match &self.toc {
+                Some(toc) => toc.toc_config(lang)?,
+                None => TocOptions::default().toc_config(lang)?,
+            },
+            fonts: self.fonts.as_ref().map(FontOptions::font_config),
+            page: self
+                .page
+                .as_ref()
+                .map(PageOptions::page_config)
+                .transpose()?,
+            base_path: Some(self.base_path.clone()),
+            includes: Some(IncludeConfig {
+                base_path: self.base_path.clone(),
+                source_root: self.source_root.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Placeholder values for the cover template
+    pub fn placeholder_context(&self) -> PlaceholderContext {
+        let mut ctx = PlaceholderContext::default();
+        if let Some(title) = &self.title {
+            ctx.set("title", title.as_str());
+        }
+        for (key, value) in &self.placeholders {
+            ctx.set(key, value.as_str());
+        }
+        ctx
+    }
+
+    /// Convert markdown, reading includes, images and templates from `files`
+    ///
+    /// Nothing is read from the file system: paths missing from the map are
+    /// reported like missing files.
+    pub fn convert(&self, markdown: &str, files: Arc<FileMap>) -> Result<Vec<u8>> {
+        let lang = self.language(markdown)?;
+
+        let templates = match &self.template_dir {
+            Some(dir) => Some(TemplateDir::from_files(dir, files.clone()).load_all()?),
+            None => None,
+        };
+
+        let mut config = self.document_config(lang)?;
+        config.header_footer_template = templates.as_ref().and_then(|t| t.header_footer.clone());
+        config.files = Some(files);
+
+        crate::markdown_to_docx_with_templates(
+            markdown,
+            lang,
+            &config,
+            templates.as_ref(),
+            &self.placeholder_context(),
+        )
+    }
+}
+
+impl TocOptions {
+    /// The library's TOC defaults, overridden by the given fields
+    fn toc_config(&self, lang: Language) -> Result<TocConfig> {
+        let defaults = TocConfig::default();
+        let page_numbers = match self.page_numbers.as_deref() {
+            None => defaults.page_numbers,
+            Some("right") => TocPageNumbers::Right,
+            Some("inline") => TocPageNumbers::Inline,
+            Some("none") => TocPageNumbers::None,
+            Some(other) => {
+                return Err(Error::Config(format!(
+                    "Unknown toc.pageNumbers '{}' (expected right, inline or none)",
+                    other
+                )))
+            }
+        };
+        let title = match &self.title {
+            Some(title) => title.clone(),
+            None => Strings::new(lang).get(Label::TableOfContents).to_string(),
+        };
+        Ok(TocConfig {
+            enabled: self.enabled.unwrap_or(defaults.enabled),
+            depth: self.depth.unwrap_or(defaults.depth),
+            title,
+            after_cover: self.after_cover.unwrap_or(defaults.after_cover),
+            dot_leader: self.dot_leader.unwrap_or(defaults.dot_leader),
+            page_numbers,
+            hyperlinks: self.hyperlinks.unwrap_or(defaults.hyperlinks),
+            ..defaults
+        })
+    }
+}
+
+impl FontOptions {
+    fn font_config(&self) -> FontConfig {
+        // Word measures font sizes in half-points
+        FontConfig {
+            default: self.default.clone(),
+            code: self.code.clone(),
+            normal_size: self.normal_size.map(|pt| pt * 2),
+            normal_color: self.normal_color.clone(),
+            h1_color: self.h1_color.clone(),
+            caption_size: self.caption_size.map(|pt| pt * 2),
+            caption_color: self.caption_color.clone(),
+            code_size: self.code_size.map(|pt| pt * 2),
+        }
+    }
+}
+
+impl PageOptions {
+    fn page_config(&self) -> Result<PageConfig> {
+        let length = |name: &str, value: &Option<String>| match value {
+            Some(value) => parse_length_to_twips(value)
+                .map(Some)
+                .ok_or_else(|| Error::Config(format!("Invalid page.{} '{}'", name, value))),
+            None => Ok(None),
+        };
+        Ok(PageConfig {
+            width: length("width", &self.width)?,
+            height: length("height", &self.height)?,
+            margin_top: length("marginTop", &self.margin_top)?,
+            margin_right: length("marginRight", &self.margin_right)?,
+            margin_bottom: length("marginBottom", &self.margin_bottom)?,
+            margin_left: length("marginLeft", &self.margin_left)?,
+            ..Default::default()
+        })
+    }
+}
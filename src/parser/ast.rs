@@ -26,6 +26,8 @@ pub struct Frontmatter {
     pub page_break_before: bool,
     pub header_override: Option<String>,
     pub language: Option<String>,
+    /// Tags used to include or exclude the file per output (`tags: [internal]`)
+    pub tags: Vec<String>,
     /// Additional custom fields
     pub extra: HashMap<String, String>,
 }
@@ -50,8 +52,15 @@ pub enum Block {
         filename: Option<String>,
         highlight_lines: Vec<u32>,
         show_line_numbers: bool,
+        /// `<N>` callout markers taken from line ends: (line, number),
+        /// lines counted from 1
+        callouts: Vec<(u32, u32)>,
     },
 
+    /// Descriptions of code callouts: the `<1> text` lines that follow a
+    /// code block with callout markers, as (number, text)
+    CodeCallouts(Vec<(u32, Vec<Inline>)>),
+
     /// Block quote (can contain nested blocks)
     BlockQuote(Vec<Block>),
 
@@ -78,6 +87,9 @@ pub enum Block {
         title: Option<String>,
         width: Option<String>,
         id: Option<String>, // For cross-references
+        /// Caption with formatting, from the title or a `Figure:` paragraph;
+        /// `alt` is the caption when unset
+        caption: Option<Vec<Inline>>,
     },
 
     /// Horizontal rule / thematic break
@@ -112,6 +124,28 @@ pub enum Block {
         font: String,
         blocks: Vec<Block>,
     },
+
+    /// Language region: blocks written in a language other than the document's.
+    /// Created from `::: {lang=en}` ... `:::` fenced divs
+    /// (or `<!-- {lang:en} -->` ... `<!-- {/lang} -->`).
+    LangGroup {
+        lang: String,
+        blocks: Vec<Block>,
+    },
+
+    /// Bilingual parallel text: (source, translation) rows rendered side by side.
+    /// Created from consecutive `::: translate` fenced divs, each holding the
+    /// source, a `---` line, then the translation.
+    ParallelText { rows: Vec<(Vec<Block>, Vec<Block>)> },
+
+    /// Images side by side as sub-figures (a), (b), ... under one caption.
+    /// Created from a `::: figures` fenced div; each figure is a
+    /// `Block::Image` whose caption (or alt text) is its sub-caption.
+    FigureGroup {
+        figures: Vec<Block>,
+        caption: Option<Vec<Inline>>,
+        id: Option<String>,
+    },
 }
 
 /// List item (can contain nested blocks)
@@ -196,6 +230,9 @@ pub enum Inline {
 
     /// Display math (inline context): $$...$$
     DisplayMath(String),
+
+    /// Keyboard key or UI element: [[Ctrl]]
+    Kbd(String),
 }
 
 /// Extract plain text from inline elements
@@ -208,7 +245,7 @@ pub fn extract_inline_text(inlines: &[Inline]) -> String {
                 extract_inline_text(inner)
             }
             Inline::BoldItalic(inner) => extract_inline_text(inner),
-            Inline::Code(code) => code.clone(),
+            Inline::Code(code) | Inline::Kbd(code) => code.clone(),
             Inline::Link { text, .. } => extract_inline_text(text),
             Inline::Image { alt, .. } => alt.clone(),
             Inline::FootnoteRef(_) => String::new(),
@@ -223,6 +260,38 @@ pub fn extract_inline_text(inlines: &[Inline]) -> String {
         .join("")
 }
 
+/// Balloon shown for a code callout: ❶ to ❿, then ⓫ to ⓴, else `(N)`
+pub fn callout_glyph(number: u32) -> String {
+    let glyph = match number {
+        1..=10 => char::from_u32(0x2776 + number - 1),
+        11..=20 => char::from_u32(0x24EB + number - 11),
+        _ => None,
+    };
+    glyph.map_or_else(|| format!("({})", number), String::from)
+}
+
+/// Code with each callout's balloon appended to its line
+pub fn code_with_callouts(content: &str, callouts: &[(u32, u32)]) -> String {
+    if callouts.is_empty() {
+        return content.to_string();
+    }
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    for &(line, number) in callouts {
+        if let Some(text) = (line as usize)
+            .checked_sub(1)
+            .and_then(|i| lines.get_mut(i))
+        {
+            text.push(' ');
+            text.push_str(&callout_glyph(number));
+        }
+    }
+    let mut code = lines.join("\n");
+    if content.ends_with('\n') {
+        code.push('\n');
+    }
+    code
+}
+
 /// Type of cross-reference
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RefType {
@@ -272,6 +341,7 @@ impl Block {
             filename: None,
             highlight_lines: Vec::new(),
             show_line_numbers: false,
+            callouts: Vec::new(),
         }
     }
 }
@@ -121,6 +121,8 @@ pub fn parse_frontmatter(input: &str) -> (Option<Frontmatter>, &str) {
 /// Handles simple key: value pairs without full YAML parser
 fn parse_yaml_frontmatter(yaml: &str) -> Option<Frontmatter> {
     let mut frontmatter = Frontmatter::default();
+    // Key of a block list (`tags:` followed by `- item` lines)
+    let mut list_key: Option<String> = None;
 
     for line in yaml.lines() {
         let line = line.trim();
@@ -130,13 +132,22 @@ fn parse_yaml_frontmatter(yaml: &str) -> Option<Frontmatter> {
             continue;
         }
 
+        if let Some(item) = line.strip_prefix("- ") {
+            if list_key.as_deref() == Some("tags") {
+                frontmatter.tags.extend(parse_yaml_value(item));
+            }
+            continue;
+        }
+        list_key = None;
+
         // Parse key: value
         if let Some(colon_pos) = line.find(':') {
             let key = line[..colon_pos].trim();
             let value = line[colon_pos + 1..].trim();
 
-            // Skip if value is empty (might be a nested structure)
+            // Skip if value is empty (might be a nested structure or list)
             if value.is_empty() {
+                list_key = Some(key.to_string());
                 continue;
             }
 
@@ -152,6 +163,7 @@ fn parse_yaml_frontmatter(yaml: &str) -> Option<Frontmatter> {
                 "page_break_before" => frontmatter.page_break_before = parse_bool(value),
                 "header_override" => frontmatter.header_override = parsed_value,
                 "language" | "lang" => frontmatter.language = parsed_value,
+                "tags" => frontmatter.tags = parse_yaml_list(value),
                 _ => {
                     // Unknown keys go to extra HashMap
                     if let Some(val) = parsed_value {
@@ -186,6 +198,16 @@ fn parse_yaml_value(value: &str) -> Option<String> {
     }
 }
 
+/// Parse a YAML flow list (`[a, "b"]`) or a single scalar into strings
+fn parse_yaml_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let items = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    items.split(',').filter_map(parse_yaml_value).collect()
+}
+
 /// Parse a boolean value from YAML
 fn parse_bool(value: &str) -> bool {
     let value = value.trim().to_lowercase();
@@ -665,4 +687,24 @@ Content
         assert_eq!(fm.title, Some("Test".to_string()));
         assert!(fm.skip_toc);
     }
+
+    #[test]
+    fn test_parse_frontmatter_tags() {
+        let (fm, _) = parse_frontmatter("---\ntags: [internal, \"draft\"]\n---\n");
+        assert_eq!(fm.unwrap().tags, vec!["internal", "draft"]);
+
+        let (fm, _) = parse_frontmatter("---\ntags: internal\n---\n");
+        assert_eq!(fm.unwrap().tags, vec!["internal"]);
+
+        let md = r#"---
+tags:
+  - internal
+  - 'pricing'
+title: "Costs"
+---
+"#;
+        let fm = parse_frontmatter(md).0.unwrap();
+        assert_eq!(fm.tags, vec!["internal", "pricing"]);
+        assert_eq!(fm.title, Some("Costs".to_string()));
+    }
 }
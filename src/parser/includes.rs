@@ -5,14 +5,23 @@
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use crate::diagnostics::SourceLocation;
 use crate::error::{Error, Result};
-use crate::parser::{parse_markdown, Block};
+use crate::parser::{
+    is_remote_include, parse_markdown, parse_remote_include, resolve_image_paths, Block,
+    RemoteIncludes,
+};
+use crate::vfs::{self, FileMap};
 
 /// Configuration for include resolution
 #[derive(Debug, Clone)]
 pub struct IncludeConfig {
     /// Base directory for relative paths (usually the document directory)
+    ///
+    /// Included files resolve their own includes and images against their
+    /// directory instead.
     pub base_path: PathBuf,
     /// Root directory for code includes (from config: source_root)
     pub source_root: PathBuf,
@@ -35,6 +44,10 @@ pub struct IncludeResolver {
     config: IncludeConfig,
     /// Track included files to detect cycles
     include_stack: Vec<PathBuf>,
+    /// Files to read includes from instead of the file system
+    files: Option<Arc<FileMap>>,
+    /// Fetches `{!include:https://...}` targets; URLs are rejected without it
+    remote: Option<RemoteIncludes>,
 }
 
 impl IncludeResolver {
@@ -42,9 +55,23 @@ impl IncludeResolver {
         Self {
             config,
             include_stack: Vec::new(),
+            files: None,
+            remote: None,
         }
     }
 
+    /// Allow including markdown from URLs
+    pub fn with_remote(mut self, remote: RemoteIncludes) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Read included files from an in-memory file map
+    pub fn with_files(mut self, files: Arc<FileMap>) -> Self {
+        self.files = Some(files);
+        self
+    }
+
     /// Resolve all include directives in a list of blocks
     /// Returns new blocks with includes expanded
     pub fn resolve_blocks(&mut self, blocks: Vec<Block>) -> Result<Vec<Block>> {
@@ -53,7 +80,7 @@ impl IncludeResolver {
         for block in blocks {
             match block {
                 Block::Include { path, .. } => {
-                    let included = self.resolve_include(&path)?;
+                    let included = self.resolve_include(&path).map_err(|e| self.locate(e))?;
                     result.extend(included);
                 }
                 Block::CodeInclude {
@@ -62,14 +89,19 @@ impl IncludeResolver {
                     end_line,
                     lang,
                 } => {
-                    let code_block =
-                        self.resolve_code(&path, start_line, end_line, lang.as_deref())?;
+                    let code_block = self
+                        .resolve_code(&path, start_line, end_line, lang.as_deref())
+                        .map_err(|e| self.locate(e))?;
                     result.push(code_block);
                 }
                 Block::BlockQuote(inner) => {
                     let resolved_inner = self.resolve_blocks(inner)?;
                     result.push(Block::BlockQuote(resolved_inner));
                 }
+                Block::LangGroup { lang, blocks } => {
+                    let blocks = self.resolve_blocks(blocks)?;
+                    result.push(Block::LangGroup { lang, blocks });
+                }
                 Block::List {
                     ordered,
                     start,
@@ -100,11 +132,23 @@ impl IncludeResolver {
     }
 
     /// Resolve a markdown include directive
-    fn resolve_include(&mut self, path: &str) -> Result<Vec<Block>> {
-        let full_path = self.config.base_path.join(path);
-        let canonical = full_path
-            .canonicalize()
-            .map_err(|e| Error::Include(format!("Cannot resolve path {}: {}", path, e)))?;
+    ///
+    /// Returns the included file's blocks with their own includes expanded.
+    /// Bounded builds call this per top-level include, so only the chapter
+    /// being built is held in memory.
+    pub fn resolve_include(&mut self, path: &str) -> Result<Vec<Block>> {
+        if is_remote_include(path) {
+            return self.resolve_remote(path);
+        }
+        let full_path = self.current_dir().join(path);
+        let canonical = match &self.files {
+            // Rooted, so image paths rewritten against it are not joined
+            // to the base path a second time
+            Some(_) => Path::new("/").join(vfs::normalize(&full_path)),
+            None => full_path
+                .canonicalize()
+                .map_err(|e| Error::Include(format!("Cannot resolve path {}: {}", path, e)))?,
+        };
 
         // Check for cycles
         if self.include_stack.contains(&canonical) {
@@ -123,9 +167,13 @@ impl IncludeResolver {
         }
 
         // Read the file
-        let content = fs::read_to_string(&canonical)
+        let content = self
+            .read(&canonical)
             .map_err(|e| Error::Include(format!("Cannot read {}: {}", path, e)))?;
 
+        // Images are relative to the included file, not the document
+        let content = resolve_image_paths(&content, &canonical);
+
         // Push to stack before parsing (to detect cycles in nested includes)
         self.include_stack.push(canonical.clone());
 
@@ -141,6 +189,56 @@ impl IncludeResolver {
         Ok(resolved)
     }
 
+    /// Resolve an `{!include:https://...}` directive
+    ///
+    /// The includes nested in the downloaded markdown are expanded (or
+    /// rejected) by [`RemoteIncludes::load`], so its blocks are not resolved
+    /// against local files.
+    fn resolve_remote(&self, target: &str) -> Result<Vec<Block>> {
+        let remote = self.remote.as_ref().ok_or_else(|| {
+            Error::Include(format!("Remote includes are not enabled: {}", target))
+        })?;
+        if self.include_stack.len() >= self.config.max_depth as usize {
+            return Err(Error::Include(format!(
+                "Include depth exceeded (max {}): {}",
+                self.config.max_depth,
+                parse_remote_include(target).0
+            )));
+        }
+
+        let content = remote.load(target)?;
+        Ok(parse_markdown(&content).blocks)
+    }
+
+    /// Point an error from a directive at the included file it is in
+    ///
+    /// Directives of the top-level document have no file to point at.
+    fn locate(&self, error: Error) -> Error {
+        match self.include_stack.last() {
+            Some(file) => error.at(SourceLocation::file(file.as_path())),
+            None => error,
+        }
+    }
+
+    /// Directory relative includes are resolved against: that of the file
+    /// being included, or the base path for the top-level document
+    fn current_dir(&self) -> &Path {
+        self.include_stack
+            .last()
+            .and_then(|file| file.parent())
+            .unwrap_or(self.config.base_path.as_path())
+    }
+
+    /// Read a file from the file map if one is set, otherwise from disk
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        match &self.files {
+            Some(files) => files.read_to_string(path).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotFound, "not in file map")
+            }),
+            None => fs::read_to_string(path),
+        }
+    }
+
     /// Resolve a code include directive
     fn resolve_code(
         &self,
@@ -151,7 +249,8 @@ impl IncludeResolver {
     ) -> Result<Block> {
         let full_path = self.config.source_root.join(path);
 
-        let content = fs::read_to_string(&full_path)
+        let content = self
+            .read(&full_path)
             .map_err(|e| Error::Include(format!("Cannot read code file {}: {}", path, e)))?;
 
         // Extract lines if specified
@@ -201,6 +300,7 @@ impl IncludeResolver {
             filename: Some(path.to_string()),
             highlight_lines: vec![],
             show_line_numbers: false,
+            callouts: vec![],
         })
     }
 }
@@ -282,6 +382,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_remote_include_needs_opt_in() {
+        let mut resolver = IncludeResolver::new(IncludeConfig::default());
+        let err = resolver
+            .resolve_include("https://example.com/LICENSE.md")
+            .unwrap_err();
+        assert!(err.to_string().contains("not enabled"), "{}", err);
+    }
+
+    #[test]
+    fn test_nested_include_paths_are_relative_to_the_including_file() {
+        let temp_dir = TempDir::new().unwrap();
+        create_temp_file(&temp_dir, "chapters/intro.md", "{!include:parts/one.md}");
+        create_temp_file(
+            &temp_dir,
+            "chapters/parts/one.md",
+            "Part one\n\n![Diagram](img/diagram.png)",
+        );
+
+        let mut resolver = IncludeResolver::new(IncludeConfig {
+            base_path: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        let blocks = resolver.resolve_include("chapters/intro.md").unwrap();
+
+        let parts = temp_dir
+            .path()
+            .join("chapters/parts")
+            .canonicalize()
+            .unwrap();
+        let expected = parts.join("img/diagram.png");
+        let expected = expected.to_string_lossy().replace('\\', "/");
+        let has_image = |src: &str| {
+            blocks.iter().any(|block| match block {
+                Block::Image { src: s, .. } => s == src,
+                Block::Paragraph(inlines) => inlines
+                    .iter()
+                    .any(|i| matches!(i, crate::parser::Inline::Image { src: s, .. } if s == src)),
+                _ => false,
+            })
+        };
+        assert!(blocks.len() >= 2);
+        assert!(has_image(&expected), "{:?}", blocks);
+    }
+
+    #[test]
+    fn test_resolve_include_from_file_map() {
+        let mut files = FileMap::new();
+        files.insert("docs/chapters/intro.md", b"{!include:one.md}".to_vec());
+        files.insert("docs/chapters/one.md", b"![Diagram](img/d.png)".to_vec());
+        files.insert("src/main.rs", b"fn main() {}".to_vec());
+
+        let mut resolver = IncludeResolver::new(IncludeConfig {
+            base_path: PathBuf::from("docs"),
+            source_root: PathBuf::from("src"),
+            ..Default::default()
+        })
+        .with_files(Arc::new(files));
+
+        let blocks = resolver.resolve_include("chapters/intro.md").unwrap();
+        let src = match &blocks[..] {
+            [Block::Image { src, .. }] => src.clone(),
+            [Block::Paragraph(inlines)] => match &inlines[..] {
+                [crate::parser::Inline::Image { src, .. }] => src.clone(),
+                other => panic!("Expected image, got {:?}", other),
+            },
+            other => panic!("Expected image, got {:?}", other),
+        };
+        assert_eq!(src, "/docs/chapters/img/d.png");
+
+        match resolver.resolve_code("main.rs", None, None, None).unwrap() {
+            Block::CodeBlock { content, lang, .. } => {
+                assert_eq!(content, "fn main() {}");
+                assert_eq!(lang.as_deref(), Some("rust"));
+            }
+            _ => panic!("Expected CodeBlock"),
+        }
+        assert!(resolver.resolve_include("missing.md").is_err());
+    }
+
     #[test]
     fn test_include_config_default() {
         let config = IncludeConfig::default();
@@ -2,6 +2,7 @@
 //!
 //! Converts raw markdown text into our AST types defined in `ast.rs`.
 
+use crate::diagnostics::WarningCategory;
 use crate::parser::ast::*;
 use once_cell::sync::Lazy;
 use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
@@ -33,6 +34,11 @@ static TABLE_CAPTION_NO_ID_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^Table:\s*(.*)$").expect("TABLE_CAPTION_NO_ID_PATTERN regex should be valid")
 });
 
+/// Matches `[^label]` footnote references in image titles
+static FOOTNOTE_REF_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\[\^([^\]\s]+)\]").expect("FOOTNOTE_REF_PATTERN regex should be valid")
+});
+
 /// Matches `<!-- {font:FontName} -->` to start a font override region
 static FONT_GROUP_START: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"<!--\s*\{font:([^}]+)\}\s*-->"#).expect("FONT_GROUP_START regex should be valid")
@@ -43,6 +49,79 @@ static FONT_GROUP_END: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"<!--\s*\{/font\}\s*-->"#).expect("FONT_GROUP_END regex should be valid")
 });
 
+/// Matches `::: {lang=en}` opening a language fenced div
+static LANG_DIV_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^ {0,3}:{3,}\s*\{\s*lang\s*=\s*"?([A-Za-z_-]+)"?\s*\}\s*$"#)
+        .expect("LANG_DIV_START regex should be valid")
+});
+
+/// Matches `::: translate` opening a source/translation pair
+static TRANSLATE_DIV_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^ {0,3}:{3,}\s*(?:translate|\{\s*\.?translate\s*\})\s*$")
+        .expect("TRANSLATE_DIV_START regex should be valid")
+});
+
+/// Matches `::: figures` (optionally `{#fig:id}`) opening a group of sub-figures
+static FIGURES_DIV_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^ {0,3}:{3,}\s*figures\s*(?:\{\s*#([a-zA-Z0-9_:-]+)\s*\})?\s*$")
+        .expect("FIGURES_DIV_START regex should be valid")
+});
+
+/// Matches a bare `:::` closing a fenced div
+static DIV_END: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^ {0,3}:{3,}\s*$").expect("DIV_END regex should be valid"));
+
+/// Matches `<!-- {lang:en} -->` to start a language region
+static LANG_GROUP_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<!--\s*\{lang:([^}]+)\}\s*-->"#).expect("LANG_GROUP_START regex should be valid")
+});
+
+/// Matches `<!-- {/lang} -->` to end a language region
+static LANG_GROUP_END: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<!--\s*\{/lang\}\s*-->"#).expect("LANG_GROUP_END regex should be valid")
+});
+
+/// Matches `<!-- {translate} -->` to start a source/translation pair
+static TRANSLATE_GROUP_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<!--\s*\{translate\}\s*-->").expect("TRANSLATE_GROUP_START regex should be valid")
+});
+
+/// Matches `<!-- {/translate} -->` to end a source/translation pair
+static TRANSLATE_GROUP_END: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<!--\s*\{/translate\}\s*-->").expect("TRANSLATE_GROUP_END regex should be valid")
+});
+
+/// Matches `<!-- {figures} -->` or `<!-- {figures:id} -->` to start a figure group
+static FIGURE_GROUP_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<!--\s*\{figures(?::([^}]+))?\}\s*-->")
+        .expect("FIGURE_GROUP_START regex should be valid")
+});
+
+/// Matches `<!-- {/figures} -->` to end a figure group
+static FIGURE_GROUP_END: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<!--\s*\{/figures\}\s*-->").expect("FIGURE_GROUP_END regex should be valid")
+});
+
+/// Matches `{ref:target}` cross-references in text
+static CROSS_REF_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{ref:([a-zA-Z0-9_:-]+)\}").expect("CROSS_REF_PATTERN regex should be valid")
+});
+
+/// Matches `<1>` callout markers at the end of a code line, with the line
+/// comment they sit in (`// <1>`, `# <1> <2>`)
+static CODE_CALLOUT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|\s+)(?:(?://|#|--|;+)\s*)?((?:<\d+>\s*)+)$")
+        .expect("CODE_CALLOUT_PATTERN regex should be valid")
+});
+
+/// Matches a `<1> description` line after a code block with callouts
+static CALLOUT_ITEM_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<(\d+)>\s*").expect("CALLOUT_ITEM_PATTERN regex should be valid"));
+
+/// Matches `[[Ctrl]]` keyboard keys and UI elements in text
+static KBD_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\[\]\n]+)\]\]").expect("KBD_PATTERN regex should be valid"));
+
 /// Builder for footnote definitions
 struct FootnoteBuilder {
     name: String,
@@ -51,7 +130,8 @@ struct FootnoteBuilder {
 
 /// Parse markdown text into a ParsedDocument
 pub fn parse_markdown(input: &str) -> ParsedDocument {
-    let parser = Parser::new_ext(input, get_parser_options());
+    let input = rewrite_fenced_divs(input);
+    let parser = Parser::new_ext(&input, get_parser_options());
 
     let mut blocks = Vec::new();
     let mut footnotes = HashMap::new();
@@ -294,51 +374,20 @@ pub fn parse_markdown(input: &str) -> ParsedDocument {
                                 }
                             }
                         } else if let Some(BlockBuilder::Paragraph(_)) = current_block.take() {
-                            // Check for single image or image + attributes
-                            // Case 1: Paragraph contains only an image
-                            // Case 2: Paragraph contains image + attributes text (e.g. {width=50%})
-                            let is_image_block = if current_inlines.len() == 1 {
-                                matches!(current_inlines[0], Inline::Image { .. })
-                            } else if current_inlines.len() == 2 {
-                                matches!(current_inlines[0], Inline::Image { .. })
-                                    && matches!(current_inlines[1], Inline::Text(ref t) if extract_image_attributes(t).is_some())
-                            } else {
-                                false
-                            };
-
-                            if is_image_block {
-                                let (image, width) = if current_inlines.len() == 1 {
-                                    (current_inlines.remove(0), None)
-                                } else {
-                                    let attrs = current_inlines
-                                        .pop()
-                                        .expect("attrs should exist when len == 2"); // Text
-                                    let img = current_inlines.remove(0); // Image
-                                    let width = if let Inline::Text(t) = attrs {
-                                        extract_image_attributes(&t)
-                                    } else {
-                                        None
-                                    };
-                                    (img, width)
-                                };
-
-                                if let Inline::Image { alt, src, title } = image {
-                                    add_block_to_correct_stack(
-                                        &mut blocks,
-                                        &mut footnote_builder,
-                                        &mut list_stack,
-                                        &mut block_stack,
-                                        Block::Image {
-                                            alt,
-                                            src,
-                                            title,
-                                            width,
-                                            id: None,
-                                        },
-                                    );
-                                    current_inlines = Vec::new();
-                                    continue;
-                                }
+                            // A paragraph holding only an image and its attributes
+                            // (e.g. {width=50%}) becomes an image block
+                            if let Some(mut images) = paragraph_images(&current_inlines)
+                                .filter(|images| images.len() == 1)
+                            {
+                                add_block_to_correct_stack(
+                                    &mut blocks,
+                                    &mut footnote_builder,
+                                    &mut list_stack,
+                                    &mut block_stack,
+                                    images.remove(0),
+                                );
+                                current_inlines = Vec::new();
+                                continue;
                             }
                             add_block_to_correct_stack(
                                 &mut blocks,
@@ -407,12 +456,15 @@ pub fn parse_markdown(input: &str) -> ParsedDocument {
                             let block = if lang.as_deref() == Some("mermaid") {
                                 Block::Mermaid { content, id: None }
                             } else {
+                                let (content, callouts) =
+                                    extract_code_callouts(content, lang.as_deref());
                                 Block::CodeBlock {
                                     lang,
                                     content,
                                     filename,
                                     highlight_lines,
                                     show_line_numbers,
+                                    callouts,
                                 }
                             };
                             add_block_to_correct_stack(
@@ -745,7 +797,7 @@ pub fn parse_markdown(input: &str) -> ParsedDocument {
                     }
                 } else if !list_stack.is_empty() {
                     if matches!(current_block, Some(BlockBuilder::Paragraph(_)) | Some(BlockBuilder::Heading { .. })) {
-                        current_inlines.push(Inline::InlineMath(math));
+                        add_inline_to_stack(&mut inline_stack, &mut current_inlines, Inline::InlineMath(math));
                     } else if inline_stack.is_empty() {
                         list_item_inlines.push(Inline::InlineMath(math));
                     } else {
@@ -756,12 +808,12 @@ pub fn parse_markdown(input: &str) -> ParsedDocument {
                 } else if let Some(block) = current_block.as_mut() {
                     match block {
                         BlockBuilder::Heading { .. } | BlockBuilder::Paragraph(_) => {
-                            current_inlines.push(Inline::InlineMath(math));
+                            add_inline_to_stack(&mut inline_stack, &mut current_inlines, Inline::InlineMath(math));
                         }
                         _ => {}
                     }
                 } else if footnote_builder.is_some() || !block_stack.is_empty() {
-                    current_inlines.push(Inline::InlineMath(math));
+                    add_inline_to_stack(&mut inline_stack, &mut current_inlines, Inline::InlineMath(math));
                 }
             }
 
@@ -940,12 +992,27 @@ pub fn parse_markdown(input: &str) -> ParsedDocument {
     // Process cross-references
     let blocks = process_blocks_for_cross_refs(blocks);
 
+    // Group images in `::: figures` regions into sub-figures
+    let blocks = process_figure_groups(blocks);
+
+    // Attach `Figure:` paragraphs to the images they follow
+    let blocks = process_figure_captions(blocks);
+
     // Process include directives
     let blocks = process_include_directives(blocks);
 
+    // Process language regions: <!-- {lang:en} --> ... <!-- {/lang} -->
+    let blocks = process_lang_groups(blocks);
+
     // Process font group directives: <!-- {font:Name} --> ... <!-- {/font} -->
     let blocks = process_font_groups(blocks);
 
+    // Pair sources with translations: <!-- {translate} --> ... <!-- {/translate} -->
+    let blocks = process_translate_groups(blocks);
+
+    // Turn `<1> text` paragraphs after code with callouts into descriptions
+    let blocks = process_code_callouts(blocks);
+
     ParsedDocument {
         frontmatter: None,
         blocks,
@@ -1060,6 +1127,144 @@ fn process_blocks_for_cross_refs(blocks: Vec<Block>) -> Vec<Block> {
         .collect()
 }
 
+/// Parse an image title as caption inlines
+///
+/// Footnote definitions live elsewhere in the document, so each `[^label]`
+/// in the title gets a stub definition for its reference to parse.
+fn parse_caption(title: &str) -> Vec<Inline> {
+    let mut input = title.to_string();
+    for cap in FOOTNOTE_REF_PATTERN.captures_iter(title) {
+        input.push_str(&format!("\n\n[^{}]: -", &cap[1]));
+    }
+    match parse_markdown(&input).blocks.into_iter().next() {
+        Some(Block::Paragraph(inlines)) => inlines,
+        _ => vec![Inline::Text(title.to_string())],
+    }
+}
+
+/// Use a `Figure: ...` paragraph right after an image as its caption
+fn process_figure_captions(blocks: Vec<Block>) -> Vec<Block> {
+    let mut result: Vec<Block> = Vec::new();
+
+    for block in blocks {
+        let block = match block {
+            Block::Paragraph(inlines) => {
+                if let Some(Block::Image { caption, .. } | Block::FigureGroup { caption, .. }) =
+                    result.last_mut()
+                {
+                    if caption.is_none() {
+                        if let Some(text) = figure_caption(&inlines) {
+                            *caption = Some(text);
+                            continue;
+                        }
+                    }
+                }
+                Block::Paragraph(inlines)
+            }
+            Block::BlockQuote(inner) => Block::BlockQuote(process_figure_captions(inner)),
+            Block::List {
+                ordered,
+                start,
+                items,
+            } => Block::List {
+                ordered,
+                start,
+                items: items
+                    .into_iter()
+                    .map(|item| ListItem {
+                        content: process_figure_captions(item.content),
+                        checked: item.checked,
+                    })
+                    .collect(),
+            },
+            other => other,
+        };
+        result.push(block);
+    }
+
+    result
+}
+
+/// Group the images between figure group markers into `Block::FigureGroup`
+///
+/// `::: figures` fenced divs are rewritten to `<!-- {figures} -->` and
+/// `<!-- {/figures} -->`. Each image in the region becomes a sub-figure, and
+/// a `Figure:` paragraph in it is the shared caption. Other blocks don't fit
+/// the side-by-side layout and follow the group instead.
+fn process_figure_groups(blocks: Vec<Block>) -> Vec<Block> {
+    let mut result = Vec::new();
+    let mut iter = blocks.into_iter();
+
+    while let Some(block) = iter.next() {
+        match block {
+            Block::Html(ref html) if FIGURE_GROUP_START.is_match(html.trim()) => {
+                let id = FIGURE_GROUP_START
+                    .captures(html.trim())
+                    .and_then(|cap| cap.get(1))
+                    .map(|m| m.as_str().trim().to_string());
+                let mut figures = Vec::new();
+                let mut caption = None;
+                let mut rest = Vec::new();
+                for inner_block in iter.by_ref() {
+                    match inner_block {
+                        Block::Html(ref inner_html)
+                            if FIGURE_GROUP_END.is_match(inner_html.trim()) =>
+                        {
+                            break
+                        }
+                        image @ Block::Image { .. } => figures.push(image),
+                        Block::Paragraph(inlines) => {
+                            if let Some(images) = paragraph_images(&inlines) {
+                                figures.extend(images);
+                            } else if let Some(text) = figure_caption(&inlines) {
+                                caption = Some(text);
+                            } else {
+                                rest.push(Block::Paragraph(inlines));
+                            }
+                        }
+                        other => rest.push(other),
+                    }
+                }
+                if !rest.is_empty() {
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        "::: figures block holds more than images and a Figure: caption; the rest is placed after it",
+                    );
+                }
+                result.push(Block::FigureGroup {
+                    figures,
+                    caption,
+                    id,
+                });
+                result.extend(rest);
+            }
+            Block::Html(ref html) if FIGURE_GROUP_END.is_match(html.trim()) => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    "Found <!-- {/figures} --> without matching <!-- {figures} -->",
+                );
+            }
+            other => result.push(other),
+        }
+    }
+
+    result
+}
+
+/// Caption inlines of a paragraph starting with `Figure:`
+fn figure_caption(inlines: &[Inline]) -> Option<Vec<Inline>> {
+    let Some(Inline::Text(first)) = inlines.first() else {
+        return None;
+    };
+    let rest = first.strip_prefix("Figure:")?.trim_start();
+    let mut caption = Vec::with_capacity(inlines.len());
+    if !rest.is_empty() {
+        caption.push(Inline::Text(rest.to_string()));
+    }
+    caption.extend_from_slice(&inlines[1..]);
+    (!caption.is_empty()).then_some(caption)
+}
+
 /// Process blocks to detect include directives
 fn process_include_directives(blocks: Vec<Block>) -> Vec<Block> {
     blocks
@@ -1196,7 +1401,10 @@ fn process_font_groups(blocks: Vec<Block>) -> Vec<Block> {
                     });
                 } else if FONT_GROUP_END.is_match(html.trim()) {
                     // Stray end tag without matching start — skip it
-                    eprintln!("Warning: Found <!-- {{/font}} --> without matching <!-- {{font:...}} -->");
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        "Found <!-- {/font} --> without matching <!-- {font:...} -->",
+                    );
                 } else {
                     result.push(block);
                 }
@@ -1231,6 +1439,13 @@ fn process_font_groups(blocks: Vec<Block>) -> Vec<Block> {
                     blocks: process_font_groups(blocks.clone()),
                 });
             }
+            // Recursively process inside language regions
+            Block::LangGroup { lang, blocks } => {
+                result.push(Block::LangGroup {
+                    lang: lang.clone(),
+                    blocks: process_font_groups(blocks.clone()),
+                });
+            }
             _ => result.push(block),
         }
     }
@@ -1238,15 +1453,342 @@ fn process_font_groups(blocks: Vec<Block>) -> Vec<Block> {
     result
 }
 
+/// Rewrite `::: {lang=xx}` and `::: translate` fenced divs into comments
+///
+/// pulldown-cmark has no fenced divs, so the opening and closing fence lines
+/// become `<!-- {lang:xx} -->` / `<!-- {translate} -->` and their matching
+/// `<!-- {/lang} -->` / `<!-- {/translate} -->` HTML blocks, one line each so
+/// source line numbers don't shift. Fences inside code blocks are left alone.
+fn rewrite_fenced_divs(input: &str) -> std::borrow::Cow<'_, str> {
+    if !input.lines().any(|line| {
+        LANG_DIV_START.is_match(line)
+            || TRANSLATE_DIV_START.is_match(line)
+            || FIGURES_DIV_START.is_match(line)
+    }) {
+        return std::borrow::Cow::Borrowed(input);
+    }
+
+    let mut out = String::with_capacity(input.len());
+    let mut code_fence: Option<String> = None;
+    // Closing comment for each open div
+    let mut open_divs: Vec<&str> = Vec::new();
+    for line in input.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let trimmed = content.trim_start();
+        let marker = if let Some(ref fence) = code_fence {
+            if trimmed.starts_with(fence.as_str()) {
+                code_fence = None;
+            }
+            None
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let marker = trimmed.chars().next().unwrap_or('`');
+            let len = trimmed.chars().take_while(|&c| c == marker).count();
+            code_fence = Some(marker.to_string().repeat(len));
+            None
+        } else if let Some(cap) = LANG_DIV_START.captures(content) {
+            open_divs.push("<!-- {/lang} -->");
+            Some(format!("<!-- {{lang:{}}} -->", &cap[1]))
+        } else if TRANSLATE_DIV_START.is_match(content) {
+            open_divs.push("<!-- {/translate} -->");
+            Some("<!-- {translate} -->".to_string())
+        } else if let Some(cap) = FIGURES_DIV_START.captures(content) {
+            open_divs.push("<!-- {/figures} -->");
+            Some(match cap.get(1) {
+                Some(id) => format!("<!-- {{figures:{}}} -->", id.as_str()),
+                None => "<!-- {figures} -->".to_string(),
+            })
+        } else if DIV_END.is_match(content) {
+            open_divs.pop().map(str::to_string)
+        } else {
+            None
+        };
+
+        match marker {
+            Some(marker) => {
+                out.push_str(&marker);
+                out.push_str(&line[content.len()..]);
+            }
+            None => out.push_str(line),
+        }
+    }
+    std::borrow::Cow::Owned(out)
+}
+
+/// Group blocks between language region markers into `Block::LangGroup`
+///
+/// Works like [`process_font_groups`] for `<!-- {lang:xx} -->` and
+/// `<!-- {/lang} -->`, which `::: {lang=xx}` fenced divs are rewritten to.
+fn process_lang_groups(blocks: Vec<Block>) -> Vec<Block> {
+    let mut result = Vec::new();
+    let mut iter = blocks.into_iter();
+
+    while let Some(block) = iter.next() {
+        match block {
+            Block::Html(ref html) if LANG_GROUP_START.is_match(html.trim()) => {
+                let lang = LANG_GROUP_START
+                    .captures(html.trim())
+                    .and_then(|cap| cap.get(1))
+                    .map(|m| m.as_str().trim().to_string())
+                    .unwrap_or_default();
+
+                // Collect all blocks until the matching <!-- {/lang} -->
+                let mut group_blocks = Vec::new();
+                let mut depth = 1u32;
+                for inner_block in iter.by_ref() {
+                    if let Block::Html(ref inner_html) = inner_block {
+                        if LANG_GROUP_START.is_match(inner_html.trim()) {
+                            depth += 1;
+                        } else if LANG_GROUP_END.is_match(inner_html.trim()) {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                    }
+                    group_blocks.push(inner_block);
+                }
+
+                result.push(Block::LangGroup {
+                    lang,
+                    blocks: process_lang_groups(group_blocks),
+                });
+            }
+            Block::Html(ref html) if LANG_GROUP_END.is_match(html.trim()) => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    "Found <!-- {/lang} --> without matching <!-- {lang:...} -->",
+                );
+            }
+            Block::BlockQuote(inner) => result.push(Block::BlockQuote(process_lang_groups(inner))),
+            Block::List {
+                ordered,
+                start,
+                items,
+            } => result.push(Block::List {
+                ordered,
+                start,
+                items: items
+                    .into_iter()
+                    .map(|item| ListItem {
+                        content: process_lang_groups(item.content),
+                        checked: item.checked,
+                    })
+                    .collect(),
+            }),
+            other => result.push(other),
+        }
+    }
+
+    result
+}
+
+/// Turn `<!-- {translate} -->` regions into rows of a `Block::ParallelText`
+///
+/// Each region holds the source, a `---` line, then the translation.
+/// Consecutive regions become consecutive rows of one table.
+fn process_translate_groups(blocks: Vec<Block>) -> Vec<Block> {
+    let mut result: Vec<Block> = Vec::new();
+    let mut iter = blocks.into_iter();
+
+    while let Some(block) = iter.next() {
+        match block {
+            Block::Html(ref html) if TRANSLATE_GROUP_START.is_match(html.trim()) => {
+                let mut source = Vec::new();
+                let mut translation = Vec::new();
+                let mut in_translation = false;
+                for inner_block in iter.by_ref() {
+                    match inner_block {
+                        Block::Html(ref inner_html)
+                            if TRANSLATE_GROUP_END.is_match(inner_html.trim()) =>
+                        {
+                            break
+                        }
+                        Block::ThematicBreak if !in_translation => in_translation = true,
+                        other if in_translation => translation.push(other),
+                        other => source.push(other),
+                    }
+                }
+                if !in_translation {
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        "::: translate block has no '---' line between source and translation",
+                    );
+                }
+
+                let row = (source, translation);
+                match result.last_mut() {
+                    Some(Block::ParallelText { rows }) => rows.push(row),
+                    _ => result.push(Block::ParallelText { rows: vec![row] }),
+                }
+            }
+            Block::Html(ref html) if TRANSLATE_GROUP_END.is_match(html.trim()) => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    "Found <!-- {/translate} --> without matching <!-- {translate} -->",
+                );
+            }
+            Block::LangGroup { lang, blocks } => result.push(Block::LangGroup {
+                lang,
+                blocks: process_translate_groups(blocks),
+            }),
+            Block::FontGroup { font, blocks } => result.push(Block::FontGroup {
+                font,
+                blocks: process_translate_groups(blocks),
+            }),
+            other => result.push(other),
+        }
+    }
+
+    result
+}
+
+/// Strip `<N>` callout markers from the ends of code lines
+///
+/// Returns the code without the markers and their (line, number) pairs.
+/// Markdown code keeps its markers, so callout syntax can be shown as is.
+fn extract_code_callouts(content: String, lang: Option<&str>) -> (String, Vec<(u32, u32)>) {
+    if matches!(lang, Some("markdown" | "md"))
+        || !content
+            .lines()
+            .any(|line| CODE_CALLOUT_PATTERN.is_match(line))
+    {
+        return (content, Vec::new());
+    }
+
+    let mut callouts = Vec::new();
+    let mut code = String::with_capacity(content.len());
+    for (i, line) in content.lines().enumerate() {
+        match CODE_CALLOUT_PATTERN.captures(line) {
+            Some(cap) => {
+                let whole = cap
+                    .get(0)
+                    .expect("CODE_CALLOUT_PATTERN should have capture group 0");
+                let numbers = cap[1]
+                    .split(|c: char| c == '<' || c == '>' || c.is_whitespace())
+                    .filter_map(|n| n.parse().ok());
+                callouts.extend(numbers.map(|number| (i as u32 + 1, number)));
+                code.push_str(&line[..whole.start()]);
+            }
+            None => code.push_str(line),
+        }
+        code.push('\n');
+    }
+    if !content.ends_with('\n') {
+        code.pop();
+    }
+
+    (code, callouts)
+}
+
+/// Turn the paragraph after a code block with callouts into
+/// `Block::CodeCallouts` when every line starts with a `<N>` marker
+fn process_code_callouts(blocks: Vec<Block>) -> Vec<Block> {
+    let mut result: Vec<Block> = Vec::new();
+
+    for block in blocks {
+        let follows_callouts = matches!(
+            result.last(),
+            Some(Block::CodeBlock { callouts, .. }) if !callouts.is_empty()
+        );
+        match block {
+            Block::Paragraph(inlines) if follows_callouts => match callout_items(&inlines) {
+                Some(items) => result.push(Block::CodeCallouts(items)),
+                None => result.push(Block::Paragraph(inlines)),
+            },
+            Block::BlockQuote(inner) => {
+                result.push(Block::BlockQuote(process_code_callouts(inner)))
+            }
+            Block::List {
+                ordered,
+                start,
+                items,
+            } => result.push(Block::List {
+                ordered,
+                start,
+                items: items
+                    .into_iter()
+                    .map(|item| ListItem {
+                        content: process_code_callouts(item.content),
+                        checked: item.checked,
+                    })
+                    .collect(),
+            }),
+            Block::LangGroup { lang, blocks } => result.push(Block::LangGroup {
+                lang,
+                blocks: process_code_callouts(blocks),
+            }),
+            Block::FontGroup { font, blocks } => result.push(Block::FontGroup {
+                font,
+                blocks: process_code_callouts(blocks),
+            }),
+            other => result.push(other),
+        }
+    }
+
+    result
+}
+
+/// Split a paragraph into `<N> text` callout descriptions, one per line
+fn callout_items(inlines: &[Inline]) -> Option<Vec<(u32, Vec<Inline>)>> {
+    // Soft breaks reach paragraphs as a lone space, so a line also ends at
+    // a space followed by the next `<N>` marker
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (i, inline) in inlines.iter().enumerate() {
+        let ends_line = match inline {
+            Inline::SoftBreak | Inline::HardBreak => true,
+            Inline::Text(text) => text == " " && callout_marker(&inlines[i + 1..]).is_some(),
+            _ => false,
+        };
+        if ends_line {
+            lines.push(&inlines[start..i]);
+            start = i + 1;
+        }
+    }
+    lines.push(&inlines[start..]);
+
+    let mut items = Vec::new();
+    for line in lines {
+        let (number, lead_count, rest) = callout_marker(line)?;
+        let mut text = Vec::new();
+        if !rest.is_empty() {
+            text.push(Inline::Text(rest));
+        }
+        text.extend(line[lead_count..].iter().cloned());
+        items.push((number, text));
+    }
+    (!items.is_empty()).then_some(items)
+}
+
+/// Number of the `<N>` marker starting `line`, the number of text inlines
+/// it was read from and the text after the marker in them
+fn callout_marker(line: &[Inline]) -> Option<(u32, usize, String)> {
+    // `<` and the digits may arrive as separate text inlines
+    let lead = line
+        .iter()
+        .map_while(|i| match i {
+            Inline::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<String>();
+    let cap = CALLOUT_ITEM_PATTERN.captures(&lead)?;
+    let number = cap[1].parse().ok()?;
+    let lead_count = line
+        .iter()
+        .take_while(|i| matches!(i, Inline::Text(_)))
+        .count();
+    Some((number, lead_count, lead[cap[0].len()..].to_string()))
+}
+
 /// Process inlines to extract cross-references from text
-/// Converts `{ref:target}` patterns in text to Inline::CrossRef
+/// Converts `{ref:target}` patterns in text to Inline::CrossRef,
+/// and `[[Key]]` patterns to Inline::Kbd
 fn process_cross_refs(inlines: Vec<Inline>) -> Vec<Inline> {
-    let cross_ref_pattern = regex::Regex::new(r"\{ref:([a-zA-Z0-9_:-]+)\}")
-        .expect("cross_ref_pattern regex should be valid");
+    let cross_ref_pattern = &*CROSS_REF_PATTERN;
 
     let mut result = Vec::new();
 
-    for inline in inlines {
+    for inline in process_kbd(inlines) {
         match inline {
             Inline::Text(text) => {
                 let mut last_end = 0;
@@ -1311,6 +1853,51 @@ fn process_cross_refs(inlines: Vec<Inline>) -> Vec<Inline> {
     result
 }
 
+/// Convert `[[Key]]` patterns in text to Inline::Kbd
+///
+/// pulldown-cmark emits brackets as separate text events, so consecutive
+/// text inlines are joined before matching. Nested inlines are handled by
+/// the caller's recursion.
+fn process_kbd(inlines: Vec<Inline>) -> Vec<Inline> {
+    let mut result = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+
+    for inline in inlines {
+        if let Inline::Text(text) = inline {
+            pending.push(text);
+            continue;
+        }
+        flush_kbd_text(&mut pending, &mut result);
+        result.push(inline);
+    }
+    flush_kbd_text(&mut pending, &mut result);
+
+    result
+}
+
+/// Push pending text inlines, splitting out any `[[Key]]` matches
+fn flush_kbd_text(pending: &mut Vec<String>, result: &mut Vec<Inline>) {
+    let text = pending.concat();
+    if !KBD_PATTERN.is_match(&text) {
+        result.extend(pending.drain(..).map(Inline::Text));
+        return;
+    }
+    pending.clear();
+
+    let mut last_end = 0;
+    for cap in KBD_PATTERN.captures_iter(&text) {
+        let whole = cap.get(0).expect("KBD_PATTERN should have capture group 0");
+        if whole.start() > last_end {
+            result.push(Inline::Text(text[last_end..whole.start()].to_string()));
+        }
+        result.push(Inline::Kbd(cap[1].trim().to_string()));
+        last_end = whole.end();
+    }
+    if last_end < text.len() {
+        result.push(Inline::Text(text[last_end..].to_string()));
+    }
+}
+
 /// Parse reference target to extract type prefix
 /// "fig:diagram" -> (RefType::Figure, "diagram")
 /// "intro" -> (RefType::Unknown, "intro")
@@ -1337,13 +1924,27 @@ fn add_text_to_inline_stack(
     }
 }
 
+/// Add an inline element to the innermost open emphasis or link, or to
+/// `current_inlines` outside of one
+fn add_inline_to_stack(
+    inline_stack: &mut [InlineBuilder],
+    current_inlines: &mut Vec<Inline>,
+    inline: Inline,
+) {
+    if let Some(builder) = inline_stack.last_mut() {
+        builder.add_child(inline);
+    } else {
+        current_inlines.push(inline);
+    }
+}
+
 /// Add an inline element to the appropriate place
 fn add_inline(current_inlines: &mut Vec<Inline>, inline: Inline) {
     current_inlines.push(inline);
 }
 
 /// Get parser options for pulldown-cmark
-fn get_parser_options() -> Options {
+pub(super) fn get_parser_options() -> Options {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_FOOTNOTES);
@@ -1454,18 +2055,52 @@ fn extract_anchor_id(
     (content, None)
 }
 
-/// Extract image attributes like {width=50%} from text
-fn extract_image_attributes(text: &str) -> Option<String> {
-    let text = text.trim();
-    if text.starts_with("{width=") && text.ends_with('}') {
-        // Extract content between {width= and }
-        // Length of "{width=" is 7
-        if text.len() > 8 {
-            let width = &text[7..text.len() - 1];
-            return Some(width.to_string());
+/// Image attributes (width, id) from text like `{width=50%}` or `{#fig:map width=50%}`
+fn extract_image_attributes(text: &str) -> Option<(Option<String>, Option<String>)> {
+    let inner = text.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut width = None;
+    let mut id = None;
+    for item in inner.split_whitespace() {
+        if let Some(value) = item.strip_prefix("width=").filter(|v| !v.is_empty()) {
+            width = Some(value.to_string());
+        } else if let Some(value) = item.strip_prefix('#').filter(|v| !v.is_empty()) {
+            id = Some(value.to_string());
+        } else {
+            return None;
         }
     }
-    None
+    (width.is_some() || id.is_some()).then_some((width, id))
+}
+
+/// Image blocks of a paragraph holding only images and their attributes
+fn paragraph_images(inlines: &[Inline]) -> Option<Vec<Block>> {
+    let mut images = Vec::new();
+    for inline in inlines {
+        match inline {
+            Inline::Image { alt, src, title } => images.push(Block::Image {
+                alt: alt.clone(),
+                src: src.clone(),
+                title: title.clone(),
+                width: None,
+                id: None,
+                caption: title.as_deref().map(parse_caption),
+            }),
+            Inline::SoftBreak | Inline::HardBreak => {}
+            Inline::Text(text) if text.trim().is_empty() => {}
+            Inline::Text(text) => {
+                let (attr_width, attr_id) = extract_image_attributes(text)?;
+                match images.last_mut() {
+                    Some(Block::Image { width, id, .. }) => {
+                        *width = attr_width;
+                        *id = attr_id;
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+    (!images.is_empty()).then_some(images)
 }
 
 /// Extract `\label{...}` from LaTeX math content.
@@ -1526,13 +2161,17 @@ impl BlockBuilder {
                 filename,
                 highlight_lines,
                 show_line_numbers,
-            } => Block::CodeBlock {
-                lang,
-                content,
-                filename,
-                highlight_lines,
-                show_line_numbers,
-            },
+            } => {
+                let (content, callouts) = extract_code_callouts(content, lang.as_deref());
+                Block::CodeBlock {
+                    lang,
+                    content,
+                    filename,
+                    highlight_lines,
+                    show_line_numbers,
+                    callouts,
+                }
+            }
             BlockBuilder::BlockQuote(content) => Block::BlockQuote(content),
         }
     }
@@ -1697,12 +2336,14 @@ mod tests {
                 filename,
                 highlight_lines,
                 show_line_numbers,
+                callouts,
             } => {
                 assert_eq!(lang, &Some("rust".to_string()));
                 assert!(content.contains("println!"));
                 assert!(filename.is_none());
                 assert!(highlight_lines.is_empty());
                 assert!(!show_line_numbers);
+                assert!(callouts.is_empty());
             }
             _ => panic!("Expected CodeBlock"),
         }
@@ -1716,10 +2357,10 @@ mod tests {
         match &doc.blocks[0] {
             Block::CodeBlock {
                 lang,
-                content: _,
                 filename,
                 highlight_lines,
                 show_line_numbers,
+                ..
             } => {
                 assert_eq!(lang, &Some("rust".to_string()));
                 assert_eq!(filename, &Some("main.rs".to_string()));
@@ -1730,6 +2371,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_code_callouts() {
+        let md = "```python\nimport os  # <1>\nprint(os.name) <2> <3>\nx = a<1>\n```\n\n<1> Import the **os** module\n<2> Print the name\n<3> Done";
+        let doc = parse_markdown(md);
+        assert_eq!(doc.blocks.len(), 2);
+        match &doc.blocks[0] {
+            Block::CodeBlock {
+                content, callouts, ..
+            } => {
+                assert_eq!(content, "import os\nprint(os.name)\nx = a<1>\n");
+                assert_eq!(callouts, &vec![(1, 1), (2, 2), (2, 3)]);
+            }
+            other => panic!("Expected CodeBlock, found {:?}", other),
+        }
+        match &doc.blocks[1] {
+            Block::CodeCallouts(items) => {
+                let numbers: Vec<u32> = items.iter().map(|(n, _)| *n).collect();
+                assert_eq!(numbers, [1, 2, 3]);
+                assert_eq!(extract_inline_text(&items[0].1), "Import the os module");
+                assert!(matches!(items[0].1[1], Inline::Bold(_)));
+            }
+            other => panic!("Expected CodeCallouts, found {:?}", other),
+        }
+
+        // Without callouts in the code, the paragraph stays a paragraph
+        let doc = parse_markdown("```\nplain\n```\n\n<1> Not a callout");
+        assert!(matches!(doc.blocks[1], Block::Paragraph(_)));
+    }
+
     #[test]
     fn test_parse_mermaid_block() {
         let md = "```mermaid\nflowchart LR\n    A --> B\n```";
@@ -1844,6 +2514,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_image_captions() {
+        let md = "![Plot](plot.png \"Sales **by year**[^src]\")\n\n![Map](map.png)\n\nFigure: Route for *$n$* stops\n\n[^src]: Annual report\n";
+        let doc = parse_markdown(md);
+        assert_eq!(doc.blocks.len(), 2);
+
+        match &doc.blocks[0] {
+            Block::Image { title, caption, .. } => {
+                assert_eq!(title.as_deref(), Some("Sales **by year**[^src]"));
+                assert_eq!(
+                    caption.as_deref(),
+                    Some(
+                        &[
+                            Inline::Text("Sales ".to_string()),
+                            Inline::Bold(vec![Inline::Text("by year".to_string())]),
+                            Inline::FootnoteRef("src".to_string()),
+                        ][..]
+                    )
+                );
+            }
+            _ => panic!("Expected Image"),
+        }
+        match &doc.blocks[1] {
+            Block::Image { alt, caption, .. } => {
+                assert_eq!(alt, "Map");
+                let caption = caption.as_ref().expect("Figure: paragraph caption");
+                assert_eq!(caption[0], Inline::Text("Route for ".to_string()));
+                assert!(matches!(caption[1], Inline::Italic(_)));
+            }
+            _ => panic!("Expected Image"),
+        }
+        assert!(doc.footnotes.contains_key("src"));
+    }
+
+    #[test]
+    fn test_parse_figure_group() {
+        let md = "::: figures {#fig:compare}\n![Before](before.png){#fig:before}\n![After](after.png \"After **cleanup**\")\n\nFigure: Cleanup results\n:::\n\nText\n";
+        let doc = parse_markdown(md);
+        assert_eq!(doc.blocks.len(), 2);
+
+        match &doc.blocks[0] {
+            Block::FigureGroup {
+                figures,
+                caption,
+                id,
+            } => {
+                assert_eq!(id.as_deref(), Some("fig:compare"));
+                assert_eq!(
+                    caption.as_deref(),
+                    Some(&[Inline::Text("Cleanup results".to_string())][..])
+                );
+                assert_eq!(figures.len(), 2);
+                assert!(matches!(
+                    &figures[0],
+                    Block::Image { src, id: Some(id), caption: None, .. }
+                        if src == "before.png" && id == "fig:before"
+                ));
+                assert!(matches!(
+                    &figures[1],
+                    Block::Image {
+                        id: None,
+                        caption: Some(_),
+                        ..
+                    }
+                ));
+            }
+            other => panic!("Expected FigureGroup, found {:?}", other),
+        }
+        assert!(matches!(doc.blocks[1], Block::Paragraph(_)));
+    }
+
+    #[test]
+    fn test_parse_image_with_id() {
+        let md = "![Map](map.png){#fig:map width=50%}";
+        let doc = parse_markdown(md);
+        match &doc.blocks[0] {
+            Block::Image { width, id, .. } => {
+                assert_eq!(width.as_deref(), Some("50%"));
+                assert_eq!(id.as_deref(), Some("fig:map"));
+            }
+            other => panic!("Expected Image block, found {:?}", other),
+        }
+    }
+
     #[test]
     fn test_parse_thematic_break() {
         let md = "---";
@@ -2161,6 +2915,36 @@ fn main() {
         }
     }
 
+    #[test]
+    fn test_parse_kbd() {
+        let md = "Press [[Ctrl]]+[[C]] to copy, then **[[ Enter ]]**. Not [a link] or [[]].";
+        let doc = parse_markdown(md);
+
+        match &doc.blocks[0] {
+            Block::Paragraph(content) => {
+                let keys: Vec<&str> = content
+                    .iter()
+                    .filter_map(|i| match i {
+                        Inline::Kbd(key) => Some(key.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(keys, ["Ctrl", "C"]);
+                assert!(matches!(&content[2], Inline::Text(t) if t == "+"));
+                let bold = content
+                    .iter()
+                    .find_map(|i| match i {
+                        Inline::Bold(inner) => Some(inner),
+                        _ => None,
+                    })
+                    .unwrap();
+                assert!(matches!(&bold[..], [Inline::Kbd(k)] if k == "Enter"));
+                assert!(extract_inline_text(content).ends_with("Not [a link] or [[]]."));
+            }
+            _ => panic!("Expected Paragraph"),
+        }
+    }
+
     #[test]
     fn test_parse_include_directive() {
         let md = "{!include:chapters/intro.md}";
@@ -2284,4 +3068,44 @@ fn main() {
             _ => panic!("Expected CodeInclude block, found {:?}", doc.blocks[0]),
         }
     }
+
+    #[test]
+    fn test_lang_fenced_div() {
+        let md = "บทนำ\n\n::: {lang=en}\n## Summary\nEnglish summary\n:::\n\nต่อ\n\n```\n::: {lang=ja}\n```\n";
+        let doc = parse_markdown(md);
+
+        assert_eq!(doc.blocks.len(), 4, "{:?}", doc.blocks);
+        match &doc.blocks[1] {
+            Block::LangGroup { lang, blocks } => {
+                assert_eq!(lang, "en");
+                assert_eq!(blocks.len(), 2);
+                assert!(matches!(&blocks[0], Block::Heading { .. }));
+                assert!(matches!(&blocks[1], Block::Paragraph(_)));
+            }
+            other => panic!("Expected LangGroup block, found {:?}", other),
+        }
+        assert!(matches!(&doc.blocks[2], Block::Paragraph(_)));
+        // Fences inside code blocks are left alone
+        match &doc.blocks[3] {
+            Block::CodeBlock { content, .. } => assert_eq!(content.trim(), "::: {lang=ja}"),
+            other => panic!("Expected CodeBlock, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_translate_fenced_div() {
+        let md = "::: translate\nHello\n\n---\n\nสวัสดี\n:::\n\n::: translate\nBye\n\n---\n\n::: {lang=ja}\nさようなら\n:::\n:::\n";
+        let doc = parse_markdown(md);
+
+        assert_eq!(doc.blocks.len(), 1, "{:?}", doc.blocks);
+        match &doc.blocks[0] {
+            Block::ParallelText { rows } => {
+                assert_eq!(rows.len(), 2);
+                assert!(matches!(rows[0].0.as_slice(), [Block::Paragraph(_)]));
+                assert!(matches!(rows[0].1.as_slice(), [Block::Paragraph(_)]));
+                assert!(matches!(rows[1].1.as_slice(), [Block::LangGroup { .. }]));
+            }
+            other => panic!("Expected ParallelText block, found {:?}", other),
+        }
+    }
 }
@@ -2,8 +2,14 @@ mod ast;
 mod frontmatter;
 mod includes;
 mod markdown;
+mod parallel;
+mod paths;
+mod remote;
 
 pub use ast::*;
 pub use frontmatter::*;
 pub use includes::*;
 pub use markdown::*;
+pub use parallel::*;
+pub use paths::*;
+pub use remote::*;
@@ -0,0 +1,127 @@
+//! Paired source and translation files
+//!
+//! Aligns a markdown file with its translation block by block and writes
+//! each pair as a `::: translate` region, so the two render side by side as
+//! a bilingual parallel table.
+
+use pulldown_cmark::{Event, Parser};
+
+use super::frontmatter::parse_frontmatter;
+use super::markdown::get_parser_options;
+use crate::diagnostics::WarningCategory;
+
+/// Combine a markdown source and its translation into `::: translate` pairs
+///
+/// Top-level blocks are paired in order. The source's frontmatter is kept;
+/// the translation's is dropped. Thematic breaks in the source stay between
+/// pairs as section breaks, and those in the translation are skipped.
+pub fn pair_translation(source: &str, translation: &str) -> String {
+    let (_, source_body) = parse_frontmatter(source);
+    let (_, translation_body) = parse_frontmatter(translation);
+    let frontmatter = &source[..source.len() - source_body.len()];
+
+    let source_blocks = top_level_blocks(source_body);
+    let translation_blocks: Vec<&str> = top_level_blocks(translation_body)
+        .into_iter()
+        .filter(|block| !is_thematic_break(block))
+        .collect();
+
+    let source_count = source_blocks
+        .iter()
+        .filter(|block| !is_thematic_break(block))
+        .count();
+    if source_count != translation_blocks.len() {
+        crate::diagnostics::warn(
+            WarningCategory::Other,
+            format!(
+                "Source has {} blocks but translation has {}; unmatched blocks get an empty cell",
+                source_count,
+                translation_blocks.len()
+            ),
+        );
+    }
+
+    let mut out = String::from(frontmatter);
+    let mut translations = translation_blocks.into_iter();
+    for block in &source_blocks {
+        if is_thematic_break(block) {
+            out.push_str(block);
+            out.push_str("\n\n");
+            continue;
+        }
+        push_pair(&mut out, block, translations.next().unwrap_or(""));
+    }
+    for translation in translations {
+        push_pair(&mut out, "", translation);
+    }
+    out
+}
+
+fn push_pair(out: &mut String, source: &str, translation: &str) {
+    out.push_str("::: translate\n");
+    out.push_str(source);
+    out.push_str("\n\n---\n\n");
+    out.push_str(translation);
+    out.push_str("\n:::\n\n");
+}
+
+/// Source text of each top-level block
+fn top_level_blocks(markdown: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (event, range) in Parser::new_ext(markdown, get_parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(_) => {
+                if depth == 0 {
+                    start = range.start;
+                }
+                depth += 1;
+            }
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    blocks.push(markdown[start..range.end].trim_end());
+                }
+            }
+            Event::Rule if depth == 0 => blocks.push(markdown[range].trim_end()),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn is_thematic_break(block: &str) -> bool {
+    let chars: String = block.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|marker| chars.chars().all(|c| c.to_string() == *marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_markdown_with_frontmatter, Block};
+
+    #[test]
+    fn test_pair_translation() {
+        let source = "---\nlanguage: en\n---\n# Terms\n\nThe parties agree.\n\n---\n\nSigned.\n";
+        let translation = "# ข้อตกลง\n\nคู่สัญญาตกลงกัน\n\n***\n\nลงนาม\n";
+        let paired = pair_translation(source, translation);
+
+        assert!(paired.starts_with("---\nlanguage: en\n---\n"));
+        let doc = parse_markdown_with_frontmatter(&paired);
+        assert_eq!(doc.blocks.len(), 3, "{:?}", doc.blocks);
+        match &doc.blocks[0] {
+            Block::ParallelText { rows } => {
+                assert_eq!(rows.len(), 2);
+                assert!(matches!(rows[0].0[0], Block::Heading { .. }));
+                assert!(matches!(rows[1].1[0], Block::Paragraph(_)));
+            }
+            other => panic!("Expected ParallelText, found {:?}", other),
+        }
+        assert!(matches!(doc.blocks[1], Block::ThematicBreak));
+        assert!(matches!(doc.blocks[2], Block::ParallelText { .. }));
+    }
+}
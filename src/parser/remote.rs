@@ -0,0 +1,334 @@
+//! Markdown included from URLs (`{!include:https://...}`)
+//!
+//! Shared boilerplate such as license text or safety notices can be pulled
+//! from a central repository. Downloads are cached on disk and reused for
+//! `max_age`; a `sha256=` pin after the URL fixes the expected content, and
+//! offline builds only read the cache. Fetching needs the `remote-includes`
+//! feature.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+/// Settings for fetching `{!include:https://...}` targets
+#[derive(Debug, Clone)]
+pub struct RemoteIncludes {
+    /// Directory downloads are cached in
+    pub cache_dir: PathBuf,
+    /// Only read the cache, never the network
+    pub offline: bool,
+    /// How long a cached download is used before it is fetched again
+    pub max_age: Duration,
+}
+
+impl Default for RemoteIncludes {
+    fn default() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".md2docx/cache"),
+            offline: false,
+            max_age: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Whether an include target is a URL
+pub fn is_remote_include(target: &str) -> bool {
+    target.starts_with("https://") || target.starts_with("http://")
+}
+
+/// Split an include target into its URL and `sha256=` pin
+///
+/// `https://example.com/LICENSE.md sha256=9f86...` gives the URL and the
+/// hex digest.
+pub fn parse_remote_include(target: &str) -> (&str, Option<&str>) {
+    let mut parts = target.split_whitespace();
+    let url = parts.next().unwrap_or("");
+    let pin = parts.find_map(|part| part.strip_prefix("sha256="));
+    (url, pin)
+}
+
+impl RemoteIncludes {
+    /// Content of `target` (`URL [sha256=HEX]`), from the cache or the network
+    ///
+    /// A cached copy is used while it is younger than `max_age`, always
+    /// when it matches the pin, and as a fallback when the download fails.
+    #[cfg(all(feature = "remote-includes", not(target_arch = "wasm32")))]
+    pub fn fetch(&self, target: &str) -> Result<String> {
+        let (url, pin) = parse_remote_include(target);
+        let matches_pin =
+            |content: &str| pin.is_none_or(|pin| pin.eq_ignore_ascii_case(&sha256_hex(content)));
+        let mismatch = || {
+            Error::Include(format!(
+                "Checksum of {} does not match sha256={}",
+                url,
+                pin.unwrap_or_default()
+            ))
+        };
+
+        let cached_path = self.cache_dir.join(format!("{}.md", sha256_hex(url)));
+        let cached = std::fs::read_to_string(&cached_path).ok();
+        let age = std::fs::metadata(&cached_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if let Some(content) = &cached {
+            let fresh = age.is_some_and(|age| age <= self.max_age);
+            if matches_pin(content) && (self.offline || pin.is_some() || fresh) {
+                return Ok(content.clone());
+            }
+        }
+        if self.offline {
+            return Err(match cached {
+                Some(_) => mismatch(),
+                None => Error::Include(format!("{} is not cached and the build is offline", url)),
+            });
+        }
+
+        match download(url) {
+            Ok(content) => {
+                if !matches_pin(&content) {
+                    return Err(mismatch());
+                }
+                std::fs::create_dir_all(&self.cache_dir)?;
+                std::fs::write(&cached_path, &content)?;
+                Ok(content)
+            }
+            Err(e) => match cached.filter(|content| matches_pin(content)) {
+                Some(content) => {
+                    crate::diagnostics::warn(
+                        crate::diagnostics::WarningCategory::Other,
+                        format!("Using cached copy of {}: {}", url, e),
+                    );
+                    Ok(content)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Content of `target`; remote includes need the `remote-includes` feature
+    #[cfg(not(all(feature = "remote-includes", not(target_arch = "wasm32"))))]
+    pub fn fetch(&self, target: &str) -> Result<String> {
+        let (url, _) = parse_remote_include(target);
+        Err(Error::Include(format!(
+            "Cannot include {}: built without the remote-includes feature",
+            url
+        )))
+    }
+}
+
+/// Replace `{!include:https://...}` lines outside code blocks with the
+/// markdown they point to
+///
+/// See [`RemoteIncludes::load`] for the includes allowed in the downloaded
+/// markdown.
+pub fn expand_remote_includes(markdown: &str, remote: &RemoteIncludes) -> Result<String> {
+    remote.expand(markdown, &mut Vec::new())
+}
+
+impl RemoteIncludes {
+    /// Markdown of `target` (`URL [sha256=HEX]`) with the includes nested
+    /// in it expanded
+    ///
+    /// Downloaded markdown may only include other URLs, and those must be
+    /// pinned with `sha256=`: a pin on the outer include then fixes
+    /// everything it pulls in. Includes of local files or code are
+    /// rejected so a remote file cannot read the machine it is built on.
+    pub fn load(&self, target: &str) -> Result<String> {
+        self.load_nested(target, &mut Vec::new())
+    }
+
+    /// Load `target` below the URLs in `stack`
+    fn load_nested(&self, target: &str, stack: &mut Vec<String>) -> Result<String> {
+        let (url, pin) = parse_remote_include(target);
+        if let Some(parent) = stack.last() {
+            if pin.is_none() {
+                return Err(Error::Include(format!(
+                    "{} includes {} without a sha256= pin; includes in remote markdown must be pinned",
+                    parent, url
+                )));
+            }
+        }
+        if stack.iter().any(|included| included == url) {
+            return Err(Error::Include(format!(
+                "Circular include detected: {} is already in the include stack",
+                url
+            )));
+        }
+        if stack.len() >= MAX_DEPTH {
+            return Err(Error::Include(format!(
+                "Include depth exceeded (max {}): {}",
+                MAX_DEPTH, url
+            )));
+        }
+
+        let content = self.fetch(target)?;
+        stack.push(url.to_string());
+        let expanded = self.expand(&content, stack);
+        stack.pop();
+        expanded
+    }
+
+    /// Expand the URL includes of `markdown`, which was downloaded from the
+    /// last URL in `stack` unless the stack is empty
+    fn expand(&self, markdown: &str, stack: &mut Vec<String>) -> Result<String> {
+        let mut out = String::with_capacity(markdown.len());
+        let mut fence: Option<&str> = None;
+        for line in markdown.split_inclusive('\n') {
+            let trimmed = line.trim();
+            match fence {
+                Some(marker) if trimmed.starts_with(marker) => fence = None,
+                Some(_) => {}
+                None if trimmed.starts_with("```") => fence = Some("```"),
+                None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+                None => {
+                    let target = trimmed
+                        .strip_prefix("{!include:")
+                        .and_then(|rest| rest.strip_suffix('}'));
+                    if let Some(target) = target.filter(|target| is_remote_include(target)) {
+                        out.push_str(self.load_nested(target, stack)?.trim_end());
+                        out.push('\n');
+                        continue;
+                    }
+                    // Includes inside quotes and lists are directives too
+                    let directive = trimmed.trim_start_matches(['>', ' ']);
+                    if let Some(parent) = stack.last() {
+                        if directive.starts_with("{!include:") || directive.starts_with("{!code:") {
+                            return Err(Error::Include(format!(
+                                "{} cannot include local files: {}",
+                                parent, directive
+                            )));
+                        }
+                    }
+                }
+            }
+            out.push_str(line);
+        }
+        Ok(out)
+    }
+}
+
+/// Nesting limit of remote includes, as [`IncludeConfig`](super::IncludeConfig)'s default
+const MAX_DEPTH: usize = 10;
+
+#[cfg(all(feature = "remote-includes", not(target_arch = "wasm32")))]
+fn download(url: &str) -> Result<String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build();
+    agent
+        .get(url)
+        .call()
+        .map_err(|e| Error::Include(format!("Cannot fetch {}: {}", url, e)))?
+        .into_string()
+        .map_err(|e| Error::Include(format!("Cannot read {}: {}", url, e)))
+}
+
+#[cfg(all(feature = "remote-includes", not(target_arch = "wasm32")))]
+fn sha256_hex(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_include() {
+        assert!(is_remote_include("https://example.com/LICENSE.md"));
+        assert!(!is_remote_include("chapters/intro.md"));
+        assert_eq!(
+            parse_remote_include("https://example.com/a.md  sha256=ABCD"),
+            ("https://example.com/a.md", Some("ABCD"))
+        );
+        assert_eq!(
+            parse_remote_include("https://example.com/a.md"),
+            ("https://example.com/a.md", None)
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "remote-includes", not(target_arch = "wasm32")))]
+    fn test_offline_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let remote = RemoteIncludes {
+            cache_dir: temp_dir.path().to_path_buf(),
+            offline: true,
+            ..Default::default()
+        };
+        let url = "https://example.com/notice.md";
+        assert!(remote.fetch(url).is_err());
+
+        std::fs::write(
+            temp_dir.path().join(format!("{}.md", sha256_hex(url))),
+            "Handle with care.\n",
+        )
+        .unwrap();
+        let pin = sha256_hex("Handle with care.\n");
+        assert_eq!(remote.fetch(url).unwrap(), "Handle with care.\n");
+        assert!(remote.fetch(&format!("{} sha256={}", url, pin)).is_ok());
+        assert!(remote.fetch(&format!("{} sha256=00", url)).is_err());
+
+        let md = "# Notice\n\n{!include:https://example.com/notice.md}\n\n```\n{!include:https://example.com/notice.md}\n```\n";
+        let expanded = expand_remote_includes(md, &remote).unwrap();
+        assert_eq!(expanded.matches("Handle with care.").count(), 1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "remote-includes", not(target_arch = "wasm32")))]
+    fn test_nested_includes_must_be_pinned_urls() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let remote = RemoteIncludes {
+            cache_dir: temp_dir.path().to_path_buf(),
+            offline: true,
+            ..Default::default()
+        };
+        let cache = |url: &str, content: &str| {
+            std::fs::write(
+                temp_dir.path().join(format!("{}.md", sha256_hex(url))),
+                content,
+            )
+            .unwrap();
+        };
+        let inner = "https://example.com/inner.md";
+        cache(inner, "Inner text.\n");
+        let pinned = format!("{} sha256={}", inner, sha256_hex("Inner text.\n"));
+
+        cache(
+            "https://example.com/pinned.md",
+            &format!("Outer.\n\n{{!include:{}}}\n", pinned),
+        );
+        let loaded = remote.load("https://example.com/pinned.md").unwrap();
+        assert_eq!(loaded, "Outer.\n\nInner text.\n");
+
+        cache(
+            "https://example.com/unpinned.md",
+            &format!("{{!include:{}}}\n", inner),
+        );
+        let err = remote.load("https://example.com/unpinned.md").unwrap_err();
+        assert!(err.to_string().contains("sha256= pin"), "{}", err);
+
+        for local in [
+            "{!include:/etc/passwd}\n",
+            "> {!include:../secrets.md}\n",
+            "{!code:/etc/hosts}\n",
+        ] {
+            cache("https://example.com/local.md", local);
+            let err = remote.load("https://example.com/local.md").unwrap_err();
+            assert!(
+                err.to_string().contains("cannot include local files"),
+                "{}",
+                err
+            );
+        }
+
+        // Local includes of the project's own markdown are left to the caller
+        let md = "{!include:intro.md}\n";
+        assert_eq!(expand_remote_includes(md, &remote).unwrap(), md);
+    }
+}
@@ -0,0 +1,408 @@
+//! Fonts for PDF output: lookup, fallback and shaping
+//!
+//! Faces come from the system fonts, the project's font directory
+//! ([`DocumentConfig::embed_dir`](crate::DocumentConfig::embed_dir)) and, as
+//! a last resort, the bundled XITS font. Text is split into runs by the
+//! first face of the fallback chain that covers each grapheme, and every
+//! run is shaped with rustybuzz, so Thai marks and ligatures sit where the
+//! font puts them.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use unicode_segmentation::UnicodeSegmentation;
+use usvg::fontdb::{self, Database, Family, Query};
+
+use crate::diagnostics::{self, WarningCategory};
+
+/// Index of a face in a [`FontBook`]
+pub(super) type FaceId = usize;
+
+/// A face used by the document, embedded whole into the PDF
+pub(super) struct Face {
+    /// PostScript name, used as the PDF base font name
+    pub name: String,
+    /// A single-face font file, taken out of its collection if needed
+    pub data: Vec<u8>,
+    /// Outlines are CFF rather than TrueType
+    pub cff: bool,
+    pub units_per_em: f32,
+    /// Ascender, descender (negative) and line gap, in em
+    pub ascender: f32,
+    pub descender: f32,
+    pub line_gap: f32,
+    /// Characters known to be covered or not
+    coverage: HashMap<char, bool>,
+}
+
+impl Face {
+    fn parse(data: &[u8], index: u32) -> Option<Self> {
+        let face = ttf_parser::Face::parse(data, index).ok()?;
+        let name = face
+            .names()
+            .into_iter()
+            .filter(|name| name.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .find_map(|name| name.to_string())
+            .map(|name| {
+                name.chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                    .collect::<String>()
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Font".to_string());
+        let cff = face
+            .raw_face()
+            .table(ttf_parser::Tag::from_bytes(b"CFF "))
+            .is_some();
+        let units_per_em = f32::from(face.units_per_em());
+        Some(Self {
+            name,
+            data: extract_face(data, index)?,
+            cff,
+            units_per_em,
+            ascender: f32::from(face.ascender()) / units_per_em,
+            descender: f32::from(face.descender()) / units_per_em,
+            line_gap: f32::from(face.line_gap()) / units_per_em,
+            coverage: HashMap::new(),
+        })
+    }
+
+    fn covers(&mut self, c: char) -> bool {
+        if let Some(&covered) = self.coverage.get(&c) {
+            return covered;
+        }
+        let covered =
+            ttf_parser::Face::parse(&self.data, 0).is_ok_and(|face| face.glyph_index(c).is_some());
+        self.coverage.insert(c, covered);
+        covered
+    }
+}
+
+/// Requested family and style of a run of text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct FontKey {
+    pub family: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// One shaped glyph, measured in points
+#[derive(Debug, Clone)]
+pub(super) struct Glyph {
+    pub id: u16,
+    pub advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+    /// Text of the cluster this glyph starts; empty for the rest of a cluster
+    pub text: String,
+}
+
+/// Text shaped with one face at one size
+#[derive(Debug, Clone)]
+pub(super) struct Shaped {
+    pub face: FaceId,
+    pub size: f32,
+    pub glyphs: Vec<Glyph>,
+}
+
+impl Shaped {
+    pub fn width(&self) -> f32 {
+        self.glyphs.iter().map(|g| g.advance).sum()
+    }
+}
+
+/// Faces loaded for one document
+pub(super) struct FontBook {
+    db: Database,
+    faces: Vec<Face>,
+    loaded: HashMap<fontdb::ID, FaceId>,
+    /// Fallback chain of each requested font
+    chains: HashMap<FontKey, Vec<FaceId>>,
+    /// Families tried after the requested one, such as the language's
+    /// complex script font
+    fallbacks: Vec<String>,
+    /// Faces found by searching every font for a character
+    found: Vec<FaceId>,
+    /// Characters no face covers
+    uncovered: HashSet<char>,
+    bundled: Option<FaceId>,
+    warned: HashSet<String>,
+}
+
+impl FontBook {
+    /// System fonts plus the fonts in `font_dir`
+    pub fn new(font_dir: Option<&Path>, fallbacks: Vec<String>) -> Self {
+        let mut db = Database::clone(&crate::mermaid::FONT_DB);
+        if let Some(dir) = font_dir {
+            db.load_fonts_dir(dir);
+        }
+        Self {
+            db,
+            faces: Vec::new(),
+            loaded: HashMap::new(),
+            chains: HashMap::new(),
+            fallbacks,
+            found: Vec::new(),
+            uncovered: HashSet::new(),
+            bundled: None,
+            warned: HashSet::new(),
+        }
+    }
+
+    pub fn face(&self, id: FaceId) -> &Face {
+        &self.faces[id]
+    }
+
+    /// Ascent, descent and line gap of a font at `size`, all positive
+    pub fn metrics(&mut self, key: &FontKey, size: f32) -> (f32, f32, f32) {
+        let primary = self.chain(key)[0];
+        let face = &self.faces[primary];
+        (
+            face.ascender * size,
+            -face.descender * size,
+            face.line_gap * size,
+        )
+    }
+
+    /// Shape `text`, splitting it into runs by the face covering each grapheme
+    pub fn shape(&mut self, text: &str, key: &FontKey, size: f32) -> Vec<Shaped> {
+        let chain = self.chain(key);
+        let mut runs: Vec<(FaceId, String)> = Vec::new();
+        for grapheme in text.graphemes(true) {
+            let c = grapheme.chars().next().unwrap_or(' ');
+            // Spaces stay with the run they follow
+            let face = match runs.last() {
+                Some(&(face, _)) if c.is_whitespace() && self.faces[face].covers(c) => face,
+                _ => self.face_for(c, &chain),
+            };
+            match runs.last_mut() {
+                Some((last, run)) if *last == face => run.push_str(grapheme),
+                _ => runs.push((face, grapheme.to_string())),
+            }
+        }
+        runs.into_iter()
+            .filter_map(|(face, run)| self.shape_run(face, &run, size))
+            .collect()
+    }
+
+    /// Faces to try for a font, in order; never empty
+    fn chain(&mut self, key: &FontKey) -> Vec<FaceId> {
+        if let Some(chain) = self.chains.get(key) {
+            return chain.clone();
+        }
+        let mut chain = Vec::new();
+        let families: Vec<String> = std::iter::once(key.family.clone())
+            .chain(self.fallbacks.iter().cloned())
+            .collect();
+        for (i, family) in families.iter().enumerate() {
+            match self.query(Family::Name(family), key) {
+                Some(face) if !chain.contains(&face) => chain.push(face),
+                Some(_) => {}
+                None if i == 0 && self.warned.insert(family.clone()) => diagnostics::warn(
+                    WarningCategory::Font,
+                    format!("Font '{}' not found; the PDF uses a fallback font", family),
+                ),
+                None => {}
+            }
+        }
+        for generic in [Family::SansSerif, Family::Serif] {
+            if let Some(face) = self.query(generic, key) {
+                if !chain.contains(&face) {
+                    chain.push(face);
+                }
+            }
+        }
+        if chain.is_empty() {
+            chain.push(self.bundled());
+        }
+        self.chains.insert(key.clone(), chain.clone());
+        chain
+    }
+
+    fn query(&mut self, family: Family<'_>, key: &FontKey) -> Option<FaceId> {
+        let id = self.db.query(&Query {
+            families: &[family],
+            weight: if key.bold {
+                fontdb::Weight::BOLD
+            } else {
+                fontdb::Weight::NORMAL
+            },
+            stretch: fontdb::Stretch::Normal,
+            style: if key.italic {
+                fontdb::Style::Italic
+            } else {
+                fontdb::Style::Normal
+            },
+        })?;
+        self.load(id)
+    }
+
+    fn load(&mut self, id: fontdb::ID) -> Option<FaceId> {
+        if let Some(&face) = self.loaded.get(&id) {
+            return Some(face);
+        }
+        let face = self.db.with_face_data(id, Face::parse)??;
+        self.faces.push(face);
+        self.loaded.insert(id, self.faces.len() - 1);
+        Some(self.faces.len() - 1)
+    }
+
+    /// The XITS font compiled into md2docx, for systems without fonts
+    fn bundled(&mut self) -> FaceId {
+        if let Some(face) = self.bundled {
+            return face;
+        }
+        let face = Face::parse(crate::docx::math_rex::MATH_FONT_DATA, 0)
+            .expect("bundled XITS font parses");
+        self.faces.push(face);
+        self.bundled = Some(self.faces.len() - 1);
+        self.faces.len() - 1
+    }
+
+    /// First face of `chain` covering `c`, else any installed face that does
+    fn face_for(&mut self, c: char, chain: &[FaceId]) -> FaceId {
+        let candidates = chain.iter().chain(&self.found).copied().collect::<Vec<_>>();
+        if let Some(face) = candidates.into_iter().find(|&f| self.faces[f].covers(c)) {
+            return face;
+        }
+        if c.is_whitespace() || c.is_control() || !self.uncovered.insert(c) {
+            return chain[0];
+        }
+        let ids: Vec<_> = self.db.faces().map(|info| info.id).collect();
+        for id in ids {
+            let covers = self
+                .db
+                .with_face_data(id, |data, index| {
+                    ttf_parser::Face::parse(data, index)
+                        .is_ok_and(|face| face.glyph_index(c).is_some())
+                })
+                .unwrap_or(false);
+            if let Some(face) = covers.then(|| self.load(id)).flatten() {
+                self.uncovered.remove(&c);
+                self.found.push(face);
+                return face;
+            }
+        }
+        chain[0]
+    }
+
+    fn shape_run(&self, face_id: FaceId, text: &str, size: f32) -> Option<Shaped> {
+        let face = &self.faces[face_id];
+        let shaper = rustybuzz::Face::from_slice(&face.data, 0)?;
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(&shaper, &[], buffer);
+
+        let scale = size / face.units_per_em;
+        let mut starts: Vec<usize> = output
+            .glyph_infos()
+            .iter()
+            .map(|info| info.cluster as usize)
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+        let cluster_text = |start: usize| {
+            let end = starts
+                .iter()
+                .find(|&&s| s > start)
+                .copied()
+                .unwrap_or(text.len());
+            text.get(start..end).unwrap_or_default().to_string()
+        };
+
+        let mut seen = HashSet::new();
+        let glyphs = output
+            .glyph_infos()
+            .iter()
+            .zip(output.glyph_positions())
+            .map(|(info, pos)| {
+                let start = info.cluster as usize;
+                Glyph {
+                    id: info.glyph_id as u16,
+                    advance: pos.x_advance as f32 * scale,
+                    x_offset: pos.x_offset as f32 * scale,
+                    y_offset: pos.y_offset as f32 * scale,
+                    text: if seen.insert(start) {
+                        cluster_text(start)
+                    } else {
+                        String::new()
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        (!glyphs.is_empty()).then_some(Shaped {
+            face: face_id,
+            size,
+            glyphs,
+        })
+    }
+}
+
+/// A standalone font file for face `index` of a font collection
+///
+/// PDF embeds single fonts, so the face's tables are copied out of the
+/// `.ttc` into a font file of their own. Other files are returned as is.
+fn extract_face(data: &[u8], index: u32) -> Option<Vec<u8>> {
+    if data.get(..4)? != b"ttcf" {
+        return Some(data.to_vec());
+    }
+    let read_u32 = |at: usize| {
+        data.get(at..at + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    let offset = read_u32(12 + 4 * index as usize)?;
+    let num_tables = data
+        .get(offset + 4..offset + 6)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)?;
+
+    let mut out = data.get(offset..offset + 12)?.to_vec();
+    let mut tables = Vec::with_capacity(num_tables);
+    let mut next = 12 + 16 * num_tables;
+    for i in 0..num_tables {
+        let record = offset + 12 + 16 * i;
+        let (table_offset, length) = (read_u32(record + 8)?, read_u32(record + 12)?);
+        // Tag and checksum are kept, the offset points into the new file
+        out.extend_from_slice(data.get(record..record + 8)?);
+        out.extend_from_slice(&(next as u32).to_be_bytes());
+        out.extend_from_slice(&(length as u32).to_be_bytes());
+        tables.push(data.get(table_offset..table_offset + length)?);
+        next += (length + 3) & !3;
+    }
+    for table in tables {
+        out.extend_from_slice(table);
+        out.resize((out.len() + 3) & !3, 0);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_face() {
+        let font = b"\x00\x01\x00\x00rest of a font".to_vec();
+        assert_eq!(extract_face(&font, 0), Some(font));
+
+        // A collection of two faces, the second with a single "abcd" table
+        let mut ttc = b"ttcf\x00\x01\x00\x00\x00\x00\x00\x02".to_vec();
+        ttc.extend_from_slice(&[0, 0, 0, 20, 0, 0, 0, 20]);
+        ttc.extend_from_slice(b"\x00\x01\x00\x00\x00\x01\x00\x10\x00\x00\x00\x00");
+        ttc.extend_from_slice(b"abcd\x00\x00\x00\x07");
+        ttc.extend_from_slice(&[0, 0, 0, 48, 0, 0, 0, 3]);
+        ttc.extend_from_slice(b"xyz");
+
+        let face = extract_face(&ttc, 1).unwrap();
+        assert_eq!(
+            &face[..12],
+            b"\x00\x01\x00\x00\x00\x01\x00\x10\x00\x00\x00\x00"
+        );
+        assert_eq!(&face[12..16], b"abcd");
+        // The table now starts right after the directory, padded to 4 bytes
+        assert_eq!(&face[20..28], &[0, 0, 0, 28, 0, 0, 0, 3]);
+        assert_eq!(&face[28..], b"xyz\0");
+
+        assert_eq!(extract_face(&ttc, 5), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! Images for PDF output
+//!
+//! JPEG files are embedded as they are. Everything else, SVG included, is
+//! decoded to RGB pixels plus an alpha mask when the image has transparency.
+
+use std::collections::HashMap;
+
+use crate::{Error, Result};
+
+/// Index of an image in an [`ImageStore`]
+pub(super) type ImageId = usize;
+
+/// CSS pixels to points
+const PX: f32 = 0.75;
+
+/// An image ready to embed
+pub(super) enum Image {
+    /// JPEG data, embedded unchanged
+    Jpeg {
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        gray: bool,
+    },
+    /// Decoded 8-bit RGB pixels
+    Pixels {
+        rgb: Vec<u8>,
+        alpha: Option<Vec<u8>>,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// An image and its natural size in points
+#[derive(Debug, Clone, Copy)]
+pub(super) struct StoredImage {
+    pub id: ImageId,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Images used by the document, each stored once
+#[derive(Default)]
+pub(super) struct ImageStore {
+    pub images: Vec<Image>,
+    by_key: HashMap<String, StoredImage>,
+}
+
+impl ImageStore {
+    /// An image file (PNG, JPEG, GIF, BMP, WebP or SVG), sized at 96 DPI
+    pub fn add_file(&mut self, key: &str, data: &[u8]) -> Result<StoredImage> {
+        self.add(key, || {
+            if is_svg(data) {
+                return rasterize_svg(data, 2.0);
+            }
+            let image = decode(data)?;
+            let (width, height) = image.pixel_size();
+            Ok((image, width as f32 * PX, height as f32 * PX))
+        })
+    }
+
+    /// A PNG rendered at `scale` times its natural size
+    pub fn add_scaled_png(&mut self, key: &str, data: &[u8], scale: f32) -> Result<StoredImage> {
+        self.add(key, || {
+            let image = decode(data)?;
+            let (width, height) = image.pixel_size();
+            Ok((image, width as f32 / scale * PX, height as f32 / scale * PX))
+        })
+    }
+
+    /// An SVG drawn at `width` x `height` points, rasterized at `scale` pixels
+    /// per point
+    pub fn add_svg(
+        &mut self,
+        key: &str,
+        svg: &[u8],
+        width: f32,
+        height: f32,
+        scale: f32,
+    ) -> Result<StoredImage> {
+        self.add(key, || {
+            let image = rasterize_svg_to(svg, width * scale, height * scale)?;
+            Ok((image, width, height))
+        })
+    }
+
+    fn add(
+        &mut self,
+        key: &str,
+        load: impl FnOnce() -> Result<(Image, f32, f32)>,
+    ) -> Result<StoredImage> {
+        if let Some(&sized) = self.by_key.get(key) {
+            return Ok(sized);
+        }
+        let (image, width, height) = load()?;
+        self.images.push(image);
+        let sized = StoredImage {
+            id: self.images.len() - 1,
+            width,
+            height,
+        };
+        self.by_key.insert(key.to_string(), sized);
+        Ok(sized)
+    }
+}
+
+impl Image {
+    pub fn pixel_size(&self) -> (u32, u32) {
+        match self {
+            Image::Jpeg { width, height, .. } | Image::Pixels { width, height, .. } => {
+                (*width, *height)
+            }
+        }
+    }
+}
+
+fn is_svg(data: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&data[..data.len().min(512)]);
+    head.contains("<svg") || head.trim_start().starts_with("<?xml")
+}
+
+fn decode(data: &[u8]) -> Result<Image> {
+    let format = image::guess_format(data).map_err(|e| Error::Image(e.to_string()))?;
+    let decoded = image::load_from_memory_with_format(data, format)
+        .map_err(|e| Error::Image(e.to_string()))?;
+    let (width, height) = (decoded.width(), decoded.height());
+    let color = decoded.color();
+    if format == image::ImageFormat::Jpeg
+        && matches!(color, image::ColorType::L8 | image::ColorType::Rgb8)
+    {
+        return Ok(Image::Jpeg {
+            data: data.to_vec(),
+            width,
+            height,
+            gray: color == image::ColorType::L8,
+        });
+    }
+
+    let rgba = decoded.to_rgba8();
+    let mut rgb = Vec::with_capacity(rgba.len() / 4 * 3);
+    let mut alpha = Vec::with_capacity(rgba.len() / 4);
+    for pixel in rgba.pixels() {
+        rgb.extend_from_slice(&pixel.0[..3]);
+        alpha.push(pixel.0[3]);
+    }
+    let opaque = alpha.iter().all(|&a| a == u8::MAX);
+    Ok(Image::Pixels {
+        rgb,
+        alpha: (!opaque).then_some(alpha),
+        width,
+        height,
+    })
+}
+
+/// Rasterize an SVG at `scale` times its size, returning the image and the
+/// SVG's size in points
+fn rasterize_svg(svg: &[u8], scale: f32) -> Result<(Image, f32, f32)> {
+    let tree = parse_svg(svg)?;
+    let size = tree.size();
+    let image = rasterize_svg_to(svg, size.width() * scale, size.height() * scale)?;
+    Ok((image, size.width() * PX, size.height() * PX))
+}
+
+/// Rasterize an SVG to `width` x `height` pixels
+fn rasterize_svg_to(svg: &[u8], width: f32, height: f32) -> Result<Image> {
+    let tree = parse_svg(svg)?;
+    let size = tree.size();
+    let (pixel_width, pixel_height) = (width.ceil().max(1.0) as u32, height.ceil().max(1.0) as u32);
+    let mut pixmap = tiny_skia::Pixmap::new(pixel_width, pixel_height)
+        .ok_or_else(|| Error::Image("SVG is too large to rasterize".to_string()))?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(width / size.width(), height / size.height()),
+        &mut pixmap.as_mut(),
+    );
+
+    let pixels = pixmap.pixels();
+    let mut rgb = Vec::with_capacity(pixels.len() * 3);
+    let mut alpha = Vec::with_capacity(pixels.len());
+    for pixel in pixels {
+        let color = pixel.demultiply();
+        rgb.extend_from_slice(&[color.red(), color.green(), color.blue()]);
+        alpha.push(color.alpha());
+    }
+    Ok(Image::Pixels {
+        rgb,
+        alpha: Some(alpha),
+        width: pixel_width,
+        height: pixel_height,
+    })
+}
+
+fn parse_svg(svg: &[u8]) -> Result<usvg::Tree> {
+    let options = usvg::Options {
+        fontdb: crate::mermaid::FONT_DB.clone(),
+        ..Default::default()
+    };
+    usvg::Tree::from_data(svg, &options).map_err(|e| Error::Image(format!("SVG: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_images() {
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20">
+            <rect width="40" height="20" fill="red"/></svg>"#;
+        let mut store = ImageStore::default();
+
+        let sized = store.add_file("chart.svg", svg).unwrap();
+        assert_eq!((sized.width, sized.height), (30.0, 15.0));
+        assert_eq!(store.images[sized.id].pixel_size(), (80, 40));
+
+        // The same key is stored once
+        assert_eq!(store.add_file("chart.svg", svg).unwrap().id, sized.id);
+        assert_eq!(store.images.len(), 1);
+
+        match &store.images[0] {
+            Image::Pixels { rgb, alpha, .. } => {
+                assert_eq!(&rgb[..3], &[255, 0, 0]);
+                assert_eq!(alpha.as_ref().unwrap()[0], 255);
+            }
+            Image::Jpeg { .. } => panic!("SVG embedded as JPEG"),
+        }
+    }
+}
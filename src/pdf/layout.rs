@@ -0,0 +1,1966 @@
+//! Page layout for PDF output
+//!
+//! Blocks are laid out top to bottom into pages of positioned glyph runs,
+//! rectangles and images, with the fonts, sizes and spacing of the DOCX
+//! styles. Paragraphs are filled greedily, breaking lines at spaces, at the
+//! zero-width spaces placed between Thai words and after CJK characters; a
+//! word wider than the line is split between graphemes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::font::{FontBook, FontKey, Shaped};
+use super::image::{ImageId, ImageStore, StoredImage};
+use crate::diagnostics::{self, WarningCategory};
+use crate::docx::highlight::highlight_code;
+use crate::docx::math_rex::render_latex_to_svg;
+use crate::docx::xref::{subfigure_anchors, subfigure_letter, CrossRefContext};
+use crate::docx::{parse_length_to_twips, FontConfig, PageConfig};
+use crate::i18n::segment::ZERO_WIDTH_SPACE;
+use crate::i18n::{insert_thai_breaks, Label, Strings};
+use crate::parser::{
+    callout_glyph, code_with_callouts, extract_inline_text, Alignment, Block, Inline, ListItem,
+    TableCell,
+};
+use crate::{FileMap, Language, TocConfig, TocPageNumbers};
+
+/// RGB, each from 0 to 1
+pub(super) type Color = [f32; 3];
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0]
+}
+
+const BLACK: Color = [0.0; 3];
+/// Word's Hyperlink style
+const LINK: Color = rgb(0x05, 0x63, 0xC1);
+const INLINE_CODE: Color = rgb(0xD6, 0x33, 0x84);
+/// Code block and table header background
+const SHADE: Color = rgb(0xF2, 0xF2, 0xF2);
+/// Table borders, quote bars and the footnote separator
+const RULE: Color = rgb(0xBF, 0xBF, 0xBF);
+
+const EMU_PER_PT: f32 = 12700.0;
+/// Pixels per point equations are rasterized at
+const MATH_SCALE: f32 = 4.0;
+
+/// First-line indent of body paragraphs (BodyText style)
+const FIRST_LINE_INDENT: f32 = 36.0;
+/// Indent of quotes and of each list level
+const INDENT: f32 = 36.0;
+/// Left indent of code blocks (Code style)
+const CODE_INDENT: f32 = 12.0;
+const CODE_PADDING: f32 = 6.0;
+const CELL_PADDING: f32 = 4.0;
+const BORDER: f32 = 0.5;
+const COLUMN_GAP: f32 = 18.0;
+/// Space between a list marker and the item text
+const MARKER_GAP: f32 = 6.0;
+/// Space before and after headings 1 to 4
+const HEADING_SPACING: [(f32, f32); 4] = [(24.0, 6.0), (18.0, 6.0), (14.0, 4.0), (10.0, 4.0)];
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Parse a `RRGGBB` color, with or without `#`
+pub(super) fn hex_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+/// Something drawn on a page, positioned in points from the top left
+#[derive(Debug, Clone)]
+pub(super) enum Item {
+    /// Glyphs starting at `x` on the baseline `y`
+    Text {
+        x: f32,
+        y: f32,
+        run: Shaped,
+        color: Color,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Color,
+    },
+    Image {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        image: ImageId,
+    },
+}
+
+/// Where a link goes
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Target {
+    Uri(String),
+    /// A position on a page of the document
+    Page {
+        page: usize,
+        y: f32,
+    },
+}
+
+/// A clickable area of a page
+#[derive(Debug, Clone)]
+pub(super) struct Link {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub target: Target,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Page {
+    pub items: Vec<Item>,
+    pub links: Vec<Link>,
+    /// Number printed in the footer; cover and contents pages have none
+    pub number: Option<u32>,
+}
+
+/// Page size and margins, in points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Geometry {
+    pub width: f32,
+    pub height: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Geometry {
+    /// The configured page, A4 with 1-inch margins by default
+    pub fn new(page: Option<&PageConfig>) -> Self {
+        let points = |field: fn(&PageConfig) -> Option<u32>, default: u32| {
+            page.and_then(field).unwrap_or(default) as f32 / 20.0
+        };
+        Self {
+            width: points(|p| p.width, 11906),
+            height: points(|p| p.height, 16838),
+            top: points(|p| p.margin_top, 1440),
+            right: points(|p| p.margin_right, 1440),
+            bottom: points(|p| p.margin_bottom, 1440),
+            left: points(|p| p.margin_left, 1440),
+        }
+    }
+}
+
+/// Fonts, sizes (in points) and colors of the document's styles
+pub(super) struct Theme {
+    pub body: FontKey,
+    pub size: f32,
+    pub color: Color,
+    pub code: FontKey,
+    pub code_size: f32,
+    pub caption_size: f32,
+    pub caption_color: Color,
+    pub heading_color: Color,
+    /// Sizes of headings 1 to 4; deeper headings use the level 4 size
+    pub heading_sizes: [f32; 4],
+}
+
+impl Theme {
+    /// The values the DOCX styles use for `lang` and `fonts`
+    pub fn new(lang: Language, fonts: Option<&FontConfig>) -> Self {
+        let normal = fonts
+            .and_then(|f| f.normal_size)
+            .unwrap_or(lang.default_font_size());
+        let heading_sizes = match lang {
+            Language::Thai | Language::Lao | Language::Khmer | Language::Myanmar => [
+                normal + 12,
+                normal + 4,
+                normal,
+                normal.saturating_sub(2).max(1),
+            ],
+            _ => [normal + 10, normal + 4, normal + 2, normal],
+        };
+        let caption_size = fonts.and_then(|f| f.caption_size).unwrap_or(match lang {
+            Language::Thai => 24,
+            Language::Lao | Language::Vietnamese | Language::Arabic | Language::Hebrew => 20,
+            _ => 18,
+        });
+        let color = |value: Option<&String>, default: Color| {
+            value.and_then(|hex| hex_color(hex)).unwrap_or(default)
+        };
+        let regular = |family: String| FontKey {
+            family,
+            bold: false,
+            italic: false,
+        };
+        let half_points = |size: u32| size as f32 / 2.0;
+
+        Self {
+            body: regular(
+                fonts
+                    .and_then(|f| f.default.clone())
+                    .unwrap_or_else(|| lang.default_ascii_font().to_string()),
+            ),
+            size: half_points(normal),
+            color: color(fonts.and_then(|f| f.normal_color.as_ref()), BLACK),
+            code: regular(
+                fonts
+                    .and_then(|f| f.code.clone())
+                    .unwrap_or_else(|| "Consolas".to_string()),
+            ),
+            code_size: half_points(fonts.and_then(|f| f.code_size).unwrap_or(20)),
+            caption_size: half_points(caption_size),
+            caption_color: color(fonts.and_then(|f| f.caption_color.as_ref()), BLACK),
+            heading_color: color(
+                fonts.and_then(|f| f.h1_color.as_ref()),
+                rgb(0x2F, 0x54, 0x96),
+            ),
+            heading_sizes: heading_sizes.map(half_points),
+        }
+    }
+}
+
+/// Where images come from and how diagrams and equations are rendered
+pub(super) struct Sources {
+    /// Directory relative image paths are resolved against
+    pub base_path: Option<PathBuf>,
+    /// Files to read images from instead of the file system
+    pub files: Option<Arc<FileMap>>,
+    /// Scale of rendered Mermaid diagrams, as in the DOCX (`mermaid_dpi / 75`)
+    pub mermaid_scale: f32,
+    pub math_font_size: String,
+}
+
+#[derive(Debug, Clone)]
+struct TextStyle {
+    font: FontKey,
+    size: f32,
+    color: Color,
+    underline: bool,
+    strike: bool,
+    /// Baseline shift upwards, for footnote numbers
+    rise: f32,
+    /// URI the text links to
+    link: Option<String>,
+}
+
+impl TextStyle {
+    fn new(font: FontKey, size: f32, color: Color) -> Self {
+        Self {
+            font,
+            size,
+            color,
+            underline: false,
+            strike: false,
+            rise: 0.0,
+            link: None,
+        }
+    }
+
+    fn bold(&self) -> Self {
+        let mut style = self.clone();
+        style.font.bold = true;
+        style
+    }
+
+    fn italic(&self) -> Self {
+        let mut style = self.clone();
+        style.font.italic = true;
+        style
+    }
+}
+
+/// A piece of a line
+#[derive(Debug, Clone)]
+enum Fragment {
+    Word {
+        text: String,
+        runs: Vec<Shaped>,
+        width: f32,
+        style: TextStyle,
+    },
+    Space {
+        width: f32,
+        style: TextStyle,
+    },
+    /// A break allowed without a space, as between Thai words
+    Opportunity,
+    /// An image or equation on the baseline, reaching `descent` below it
+    Object {
+        image: ImageId,
+        width: f32,
+        height: f32,
+        descent: f32,
+    },
+    /// Fixed space at the start of a line, for first-line indents
+    Gap(f32),
+    /// Forced line break
+    Break,
+}
+
+impl Fragment {
+    fn width(&self) -> f32 {
+        match self {
+            Fragment::Word { width, .. }
+            | Fragment::Space { width, .. }
+            | Fragment::Object { width, .. }
+            | Fragment::Gap(width) => *width,
+            Fragment::Opportunity | Fragment::Break => 0.0,
+        }
+    }
+
+    fn is_space(&self) -> bool {
+        matches!(self, Fragment::Space { .. } | Fragment::Opportunity)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    fragments: Vec<Fragment>,
+    width: f32,
+    ascent: f32,
+    descent: f32,
+    gap: f32,
+}
+
+impl Line {
+    fn height(&self) -> f32 {
+        self.ascent + self.descent + self.gap
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+    Left,
+    Center,
+}
+
+/// A table row broken into lines, one list of lines per cell
+struct Row {
+    cells: Vec<Vec<Line>>,
+    height: f32,
+    header: bool,
+}
+
+/// A heading listed in the table of contents
+struct TocEntry {
+    level: u8,
+    text: String,
+    page: usize,
+    y: f32,
+}
+
+/// Lays out a document into pages
+pub(super) struct Layout {
+    pub fonts: FontBook,
+    pub images: ImageStore,
+    pub pages: Vec<Page>,
+    theme: Theme,
+    strings: Strings,
+    sources: Sources,
+    geometry: Geometry,
+    xref: CrossRefContext,
+    /// Page being filled and the top of the free space on it
+    current: usize,
+    y: f32,
+    /// Left edge and width of the column being filled
+    x: f32,
+    width: f32,
+    /// Style paragraphs start from; italic in quotes, other fonts in font
+    /// groups
+    base: TextStyle,
+    /// x of the bars of the quotes being filled
+    bars: Vec<f32>,
+    /// List marker drawn beside the next line placed
+    marker: Option<(Vec<Shaped>, Color)>,
+    /// Baseline of the last line placed
+    last_baseline: f32,
+    /// Lists and footnotes being filled, which use tight paragraphs
+    compact: usize,
+    lists: usize,
+    /// Footnote labels in order of first reference
+    footnotes: Vec<String>,
+    figure_count: u32,
+    table_count: u32,
+    /// Before the first thematic break, whose headings are left out of the
+    /// contents
+    in_cover: bool,
+    /// First page after the cover
+    cover_end: Option<usize>,
+    toc_off: bool,
+    headings: Vec<TocEntry>,
+    /// Page of the first chapter, numbered 1
+    first_chapter: Option<usize>,
+    space_widths: HashMap<(FontKey, u32), f32>,
+}
+
+impl Layout {
+    pub fn new(
+        fonts: FontBook,
+        theme: Theme,
+        strings: Strings,
+        sources: Sources,
+        geometry: Geometry,
+    ) -> Self {
+        let base = TextStyle::new(theme.body.clone(), theme.size, theme.color);
+        Self {
+            fonts,
+            images: ImageStore::default(),
+            pages: vec![Page::default()],
+            theme,
+            strings,
+            sources,
+            geometry,
+            xref: CrossRefContext::new(),
+            current: 0,
+            y: geometry.top,
+            x: geometry.left,
+            width: geometry.width - geometry.left - geometry.right,
+            base,
+            bars: Vec::new(),
+            marker: None,
+            last_baseline: geometry.top,
+            compact: 0,
+            lists: 0,
+            footnotes: Vec::new(),
+            figure_count: 0,
+            table_count: 0,
+            in_cover: false,
+            cover_end: None,
+            toc_off: false,
+            headings: Vec::new(),
+            first_chapter: None,
+            space_widths: HashMap::new(),
+        }
+    }
+
+    /// Lay out the body, then the footnotes, the table of contents and the
+    /// page numbers
+    pub fn document(
+        &mut self,
+        blocks: &[Block],
+        notes: &HashMap<String, Vec<Block>>,
+        toc: &TocConfig,
+        all_headings: bool,
+    ) {
+        self.register_anchors(blocks);
+        self.in_cover = !all_headings && blocks.iter().any(|b| matches!(b, Block::ThematicBreak));
+        self.blocks(blocks);
+        self.notes(notes);
+        // Without a chapter, numbering starts after the cover
+        self.first_chapter = Some(self.first_chapter.or(self.cover_end).unwrap_or(0));
+        if toc.enabled {
+            self.contents(toc);
+        }
+        self.number_pages();
+    }
+
+    /// Number headings, figures, tables and equations with ids up front,
+    /// so references to later targets resolve too
+    fn register_anchors(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            match block {
+                Block::Heading {
+                    level,
+                    content,
+                    id: Some(id),
+                } => {
+                    self.xref
+                        .register_heading(id, *level, &extract_inline_text(content));
+                }
+                Block::Image {
+                    alt, id: Some(id), ..
+                } => {
+                    self.xref.register_figure(id, alt);
+                }
+                Block::Mermaid { id: Some(id), .. } => {
+                    self.xref.register_figure(id, "Mermaid Diagram");
+                }
+                Block::Table {
+                    caption,
+                    id: Some(id),
+                    ..
+                } => {
+                    self.xref
+                        .register_table(id, caption.as_deref().unwrap_or(""));
+                }
+                Block::MathBlock { id: Some(id), .. } => {
+                    self.xref.register_equation(id);
+                }
+                Block::BlockQuote(blocks)
+                | Block::FontGroup { blocks, .. }
+                | Block::LangGroup { blocks, .. }
+                | Block::Include {
+                    resolved: Some(blocks),
+                    ..
+                } => self.register_anchors(blocks),
+                Block::List { items, .. } => {
+                    for item in items {
+                        self.register_anchors(&item.content);
+                    }
+                }
+                Block::ParallelText { rows } => {
+                    for (source, translation) in rows {
+                        self.register_anchors(source);
+                        self.register_anchors(translation);
+                    }
+                }
+                Block::FigureGroup {
+                    figures,
+                    caption,
+                    id,
+                } => {
+                    let subfigures = subfigure_anchors(figures);
+                    if id.is_some() || subfigures.iter().any(|(sub_id, _)| sub_id.is_some()) {
+                        let caption = caption.as_deref().map(extract_inline_text);
+                        self.xref.register_figure_group(
+                            id.as_deref(),
+                            caption.as_deref().unwrap_or(""),
+                            &subfigures,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Heading { level, content, .. } => self.heading(*level, content),
+            Block::Paragraph(content) => self.paragraph(content),
+            Block::CodeBlock {
+                lang,
+                content,
+                filename,
+                callouts,
+                ..
+            } => self.code_block(
+                &code_with_callouts(content, callouts),
+                lang.as_deref(),
+                filename.as_deref(),
+            ),
+            Block::CodeCallouts(items) => {
+                for (number, text) in items {
+                    let mut content = vec![Inline::Text(format!("{} ", callout_glyph(*number)))];
+                    content.extend(text.iter().cloned());
+                    self.paragraph(&content);
+                }
+            }
+            Block::BlockQuote(blocks) => self.quote(blocks),
+            Block::List {
+                ordered,
+                start,
+                items,
+            } => self.list(*ordered, *start, items),
+            Block::Table {
+                headers,
+                alignments,
+                rows,
+                caption,
+                id,
+            } => self.table(headers, alignments, rows, caption.as_deref(), id.as_deref()),
+            Block::Image {
+                alt,
+                src,
+                width,
+                id,
+                caption,
+                ..
+            } => {
+                // Captions are laid out as plain text
+                let caption = caption.as_deref().map(extract_inline_text);
+                let caption = caption.as_deref().unwrap_or(alt);
+                self.image_block(alt, caption, src, width.as_deref(), id.as_deref())
+            }
+            Block::ThematicBreak => self.section_break(),
+            Block::Mermaid { content, .. } => self.mermaid(content),
+            Block::Html(html) => self.directive(html),
+            Block::MathBlock { content, id } => self.math_block(content, id.as_deref()),
+            Block::Include {
+                resolved: Some(blocks),
+                ..
+            }
+            | Block::LangGroup { blocks, .. } => self.blocks(blocks),
+            // Left unresolved when no include configuration is given
+            Block::Include { .. } | Block::CodeInclude { .. } => {}
+            Block::FontGroup { font, blocks } => {
+                let family = std::mem::replace(&mut self.base.font.family, font.clone());
+                self.blocks(blocks);
+                self.base.font.family = family;
+            }
+            Block::ParallelText { rows } => self.parallel(rows),
+            Block::FigureGroup {
+                figures,
+                caption,
+                id,
+            } => self.figure_group(figures, caption.as_deref(), id.as_deref()),
+        }
+    }
+
+    fn heading(&mut self, level: u8, content: &[Inline]) {
+        let lines = self.heading_lines(level, content);
+        if !self.in_cover && !self.toc_off {
+            self.headings.push(TocEntry {
+                level,
+                text: extract_inline_text(content),
+                page: self.current,
+                y: self.y,
+            });
+        }
+        if level == 1 && !self.in_cover && self.first_chapter.is_none() {
+            self.first_chapter = Some(self.current);
+        }
+        self.place_lines(lines, Align::Left, None);
+        let index = usize::from(level.clamp(1, 4)) - 1;
+        self.space(HEADING_SPACING[index].1);
+    }
+
+    /// Lines of a heading, with the space above it taken and the page
+    /// broken if the heading would not fit with the line after it
+    fn heading_lines(&mut self, level: u8, content: &[Inline]) -> Vec<Line> {
+        let index = usize::from(level.clamp(1, 4)) - 1;
+        let mut style = self.base.bold();
+        style.size = self.theme.heading_sizes[index];
+        style.color = self.theme.heading_color;
+        style.font.italic = level >= 4;
+
+        let fragments = self.fragments(content, &style);
+        let lines = self.break_lines(fragments, self.width, &style);
+        let height: f32 = lines.iter().map(Line::height).sum();
+        let base = self.base.clone();
+        let next = self.line(Vec::new(), &base).height();
+        self.space(HEADING_SPACING[index].0);
+        self.ensure(height + next);
+        lines
+    }
+
+    fn paragraph(&mut self, content: &[Inline]) {
+        let style = self.base.clone();
+        let body = self.compact == 0 && self.bars.is_empty();
+        let mut fragments = Vec::new();
+        if body {
+            fragments.push(Fragment::Gap(FIRST_LINE_INDENT));
+        }
+        fragments.extend(self.fragments(content, &style));
+        let lines = self.break_lines(fragments, self.width, &style);
+        self.place_lines(lines, Align::Left, None);
+        self.space(match (body, self.compact) {
+            (true, _) => 12.0,
+            (false, 0) => 6.0,
+            (false, _) => 3.0,
+        });
+    }
+
+    fn code_block(&mut self, content: &str, lang: Option<&str>, filename: Option<&str>) {
+        let style = TextStyle::new(self.theme.code.clone(), self.theme.code_size, BLACK);
+        self.space(6.0);
+        self.indented(CODE_INDENT, |layout| {
+            if let Some(name) = filename {
+                let fragments = layout.text_fragments(name, &style.bold());
+                let lines = layout.break_lines(fragments, layout.width, &style);
+                layout.place_lines(lines, Align::Left, None);
+            }
+            layout.shade(CODE_PADDING / 2.0);
+            for tokens in highlight_code(content.trim_end_matches('\n'), lang) {
+                let mut fragments = Vec::new();
+                for (text, color) in tokens {
+                    let text = text.trim_end_matches(['\r', '\n']).replace('\t', "    ");
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let token_style = TextStyle {
+                        color: color.as_deref().and_then(hex_color).unwrap_or(BLACK),
+                        ..style.clone()
+                    };
+                    fragments.push(layout.word(&text, &token_style));
+                }
+                let lines = layout.break_lines(fragments, layout.width, &style);
+                layout.place_lines(lines, Align::Left, Some(SHADE));
+            }
+            layout.shade(CODE_PADDING / 2.0);
+        });
+        self.space(6.0);
+    }
+
+    fn quote(&mut self, blocks: &[Block]) {
+        self.space(6.0);
+        self.bars.push(self.x + INDENT / 2.0);
+        let italic = std::mem::replace(&mut self.base.font.italic, true);
+        self.indented(INDENT, |layout| layout.blocks(blocks));
+        self.base.font.italic = italic;
+        self.bars.pop();
+        self.space(6.0);
+    }
+
+    fn list(&mut self, ordered: bool, start: Option<u32>, items: &[ListItem]) {
+        let depth = self.lists;
+        self.lists += 1;
+        self.compact += 1;
+        for (i, item) in items.iter().enumerate() {
+            let marker = match item.checked {
+                Some(true) => "☒".to_string(),
+                Some(false) => "☐".to_string(),
+                None if ordered => format!("{}.", start.unwrap_or(1) + i as u32),
+                None => BULLETS[depth % BULLETS.len()].to_string(),
+            };
+            self.set_marker(&marker);
+            self.indented(INDENT, |layout| layout.blocks(&item.content));
+            self.marker = None;
+        }
+        self.compact -= 1;
+        self.lists -= 1;
+        if self.lists == 0 {
+            self.space(9.0);
+        }
+    }
+
+    fn table(
+        &mut self,
+        headers: &[TableCell],
+        alignments: &[Alignment],
+        rows: &[Vec<TableCell>],
+        caption: Option<&str>,
+        id: Option<&str>,
+    ) {
+        let number = match id {
+            Some(id) => self.anchor_number(id),
+            None => {
+                self.table_count += 1;
+                self.table_count.to_string()
+            }
+        };
+        let columns = headers
+            .len()
+            .max(rows.iter().map(Vec::len).max().unwrap_or(0));
+        if columns == 0 {
+            return;
+        }
+        self.space(6.0);
+        if let Some(caption) = caption {
+            let text = format!("{} {}: {}", self.strings.get(Label::Table), number, caption);
+            let lines = self.caption_lines(&text);
+            self.place_lines(lines, Align::Center, None);
+            self.space(6.0);
+        }
+
+        let body = self.base.clone();
+        let header = body.bold();
+        let mut cells = Vec::new();
+        if !headers.is_empty() {
+            let row: Vec<_> = headers
+                .iter()
+                .map(|cell| self.fragments(&cell.content, &header))
+                .collect();
+            cells.push((row, true));
+        }
+        for row in rows {
+            let row: Vec<_> = row
+                .iter()
+                .map(|cell| {
+                    let style = if cell.is_header { &header } else { &body };
+                    self.fragments(&cell.content, style)
+                })
+                .collect();
+            cells.push((row, false));
+        }
+
+        let mut natural = vec![0.0f32; columns];
+        let mut minimum = vec![0.0f32; columns];
+        for (row, _) in &cells {
+            for (i, fragments) in row.iter().enumerate() {
+                let line: f32 = fragments.iter().map(Fragment::width).sum();
+                let word = fragments
+                    .iter()
+                    .filter(|f| !f.is_space())
+                    .map(Fragment::width)
+                    .fold(0.0, f32::max);
+                natural[i] = natural[i].max(line + 2.0 * CELL_PADDING);
+                minimum[i] = minimum[i].max(word + 2.0 * CELL_PADDING);
+            }
+        }
+        let widths = column_widths(&natural, &minimum, self.width);
+
+        let rows: Vec<Row> = cells
+            .into_iter()
+            .map(|(row, header)| self.row(row, &widths, header, &body))
+            .collect();
+        for (i, row) in rows.iter().enumerate() {
+            if self.y + row.height > self.bottom() && !self.at_top() {
+                self.new_page();
+                // The header row is repeated on every page of the table
+                if i > 0 && rows[0].header {
+                    self.draw_row(&rows[0], &widths, alignments);
+                }
+            }
+            self.draw_row(row, &widths, alignments);
+        }
+        self.space(12.0);
+    }
+
+    fn row(
+        &mut self,
+        cells: Vec<Vec<Fragment>>,
+        widths: &[f32],
+        header: bool,
+        style: &TextStyle,
+    ) -> Row {
+        let mut cells = cells.into_iter();
+        let cells: Vec<Vec<Line>> = widths
+            .iter()
+            .map(|width| {
+                let fragments = cells.next().unwrap_or_default();
+                self.break_lines(fragments, width - 2.0 * CELL_PADDING, style)
+            })
+            .collect();
+        let height = cells
+            .iter()
+            .map(|lines| lines.iter().map(Line::height).sum::<f32>())
+            .fold(0.0, f32::max);
+        Row {
+            cells,
+            height: height + 2.0 * CELL_PADDING,
+            header,
+        }
+    }
+
+    fn draw_row(&mut self, row: &Row, widths: &[f32], alignments: &[Alignment]) {
+        let top = self.y;
+        let total: f32 = widths.iter().sum();
+        if row.header {
+            self.rect(self.x, top, total, row.height, SHADE);
+        }
+        let mut x = self.x;
+        for (i, (lines, width)) in row.cells.iter().zip(widths).enumerate() {
+            let inner = width - 2.0 * CELL_PADDING;
+            let mut y = top + CELL_PADDING;
+            for line in lines {
+                let offset = match alignments.get(i) {
+                    Some(Alignment::Center) => (inner - line.width) / 2.0,
+                    Some(Alignment::Right) => inner - line.width,
+                    _ => 0.0,
+                };
+                self.draw_line(line, x + CELL_PADDING + offset.max(0.0), y + line.ascent);
+                y += line.height();
+            }
+            self.rect(x, top, BORDER, row.height, RULE);
+            x += width;
+        }
+        self.rect(x - BORDER, top, BORDER, row.height, RULE);
+        self.rect(self.x, top, total, BORDER, RULE);
+        self.rect(self.x, top + row.height - BORDER, total, BORDER, RULE);
+        self.y += row.height;
+    }
+
+    fn image_block(
+        &mut self,
+        alt: &str,
+        caption: &str,
+        src: &str,
+        width: Option<&str>,
+        id: Option<&str>,
+    ) {
+        let number = match id {
+            Some(id) => self.anchor_number(id),
+            None => {
+                self.figure_count += 1;
+                self.figure_count.to_string()
+            }
+        };
+        let Some(image) = self.load_image(src) else {
+            self.paragraph(&[Inline::Italic(vec![Inline::Text(alt.to_string())])]);
+            return;
+        };
+        let (width, height) = self.fit(image, width);
+        let caption = if caption.is_empty() {
+            None
+        } else {
+            let text = format!(
+                "{} {}: {}",
+                self.strings.get(Label::Figure),
+                number,
+                caption
+            );
+            Some(self.caption_lines(&text))
+        };
+        self.figure(image.id, width, height, caption);
+    }
+
+    /// Sub-figures in columns, each with an `(a)` caption, then the shared
+    /// caption across the full width
+    fn figure_group(&mut self, figures: &[Block], caption: Option<&[Inline]>, id: Option<&str>) {
+        let subfigures = subfigure_anchors(figures);
+        let sub_ids = subfigures.iter().filter_map(|(sub_id, _)| *sub_id);
+        let number = match self.xref.figure_group_number(id.into_iter().chain(sub_ids)) {
+            Some(number) => number,
+            None => {
+                self.figure_count += 1;
+                self.figure_count.to_string()
+            }
+        };
+        let images: Vec<(&str, String, &str, Option<&str>)> = figures
+            .iter()
+            .filter_map(|figure| match figure {
+                Block::Image {
+                    alt,
+                    src,
+                    width,
+                    caption,
+                    ..
+                } => {
+                    // Captions are laid out as plain text
+                    let text = caption.as_deref().map(extract_inline_text);
+                    let text = text.unwrap_or_else(|| alt.clone());
+                    Some((alt.as_str(), text, src.as_str(), width.as_deref()))
+                }
+                _ => None,
+            })
+            .collect();
+        if images.is_empty() {
+            return;
+        }
+
+        let (x, width) = (self.x, self.width);
+        let count = images.len() as f32;
+        let column = (width - COLUMN_GAP * (count - 1.0)) / count;
+        self.width = column;
+        let start = (self.current, self.y);
+        let mut end = start;
+        for (i, (alt, text, src, image_width)) in images.into_iter().enumerate() {
+            (self.current, self.y) = start;
+            self.x = x + (column + COLUMN_GAP) * i as f32;
+            let label = format!("({}) {}", subfigure_letter(i), text);
+            match self.load_image(src) {
+                Some(image) => {
+                    let (image_width, height) = self.fit(image, image_width);
+                    let lines = self.caption_lines(&label);
+                    self.figure(image.id, image_width, height, Some(lines));
+                }
+                None => {
+                    self.paragraph(&[Inline::Italic(vec![Inline::Text(alt.to_string())])]);
+                    let lines = self.caption_lines(&label);
+                    self.place_lines(lines, Align::Center, None);
+                }
+            }
+            if self.current > end.0 || (self.current == end.0 && self.y > end.1) {
+                end = (self.current, self.y);
+            }
+        }
+        (self.current, self.y) = end;
+        self.x = x;
+        self.width = width;
+
+        let mut text = format!("{} {}", self.strings.get(Label::Figure), number);
+        if let Some(caption) = caption {
+            text.push_str(": ");
+            text.push_str(&extract_inline_text(caption));
+        }
+        self.space(6.0);
+        let lines = self.caption_lines(&text);
+        self.place_lines(lines, Align::Center, None);
+    }
+
+    /// A centered image and its caption, kept on one page
+    fn figure(&mut self, image: ImageId, width: f32, height: f32, caption: Option<Vec<Line>>) {
+        let caption_height = caption.as_ref().map_or(0.0, |lines| {
+            lines.iter().map(Line::height).sum::<f32>() + 6.0
+        });
+        self.space(6.0);
+        self.ensure(height + caption_height);
+        self.push(Item::Image {
+            x: self.x + (self.width - width) / 2.0,
+            y: self.y,
+            width,
+            height,
+            image,
+        });
+        self.y += height;
+        if let Some(lines) = caption {
+            self.space(6.0);
+            self.place_lines(lines, Align::Center, None);
+        }
+        self.space(6.0);
+    }
+
+    fn caption_lines(&mut self, text: &str) -> Vec<Line> {
+        let style = TextStyle::new(
+            self.base.font.clone(),
+            self.theme.caption_size,
+            self.theme.caption_color,
+        );
+        let fragments = self.text_fragments(text, &style);
+        self.break_lines(fragments, self.width, &style)
+    }
+
+    fn mermaid(&mut self, content: &str) {
+        let scale = self.sources.mermaid_scale;
+        let image = crate::mermaid::render_to_png(content, scale).and_then(|png| {
+            self.images
+                .add_scaled_png(&format!("mermaid:{}", content), &png, scale)
+        });
+        match image {
+            Ok(image) => {
+                let (width, height) = self.fit(image, None);
+                self.figure(image.id, width, height, None);
+            }
+            Err(e) => {
+                diagnostics::warn(
+                    WarningCategory::Diagram,
+                    format!("Mermaid diagram shown as source in the PDF: {}", e),
+                );
+                self.code_block(content, None, None);
+            }
+        }
+    }
+
+    fn math_block(&mut self, content: &str, id: Option<&str>) {
+        let Some(object) = self.math(content, true) else {
+            self.code_block(content, None, None);
+            return;
+        };
+        let style = self.base.clone();
+        let width = object.width();
+        let line = self.line(vec![object], &style);
+        self.space(6.0);
+        self.ensure(line.height());
+        let baseline = self.y + line.ascent;
+        self.draw_line(&line, self.x + (self.width - width) / 2.0, baseline);
+        if let Some(id) = id {
+            let number = format!("({})", self.anchor_number(id));
+            let runs = self.fonts.shape(&number, &style.font, style.size);
+            let number_width: f32 = runs.iter().map(Shaped::width).sum();
+            self.draw_runs(
+                runs,
+                self.x + self.width - number_width,
+                baseline,
+                style.color,
+            );
+        }
+        self.y += line.height();
+        self.space(6.0);
+    }
+
+    /// An equation as an image on the baseline, `None` if it fails to render
+    fn math(&mut self, latex: &str, display: bool) -> Option<Fragment> {
+        let rendered =
+            render_latex_to_svg(latex, display, &self.sources.math_font_size).and_then(|result| {
+                let points = |emu: i64| emu as f32 / EMU_PER_PT;
+                let (width, height) = (points(result.width_emu), points(result.height_emu));
+                let key = format!("math:{}:{}", display, latex);
+                let image =
+                    self.images
+                        .add_svg(&key, &result.svg_bytes, width, height, MATH_SCALE)?;
+                Ok(Fragment::Object {
+                    image: image.id,
+                    width,
+                    height,
+                    descent: points(result.descent_emu),
+                })
+            });
+        match rendered {
+            Ok(object) => Some(object),
+            Err(e) => {
+                diagnostics::warn(
+                    WarningCategory::Math,
+                    format!("Equation shown as source in the PDF: {}", e),
+                );
+                None
+            }
+        }
+    }
+
+    /// A thematic break starts a new page, as it starts a section in the DOCX
+    fn section_break(&mut self) {
+        if !self.pages[self.current].items.is_empty() {
+            self.new_page();
+        }
+        if self.in_cover {
+            self.in_cover = false;
+            self.cover_end = Some(self.current);
+        }
+    }
+
+    fn directive(&mut self, html: &str) {
+        let compact: String = html.chars().filter(|c| !c.is_whitespace()).collect();
+        match compact.as_str() {
+            "<!--{toc:off}-->" => self.toc_off = true,
+            "<!--{toc:on}-->" => self.toc_off = false,
+            _ => {}
+        }
+    }
+
+    /// Source and translation side by side, each row starting level
+    fn parallel(&mut self, rows: &[(Vec<Block>, Vec<Block>)]) {
+        let (x, width) = (self.x, self.width);
+        let column = (width - COLUMN_GAP) / 2.0;
+        self.width = column;
+        for (source, translation) in rows {
+            let start = (self.current, self.y);
+            self.x = x;
+            self.blocks(source);
+            let left = (self.current, self.y);
+
+            (self.current, self.y) = start;
+            self.x = x + column + COLUMN_GAP;
+            self.blocks(translation);
+            if left.0 > self.current || (left.0 == self.current && left.1 > self.y) {
+                (self.current, self.y) = left;
+            }
+        }
+        self.x = x;
+        self.width = width;
+    }
+
+    /// Footnotes, numbered in order of reference, after the body
+    fn notes(&mut self, notes: &HashMap<String, Vec<Block>>) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+        self.space(12.0);
+        self.ensure(self.theme.size * 2.0);
+        self.rect(self.x, self.y, 144.0, BORDER, RULE);
+        self.space(6.0);
+
+        let size = std::mem::replace(&mut self.base.size, self.theme.caption_size);
+        self.compact += 1;
+        // Notes may reference further notes, which are appended as they go
+        let mut i = 0;
+        while i < self.footnotes.len() {
+            let label = self.footnotes[i].clone();
+            self.set_marker(&format!("{}.", i + 1));
+            if let Some(blocks) = notes.get(&label) {
+                self.indented(INDENT / 2.0, |layout| layout.blocks(blocks));
+            }
+            self.marker = None;
+            i += 1;
+        }
+        self.compact -= 1;
+        self.base.size = size;
+    }
+
+    /// Lay out the table of contents and insert its pages after the cover,
+    /// or first
+    fn contents(&mut self, toc: &TocConfig) {
+        let entries: Vec<TocEntry> = std::mem::take(&mut self.headings)
+            .into_iter()
+            .filter(|entry| toc.includes_level(entry.level))
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+        let first = self.first_chapter.unwrap_or(0);
+        let at = if toc.after_cover {
+            self.cover_end.unwrap_or(0)
+        } else {
+            0
+        };
+
+        let body = std::mem::replace(&mut self.pages, vec![Page::default()]);
+        let (current, y) = (self.current, self.y);
+        (self.current, self.y) = (0, self.geometry.top);
+
+        let lines = self.heading_lines(1, &[Inline::Text(toc.title.clone())]);
+        self.place_lines(lines, Align::Left, None);
+        self.space(HEADING_SPACING[0].1);
+
+        let style = self.base.clone();
+        let dot = self.measure(".", &style);
+        for entry in &entries {
+            let number = match (entry.page.checked_sub(first), toc.page_numbers) {
+                (Some(n), TocPageNumbers::Right | TocPageNumbers::Inline) => (n + 1).to_string(),
+                _ => String::new(),
+            };
+            let indent = f32::from(entry.level.saturating_sub(1)) * 12.0;
+            let inline = toc.page_numbers == TocPageNumbers::Inline;
+            let text = if inline && !number.is_empty() {
+                format!("{} {}", entry.text, number)
+            } else {
+                entry.text.clone()
+            };
+            let runs = self.fonts.shape(&number, &style.font, style.size);
+            let number_width: f32 = runs.iter().map(Shaped::width).sum();
+            // Room for the page number and a few leader dots
+            let reserved = if inline {
+                0.0
+            } else {
+                number_width + 4.0 * dot
+            };
+
+            self.indented(indent, |layout| {
+                let fragments = layout.text_fragments(&text, &style);
+                let lines = layout.break_lines(fragments, layout.width - reserved, &style);
+                let last_width = lines.last().map_or(0.0, |line| line.width);
+                let (page, top) = (layout.current, layout.y);
+                layout.place_lines(lines, Align::Left, None);
+                let baseline = layout.last_baseline;
+                if !inline && !number.is_empty() {
+                    let right = layout.x + layout.width;
+                    if toc.dot_leader && dot > 0.0 {
+                        let space = right - number_width - (layout.x + last_width) - 2.0 * dot;
+                        let dots = ".".repeat((space / dot).max(0.0) as usize);
+                        let leader = layout.fonts.shape(&dots, &style.font, style.size);
+                        let leader_width: f32 = leader.iter().map(Shaped::width).sum();
+                        let start = right - number_width - dot - leader_width;
+                        layout.draw_runs(leader, start, baseline, style.color);
+                    }
+                    layout.draw_runs(runs, right - number_width, baseline, style.color);
+                }
+                if toc.hyperlinks {
+                    // An entry broken over two pages links from its second part
+                    let top = if layout.current == page {
+                        top
+                    } else {
+                        layout.geometry.top
+                    };
+                    let (x, width, height) = (layout.x, layout.width, layout.y - top);
+                    layout.pages[layout.current].links.push(Link {
+                        x,
+                        y: top,
+                        width,
+                        height,
+                        target: Target::Page {
+                            page: entry.page,
+                            y: entry.y,
+                        },
+                    });
+                }
+            });
+            self.space(2.0);
+        }
+
+        let mut pages = std::mem::replace(&mut self.pages, body);
+        (self.current, self.y) = (current, y);
+        let count = pages.len();
+        for page in self.pages.iter_mut().chain(pages.iter_mut()) {
+            for link in &mut page.links {
+                if let Target::Page { page, .. } = &mut link.target {
+                    if *page >= at {
+                        *page += count;
+                    }
+                }
+            }
+        }
+        if first >= at {
+            self.first_chapter = Some(first + count);
+        }
+        self.pages.splice(at..at, pages);
+    }
+
+    /// Number the pages from the first chapter on and print the numbers
+    /// centered in the bottom margin
+    fn number_pages(&mut self) {
+        let first = self.first_chapter.unwrap_or(0);
+        let style = TextStyle::new(self.theme.body.clone(), self.theme.size, self.theme.color);
+        let y = self.geometry.height - self.geometry.bottom / 2.0;
+        for (i, page) in self.pages.iter_mut().enumerate().skip(first) {
+            let number = (i - first + 1) as u32;
+            page.number = Some(number);
+            let runs = self
+                .fonts
+                .shape(&number.to_string(), &style.font, style.size);
+            let width: f32 = runs.iter().map(Shaped::width).sum();
+            let mut x = (self.geometry.width - width) / 2.0;
+            for run in runs {
+                let run_width = run.width();
+                page.items.push(Item::Text {
+                    x,
+                    y,
+                    run,
+                    color: style.color,
+                });
+                x += run_width;
+            }
+        }
+    }
+
+    fn set_marker(&mut self, marker: &str) {
+        let style = self.base.clone();
+        let runs = self.fonts.shape(marker, &style.font, style.size);
+        self.marker = Some((runs, style.color));
+    }
+
+    fn indented(&mut self, amount: f32, f: impl FnOnce(&mut Self)) {
+        self.x += amount;
+        self.width -= amount;
+        f(self);
+        self.x -= amount;
+        self.width += amount;
+    }
+
+    /// Text, images and equations of `inlines` as line fragments
+    fn fragments(&mut self, inlines: &[Inline], style: &TextStyle) -> Vec<Fragment> {
+        let mut fragments = Vec::new();
+        self.inlines(inlines, style, &mut fragments);
+        fragments
+    }
+
+    fn inlines(&mut self, inlines: &[Inline], style: &TextStyle, out: &mut Vec<Fragment>) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => out.extend(self.text_fragments(text, style)),
+                Inline::Bold(inner) => self.inlines(inner, &style.bold(), out),
+                Inline::Italic(inner) => self.inlines(inner, &style.italic(), out),
+                Inline::BoldItalic(inner) => self.inlines(inner, &style.bold().italic(), out),
+                Inline::Strikethrough(inner) => {
+                    let struck = TextStyle {
+                        strike: true,
+                        ..style.clone()
+                    };
+                    self.inlines(inner, &struck, out)
+                }
+                Inline::Code(code) => {
+                    let code_style = TextStyle {
+                        font: self.theme.code.clone(),
+                        color: INLINE_CODE,
+                        ..style.clone()
+                    };
+                    out.extend(self.text_fragments(code, &code_style));
+                }
+                Inline::Kbd(key) => {
+                    let kbd_style = TextStyle {
+                        size: style.size * 0.9,
+                        ..style.clone()
+                    };
+                    out.extend(self.text_fragments(key, &kbd_style));
+                }
+                Inline::Link { text, url, .. } => {
+                    let external = ["http://", "https://", "mailto:"]
+                        .iter()
+                        .any(|scheme| url.starts_with(scheme));
+                    let linked = TextStyle {
+                        color: LINK,
+                        underline: true,
+                        link: external.then(|| url.clone()),
+                        ..style.clone()
+                    };
+                    self.inlines(text, &linked, out)
+                }
+                Inline::Image { alt, src, .. } => match self.load_image(src) {
+                    Some(image) => {
+                        let (width, height) = self.fit(image, None);
+                        out.push(Fragment::Object {
+                            image: image.id,
+                            width,
+                            height,
+                            descent: 0.0,
+                        });
+                    }
+                    None => out.extend(self.text_fragments(alt, style)),
+                },
+                Inline::FootnoteRef(label) => {
+                    let number = match self.footnotes.iter().position(|l| l == label) {
+                        Some(i) => i + 1,
+                        None => {
+                            self.footnotes.push(label.clone());
+                            self.footnotes.len()
+                        }
+                    };
+                    let mark = TextStyle {
+                        size: style.size * 0.65,
+                        rise: style.size * 0.35,
+                        ..style.clone()
+                    };
+                    out.extend(self.text_fragments(&number.to_string(), &mark));
+                }
+                Inline::CrossRef { target, .. } => {
+                    let text = self.xref.get_localized_display_text(target, &self.strings);
+                    out.extend(self.text_fragments(&text, style));
+                }
+                Inline::SoftBreak => out.extend(self.text_fragments(" ", style)),
+                Inline::HardBreak => out.push(Fragment::Break),
+                Inline::Html(html) => {
+                    let tag = html.trim().to_ascii_lowercase();
+                    if matches!(tag.as_str(), "<br>" | "<br/>" | "<br />") {
+                        out.push(Fragment::Break);
+                    }
+                }
+                Inline::IndexMarker(_) => {}
+                Inline::InlineMath(latex) | Inline::DisplayMath(latex) => {
+                    let display = matches!(inline, Inline::DisplayMath(_));
+                    match self.math(latex, display) {
+                        Some(object) => out.push(object),
+                        None => out.extend(self.text_fragments(latex, &style.italic())),
+                    }
+                }
+            }
+        }
+    }
+
+    fn text_fragments(&mut self, text: &str, style: &TextStyle) -> Vec<Fragment> {
+        let text = insert_thai_breaks(text);
+        let mut fragments = Vec::new();
+        for token in tokens(&text) {
+            fragments.push(match token {
+                Token::Word(word) => self.word(word, style),
+                Token::Space => Fragment::Space {
+                    width: self.space_width(style),
+                    style: style.clone(),
+                },
+                Token::Opportunity => Fragment::Opportunity,
+            });
+        }
+        fragments
+    }
+
+    fn word(&mut self, text: &str, style: &TextStyle) -> Fragment {
+        let runs = self.fonts.shape(text, &style.font, style.size);
+        Fragment::Word {
+            text: text.to_string(),
+            width: runs.iter().map(Shaped::width).sum(),
+            runs,
+            style: style.clone(),
+        }
+    }
+
+    fn measure(&mut self, text: &str, style: &TextStyle) -> f32 {
+        self.fonts
+            .shape(text, &style.font, style.size)
+            .iter()
+            .map(Shaped::width)
+            .sum()
+    }
+
+    fn space_width(&mut self, style: &TextStyle) -> f32 {
+        let key = (style.font.clone(), style.size.to_bits());
+        if let Some(&width) = self.space_widths.get(&key) {
+            return width;
+        }
+        let width = self.measure(" ", style);
+        self.space_widths.insert(key, width);
+        width
+    }
+
+    /// Fill lines of at most `width` points
+    ///
+    /// Lines break at spaces and break opportunities; a line without one is
+    /// broken between fragments, and a word wider than `width` between its
+    /// graphemes. Every line is at least as tall as `style`'s font, and an
+    /// empty paragraph still gets one line.
+    fn break_lines(
+        &mut self,
+        fragments: Vec<Fragment>,
+        width: f32,
+        style: &TextStyle,
+    ) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut line: Vec<Fragment> = Vec::new();
+        let mut line_width = 0.0;
+        // Start of the fragments after the line's last break opportunity
+        let mut tail = 0;
+        for fragment in fragments {
+            match fragment {
+                Fragment::Break => {
+                    lines.push(self.line(std::mem::take(&mut line), style));
+                    line_width = 0.0;
+                    tail = 0;
+                }
+                Fragment::Space { .. } | Fragment::Opportunity => {
+                    // Spaces at the start of a line are dropped
+                    if !line.is_empty() {
+                        line_width += fragment.width();
+                        line.push(fragment);
+                        tail = line.len();
+                    }
+                }
+                _ => {
+                    let fragment_width = fragment.width();
+                    while line_width + fragment_width > width && !line.is_empty() {
+                        let rest = if tail > 0 {
+                            line.split_off(tail)
+                        } else {
+                            Vec::new()
+                        };
+                        lines.push(self.line(std::mem::replace(&mut line, rest), style));
+                        line_width = line.iter().map(Fragment::width).sum();
+                        tail = 0;
+                    }
+                    let parts = match fragment {
+                        Fragment::Word {
+                            text,
+                            style: word_style,
+                            ..
+                        } if fragment_width > width => self.split(&text, &word_style, width),
+                        fragment => vec![fragment],
+                    };
+                    for part in parts {
+                        let part_width = part.width();
+                        if line_width + part_width > width && !line.is_empty() {
+                            lines.push(self.line(std::mem::take(&mut line), style));
+                            line_width = 0.0;
+                            tail = 0;
+                        }
+                        line_width += part_width;
+                        line.push(part);
+                    }
+                }
+            }
+        }
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(self.line(line, style));
+        }
+        lines
+    }
+
+    /// Split a word wider than `width` between graphemes
+    fn split(&mut self, text: &str, style: &TextStyle, width: f32) -> Vec<Fragment> {
+        let mut parts = Vec::new();
+        let mut current = String::new();
+        for grapheme in text.graphemes(true) {
+            let candidate = format!("{}{}", current, grapheme);
+            if !current.is_empty() && self.measure(&candidate, style) > width {
+                parts.push(self.word(&current, style));
+                current = grapheme.to_string();
+            } else {
+                current = candidate;
+            }
+        }
+        if !current.is_empty() {
+            parts.push(self.word(&current, style));
+        }
+        parts
+    }
+
+    /// A line of fragments with trailing spaces dropped, at least as tall as
+    /// `style`'s font
+    fn line(&mut self, mut fragments: Vec<Fragment>, style: &TextStyle) -> Line {
+        while fragments.last().is_some_and(Fragment::is_space) {
+            fragments.pop();
+        }
+        let (mut ascent, mut descent, mut gap) = self.fonts.metrics(&style.font, style.size);
+        for fragment in &fragments {
+            match fragment {
+                Fragment::Word {
+                    style: word_style, ..
+                } => {
+                    let (a, d, g) = self.fonts.metrics(&word_style.font, word_style.size);
+                    ascent = ascent.max(a + word_style.rise);
+                    descent = descent.max(d);
+                    gap = gap.max(g);
+                }
+                Fragment::Object {
+                    height,
+                    descent: below,
+                    ..
+                } => {
+                    ascent = ascent.max(height - below);
+                    descent = descent.max(*below);
+                }
+                _ => {}
+            }
+        }
+        Line {
+            width: fragments.iter().map(Fragment::width).sum(),
+            fragments,
+            ascent,
+            descent,
+            gap,
+        }
+    }
+
+    /// Place lines below the cursor, breaking pages between them
+    fn place_lines(&mut self, lines: Vec<Line>, align: Align, shade: Option<Color>) {
+        for line in lines {
+            let height = line.height();
+            self.ensure(height);
+            if let Some(color) = shade {
+                self.rect(
+                    self.x - CODE_PADDING,
+                    self.y,
+                    self.width + CODE_PADDING,
+                    height,
+                    color,
+                );
+            }
+            self.draw_bars(self.y, height);
+            let x = match align {
+                Align::Left => self.x,
+                Align::Center => self.x + ((self.width - line.width) / 2.0).max(0.0),
+            };
+            let baseline = self.y + line.ascent;
+            if let Some((runs, color)) = self.marker.take() {
+                let width: f32 = runs.iter().map(Shaped::width).sum();
+                self.draw_runs(runs, self.x - width - MARKER_GAP, baseline, color);
+            }
+            self.draw_line(&line, x, baseline);
+            self.last_baseline = baseline;
+            self.y += height;
+        }
+    }
+
+    fn draw_line(&mut self, line: &Line, x: f32, baseline: f32) {
+        let mut pen = x;
+        for fragment in &line.fragments {
+            match fragment {
+                Fragment::Word {
+                    runs, width, style, ..
+                } => {
+                    self.draw_runs(runs.clone(), pen, baseline - style.rise, style.color);
+                    self.decorate(style, pen, baseline, *width);
+                }
+                Fragment::Space { width, style } => self.decorate(style, pen, baseline, *width),
+                Fragment::Object {
+                    image,
+                    width,
+                    height,
+                    descent,
+                } => self.push(Item::Image {
+                    x: pen,
+                    y: baseline + descent - height,
+                    width: *width,
+                    height: *height,
+                    image: *image,
+                }),
+                Fragment::Opportunity | Fragment::Gap(_) | Fragment::Break => {}
+            }
+            pen += fragment.width();
+        }
+    }
+
+    fn draw_runs(&mut self, runs: Vec<Shaped>, mut x: f32, baseline: f32, color: Color) {
+        for run in runs {
+            let width = run.width();
+            self.push(Item::Text {
+                x,
+                y: baseline,
+                run,
+                color,
+            });
+            x += width;
+        }
+    }
+
+    /// Underline, strikethrough and link area of a piece of text
+    fn decorate(&mut self, style: &TextStyle, x: f32, baseline: f32, width: f32) {
+        let thickness = style.size / 18.0;
+        if style.underline {
+            self.rect(
+                x,
+                baseline + style.size * 0.1,
+                width,
+                thickness,
+                style.color,
+            );
+        }
+        if style.strike {
+            self.rect(
+                x,
+                baseline - style.size * 0.3,
+                width,
+                thickness,
+                style.color,
+            );
+        }
+        if let Some(url) = &style.link {
+            let target = Target::Uri(url.clone());
+            let (y, height) = (baseline - style.size * 0.9, style.size * 1.2);
+            let links = &mut self.pages[self.current].links;
+            // Words of one link on one line share an annotation
+            match links.last_mut() {
+                Some(last)
+                    if last.target == target
+                        && (last.y - y).abs() < 0.01
+                        && (last.x + last.width - x).abs() < 0.01 =>
+                {
+                    last.width += width
+                }
+                _ => links.push(Link {
+                    x,
+                    y,
+                    width,
+                    height,
+                    target,
+                }),
+            }
+        }
+    }
+
+    fn draw_bars(&mut self, y: f32, height: f32) {
+        let page = &mut self.pages[self.current];
+        for &x in &self.bars {
+            page.items.push(Item::Rect {
+                x,
+                y,
+                width: 2.0,
+                height,
+                color: RULE,
+            });
+        }
+    }
+
+    /// Shaded space, padding a code block
+    fn shade(&mut self, height: f32) {
+        self.ensure(height);
+        self.rect(
+            self.x - CODE_PADDING,
+            self.y,
+            self.width + CODE_PADDING,
+            height,
+            SHADE,
+        );
+        self.y += height;
+    }
+
+    fn anchor_number(&self, id: &str) -> String {
+        self.xref
+            .resolve(id)
+            .and_then(|anchor| anchor.number.clone())
+            .unwrap_or_default()
+    }
+
+    /// Read and store an image, `None` with a warning if it can't be embedded
+    fn load_image(&mut self, src: &str) -> Option<StoredImage> {
+        if ["http://", "https://", "data:"]
+            .iter()
+            .any(|scheme| src.starts_with(scheme))
+        {
+            diagnostics::warn(
+                WarningCategory::MissingImage,
+                format!(
+                    "Image '{}' is not a local file and is left out of the PDF",
+                    src
+                ),
+            );
+            return None;
+        }
+        let path = match &self.sources.base_path {
+            Some(base) if Path::new(src).is_relative() => base.join(src),
+            _ => PathBuf::from(src),
+        };
+        let data = match &self.sources.files {
+            Some(files) => files.get(&path).map(|data| data.to_vec()),
+            None => std::fs::read(&path).ok(),
+        };
+        let Some(data) = data else {
+            diagnostics::warn(
+                WarningCategory::MissingImage,
+                format!("Image not found: {}", path.display()),
+            );
+            return None;
+        };
+        match self.images.add_file(&path.to_string_lossy(), &data) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                diagnostics::warn(
+                    WarningCategory::MissingImage,
+                    format!("Image '{}' could not be embedded in the PDF: {}", src, e),
+                );
+                None
+            }
+        }
+    }
+
+    /// Display size of an image: the requested width (a length or a
+    /// percentage of the column) or its natural size, kept within the column
+    /// and the page
+    fn fit(&self, image: StoredImage, width: Option<&str>) -> (f32, f32) {
+        let aspect = image.height / image.width.max(0.01);
+        let requested = width.and_then(|width| match width.trim().strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .map(|percent| self.width * percent / 100.0),
+            None => parse_length_to_twips(width).map(|twips| twips as f32 / 20.0),
+        });
+        let max_height = (self.bottom() - self.geometry.top) * 0.9;
+        let width = requested
+            .unwrap_or(image.width)
+            .min(self.width)
+            .min(max_height / aspect.max(0.01));
+        (width, width * aspect)
+    }
+
+    fn push(&mut self, item: Item) {
+        self.pages[self.current].items.push(item);
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.push(Item::Rect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    fn bottom(&self) -> f32 {
+        self.geometry.height - self.geometry.bottom
+    }
+
+    fn at_top(&self) -> bool {
+        self.y <= self.geometry.top + 0.01
+    }
+
+    /// Move to the next page unless `height` fits below the cursor
+    fn ensure(&mut self, height: f32) {
+        if self.y + height > self.bottom() && !self.at_top() {
+            self.new_page();
+        }
+    }
+
+    /// Vertical space, dropped at the top of a page
+    fn space(&mut self, height: f32) {
+        if self.at_top() {
+            return;
+        }
+        let height = height.min(self.bottom() - self.y).max(0.0);
+        self.draw_bars(self.y, height);
+        self.y += height;
+    }
+
+    fn new_page(&mut self) {
+        self.current += 1;
+        // Parallel columns revisit pages the other column already added
+        if self.current == self.pages.len() {
+            self.pages.push(Page::default());
+        }
+        self.y = self.geometry.top;
+    }
+}
+
+/// Widths of table columns filling `available` points
+///
+/// Columns get their natural width scaled to fill the table. When that is
+/// too wide, each column keeps its longest word and the rest of the space
+/// is shared in proportion to how much more the column would like.
+fn column_widths(natural: &[f32], minimum: &[f32], available: f32) -> Vec<f32> {
+    let total: f32 = natural.iter().sum();
+    if total <= available {
+        return natural.iter().map(|w| w * available / total).collect();
+    }
+    let least: f32 = minimum.iter().sum();
+    if least >= available {
+        return minimum.iter().map(|w| w * available / least).collect();
+    }
+    let extra = (available - least) / (total - least);
+    minimum
+        .iter()
+        .zip(natural)
+        .map(|(min, natural)| min + (natural - min) * extra)
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Space,
+    /// A break allowed between two words, without a space
+    Opportunity,
+}
+
+/// Split text into words and the breaks between them
+///
+/// Runs of spaces collapse into one; no-break spaces stay inside words.
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace() && !matches!(c, '\u{A0}' | '\u{2007}' | '\u{202F}');
+        if space || c == ZERO_WIDTH_SPACE {
+            if let Some(s) = start.take() {
+                tokens.push(Token::Word(&text[s..i]));
+            }
+            if !space {
+                tokens.push(Token::Opportunity);
+            } else if tokens.last() != Some(&Token::Space) {
+                tokens.push(Token::Space);
+            }
+        } else {
+            let s = *start.get_or_insert(i);
+            if is_ideograph(c) {
+                tokens.push(Token::Word(&text[s..i + c.len_utf8()]));
+                tokens.push(Token::Opportunity);
+                start = None;
+            }
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token::Word(&text[s..]));
+    }
+    tokens
+}
+
+/// Chinese and Japanese characters, which lines may break after
+fn is_ideograph(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0xFF01..=0xFF60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            tokens("a  b\u{A0}c"),
+            vec![Token::Word("a"), Token::Space, Token::Word("b\u{A0}c")]
+        );
+        assert_eq!(
+            tokens("สวัสดี\u{200B}ครับ"),
+            vec![Token::Word("สวัสดี"), Token::Opportunity, Token::Word("ครับ")]
+        );
+        assert_eq!(
+            tokens("日本x"),
+            vec![
+                Token::Word("日"),
+                Token::Opportunity,
+                Token::Word("本"),
+                Token::Opportunity,
+                Token::Word("x")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_widths() {
+        // Narrow tables are stretched to the full width
+        assert_eq!(
+            column_widths(&[10.0, 30.0], &[5.0, 5.0], 80.0),
+            vec![20.0, 60.0]
+        );
+        // Wide ones keep each column's longest word
+        assert_eq!(
+            column_widths(&[100.0, 20.0], &[20.0, 20.0], 60.0),
+            vec![40.0, 20.0]
+        );
+        assert_eq!(
+            column_widths(&[50.0, 50.0], &[40.0, 60.0], 50.0),
+            vec![20.0, 30.0]
+        );
+    }
+
+    #[test]
+    fn test_theme() {
+        let theme = Theme::new(Language::Thai, None);
+        assert_eq!(theme.body.family, "TH Sarabun New");
+        assert_eq!(theme.size, 14.0);
+        assert_eq!(theme.heading_sizes, [20.0, 16.0, 14.0, 13.0]);
+        assert_eq!(theme.caption_size, 12.0);
+
+        let fonts = FontConfig {
+            default: Some("Inter".to_string()),
+            h1_color: Some("#FF0000".to_string()),
+            ..Default::default()
+        };
+        let theme = Theme::new(Language::English, Some(&fonts));
+        assert_eq!(theme.body.family, "Inter");
+        assert_eq!(theme.heading_sizes, [16.0, 13.0, 12.0, 11.0]);
+        assert_eq!(theme.heading_color, [1.0, 0.0, 0.0]);
+        assert_eq!(theme.code.family, "Consolas");
+    }
+}
@@ -0,0 +1,80 @@
+//! PDF output (`md2docx build --pdf`)
+//!
+//! The document is laid out and written directly, without Word or
+//! LibreOffice: fonts are found and shaped by `font`, blocks are broken
+//! into lines and pages by `layout`, and `write` serializes the pages.
+//! The result follows the DOCX styles and numbering, with a linked table
+//! of contents and page numbers in the footer. Cover, header and footer
+//! templates and watermarks only apply to DOCX output.
+
+mod font;
+mod image;
+mod layout;
+mod write;
+
+use crate::i18n::Strings;
+use crate::{DocumentConfig, Language, ParsedDocument, Result};
+
+use font::FontBook;
+use layout::{Geometry, Layout, Sources, Theme};
+
+/// Render a parsed document to PDF bytes
+pub fn render(doc: &ParsedDocument, lang: Language, config: &DocumentConfig) -> Result<Vec<u8>> {
+    let blocks = match config.include_resolver() {
+        Some(mut resolver) => resolver.resolve_blocks(doc.blocks.clone())?,
+        None => doc.blocks.clone(),
+    };
+
+    let mut fallbacks = vec![lang.default_cs_font().to_string()];
+    fallbacks.extend(lang.default_east_asia_font().map(str::to_string));
+    let fonts = FontBook::new(config.embed_dir.as_deref(), fallbacks);
+    let geometry = Geometry::new(config.page.as_ref());
+    let sources = Sources {
+        base_path: config.base_path.clone(),
+        files: config.files.clone(),
+        // Diagrams are rendered at `mermaid_dpi` and drawn at 75 DPI
+        mermaid_scale: config.mermaid_dpi.max(1) as f32 / 75.0,
+        math_font_size: config.math_font_size.clone(),
+    };
+    let strings = Strings::new(lang).with_overrides(config.string_overrides.clone());
+
+    let mut layout = Layout::new(
+        fonts,
+        Theme::new(lang, config.fonts.as_ref()),
+        strings,
+        sources,
+        geometry,
+    );
+    layout.document(
+        &blocks,
+        &doc.footnotes,
+        &config.toc,
+        config.process_all_headings,
+    );
+    Ok(write::write(
+        &layout.pages,
+        &layout.fonts,
+        &layout.images,
+        geometry,
+        &config.title,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_markdown_with_frontmatter;
+
+    #[test]
+    fn test_render_pdf() {
+        let markdown = "# บทนำ\n\nภาษาไทยและ English text.\n\n| A | B |\n|---|---|\n| 1 | 2 |\n";
+        let doc = parse_markdown_with_frontmatter(markdown);
+        let config = DocumentConfig {
+            title: "Report".to_string(),
+            ..Default::default()
+        };
+        let pdf = render(&doc, Language::Thai, &config).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(pdf.windows(6).any(|w| w == b"/Type0"));
+    }
+}
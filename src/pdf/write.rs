@@ -0,0 +1,424 @@
+//! Serialization of laid-out pages into a PDF file
+//!
+//! Every face is embedded whole as a CID font with the Identity-H
+//! encoding, so glyph IDs from shaping are written as they are, and a
+//! ToUnicode map built from the shaped clusters keeps the text searchable
+//! and copyable. Content streams, fonts and pixel images are deflated.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pdf_writer::types::{
+    ActionType, AnnotationType, CidFontType, FontFlags, SystemInfo, UnicodeCmap,
+};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use super::font::{Face, FaceId, FontBook, Shaped};
+use super::image::{Image, ImageStore};
+use super::layout::{Geometry, Item, Page, Target};
+
+const SYSTEM_INFO: SystemInfo = SystemInfo {
+    registry: Str(b"Adobe"),
+    ordering: Str(b"Identity"),
+    supplement: 0,
+};
+
+/// Hands out object IDs in order
+struct Refs(i32);
+
+impl Refs {
+    fn next(&mut self) -> Ref {
+        self.0 += 1;
+        Ref::new(self.0)
+    }
+}
+
+/// Glyphs shown in a text object, split where the vertical offset changes
+enum Shown {
+    Glyph(u16),
+    /// Horizontal move in thousandths of the font size, leftwards
+    Adjust(f32),
+}
+
+/// Write `pages` as a PDF document
+pub(super) fn write(
+    pages: &[Page],
+    fonts: &FontBook,
+    images: &ImageStore,
+    geometry: Geometry,
+    title: &str,
+) -> Vec<u8> {
+    let mut pdf = Pdf::new();
+    let mut refs = Refs(0);
+    let catalog = refs.next();
+    let tree = refs.next();
+    let info = refs.next();
+    let page_refs: Vec<Ref> = pages.iter().map(|_| refs.next()).collect();
+
+    // Glyphs used from each face, with the text each stands for
+    let mut used: BTreeMap<FaceId, BTreeMap<u16, String>> = BTreeMap::new();
+    for page in pages {
+        for item in &page.items {
+            if let Item::Text { run, .. } = item {
+                let glyphs = used.entry(run.face).or_default();
+                for glyph in &run.glyphs {
+                    let text = glyphs.entry(glyph.id).or_default();
+                    if text.is_empty() {
+                        text.push_str(&glyph.text);
+                    }
+                }
+            }
+        }
+    }
+    let font_refs: HashMap<FaceId, Ref> = used.keys().map(|&face| (face, refs.next())).collect();
+    for (face, glyphs) in &used {
+        write_font(
+            &mut pdf,
+            &mut refs,
+            font_refs[face],
+            fonts.face(*face),
+            glyphs,
+        );
+    }
+    let image_refs: Vec<Ref> = images
+        .images
+        .iter()
+        .map(|image| write_image(&mut pdf, &mut refs, image))
+        .collect();
+
+    for (page, &page_ref) in pages.iter().zip(&page_refs) {
+        let content = refs.next();
+        pdf.stream(content, &page_content(page, fonts, geometry.height))
+            .filter(Filter::FlateDecode);
+
+        let annotation_refs: Vec<Ref> = page.links.iter().map(|_| refs.next()).collect();
+        let mut writer = pdf.page(page_ref);
+        writer
+            .parent(tree)
+            .media_box(Rect::new(0.0, 0.0, geometry.width, geometry.height))
+            .contents(content);
+        if !annotation_refs.is_empty() {
+            writer.annotations(annotation_refs.iter().copied());
+        }
+        let mut resources = writer.resources();
+        let mut font_dict = resources.fonts();
+        for (face, &font) in &font_refs {
+            font_dict.pair(Name(font_name(*face).as_bytes()), font);
+        }
+        font_dict.finish();
+        let mut x_objects = resources.x_objects();
+        for (i, &image) in image_refs.iter().enumerate() {
+            x_objects.pair(Name(image_name(i).as_bytes()), image);
+        }
+        x_objects.finish();
+        resources.finish();
+        writer.finish();
+
+        for (link, &annotation_ref) in page.links.iter().zip(&annotation_refs) {
+            let top = geometry.height - link.y;
+            let mut annotation = pdf.annotation(annotation_ref);
+            annotation
+                .subtype(AnnotationType::Link)
+                .rect(Rect::new(
+                    link.x,
+                    top - link.height,
+                    link.x + link.width,
+                    top,
+                ))
+                .border(0.0, 0.0, 0.0, None);
+            match &link.target {
+                Target::Uri(uri) => {
+                    annotation
+                        .action()
+                        .action_type(ActionType::Uri)
+                        .uri(Str(uri.as_bytes()));
+                }
+                Target::Page { page, y } => {
+                    annotation
+                        .action()
+                        .action_type(ActionType::GoTo)
+                        .destination()
+                        .page(page_refs[*page])
+                        .xyz(0.0, geometry.height - y, None);
+                }
+            }
+        }
+    }
+
+    pdf.catalog(catalog).pages(tree);
+    pdf.pages(tree)
+        .kids(page_refs.iter().copied())
+        .count(page_refs.len() as i32);
+    let mut document_info = pdf.document_info(info);
+    if !title.is_empty() {
+        document_info.title(TextStr(title));
+    }
+    document_info.producer(TextStr("md2docx"));
+    document_info.finish();
+    pdf.finish()
+}
+
+fn font_name(face: FaceId) -> String {
+    format!("F{}", face)
+}
+
+fn image_name(image: usize) -> String {
+    format!("Im{}", image)
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+/// The deflated content stream of a page
+fn page_content(page: &Page, fonts: &FontBook, page_height: f32) -> Vec<u8> {
+    let mut content = Content::new();
+    for item in &page.items {
+        match item {
+            Item::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => {
+                content.set_fill_rgb(color[0], color[1], color[2]);
+                content.rect(*x, page_height - y - height, *width, *height);
+                content.fill_nonzero();
+            }
+            Item::Image {
+                x,
+                y,
+                width,
+                height,
+                image,
+            } => {
+                content.save_state();
+                content.transform([*width, 0.0, 0.0, *height, *x, page_height - y - height]);
+                content.x_object(Name(image_name(*image).as_bytes()));
+                content.restore_state();
+            }
+            Item::Text { x, y, run, color } => {
+                content.set_fill_rgb(color[0], color[1], color[2]);
+                show(&mut content, run, fonts.face(run.face), *x, page_height - y);
+            }
+        }
+    }
+    deflate(&content.finish())
+}
+
+/// Draw a shaped run with its baseline starting at (`x`, `y`)
+///
+/// The PDF viewer advances by the font's own glyph widths, so the
+/// differences from the shaped advances (kerning, mark offsets) are written
+/// as adjustments, and vertical offsets as text rise.
+fn show(content: &mut Content, run: &Shaped, face: &Face, x: f32, y: f32) {
+    let ttf = ttf_parser::Face::parse(&face.data, 0).ok();
+    let advance = |id: u16| {
+        ttf.as_ref()
+            .and_then(|ttf| ttf.glyph_hor_advance(ttf_parser::GlyphId(id)))
+            .map_or(0.0, |advance| {
+                f32::from(advance) / face.units_per_em * run.size
+            })
+    };
+
+    let mut segments: Vec<(f32, Vec<Shown>)> = Vec::new();
+    // Where the viewer will draw the next glyph, and where shaping put it
+    let (mut cursor, mut pen) = (x, x);
+    for glyph in &run.glyphs {
+        if segments
+            .last()
+            .is_none_or(|(rise, _)| *rise != glyph.y_offset)
+        {
+            segments.push((glyph.y_offset, Vec::new()));
+        }
+        let shown = &mut segments.last_mut().expect("segment pushed above").1;
+        let target = pen + glyph.x_offset;
+        if (cursor - target).abs() > 0.001 {
+            shown.push(Shown::Adjust((cursor - target) * 1000.0 / run.size));
+        }
+        shown.push(Shown::Glyph(glyph.id));
+        cursor = target + advance(glyph.id);
+        pen += glyph.advance;
+    }
+
+    content.begin_text();
+    content.set_font(Name(font_name(run.face).as_bytes()), run.size);
+    content.set_text_matrix([1.0, 0.0, 0.0, 1.0, x, y]);
+    for (rise, shown) in segments {
+        content.set_rise(rise);
+        let mut positioned = content.show_positioned();
+        let mut items = positioned.items();
+        let mut glyphs = Vec::new();
+        for part in shown {
+            match part {
+                Shown::Glyph(id) => glyphs.extend_from_slice(&id.to_be_bytes()),
+                Shown::Adjust(amount) => {
+                    if !glyphs.is_empty() {
+                        items.show(Str(&glyphs));
+                        glyphs.clear();
+                    }
+                    items.adjust(amount);
+                }
+            }
+        }
+        if !glyphs.is_empty() {
+            items.show(Str(&glyphs));
+        }
+    }
+    content.end_text();
+}
+
+/// Embed a face as a Type 0 font, with widths and Unicode mappings for the
+/// glyphs the document uses
+fn write_font(
+    pdf: &mut Pdf,
+    refs: &mut Refs,
+    font: Ref,
+    face: &Face,
+    glyphs: &BTreeMap<u16, String>,
+) {
+    let cid = refs.next();
+    let descriptor = refs.next();
+    let file = refs.next();
+    let cmap = refs.next();
+    let ttf = ttf_parser::Face::parse(&face.data, 0).ok();
+    let scale = 1000.0 / face.units_per_em;
+    let name = Name(face.name.as_bytes());
+
+    pdf.type0_font(font)
+        .base_font(name)
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid)
+        .to_unicode(cmap);
+
+    let mut cid_font = pdf.cid_font(cid);
+    cid_font
+        .subtype(if face.cff {
+            CidFontType::Type0
+        } else {
+            CidFontType::Type2
+        })
+        .base_font(name)
+        .system_info(SYSTEM_INFO)
+        .font_descriptor(descriptor)
+        .default_width(0.0);
+    if !face.cff {
+        cid_font.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+    let mut widths = cid_font.widths();
+    for &id in glyphs.keys() {
+        let advance = ttf
+            .as_ref()
+            .and_then(|ttf| ttf.glyph_hor_advance(ttf_parser::GlyphId(id)))
+            .unwrap_or(0);
+        widths.consecutive(id, [f32::from(advance) * scale]);
+    }
+    widths.finish();
+    cid_font.finish();
+
+    let bbox = ttf
+        .as_ref()
+        .map_or(Rect::new(0.0, -200.0, 1000.0, 800.0), |ttf| {
+            let bbox = ttf.global_bounding_box();
+            Rect::new(
+                f32::from(bbox.x_min) * scale,
+                f32::from(bbox.y_min) * scale,
+                f32::from(bbox.x_max) * scale,
+                f32::from(bbox.y_max) * scale,
+            )
+        });
+    let cap_height = ttf
+        .as_ref()
+        .and_then(|ttf| ttf.capital_height())
+        .map_or(face.ascender * 1000.0, |height| f32::from(height) * scale);
+    let mut descriptor_writer = pdf.font_descriptor(descriptor);
+    descriptor_writer
+        .name(name)
+        .flags(FontFlags::SYMBOLIC)
+        .bbox(bbox)
+        .italic_angle(0.0)
+        .ascent(face.ascender * 1000.0)
+        .descent(face.descender * 1000.0)
+        .cap_height(cap_height)
+        .stem_v(80.0);
+    if face.cff {
+        descriptor_writer.font_file3(file);
+    } else {
+        descriptor_writer.font_file2(file);
+    }
+    descriptor_writer.finish();
+
+    let data = deflate(&face.data);
+    let mut stream = pdf.stream(file, &data);
+    stream.filter(Filter::FlateDecode);
+    if face.cff {
+        stream.pair(Name(b"Subtype"), Name(b"OpenType"));
+    } else {
+        stream.pair(Name(b"Length1"), face.data.len() as i32);
+    }
+    stream.finish();
+
+    let mut unicode = UnicodeCmap::new(Name(b"Custom"), SYSTEM_INFO);
+    for (&id, text) in glyphs {
+        if !text.is_empty() {
+            unicode.pair_with_multiple(id, text.chars());
+        }
+    }
+    pdf.cmap(cmap, &unicode.finish());
+}
+
+/// Embed an image, with its alpha channel as a soft mask
+fn write_image(pdf: &mut Pdf, refs: &mut Refs, image: &Image) -> Ref {
+    let id = refs.next();
+    match image {
+        Image::Jpeg {
+            data,
+            width,
+            height,
+            gray,
+        } => {
+            let mut xobject = pdf.image_xobject(id, data);
+            xobject.filter(Filter::DctDecode);
+            xobject
+                .width(*width as i32)
+                .height(*height as i32)
+                .bits_per_component(8);
+            if *gray {
+                xobject.color_space().device_gray();
+            } else {
+                xobject.color_space().device_rgb();
+            }
+        }
+        Image::Pixels {
+            rgb,
+            alpha,
+            width,
+            height,
+        } => {
+            let mask = alpha.as_ref().map(|alpha| {
+                let mask = refs.next();
+                let data = deflate(alpha);
+                let mut xobject = pdf.image_xobject(mask, &data);
+                xobject.filter(Filter::FlateDecode);
+                xobject
+                    .width(*width as i32)
+                    .height(*height as i32)
+                    .bits_per_component(8);
+                xobject.color_space().device_gray();
+                mask
+            });
+            let data = deflate(rgb);
+            let mut xobject = pdf.image_xobject(id, &data);
+            xobject.filter(Filter::FlateDecode);
+            xobject
+                .width(*width as i32)
+                .height(*height as i32)
+                .bits_per_component(8);
+            xobject.color_space().device_rgb();
+            if let Some(mask) = mask {
+                xobject.s_mask(mask);
+            }
+        }
+    }
+    id
+}
@@ -0,0 +1,259 @@
+//! Batch builds of several projects (`md2docx build --all`)
+//!
+//! Projects are given either as a list of directories or through a
+//! workspace file:
+//!
+//! ```toml
+//! # md2docx-workspace.toml
+//! projects = ["handbook", "guides/*"]
+//! ```
+//!
+//! Entries are relative to the workspace file and may use glob patterns.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use super::{BuildReport, ProjectBuilder};
+use crate::{Error, Result};
+
+/// Default workspace file name looked up by `md2docx build --all`
+pub const WORKSPACE_FILE: &str = "md2docx-workspace.toml";
+
+/// A list of project directories built together
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Workspace {
+    /// Project directories (or glob patterns), relative to the workspace file
+    pub projects: Vec<String>,
+}
+
+impl Workspace {
+    /// Load a workspace file and return the project directories it lists
+    ///
+    /// Glob patterns are expanded; only matching directories are kept.
+    pub fn load_projects(path: &Path) -> Result<Vec<PathBuf>> {
+        let content = std::fs::read_to_string(path)?;
+        let workspace: Workspace = toml::from_str(&content)
+            .map_err(|e| Error::Config(format!("Invalid {}: {}", path.display(), e)))?;
+
+        let root = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut dirs = Vec::new();
+        for entry in &workspace.projects {
+            let joined = root.join(entry);
+            if entry.contains(['*', '?', '[']) {
+                let pattern = joined.to_string_lossy();
+                let matches = glob::glob(&pattern).map_err(|e| {
+                    Error::Config(format!("Invalid project pattern '{}': {}", entry, e))
+                })?;
+                dirs.extend(matches.flatten().filter(|p| p.is_dir()));
+            } else {
+                dirs.push(joined);
+            }
+        }
+        Ok(dirs)
+    }
+}
+
+/// Options shared by all projects in a batch
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Force the table of contents on (like `--toc`)
+    pub toc: bool,
+    /// Number of projects built at the same time; 0 uses all cores
+    pub jobs: usize,
+    /// Build profile (`[profile.<name>]`) applied to every project
+    pub profile: Option<String>,
+    /// `[document]` overrides (like `--var key=value`)
+    pub vars: Vec<(String, String)>,
+    /// Only include chapters with one of these tags (like `--only-tags`)
+    pub only_tags: Vec<String>,
+    /// Embed images at full size (like `--no-optimize`)
+    pub no_optimize: bool,
+    /// Byte-identical output for identical input (like `--reproducible`)
+    pub reproducible: bool,
+    /// Only use cached remote includes (like `--offline`)
+    pub offline: bool,
+    /// Directory for all outputs (like `--output-dir`)
+    pub output_dir: Option<PathBuf>,
+}
+
+/// Outcome of building one project output in a batch
+#[derive(Debug)]
+pub struct BatchResult {
+    /// Project directory
+    pub dir: PathBuf,
+    /// Build report on success, error message on failure
+    pub outcome: std::result::Result<BuildReport, String>,
+    /// Wall-clock build time
+    pub duration: Duration,
+}
+
+/// Build several projects, optionally in parallel
+///
+/// Relative `output.file` paths are resolved against each project
+/// directory rather than the working directory. A failing project does not
+/// stop the others. Results are returned in the same order as `dirs`, with
+/// one result per `[[output]]` target of a project.
+pub fn build_all(dirs: &[PathBuf], options: &BatchOptions) -> Result<Vec<BatchResult>> {
+    let jobs = if options.jobs == 0 {
+        rayon::current_num_threads()
+    } else {
+        options.jobs
+    };
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|e| Error::Config(format!("Failed to start build threads: {}", e)))?;
+
+    let results: Vec<Vec<BatchResult>> =
+        pool.install(|| dirs.par_iter().map(|dir| build_one(dir, options)).collect());
+    Ok(results.into_iter().flatten().collect())
+}
+
+fn build_one(dir: &Path, options: &BatchOptions) -> Vec<BatchResult> {
+    let start = Instant::now();
+    let builders = match ProjectBuilder::targets_from_directory(dir, options.profile.as_deref()) {
+        Ok(builders) => builders,
+        Err(e) => {
+            return vec![BatchResult {
+                dir: dir.to_path_buf(),
+                outcome: Err(e.to_string()),
+                duration: start.elapsed(),
+            }]
+        }
+    };
+
+    builders
+        .into_iter()
+        .map(|mut builder| {
+            let start = Instant::now();
+            if options.toc {
+                builder = builder.with_toc(true);
+            }
+            for (key, value) in &options.vars {
+                builder = builder.with_var(key, value);
+            }
+            if !options.only_tags.is_empty() {
+                builder = builder.with_only_tags(&options.only_tags);
+            }
+            if options.no_optimize {
+                builder = builder.without_image_limits();
+            }
+            if options.reproducible {
+                builder = builder.reproducible();
+            }
+            if options.offline {
+                builder = builder.offline();
+            }
+            // Relative output paths would otherwise all land in the current
+            // directory; keep each project's output inside its own folder.
+            let output_dir = options.output_dir.as_deref().unwrap_or(dir);
+            builder = builder.with_output_dir(output_dir.to_path_buf());
+            BatchResult {
+                dir: dir.to_path_buf(),
+                outcome: builder
+                    .build_to_file_with_report()
+                    .map_err(|e| e.to_string()),
+                duration: start.elapsed(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_workspace_expands_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("books/a")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("books/b")).unwrap();
+        std::fs::write(temp_dir.path().join("books/notes.txt"), "").unwrap();
+        let ws = temp_dir.path().join(WORKSPACE_FILE);
+        std::fs::write(&ws, "projects = [\"main\", \"books/*\"]\n").unwrap();
+
+        let dirs = Workspace::load_projects(&ws).unwrap();
+        let names: Vec<_> = dirs
+            .iter()
+            .map(|d| d.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["main", "a", "b"]);
+    }
+
+    #[test]
+    fn test_build_all_reports_failures() {
+        let temp_dir = TempDir::new().unwrap();
+        let good = temp_dir.path().join("good");
+        let empty = temp_dir.path().join("empty");
+        std::fs::create_dir_all(&good).unwrap();
+        std::fs::create_dir_all(&empty).unwrap();
+        std::fs::write(good.join("ch01_intro.md"), "# Intro\n\nHello.").unwrap();
+        std::fs::write(good.join("md2docx.toml"), "[output]\nfile = \"out.docx\"\n").unwrap();
+
+        let results = build_all(
+            &[good.clone(), empty.clone()],
+            &BatchOptions {
+                jobs: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].dir, good);
+        assert!(results[0].outcome.is_ok(), "{:?}", results[0].outcome);
+        assert!(good.join("out.docx").exists());
+        let report = results[0].outcome.as_ref().unwrap();
+        let stages: Vec<_> = report.timings.iter().map(|t| t.stage.as_str()).collect();
+        assert_eq!(
+            stages,
+            ["load", "combine", "parse", "build", "package", "write"]
+        );
+        assert!(results[1].outcome.is_err());
+    }
+
+    #[test]
+    fn test_build_all_resolves_images_per_project() {
+        // Projects built in parallel must not rely on the working directory
+        // to find their relative image paths
+        let temp_dir = TempDir::new().unwrap();
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 3]);
+        let dirs: Vec<_> = ["a", "b"]
+            .iter()
+            .map(|name| {
+                let dir = temp_dir.path().join(name);
+                std::fs::create_dir_all(dir.join("img")).unwrap();
+                std::fs::write(dir.join("img/fig.png"), &png).unwrap();
+                std::fs::write(
+                    dir.join("ch01_intro.md"),
+                    "# Intro\n\n![Fig](img/fig.png)\n",
+                )
+                .unwrap();
+                std::fs::write(dir.join("md2docx.toml"), "[output]\nfile = \"out.docx\"\n")
+                    .unwrap();
+                dir
+            })
+            .collect();
+
+        let results = build_all(
+            &dirs,
+            &BatchOptions {
+                jobs: 2,
+                no_optimize: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        for result in &results {
+            let report = result.outcome.as_ref().unwrap();
+            assert_eq!(report.images, 1, "{}", result.dir.display());
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Revision history appendix from the git log of a project's files, and the
+//! `{!revisions}` table of the versions listed in `[[revisions]]`
+
+use std::path::Path;
+
+use super::metadata::expand_directive_lines;
+use crate::config::{ChangelogSection, RevisionEntry};
+use crate::i18n::{format_date, Label, Strings};
+#[cfg(feature = "git")]
+use crate::Error;
+use crate::{Language, Result};
+
+/// One commit listed in the revision history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    /// Commit date as `YYYY-MM-DD`, in the committer's time zone
+    pub date: String,
+    pub author: String,
+    /// First line of the commit message
+    pub summary: String,
+    /// Tags pointing at the commit ("v1.2")
+    pub tags: Vec<String>,
+}
+
+/// Conventional commit type of a summary: "feat" for "feat(api)!: add export"
+pub fn commit_type(summary: &str) -> Option<&str> {
+    let (head, _) = summary.split_once(':')?;
+    let head = head.strip_suffix('!').unwrap_or(head);
+    let kind = match head.split_once('(') {
+        Some((kind, scope)) if scope.ends_with(')') => kind,
+        Some(_) => return None,
+        None => head,
+    };
+    (!kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .then_some(kind)
+}
+
+/// Commits from `history` the `[changelog]` section selects, newest first
+pub fn select<'a>(history: &'a [Revision], config: &ChangelogSection) -> Vec<&'a Revision> {
+    history
+        .iter()
+        .filter(|revision| {
+            config.types.is_empty()
+                || commit_type(&revision.summary)
+                    .is_some_and(|kind| config.types.iter().any(|t| t.eq_ignore_ascii_case(kind)))
+        })
+        .take(config.max_entries.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Markdown for the revision history appendix: a heading and a table
+pub fn revision_table(
+    revisions: &[&Revision],
+    title: Option<&str>,
+    strings: &Strings,
+    date_format: Option<&str>,
+) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut markdown = format!(
+        "# {}\n\n| {} | {} | {} | {} |\n|---|---|---|---|\n",
+        title.unwrap_or_else(|| strings.get(Label::RevisionHistory)),
+        strings.get(Label::RevisionDate),
+        strings.get(Label::RevisionVersion),
+        strings.get(Label::RevisionAuthor),
+        strings.get(Label::RevisionDescription),
+    );
+    for revision in revisions {
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            format_date(&revision.date, strings.lang, date_format),
+            cell(&revision.tags.join(", ")),
+            cell(&revision.author),
+            cell(&revision.summary),
+        ));
+    }
+    markdown
+}
+
+/// Markdown for the table of chapters and who last changed them
+pub fn chapter_table(
+    chapters: &[(String, Revision)],
+    strings: &Strings,
+    date_format: Option<&str>,
+) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut markdown = format!(
+        "| {} | {} | {} |\n|---|---|---|\n",
+        strings.get(Label::RevisionChapter),
+        strings.get(Label::RevisionAuthor),
+        strings.get(Label::RevisionDate),
+    );
+    for (title, revision) in chapters {
+        markdown.push_str(&format!(
+            "| {} | {} | {} |\n",
+            cell(title),
+            cell(&revision.author),
+            format_date(&revision.date, strings.lang, date_format),
+        ));
+    }
+    markdown
+}
+
+/// Line replaced by the `[[revisions]]` table
+pub const REVISIONS_DIRECTIVE: &str = "{!revisions}";
+
+/// Markdown table of the `[[revisions]]` entries, in the order written
+pub fn revisions_table(
+    entries: &[RevisionEntry],
+    strings: &Strings,
+    date_format: Option<&str>,
+) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut markdown = format!(
+        "| {} | {} | {} | {} |\n|---|---|---|---|\n",
+        strings.get(Label::RevisionVersion),
+        strings.get(Label::RevisionDate),
+        strings.get(Label::RevisionAuthor),
+        strings.get(Label::RevisionDescription),
+    );
+    for entry in entries {
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            cell(&entry.version),
+            cell(&format_date(&entry.date, strings.lang, date_format)),
+            cell(&entry.author),
+            cell(&entry.description),
+        ));
+    }
+    markdown
+}
+
+/// Replace `{!revisions}` lines outside code blocks with `table`
+pub fn expand_revisions(markdown: &str, table: &str) -> String {
+    expand_directive_lines(markdown, REVISIONS_DIRECTIVE, table)
+}
+
+/// `<!-- {edited:...} -->` directive carrying a chapter's last author and
+/// edit date to the builder
+pub fn edited_directive(revision: &Revision, lang: Language, date_format: Option<&str>) -> String {
+    let clean = |text: &str| text.replace(['|', '}'], " ");
+    format!(
+        "<!-- {{edited: {} | {}}} -->",
+        clean(&revision.author),
+        clean(&format_date(&revision.date, lang, date_format)),
+    )
+}
+
+/// Commits that changed any of `files`, newest first
+///
+/// Merge commits are left out; the commits they bring in are listed
+/// themselves.
+pub fn file_history(dir: &Path, files: &[&Path]) -> Result<Vec<Revision>> {
+    Ok(changes(dir, files)?
+        .into_iter()
+        .map(|(revision, _)| revision)
+        .collect())
+}
+
+/// The latest commit that changed each of `files`
+///
+/// `None` for files no commit has touched yet, such as new chapters.
+pub fn last_edits(dir: &Path, files: &[&Path]) -> Result<Vec<Option<Revision>>> {
+    let changes = changes(dir, files)?;
+    Ok((0..files.len())
+        .map(|i| {
+            changes
+                .iter()
+                .find(|(_, changed)| changed[i])
+                .map(|(revision, _)| revision.clone())
+        })
+        .collect())
+}
+
+/// Non-merge commits that changed any of `files`, newest first, each with
+/// which of the files it changed
+#[cfg(feature = "git")]
+fn changes(dir: &Path, files: &[&Path]) -> Result<Vec<(Revision, Vec<bool>)>> {
+    use std::collections::HashMap;
+
+    let git_error = |e: &dyn std::fmt::Display| Error::Git(e.to_string());
+    let repo = gix::discover(dir).map_err(|e| git_error(&e))?;
+    let root = repo
+        .work_dir()
+        .ok_or_else(|| Error::Git("bare repositories have no working tree".to_string()))?
+        .canonicalize()?;
+    let paths: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let file = file.canonicalize().ok()?;
+            file.strip_prefix(&root).ok().map(Path::to_path_buf)
+        })
+        .collect();
+
+    // Tags by the commit they point at
+    let mut tags: HashMap<gix::ObjectId, Vec<String>> = HashMap::new();
+    let references = repo.references().map_err(|e| git_error(&e))?;
+    for mut reference in references.tags().map_err(|e| git_error(&e))?.flatten() {
+        let name = reference.name().shorten().to_string();
+        if let Ok(id) = reference.peel_to_id_in_place() {
+            tags.entry(id.detach()).or_default().push(name);
+        }
+    }
+
+    let entry_id = |tree: &gix::Tree<'_>, path: &Path| {
+        tree.lookup_entry_by_path(path)
+            .ok()
+            .flatten()
+            .map(|entry| entry.object_id())
+    };
+
+    let mut history = Vec::new();
+    let head = repo.head_id().map_err(|e| git_error(&e))?;
+    for info in head.ancestors().all().map_err(|e| git_error(&e))? {
+        let commit = info
+            .map_err(|e| git_error(&e))?
+            .object()
+            .map_err(|e| git_error(&e))?;
+        let parents: Vec<_> = commit.parent_ids().collect();
+        if parents.len() > 1 {
+            continue;
+        }
+        let tree = commit.tree().map_err(|e| git_error(&e))?;
+        let parent_tree = match parents.first() {
+            Some(parent) => Some(
+                parent
+                    .object()
+                    .map_err(|e| git_error(&e))?
+                    .peel_to_tree()
+                    .map_err(|e| git_error(&e))?,
+            ),
+            None => None,
+        };
+        let changed: Vec<bool> = paths
+            .iter()
+            .map(|path| {
+                path.as_ref().is_some_and(|path| {
+                    entry_id(&tree, path) != parent_tree.as_ref().and_then(|t| entry_id(t, path))
+                })
+            })
+            .collect();
+        if !changed.contains(&true) {
+            continue;
+        }
+
+        let time = commit.time().map_err(|e| git_error(&e))?;
+        let author = commit.author().map_err(|e| git_error(&e))?.name.to_string();
+        let summary = commit
+            .message()
+            .map_err(|e| git_error(&e))?
+            .summary()
+            .to_string();
+        let revision = Revision {
+            date: super::git::commit_date(time),
+            author,
+            summary,
+            tags: tags.remove(&commit.id).unwrap_or_default(),
+        };
+        history.push((time.seconds, revision, changed));
+    }
+    history.sort_by_key(|entry| std::cmp::Reverse(entry.0));
+    Ok(history
+        .into_iter()
+        .map(|(_, revision, changed)| (revision, changed))
+        .collect())
+}
+
+/// Without the `git` feature there is no history to read
+#[cfg(not(feature = "git"))]
+fn changes(_dir: &Path, _files: &[&Path]) -> Result<Vec<(Revision, Vec<bool>)>> {
+    Err(crate::Error::Config(
+        "[changelog] needs md2docx built with the 'git' feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(date: &str, summary: &str, tags: &[&str]) -> Revision {
+        Revision {
+            date: date.to_string(),
+            author: "Somchai".to_string(),
+            summary: summary.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_commit_type() {
+        assert_eq!(commit_type("feat: add export"), Some("feat"));
+        assert_eq!(commit_type("fix(setup)!: drop old flag"), Some("fix"));
+        assert_eq!(commit_type("docs!: rewrite intro"), Some("docs"));
+        assert_eq!(commit_type("Update chapter 2"), None);
+        assert_eq!(commit_type("Note: see chapter 2"), Some("Note"));
+        assert_eq!(commit_type("fix(setup: typo"), None);
+        assert_eq!(commit_type("Fix the build: again"), None);
+    }
+
+    #[test]
+    fn test_select_filters_types() {
+        let history = vec![
+            revision("2025-03-02", "docs: explain setup", &["v1.1"]),
+            revision("2025-03-01", "chore: bump version", &[]),
+            revision("2025-02-20", "fix(ch02): typo", &[]),
+            revision("2025-02-01", "Initial draft", &["v1.0"]),
+        ];
+
+        assert_eq!(select(&history, &ChangelogSection::default()).len(), 4);
+
+        let config = ChangelogSection {
+            types: vec!["docs".to_string(), "FIX".to_string()],
+            ..Default::default()
+        };
+        let selected = select(&history, &config);
+        assert_eq!(selected, [&history[0], &history[2]]);
+
+        let config = ChangelogSection {
+            max_entries: Some(1),
+            ..config
+        };
+        assert_eq!(select(&history, &config), [&history[0]]);
+    }
+
+    #[test]
+    fn test_revision_table() {
+        let history = [revision("2025-03-02", "docs: a | b", &["v1.1", "stable"])];
+        let selected: Vec<_> = history.iter().collect();
+
+        let markdown = revision_table(&selected, None, &Strings::new(Language::English), None);
+        assert!(markdown.starts_with("# Revision History\n"));
+        assert!(markdown.contains("| Date | Version | Author | Description |"));
+        assert!(markdown.contains("| v1.1, stable | Somchai | docs: a \\| b |"));
+
+        let thai = revision_table(&selected, Some("บันทึก"), &Strings::new(Language::Thai), None);
+        assert!(thai.starts_with("# บันทึก\n"));
+        assert!(thai.contains("| วันที่ | เวอร์ชัน |"));
+    }
+
+    #[test]
+    fn test_revisions_table() {
+        let entries = [
+            RevisionEntry {
+                version: "1.0".to_string(),
+                date: "2025-01-15".to_string(),
+                author: "Somchai".to_string(),
+                description: "First issue".to_string(),
+            },
+            RevisionEntry {
+                version: "1.1".to_string(),
+                date: "draft".to_string(),
+                description: "Budget | schedule".to_string(),
+                ..Default::default()
+            },
+        ];
+        let markdown = revisions_table(&entries, &Strings::new(Language::English), None);
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0], "| Version | Date | Author | Description |");
+        assert_eq!(lines[2], "| 1.0 | 2025-01-15 | Somchai | First issue |");
+        assert_eq!(lines[3], "| 1.1 | draft |  | Budget \\| schedule |");
+
+        let thai = revisions_table(&entries, &Strings::new(Language::Thai), None);
+        assert!(thai.starts_with("| เวอร์ชัน | วันที่ |"));
+        assert!(thai.contains("| 1.0 | 15 มกราคม พ.ศ. 2568 |"));
+
+        let expanded = expand_revisions("# History\n\n{!revisions}\n", &markdown);
+        assert!(expanded.contains("\n| Version | Date |"));
+        assert!(!expanded.contains(REVISIONS_DIRECTIVE));
+    }
+
+    #[test]
+    fn test_chapter_edits() {
+        let edit = Revision {
+            author: "Jane | Doe".to_string(),
+            ..revision("2025-01-28", "docs: setup", &[])
+        };
+
+        let markdown = chapter_table(
+            &[("Setup".to_string(), edit.clone())],
+            &Strings::new(Language::English),
+            Some("%Y-%m-%d"),
+        );
+        assert!(markdown.starts_with("| Chapter | Author | Date |\n"));
+        assert!(markdown.contains("| Setup | Jane \\| Doe | 2025-01-28 |"));
+
+        assert_eq!(
+            edited_directive(&edit, Language::Thai, None),
+            "<!-- {edited: Jane   Doe | 28 มกราคม พ.ศ. 2568} -->"
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Project lint checks (`md2docx check`)
+//!
+//! Scans the project's markdown sources without generating any output and
+//! reports problems that would otherwise only show up in the built document:
+//! broken includes, missing images, unresolved cross-references, links to
+//! other files that don't resolve, duplicate anchors, missing footnote
+//! definitions and placeholders without values.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::links::LinkIndex;
+use crate::config::ProjectConfig;
+use crate::diagnostics::SourceLocation;
+use crate::discovery::DiscoveredProject;
+use crate::parser::{is_remote_include, ASSETS_PREFIX};
+use crate::{Error, Result};
+
+static INCLUDE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\{!include:([^}]+)\}$").expect("Invalid regex"));
+static CODE_INCLUDE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\{!code:([^:}]+)(?::\d+-\d+)?(?::[a-zA-Z0-9]+)?\}$").expect("Invalid regex")
+});
+pub(super) static IMAGE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"!\[[^\]]*\]\(\s*([^)\s]+)").expect("Invalid regex"));
+static ANCHOR_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{#([a-zA-Z0-9_:-]+)\}").expect("Invalid regex"));
+static LABEL_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\\label\{([^}]+)\}").expect("Invalid regex"));
+static REF_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{ref:([a-zA-Z0-9_:-]+)\}").expect("Invalid regex"));
+static FOOTNOTE_DEF_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s{0,3}\[\^([^\]]+)\]:").expect("Invalid regex"));
+static FOOTNOTE_REF_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\^([^\]]+)\]").expect("Invalid regex"));
+static PLACEHOLDER_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([a-zA-Z0-9_:.%-]+)\s*\}\}").expect("Invalid regex"));
+pub(super) static INLINE_CODE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"`[^`]*`").expect("Invalid regex"));
+
+/// Kind of problem found by [`check_project`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckCategory {
+    /// `{!include:...}`, `{!code:...}` or `{!snippet:...}` points to a
+    /// missing file or snippet
+    BrokenInclude,
+    /// Image source does not exist on disk
+    MissingImage,
+    /// `{ref:...}` does not match any anchor in the project
+    UnresolvedRef,
+    /// Link to another markdown file or heading that isn't in the project
+    BrokenLink,
+    /// The same anchor id is defined more than once
+    DuplicateAnchor,
+    /// Footnote reference without a matching definition
+    MissingFootnote,
+    /// `{{var}}` placeholder with no configured value
+    UndefinedPlaceholder,
+}
+
+impl CheckCategory {
+    /// Short machine-friendly name of the category
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckCategory::BrokenInclude => "broken-include",
+            CheckCategory::MissingImage => "missing-image",
+            CheckCategory::UnresolvedRef => "unresolved-ref",
+            CheckCategory::BrokenLink => "broken-link",
+            CheckCategory::DuplicateAnchor => "duplicate-anchor",
+            CheckCategory::MissingFootnote => "missing-footnote",
+            CheckCategory::UndefinedPlaceholder => "undefined-placeholder",
+        }
+    }
+
+    /// Whether `[output] strict` fails the build on this kind of problem
+    pub fn is_strict(&self) -> bool {
+        matches!(
+            self,
+            CheckCategory::BrokenInclude
+                | CheckCategory::MissingImage
+                | CheckCategory::UnresolvedRef
+                | CheckCategory::BrokenLink
+        )
+    }
+}
+
+impl fmt::Display for CheckCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single problem found in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckIssue {
+    /// File containing the problem
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    /// Problem category
+    pub category: CheckCategory,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl fmt::Display for CheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: [{}] {}",
+            self.path.display(),
+            self.line,
+            self.category,
+            self.message
+        )
+    }
+}
+
+impl CheckIssue {
+    /// The issue as an error pointing at its line
+    pub fn to_error(&self) -> Error {
+        let error = match self.category {
+            CheckCategory::BrokenInclude => Error::Include(self.message.clone()),
+            CheckCategory::MissingImage => Error::Image(self.message.clone()),
+            _ => Error::Reference(self.message.clone()),
+        };
+        error.at(SourceLocation::new(&self.path, self.line, 0))
+    }
+}
+
+/// Location of a definition or reference
+#[derive(Debug, Clone)]
+struct Location {
+    path: PathBuf,
+    line: usize,
+}
+
+/// Project-wide state collected while scanning files
+#[derive(Default)]
+struct Scan {
+    anchors: HashMap<String, Location>,
+    refs: Vec<(String, Location)>,
+    footnote_defs: HashSet<String>,
+    footnote_refs: Vec<(String, Location)>,
+}
+
+/// Lint every markdown file of a project
+///
+/// Files are discovered with the same rules as `md2docx build`. Returns all
+/// issues sorted by file and line; an empty list means the project is clean.
+pub fn check_project(base_dir: &Path, config: &ProjectConfig) -> Result<Vec<CheckIssue>> {
+    let project = DiscoveredProject::discover_with_config(base_dir, config)?;
+    check_discovered(base_dir, config, &project)
+}
+
+/// Lint the files of an already discovered project
+pub(super) fn check_discovered(
+    base_dir: &Path,
+    config: &ProjectConfig,
+    project: &DiscoveredProject,
+) -> Result<Vec<CheckIssue>> {
+    let code_root = config
+        .code
+        .source_root
+        .as_ref()
+        .map(|root| base_dir.join(root));
+    let placeholders = known_placeholders(config);
+    let assets_dir = base_dir.join(&config.assets.dir);
+    let snippets = super::snippets::load_snippets(base_dir, &config.snippets)?;
+
+    let mut issues = Vec::new();
+    let mut scan = Scan::default();
+
+    let mut files = Vec::new();
+    for path in project.all_files() {
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        files.push((path, std::fs::read_to_string(path)?));
+    }
+    let sources: Vec<(&Path, &str)> = files
+        .iter()
+        .map(|(path, content)| (path.as_path(), content.as_str()))
+        .collect();
+    let links = LinkIndex::new(&sources);
+
+    for (index, (path, content)) in sources.iter().enumerate() {
+        check_file(
+            path,
+            content,
+            code_root.as_deref(),
+            &assets_dir,
+            &placeholders,
+            &mut scan,
+            &mut issues,
+        );
+        for (line, reason) in links.broken_links(index, content) {
+            issues.push(CheckIssue {
+                path: path.to_path_buf(),
+                line,
+                category: CheckCategory::BrokenLink,
+                message: format!("Broken link: {}", reason),
+            });
+        }
+        for (line, name) in super::snippets::unknown_snippets(content, &snippets) {
+            issues.push(CheckIssue {
+                path: path.to_path_buf(),
+                line,
+                category: CheckCategory::BrokenInclude,
+                message: format!("Snippet '{}' not found", name),
+            });
+        }
+    }
+
+    for (target, loc) in &scan.refs {
+        if !ref_resolves(target, &scan.anchors) {
+            issues.push(CheckIssue {
+                path: loc.path.clone(),
+                line: loc.line,
+                category: CheckCategory::UnresolvedRef,
+                message: format!("Cross-reference target '{}' not found", target),
+            });
+        }
+    }
+
+    for (name, loc) in &scan.footnote_refs {
+        if !scan.footnote_defs.contains(name) {
+            issues.push(CheckIssue {
+                path: loc.path.clone(),
+                line: loc.line,
+                category: CheckCategory::MissingFootnote,
+                message: format!("Footnote '[^{}]' has no definition", name),
+            });
+        }
+    }
+
+    issues.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    Ok(issues)
+}
+
+/// Scan one file, recording per-line issues and project-wide definitions
+fn check_file(
+    path: &Path,
+    content: &str,
+    code_root: Option<&Path>,
+    assets_dir: &Path,
+    placeholders: &HashSet<String>,
+    scan: &mut Scan,
+    issues: &mut Vec<CheckIssue>,
+) {
+    let file_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let body_start = frontmatter_end(content);
+    let mut fence: Option<(char, usize)> = None;
+
+    for (idx, raw_line) in content.lines().enumerate().skip(body_start) {
+        let line_no = idx + 1;
+        let trimmed = raw_line.trim();
+
+        // Skip fenced code blocks
+        if track_fence(&mut fence, trimmed) {
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        let loc = || Location {
+            path: path.to_path_buf(),
+            line: line_no,
+        };
+        let mut issue = |category: CheckCategory, message: String| {
+            issues.push(CheckIssue {
+                path: path.to_path_buf(),
+                line: line_no,
+                category,
+                message,
+            });
+        };
+
+        if let Some(caps) = INCLUDE_PATTERN.captures(trimmed) {
+            let target = caps[1].trim();
+            if !is_remote_include(target) && !file_dir.join(target).exists() {
+                issue(
+                    CheckCategory::BrokenInclude,
+                    format!("Included file '{}' not found", target),
+                );
+            }
+            continue;
+        }
+        if let Some(caps) = CODE_INCLUDE_PATTERN.captures(trimmed) {
+            let target = caps[1].trim();
+            let resolved = code_root.unwrap_or(file_dir).join(target);
+            if !resolved.exists() {
+                issue(
+                    CheckCategory::BrokenInclude,
+                    format!("Code file '{}' not found", target),
+                );
+            }
+            continue;
+        }
+
+        let line = INLINE_CODE_PATTERN.replace_all(raw_line, "");
+
+        for caps in IMAGE_PATTERN.captures_iter(&line) {
+            let src = &caps[1];
+            if is_remote(src) {
+                continue;
+            }
+            if !image_path(file_dir, src, assets_dir).exists() {
+                issue(
+                    CheckCategory::MissingImage,
+                    format!("Image '{}' not found", src),
+                );
+            }
+        }
+
+        let mut ids: Vec<String> = ANCHOR_PATTERN
+            .captures_iter(&line)
+            .map(|c| c[1].to_string())
+            .collect();
+        ids.extend(
+            LABEL_PATTERN
+                .captures_iter(&line)
+                .map(|c| c[1].trim().to_string()),
+        );
+        for id in ids {
+            if let Some(first) = scan.anchors.get(&id) {
+                issue(
+                    CheckCategory::DuplicateAnchor,
+                    format!(
+                        "Anchor '{}' already defined at {}:{}",
+                        id,
+                        first.path.display(),
+                        first.line
+                    ),
+                );
+            } else {
+                scan.anchors.insert(id, loc());
+            }
+        }
+
+        for caps in REF_PATTERN.captures_iter(&line) {
+            scan.refs.push((caps[1].to_string(), loc()));
+        }
+
+        if let Some(caps) = FOOTNOTE_DEF_PATTERN.captures(&line) {
+            scan.footnote_defs.insert(caps[1].to_string());
+        }
+        let def_end = FOOTNOTE_DEF_PATTERN
+            .find(&line)
+            .map(|m| m.end())
+            .unwrap_or(0);
+        for caps in FOOTNOTE_REF_PATTERN.captures_iter(&line[def_end..]) {
+            scan.footnote_refs.push((caps[1].to_string(), loc()));
+        }
+
+        for caps in PLACEHOLDER_PATTERN.captures_iter(&line) {
+            let key = &caps[1];
+            if !key.starts_with("currenttime") && !placeholders.contains(key) {
+                issue(
+                    CheckCategory::UndefinedPlaceholder,
+                    format!("Placeholder '{{{{{}}}}}' has no value", key),
+                );
+            }
+        }
+    }
+}
+
+/// Placeholder names that have a non-empty value in the config
+fn known_placeholders(config: &ProjectConfig) -> HashSet<String> {
+    let doc = &config.document;
+    let mut keys: HashSet<String> = [
+        ("title", doc.title.as_str()),
+        ("subtitle", doc.subtitle.as_str()),
+        ("author", doc.author.as_str()),
+        ("version", doc.version.as_str()),
+    ]
+    .into_iter()
+    .filter(|(_, value)| !value.is_empty())
+    .map(|(key, _)| key.to_string())
+    .collect();
+
+    keys.insert("date".to_string());
+    keys.insert("inside".to_string());
+    keys.extend(
+        doc.extra_as_strings()
+            .into_iter()
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(k, _)| k),
+    );
+    keys
+}
+
+/// Whether a `{ref:...}` target matches a defined anchor
+///
+/// Targets and anchors are compared both as written (`fig:arch`) and with
+/// any type prefix stripped (`arch`), since the parser drops prefixes such
+/// as `eq:` from labels and ref targets.
+fn ref_resolves(target: &str, anchors: &HashMap<String, Location>) -> bool {
+    if anchors.contains_key(target) {
+        return true;
+    }
+    let bare = strip_ref_prefix(target);
+    anchors.keys().any(|id| strip_ref_prefix(id) == bare)
+}
+
+fn strip_ref_prefix(id: &str) -> &str {
+    id.split_once(':').map(|(_, bare)| bare).unwrap_or(id)
+}
+
+/// File an image source points to: `@assets/` paths are in the shared assets
+/// directory, others relative to the referencing file
+pub(super) fn image_path(file_dir: &Path, src: &str, assets_dir: &Path) -> PathBuf {
+    match src.strip_prefix(ASSETS_PREFIX) {
+        Some(asset) => assets_dir.join(asset),
+        None => file_dir.join(src),
+    }
+}
+
+/// Index of the first line after the frontmatter block (0 if none)
+pub(super) fn frontmatter_end(content: &str) -> usize {
+    if !content.starts_with("---") {
+        return 0;
+    }
+    content
+        .lines()
+        .enumerate()
+        .skip(1)
+        .find(|(_, line)| line.trim() == "---")
+        .map(|(i, _)| i + 1)
+        .unwrap_or(0)
+}
+
+/// Return the fence character and length if the line opens or closes a code fence
+fn fence_marker(trimmed: &str) -> Option<(char, usize)> {
+    let first = trimmed.chars().next()?;
+    if first != '`' && first != '~' {
+        return None;
+    }
+    let count = trimmed.chars().take_while(|&c| c == first).count();
+    (count >= 3).then_some((first, count))
+}
+
+/// Track fenced code blocks; whether `trimmed` is a fence line
+///
+/// A block is closed only by a fence of the same character, at least as
+/// long as the opening one and without an info string, as in CommonMark.
+pub(super) fn track_fence(fence: &mut Option<(char, usize)>, trimmed: &str) -> bool {
+    let Some((c, n)) = fence_marker(trimmed) else {
+        return false;
+    };
+    match *fence {
+        Some((open, len)) if c == open && n >= len && trimmed[n..].trim().is_empty() => {
+            *fence = None
+        }
+        None => *fence = Some((c, n)),
+        _ => {}
+    }
+    true
+}
+
+pub(super) fn is_remote(src: &str) -> bool {
+    src.starts_with("http://") || src.starts_with("https://") || src.starts_with("data:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn categories(issues: &[CheckIssue]) -> Vec<CheckCategory> {
+        issues.iter().map(|i| i.category).collect()
+    }
+
+    #[test]
+    fn test_clean_project() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("logo.png"), b"png").unwrap();
+        std::fs::create_dir(temp_dir.path().join("assets")).unwrap();
+        std::fs::write(temp_dir.path().join("assets/seal.png"), b"png").unwrap();
+        std::fs::write(
+            temp_dir.path().join("ch01_intro.md"),
+            "---\ntitle: Intro\n---\n\n# Intro {#intro}\n\n![Logo](logo.png) ![Seal](@assets/seal.png)\n\nSee {ref:intro} and [the intro](ch01_intro.md#intro).[^1]\n\n[^1]: Note.\n",
+        )
+        .unwrap();
+
+        let issues = check_project(temp_dir.path(), &ProjectConfig::default()).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_reports_problems_with_locations() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("ch01_intro.md"),
+            "# Intro {#intro}\n\n![Missing](missing.png)\n\n{!include:nope.md}\n\nSee {ref:fig:none}.[^x]\n\n{{author}}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("ch02_more.md"),
+            "# More {#intro}\n\n{!snippet:nope}\n",
+        )
+        .unwrap();
+
+        let issues = check_project(temp_dir.path(), &ProjectConfig::default()).unwrap();
+        assert_eq!(
+            categories(&issues),
+            vec![
+                CheckCategory::MissingImage,
+                CheckCategory::BrokenInclude,
+                CheckCategory::UnresolvedRef,
+                CheckCategory::MissingFootnote,
+                CheckCategory::UndefinedPlaceholder,
+                CheckCategory::DuplicateAnchor,
+                CheckCategory::BrokenInclude,
+            ]
+        );
+        assert_eq!(issues[0].line, 3);
+        assert_eq!(issues[1].line, 5);
+        assert_eq!(issues[5].line, 1);
+        assert!(issues[5].path.ends_with("ch02_more.md"));
+
+        // Strict builds fail on the first of these, at its line
+        assert!(issues[0].category.is_strict());
+        assert!(!issues[3].category.is_strict());
+        let error = issues[0].to_error();
+        assert_eq!(error.code(), "image");
+        assert_eq!(error.location().map(|l| l.line), Some(3));
+    }
+
+    #[test]
+    fn test_ignores_code_blocks_and_frontmatter() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("ch01_intro.md"),
+            "---\ntitle: \"{{nothing}}\"\n---\n\n```markdown\n![X](x.png) {ref:nope}\n```\n\nUse `{{var}}` inline.\n",
+        )
+        .unwrap();
+
+        let issues = check_project(temp_dir.path(), &ProjectConfig::default()).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_fence_with_info_string_does_not_close_block() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("ch01_intro.md"),
+            "# Intro\n\n```text\n```rust\nSee [setup](setup.md) and {ref:nope}.\n```\n\nDone.\n",
+        )
+        .unwrap();
+
+        let issues = check_project(temp_dir.path(), &ProjectConfig::default()).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_prefixed_ref_matches_bare_label() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("ch01_intro.md"),
+            "$$\nE = mc^2 \\label{eq:energy}\n$$\n\nSee {ref:eq:energy} and {ref:energy}.\n",
+        )
+        .unwrap();
+
+        let issues = check_project(temp_dir.path(), &ProjectConfig::default()).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+}
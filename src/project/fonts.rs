@@ -0,0 +1,183 @@
+//! Font directory inspection (`md2docx fonts`)
+//!
+//! Lists the font files available for embedding, checks that the fonts
+//! named in `[fonts]` resolve to real files and reports which scripts each
+//! font has glyphs for.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::ProjectConfig;
+use crate::docx::font_embed::{
+    check_embed_permission, detect_font_name, detect_variant, font_name_matches, glyph_coverage,
+    read_font_metrics, FontVariant, GlyphCoverage,
+};
+use crate::Result;
+
+/// Directory searched for fonts to embed
+///
+/// `fonts.embed_dir` if set, otherwise `fonts/` inside the template
+/// directory, otherwise `fonts/` in the project.
+pub fn font_dir(base_dir: &Path, config: &ProjectConfig) -> PathBuf {
+    if let Some(ref embed_dir) = config.fonts.embed_dir {
+        base_dir.join(embed_dir)
+    } else if let Some(ref template_dir) = config.template.dir {
+        base_dir.join(template_dir).join("fonts")
+    } else {
+        base_dir.join("fonts")
+    }
+}
+
+/// One font file in the font directory
+#[derive(Debug, Clone)]
+pub struct FontFile {
+    pub path: PathBuf,
+    /// Family name from the font's name table, or derived from the filename
+    pub family: String,
+    /// Embedding slot; `None` for weights OOXML cannot embed (Light, Medium, ...)
+    pub variant: Option<FontVariant>,
+    /// Whether the font's license flags allow embedding (`None` if unreadable)
+    pub embeddable: Option<bool>,
+    /// Glyph coverage per script; empty if the file could not be parsed
+    pub coverage: Vec<GlyphCoverage>,
+}
+
+impl FontFile {
+    /// Whether this file provides the configured font `name`
+    pub fn provides(&self, name: &str) -> bool {
+        let file_family = self
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(detect_font_name)
+            .unwrap_or_default();
+        font_name_matches(&file_family, name) || font_name_matches(&self.family, name)
+    }
+}
+
+/// A font named in `[fonts]` and the files that provide it
+#[derive(Debug, Clone)]
+pub struct ConfiguredFont {
+    /// Config key (`default` or `code`)
+    pub role: &'static str,
+    pub name: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Result of inspecting a project's fonts
+#[derive(Debug, Clone)]
+pub struct FontReport {
+    /// Font directory that was scanned
+    pub dir: PathBuf,
+    /// Font files found, sorted by path (empty if the directory is missing)
+    pub files: Vec<FontFile>,
+    /// Configured fonts and the files that resolve them
+    pub configured: Vec<ConfiguredFont>,
+}
+
+impl FontReport {
+    /// Configured fonts with no matching file
+    pub fn missing(&self) -> impl Iterator<Item = &ConfiguredFont> {
+        self.configured.iter().filter(|f| f.files.is_empty())
+    }
+}
+
+/// Scan the project's font directory and resolve the configured fonts
+pub fn inspect_fonts(base_dir: &Path, config: &ProjectConfig) -> Result<FontReport> {
+    let dir = font_dir(base_dir, config);
+    let mut files = Vec::new();
+
+    if dir.is_dir() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let is_font = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("ttf") || e.eq_ignore_ascii_case("otf"));
+            if path.is_file() && is_font {
+                files.push(inspect_file(path)?);
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let configured = [
+        ("default", &config.fonts.default),
+        ("code", &config.fonts.code),
+    ]
+    .into_iter()
+    .filter(|(_, name)| !name.trim().is_empty())
+    .map(|(role, name)| ConfiguredFont {
+        role,
+        name: name.clone(),
+        files: files
+            .iter()
+            .filter(|f| f.provides(name))
+            .map(|f| f.path.clone())
+            .collect(),
+    })
+    .collect();
+
+    Ok(FontReport {
+        dir,
+        files,
+        configured,
+    })
+}
+
+fn inspect_file(path: PathBuf) -> Result<FontFile> {
+    let data = std::fs::read(&path)?;
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    let family = read_font_metrics(&data)
+        .map(|m| m.family_name)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| detect_font_name(filename));
+
+    Ok(FontFile {
+        family,
+        variant: detect_variant(filename),
+        embeddable: check_embed_permission(&path)
+            .ok()
+            .map(|p| p.is_embeddable()),
+        coverage: glyph_coverage(&data).unwrap_or_default(),
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_inspect_fonts() {
+        let temp_dir = TempDir::new().unwrap();
+        let fonts = temp_dir.path().join("fonts");
+        std::fs::create_dir_all(&fonts).unwrap();
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/fonts");
+        std::fs::copy(
+            source.join("NotoSansThai-Regular.ttf"),
+            fonts.join("NotoSansThai-Regular.ttf"),
+        )
+        .unwrap();
+        std::fs::write(fonts.join("notes.txt"), "").unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.fonts.default = "Noto Sans Thai".into();
+        config.fonts.code = "Fira Code".into();
+
+        let report = inspect_fonts(temp_dir.path(), &config).unwrap();
+        assert_eq!(report.files.len(), 1);
+        let file = &report.files[0];
+        assert_eq!(file.variant, Some(FontVariant::Regular));
+        assert_eq!(file.embeddable, Some(true));
+        assert_eq!(file.coverage.len(), 3);
+
+        assert_eq!(report.configured[0].files, vec![file.path.clone()]);
+        let missing: Vec<_> = report.missing().map(|f| f.role).collect();
+        assert_eq!(missing, vec!["code"]);
+    }
+}
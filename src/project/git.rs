@@ -0,0 +1,58 @@
+//! Repository metadata for the `{{git_*}}` placeholders
+
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// The commit a project is built from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoInfo {
+    /// Abbreviated commit hash ("1a2b3c4")
+    pub hash: String,
+    /// Closest tag reachable from the commit; empty when there is none
+    pub tag: String,
+    /// Commit date as `YYYY-MM-DD`, in the committer's time zone
+    pub date: String,
+}
+
+impl RepoInfo {
+    /// Placeholder names and values, for covers, headers and footers
+    pub fn placeholders(&self) -> [(&'static str, &str); 3] {
+        [
+            ("git_hash", &self.hash),
+            ("git_tag", &self.tag),
+            ("git_date", &self.date),
+        ]
+    }
+}
+
+/// Metadata of the checked-out commit of the repository containing `dir`
+pub fn repo_info(dir: &Path) -> Result<RepoInfo> {
+    let git_error = |e: &dyn std::fmt::Display| Error::Git(e.to_string());
+    let repo = gix::discover(dir).map_err(|e| git_error(&e))?;
+    let commit = repo.head_commit().map_err(|e| git_error(&e))?;
+
+    let hash = commit
+        .id()
+        .shorten()
+        .map_err(|e| git_error(&e))?
+        .to_string();
+    let tag = commit
+        .describe()
+        .names(gix::commit::describe::SelectRef::AllTags)
+        .try_resolve()
+        .map_err(|e| git_error(&e))?
+        .and_then(|resolution| resolution.outcome.name)
+        .map(|name| name.to_string())
+        .unwrap_or_default();
+    let date = commit_date(commit.time().map_err(|e| git_error(&e))?);
+
+    Ok(RepoInfo { hash, tag, date })
+}
+
+/// `YYYY-MM-DD` of a commit time, in the committer's time zone
+pub(super) fn commit_date(time: gix::date::Time) -> String {
+    chrono::DateTime::from_timestamp(time.seconds + i64::from(time.offset), 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,184 @@
+//! Image inventory for a project (`md2docx images list`)
+//!
+//! Finds every `![alt](path)` reference in the project's markdown files,
+//! resolves it the way the build does (relative to the referencing file)
+//! and flags images that are missing or larger than a size limit.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::check::{
+    frontmatter_end, image_path, is_remote, track_fence, IMAGE_PATTERN, INLINE_CODE_PATTERN,
+};
+use crate::config::ProjectConfig;
+use crate::discovery::DiscoveredProject;
+use crate::docx::image_utils::{read_image_dimensions, ImageDimensions};
+use crate::Result;
+
+/// Default size above which an image is reported as oversized (2 MiB)
+pub const DEFAULT_MAX_IMAGE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// State of a referenced image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageStatus {
+    Ok,
+    /// The file does not exist
+    Missing,
+    /// The file is larger than the size limit
+    Oversized,
+    /// `http(s):` or `data:` URL, not checked
+    Remote,
+}
+
+impl ImageStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageStatus::Ok => "ok",
+            ImageStatus::Missing => "missing",
+            ImageStatus::Oversized => "oversized",
+            ImageStatus::Remote => "remote",
+        }
+    }
+}
+
+impl fmt::Display for ImageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One image reference in a markdown file
+#[derive(Debug, Clone)]
+pub struct ImageRef {
+    /// Markdown file containing the reference
+    pub file: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    /// Image path as written in the markdown
+    pub src: String,
+    /// Resolved path on disk (`None` for remote images)
+    pub resolved: Option<PathBuf>,
+    /// File size in bytes, if the file exists
+    pub size_bytes: Option<u64>,
+    /// Pixel dimensions, if the format is recognised
+    pub dimensions: Option<ImageDimensions>,
+    pub status: ImageStatus,
+}
+
+/// List every image referenced by the project's markdown files
+///
+/// Images above `max_bytes` are marked [`ImageStatus::Oversized`]. Results
+/// are in file order, then line order.
+pub fn list_images(
+    base_dir: &Path,
+    config: &ProjectConfig,
+    max_bytes: u64,
+) -> Result<Vec<ImageRef>> {
+    let project = DiscoveredProject::discover_with_config(base_dir, config)?;
+    let assets_dir = base_dir.join(&config.assets.dir);
+    let mut images = Vec::new();
+
+    for path in project.all_files() {
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let content = std::fs::read_to_string(path)?;
+        scan_file(path, &content, &assets_dir, max_bytes, &mut images);
+    }
+
+    Ok(images)
+}
+
+fn scan_file(
+    path: &Path,
+    content: &str,
+    assets_dir: &Path,
+    max_bytes: u64,
+    images: &mut Vec<ImageRef>,
+) {
+    let file_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut fence: Option<(char, usize)> = None;
+
+    for (idx, raw_line) in content.lines().enumerate().skip(frontmatter_end(content)) {
+        if track_fence(&mut fence, raw_line.trim()) {
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        let line = INLINE_CODE_PATTERN.replace_all(raw_line, "");
+        for caps in IMAGE_PATTERN.captures_iter(&line) {
+            let src = caps[1].to_string();
+            let mut image = ImageRef {
+                file: path.to_path_buf(),
+                line: idx + 1,
+                src,
+                resolved: None,
+                size_bytes: None,
+                dimensions: None,
+                status: ImageStatus::Remote,
+            };
+
+            if !is_remote(&image.src) {
+                let resolved = image_path(file_dir, &image.src, assets_dir);
+                image.status = match std::fs::read(&resolved) {
+                    Ok(data) => {
+                        image.size_bytes = Some(data.len() as u64);
+                        image.dimensions = read_image_dimensions(&data);
+                        if data.len() as u64 > max_bytes {
+                            ImageStatus::Oversized
+                        } else {
+                            ImageStatus::Ok
+                        }
+                    }
+                    Err(_) => ImageStatus::Missing,
+                };
+                image.resolved = Some(resolved);
+            }
+
+            images.push(image);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_images() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("img")).unwrap();
+        // 1x1 PNG header is enough for the dimension reader
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 3]);
+        std::fs::write(dir.join("img/small.png"), &png).unwrap();
+        std::fs::write(dir.join("img/big.png"), vec![0u8; 64]).unwrap();
+        std::fs::write(
+            dir.join("ch01_intro.md"),
+            "# Intro\n\n![Small](img/small.png)\n\n```\n![Ignored](nope.png)\n```\n\
+             ![Big](img/big.png) ![Gone](img/gone.png)\n![Web](https://example.com/a.png)\n",
+        )
+        .unwrap();
+
+        let images = list_images(dir, &ProjectConfig::default(), 40).unwrap();
+        let summary: Vec<_> = images
+            .iter()
+            .map(|i| (i.src.as_str(), i.line, i.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("img/small.png", 3, ImageStatus::Ok),
+                ("img/big.png", 8, ImageStatus::Oversized),
+                ("img/gone.png", 8, ImageStatus::Missing),
+                ("https://example.com/a.png", 9, ImageStatus::Remote),
+            ]
+        );
+        let dims = images[0].dimensions.unwrap();
+        assert_eq!((dims.width, dims.height), (4, 3));
+    }
+}
@@ -0,0 +1,379 @@
+//! Links between the project's files (`[setup](ch02_setup.md#install)`)
+//!
+//! The chapters end up in one document, so a link to another markdown file
+//! of the project is rewritten to an `#id` link to the heading it points at:
+//! the heading's `{#id}`, or one added to it when the link uses the
+//! GitHub-style slug of its text. A link to a file alone points at its first
+//! heading. Links to files outside the project or to headings that don't
+//! exist are reported and left as they are.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::check::{frontmatter_end, is_remote, track_fence, INLINE_CODE_PATTERN};
+use crate::diagnostics::{SourceLocation, WarningCategory};
+
+static HEADING_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^ {0,3}(#{1,6})[ \t]+(.*?)[ \t]*$").expect("Invalid regex"));
+static LINK_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(!?)\[([^\]]*)\]\(\s*([^)\s]+)(\s+"[^"]*")?\s*\)"#).expect("Invalid regex")
+});
+
+/// An ATX heading of a file
+#[derive(Debug)]
+struct Heading {
+    /// 0-based line index
+    line: usize,
+    /// Explicit `{#id}`
+    id: Option<String>,
+    /// GitHub-style slug of the text (`## Quick Start` -> `quick-start`)
+    slug: String,
+}
+
+/// Where a link points
+enum Target {
+    /// Not a link to a project file (web page, image, figure anchor, ...)
+    Unchanged,
+    /// A heading, as (file, heading) indexes
+    Heading(usize, usize),
+    /// A project link that doesn't resolve, with the reason
+    Broken(String),
+}
+
+/// Headings of every file of a project, to resolve links against
+pub(super) struct LinkIndex {
+    files: Vec<(PathBuf, Vec<Heading>)>,
+}
+
+impl LinkIndex {
+    /// Index `files` (path and full content, frontmatter included)
+    pub fn new(files: &[(&Path, &str)]) -> Self {
+        let files = files
+            .iter()
+            .map(|(path, content)| {
+                let mut headings = Vec::new();
+                for_each_line(content, |index, line| {
+                    if let Some(caps) = HEADING_PATTERN.captures(line) {
+                        let (text, id) = split_heading_id(&caps[2]);
+                        headings.push(Heading {
+                            line: index,
+                            id,
+                            slug: slug(text),
+                        });
+                    }
+                });
+                (path.to_path_buf(), headings)
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Resolve `url`, written in file `from`
+    fn target(&self, from: usize, url: &str) -> Target {
+        if is_remote(url) || url.contains("://") || url.starts_with("mailto:") {
+            return Target::Unchanged;
+        }
+        let (file, fragment) = match url.split_once('#') {
+            Some((file, fragment)) => (file, Some(fragment).filter(|f| !f.is_empty())),
+            None => (url, None),
+        };
+
+        let index = if file.is_empty() {
+            from
+        } else {
+            if !file.to_ascii_lowercase().ends_with(".md") {
+                return Target::Unchanged;
+            }
+            let dir = self.files[from]
+                .0
+                .parent()
+                .unwrap_or_else(|| Path::new("."));
+            let path = dir.join(file.replace("%20", " "));
+            let found = path
+                .canonicalize()
+                .ok()
+                .and_then(|path| self.files.iter().position(|(p, _)| *p == path));
+            match found {
+                Some(index) => index,
+                None if path.exists() => {
+                    return Target::Broken(format!("'{}' is not part of the project", file))
+                }
+                None => return Target::Broken(format!("'{}' not found", file)),
+            }
+        };
+
+        let headings = &self.files[index].1;
+        let heading = match fragment {
+            None if file.is_empty() => return Target::Unchanged,
+            None => headings.first().map(|_| 0),
+            Some(fragment) => headings
+                .iter()
+                .position(|h| h.id.as_deref() == Some(fragment))
+                .or_else(|| headings.iter().position(|h| h.slug == fragment)),
+        };
+        match heading {
+            Some(heading) => Target::Heading(index, heading),
+            // In the same file the anchor may be a figure or table
+            None if file.is_empty() => Target::Unchanged,
+            None => Target::Broken(match fragment {
+                Some(fragment) => format!("no heading '#{}' in '{}'", fragment, file),
+                None => format!("'{}' has no heading to link to", file),
+            }),
+        }
+    }
+
+    /// Broken project links of file `index`, as (1-based line, reason)
+    pub fn broken_links(&self, index: usize, content: &str) -> Vec<(usize, String)> {
+        let mut broken = Vec::new();
+        for_each_line(content, |line_index, line| {
+            replace_links(line, |caps| {
+                if let Target::Broken(reason) = self.target(index, &caps[3]) {
+                    broken.push((line_index + 1, reason));
+                }
+                None
+            });
+        });
+        broken
+    }
+}
+
+/// Rewrite the links between `files` (path and full content) to `#id`
+/// links, adding the ids they need to the headings they point at
+///
+/// Broken links are reported as warnings and kept.
+pub(super) fn resolve_links(files: &[(&Path, &str)]) -> Vec<String> {
+    let index = LinkIndex::new(files);
+    let mut used: HashSet<String> = index
+        .files
+        .iter()
+        .flat_map(|(_, headings)| headings.iter().filter_map(|h| h.id.clone()))
+        .collect();
+    let mut added: HashMap<(usize, usize), String> = HashMap::new();
+
+    let linked: Vec<String> = files
+        .iter()
+        .enumerate()
+        .map(|(from, (path, content))| {
+            map_lines(content, |line_index, line| {
+                replace_links(line, |caps| match index.target(from, &caps[3]) {
+                    Target::Unchanged => None,
+                    Target::Broken(reason) => {
+                        let column = line
+                            .find(&caps[0])
+                            .map_or(0, |start| line[..start].chars().count() + 1);
+                        crate::diagnostics::warn_at(
+                            WarningCategory::UnresolvedRef,
+                            SourceLocation::new(*path, line_index + 1, column),
+                            format!("Broken link: {}", reason),
+                        );
+                        None
+                    }
+                    Target::Heading(file, heading) => {
+                        let target = &index.files[file].1[heading];
+                        let id = match &target.id {
+                            Some(id) => id.clone(),
+                            None => added
+                                .entry((file, heading))
+                                .or_insert_with(|| unique_id(&target.slug, &mut used))
+                                .clone(),
+                        };
+                        let title = caps.get(4).map_or("", |m| m.as_str());
+                        Some(format!("[{}](#{}{})", &caps[2], id, title))
+                    }
+                })
+            })
+        })
+        .collect();
+
+    linked
+        .into_iter()
+        .enumerate()
+        .map(|(file, content)| {
+            let headings = &index.files[file].1;
+            map_lines(&content, |line_index, line| {
+                let id = headings
+                    .iter()
+                    .position(|h| h.line == line_index)
+                    .and_then(|heading| added.get(&(file, heading)));
+                match id {
+                    Some(id) => {
+                        let line = line.trim_end();
+                        let text = line.trim_end_matches('#');
+                        // `## Title ##`: the closing hashes follow a space
+                        let line = if text.ends_with([' ', '\t']) {
+                            text
+                        } else {
+                            line
+                        };
+                        format!("{} {{#{}}}", line.trim_end(), id)
+                    }
+                    None => line.to_string(),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Call `f` with the index and text of every line outside the frontmatter
+/// and fenced code blocks
+fn for_each_line(content: &str, mut f: impl FnMut(usize, &str)) {
+    map_lines(content, |index, line| {
+        f(index, line);
+        String::new()
+    });
+}
+
+/// Rebuild `content` with every line outside the frontmatter and fenced code
+/// blocks replaced by `f(index, line)`
+fn map_lines(content: &str, mut f: impl FnMut(usize, &str) -> String) -> String {
+    let body_start = frontmatter_end(content);
+    let mut fence: Option<(char, usize)> = None;
+    let mut out = String::with_capacity(content.len());
+    for (index, raw) in content.split_inclusive('\n').enumerate() {
+        let line = raw.trim_end_matches(['\r', '\n']);
+        let ending = &raw[line.len()..];
+        if index < body_start {
+            out.push_str(raw);
+            continue;
+        }
+        if track_fence(&mut fence, line.trim()) {
+            out.push_str(raw);
+            continue;
+        }
+        if fence.is_some() {
+            out.push_str(raw);
+            continue;
+        }
+        out.push_str(&f(index, line));
+        out.push_str(ending);
+    }
+    out
+}
+
+/// Replace the links of a line outside inline code spans; `f` returns the
+/// new link, or `None` to keep it. Images are left alone.
+fn replace_links(line: &str, mut f: impl FnMut(&Captures) -> Option<String>) -> String {
+    let mut replace = |text: &str| {
+        LINK_PATTERN
+            .replace_all(text, |caps: &Captures| match &caps[1] {
+                "!" => caps[0].to_string(),
+                _ => f(caps).unwrap_or_else(|| caps[0].to_string()),
+            })
+            .into_owned()
+    };
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for code in INLINE_CODE_PATTERN.find_iter(line) {
+        out.push_str(&replace(&line[last..code.start()]));
+        out.push_str(code.as_str());
+        last = code.end();
+    }
+    out.push_str(&replace(&line[last..]));
+    out
+}
+
+/// Heading text without its closing hashes and `{#id}`, and the id
+fn split_heading_id(text: &str) -> (&str, Option<String>) {
+    let text = text.trim_end_matches('#').trim_end();
+    if let Some(start) = text.rfind("{#").filter(|_| text.ends_with('}')) {
+        let id = &text[start + 2..text.len() - 1];
+        return (text[..start].trim_end(), Some(id.to_string()));
+    }
+    (text, None)
+}
+
+/// GitHub-style heading slug: lower case, spaces to `-`, punctuation dropped
+fn slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_whitespace() => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_ascii_punctuation() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// An id for a heading that has none: its slug when that makes a plain
+/// ASCII bookmark name, else `section`, numbered when already taken
+fn unique_id(slug: &str, used: &mut HashSet<String>) -> String {
+    let plain = slug.starts_with(|c: char| c.is_ascii_alphabetic())
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let base = if plain { slug } else { "section" };
+    let mut id = base.to_string();
+    let mut n = 1;
+    while !used.insert(id.clone()) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Quick Start!"), "quick-start");
+        assert_eq!(slug("Step 2: `cargo` build"), "step-2-cargo-build");
+        assert_eq!(slug("การติดตั้ง"), "การติดตั้ง");
+        assert_eq!(
+            split_heading_id("Install {#install} ##"),
+            ("Install", Some("install".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_resolve_links() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        let intro = base.join("ch01_intro.md");
+        let setup = base.join("ch02_setup.md");
+        std::fs::write(&intro, "").unwrap();
+        std::fs::write(&setup, "").unwrap();
+        std::fs::write(base.join("notes.md"), "").unwrap();
+
+        let intro_md = "# Intro\n\nSee [install](ch02_setup.md#install), [setup](ch02_setup.md), \
+                        [config](./ch02_setup.md#configure-it) and [notes](notes.md).\n\n\
+                        ![logo](ch02_setup.md)\n\n`[code](ch02_setup.md)`\n";
+        let setup_md = "# Setup {#setup}\n\n## Install\n\n```\n## Not a heading\n```\n\n\
+                        ## Configure it ##\n\nBack to [intro](ch01_intro.md#nowhere).\n";
+        let files = [(intro.as_path(), intro_md), (setup.as_path(), setup_md)];
+
+        let index = LinkIndex::new(&files);
+        assert_eq!(
+            index.broken_links(0, intro_md),
+            vec![(3, "'notes.md' is not part of the project".to_string())]
+        );
+        assert_eq!(
+            index.broken_links(1, setup_md),
+            vec![(11, "no heading '#nowhere' in 'ch01_intro.md'".to_string())]
+        );
+
+        let (resolved, warnings) = crate::diagnostics::capture(|| resolve_links(&files));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[1].location,
+            Some(SourceLocation::new(&setup, 11, 9))
+        );
+        assert!(resolved[0].contains("[install](#install)"));
+        assert!(resolved[0].contains("[setup](#setup)"));
+        assert!(resolved[0].contains("[config](#configure-it)"));
+        assert!(resolved[0].contains("[notes](notes.md)"));
+        assert!(resolved[0].contains("![logo](ch02_setup.md)"));
+        assert!(resolved[0].contains("`[code](ch02_setup.md)`"));
+        assert!(resolved[1].contains("## Install {#install}\n"));
+        assert!(resolved[1].contains("## Configure it {#configure-it}\n"));
+        assert!(resolved[1].contains("```\n## Not a heading\n```"));
+        assert!(resolved[1].contains("[intro](ch01_intro.md#nowhere)"));
+    }
+}
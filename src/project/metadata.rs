@@ -0,0 +1,171 @@
+//! Chapter metadata index (`{!chapterlist}`)
+//!
+//! Collects the title, author and summary of every chapter from its
+//! frontmatter, for the `{{chapter_N_title}}` style template placeholders and
+//! the `{!chapterlist}` directive, which a line of its own in any file turns
+//! into a table of the chapters.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::discovery::DiscoveredProject;
+use crate::i18n::{Label, Strings};
+use crate::parser::parse_frontmatter;
+use crate::Result;
+
+/// Line replaced by the chapter summary table
+pub const CHAPTER_LIST_DIRECTIVE: &str = "{!chapterlist}";
+
+/// Metadata of one chapter
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChapterInfo {
+    /// Chapter number
+    pub number: u32,
+    /// Source file
+    pub path: PathBuf,
+    /// Frontmatter title, first heading, or file stem
+    pub title: String,
+    /// `author:` from the frontmatter
+    pub author: Option<String>,
+    /// `summary:` (or `description:`) from the frontmatter
+    pub summary: Option<String>,
+}
+
+/// Metadata of every chapter of a project, in build order
+pub fn chapter_index(project: &DiscoveredProject) -> Result<Vec<ChapterInfo>> {
+    project
+        .chapters
+        .iter()
+        .map(|ch| {
+            let content = std::fs::read_to_string(&ch.path)?;
+            Ok(chapter_info(ch.number, &ch.path, &content))
+        })
+        .collect()
+}
+
+/// Metadata of a chapter from its content
+pub fn chapter_info(number: u32, path: &Path, content: &str) -> ChapterInfo {
+    let extra = parse_frontmatter(content)
+        .0
+        .map(|fm| fm.extra)
+        .unwrap_or_default();
+    let field = |key: &str| extra.get(key).filter(|v| !v.is_empty()).cloned();
+    ChapterInfo {
+        number,
+        path: path.to_path_buf(),
+        title: super::stats::file_title(path, content),
+        author: field("author"),
+        summary: field("summary").or_else(|| field("description")),
+    }
+}
+
+/// Markdown table of the chapters for `{!chapterlist}`
+///
+/// The author and summary columns are left out when no chapter sets them.
+pub fn chapter_list_table(index: &[ChapterInfo], strings: &Strings) -> String {
+    let cell = |text: Option<&str>| text.unwrap_or("").replace('|', "\\|");
+    let authors = index.iter().any(|ch| ch.author.is_some());
+    let summaries = index.iter().any(|ch| ch.summary.is_some());
+
+    let mut header = vec![strings.get(Label::RevisionChapter)];
+    if summaries {
+        header.push(strings.get(Label::Summary));
+    }
+    if authors {
+        header.push(strings.get(Label::RevisionAuthor));
+    }
+    let mut markdown = format!(
+        "| {} |\n|{}\n",
+        header.join(" | "),
+        "---|".repeat(header.len())
+    );
+    for chapter in index {
+        let mut row = vec![cell(Some(&chapter.title))];
+        if summaries {
+            row.push(cell(chapter.summary.as_deref()));
+        }
+        if authors {
+            row.push(cell(chapter.author.as_deref()));
+        }
+        markdown.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    markdown
+}
+
+/// Replace `{!chapterlist}` lines outside code blocks with `table`
+pub fn expand_chapter_list(markdown: &str, table: &str) -> String {
+    expand_directive_lines(markdown, CHAPTER_LIST_DIRECTIVE, table)
+}
+
+/// Replace lines holding only `directive` outside code blocks with `table`
+pub(crate) fn expand_directive_lines(markdown: &str, directive: &str, table: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut fence: Option<&str> = None;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim();
+        match fence {
+            Some(marker) if trimmed.starts_with(marker) => fence = None,
+            Some(_) => {}
+            None if trimmed.starts_with("```") => fence = Some("```"),
+            None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+            None if trimmed == directive => {
+                out.push('\n');
+                out.push_str(table);
+                out.push('\n');
+                continue;
+            }
+            None => {}
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    #[test]
+    fn test_chapter_info() {
+        let content =
+            "---\ntitle: Budget\nauthor: Somchai\nsummary: Costs per year\n---\n\n# Ignored\n";
+        let info = chapter_info(3, Path::new("ch03_budget.md"), content);
+        assert_eq!(info.number, 3);
+        assert_eq!(info.title, "Budget");
+        assert_eq!(info.author.as_deref(), Some("Somchai"));
+        assert_eq!(info.summary.as_deref(), Some("Costs per year"));
+
+        let info = chapter_info(1, Path::new("ch01_intro.md"), "# Introduction\n");
+        assert_eq!(info.title, "Introduction");
+        assert_eq!(info.author, None);
+    }
+
+    #[test]
+    fn test_chapter_list() {
+        let index = vec![
+            ChapterInfo {
+                number: 1,
+                title: "Scope".to_string(),
+                summary: Some("What | why".to_string()),
+                ..Default::default()
+            },
+            ChapterInfo {
+                number: 2,
+                title: "Budget".to_string(),
+                ..Default::default()
+            },
+        ];
+        let table = chapter_list_table(&index, &Strings::new(Language::English));
+        assert_eq!(
+            table,
+            "| Chapter | Summary |\n|---|---|\n| Scope | What \\| why |\n| Budget |  |\n"
+        );
+
+        let md = "# Overview\n\n{!chapterlist}\n\n```\n{!chapterlist}\n```\n";
+        let expanded = expand_chapter_list(md, &table);
+        assert_eq!(expanded.matches("| Scope |").count(), 1);
+        assert!(expanded.contains("```\n{!chapterlist}\n```"));
+    }
+}
@@ -3,20 +3,263 @@
 //! This module provides a high-level API for building DOCX documents from
 //! project directories containing markdown files and configuration.
 
+mod batch;
+mod changelog;
+mod check;
+mod fonts;
+#[cfg(feature = "git")]
+mod git;
+mod images;
+mod links;
 mod markdown;
+mod metadata;
+mod report;
+mod scaffold;
+mod signatures;
+mod snippets;
+mod stats;
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
-use crate::config::ProjectConfig;
+use crate::config::{ChapterOverride, HeaderFooterSection, ProjectConfig};
+use crate::diagnostics::{SourceLocation, Warning, WarningCategory};
 #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
-use crate::discovery::DiscoveredProject;
+use crate::discovery::{DiscoveredProject, PartDir};
+use crate::docx::ooxml::numbering::ORDERED_FORMATS;
+use crate::docx::toc::{TocConfig, TocPageNumbers};
+use crate::i18n::{Label, Punctuation, PunctuationSpacing, Strings};
+use crate::parser::{expand_remote_includes, RemoteIncludes};
+use crate::template::replace_placeholders;
+use crate::vfs;
 use crate::{
-    markdown_to_docx_with_templates, DocumentConfig, Error, Language, PlaceholderContext, Result,
-    TemplateDir, TemplateSet,
+    markdown_to_docx_with_stages, DocumentConfig, Error, FileMap, FooterConfig, HeaderConfig,
+    HeaderFooterField, HeaderFooterImage, Language, NoteNumbering, NoteRestart, NumberingConfig,
+    ParagraphPreset, PlaceholderContext, Result, Spacers, StyleGallery, StyleSpacing, TemplateDir,
+    TemplateSet,
 };
 
-pub use markdown::{extract_cover_inside_content, resolve_image_paths, strip_frontmatter};
+pub use crate::parser::{resolve_asset_paths, resolve_image_paths, ASSETS_PREFIX};
+pub use batch::{build_all, BatchOptions, BatchResult, Workspace, WORKSPACE_FILE};
+pub use check::{check_project, CheckCategory, CheckIssue};
+pub use fonts::{font_dir, inspect_fonts, ConfiguredFont, FontFile, FontReport};
+pub use images::{list_images, ImageRef, ImageStatus, DEFAULT_MAX_IMAGE_BYTES};
+pub use markdown::{extract_cover_inside_content, strip_frontmatter};
+pub use metadata::{
+    chapter_index, chapter_info, chapter_list_table, expand_chapter_list, ChapterInfo,
+    CHAPTER_LIST_DIRECTIVE,
+};
+pub use report::{BuildReport, StageTiming};
+pub use scaffold::{new_chapter, NewChapter};
+pub use signatures::{expand_signatures, signatures_table, SIGNATURES_DIRECTIVE};
+pub use snippets::{
+    expand_snippets, load_snippets, unknown_snippets, SNIPPETS_DIR, SNIPPET_DIRECTIVE,
+};
+pub use stats::{file_stats, project_stats, FileStats, ProjectStats};
+
+/// Load the effective `md2docx.toml` configuration for a project directory
+///
+/// Uses the layered approach:
+/// 1. First peek at root md2docx.toml to find template.dir
+/// 2. Load template/md2docx.toml as base defaults
+/// 3. Merge root md2docx.toml as overrides on top
+///
+/// Returns the default config if neither file exists.
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+pub fn load_project_config(base_dir: &Path) -> Result<ProjectConfig> {
+    load_project_config_with_profile(base_dir, None)
+}
+
+/// Load the project configuration with a `[profile.<name>]` section applied
+///
+/// See [`ProjectConfig::from_files_layered_with_profile`].
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+pub fn load_project_config_with_profile(
+    base_dir: &Path,
+    profile: Option<&str>,
+) -> Result<ProjectConfig> {
+    load_project_targets(base_dir, profile).map(|mut targets| targets.swap_remove(0))
+}
+
+/// Load one configuration per `[[output]]` target of a project
+///
+/// Projects with a plain `[output]` section (or none) have one target.
+/// See [`ProjectConfig::targets_from_files_layered`].
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+pub fn load_project_targets(base_dir: &Path, profile: Option<&str>) -> Result<Vec<ProjectConfig>> {
+    let root_config_path = base_dir.join("md2docx.toml");
+
+    // Peek at root config to find template dir
+    let template_dir_hint = if root_config_path.exists() {
+        ProjectConfig::from_file(&root_config_path)
+            .ok()
+            .and_then(|c| c.template.dir.clone())
+    } else {
+        None
+    };
+
+    // Resolve template config path
+    let template_config_path = template_dir_hint
+        .as_ref()
+        .map(|td| base_dir.join(td).join("md2docx.toml"));
+
+    // Load layered config: template defaults + root overrides + profile
+    ProjectConfig::targets_from_files_layered(
+        template_config_path.as_deref(),
+        if root_config_path.exists() { Some(&root_config_path) } else { None },
+        profile,
+    )
+}
+
+/// Wrap chapter content in the directives that carry its settings to the builder
+///
+/// Fonts use `<!-- {font:...} -->`, page numbering and header variants use
+/// `<!-- {section:...} -->` and TOC exclusion uses `<!-- {toc:off} -->`.
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn apply_chapter_override(mut content: String, chapter: &ChapterOverride) -> String {
+    if let Some(ref font) = chapter.font {
+        content = format!(
+            "<!-- {{font:{}}} -->\n\n{}\n\n<!-- {{/font}} -->\n",
+            font, content
+        );
+    }
+    let mut options = Vec::new();
+    if let Some(start) = chapter.restart_numbering {
+        options.push(format!("restart={}", start));
+    }
+    if let Some(ref format) = chapter.page_format {
+        options.push(format!("format={}", format));
+    }
+    if let Some(ref header) = chapter.header {
+        options.push(format!("header={}", header));
+    }
+    if !options.is_empty() {
+        content = format!(
+            "<!-- {{section: {}}} -->\n\n{}",
+            options.join(", "),
+            content
+        );
+    }
+    if chapter.toc == Some(false) {
+        content = format!(
+            "<!-- {{toc:off}} -->\n\n{}\n\n<!-- {{toc:on}} -->\n",
+            content
+        );
+    }
+    content
+}
+
+/// Short commit hash of the git repository containing `dir`, if any
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn git_short_hash(dir: &Path) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!hash.is_empty()).then_some(hash)
+}
+
+/// Built-in part divider, used when neither the part nor `[parts]` has one
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+const DEFAULT_PART_DIVIDER: &str = "# {{part}}: {{part_title}}\n";
+
+/// Upper-case Roman numeral for a part number (4 -> "IV")
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn roman_numeral(mut n: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut roman = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            roman.push_str(numeral);
+            n -= value;
+        }
+    }
+    roman
+}
+
+/// Drop files excluded by the output's tag filters
+///
+/// Tags come from each file's frontmatter (`tags: [internal]`). Files that
+/// cannot be read are kept so the build reports the error.
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn filter_tagged(
+    project: &mut DiscoveredProject,
+    output: &crate::config::OutputSection,
+    sources: Option<&HashMap<PathBuf, String>>,
+) {
+    if output.exclude_tags.is_empty() && output.only_tags.is_empty() {
+        return;
+    }
+    let keep = |path: &Path| {
+        let Ok(content) = read_source(sources, path) else {
+            return true;
+        };
+        let tags = crate::parser::parse_frontmatter(&content)
+            .0
+            .map(|fm| fm.tags)
+            .unwrap_or_default();
+        let keep = output.includes_tags(&tags);
+        if !keep && tags.is_empty() {
+            log::info!("Skipping {} (untagged)", path.display());
+        } else if !keep {
+            log::info!("Skipping {} (tags: {})", path.display(), tags.join(", "));
+        }
+        keep
+    };
+    project.frontmatter.retain(|path| keep(path));
+    project.chapters.retain(|ch| keep(&ch.path));
+    project.appendices.retain(|ap| keep(&ap.path));
+    project.backmatter.retain(|path| keep(path));
+}
+
+/// Content of a project file, from `sources` when the project is held in
+/// memory
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn read_source(sources: Option<&HashMap<PathBuf, String>>, path: &Path) -> Result<String> {
+    match sources {
+        Some(sources) => sources
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::Config(format!("No source named {}", path.display()))),
+        None => Ok(std::fs::read_to_string(path)?),
+    }
+}
+
+/// Error for a build warning that `[output] strict` doesn't allow
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+fn strict_error(warning: &Warning) -> Option<Error> {
+    let error = match warning.category {
+        WarningCategory::MissingImage => Error::Image(warning.message.clone()),
+        WarningCategory::UnresolvedRef => Error::Reference(warning.message.clone()),
+        _ => return None,
+    };
+    Some(match &warning.location {
+        Some(location) => error.at(location.clone()),
+        None => error,
+    })
+}
 
 /// High-level project builder for converting markdown projects to DOCX
 ///
@@ -36,6 +279,13 @@ pub struct ProjectBuilder {
     templates: Option<TemplateSet>,
     toc_override: Option<bool>,
     output_override: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    load_time: Duration,
+    load_warnings: Vec<Warning>,
+    /// Markdown of each file for builders made with `from_sources`
+    sources: Option<HashMap<PathBuf, String>>,
+    /// Images and templates to read instead of the file system
+    files: Option<Arc<FileMap>>,
 }
 
 #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
@@ -46,36 +296,92 @@ impl ProjectBuilder {
     /// Discovers markdown files using the config patterns.
     /// Loads templates from the configured template directory.
     pub fn from_directory(dir: impl AsRef<Path>) -> Result<Self> {
-        let base_dir = dir.as_ref().to_path_buf();
-
-        // Load config with layered approach:
-        // 1. First peek at root md2docx.toml to find template.dir
-        // 2. Load template/md2docx.toml as base defaults
-        // 3. Merge root md2docx.toml as overrides on top
-        let root_config_path = base_dir.join("md2docx.toml");
-
-        // Peek at root config to find template dir
-        let template_dir_hint = if root_config_path.exists() {
-            ProjectConfig::from_file(&root_config_path)
-                .ok()
-                .and_then(|c| c.template.dir.clone())
-        } else {
-            None
-        };
+        Self::from_directory_with_profile(dir, None)
+    }
 
-        // Resolve template config path
-        let template_config_path = template_dir_hint.as_ref().map(|td| {
-            base_dir.join(td).join("md2docx.toml")
-        });
+    /// Create a builder from a directory path using a build profile
+    ///
+    /// The `[profile.<name>]` section of `md2docx.toml` is merged over the
+    /// rest of the configuration before files are discovered.
+    pub fn from_directory_with_profile(
+        dir: impl AsRef<Path>,
+        profile: Option<&str>,
+    ) -> Result<Self> {
+        let dir = dir.as_ref();
+        Self::timed(|| Self::load(dir, load_project_config_with_profile(dir, profile)?))
+    }
+
+    /// Create one builder per output target of a project
+    ///
+    /// A project listing several `[[output]]` entries produces several
+    /// documents; each builder discovers files and loads templates using
+    /// its own target's settings.
+    pub fn targets_from_directory(
+        dir: impl AsRef<Path>,
+        profile: Option<&str>,
+    ) -> Result<Vec<Self>> {
+        let dir = dir.as_ref();
+        load_project_targets(dir, profile)?
+            .into_iter()
+            .map(|config| Self::timed(|| Self::load(dir, config)))
+            .collect()
+    }
+
+    /// Create a builder from markdown held in memory
+    ///
+    /// `sources` are `(name, markdown)` pairs such as
+    /// `("ch01_intro.md", "# Introduction")`. Nothing is discovered or read
+    /// from disk: the files are arranged by name in the order given (see
+    /// [`DiscoveredProject::from_files`]), and images and templates come
+    /// from [`with_files`](Self::with_files).
+    pub fn from_sources(sources: Vec<(String, String)>, config: ProjectConfig) -> Result<Self> {
+        Self::timed(|| {
+            let base_dir = PathBuf::new();
+            let mut contents = HashMap::new();
+            let mut names = Vec::new();
+            for (name, markdown) in sources {
+                let path = vfs::normalize(Path::new(&name));
+                if contents.insert(path.clone(), markdown).is_some() {
+                    return Err(Error::Config(format!("Duplicate source name: {}", name)));
+                }
+                names.push(path);
+            }
+
+            let mut project = DiscoveredProject::from_files(&base_dir, &names, &config);
+            filter_tagged(&mut project, &config.output, Some(&contents));
+
+            Ok(Self {
+                base_dir,
+                config,
+                project,
+                templates: None,
+                toc_override: None,
+                output_override: None,
+                output_dir: None,
+                load_time: Duration::ZERO,
+                load_warnings: Vec::new(),
+                sources: Some(contents),
+                files: Some(Arc::new(FileMap::new())),
+            })
+        })
+    }
+
+    /// Run a loader, recording its duration and warnings for the report
+    fn timed(load: impl FnOnce() -> Result<Self>) -> Result<Self> {
+        let start = Instant::now();
+        let (builder, warnings) = crate::diagnostics::capture(load);
+        let mut builder = builder?;
+        builder.load_time = start.elapsed();
+        builder.load_warnings = warnings;
+        Ok(builder)
+    }
 
-        // Load layered config: template defaults + root overrides
-        let config = ProjectConfig::from_files_layered(
-            template_config_path.as_deref(),
-            if root_config_path.exists() { Some(&root_config_path) } else { None },
-        )?;
+    fn load(dir: &Path, config: ProjectConfig) -> Result<Self> {
+        let base_dir = dir.to_path_buf();
 
         // Discover project files
-        let project = DiscoveredProject::discover_with_config(&base_dir, &config)?;
+        let mut project = DiscoveredProject::discover_with_config(&base_dir, &config)?;
+        filter_tagged(&mut project, &config.output, None);
 
         // Load templates if configured
         let templates = if let Some(ref template_dir) = config.template.dir {
@@ -97,69 +403,282 @@ impl ProjectBuilder {
             templates,
             toc_override: None,
             output_override: None,
+            output_dir: None,
+            load_time: Duration::ZERO,
+            load_warnings: Vec::new(),
+            sources: None,
+            files: None,
         })
     }
 
+    /// Read images and templates from `files` instead of the file system
+    ///
+    /// Paths are relative to the project root; templates are looked up in
+    /// `template.dir`, e.g. `template/cover.docx`.
+    pub fn with_files(mut self, files: FileMap) -> Result<Self> {
+        let files = Arc::new(files);
+        if let Some(dir) = &self.config.template.dir {
+            let templates =
+                TemplateDir::from_files(&self.base_dir.join(dir), files.clone()).load_all()?;
+            self.templates = (!templates.is_empty()).then_some(templates);
+        }
+        self.files = Some(files);
+        Ok(self)
+    }
+
     /// Override TOC settings from CLI
     pub fn with_toc(mut self, enabled: bool) -> Self {
         self.toc_override = Some(enabled);
         self
     }
 
+    /// Only include chapters and appendices tagged with one of `tags`
+    ///
+    /// Replaces `only_tags` from the config; `exclude_tags` still applies.
+    pub fn with_only_tags(mut self, tags: &[String]) -> Self {
+        self.config.output.only_tags = tags.to_vec();
+        filter_tagged(
+            &mut self.project,
+            &self.config.output,
+            self.sources.as_ref(),
+        );
+        self
+    }
+
+    /// Produce byte-identical output for identical input
+    pub fn reproducible(mut self) -> Self {
+        self.config.output.reproducible = true;
+        self
+    }
+
+    /// Only use cached copies of remote includes, never download
+    pub fn offline(mut self) -> Self {
+        self.config.remote.offline = true;
+        self
+    }
+
+    /// Embed images at full size, ignoring `[images]` limits
+    pub fn without_image_limits(mut self) -> Self {
+        self.config.images.max_dpi = None;
+        self.config.images.max_width = None;
+        self
+    }
+
     /// Override output path from CLI
     pub fn with_output(mut self, path: PathBuf) -> Self {
         self.output_override = Some(path);
         self
     }
 
+    /// Write the output into `dir`
+    ///
+    /// Relative output paths, whether from `--output` or `output.file`, are
+    /// placed under `dir`.
+    pub fn with_output_dir(mut self, dir: PathBuf) -> Self {
+        self.output_dir = Some(dir);
+        self
+    }
+
+    /// Override a `[document]` value or set a custom placeholder variable
+    pub fn with_var(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.config.document.set(key, value);
+        self
+    }
+
     /// Build the DOCX document and return bytes
     pub fn build(self) -> Result<Vec<u8>> {
+        self.render(&mut Vec::new())
+    }
+
+    /// Build the DOCX document along with the warnings reported while
+    /// loading and building the project
+    ///
+    /// Unlike [`build_to_file_with_report`](Self::build_to_file_with_report)
+    /// nothing is written; the report carries the DOCX bytes.
+    pub fn build_with_report(self) -> Result<crate::diagnostics::BuildReport> {
+        let mut report = crate::diagnostics::BuildReport::capture(|| self.render(&mut Vec::new()))?;
+        let mut warnings = self.load_warnings;
+        warnings.append(&mut report.warnings);
+        report.warnings = warnings;
+        Ok(report)
+    }
+
+    /// Build the document, recording how long each stage takes
+    fn render(&self, timings: &mut Vec<StageTiming>) -> Result<Vec<u8>> {
         if !self.project.is_valid() {
             return Err(Error::Config(
                 "No markdown files found in project directory".into(),
             ));
         }
+        self.check_strict()?;
+
+        log::info!(
+            "Building {} file(s) from {}",
+            self.project.all_files().len(),
+            self.base_dir.display()
+        );
 
         // Combine markdown files
+        let start = Instant::now();
         let (combined_markdown, first_content_dir) = self.combine_markdown_files()?;
+        timings.push(StageTiming::new("combine", start.elapsed()));
 
         // Determine language
-        let lang = if self.config.is_thai() {
-            Language::Thai
-        } else {
-            Language::English
-        };
+        let lang = self.language();
 
         // Build placeholder context
         let placeholder_ctx = self.build_placeholder_context();
 
         // Build document config
-        let doc_config = self.build_document_config(first_content_dir);
-
-        // Change to project directory for relative image paths
-        let original_dir = std::env::current_dir()?;
-        std::env::set_current_dir(&self.base_dir)?;
-
-        let result = markdown_to_docx_with_templates(
-            &combined_markdown,
-            lang,
-            &doc_config,
-            self.templates.as_ref(),
-            &placeholder_ctx,
-        );
+        let doc_config = self.build_document_config(first_content_dir, &placeholder_ctx)?;
+
+        // Discovered chapter paths are absolute, so image paths resolved per
+        // chapter don't depend on the working directory. This keeps builds of
+        // different projects safe to run in parallel.
+        let mut start = Instant::now();
+        let (docx, warnings) = crate::diagnostics::capture(|| {
+            markdown_to_docx_with_stages(
+                &combined_markdown,
+                lang,
+                &doc_config,
+                self.templates.as_ref(),
+                &placeholder_ctx,
+                &mut |stage| {
+                    timings.push(StageTiming::new(stage, start.elapsed()));
+                    start = Instant::now();
+                },
+            )
+        });
+        let docx = docx?;
+
+        // Images that turned out unreadable and refs without a target
+        if self.config.output.strict {
+            if let Some(error) = warnings.iter().find_map(strict_error) {
+                return Err(error);
+            }
+        }
+        Ok(docx)
+    }
+
+    /// The project as one side of a [`diff::compare`](crate::diff::compare)
+    ///
+    /// Chapters and appendices are combined as for a build, with the
+    /// project's language and configuration. Templates are not applied.
+    pub fn to_version(&self) -> Result<crate::diff::Version> {
+        let (markdown, config, lang) = self.prepare()?;
+        Ok(crate::diff::Version {
+            markdown,
+            lang: Some(lang),
+            config,
+        })
+    }
+
+    /// Build the project as a standalone HTML page
+    ///
+    /// Uses the same combined markdown and document settings as the DOCX
+    /// build; templates are not applied.
+    pub fn build_html(&self) -> Result<String> {
+        let (combined_markdown, doc_config, lang) = self.prepare()?;
+        crate::markdown_to_html(&combined_markdown, lang, &doc_config)
+    }
+
+    /// Build the project as an OpenDocument text file
+    ///
+    /// Uses the same combined markdown and document settings as the DOCX
+    /// build; templates are not applied.
+    pub fn build_odt(&self) -> Result<Vec<u8>> {
+        let (combined_markdown, doc_config, lang) = self.prepare()?;
+        crate::markdown_to_odt(&combined_markdown, lang, &doc_config)
+    }
+
+    /// Build the project as an EPUB, one spine item per chapter
+    ///
+    /// Uses the same chapters and document settings as the DOCX build, with
+    /// the cover and the revision history as chapters of their own;
+    /// templates are not applied.
+    pub fn build_epub(&self) -> Result<Vec<u8>> {
+        self.ensure_files()?;
+        let (chapters, first_content_dir) = self.chapter_markdown()?;
+        let (doc_config, lang) = self.output_settings(first_content_dir)?;
+        let chapters: Vec<&str> = chapters.iter().map(String::as_str).collect();
+        crate::markdown_to_epub(&chapters, lang, &doc_config)
+    }
+
+    /// Build the project as a PDF
+    ///
+    /// Uses the same combined markdown and document settings as the DOCX
+    /// build; templates are not applied.
+    #[cfg(feature = "pdf")]
+    pub fn build_pdf(&self) -> Result<Vec<u8>> {
+        let (combined_markdown, doc_config, lang) = self.prepare()?;
+        crate::markdown_to_pdf(&combined_markdown, lang, &doc_config)
+    }
+
+    /// Combined markdown, document settings and language for diffs and the
+    /// outputs other than DOCX
+    fn prepare(&self) -> Result<(String, DocumentConfig, Language)> {
+        self.ensure_files()?;
+        self.check_strict()?;
+        let (combined_markdown, first_content_dir) = self.combine_markdown_files()?;
+        let (doc_config, lang) = self.output_settings(first_content_dir)?;
+        Ok((combined_markdown, doc_config, lang))
+    }
 
-        std::env::set_current_dir(original_dir)?;
+    fn ensure_files(&self) -> Result<()> {
+        if !self.project.is_valid() {
+            return Err(Error::Config(
+                "No markdown files found in project directory".into(),
+            ));
+        }
+        Ok(())
+    }
 
-        result
+    /// Fail on the first problem `[output] strict` rules out
+    ///
+    /// Projects on disk are checked like `md2docx check`, so the error
+    /// points at the line. Builders made with `from_sources` rely on the
+    /// warnings of the build itself.
+    fn check_strict(&self) -> Result<()> {
+        if !self.config.output.strict || self.sources.is_some() {
+            return Ok(());
+        }
+        let issues = check::check_discovered(&self.base_dir, &self.config, &self.project)?;
+        match issues.iter().find(|issue| issue.category.is_strict()) {
+            Some(issue) => Err(issue.to_error()),
+            None => Ok(()),
+        }
+    }
+
+    /// Document settings and language for the outputs other than DOCX
+    fn output_settings(
+        &self,
+        first_content_dir: Option<PathBuf>,
+    ) -> Result<(DocumentConfig, Language)> {
+        let lang = self.language();
+        let placeholder_ctx = self.build_placeholder_context();
+        let doc_config = self.build_document_config(first_content_dir, &placeholder_ctx)?;
+        Ok((doc_config, lang))
     }
 
     /// Build the DOCX document and write to file
     ///
     /// Returns the path of the output file.
     pub fn build_to_file(self) -> Result<PathBuf> {
+        self.build_to_file_with_report().map(|report| report.output)
+    }
+
+    /// Build the DOCX document, write it to file and summarize the build
+    ///
+    /// The report includes warnings raised while loading the project and
+    /// timings for the `load`, `combine`, `parse`, `build`, `package` and
+    /// `write` stages.
+    pub fn build_to_file_with_report(self) -> Result<BuildReport> {
         let output_path = self.resolve_output_path();
-        let docx_bytes = self.build()?;
+        let mut timings = vec![StageTiming::new("load", self.load_time)];
+        let (docx_bytes, warnings) = crate::diagnostics::capture(|| self.render(&mut timings));
+        let docx_bytes = docx_bytes?;
 
+        let start = Instant::now();
         // Create parent directories if needed
         if let Some(parent) = output_path.parent() {
             if !parent.exists() {
@@ -167,8 +686,17 @@ impl ProjectBuilder {
             }
         }
 
-        std::fs::write(&output_path, docx_bytes)?;
-        Ok(output_path)
+        std::fs::write(&output_path, &docx_bytes)?;
+        timings.push(StageTiming::new("write", start.elapsed()));
+
+        let mut all_warnings = self.load_warnings;
+        all_warnings.extend(warnings);
+        Ok(BuildReport::new(
+            output_path,
+            &docx_bytes,
+            all_warnings,
+            timings,
+        ))
     }
 
     /// Get the base directory
@@ -191,25 +719,95 @@ impl ProjectBuilder {
         &self.project
     }
 
+    /// Title, author and summary of every chapter, from its frontmatter
+    pub fn chapter_index(&self) -> Result<Vec<ChapterInfo>> {
+        if self.sources.is_none() {
+            return chapter_index(&self.project);
+        }
+        self.project
+            .chapters
+            .iter()
+            .map(|ch| {
+                let content = self.read_file(&ch.path)?;
+                Ok(chapter_info(ch.number, &ch.path, &content))
+            })
+            .collect()
+    }
+
     // --- Private helpers ---
 
+    fn read_file(&self, path: &Path) -> Result<String> {
+        read_source(self.sources.as_ref(), path)
+    }
+
     fn resolve_output_path(&self) -> PathBuf {
         if let Some(ref override_path) = self.output_override {
-            return override_path.clone();
+            return match self.output_dir {
+                Some(ref dir) => dir.join(override_path),
+                None => override_path.clone(),
+            };
         }
 
-        if let Some(resolved) = self.config.output.resolve_filename(Some(&self.config)) {
+        if let Some(resolved) = self
+            .config
+            .output
+            .resolve_filename_with(Some(&self.config), &self.filename_vars())
+        {
             // Output path is relative to current directory, not input directory
-            resolved
+            match self.output_dir {
+                Some(ref dir) => dir.join(resolved),
+                None => resolved,
+            }
         } else {
-            self.base_dir.join("output.docx")
+            self.output_dir
+                .as_ref()
+                .unwrap_or(&self.base_dir)
+                .join("output.docx")
+        }
+    }
+
+    /// Project-level variables for the output filename pattern
+    fn filename_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = vec![("chapter_count", self.project.chapters.len().to_string())];
+        let uses_git = self
+            .config
+            .output
+            .file
+            .as_ref()
+            .is_some_and(|f| f.to_string_lossy().contains("{{git_hash}}"));
+        if uses_git {
+            vars.push((
+                "git_hash",
+                git_short_hash(&self.base_dir).unwrap_or_default(),
+            ));
         }
+        vars
     }
 
     fn combine_markdown_files(&self) -> Result<(String, Option<PathBuf>)> {
-        let files = self.project.all_files();
+        let (chapters, first_content_dir) = self.chapter_markdown()?;
         let mut combined = String::new();
+        for chapter in chapters {
+            // Add section break between chapters
+            if !combined.is_empty() {
+                combined.push_str("\n\n---\n\n");
+            }
+            combined.push_str(&chapter);
+        }
+        Ok((combined, first_content_dir))
+    }
+
+    /// Markdown of each file in build order, ready to be converted, and the
+    /// directory of the first one
+    ///
+    /// The revision history, when enabled, comes last as a chapter of its
+    /// own.
+    fn chapter_markdown(&self) -> Result<(Vec<String>, Option<PathBuf>)> {
+        let files = self.project.all_files();
+        let mut chapters = Vec::new();
         let mut first_content_dir: Option<PathBuf> = None;
+        let edits = self.chapter_edits();
+        let mut edited_chapters = Vec::new();
 
         // Check if using cover template - if so, skip cover.md from main content
         let using_cover_template = self
@@ -218,7 +816,41 @@ impl ProjectBuilder {
             .map(|t| t.has_cover())
             .unwrap_or(false);
 
-        for file_path in files {
+        // Links between files become links to headings of the merged document
+        let raw_contents = files
+            .iter()
+            .map(|path| {
+                self.read_file(path)
+                    .map_err(|e| e.at(SourceLocation::file(path.as_path())))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let sources: Vec<(&Path, &str)> = files
+            .iter()
+            .zip(&raw_contents)
+            .map(|(path, content)| (path.as_path(), content.as_str()))
+            .collect();
+        let linked = links::resolve_links(&sources);
+
+        let mut current_part = None;
+        let mut chapter_list = None;
+        let mut snippets = None;
+        for ((file_path, raw_content), linked_content) in
+            files.into_iter().zip(&raw_contents).zip(&linked)
+        {
+            // A part's divider page comes before its first chapter
+            let part = self
+                .project
+                .chapters
+                .iter()
+                .find(|ch| ch.path == *file_path)
+                .and_then(|ch| ch.part);
+            if part.is_some() && part != current_part {
+                if let Some(dir) = self.project.parts.iter().find(|p| Some(p.number) == part) {
+                    chapters.push(self.part_divider(dir)?);
+                }
+            }
+            current_part = part;
+
             let file_name = file_path
                 .file_name()
                 .and_then(|n| n.to_str())
@@ -236,23 +868,370 @@ impl ProjectBuilder {
                 }
             }
 
-            let raw_content = std::fs::read_to_string(file_path)?;
-
             // Strip frontmatter
-            let content_without_frontmatter = strip_frontmatter(&raw_content);
+            let mut content_without_frontmatter = strip_frontmatter(linked_content);
+
+            // Markdown pulled in from URLs
+            if content_without_frontmatter.contains("{!include:http") {
+                content_without_frontmatter =
+                    expand_remote_includes(&content_without_frontmatter, &self.remote_includes())
+                        .map_err(|e| e.at(SourceLocation::file(file_path.as_path())))?;
+            }
+
+            // Reusable fragments from snippets/ and [snippets]
+            if content_without_frontmatter.contains(SNIPPET_DIRECTIVE) {
+                if snippets.is_none() {
+                    snippets = Some(match self.sources {
+                        Some(_) => self.config.snippets.clone(),
+                        None => load_snippets(&self.base_dir, &self.config.snippets)?,
+                    });
+                }
+                if let Some(snippets) = &snippets {
+                    content_without_frontmatter =
+                        expand_snippets(&content_without_frontmatter, snippets)
+                            .map_err(|e| e.at(SourceLocation::file(file_path.as_path())))?;
+                }
+            }
+
+            // Chapter summary table from the chapters' frontmatter
+            if content_without_frontmatter.contains(CHAPTER_LIST_DIRECTIVE) {
+                if chapter_list.is_none() {
+                    let index = self.chapter_index()?;
+                    chapter_list = Some(chapter_list_table(&index, &self.strings()));
+                }
+                if let Some(table) = &chapter_list {
+                    content_without_frontmatter =
+                        expand_chapter_list(&content_without_frontmatter, table);
+                }
+            }
+
+            // Version history table from [[revisions]]
+            if content_without_frontmatter.contains(changelog::REVISIONS_DIRECTIVE) {
+                let table = changelog::revisions_table(
+                    &self.config.revisions,
+                    &self.strings(),
+                    self.config.i18n.date_format.as_deref(),
+                );
+                content_without_frontmatter =
+                    changelog::expand_revisions(&content_without_frontmatter, &table);
+            }
+
+            // Approval table from [signatures]
+            if content_without_frontmatter.contains(SIGNATURES_DIRECTIVE) {
+                let table = signatures_table(&self.config.signatures, &self.strings());
+                content_without_frontmatter =
+                    expand_signatures(&content_without_frontmatter, &table);
+            }
 
             // Resolve image paths
-            let content = resolve_image_paths(&content_without_frontmatter, file_path);
+            let mut content = self.resolve_images(&content_without_frontmatter, file_path);
 
-            // Add section break between chapters
-            if !combined.is_empty() {
-                combined.push_str("\n\n---\n\n");
+            // Last author and edit date for {chapter_author} / {chapter_date}
+            if let Some(revision) = edits.get(file_path.as_path()) {
+                let directive = changelog::edited_directive(
+                    revision,
+                    Language::from_code(self.config.language()).unwrap_or_default(),
+                    self.config.i18n.date_format.as_deref(),
+                );
+                content = format!("{}\n\n{}", directive, content);
+                let title = stats::file_title(file_path, raw_content);
+                edited_chapters.push((title, revision.clone()));
             }
 
-            combined.push_str(&content);
+            // Per-chapter font, page numbering, header and TOC settings
+            let chapter = self
+                .chapter_override(file_path, raw_content)
+                .or(self.matter_numbering(file_path));
+            if chapter != ChapterOverride::default() {
+                content = apply_chapter_override(content, &chapter);
+            }
+
+            chapters.push(content);
         }
 
-        Ok((combined, first_content_dir))
+        if let Some(history) = self.revision_history(&edited_chapters) {
+            chapters.push(history);
+        }
+
+        Ok((chapters, first_content_dir))
+    }
+
+    /// Download settings for `{!include:https://...}` from `[remote]`
+    fn remote_includes(&self) -> RemoteIncludes {
+        let remote = &self.config.remote;
+        RemoteIncludes {
+            cache_dir: self.base_dir.join(&remote.cache_dir),
+            offline: remote.offline,
+            max_age: Duration::from_secs(remote.max_age_hours * 60 * 60),
+        }
+    }
+
+    /// Resolve the image paths of `file_path`'s content, `@assets/` ones
+    /// against the shared assets directory
+    fn resolve_images(&self, content: &str, file_path: &Path) -> String {
+        let assets_dir = self.base_dir.join(&self.config.assets.dir);
+        resolve_asset_paths(&resolve_image_paths(content, file_path), &assets_dir)
+    }
+
+    /// Divider page of a part, from its `part.md`, `[parts] template` or the
+    /// built-in heading
+    ///
+    /// The part's title is the `title` in `part.md`'s frontmatter, or its
+    /// directory name (`part-01-getting-started` -> "Getting started").
+    fn part_divider(&self, part: &PartDir) -> Result<String> {
+        let own = match &part.divider {
+            Some(path) => Some((std::fs::read_to_string(path)?, path.clone())),
+            None => None,
+        };
+        let title = own
+            .as_ref()
+            .and_then(|(raw, _)| crate::parser::parse_frontmatter(raw).0?.title)
+            .unwrap_or_else(|| {
+                let name = part.name.replace(['-', '_'], " ");
+                let mut chars = name.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            });
+        let template = match own {
+            Some((raw, path)) if !strip_frontmatter(&raw).trim().is_empty() => {
+                Some((strip_frontmatter(&raw), path))
+            }
+            _ => match &self.config.parts.template {
+                Some(file) => {
+                    let path = self.base_dir.join(file);
+                    let raw = std::fs::read_to_string(&path).map_err(|e| {
+                        Error::Config(format!(
+                            "Cannot read part template {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?;
+                    Some((strip_frontmatter(&raw), path))
+                }
+                None => None,
+            },
+        };
+
+        let number = if self.config.parts.roman {
+            roman_numeral(part.number)
+        } else {
+            part.number.to_string()
+        };
+        let mut ctx = PlaceholderContext::new(
+            self.config.document.title.clone(),
+            self.config.document.author.clone(),
+        );
+        ctx.set("part", self.strings().part(&number));
+        ctx.set("part_number", &number);
+        ctx.set("part_title", &title);
+
+        let content = match template {
+            Some((markdown, path)) => {
+                self.resolve_images(&replace_placeholders(&markdown, &ctx), &path)
+            }
+            None if title.is_empty() => replace_placeholders("# {{part}}\n", &ctx),
+            None => replace_placeholders(DEFAULT_PART_DIVIDER, &ctx),
+        };
+        // Like the cover and TOC, divider pages have no header or footer
+        let divider = ChapterOverride {
+            header: Some("none".to_string()),
+            ..Default::default()
+        };
+        Ok(apply_chapter_override(content, &divider))
+    }
+
+    /// Localized strings with the `[i18n.strings]` overrides
+    fn strings(&self) -> Strings {
+        // Unknown keys are already reported by build_string_overrides
+        let overrides = self
+            .config
+            .i18n
+            .strings
+            .iter()
+            .filter_map(|(key, text)| Some((Label::from_key(key)?, text.clone())))
+            .collect();
+        let lang = Language::from_code(self.config.language()).unwrap_or_default();
+        Strings::new(lang).with_overrides(overrides)
+    }
+
+    /// `[changelog]` revision history table for the project's files
+    ///
+    /// `chapters` (title and last commit of each chapter) adds a second
+    /// table. A repository that can't be read is reported as a warning and
+    /// the appendix left out, so the document still builds.
+    fn revision_history(&self, chapters: &[(String, changelog::Revision)]) -> Option<String> {
+        let config = &self.config.changelog;
+        if !config.enabled || self.sources.is_some() {
+            return None;
+        }
+        let files: Vec<&Path> = self
+            .project
+            .all_files()
+            .into_iter()
+            .map(PathBuf::as_path)
+            .collect();
+        let history = match changelog::file_history(&self.base_dir, &files) {
+            Ok(history) => history,
+            Err(e) => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!("Skipping the revision history: {}", e),
+                );
+                return None;
+            }
+        };
+
+        let strings = self.strings();
+        let date_format = self.config.i18n.date_format.as_deref();
+        let mut markdown = changelog::revision_table(
+            &changelog::select(&history, config),
+            config.title.as_deref(),
+            &strings,
+            date_format,
+        );
+        if !chapters.is_empty() {
+            markdown.push('\n');
+            markdown.push_str(&changelog::chapter_table(chapters, &strings, date_format));
+        }
+        Some(markdown)
+    }
+
+    /// Last commit of each chapter, appendix and front or back matter file,
+    /// with `[changelog] chapters`
+    fn chapter_edits(&self) -> HashMap<&Path, changelog::Revision> {
+        if !self.config.changelog.chapters || self.sources.is_some() {
+            return HashMap::new();
+        }
+        let project = &self.project;
+        let files: Vec<&Path> = project
+            .frontmatter
+            .iter()
+            .map(PathBuf::as_path)
+            .chain(project.chapters.iter().map(|ch| ch.path.as_path()))
+            .chain(project.appendices.iter().map(|ap| ap.path.as_path()))
+            .chain(project.backmatter.iter().map(PathBuf::as_path))
+            .collect();
+        match changelog::last_edits(&self.base_dir, &files) {
+            Ok(edits) => files
+                .into_iter()
+                .zip(edits)
+                .filter_map(|(file, edit)| Some((file, edit?)))
+                .collect(),
+            Err(e) => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!("Skipping chapter authors: {}", e),
+                );
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Settings for one chapter from `[chapters.overrides]` and its frontmatter
+    ///
+    /// Front-matter chapters (`ch00_*.md`) are left out of the TOC when
+    /// `toc.front_matter` is off, unless the chapter sets `toc` itself.
+    fn chapter_override(&self, path: &Path, raw_content: &str) -> ChapterOverride {
+        let configured = self
+            .config
+            .chapters
+            .override_for(path)
+            .cloned()
+            .unwrap_or_default();
+        let from_frontmatter = crate::parser::parse_frontmatter(raw_content)
+            .0
+            .map(|fm| ChapterOverride {
+                font: fm.extra.get("font").cloned(),
+                restart_numbering: fm
+                    .extra
+                    .get("restart_numbering")
+                    .and_then(|n| n.parse().ok()),
+                page_format: fm.extra.get("page_format").cloned(),
+                toc: fm
+                    .extra
+                    .get("toc")
+                    .map(|v| matches!(v.as_str(), "true" | "yes"))
+                    .or(fm.skip_toc.then_some(false)),
+                header: fm.extra.get("header").cloned(),
+            })
+            .unwrap_or_default();
+        let mut chapter = configured.or(from_frontmatter);
+        if chapter.toc.is_none() && !self.config.toc.front_matter && self.is_front_matter(path) {
+            chapter.toc = Some(false);
+        }
+        chapter
+    }
+
+    /// Whether `path` is front matter: listed in `[chapters] frontmatter` or
+    /// a chapter numbered 0, like `ch00_preface.md`
+    fn is_front_matter(&self, path: &Path) -> bool {
+        self.project.frontmatter.iter().any(|file| file == path)
+            || self
+                .project
+                .chapters
+                .iter()
+                .any(|ch| ch.number == 0 && ch.path == path)
+    }
+
+    /// Page numbering of the `[chapters]` front and back matter
+    ///
+    /// Front matter is numbered with `frontmatter_pages` from 1, and the
+    /// first chapter after it restarts at 1 in the document's own format.
+    /// Back matter only gets its own numbering with `backmatter_pages`.
+    fn matter_numbering(&self, path: &Path) -> ChapterOverride {
+        let project = &self.project;
+        let config = &self.config.chapters;
+        let restart_at = |files: &[PathBuf]| files.first().filter(|f| *f == path).map(|_| 1);
+        if project.frontmatter.iter().any(|file| file == path) {
+            return ChapterOverride {
+                restart_numbering: restart_at(&project.frontmatter),
+                page_format: Some(config.frontmatter_pages.clone()),
+                ..Default::default()
+            };
+        }
+        if let Some(format) = &config.backmatter_pages {
+            if project.backmatter.iter().any(|file| file == path) {
+                return ChapterOverride {
+                    restart_numbering: restart_at(&project.backmatter),
+                    page_format: Some(format.clone()),
+                    ..Default::default()
+                };
+            }
+        }
+        let first_chapter = project.chapters.first().map(|ch| ch.path.as_path());
+        if !project.frontmatter.is_empty() && first_chapter == Some(path) {
+            return ChapterOverride {
+                restart_numbering: Some(1),
+                ..Default::default()
+            };
+        }
+        ChapterOverride::default()
+    }
+
+    /// Document date for covers and headers, in the language's date format
+    /// Document language from the config, English if it is unknown
+    fn language(&self) -> Language {
+        Language::from_code(self.config.language()).unwrap_or_else(|| {
+            crate::diagnostics::warn(
+                WarningCategory::Other,
+                format!(
+                    "Unknown language '{}', using English",
+                    self.config.language()
+                ),
+            );
+            Language::English
+        })
+    }
+
+    fn display_date(&self) -> String {
+        #[cfg(feature = "cli")]
+        let date = self.config.date();
+        #[cfg(not(feature = "cli"))]
+        let date = self.config.document.date.clone();
+        let lang = Language::from_code(self.config.language()).unwrap_or_default();
+        crate::i18n::format_date(&date, lang, self.config.i18n.date_format.as_deref())
     }
 
     fn build_placeholder_context(&self) -> PlaceholderContext {
@@ -260,12 +1239,31 @@ impl ProjectBuilder {
         ctx.set("title", &self.config.document.title);
         ctx.set("subtitle", &self.config.document.subtitle);
         ctx.set("author", &self.config.document.author);
-        #[cfg(feature = "cli")]
-        ctx.set("date", self.config.date());
-        #[cfg(not(feature = "cli"))]
-        ctx.set("date", &self.config.document.date);
+        ctx.set("date", self.display_date());
         ctx.set("version", &self.config.document.version);
 
+        // Outside a repository, or without files on disk, the git
+        // placeholders are left empty
+        #[cfg(feature = "git")]
+        {
+            let repo = match self.sources {
+                Some(_) => Default::default(),
+                None => git::repo_info(&self.base_dir).unwrap_or_default(),
+            };
+            for (key, value) in repo.placeholders() {
+                ctx.set(key, value);
+            }
+        }
+
+        // {{chapter_1_title}}, {{chapter_1_author}}, ... from the chapter index;
+        // unreadable chapters are reported when the build reads them
+        for chapter in self.chapter_index().unwrap_or_default() {
+            let key = |field: &str| format!("chapter_{}_{}", chapter.number, field);
+            ctx.set(&key("title"), &chapter.title);
+            ctx.set(&key("author"), chapter.author.clone().unwrap_or_default());
+            ctx.set(&key("summary"), chapter.summary.clone().unwrap_or_default());
+        }
+
         // Pass user-defined extra variables from [document] section
         for (key, value) in self.config.document.extra_as_strings() {
             ctx.set(&key, value);
@@ -278,7 +1276,16 @@ impl ProjectBuilder {
             .map(|t| t.has_cover())
             .unwrap_or(false)
         {
-            if let Some(inside) = extract_cover_inside_content(&self.base_dir) {
+            let inside = match (&self.sources, &self.project.cover) {
+                (Some(_), Some(cover)) => self
+                    .read_file(cover)
+                    .ok()
+                    .map(|content| strip_frontmatter(&content).trim().to_string())
+                    .filter(|inside| !inside.is_empty()),
+                (Some(_), None) => None,
+                (None, _) => extract_cover_inside_content(&self.base_dir),
+            };
+            if let Some(inside) = inside {
                 ctx = ctx.with_custom("inside", inside);
             }
         }
@@ -286,21 +1293,331 @@ impl ProjectBuilder {
         ctx
     }
 
-    fn build_document_config(&self, first_content_dir: Option<PathBuf>) -> DocumentConfig {
-        let template_loaded = self.templates.is_some();
+    /// `[numbering]` formats, with unknown formats replaced by decimal
+    fn build_numbering_config(&self) -> NumberingConfig {
+        let known_format = |format: &String, what: &str| {
+            if let Some(known) = ORDERED_FORMATS
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(format))
+            {
+                return known.to_string();
+            }
+            crate::diagnostics::warn(
+                WarningCategory::Other,
+                format!(
+                    "Unknown {} number format '{}' (expected one of: {})",
+                    what,
+                    format,
+                    ORDERED_FORMATS.join(", ")
+                ),
+            );
+            "decimal".to_string()
+        };
+        NumberingConfig {
+            ordered: self
+                .config
+                .numbering
+                .ordered
+                .iter()
+                .map(|format| known_format(format, "list"))
+                .collect(),
+            bullets: self.config.numbering.bullets.clone(),
+            pages: self
+                .config
+                .numbering
+                .pages
+                .as_ref()
+                .map(|format| known_format(format, "page")),
+        }
+    }
 
-        // Load header/footer template if available
-        let header_footer_template = if let Some(ref template_dir) = self.config.template.dir {
-            let hf_path = self.base_dir.join(template_dir).join("header-footer.docx");
-            if hf_path.exists() {
-                crate::template::extract::header_footer::extract(&hf_path).ok()
-            } else {
-                None
+    /// `[footnotes]` formats and restart, with unknown values left at Word's defaults
+    fn build_note_numbering(&self) -> NoteNumbering {
+        let section = &self.config.footnotes;
+        let known_format = |format: &Option<String>, key: &str| {
+            let format = format.as_deref()?;
+            if format.eq_ignore_ascii_case("symbols") {
+                return Some("chicago".to_string());
+            }
+            let known = ORDERED_FORMATS
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(format));
+            if known.is_none() {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Unknown footnotes.{} '{}' (expected symbols or one of: {})",
+                        key,
+                        format,
+                        ORDERED_FORMATS.join(", ")
+                    ),
+                );
+            }
+            known.map(|known| known.to_string())
+        };
+        let restart = match section.restart.as_deref() {
+            None => NoteRestart::Continuous,
+            Some(name) => NoteRestart::from_name(name).unwrap_or_else(|| {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Unknown footnotes.restart '{}' (expected continuous, section or page)",
+                        name
+                    ),
+                );
+                NoteRestart::Continuous
+            }),
+        };
+        NoteNumbering {
+            footnotes: known_format(&section.format, "format"),
+            endnotes: known_format(&section.endnote_format, "endnote_format"),
+            restart,
+            separator: section.separator.clone(),
+        }
+    }
+
+    /// `[document] paragraph_preset`, the built-in layout when it is unknown
+    fn paragraph_preset(&self) -> ParagraphPreset {
+        let name = &self.config.document.paragraph_preset;
+        ParagraphPreset::from_name(name).unwrap_or_else(|| {
+            crate::diagnostics::warn(
+                WarningCategory::Other,
+                format!(
+                    "Unknown document.paragraph_preset '{}' (expected default or thai-official)",
+                    name
+                ),
+            );
+            ParagraphPreset::Default
+        })
+    }
+
+    /// `[document] spacers`, empty paragraphs when it is unknown
+    fn spacers(&self) -> Spacers {
+        let name = &self.config.document.spacers;
+        Spacers::from_name(name).unwrap_or_else(|| {
+            crate::diagnostics::warn(
+                WarningCategory::Other,
+                format!(
+                    "Unknown document.spacers '{}' (expected blank, spacing or none)",
+                    name
+                ),
+            );
+            Spacers::Blank
+        })
+    }
+
+    /// `[styles]` spacing, converted to twips and 240ths of a line
+    fn build_style_spacing(&self) -> HashMap<String, StyleSpacing> {
+        let twips = |points: Option<f32>| points.map(|pt| (pt.max(0.0) * 20.0).round() as u32);
+        self.config
+            .styles
+            .iter()
+            .map(|(id, style)| {
+                let line = style.line_spacing.filter(|&lines| {
+                    if lines > 0.0 {
+                        return true;
+                    }
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        format!("[styles.{}] line_spacing must be positive; ignored", id),
+                    );
+                    false
+                });
+                let spacing = StyleSpacing {
+                    before: twips(style.space_before),
+                    after: twips(style.space_after),
+                    line: line.map(|lines| (lines * 240.0).round() as u32),
+                };
+                (id.clone(), spacing)
+            })
+            .collect()
+    }
+
+    /// `[i18n.strings]` overrides, skipping unknown keys
+    fn build_string_overrides(&self) -> HashMap<Label, String> {
+        let mut overrides = HashMap::new();
+        for (key, text) in &self.config.i18n.strings {
+            match Label::from_key(key) {
+                Some(label) => {
+                    overrides.insert(label, text.clone());
+                }
+                None => crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Unknown [i18n.strings] key '{}' (expected one of: {})",
+                        key,
+                        Label::ALL.map(|label| label.key()).join(", ")
+                    ),
+                ),
+            }
+        }
+        overrides
+    }
+
+    /// Smart punctuation rules from `[i18n]`, if enabled
+    fn build_punctuation(&self, lang: Language) -> Option<Punctuation> {
+        let i18n = &self.config.i18n;
+        if !i18n.smart_punctuation {
+            return None;
+        }
+        let mut punctuation = Punctuation::for_language(lang);
+        if let Some(ref quotes) = i18n.quotes {
+            match punctuation.with_quotes(quotes) {
+                Some(custom) => punctuation = custom,
+                None => crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Invalid i18n.quotes '{}' (expected 2 or 4 quote characters)",
+                        quotes
+                    ),
+                ),
+            }
+        }
+        if let Some(ref spacing) = i18n.punctuation_spacing {
+            match PunctuationSpacing::from_name(spacing) {
+                Some(spacing) => punctuation = punctuation.with_spacing(spacing),
+                None => crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Unknown i18n.punctuation_spacing '{}' (expected none or narrow)",
+                        spacing
+                    ),
+                ),
+            }
+        }
+        Some(punctuation)
+    }
+
+    /// Put the `logo` of a `[header]` or `[footer]` section in front of its slot
+    fn place_logo(
+        &self,
+        name: &str,
+        section: &HeaderFooterSection,
+        slots: &mut [Vec<HeaderFooterField>; 3],
+    ) {
+        let Some(logo) = &section.logo else {
+            return;
+        };
+        let position = section.logo_position.as_deref().unwrap_or("left");
+        let index = match position {
+            "left" => 0,
+            "center" => 1,
+            "right" => 2,
+            _ => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Unknown {}.logo_position '{}' (expected left, center or right)",
+                        name, position
+                    ),
+                );
+                0
+            }
+        };
+        let length = |key: &str, value: &Option<String>| {
+            let value = value.as_deref()?;
+            let twips = crate::docx::parse_length_to_twips(value);
+            if twips.is_none() {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!("Invalid {}.{} value '{}'", name, key, value),
+                );
             }
+            twips
+        };
+        let width = length("logo_width", &section.logo_width);
+        let height = length("logo_height", &section.logo_height);
+
+        let path = self.base_dir.join(logo);
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let image = std::fs::read(&path)
+            .ok()
+            .and_then(|data| HeaderFooterImage::new(&filename, data, width, height));
+        match image {
+            Some(image) => slots[index].insert(0, HeaderFooterField::Image(image)),
+            None => crate::diagnostics::warn(
+                WarningCategory::MissingImage,
+                format!("Cannot read {}.logo image '{}'", name, path.display()),
+            ),
+        }
+    }
+
+    fn build_toc_config(&self, enabled: bool, strings: &Strings) -> TocConfig {
+        let toc = &self.config.toc;
+        let indents = toc
+            .indents
+            .iter()
+            .map(|indent| {
+                let twips = crate::docx::parse_length_to_twips(indent);
+                if twips.is_none() {
+                    crate::diagnostics::warn(
+                        WarningCategory::Other,
+                        format!("Invalid toc.indents value '{}'", indent),
+                    );
+                }
+                twips
+            })
+            .collect();
+        let page_numbers = match toc.page_numbers.as_str() {
+            "inline" => TocPageNumbers::Inline,
+            "none" => TocPageNumbers::None,
+            "right" => TocPageNumbers::Right,
+            other => {
+                crate::diagnostics::warn(
+                    WarningCategory::Other,
+                    format!(
+                        "Unknown toc.page_numbers '{}' (expected right, inline or none)",
+                        other
+                    ),
+                );
+                TocPageNumbers::Right
+            }
+        };
+
+        // The default English title follows the document language and [i18n.strings]
+        let title = if toc.title == Language::English.toc_title() {
+            strings.get(Label::TableOfContents).to_string()
         } else {
-            None
+            toc.title.clone()
         };
 
+        TocConfig {
+            enabled,
+            depth: toc.depth,
+            title,
+            after_cover: toc.after_cover,
+            include_styles: toc
+                .include_styles
+                .iter()
+                .map(|(style, level)| (style.clone(), *level))
+                .collect(),
+            exclude_styles: toc.exclude_styles.clone(),
+            indents,
+            dot_leader: toc.dot_leader,
+            page_numbers,
+            hyperlinks: toc.hyperlinks,
+        }
+    }
+
+    fn build_document_config(
+        &self,
+        first_content_dir: Option<PathBuf>,
+        placeholder_ctx: &PlaceholderContext,
+    ) -> Result<DocumentConfig> {
+        let template_loaded = self.templates.is_some();
+        let string_overrides = self.build_string_overrides();
+        let lang = Language::from_code(self.config.language()).unwrap_or_default();
+        let strings = Strings::new(lang).with_overrides(string_overrides.clone());
+
+        // Header/footer template, if the template directory has one
+        let header_footer_template = self
+            .templates
+            .as_ref()
+            .and_then(|templates| templates.header_footer.clone());
+
         // Build font config
         let fonts = Some(crate::docx::ooxml::FontConfig {
             default: if self.config.fonts.default.is_empty() {
@@ -366,14 +1683,7 @@ impl ProjectBuilder {
 
         // Prepare embedded fonts if enabled
         let embedded_fonts = if self.config.fonts.embed {
-            let font_dir = if let Some(ref embed_dir) = self.config.fonts.embed_dir {
-                self.base_dir.join(embed_dir)
-            } else if let Some(ref template_dir) = self.config.template.dir {
-                // Default: look for fonts in template/fonts/
-                self.base_dir.join(template_dir).join("fonts")
-            } else {
-                self.base_dir.join("fonts")
-            };
+            let font_dir = font_dir(&self.base_dir, &self.config);
 
             if font_dir.exists() {
                 // Collect font names to embed: only fonts actually used (default + code)
@@ -387,18 +1697,18 @@ impl ProjectBuilder {
 
                 // Only embed fonts that are explicitly configured as used
                 if font_names.is_empty() {
-                    eprintln!(
-                        "Warning: Font embedding enabled but no fonts configured (fonts.default / fonts.code). Skipping embed."
+                    crate::diagnostics::warn(
+                        WarningCategory::Font,
+                        "Font embedding enabled but no fonts configured (fonts.default / fonts.code). Skipping embed.",
                     );
                     Vec::new()
                 } else {
-                    crate::docx::font_embed::prepare_embedded_fonts(&font_dir, &font_names)
-                        .unwrap_or_default()
+                    crate::docx::font_embed::prepare_embedded_fonts(&font_dir, &font_names)?
                 }
             } else {
-                eprintln!(
-                    "Warning: Font embed directory not found: {}",
-                    font_dir.display()
+                crate::diagnostics::warn(
+                    WarningCategory::Font,
+                    format!("Font embed directory not found: {}", font_dir.display()),
                 );
                 Vec::new()
             }
@@ -406,23 +1716,62 @@ impl ProjectBuilder {
             Vec::new()
         };
 
-        DocumentConfig {
+        // Header/footer text from [header] / [footer]
+        let slot = |text: &Option<String>| {
+            let text = text.as_deref().unwrap_or_default();
+            HeaderFooterField::parse_template_localized(text, &strings)
+                .into_iter()
+                .map(|field| match field {
+                    HeaderFooterField::Text(t) => {
+                        HeaderFooterField::Text(replace_placeholders(&t, placeholder_ctx))
+                    }
+                    field => field,
+                })
+                .collect::<Vec<_>>()
+        };
+        let slots = |name: &str, section: &HeaderFooterSection| {
+            let mut slots = [
+                slot(&section.left),
+                slot(&section.center),
+                slot(&section.right),
+            ];
+            self.place_logo(name, section, &mut slots);
+            slots
+        };
+        let header = &self.config.header;
+        let header = if header.is_set() {
+            let [left, center, right] = slots("header", header);
+            HeaderConfig {
+                left,
+                center,
+                right,
+            }
+        } else {
+            HeaderConfig::default()
+        };
+        let footer = &self.config.footer;
+        let footer = if footer.is_set() {
+            let [left, center, right] = slots("footer", footer);
+            FooterConfig {
+                left,
+                center,
+                right,
+            }
+        } else {
+            FooterConfig::default()
+        };
+
+        Ok(DocumentConfig {
             title: self.config.document.title.clone(),
-            toc: crate::docx::toc::TocConfig {
-                enabled: toc_enabled,
-                depth: self.config.toc.depth,
-                title: self.config.toc.title.clone(),
-                after_cover: self.config.toc.after_cover,
-            },
+            header,
+            footer,
+            toc: self.build_toc_config(toc_enabled, &strings),
             header_footer_template,
             document_meta: Some(crate::DocumentMeta {
                 title: self.config.document.title.clone(),
                 subtitle: self.config.document.subtitle.clone(),
                 author: self.config.document.author.clone(),
-                #[cfg(feature = "cli")]
-                date: self.config.date(),
-                #[cfg(not(feature = "cli"))]
-                date: self.config.document.date.clone(),
+                date: self.display_date(),
             }),
             fonts,
             template_dir: self
@@ -435,6 +1784,7 @@ impl ProjectBuilder {
             base_path: first_content_dir,
             page: page_config,
             embedded_fonts,
+            subset_fonts: self.config.fonts.subset,
             mermaid_spacing: {
                 let before = self.config.mermaid.spacing_before.parse::<u32>().unwrap_or(120);
                 let after = self.config.mermaid.spacing_after.parse::<u32>().unwrap_or(120);
@@ -445,7 +1795,45 @@ impl ProjectBuilder {
             math_renderer: self.config.math.renderer.clone(),
             math_font_size: self.config.math.font_size.clone(),
             math_number_all: self.config.math.number_all,
+            chapter_break: self.config.chapters.chapter_break.clone(),
+            h1_page_break: self.config.chapters.h1_page_break,
+            h2_keep_with_next: self.config.chapters.h2_keep_with_next,
+            widow_control: self.config.pagination.widow_control,
+            table_rows_cant_split: self.config.pagination.table_rows_cant_split,
+            repeat_table_header: self.config.pagination.repeat_table_header,
+            table_continuation_rows: self.config.pagination.table_continuation_rows,
+            code_continuation_lines: self.config.pagination.code_continuation_lines,
+            watermark: self.config.output.watermark.clone(),
+            numbering: self.build_numbering_config(),
+            notes: self.build_note_numbering(),
+            style_spacing: self.build_style_spacing(),
+            paragraph_preset: self.paragraph_preset(),
+            spacers: self.spacers(),
+            style_gallery: StyleGallery {
+                styles: self.config.gallery.styles.clone(),
+                hide_builtin: self.config.gallery.hide_builtin,
+                lock_builtin: self.config.gallery.lock_builtin,
+            },
+            string_overrides,
+            punctuation: self.build_punctuation(lang),
+            compression: crate::docx::Compression {
+                level: self.config.output.compression_level,
+                store_media: self.config.output.store_media,
+            },
+            image_limits: crate::docx::ImageLimits {
+                max_dpi: self.config.images.max_dpi,
+                max_width: self.config.images.max_width,
+                jpeg_quality: self.config.images.jpeg_quality,
+            },
+            memory_budget: self
+                .config
+                .output
+                .memory_budget_mb
+                .map(|mb| crate::docx::MemoryBudget::new(mb * 1024 * 1024)),
+            reproducible: self.config.output.reproducible,
+            audit_rels: self.config.output.audit_rels,
+            files: self.files.clone(),
             ..DocumentConfig::default()
-        }
+        })
     }
 }
@@ -0,0 +1,122 @@
+//! Machine-readable build reports (`md2docx build --format json`)
+
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::diagnostics::Warning;
+
+/// Time spent in one build stage
+#[derive(Debug, Clone, Serialize)]
+pub struct StageTiming {
+    /// Stage name (`load`, `combine`, `parse`, `build`, `package`, `write`)
+    pub stage: String,
+    /// Duration in milliseconds
+    pub millis: u64,
+}
+
+impl StageTiming {
+    pub fn new(stage: &str, duration: Duration) -> Self {
+        log::debug!("Stage {} took {:.1?}", stage, duration);
+        Self {
+            stage: stage.to_string(),
+            millis: duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Summary of a successful build
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildReport {
+    /// Path of the written DOCX file
+    pub output: PathBuf,
+    /// Size of the DOCX file in bytes
+    pub size_bytes: u64,
+    /// Number of embedded images (files under `word/media/`)
+    pub images: usize,
+    /// Warnings reported while building
+    pub warnings: Vec<Warning>,
+    /// Per-stage timings, in build order
+    pub timings: Vec<StageTiming>,
+    /// Total build time in milliseconds
+    pub total_millis: u64,
+}
+
+impl BuildReport {
+    /// Create a report for the DOCX bytes written to `output`
+    pub fn new(
+        output: PathBuf,
+        docx: &[u8],
+        warnings: Vec<Warning>,
+        timings: Vec<StageTiming>,
+    ) -> Self {
+        let total_millis = timings.iter().map(|t| t.millis).sum();
+        Self {
+            output,
+            size_bytes: docx.len() as u64,
+            images: count_media(docx),
+            warnings,
+            timings,
+            total_millis,
+        }
+    }
+}
+
+/// Count the media parts in a DOCX package
+fn count_media(docx: &[u8]) -> usize {
+    zip::ZipArchive::new(Cursor::new(docx))
+        .map(|archive| {
+            archive
+                .file_names()
+                .filter(|name| name.starts_with("word/media/"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::WarningCategory;
+
+    fn package(names: &[&str]) -> Vec<u8> {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for name in names {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"x").unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_report_counts_images_and_time() {
+        let docx = package(&[
+            "word/document.xml",
+            "word/media/image1.png",
+            "word/media/image2.svg",
+        ]);
+
+        let report = BuildReport::new(
+            PathBuf::from("out.docx"),
+            &docx,
+            vec![Warning::new(WarningCategory::Diagram, "something odd")],
+            vec![
+                StageTiming::new("render", Duration::from_millis(12)),
+                StageTiming::new("write", Duration::from_millis(3)),
+            ],
+        );
+
+        assert_eq!(report.images, 2);
+        assert_eq!(report.size_bytes, docx.len() as u64);
+        assert_eq!(report.total_millis, 15);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["timings"][0]["stage"], "render");
+        assert_eq!(json["warnings"][0]["category"], "diagram");
+        assert_eq!(json["warnings"][0]["message"], "something odd");
+    }
+}
@@ -0,0 +1,268 @@
+//! Project scaffolding helpers (`md2docx new ...`)
+
+use std::path::{Path, PathBuf};
+
+use crate::config::ProjectConfig;
+use crate::discovery::DiscoveredProject;
+use crate::{Error, Result};
+
+/// Options for creating a new chapter file
+#[derive(Debug, Clone, Default)]
+pub struct NewChapter {
+    /// Chapter title (written to frontmatter and as the H1 heading)
+    pub title: String,
+    /// Name part of the filename; derived from the title when `None`
+    pub slug: Option<String>,
+    /// Explicit chapter number; defaults to the next number after the last chapter
+    pub number: Option<u32>,
+}
+
+/// Create the next numbered chapter file in a project directory
+///
+/// The filename follows `chapters.pattern` from the config: the first `*`
+/// is replaced by the zero-padded chapter number and the second `*` by the
+/// slug (e.g. `ch*_*.md` -> `ch04_getting_started.md`). The file starts with
+/// frontmatter pre-filled from `[document]` (author) and today's date.
+///
+/// Projects that list their chapters in `chapters.files` get the new file
+/// appended to that list in `md2docx.toml`, keeping the file's comments and
+/// formatting. When the list comes from elsewhere (a template or `extends`
+/// config) a warning asks to add it by hand.
+///
+/// Returns the path of the created file. Fails if the file already exists.
+pub fn new_chapter(base_dir: &Path, config: &ProjectConfig, opts: &NewChapter) -> Result<PathBuf> {
+    let number = match opts.number {
+        Some(n) => n,
+        None => {
+            let project = DiscoveredProject::discover_with_config(base_dir, config)?;
+            project.chapters.iter().map(|c| c.number).max().unwrap_or(0) + 1
+        }
+    };
+
+    let slug = match opts.slug {
+        Some(ref s) => slugify(s),
+        None => slugify(&opts.title),
+    };
+
+    let filename = chapter_filename(&config.chapters.pattern, number, &slug);
+    let path = base_dir.join(&filename);
+    if path.exists() {
+        return Err(Error::Config(format!(
+            "Chapter file already exists: {}",
+            path.display()
+        )));
+    }
+
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let content = chapter_template(&opts.title, &config.document.author, &date);
+    std::fs::write(&path, content)?;
+
+    if !config.chapters.files.is_empty() && !append_to_chapter_list(base_dir, &filename)? {
+        log::warn!(
+            "{} is not listed in [chapters] files of {}; add it there to include it in the build",
+            filename,
+            base_dir.join("md2docx.toml").display()
+        );
+    }
+
+    Ok(path)
+}
+
+/// Append `filename` to `[chapters] files` in the project's `md2docx.toml`
+///
+/// Returns `false` when the file has no such list to extend.
+fn append_to_chapter_list(base_dir: &Path, filename: &str) -> Result<bool> {
+    let config_path = base_dir.join("md2docx.toml");
+    if !config_path.exists() {
+        return Ok(false);
+    }
+
+    let source = std::fs::read_to_string(&config_path)?;
+    let mut doc: toml_edit::DocumentMut = source
+        .parse()
+        .map_err(|e| Error::Config(format!("Failed to parse {}: {}", config_path.display(), e)))?;
+    let Some(files) = doc
+        .get_mut("chapters")
+        .and_then(|chapters| chapters.get_mut("files"))
+        .and_then(|files| files.as_array_mut())
+    else {
+        return Ok(false);
+    };
+
+    files.push(filename);
+    std::fs::write(&config_path, doc.to_string())?;
+    Ok(true)
+}
+
+/// Expand a chapter pattern like `ch*_*.md` into a concrete filename
+///
+/// Patterns without wildcards fall back to the default `ch##_name.md` layout.
+fn chapter_filename(pattern: &str, number: u32, slug: &str) -> String {
+    let pattern = if pattern.contains('*') {
+        pattern
+    } else {
+        "ch*_*.md"
+    };
+
+    let mut result = String::with_capacity(pattern.len() + slug.len() + 2);
+    let mut wildcard_index = 0;
+    for c in pattern.chars() {
+        if c == '*' {
+            match wildcard_index {
+                0 => result.push_str(&format!("{:02}", number)),
+                _ => result.push_str(slug),
+            }
+            wildcard_index += 1;
+        } else {
+            result.push(c);
+        }
+    }
+
+    // Single-wildcard patterns (e.g. "ch*.md") still need the slug
+    if wildcard_index == 1 {
+        if let Some(stem) = result.strip_suffix(".md") {
+            result = format!("{}_{}.md", stem, slug);
+        }
+    }
+
+    result
+}
+
+/// Convert a title into a filename-safe slug
+///
+/// ASCII letters are lowercased and other ASCII characters collapse into a
+/// single `_`. Non-ASCII characters (e.g. Thai) are kept as-is.
+fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    let mut pending_sep = false;
+
+    for c in title.trim().chars() {
+        if c.is_ascii_alphanumeric() || !c.is_ascii() {
+            if pending_sep && !slug.is_empty() {
+                slug.push('_');
+            }
+            pending_sep = false;
+            slug.push(c.to_ascii_lowercase());
+        } else {
+            pending_sep = true;
+        }
+    }
+
+    if slug.is_empty() {
+        "chapter".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Render the initial content of a new chapter file
+fn chapter_template(title: &str, author: &str, date: &str) -> String {
+    let mut content = String::from("---\n");
+    content.push_str(&format!("title: \"{}\"\n", title.replace('"', "\\\"")));
+    if !author.is_empty() {
+        content.push_str(&format!("author: \"{}\"\n", author.replace('"', "\\\"")));
+    }
+    content.push_str(&format!("date: {}\n", date));
+    content.push_str("---\n\n");
+    content.push_str(&format!("# {}\n\n", title));
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Getting Started"), "getting_started");
+        assert_eq!(slugify("  API: v2 / Usage  "), "api_v2_usage");
+        assert_eq!(slugify("บทนำ"), "บทนำ");
+        assert_eq!(slugify("!!!"), "chapter");
+    }
+
+    #[test]
+    fn test_chapter_filename() {
+        assert_eq!(chapter_filename("ch*_*.md", 4, "setup"), "ch04_setup.md");
+        assert_eq!(chapter_filename("ch*.md", 12, "setup"), "ch12_setup.md");
+        assert_eq!(chapter_filename("", 1, "intro"), "ch01_intro.md");
+    }
+
+    #[test]
+    fn test_new_chapter_next_number() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("ch01_intro.md"), "# Intro").unwrap();
+        std::fs::write(temp_dir.path().join("ch03_usage.md"), "# Usage").unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.document.author = "Team".to_string();
+
+        let path = new_chapter(
+            temp_dir.path(),
+            &config,
+            &NewChapter {
+                title: "Advanced Topics".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            path.file_name().unwrap().to_str().unwrap(),
+            "ch04_advanced_topics.md"
+        );
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("---\ntitle: \"Advanced Topics\"\nauthor: \"Team\"\n"));
+        assert!(content.contains("# Advanced Topics"));
+
+        let (fm, _) = crate::parser::parse_frontmatter(&content);
+        assert_eq!(fm.unwrap().title, Some("Advanced Topics".to_string()));
+    }
+
+    #[test]
+    fn test_new_chapter_appends_to_file_list() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("intro.md"), "# Intro").unwrap();
+        std::fs::write(temp_dir.path().join("setup.md"), "# Setup").unwrap();
+        let toml =
+            "# Book layout\n[chapters]\nfiles = [\"intro.md\", \"setup.md\"]  # reading order\n";
+        std::fs::write(temp_dir.path().join("md2docx.toml"), toml).unwrap();
+
+        let config = crate::project::load_project_config(temp_dir.path()).unwrap();
+        let path = new_chapter(
+            temp_dir.path(),
+            &config,
+            &NewChapter {
+                title: "Usage".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(path.file_name().unwrap().to_str().unwrap(), "ch03_usage.md");
+
+        let updated = std::fs::read_to_string(temp_dir.path().join("md2docx.toml")).unwrap();
+        assert_eq!(
+            updated,
+            "# Book layout\n[chapters]\nfiles = [\"intro.md\", \"setup.md\", \"ch03_usage.md\"]  # reading order\n"
+        );
+
+        let config = crate::project::load_project_config(temp_dir.path()).unwrap();
+        let project = DiscoveredProject::discover_with_config(temp_dir.path(), &config).unwrap();
+        assert_eq!(project.chapters.len(), 3);
+        assert_eq!(project.chapters[2].path, path);
+    }
+
+    #[test]
+    fn test_new_chapter_refuses_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("ch01_intro.md"), "# Intro").unwrap();
+
+        let opts = NewChapter {
+            title: "Intro".to_string(),
+            number: Some(1),
+            ..Default::default()
+        };
+        let result = new_chapter(temp_dir.path(), &ProjectConfig::default(), &opts);
+        assert!(result.is_err());
+    }
+}
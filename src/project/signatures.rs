@@ -0,0 +1,125 @@
+//! Approval table (`{!signatures}`)
+//!
+//! Thai corporate and government documents close with a block where the
+//! people who prepared, reviewed and approved them sign. A `{!signatures}`
+//! line becomes a table with one column per signer from `[signatures]` and
+//! rows for the signature, name, position and date; blank values are left
+//! as dotted lines to fill in by hand.
+
+use super::metadata::expand_directive_lines;
+use crate::config::{SignaturesSection, Signer};
+use crate::i18n::{Label, Strings};
+
+/// Line replaced by the approval table
+pub const SIGNATURES_DIRECTIVE: &str = "{!signatures}";
+
+/// Dotted line standing for a value written by hand
+const BLANK: &str = "........................";
+
+/// Roles of the first signers without a `role`
+const DEFAULT_ROLES: [Label; 3] = [Label::PreparedBy, Label::ReviewedBy, Label::ApprovedBy];
+
+/// Markdown table of the signers, or of three blank columns without any
+pub fn signatures_table(section: &SignaturesSection, strings: &Strings) -> String {
+    let blank = [Signer::default(), Signer::default(), Signer::default()];
+    let signers = if section.signers.is_empty() {
+        &blank[..]
+    } else {
+        &section.signers[..]
+    };
+    let cell = |text: &str| text.replace('|', "\\|");
+
+    let header: Vec<String> = signers
+        .iter()
+        .enumerate()
+        .map(|(i, signer)| match &signer.role {
+            Some(role) => cell(role),
+            None => DEFAULT_ROLES
+                .get(i)
+                .map(|&label| strings.get(label).to_string())
+                .unwrap_or_default(),
+        })
+        .collect();
+    let mut markdown = format!(
+        "| {} |\n|{}\n",
+        header.join(" | "),
+        "---|".repeat(header.len())
+    );
+
+    let rows = [
+        Label::Signature,
+        Label::SignerName,
+        Label::SignerPosition,
+        Label::RevisionDate,
+    ];
+    for label in rows {
+        let row: Vec<String> = signers
+            .iter()
+            .map(|signer| {
+                let value = match label {
+                    Label::SignerName => signer.name.trim(),
+                    Label::SignerPosition => signer.position.trim(),
+                    Label::RevisionDate => signer.date.trim(),
+                    // Left blank to sign on
+                    _ => "",
+                };
+                let value = if value.is_empty() { BLANK } else { value };
+                format!("{}: {}", strings.get(label), cell(value))
+            })
+            .collect();
+        markdown.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    markdown
+}
+
+/// Replace `{!signatures}` lines outside code blocks with `table`
+pub fn expand_signatures(markdown: &str, table: &str) -> String {
+    expand_directive_lines(markdown, SIGNATURES_DIRECTIVE, table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    #[test]
+    fn test_signatures_table() {
+        let strings = Strings::new(Language::Thai);
+        let table = signatures_table(&SignaturesSection::default(), &strings);
+        assert!(table.starts_with("| ผู้จัดทำ | ผู้ตรวจสอบ | ผู้อนุมัติ |\n|---|---|---|\n"));
+        assert!(table.contains(&format!("| ลงชื่อ: {BLANK} |")));
+        assert_eq!(table.lines().count(), 6);
+
+        let section = SignaturesSection {
+            signers: vec![
+                Signer {
+                    name: "Somchai".to_string(),
+                    position: "Engineer | QA".to_string(),
+                    ..Default::default()
+                },
+                Signer {
+                    role: Some("Checked by".to_string()),
+                    date: "1 May 2025".to_string(),
+                    ..Default::default()
+                },
+            ],
+        };
+        let table = signatures_table(&section, &Strings::new(Language::English));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "| Prepared by | Checked by |");
+        assert_eq!(lines[3], format!("| Name: Somchai | Name: {BLANK} |"));
+        assert_eq!(
+            lines[4],
+            format!("| Position: Engineer \\| QA | Position: {BLANK} |")
+        );
+        assert_eq!(lines[5], format!("| Date: {BLANK} | Date: 1 May 2025 |"));
+    }
+
+    #[test]
+    fn test_expand_signatures() {
+        let markdown = "Text\n\n{!signatures}\n\n```\n{!signatures}\n```\n";
+        let expanded = expand_signatures(markdown, "| A |\n|---|\n");
+        assert!(expanded.contains("\n| A |\n|---|\n"));
+        assert!(expanded.contains("```\n{!signatures}\n```"));
+    }
+}
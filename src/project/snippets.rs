@@ -0,0 +1,233 @@
+//! Reusable markdown fragments (`{!snippet:name}`)
+//!
+//! Snippets come from `snippets/<name>.md` files in the project root and
+//! from the `[snippets]` config table, which wins over a file of the same
+//! name. `{!snippet:warning(text="High voltage")}` expands the `warning`
+//! snippet with its `{{text}}` placeholders replaced by "High voltage".
+//! Snippets may use other snippets.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::check::INLINE_CODE_PATTERN;
+use super::markdown::strip_frontmatter;
+use crate::diagnostics::{warn, WarningCategory};
+use crate::error::{Error, Result};
+
+/// Directory of the project root holding `<name>.md` snippets
+pub const SNIPPETS_DIR: &str = "snippets";
+
+/// Prefix of the snippet directive
+pub const SNIPPET_DIRECTIVE: &str = "{!snippet:";
+
+static SNIPPET_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\{!snippet:([\w-]+)(?:\(((?:"[^"]*"|'[^']*'|[^)"'])*)\))?\}"#)
+        .expect("Invalid regex")
+});
+static PARAM_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([\w-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^,\s]+))"#).expect("Invalid regex")
+});
+
+/// Snippets of a project, by name
+pub fn load_snippets(
+    base_dir: &Path,
+    configured: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut snippets = BTreeMap::new();
+    let dir = base_dir.join(SNIPPETS_DIR);
+    if dir.is_dir() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                let content = std::fs::read_to_string(&path)?;
+                snippets.insert(name.to_string(), strip_frontmatter(&content));
+            }
+        }
+    }
+    snippets.extend(configured.iter().map(|(k, v)| (k.clone(), v.clone())));
+    Ok(snippets)
+}
+
+/// Replace `{!snippet:...}` directives outside code with their snippets
+///
+/// Unknown snippets are left as they are with a warning; a snippet that
+/// ends up using itself is an error.
+pub fn expand_snippets(markdown: &str, snippets: &BTreeMap<String, String>) -> Result<String> {
+    expand(markdown, snippets, &mut Vec::new())
+}
+
+/// Line numbers and names of directives that use an undefined snippet
+pub fn unknown_snippets(
+    content: &str,
+    snippets: &BTreeMap<String, String>,
+) -> Vec<(usize, String)> {
+    let mut unknown = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (idx, line) in content.lines().enumerate() {
+        if !in_prose(line, &mut fence) {
+            continue;
+        }
+        let line = INLINE_CODE_PATTERN.replace_all(line, "");
+        for caps in SNIPPET_PATTERN.captures_iter(&line) {
+            if !snippets.contains_key(&caps[1]) {
+                unknown.push((idx + 1, caps[1].to_string()));
+            }
+        }
+    }
+    unknown
+}
+
+fn expand(
+    markdown: &str,
+    snippets: &BTreeMap<String, String>,
+    chain: &mut Vec<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(markdown.len());
+    let mut fence: Option<&str> = None;
+    for line in markdown.split_inclusive('\n') {
+        if !in_prose(line, &mut fence) || !line.contains(SNIPPET_DIRECTIVE) {
+            out.push_str(line);
+            continue;
+        }
+        // Directives inside inline code are kept as written
+        let mut last = 0;
+        for code in INLINE_CODE_PATTERN.find_iter(line) {
+            out.push_str(&expand_text(&line[last..code.start()], snippets, chain)?);
+            out.push_str(code.as_str());
+            last = code.end();
+        }
+        out.push_str(&expand_text(&line[last..], snippets, chain)?);
+    }
+    Ok(out)
+}
+
+fn expand_text(
+    text: &str,
+    snippets: &BTreeMap<String, String>,
+    chain: &mut Vec<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in SNIPPET_PATTERN.captures_iter(text) {
+        let whole = caps.get(0).expect("whole match");
+        out.push_str(&text[last..whole.start()]);
+        last = whole.end();
+
+        let name = &caps[1];
+        let Some(snippet) = snippets.get(name) else {
+            warn(
+                WarningCategory::UnresolvedRef,
+                format!("Unknown snippet: {}", name),
+            );
+            out.push_str(whole.as_str());
+            continue;
+        };
+        if chain.iter().any(|n| n == name) {
+            return Err(Error::Include(format!(
+                "Snippet cycle: {} -> {}",
+                chain.join(" -> "),
+                name
+            )));
+        }
+
+        let body = substitute(snippet.trim_end_matches(['\n', '\r']), &caps);
+        chain.push(name.to_string());
+        out.push_str(&expand(&body, snippets, chain)?);
+        chain.pop();
+    }
+    out.push_str(&text[last..]);
+    Ok(out)
+}
+
+/// Replace a snippet's `{{key}}` placeholders with the directive's parameters
+fn substitute(snippet: &str, directive: &Captures) -> String {
+    let params = directive.get(2).map_or("", |m| m.as_str());
+    PARAM_PATTERN
+        .captures_iter(params)
+        .fold(snippet.to_string(), |body, param| {
+            let value = param
+                .get(2)
+                .or_else(|| param.get(3))
+                .or_else(|| param.get(4))
+                .map_or("", |m| m.as_str());
+            body.replace(&format!("{{{{{}}}}}", &param[1]), value)
+        })
+}
+
+/// Track fenced code blocks; whether `line` is outside of them
+fn in_prose(line: &str, fence: &mut Option<&str>) -> bool {
+    let trimmed = line.trim();
+    match *fence {
+        Some(marker) if trimmed.starts_with(marker) => *fence = None,
+        Some(_) => {}
+        None if trimmed.starts_with("```") => *fence = Some("```"),
+        None if trimmed.starts_with("~~~") => *fence = Some("~~~"),
+        None => return true,
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippets() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (
+                "warning".to_string(),
+                "> **Warning:** {{text}}\n".to_string(),
+            ),
+            ("company".to_string(), "ACME Co., Ltd.".to_string()),
+            (
+                "notice".to_string(),
+                "{!snippet:warning(text=\"Ask {{who}}\")}".to_string(),
+            ),
+            ("loop".to_string(), "{!snippet:loop}".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_expand_snippets() {
+        let snippets = snippets();
+        let md = "Made by {!snippet:company}.\n\n{!snippet:warning(text=\"High voltage\")}\n\n{!snippet:notice(who='the site lead')}\n\n`{!snippet:company}`\n\n```\n{!snippet:company}\n```\n";
+        let expanded = expand_snippets(md, &snippets).unwrap();
+        assert!(expanded.starts_with("Made by ACME Co., Ltd..\n"));
+        assert!(expanded.contains("\n> **Warning:** High voltage\n"));
+        assert!(expanded.contains("\n> **Warning:** Ask the site lead\n"));
+        assert!(expanded.contains("`{!snippet:company}`"));
+        assert!(expanded.contains("```\n{!snippet:company}\n```"));
+
+        let (result, warnings) =
+            crate::diagnostics::capture(|| expand_snippets("{!snippet:nope}\n", &snippets));
+        assert_eq!(result.unwrap(), "{!snippet:nope}\n");
+        assert_eq!(warnings.len(), 1);
+
+        assert!(expand_snippets("{!snippet:loop}\n", &snippets).is_err());
+        assert_eq!(
+            unknown_snippets("{!snippet:company}\n{!snippet:nope(x=1)}\n", &snippets),
+            vec![(2, "nope".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_load_snippets() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join(SNIPPETS_DIR);
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("footer.md"), "---\nnote: x\n---\nThanks!\n").unwrap();
+        std::fs::write(dir.join("company.md"), "From file\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let configured = BTreeMap::from([("company".to_string(), "ACME".to_string())]);
+        let snippets = load_snippets(temp_dir.path(), &configured).unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets["footer"].trim(), "Thanks!");
+        assert_eq!(snippets["company"], "ACME");
+    }
+}
@@ -0,0 +1,256 @@
+//! Project statistics (`md2docx stats`)
+//!
+//! Counts words, figures, tables and equations per chapter and estimates
+//! page count and reading time for the whole document.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::ProjectConfig;
+use crate::discovery::DiscoveredProject;
+use crate::i18n::count_words;
+use crate::parser::{
+    extract_inline_text, parse_markdown_with_frontmatter, Block, Inline, ParsedDocument,
+};
+use crate::Result;
+
+/// Words per printed page used for the page estimate
+const WORDS_PER_PAGE: f64 = 350.0;
+/// Page space taken by each figure or table
+const PAGES_PER_FLOAT: f64 = 0.25;
+/// Average reading speed in words per minute
+const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// Counts for a single source file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileStats {
+    /// Source file
+    pub path: PathBuf,
+    /// Chapter title (frontmatter title, first heading, or file stem)
+    pub title: String,
+    /// Word count (prose only; code, math and diagrams are excluded)
+    pub words: usize,
+    /// Images and mermaid diagrams
+    pub figures: usize,
+    /// Tables
+    pub tables: usize,
+    /// Display equations
+    pub equations: usize,
+    /// Estimated printed pages
+    pub pages: f64,
+    /// Estimated reading time in minutes
+    pub reading_minutes: f64,
+}
+
+/// Statistics for a whole project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectStats {
+    /// Per-file statistics in build order (chapters, then appendices)
+    pub files: Vec<FileStats>,
+    /// Sum over all files
+    pub total: FileStats,
+}
+
+/// Collect statistics for the front matter, chapters, appendices and back
+/// matter of a project
+pub fn project_stats(base_dir: &Path, config: &ProjectConfig) -> Result<ProjectStats> {
+    let project = DiscoveredProject::discover_with_config(base_dir, config)?;
+
+    let paths = project
+        .frontmatter
+        .iter()
+        .chain(project.chapters.iter().map(|c| &c.path))
+        .chain(project.appendices.iter().map(|a| &a.path))
+        .chain(&project.backmatter);
+
+    let mut stats = ProjectStats::default();
+    for path in paths {
+        let content = std::fs::read_to_string(path)?;
+        stats.files.push(file_stats(path, &content));
+    }
+
+    let total = &mut stats.total;
+    total.title = config.document.title.clone();
+    total.path = base_dir.to_path_buf();
+    for file in &stats.files {
+        total.words += file.words;
+        total.figures += file.figures;
+        total.tables += file.tables;
+        total.equations += file.equations;
+    }
+    total.estimate();
+
+    Ok(stats)
+}
+
+/// Compute statistics for a single markdown file
+pub fn file_stats(path: &Path, content: &str) -> FileStats {
+    let parsed = parse_markdown_with_frontmatter(content);
+    let mut stats = FileStats {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+
+    stats.title = title(path, &parsed);
+
+    count_blocks(&parsed.blocks, &mut stats);
+    for blocks in parsed.footnotes.values() {
+        count_blocks(blocks, &mut stats);
+    }
+    stats.estimate();
+    stats
+}
+
+impl FileStats {
+    /// Fill in the page and reading time estimates from the counts
+    fn estimate(&mut self) {
+        let floats = (self.figures + self.tables) as f64;
+        self.pages = round1(self.words as f64 / WORDS_PER_PAGE + floats * PAGES_PER_FLOAT);
+        self.reading_minutes = round1(self.words as f64 / WORDS_PER_MINUTE);
+    }
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Title of a markdown file: its frontmatter title, else its first heading,
+/// else its file name
+pub(super) fn file_title(path: &Path, content: &str) -> String {
+    title(path, &parse_markdown_with_frontmatter(content))
+}
+
+fn title(path: &Path, parsed: &ParsedDocument) -> String {
+    parsed
+        .frontmatter
+        .as_ref()
+        .and_then(|fm| fm.title.clone())
+        .or_else(|| first_heading(&parsed.blocks))
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
+
+fn first_heading(blocks: &[Block]) -> Option<String> {
+    blocks.iter().find_map(|block| match block {
+        Block::Heading { content, .. } => Some(extract_inline_text(content).trim().to_string()),
+        _ => None,
+    })
+}
+
+fn count_blocks(blocks: &[Block], stats: &mut FileStats) {
+    for block in blocks {
+        match block {
+            Block::Heading { content, .. } | Block::Paragraph(content) => {
+                count_inlines(content, stats);
+            }
+            Block::BlockQuote(inner) => count_blocks(inner, stats),
+            Block::List { items, .. } => {
+                for item in items {
+                    count_blocks(&item.content, stats);
+                }
+            }
+            Block::Table {
+                headers,
+                rows,
+                caption,
+                ..
+            } => {
+                stats.tables += 1;
+                for cell in headers.iter().chain(rows.iter().flatten()) {
+                    count_inlines(&cell.content, stats);
+                }
+                if let Some(caption) = caption {
+                    stats.words += count_words(caption);
+                }
+            }
+            Block::Image { alt, .. } => {
+                stats.figures += 1;
+                stats.words += count_words(alt);
+            }
+            Block::Mermaid { .. } => stats.figures += 1,
+            Block::MathBlock { .. } => stats.equations += 1,
+            Block::FontGroup { blocks, .. } | Block::LangGroup { blocks, .. } => {
+                count_blocks(blocks, stats)
+            }
+            Block::ParallelText { rows } => {
+                for (source, translation) in rows {
+                    count_blocks(source, stats);
+                    count_blocks(translation, stats);
+                }
+            }
+            Block::CodeCallouts(items) => {
+                for (_, text) in items {
+                    count_inlines(text, stats);
+                }
+            }
+            Block::FigureGroup {
+                figures, caption, ..
+            } => {
+                count_blocks(figures, stats);
+                if let Some(caption) = caption {
+                    count_inlines(caption, stats);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn count_inlines(inlines: &[Inline], stats: &mut FileStats) {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::DisplayMath(_) => stats.equations += 1,
+            Inline::InlineMath(_) | Inline::Code(_) => {}
+            other => text.push_str(&extract_inline_text(std::slice::from_ref(other))),
+        }
+    }
+    stats.words += count_words(&text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_stats_counts() {
+        let content = "---\ntitle: Intro\n---\n\n# Introduction\n\nOne two three four.\n\n![Diagram](d.png)\n\n| A | B |\n|---|---|\n| x | y |\n\n$$\nE = mc^2\n$$\n\n```rust\nfn main() {}\n```\n";
+        let stats = file_stats(Path::new("ch01_intro.md"), content);
+
+        assert_eq!(stats.title, "Intro");
+        assert_eq!(stats.figures, 1);
+        assert_eq!(stats.tables, 1);
+        assert_eq!(stats.equations, 1);
+        // "Introduction" + 4 words + alt "Diagram" + 4 table cells
+        assert_eq!(stats.words, 10);
+    }
+
+    #[test]
+    fn test_title_falls_back_to_heading() {
+        let stats = file_stats(Path::new("ch01_intro.md"), "# Getting Started\n\nText.");
+        assert_eq!(stats.title, "Getting Started");
+    }
+
+    #[test]
+    fn test_project_totals() {
+        let temp_dir = TempDir::new().unwrap();
+        let words = vec!["word"; 700].join(" ");
+        std::fs::write(
+            temp_dir.path().join("ch01_a.md"),
+            format!("# A\n\n{}", words),
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("ch02_b.md"), "# B\n\nShort.").unwrap();
+
+        let stats = project_stats(temp_dir.path(), &ProjectConfig::default()).unwrap();
+        assert_eq!(stats.files.len(), 2);
+        assert_eq!(stats.total.words, 703);
+        assert_eq!(stats.total.pages, 2.0);
+        assert_eq!(stats.total.reading_minutes, 3.5);
+    }
+}
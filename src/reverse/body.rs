@@ -0,0 +1,650 @@
+//! Conversion of `word/document.xml` into markdown blocks
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::Regex;
+
+use super::parts::{attr, Numbering, Relationship, StyleKind, Styles};
+
+/// Character formatting of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct RunFormat {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+}
+
+/// Inline content collected for a paragraph
+#[derive(Debug, Clone)]
+enum Segment {
+    Text(RunFormat, String),
+    /// Already-rendered markdown (images, links)
+    Raw(String),
+    LineBreak,
+}
+
+/// A markdown block produced from the document body
+#[derive(Debug, Clone)]
+pub(crate) enum MdBlock {
+    Paragraph(String),
+    Heading(u8, String),
+    ListItem {
+        ordered: bool,
+        level: usize,
+        text: String,
+    },
+    /// One line of a code block; consecutive lines form one fence
+    CodeLine(String),
+    Quote(String),
+    Table(Vec<Vec<String>>),
+    Image(String),
+}
+
+/// Paragraph being collected
+#[derive(Debug, Default)]
+struct ParagraphState {
+    style: Option<String>,
+    num_id: Option<String>,
+    ilvl: Option<String>,
+    segments: Vec<Segment>,
+}
+
+/// Table being collected
+#[derive(Debug, Default)]
+struct TableState {
+    rows: Vec<Vec<String>>,
+    cell: Option<String>,
+    span: usize,
+}
+
+/// Hyperlink being collected
+#[derive(Debug)]
+struct LinkState {
+    target: Option<String>,
+    segments: Vec<Segment>,
+}
+
+/// Image reference found in the body: relationship id and alt text
+#[derive(Debug, Clone)]
+pub(crate) struct ImageRef {
+    pub rel_id: String,
+    pub alt: String,
+}
+
+/// Resolves an image relationship to the markdown path of the exported file
+pub(crate) trait ImageSink {
+    fn export(&mut self, image: &ImageRef) -> Option<String>;
+}
+
+/// Break hint inserted between Thai words, which the markdown never had
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// A `<w:footnote>` element of `word/footnotes.xml` and its content
+static FOOTNOTE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)(<w:footnote\b[^>]*[^/]>)(.*?)</w:footnote>")
+        .expect("FOOTNOTE_PATTERN regex should be valid")
+});
+
+/// Walk the document body and produce markdown blocks
+///
+/// Footnote ids are appended to `footnotes` in order of first reference;
+/// each reference is written as `[^N]` with N its position in that list.
+pub(crate) fn convert_body(
+    xml: &str,
+    styles: &Styles,
+    numbering: &Numbering,
+    rels: &HashMap<String, Relationship>,
+    images: &mut dyn ImageSink,
+    footnotes: &mut Vec<String>,
+) -> Vec<MdBlock> {
+    let mut reader = Reader::from_str(xml);
+    let mut blocks = Vec::new();
+
+    let mut para: Option<ParagraphState> = None;
+    let mut tables: Vec<TableState> = Vec::new();
+    let mut link: Option<LinkState> = None;
+    let mut format = RunFormat::default();
+    let mut in_run = false;
+    let mut in_rpr = false;
+    let mut in_text = false;
+    let mut skip_depth = 0usize;
+    let mut pending_alt = String::new();
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(event) => event,
+        };
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_start = matches!(event, Event::Start(_));
+                let name = e.name();
+                match name.as_ref() {
+                    b"w:del" | b"w:delText" | b"w:instrText" if is_start => skip_depth += 1,
+                    _ if skip_depth > 0 => {}
+                    b"w:p" => {
+                        para = Some(ParagraphState::default());
+                        if !is_start {
+                            finish_paragraph(
+                                para.take(),
+                                styles,
+                                numbering,
+                                &mut tables,
+                                &mut blocks,
+                            );
+                        }
+                    }
+                    b"w:pStyle" => {
+                        if let Some(p) = para.as_mut() {
+                            p.style = attr(e, b"w:val");
+                        }
+                    }
+                    b"w:numId" => {
+                        if let Some(p) = para.as_mut() {
+                            p.num_id = attr(e, b"w:val");
+                        }
+                    }
+                    b"w:ilvl" => {
+                        if let Some(p) = para.as_mut() {
+                            p.ilvl = attr(e, b"w:val");
+                        }
+                    }
+                    b"w:r" if is_start => {
+                        in_run = true;
+                        format = RunFormat::default();
+                    }
+                    b"w:rPr" if is_start => in_rpr = true,
+                    b"w:b" if in_run && in_rpr => format.bold = is_on(e),
+                    b"w:i" if in_run && in_rpr => format.italic = is_on(e),
+                    b"w:strike" | b"w:dstrike" if in_run && in_rpr => format.strike = is_on(e),
+                    b"w:rStyle" if in_run && in_rpr => {
+                        let style = attr(e, b"w:val").unwrap_or_default();
+                        if style.to_ascii_lowercase().contains("code") {
+                            format.code = true;
+                        }
+                    }
+                    b"w:t" if is_start => in_text = true,
+                    b"w:tab" if in_run => {
+                        push_segment(&mut para, &mut link, Segment::Text(format, " ".to_string()))
+                    }
+                    b"w:br" | b"w:cr"
+                        if in_run && attr(e, b"w:type").as_deref() != Some("page") =>
+                    {
+                        push_segment(&mut para, &mut link, Segment::LineBreak)
+                    }
+                    b"w:footnoteReference" if in_run => {
+                        if let Some(id) = attr(e, b"w:id") {
+                            let label = match footnotes.iter().position(|f| *f == id) {
+                                Some(index) => index + 1,
+                                None => {
+                                    footnotes.push(id);
+                                    footnotes.len()
+                                }
+                            };
+                            push_segment(
+                                &mut para,
+                                &mut link,
+                                Segment::Raw(format!("[^{}]", label)),
+                            );
+                        }
+                    }
+                    b"w:hyperlink" if is_start => {
+                        let target = attr(e, b"r:id")
+                            .and_then(|id| rels.get(&id))
+                            .map(|rel| rel.target.clone())
+                            .or_else(|| attr(e, b"w:anchor").map(|a| format!("#{}", a)));
+                        link = Some(LinkState {
+                            target,
+                            segments: Vec::new(),
+                        });
+                    }
+                    b"wp:docPr" => {
+                        let alt = attr(e, b"descr")
+                            .filter(|d| !d.is_empty())
+                            .or_else(|| attr(e, b"title"))
+                            .unwrap_or_default();
+                        pending_alt = alt.split_whitespace().collect::<Vec<_>>().join(" ");
+                    }
+                    b"a:blip" | b"v:imagedata" => {
+                        let rel_id = attr(e, b"r:embed").or_else(|| attr(e, b"r:id"));
+                        if let Some(rel_id) = rel_id {
+                            let image = ImageRef {
+                                rel_id,
+                                alt: std::mem::take(&mut pending_alt),
+                            };
+                            if let Some(path) = images.export(&image) {
+                                let md = format!("![{}]({})", escape_text(&image.alt), path);
+                                push_segment(&mut para, &mut link, Segment::Raw(md));
+                            }
+                        }
+                    }
+                    b"w:tbl" if is_start => tables.push(TableState::default()),
+                    b"w:tr" if is_start => {
+                        if let Some(t) = tables.last_mut() {
+                            t.rows.push(Vec::new());
+                        }
+                    }
+                    b"w:tc" if is_start => {
+                        if let Some(t) = tables.last_mut() {
+                            t.cell = Some(String::new());
+                            t.span = 1;
+                        }
+                    }
+                    b"w:gridSpan" => {
+                        if let Some(t) = tables.last_mut() {
+                            t.span = attr(e, b"w:val").and_then(|v| v.parse().ok()).unwrap_or(1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) if in_text && skip_depth == 0 => {
+                if let Ok(text) = t.unescape() {
+                    push_segment(
+                        &mut para,
+                        &mut link,
+                        Segment::Text(format, text.replace(ZERO_WIDTH_SPACE, "")),
+                    );
+                }
+            }
+            Event::End(ref e) => match e.name().as_ref() {
+                b"w:del" | b"w:delText" | b"w:instrText" => {
+                    skip_depth = skip_depth.saturating_sub(1)
+                }
+                _ if skip_depth > 0 => {}
+                b"w:t" => in_text = false,
+                b"w:r" => in_run = false,
+                b"w:rPr" => in_rpr = false,
+                b"w:hyperlink" => {
+                    if let Some(l) = link.take() {
+                        let text = render_inline(&l.segments);
+                        let segment = match l.target {
+                            Some(target) if !text.is_empty() => {
+                                Segment::Raw(format!("[{}]({})", text, target))
+                            }
+                            _ => Segment::Raw(text),
+                        };
+                        push_segment(&mut para, &mut link, segment);
+                    }
+                }
+                b"w:p" => {
+                    finish_paragraph(para.take(), styles, numbering, &mut tables, &mut blocks)
+                }
+                b"w:tc" => {
+                    if let Some(t) = tables.last_mut() {
+                        if let (Some(cell), Some(row)) = (t.cell.take(), t.rows.last_mut()) {
+                            row.push(cell);
+                            for _ in 1..t.span {
+                                row.push(String::new());
+                            }
+                        }
+                    }
+                }
+                b"w:tbl" => {
+                    if let Some(table) = tables.pop() {
+                        let rows: Vec<Vec<String>> =
+                            table.rows.into_iter().filter(|r| !r.is_empty()).collect();
+                        match tables.last_mut().and_then(|t| t.cell.as_mut()) {
+                            // Nested tables are flattened into the outer cell
+                            Some(cell) => {
+                                for row in rows {
+                                    append_cell_text(cell, &row.join(" "));
+                                }
+                            }
+                            None if !rows.is_empty() => blocks.push(MdBlock::Table(rows)),
+                            None => {}
+                        }
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+/// Convert the notes of `word/footnotes.xml`, keyed by footnote id
+///
+/// Separator notes (which carry a `w:type`) are skipped.
+pub(crate) fn convert_footnotes(
+    xml: &str,
+    styles: &Styles,
+    numbering: &Numbering,
+    rels: &HashMap<String, Relationship>,
+    images: &mut dyn ImageSink,
+) -> HashMap<String, Vec<MdBlock>> {
+    let mut notes = HashMap::new();
+    for caps in FOOTNOTE_PATTERN.captures_iter(xml) {
+        let mut reader = Reader::from_str(&caps[1]);
+        let Ok(Event::Start(start)) = reader.read_event() else {
+            continue;
+        };
+        if attr(&start, b"w:type").is_some() {
+            continue;
+        }
+        if let Some(id) = attr(&start, b"w:id") {
+            let blocks = convert_body(&caps[2], styles, numbering, rels, images, &mut Vec::new());
+            notes.insert(id, blocks);
+        }
+    }
+    notes
+}
+
+/// Render footnote definitions for the referenced ids, in label order
+pub(crate) fn render_footnotes(ids: &[String], notes: &HashMap<String, Vec<MdBlock>>) -> String {
+    let mut out = String::new();
+    for (index, id) in ids.iter().enumerate() {
+        let text = notes.get(id).map(|b| render_blocks(b)).unwrap_or_default();
+        let mut lines = text.trim_end().lines();
+        out.push_str(&format!(
+            "\n[^{}]: {}",
+            index + 1,
+            lines.next().unwrap_or("")
+        ));
+        // Continuation paragraphs are indented to stay inside the note
+        for line in lines {
+            out.push('\n');
+            if !line.is_empty() {
+                out.push_str("    ");
+                out.push_str(line);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether a toggle property like `<w:b/>` or `<w:b w:val="0"/>` is on
+fn is_on(e: &BytesStart) -> bool {
+    !matches!(
+        attr(e, b"w:val").as_deref(),
+        Some("0") | Some("false") | Some("off")
+    )
+}
+
+fn push_segment(para: &mut Option<ParagraphState>, link: &mut Option<LinkState>, seg: Segment) {
+    if let Some(l) = link.as_mut() {
+        l.segments.push(seg);
+    } else if let Some(p) = para.as_mut() {
+        p.segments.push(seg);
+    }
+}
+
+fn append_cell_text(cell: &mut String, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    if !cell.is_empty() {
+        cell.push(' ');
+    }
+    cell.push_str(text);
+}
+
+/// Turn a completed paragraph into a block (or table cell content)
+fn finish_paragraph(
+    para: Option<ParagraphState>,
+    styles: &Styles,
+    numbering: &Numbering,
+    tables: &mut [TableState],
+    blocks: &mut Vec<MdBlock>,
+) {
+    let Some(para) = para else {
+        return;
+    };
+    let kind = para
+        .style
+        .as_deref()
+        .map(|s| styles.kind(s))
+        .unwrap_or(StyleKind::Normal);
+
+    if let Some(cell) = tables.last_mut().and_then(|t| t.cell.as_mut()) {
+        let text = render_inline(&para.segments).replace('\n', " ");
+        append_cell_text(cell, &text.replace('|', "\\|"));
+        return;
+    }
+
+    if kind == StyleKind::Code {
+        blocks.push(MdBlock::CodeLine(plain_text(&para.segments)));
+        return;
+    }
+
+    let text = render_inline(&para.segments);
+    let trimmed = text.trim();
+    if trimmed.is_empty() || kind == StyleKind::Toc {
+        return;
+    }
+
+    let block = match kind {
+        StyleKind::Heading(level) => MdBlock::Heading(level, trimmed.replace('\n', " ")),
+        StyleKind::Quote => MdBlock::Quote(trimmed.to_string()),
+        // md2docx regenerates figure and table captions
+        StyleKind::Caption
+            if matches!(
+                blocks.last(),
+                Some(MdBlock::Image(_)) | Some(MdBlock::Table(_))
+            ) =>
+        {
+            return;
+        }
+        _ => {
+            let list = para
+                .num_id
+                .as_deref()
+                .and_then(|id| numbering.is_ordered(id, para.ilvl.as_deref().unwrap_or("0")));
+            match list {
+                Some(ordered) => MdBlock::ListItem {
+                    ordered,
+                    level: para
+                        .ilvl
+                        .as_deref()
+                        .and_then(|l| l.parse().ok())
+                        .unwrap_or(0),
+                    text: trimmed.to_string(),
+                },
+                None if is_image_only(&para.segments) => MdBlock::Image(trimmed.to_string()),
+                None => MdBlock::Paragraph(escape_line_start(trimmed)),
+            }
+        }
+    };
+    blocks.push(block);
+}
+
+fn is_image_only(segments: &[Segment]) -> bool {
+    let mut has_image = false;
+    for seg in segments {
+        match seg {
+            Segment::Raw(md) if md.starts_with("![") => has_image = true,
+            Segment::Text(_, t) if t.trim().is_empty() => {}
+            _ => return false,
+        }
+    }
+    has_image
+}
+
+/// Text of a paragraph without any markdown formatting (for code blocks)
+fn plain_text(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for seg in segments {
+        match seg {
+            Segment::Text(_, t) => out.push_str(t),
+            Segment::Raw(md) => out.push_str(md),
+            Segment::LineBreak => out.push('\n'),
+        }
+    }
+    out
+}
+
+/// Render inline segments as markdown, merging runs with equal formatting
+fn render_inline(segments: &[Segment]) -> String {
+    let mut merged: Vec<Segment> = Vec::new();
+    for seg in segments {
+        match (merged.last_mut(), seg) {
+            (Some(Segment::Text(prev_fmt, prev)), Segment::Text(fmt, text)) if prev_fmt == fmt => {
+                prev.push_str(text);
+            }
+            _ => merged.push(seg.clone()),
+        }
+    }
+
+    let mut out = String::new();
+    for seg in &merged {
+        match seg {
+            Segment::Text(fmt, text) => out.push_str(&format_text(*fmt, text)),
+            Segment::Raw(md) => out.push_str(md),
+            Segment::LineBreak => out.push_str("\\\n"),
+        }
+    }
+    out
+}
+
+/// Wrap text in emphasis markers, keeping surrounding whitespace outside
+fn format_text(fmt: RunFormat, text: &str) -> String {
+    let core = text.trim();
+    if core.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+
+    let body = if fmt.code {
+        let fence = if core.contains('`') { "``" } else { "`" };
+        format!("{fence}{core}{fence}")
+    } else {
+        let mut body = escape_text(core);
+        if fmt.strike {
+            body = format!("~~{}~~", body);
+        }
+        if fmt.italic {
+            body = format!("*{}*", body);
+        }
+        if fmt.bold {
+            body = format!("**{}**", body);
+        }
+        body
+    };
+
+    format!("{leading}{body}{trailing}")
+}
+
+/// Escape characters that have inline meaning in markdown
+pub(crate) fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '$') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape characters that would start a block construct at line start
+fn escape_line_start(text: &str) -> String {
+    let needs_escape = text.starts_with('#')
+        || text.starts_with('>')
+        || text.starts_with("- ")
+        || text.starts_with("+ ")
+        || text
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    if needs_escape {
+        format!("\\{}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Render blocks into a markdown document
+pub(crate) fn render_blocks(blocks: &[MdBlock]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<&MdBlock> = None;
+
+    for block in blocks {
+        let separator = match (prev, block) {
+            (None, _) => "",
+            (Some(MdBlock::ListItem { .. }), MdBlock::ListItem { .. }) => "\n",
+            (Some(MdBlock::CodeLine(_)), MdBlock::CodeLine(_)) => "\n",
+            (Some(MdBlock::Quote(_)), MdBlock::Quote(_)) => "\n>\n",
+            (Some(MdBlock::CodeLine(_)), _) => "\n```\n\n",
+            _ => "\n\n",
+        };
+        out.push_str(separator);
+
+        match block {
+            MdBlock::Paragraph(text) | MdBlock::Image(text) => out.push_str(text),
+            MdBlock::Heading(level, text) => {
+                out.push_str(&"#".repeat(*level as usize));
+                out.push(' ');
+                out.push_str(text);
+            }
+            MdBlock::ListItem {
+                ordered,
+                level,
+                text,
+            } => {
+                out.push_str(&"    ".repeat(*level));
+                out.push_str(if *ordered { "1. " } else { "- " });
+                out.push_str(&text.replace("\\\n", "\\\n    "));
+            }
+            MdBlock::CodeLine(line) => {
+                if !matches!(prev, Some(MdBlock::CodeLine(_))) {
+                    out.push_str("```\n");
+                }
+                out.push_str(line);
+            }
+            MdBlock::Quote(text) => {
+                let quoted: Vec<String> = text.lines().map(|l| format!("> {}", l)).collect();
+                out.push_str(&quoted.join("\n"));
+            }
+            MdBlock::Table(rows) => out.push_str(&render_table(rows)),
+        }
+        prev = Some(block);
+    }
+
+    if matches!(prev, Some(MdBlock::CodeLine(_))) {
+        out.push_str("\n```");
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Render table rows as a pipe table, using the first row as header
+fn render_table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    let render_row = |row: &[String]| {
+        let cells: Vec<&str> = (0..columns)
+            .map(|i| row.get(i).map(String::as_str).unwrap_or(""))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    // Header rows are bold in Word; markdown headers are bold already
+    let header: Vec<String> = rows[0]
+        .iter()
+        .map(|cell| {
+            cell.strip_prefix("**")
+                .and_then(|c| c.strip_suffix("**"))
+                .filter(|c| !c.contains("**"))
+                .unwrap_or(cell)
+                .to_string()
+        })
+        .collect();
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    lines.push(render_row(&header));
+    lines.push(format!("|{}", "---|".repeat(columns)));
+    for row in &rows[1..] {
+        lines.push(render_row(row));
+    }
+    lines.join("\n")
+}
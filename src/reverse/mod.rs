@@ -0,0 +1,292 @@
+//! DOCX to Markdown reverse conversion
+//!
+//! Reads an existing `.docx` (for example one edited by reviewers in Word)
+//! and converts it back to markdown so changes can be merged into the
+//! source tree. Supported content:
+//!
+//! - Headings (Word built-in `Heading N` / `Title` styles)
+//! - Paragraphs with bold, italic, strikethrough, inline code and links
+//! - Bulleted and numbered lists, including nesting
+//! - Tables (first row becomes the header row)
+//! - Code blocks (paragraphs in the `Code` style written by md2docx)
+//! - Images, exported as separate files
+//! - Footnotes, as `[^N]` references with definitions at the end
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use md2docx::reverse::{docx_to_markdown, ExtractOptions};
+//!
+//! let bytes = std::fs::read("reviewed.docx").unwrap();
+//! let extracted = docx_to_markdown(&bytes, &ExtractOptions::default()).unwrap();
+//! println!("{}", extracted.markdown);
+//! for image in &extracted.media {
+//!     println!("image: {}", image.name);
+//! }
+//! ```
+
+mod body;
+pub(crate) mod parts;
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::{Error, Result};
+use body::{convert_body, convert_footnotes, render_blocks, render_footnotes, ImageRef, ImageSink};
+use parts::{parse_relationships, Numbering, Relationship, Styles};
+
+/// Options for [`docx_to_markdown`]
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Directory used in image links, relative to the markdown file
+    pub media_dir: String,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            media_dir: "media".to_string(),
+        }
+    }
+}
+
+/// An image extracted from the document
+#[derive(Debug, Clone)]
+pub struct MediaFile {
+    /// File name inside the media directory
+    pub name: String,
+    /// Raw image bytes
+    pub data: Vec<u8>,
+}
+
+/// Result of a reverse conversion
+#[derive(Debug, Clone, Default)]
+pub struct ExtractedDocument {
+    /// Generated markdown
+    pub markdown: String,
+    /// Images referenced by the markdown
+    pub media: Vec<MediaFile>,
+}
+
+/// Convert DOCX bytes to markdown
+pub fn docx_to_markdown(docx: &[u8], options: &ExtractOptions) -> Result<ExtractedDocument> {
+    let mut archive = ZipArchive::new(Cursor::new(docx))?;
+
+    let document_xml = read_part(&mut archive, "word/document.xml")?
+        .ok_or_else(|| Error::Parse("DOCX has no word/document.xml".to_string()))?;
+    let styles = read_part(&mut archive, "word/styles.xml")?
+        .map(|xml| Styles::parse(&xml))
+        .unwrap_or_default();
+    let numbering = read_part(&mut archive, "word/numbering.xml")?
+        .map(|xml| Numbering::parse(&xml))
+        .unwrap_or_default();
+    let rels = read_part(&mut archive, "word/_rels/document.xml.rels")?
+        .map(|xml| parse_relationships(&xml))
+        .unwrap_or_default();
+    let footnotes_xml = read_part(&mut archive, "word/footnotes.xml")?;
+    let footnote_rels = read_part(&mut archive, "word/_rels/footnotes.xml.rels")?
+        .map(|xml| parse_relationships(&xml))
+        .unwrap_or_default();
+
+    let mut exporter = MediaExporter {
+        archive: &mut archive,
+        rels: &rels,
+        media_dir: options.media_dir.trim_end_matches('/'),
+        exported: HashMap::new(),
+        media: Vec::new(),
+    };
+    let mut footnote_ids = Vec::new();
+    let blocks = convert_body(
+        &document_xml,
+        &styles,
+        &numbering,
+        &rels,
+        &mut exporter,
+        &mut footnote_ids,
+    );
+    let mut markdown = render_blocks(&blocks);
+
+    if let Some(xml) = footnotes_xml.filter(|_| !footnote_ids.is_empty()) {
+        // Images inside notes resolve against the footnotes part's relationships
+        exporter.rels = &footnote_rels;
+        let notes = convert_footnotes(&xml, &styles, &numbering, &footnote_rels, &mut exporter);
+        markdown.push_str(&render_footnotes(&footnote_ids, &notes));
+    }
+    let media = exporter.media;
+
+    Ok(ExtractedDocument { markdown, media })
+}
+
+/// Convert a DOCX file and write the markdown and its images to disk
+///
+/// Images are written to `options.media_dir` next to `output`.
+/// Returns the path of the written markdown file.
+pub fn extract_to_file(input: &Path, output: &Path, options: &ExtractOptions) -> Result<PathBuf> {
+    let bytes = std::fs::read(input)?;
+    let extracted = docx_to_markdown(&bytes, options)?;
+
+    let out_dir = output.parent().unwrap_or_else(|| Path::new(""));
+    if !out_dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(out_dir)?;
+    }
+    std::fs::write(output, &extracted.markdown)?;
+
+    if !extracted.media.is_empty() {
+        let media_dir = out_dir.join(&options.media_dir);
+        std::fs::create_dir_all(&media_dir)?;
+        for file in &extracted.media {
+            std::fs::write(media_dir.join(&file.name), &file.data)?;
+        }
+    }
+
+    Ok(output.to_path_buf())
+}
+
+/// Read a text part from the package, returning `None` if it is absent
+fn read_part(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Copies referenced images out of the package, once per target
+struct MediaExporter<'a, 'b> {
+    archive: &'a mut ZipArchive<Cursor<&'b [u8]>>,
+    rels: &'a HashMap<String, Relationship>,
+    media_dir: &'a str,
+    /// Package path -> exported file name
+    exported: HashMap<String, String>,
+    media: Vec<MediaFile>,
+}
+
+impl ImageSink for MediaExporter<'_, '_> {
+    fn export(&mut self, image: &ImageRef) -> Option<String> {
+        let rel = self.rels.get(&image.rel_id)?;
+        if rel.external {
+            return Some(rel.target.clone());
+        }
+
+        let part = match rel.target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("word/{}", rel.target),
+        };
+
+        let name = match self.exported.get(&part) {
+            Some(name) => name.clone(),
+            None => {
+                let mut data = Vec::new();
+                self.archive
+                    .by_name(&part)
+                    .ok()?
+                    .read_to_end(&mut data)
+                    .ok()?;
+                let name = Path::new(&part)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| format!("image{}", self.media.len() + 1));
+                self.exported.insert(part, name.clone());
+                self.media.push(MediaFile {
+                    name: name.clone(),
+                    data,
+                });
+                name
+            }
+        };
+
+        Some(if self.media_dir.is_empty() {
+            name
+        } else {
+            format!("{}/{}", self.media_dir, name)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown_to_docx;
+
+    fn round_trip(markdown: &str) -> String {
+        let docx = markdown_to_docx(markdown).unwrap();
+        docx_to_markdown(&docx, &ExtractOptions::default())
+            .unwrap()
+            .markdown
+    }
+
+    #[test]
+    fn test_headings_and_paragraphs() {
+        let md = round_trip("# Title\n\nSome **bold** and *italic* text.\n\n## Section\n\nMore.");
+        assert!(md.contains("# Title"), "{}", md);
+        assert!(md.contains("## Section"), "{}", md);
+        assert!(md.contains("Some **bold** and *italic* text."), "{}", md);
+    }
+
+    #[test]
+    fn test_lists() {
+        let md = round_trip("- one\n- two\n    - nested\n\n1. first\n2. second");
+        assert!(md.contains("- one\n- two\n    - nested"), "{}", md);
+        assert!(md.contains("1. first\n1. second"), "{}", md);
+    }
+
+    #[test]
+    fn test_table() {
+        let md = round_trip("| A | B |\n|---|---|\n| 1 | 2 |");
+        assert!(md.contains("| A | B |\n|---|---|\n| 1 | 2 |"), "{}", md);
+    }
+
+    #[test]
+    fn test_code_block() {
+        let md = round_trip("```\nlet x = 1;\nlet y = 2;\n```");
+        assert!(md.contains("```\nlet x = 1;\nlet y = 2;\n```"), "{}", md);
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let md =
+            round_trip("First[^a] and second[^b].\n\n[^a]: A **bold** note.\n\n[^b]: Another.");
+        assert!(md.contains("First[^1] and second[^2]."), "{}", md);
+        assert!(md.contains("\n[^1]: A **bold** note.\n"), "{}", md);
+        assert!(md.contains("\n[^2]: Another.\n"), "{}", md);
+    }
+
+    #[test]
+    #[cfg(feature = "thai-segmentation")]
+    fn test_thai_break_hints_are_dropped() {
+        let thai = "ภาษาไทยเป็นภาษาที่เขียนติดกันโดยไม่มีการเว้นวรรคระหว่างคำแต่ละคำในประโยคเดียวกัน";
+        let docx = markdown_to_docx(thai).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(&docx[..])).unwrap();
+        let xml = read_part(&mut archive, "word/document.xml")
+            .unwrap()
+            .unwrap();
+        assert!(xml.contains('\u{200B}'));
+
+        let md = round_trip(thai);
+        assert!(md.contains(thai), "{}", md);
+        assert!(!md.contains('\u{200B}'), "{}", md);
+    }
+
+    #[test]
+    fn test_escapes_markdown_characters() {
+        assert_eq!(body::escape_text("a*b_c"), "a\\*b\\_c");
+    }
+
+    #[test]
+    fn test_missing_document_part() {
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buf));
+            zip.start_file("other.xml", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.finish().unwrap();
+        }
+        assert!(docx_to_markdown(&buf, &ExtractOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Parsing of DOCX package parts needed for reverse conversion
+//!
+//! Reads paragraph styles (`word/styles.xml`), list definitions
+//! (`word/numbering.xml`) and relationships (`word/_rels/document.xml.rels`).
+
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// How a paragraph style maps to markdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StyleKind {
+    Normal,
+    Heading(u8),
+    Code,
+    Quote,
+    Caption,
+    Toc,
+}
+
+/// Paragraph style classification keyed by style id
+#[derive(Debug, Default)]
+pub(crate) struct Styles {
+    kinds: HashMap<String, StyleKind>,
+}
+
+impl Styles {
+    /// Parse `word/styles.xml`
+    pub(crate) fn parse(xml: &str) -> Self {
+        let mut styles = Styles::default();
+        let mut reader = Reader::from_str(xml);
+        let mut current: Option<(String, Option<String>)> = None;
+
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"w:style" => {
+                    current = attr(&e, b"w:styleId").map(|id| (id, None));
+                }
+                Ok(Event::Empty(e)) if e.name().as_ref() == b"w:name" => {
+                    if let Some((_, name)) = current.as_mut() {
+                        *name = attr(&e, b"w:val");
+                    }
+                }
+                Ok(Event::End(e)) if e.name().as_ref() == b"w:style" => {
+                    if let Some((id, name)) = current.take() {
+                        let kind = classify_style(&id, name.as_deref().unwrap_or(""));
+                        styles.kinds.insert(id, kind);
+                    }
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+
+        styles
+    }
+
+    /// Classify a style id, falling back to the id itself for unknown styles
+    pub(crate) fn kind(&self, style_id: &str) -> StyleKind {
+        self.kinds
+            .get(style_id)
+            .copied()
+            .unwrap_or_else(|| classify_style(style_id, ""))
+    }
+}
+
+/// Map a style id / display name to its markdown meaning
+///
+/// Recognises the built-in Word names (`heading 1`, `Title`, `toc 1`) as
+/// well as the style ids md2docx itself writes (`Heading1`, `Code`, `Quote`).
+fn classify_style(id: &str, name: &str) -> StyleKind {
+    let id_lower = id.to_ascii_lowercase();
+    let name_lower = name.to_ascii_lowercase();
+
+    for candidate in [name_lower.as_str(), id_lower.as_str()] {
+        let rest = candidate
+            .strip_prefix("heading")
+            .map(|r| r.trim_start_matches([' ', '_']));
+        if let Some(level) = rest.and_then(|r| r.parse::<u8>().ok()) {
+            if (1..=9).contains(&level) {
+                return StyleKind::Heading(level.min(6));
+            }
+        }
+    }
+
+    if id_lower == "title" || name_lower == "title" {
+        StyleKind::Heading(1)
+    } else if id_lower.starts_with("toc") || name_lower.starts_with("toc") {
+        StyleKind::Toc
+    } else if id_lower == "code" || id_lower == "sourcecode" || name_lower == "code" {
+        StyleKind::Code
+    } else if id_lower.contains("quote") || name_lower.contains("quote") {
+        StyleKind::Quote
+    } else if id_lower == "caption" || name_lower == "caption" {
+        StyleKind::Caption
+    } else {
+        StyleKind::Normal
+    }
+}
+
+/// List definitions: whether each `(numId, ilvl)` is ordered
+#[derive(Debug, Default)]
+pub(crate) struct Numbering {
+    /// abstractNumId -> (ilvl -> numFmt)
+    abstract_formats: HashMap<String, HashMap<String, String>>,
+    /// numId -> abstractNumId
+    nums: HashMap<String, String>,
+}
+
+impl Numbering {
+    /// Parse `word/numbering.xml`
+    pub(crate) fn parse(xml: &str) -> Self {
+        let mut numbering = Numbering::default();
+        let mut reader = Reader::from_str(xml);
+        let mut abstract_id: Option<String> = None;
+        let mut level: Option<String> = None;
+        let mut num_id: Option<String> = None;
+
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => match e.name().as_ref() {
+                    b"w:abstractNum" => abstract_id = attr(&e, b"w:abstractNumId"),
+                    b"w:lvl" => level = attr(&e, b"w:ilvl"),
+                    b"w:num" => num_id = attr(&e, b"w:numId"),
+                    _ => {}
+                },
+                Ok(Event::Empty(e)) => match e.name().as_ref() {
+                    b"w:numFmt" => {
+                        if let (Some(a), Some(l), Some(fmt)) =
+                            (&abstract_id, &level, attr(&e, b"w:val"))
+                        {
+                            numbering
+                                .abstract_formats
+                                .entry(a.clone())
+                                .or_default()
+                                .insert(l.clone(), fmt);
+                        }
+                    }
+                    b"w:abstractNumId" => {
+                        if let (Some(n), Some(a)) = (&num_id, attr(&e, b"w:val")) {
+                            numbering.nums.insert(n.clone(), a);
+                        }
+                    }
+                    _ => {}
+                },
+                Ok(Event::End(e)) => match e.name().as_ref() {
+                    b"w:abstractNum" => abstract_id = None,
+                    b"w:lvl" => level = None,
+                    b"w:num" => num_id = None,
+                    _ => {}
+                },
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+
+        numbering
+    }
+
+    /// Whether the list level uses numbers rather than bullets
+    ///
+    /// Returns `None` when the numbering id is unknown or disables
+    /// numbering (`numId` 0).
+    pub(crate) fn is_ordered(&self, num_id: &str, ilvl: &str) -> Option<bool> {
+        if num_id == "0" {
+            return None;
+        }
+        let fmt = self
+            .nums
+            .get(num_id)
+            .and_then(|a| self.abstract_formats.get(a))
+            .and_then(|levels| levels.get(ilvl));
+        match fmt.map(String::as_str) {
+            Some("none") => None,
+            Some("bullet") => Some(false),
+            Some(_) => Some(true),
+            // Unknown numbering part: treat as bullet list
+            None => Some(false),
+        }
+    }
+}
+
+/// A package relationship target
+#[derive(Debug, Clone)]
+pub(crate) struct Relationship {
+    pub target: String,
+    pub external: bool,
+}
+
+/// Parse a `.rels` part into a map of relationship id to target
+pub(crate) fn parse_relationships(xml: &str) -> HashMap<String, Relationship> {
+    let mut rels = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e)) | Ok(Event::Start(e)) if e.name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attr(&e, b"Id"), attr(&e, b"Target")) {
+                    let external = attr(&e, b"TargetMode").as_deref() == Some("External");
+                    rels.insert(id, Relationship { target, external });
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    rels
+}
+
+/// Read an attribute value by its qualified name
+pub(crate) fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_style() {
+        assert_eq!(classify_style("Heading2", ""), StyleKind::Heading(2));
+        assert_eq!(
+            classify_style("berschrift1", "heading 1"),
+            StyleKind::Heading(1)
+        );
+        assert_eq!(classify_style("TOC1", "toc 1"), StyleKind::Toc);
+        assert_eq!(classify_style("Code", "Code"), StyleKind::Code);
+        assert_eq!(
+            classify_style("IntenseQuote", "Intense Quote"),
+            StyleKind::Quote
+        );
+        assert_eq!(classify_style("BodyText", "Body Text"), StyleKind::Normal);
+    }
+
+    #[test]
+    fn test_numbering_formats() {
+        let xml = r#"<w:numbering>
+            <w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/></w:lvl></w:abstractNum>
+            <w:abstractNum w:abstractNumId="1"><w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/></w:lvl></w:abstractNum>
+            <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+            <w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+        </w:numbering>"#;
+        let numbering = Numbering::parse(xml);
+        assert_eq!(numbering.is_ordered("1", "0"), Some(false));
+        assert_eq!(numbering.is_ordered("2", "0"), Some(true));
+        assert_eq!(numbering.is_ordered("0", "0"), None);
+    }
+
+    #[test]
+    fn test_parse_relationships() {
+        let xml = r#"<Relationships>
+            <Relationship Id="rId1" Type="image" Target="media/image1.png"/>
+            <Relationship Id="rId2" Type="hyperlink" Target="https://example.com" TargetMode="External"/>
+        </Relationships>"#;
+        let rels = parse_relationships(xml);
+        assert_eq!(rels["rId1"].target, "media/image1.png");
+        assert!(!rels["rId1"].external);
+        assert!(rels["rId2"].external);
+    }
+}
@@ -0,0 +1,314 @@
+//! HTTP conversion service (`md2docx serve --api`)
+//!
+//! `POST /convert` takes markdown and [`ConvertOptions`] and answers with
+//! the DOCX. The request is either JSON:
+//!
+//! ```json
+//! { "markdown": "# Title", "options": { "language": "th" } }
+//! ```
+//!
+//! or `multipart/form-data` with a `markdown` part, an optional `options`
+//! part holding the same JSON object, and one part per include, image or
+//! template file, registered at the path given by the part name
+//! (`images/logo.png`). Conversions never read the server's file system.
+//!
+//! Requests are limited in size, at most `max_concurrent` conversions run
+//! at once, and a request that waits or converts longer than `timeout` is
+//! answered with an error.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{DefaultBodyLimit, FromRequest, Multipart, Request, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+
+use crate::options::ConvertOptions;
+use crate::{Error, FileMap, Result};
+
+const DOCX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// Settings of the conversion service
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Address to listen on
+    pub addr: SocketAddr,
+    /// Largest accepted request body, assets included
+    pub max_body_bytes: usize,
+    /// Conversions allowed to run at the same time
+    pub max_concurrent: usize,
+    /// Longest a request may wait for a slot and convert
+    pub timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            max_body_bytes: 32 * 1024 * 1024,
+            max_concurrent: 4,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Shared by all requests
+struct AppState {
+    slots: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+/// JSON body of `POST /convert`
+#[derive(Debug, Deserialize)]
+struct ConvertRequest {
+    markdown: String,
+    #[serde(default)]
+    options: ConvertOptions,
+}
+
+/// A failed request, answered with `{"error": "..."}`
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Routes of the service, with its limits applied
+pub fn router(config: &ServerConfig) -> Router {
+    let state = Arc::new(AppState {
+        slots: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+        timeout: config.timeout,
+    });
+    Router::new()
+        .route("/convert", post(convert))
+        .route("/health", get(health))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .with_state(state)
+}
+
+/// Serve until Ctrl-C, finishing the requests in flight
+pub async fn serve(config: ServerConfig) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+    log::info!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(&config))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            log::info!("Shutting down");
+        })
+        .await?;
+    Ok(())
+}
+
+/// Run the service on a new runtime, blocking until it shuts down
+pub fn run(config: ServerConfig) -> Result<()> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(serve(config))
+}
+
+async fn health() -> &'static str {
+    "ok"
+}
+
+async fn convert(State(state): State<Arc<AppState>>, request: Request) -> Response {
+    match convert_request(&state, request).await {
+        Ok((docx, filename)) => (
+            [
+                (header::CONTENT_TYPE, DOCX_CONTENT_TYPE.to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", filename),
+                ),
+            ],
+            docx,
+        )
+            .into_response(),
+        Err(e) => {
+            log::warn!("Conversion failed: {}", e.message);
+            e.into_response()
+        }
+    }
+}
+
+async fn convert_request(
+    state: &AppState,
+    request: Request,
+) -> std::result::Result<(Vec<u8>, String), ApiError> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+    let (markdown, options, files) = if is_multipart {
+        let multipart = Multipart::from_request(request, &())
+            .await
+            .map_err(|e| ApiError::bad_request(e.body_text()))?;
+        read_multipart(multipart).await?
+    } else {
+        let Json(body) = Json::<ConvertRequest>::from_request(request, &())
+            .await
+            .map_err(|e| ApiError::new(e.status(), e.body_text()))?;
+        (body.markdown, body.options, FileMap::new())
+    };
+    let filename = docx_filename(options.title.as_deref());
+
+    // The deadline covers both waiting for a slot and converting
+    let deadline = tokio::time::Instant::now() + state.timeout;
+    let permit = tokio::time::timeout_at(deadline, state.slots.clone().acquire_owned())
+        .await
+        .map_err(|_| {
+            ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Too many conversions in progress",
+            )
+        })?
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // The blocking task keeps its slot until the conversion really ends,
+    // so conversions that time out still count against the limit
+    let files = Arc::new(files);
+    let task = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        options.convert(&markdown, files)
+    });
+    let docx = tokio::time::timeout_at(deadline, task)
+        .await
+        .map_err(|_| ApiError::new(StatusCode::GATEWAY_TIMEOUT, "Conversion timed out"))?
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ApiError::new(error_status(&e), e.to_string()))?;
+    Ok((docx, filename))
+}
+
+/// Markdown, options and files from a multipart request
+async fn read_multipart(
+    mut multipart: Multipart,
+) -> std::result::Result<(String, ConvertOptions, FileMap), ApiError> {
+    let mut markdown = None;
+    let mut options = ConvertOptions::default();
+    let mut files = FileMap::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::new(e.status(), e.body_text()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let data = field
+            .bytes()
+            .await
+            .map_err(|e| ApiError::new(e.status(), e.body_text()))?;
+        match name.as_str() {
+            "markdown" => {
+                let text = String::from_utf8(data.to_vec())
+                    .map_err(|_| ApiError::bad_request("'markdown' is not valid UTF-8"))?;
+                markdown = Some(text);
+            }
+            "options" => options = parse_options(&data)?,
+            "" => return Err(ApiError::bad_request("Multipart part without a name")),
+            path => files.insert(path, data.to_vec()),
+        }
+    }
+    let markdown = markdown.ok_or_else(|| ApiError::bad_request("Missing 'markdown' part"))?;
+    Ok((markdown, options, files))
+}
+
+/// [`ConvertOptions`] from the JSON text of an `options` part
+fn parse_options(json: &[u8]) -> std::result::Result<ConvertOptions, ApiError> {
+    if json.iter().all(u8::is_ascii_whitespace) {
+        return Ok(ConvertOptions::default());
+    }
+    serde_json::from_slice(json)
+        .map_err(|e| ApiError::bad_request(format!("Invalid options: {}", e)))
+}
+
+/// Status for a failed conversion: the input's fault unless the server
+/// itself failed
+fn error_status(error: &Error) -> StatusCode {
+    match error {
+        Error::Io(_) | Error::Zip(_) | Error::NotImplemented(_) | Error::Validation(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        Error::Located { source, .. } => error_status(source),
+        _ => StatusCode::UNPROCESSABLE_ENTITY,
+    }
+}
+
+/// Download name for the document, from its title when that is a safe one
+fn docx_filename(title: Option<&str>) -> String {
+    let stem: String = title
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .map(|c| if c == ' ' { '-' } else { c })
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    let stem = stem.trim_matches('.');
+    if stem.is_empty() {
+        "document.docx".to_string()
+    } else {
+        format!("{}.docx", stem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options() {
+        assert!(parse_options(b"  \n").unwrap().language.is_none());
+
+        let options = parse_options(br#"{"language": "th", "toc": {"depth": 2}}"#).unwrap();
+        assert_eq!(options.language.as_deref(), Some("th"));
+        assert_eq!(options.toc.and_then(|toc| toc.depth), Some(2));
+
+        let error = parse_options(b"{language: th}").unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_error_status() {
+        assert_eq!(
+            error_status(&Error::Include("missing.md".to_string())),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            error_status(&Error::Zip("write failed".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn test_docx_filename() {
+        assert_eq!(docx_filename(None), "document.docx");
+        assert_eq!(docx_filename(Some("Annual Report")), "Annual-Report.docx");
+        assert_eq!(docx_filename(Some("../\"x\"")), "x.docx");
+        assert_eq!(docx_filename(Some("รายงาน")), "document.docx");
+    }
+}
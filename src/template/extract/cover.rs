@@ -5,8 +5,9 @@
 
 use super::{extract_attribute, extract_run_properties, RunPropertiesDefaults};
 use crate::error::{Error, Result};
-use std::io::Read;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
+use std::sync::Arc;
 
 /// Represents an extracted cover page template
 #[derive(Debug, Clone)]
@@ -108,8 +109,8 @@ pub enum CoverElement {
         height: i64,
         /// Image filename (e.g., "image1.png")
         filename: String,
-        /// Image data bytes (loaded from cover.docx)
-        data: Option<Vec<u8>>,
+        /// Image data bytes (loaded from cover.docx), shared with the output
+        data: Option<Arc<[u8]>>,
     },
 }
 
@@ -222,8 +223,15 @@ pub fn extract(path: &Path) -> Result<CoverTemplate> {
 
     // Read the DOCX file (it's a ZIP archive)
     let file = std::fs::File::open(path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
+    from_archive(zip::ZipArchive::new(file)?)
+}
+
+/// Extract cover template from the bytes of a cover.docx file
+pub fn extract_from_bytes(data: &[u8]) -> Result<CoverTemplate> {
+    from_archive(zip::ZipArchive::new(Cursor::new(data))?)
+}
 
+fn from_archive<R: Read + Seek>(mut archive: zip::ZipArchive<R>) -> Result<CoverTemplate> {
     // Read document.xml
     let mut document_xml = String::new();
     {
@@ -270,7 +278,7 @@ pub fn extract(path: &Path) -> Result<CoverTemplate> {
                 // So full path is "word/media/image1.png"
                 let full_path = format!("word/{}", img_path);
                 if let Ok(img_data) = read_archive_file_bytes(&mut archive, &full_path) {
-                    *data = Some(img_data);
+                    *data = Some(img_data.into());
                 }
             }
         }
@@ -331,7 +339,7 @@ pub fn extract(path: &Path) -> Result<CoverTemplate> {
                     width,
                     height,
                     filename,
-                    data: Some(img_data),
+                    data: Some(img_data.into()),
                 });
             }
         }
@@ -341,7 +349,9 @@ pub fn extract(path: &Path) -> Result<CoverTemplate> {
     let background_color = extract_background_color(&document_xml);
 
     // Extract header/footer from cover.docx if present
-    let header_footer = super::header_footer::extract(path).ok().filter(|hf| !hf.is_empty());
+    let header_footer = super::header_footer::from_archive(&mut archive)
+        .ok()
+        .filter(|hf| !hf.is_empty());
 
     Ok(CoverTemplate {
         background_color,
@@ -355,7 +365,10 @@ pub fn extract(path: &Path) -> Result<CoverTemplate> {
 }
 
 /// Read a file from the ZIP archive as string
-fn read_archive_file(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<String> {
+fn read_archive_file<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<String> {
     let mut content = String::new();
     if let Ok(mut file) = archive.by_name(name) {
         file.read_to_string(&mut content)?;
@@ -364,8 +377,8 @@ fn read_archive_file(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -
 }
 
 /// Read a file from the ZIP archive as bytes
-fn read_archive_file_bytes(
-    archive: &mut zip::ZipArchive<std::fs::File>,
+fn read_archive_file_bytes<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
     name: &str,
 ) -> Result<Vec<u8>> {
     let mut content = Vec::new();
@@ -2,8 +2,9 @@
 
 use crate::error::{Error, Result};
 use std::collections::HashMap;
-use std::io::Read;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
+use std::sync::Arc;
 
 /// Represents an extracted header/footer template
 #[derive(Debug, Clone, Default)]
@@ -42,8 +43,8 @@ pub struct HeaderFooterContent {
 pub struct MediaFile {
     /// Filename (e.g., "image1.png")
     pub filename: String,
-    /// File content bytes
-    pub data: Vec<u8>,
+    /// File content bytes, shared rather than copied into each output
+    pub data: Arc<[u8]>,
     /// Content type (e.g., "image/png")
     pub content_type: String,
 }
@@ -100,17 +101,27 @@ pub fn extract(path: &Path) -> Result<HeaderFooterTemplate> {
     }
 
     let file = std::fs::File::open(path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
+    from_archive(&mut zip::ZipArchive::new(file)?)
+}
+
+/// Extract header/footer template from the bytes of a header-footer.docx file
+pub fn extract_from_bytes(data: &[u8]) -> Result<HeaderFooterTemplate> {
+    from_archive(&mut zip::ZipArchive::new(Cursor::new(data))?)
+}
 
+/// Extract header/footer template from an opened DOCX archive
+pub(super) fn from_archive<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<HeaderFooterTemplate> {
     // 1. Read document.xml.rels to find header/footer files
-    let doc_rels = read_archive_file(&mut archive, "word/_rels/document.xml.rels")?;
+    let doc_rels = read_archive_file(archive, "word/_rels/document.xml.rels")?;
 
     // 2. Parse relationships to find header/footer file mappings
     let _header_files = find_header_footer_files(&doc_rels, "header");
     let _footer_files = find_header_footer_files(&doc_rels, "footer");
 
     // 3. Read document.xml to check for w:titlePg (different first page) and header/footer references
-    let document_xml = read_archive_file(&mut archive, "word/document.xml")?;
+    let document_xml = read_archive_file(archive, "word/document.xml")?;
     let different_first_page =
         document_xml.contains("<w:titlePg") || document_xml.contains("<w:titlePg/>");
 
@@ -123,29 +134,29 @@ pub fn extract(path: &Path) -> Result<HeaderFooterTemplate> {
         categorize_header_footer_files(&document_xml, &doc_rels, "footer");
 
     // 5. Extract each header/footer content
-    let default_header = extract_header_footer_content(&mut archive, &default_header_file, "word")?;
+    let default_header = extract_header_footer_content(archive, &default_header_file, "word")?;
     let first_page_header = if different_first_page {
-        extract_header_footer_content(&mut archive, &first_header_file, "word")?
+        extract_header_footer_content(archive, &first_header_file, "word")?
     } else {
         None
     };
 
-    let default_footer = extract_header_footer_content(&mut archive, &default_footer_file, "word")?;
+    let default_footer = extract_header_footer_content(archive, &default_footer_file, "word")?;
     let first_page_footer = if different_first_page {
-        extract_header_footer_content(&mut archive, &first_footer_file, "word")?
+        extract_header_footer_content(archive, &first_footer_file, "word")?
     } else {
         None
     };
 
     // 6. Collect all media files referenced by headers/footers
     let mut media = Vec::new();
-    collect_media_files(&mut archive, &default_header, &mut media)?;
-    collect_media_files(&mut archive, &first_page_header, &mut media)?;
-    collect_media_files(&mut archive, &default_footer, &mut media)?;
-    collect_media_files(&mut archive, &first_page_footer, &mut media)?;
+    collect_media_files(archive, &default_header, &mut media)?;
+    collect_media_files(archive, &first_page_header, &mut media)?;
+    collect_media_files(archive, &default_footer, &mut media)?;
+    collect_media_files(archive, &first_page_footer, &mut media)?;
 
     // 7. Extract Header/Footer style tab stops from styles.xml
-    let (header_style_tabs, footer_style_tabs) = extract_style_tabs(&mut archive)?;
+    let (header_style_tabs, footer_style_tabs) = extract_style_tabs(archive)?;
 
     Ok(HeaderFooterTemplate {
         default_header,
@@ -424,7 +435,7 @@ fn collect_media_files<R: Read + std::io::Seek>(
 
             media.push(MediaFile {
                 filename,
-                data,
+                data: data.into(),
                 content_type,
             });
         }
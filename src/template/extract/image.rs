@@ -5,7 +5,7 @@
 
 use super::{extract_attribute, extract_run_properties, RunPropertiesDefaults};
 use crate::error::{Error, Result};
-use std::io::Read;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 use zip::ZipArchive;
 
@@ -226,7 +226,16 @@ pub fn extract(path: &Path) -> Result<ImageTemplate> {
     // Open DOCX as ZIP
     let file = std::fs::File::open(path)
         .map_err(|e| Error::Template(format!("Failed to open image template: {}", e)))?;
-    let mut archive = ZipArchive::new(file)
+    from_reader(file)
+}
+
+/// Extract image template from the bytes of a image.docx file
+pub fn extract_from_bytes(data: &[u8]) -> Result<ImageTemplate> {
+    from_reader(Cursor::new(data))
+}
+
+fn from_reader<R: Read + Seek>(reader: R) -> Result<ImageTemplate> {
+    let mut archive = ZipArchive::new(reader)
         .map_err(|e| Error::Template(format!("Failed to read image template as ZIP: {}", e)))?;
 
     // Read word/document.xml
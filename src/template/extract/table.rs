@@ -9,7 +9,7 @@
 
 use super::{extract_attribute, extract_run_properties, RunPropertiesDefaults};
 use crate::error::{Error, Result};
-use std::io::Read;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 use zip::ZipArchive;
 
@@ -164,6 +164,25 @@ pub struct BorderStyles {
     pub inside_v: BorderStyle,
 }
 
+impl BorderStyles {
+    /// No borders on any side, for tables used only for layout
+    pub fn none() -> Self {
+        let none = BorderStyle {
+            style: "none".to_string(),
+            color: "auto".to_string(),
+            width: 0,
+        };
+        Self {
+            top: none.clone(),
+            bottom: none.clone(),
+            left: none.clone(),
+            right: none.clone(),
+            inside_h: none.clone(),
+            inside_v: none,
+        }
+    }
+}
+
 /// Individual border style
 #[derive(Debug, Clone)]
 pub struct BorderStyle {
@@ -324,7 +343,16 @@ pub fn extract(path: &Path) -> Result<TableTemplate> {
     // Open DOCX as ZIP
     let file = std::fs::File::open(path)
         .map_err(|e| Error::Template(format!("Failed to open table template: {}", e)))?;
-    let mut archive = ZipArchive::new(file)
+    from_reader(file)
+}
+
+/// Extract table template from the bytes of a table.docx file
+pub fn extract_from_bytes(data: &[u8]) -> Result<TableTemplate> {
+    from_reader(Cursor::new(data))
+}
+
+fn from_reader<R: Read + Seek>(reader: R) -> Result<TableTemplate> {
+    let mut archive = ZipArchive::new(reader)
         .map_err(|e| Error::Template(format!("Failed to read table template as ZIP: {}", e)))?;
 
     // Read word/document.xml
@@ -46,13 +46,26 @@ pub use placeholder::{
 };
 
 use crate::error::{Error, Result};
+use crate::vfs::FileMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Template files that can supply styles, theme and numbering, in order
+const STYLE_SOURCES: [&str; 5] = [
+    "styles.docx",
+    "header-footer.docx",
+    "cover.docx",
+    "table.docx",
+    "image.docx",
+];
 
 /// Represents a directory containing template DOCX files
 #[derive(Debug, Clone)]
 pub struct TemplateDir {
     /// Path to the template directory
     pub path: PathBuf,
+    /// Files to read the templates from instead of the file system
+    files: Option<Arc<FileMap>>,
 }
 
 impl TemplateDir {
@@ -88,12 +101,32 @@ impl TemplateDir {
 
         Ok(Self {
             path: path.to_path_buf(),
+            files: None,
         })
     }
 
+    /// Use a template directory registered in an in-memory file map
+    ///
+    /// Template files are looked up as `path/<name>.docx` in `files`;
+    /// missing ones are skipped just like on disk.
+    pub fn from_files(path: &Path, files: Arc<FileMap>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            files: Some(files),
+        }
+    }
+
     /// Check if a template file exists
     fn has_file(&self, filename: &str) -> bool {
-        self.path.join(filename).exists()
+        match &self.files {
+            Some(files) => files.contains(&self.file_path(filename)),
+            None => self.file_path(filename).exists(),
+        }
+    }
+
+    /// Bytes of a template file held in the file map
+    fn file_data(&self, filename: &str) -> Option<Arc<[u8]>> {
+        self.files.as_ref()?.get(&self.file_path(filename))
     }
 
     /// Get the path to a template file
@@ -109,6 +142,9 @@ impl TemplateDir {
             return Ok(None);
         }
 
+        if let Some(data) = self.file_data("cover.docx") {
+            return extract::cover::extract_from_bytes(&data).map(Some);
+        }
         let path = self.file_path("cover.docx");
         extract::extract_cover(&path).map(Some)
     }
@@ -121,6 +157,9 @@ impl TemplateDir {
             return Ok(None);
         }
 
+        if let Some(data) = self.file_data("table.docx") {
+            return extract::table::extract_from_bytes(&data).map(Some);
+        }
         let path = self.file_path("table.docx");
         extract::extract_table(&path).map(Some)
     }
@@ -133,6 +172,9 @@ impl TemplateDir {
             return Ok(None);
         }
 
+        if let Some(data) = self.file_data("image.docx") {
+            return extract::image::extract_from_bytes(&data).map(Some);
+        }
         let path = self.file_path("image.docx");
         extract::extract_image(&path).map(Some)
     }
@@ -145,10 +187,28 @@ impl TemplateDir {
             return Ok(None);
         }
 
+        if let Some(data) = self.file_data("header-footer.docx") {
+            return extract::header_footer::extract_from_bytes(&data).map(Some);
+        }
         let path = self.file_path("header-footer.docx");
         extract::extract_header_footer(&path).map(Some)
     }
 
+    /// Bytes of the template file whose styles, theme and numbering apply
+    ///
+    /// That is `styles.docx`, or else the first of `header-footer.docx`,
+    /// `cover.docx`, `table.docx` and `image.docx` present; `None` if there
+    /// is none.
+    pub fn style_source(&self) -> Result<Option<Vec<u8>>> {
+        let Some(name) = STYLE_SOURCES.into_iter().find(|name| self.has_file(name)) else {
+            return Ok(None);
+        };
+        match self.file_data(name) {
+            Some(data) => Ok(Some(data.to_vec())),
+            None => Ok(Some(std::fs::read(self.file_path(name))?)),
+        }
+    }
+
     /// Load all available templates
     ///
     /// Returns a `TemplateSet` containing all extracted templates
@@ -248,6 +308,35 @@ mod tests {
         assert!(!template.has_file("table.docx"));
     }
 
+    #[test]
+    fn test_from_files() {
+        let mut files = FileMap::new();
+        files.insert("my-template/table.docx", b"not a docx".to_vec());
+
+        let template = TemplateDir::from_files(Path::new("my-template"), Arc::new(files));
+        assert!(template.has_file("table.docx"));
+        assert!(!template.has_file("cover.docx"));
+        assert!(template.extract_cover().unwrap().is_none());
+        assert!(template.extract_table().is_err());
+    }
+
+    #[test]
+    fn test_style_source() {
+        let mut files = FileMap::new();
+        files.insert("my-template/table.docx", b"table".to_vec());
+        files.insert("my-template/cover.docx", b"cover".to_vec());
+
+        let template = TemplateDir::from_files(Path::new("my-template"), Arc::new(files.clone()));
+        assert_eq!(template.style_source().unwrap().unwrap(), b"cover");
+
+        files.insert("my-template/styles.docx", b"styles".to_vec());
+        let template = TemplateDir::from_files(Path::new("my-template"), Arc::new(files));
+        assert_eq!(template.style_source().unwrap().unwrap(), b"styles");
+
+        let empty = TemplateDir::from_files(Path::new("other"), Arc::new(FileMap::new()));
+        assert!(empty.style_source().unwrap().is_none());
+    }
+
     #[test]
     fn test_template_set_empty() {
         let set = TemplateSet::default();
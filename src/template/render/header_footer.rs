@@ -7,6 +7,10 @@
 //!   - {{numpages}} -> NUMPAGES field
 //!   - {{chapter}} -> STYLEREF "Heading 1" field
 
+use crate::docx::ooxml::xml_writer::{escape, fragment, XmlWriter};
+use crate::docx::ooxml::{
+    write_simple_field, CHAPTER_AUTHOR_FIELD, CHAPTER_DATE_FIELD, CHAPTER_FIELD,
+};
 use crate::error::Result;
 use crate::template::extract::header_footer::{HeaderFooterTemplate, MediaFile};
 use regex::Regex;
@@ -69,6 +73,7 @@ pub(crate) struct RenderedHeaderFooter {
 /// - {{page}} -> PAGE field
 /// - {{numpages}} -> NUMPAGES field
 /// - {{chapter}} -> STYLEREF "Heading 1" field
+/// - {{chapter_author}} / {{chapter_date}} -> STYLEREF "Chapter Author" / "Chapter Date" fields
 pub(crate) fn render_header_footer(
     content: &crate::template::extract::header_footer::HeaderFooterContent,
     ctx: &HeaderFooterContext,
@@ -81,15 +86,15 @@ pub(crate) fn render_header_footer(
     xml = consolidate_fragmented_placeholders(&xml);
 
     // Replace static placeholders
-    xml = xml.replace("{{title}}", &xml_escape(&ctx.title));
-    xml = xml.replace("{{subtitle}}", &xml_escape(&ctx.subtitle));
-    xml = xml.replace("{{author}}", &xml_escape(&ctx.author));
-    xml = xml.replace("{{date}}", &xml_escape(&ctx.date));
+    xml = xml.replace("{{title}}", &escape(&ctx.title));
+    xml = xml.replace("{{subtitle}}", &escape(&ctx.subtitle));
+    xml = xml.replace("{{author}}", &escape(&ctx.author));
+    xml = xml.replace("{{date}}", &escape(&ctx.date));
 
     // Replace dynamic placeholders with Word fields
-    xml = replace_page_placeholder(&xml);
-    xml = replace_numpages_placeholder(&xml);
-    xml = replace_chapter_placeholder(&xml);
+    xml = replace_page_placeholder(&xml)?;
+    xml = replace_numpages_placeholder(&xml)?;
+    xml = replace_chapter_placeholder(&xml)?;
 
     // Remap relationship IDs for media files and update XML
     let (media, rid_replacements) =
@@ -176,7 +181,7 @@ pub(crate) fn render_first_page_footer(
 /// Creates a .rels file that maps relationship IDs to media file targets.
 /// This is required for images in headers/footers to display correctly.
 #[allow(dead_code)]
-pub(crate) fn generate_header_footer_rels_xml(media: &[(String, MediaFile)]) -> Vec<u8> {
+pub(crate) fn generate_header_footer_rels_xml(media: &[(String, MediaFile)]) -> Result<Vec<u8>> {
     generate_header_footer_rels_xml_with_prefix(media, "")
 }
 
@@ -187,37 +192,34 @@ pub(crate) fn generate_header_footer_rels_xml(media: &[(String, MediaFile)]) ->
 pub(crate) fn generate_header_footer_rels_xml_with_prefix(
     media: &[(String, MediaFile)],
     prefix: &str,
-) -> Vec<u8> {
-    let mut xml = String::from(
-        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-"#,
-    );
+) -> Result<Vec<u8>> {
+    let mut w = XmlWriter::indented(Vec::new());
+    w.declaration()?;
+    w.start(
+        "Relationships",
+        &[(
+            "xmlns",
+            "http://schemas.openxmlformats.org/package/2006/relationships",
+        )],
+    )?;
 
     for (r_id, media_file) in media {
-        let filename = if prefix.is_empty() {
-            media_file.filename.clone()
-        } else {
-            format!("{}{}", prefix, media_file.filename)
-        };
-        xml.push_str(&format!(
-            r#"  <Relationship Id="{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/{}"/>
-"#,
-            r_id, filename
-        ));
+        let target = format!("media/{}{}", prefix, media_file.filename);
+        w.empty(
+            "Relationship",
+            &[
+                ("Id", r_id),
+                (
+                    "Type",
+                    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image",
+                ),
+                ("Target", &target),
+            ],
+        )?;
     }
 
-    xml.push_str("</Relationships>");
-    xml.into_bytes()
-}
-
-/// Escape XML special characters
-fn xml_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&apos;")
+    w.end("Relationships")?;
+    Ok(w.into_inner())
 }
 
 /// Consolidate fragmented placeholders in Word XML
@@ -288,23 +290,50 @@ fn try_consolidate_three_run_pattern(xml: &str, placeholder: &str, full: &str) -
 }
 
 /// Replace {{page}} placeholder with Word PAGE field
-fn replace_page_placeholder(xml: &str) -> String {
+fn replace_page_placeholder(xml: &str) -> Result<String> {
     // Note: consolidate_fragmented_placeholders() is called before this,
     // so placeholders should already be in a single run by this point.
-    let page_field = r#"</w:t></w:r><w:fldSimple w:instr=" PAGE "><w:r><w:t>1</w:t></w:r></w:fldSimple><w:r><w:t xml:space="preserve">"#;
-    xml.replace("{{page}}", page_field)
+    replace_with_field(xml, "{{page}}", " PAGE ", "1", false)
 }
 
 /// Replace {{numpages}} placeholder with Word NUMPAGES field
-fn replace_numpages_placeholder(xml: &str) -> String {
-    let numpages_field = r#"</w:t></w:r><w:fldSimple w:instr=" NUMPAGES "><w:r><w:t>1</w:t></w:r></w:fldSimple><w:r><w:t xml:space="preserve">"#;
-    xml.replace("{{numpages}}", numpages_field)
+fn replace_numpages_placeholder(xml: &str) -> Result<String> {
+    replace_with_field(xml, "{{numpages}}", " NUMPAGES ", "1", false)
 }
 
-/// Replace {{chapter}} placeholder with Word STYLEREF field
-fn replace_chapter_placeholder(xml: &str) -> String {
-    let chapter_field = r#"</w:t></w:r><w:fldSimple w:instr="STYLEREF &quot;Heading 1&quot; \* MERGEFORMAT"><w:r><w:rPr><w:noProof/></w:rPr><w:t>Chapter</w:t></w:r></w:fldSimple><w:r><w:t xml:space="preserve">"#;
-    xml.replace("{{chapter}}", chapter_field)
+/// Replace {{chapter}}, {{chapter_author}} and {{chapter_date}} placeholders with Word STYLEREF fields
+fn replace_chapter_placeholder(xml: &str) -> Result<String> {
+    let xml = replace_with_field(xml, "{{chapter}}", CHAPTER_FIELD, "Chapter", true)?;
+    let xml = replace_with_field(
+        &xml,
+        "{{chapter_author}}",
+        CHAPTER_AUTHOR_FIELD,
+        "Author",
+        true,
+    )?;
+    replace_with_field(&xml, "{{chapter_date}}", CHAPTER_DATE_FIELD, "Date", true)
+}
+
+/// Replace a placeholder inside a `w:t` with a simple field
+///
+/// The placeholder's run is closed before the field and a new run is
+/// opened after it for any text that followed the placeholder.
+fn replace_with_field(
+    xml: &str,
+    placeholder: &str,
+    instr: &str,
+    cached: &str,
+    no_proof: bool,
+) -> Result<String> {
+    if !xml.contains(placeholder) {
+        return Ok(xml.to_string());
+    }
+    let field = fragment(|w| {
+        w.raw("</w:t></w:r>")?;
+        write_simple_field(w, instr, cached, no_proof)?;
+        w.raw(r#"<w:r><w:t xml:space="preserve">"#)
+    })?;
+    Ok(xml.replace(placeholder, &field))
 }
 
 /// Media remapping result containing remapped media files and ID replacements
@@ -362,16 +391,16 @@ mod tests {
 
     #[test]
     fn test_xml_escape() {
-        assert_eq!(xml_escape("Hello & World"), "Hello &amp; World");
-        assert_eq!(xml_escape("<tag>"), "&lt;tag&gt;");
-        assert_eq!(xml_escape("\"quoted\""), "&quot;quoted&quot;");
-        assert_eq!(xml_escape("'apostrophe'"), "&apos;apostrophe&apos;");
+        assert_eq!(escape("Hello & World"), "Hello &amp; World");
+        assert_eq!(escape("<tag>"), "&lt;tag&gt;");
+        assert_eq!(escape("\"quoted\""), "&quot;quoted&quot;");
+        assert_eq!(escape("'apostrophe'"), "&apos;apostrophe&apos;");
     }
 
     #[test]
     fn test_replace_page_placeholder() {
         let xml = r#"<w:p><w:r><w:t>Page {{page}}</w:t></w:r></w:p>"#;
-        let result = replace_page_placeholder(xml);
+        let result = replace_page_placeholder(xml).unwrap();
         assert!(result.contains("PAGE"));
         assert!(!result.contains("{{page}}"));
     }
@@ -379,7 +408,7 @@ mod tests {
     #[test]
     fn test_replace_numpages_placeholder() {
         let xml = r#"<w:p><w:r><w:t>Total: {{numpages}}</w:t></w:r></w:p>"#;
-        let result = replace_numpages_placeholder(xml);
+        let result = replace_numpages_placeholder(xml).unwrap();
         assert!(result.contains("NUMPAGES"));
         assert!(!result.contains("{{numpages}}"));
     }
@@ -387,9 +416,16 @@ mod tests {
     #[test]
     fn test_replace_chapter_placeholder() {
         let xml = r#"<w:p><w:r><w:t>Chapter: {{chapter}}</w:t></w:r></w:p>"#;
-        let result = replace_chapter_placeholder(xml);
+        let result = replace_chapter_placeholder(xml).unwrap();
         assert!(result.contains("STYLEREF"));
         assert!(!result.contains("{{chapter}}"));
+
+        let xml =
+            r#"<w:p><w:r><w:t>Edited by {{chapter_author}} on {{chapter_date}}</w:t></w:r></w:p>"#;
+        let result = replace_chapter_placeholder(xml).unwrap();
+        assert!(result.contains("Chapter Author"));
+        assert!(result.contains("Chapter Date"));
+        assert!(!result.contains("{{chapter_"));
     }
 
     #[test]
@@ -533,7 +569,7 @@ mod tests {
                 "rId1".to_string(),
                 MediaFile {
                     filename: "image1.png".to_string(),
-                    data: vec![1, 2, 3],
+                    data: vec![1, 2, 3].into(),
                     content_type: "image/png".to_string(),
                 },
             ),
@@ -541,13 +577,13 @@ mod tests {
                 "rId2".to_string(),
                 MediaFile {
                     filename: "logo.jpg".to_string(),
-                    data: vec![4, 5, 6],
+                    data: vec![4, 5, 6].into(),
                     content_type: "image/jpeg".to_string(),
                 },
             ),
         ];
 
-        let xml = generate_header_footer_rels_xml(&media);
+        let xml = generate_header_footer_rels_xml(&media).unwrap();
         let xml_str = String::from_utf8(xml).unwrap();
 
         assert!(xml_str.contains("<?xml version=\"1.0\""));
@@ -563,7 +599,7 @@ mod tests {
     #[test]
     fn test_generate_header_footer_rels_xml_empty() {
         let media: Vec<(String, MediaFile)> = vec![];
-        let xml = generate_header_footer_rels_xml(&media);
+        let xml = generate_header_footer_rels_xml(&media).unwrap();
         let xml_str = String::from_utf8(xml).unwrap();
 
         assert!(xml_str.contains("<?xml version=\"1.0\""));
@@ -132,7 +132,7 @@ fn create_styled_paragraph(
     let mut run = Run::new(text);
 
     // Apply font
-    run.font = Some(font_family);
+    run.font = Some(font_family.into());
 
     // Apply size
     run.size = Some(font_size);
@@ -0,0 +1,300 @@
+//! Reading generated documents back for tests
+//!
+//! [`open_and_extract_text`] unzips a DOCX and returns the text of its body
+//! as headings, paragraphs and tables, so tests can assert on what a
+//! document says without unzipping it and walking the XML by hand.
+//! Formatting is dropped: `Hello **world**` reads back as `Hello world`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use md2docx::verify::open_and_extract_text;
+//!
+//! let docx = md2docx::markdown_to_docx("# Intro\n\nHello **world**.\n").unwrap();
+//! let text = open_and_extract_text(&docx).unwrap();
+//! assert_eq!(text.headings(), vec![(1, "Intro")]);
+//! assert!(text.paragraphs().contains(&"Hello world."));
+//! ```
+
+use std::io::{Cursor, Read};
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+use crate::reverse::parts::{attr, StyleKind, Styles};
+use crate::{Error, Result};
+
+/// A top-level block of the document body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Paragraph in a heading (or `Title`) style
+    Heading { level: u8, text: String },
+    /// Any other paragraph with text: body text, list items, code lines,
+    /// captions and table of contents entries
+    Paragraph {
+        /// Paragraph style id, if one is set
+        style: Option<String>,
+        text: String,
+    },
+    /// Table as rows of cell text; paragraphs in a cell are joined by `\n`
+    Table(Vec<Vec<String>>),
+}
+
+/// Text of a document body, in document order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractedText {
+    pub blocks: Vec<Block>,
+}
+
+impl ExtractedText {
+    /// Level and text of every heading
+    pub fn headings(&self) -> Vec<(u8, &str)> {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Heading { level, text } => Some((*level, text.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Text of every paragraph that isn't a heading or in a table
+    pub fn paragraphs(&self) -> Vec<&str> {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Paragraph { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every table, as rows of cell text
+    pub fn tables(&self) -> Vec<&[Vec<String>]> {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Table(rows) => Some(rows.as_slice()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether any heading, paragraph or cell contains `needle`
+    pub fn contains(&self, needle: &str) -> bool {
+        self.blocks.iter().any(|block| match block {
+            Block::Heading { text, .. } | Block::Paragraph { text, .. } => text.contains(needle),
+            Block::Table(rows) => rows.iter().flatten().any(|cell| cell.contains(needle)),
+        })
+    }
+}
+
+/// Table being collected
+#[derive(Default)]
+struct TableState {
+    rows: Vec<Vec<String>>,
+    cell: Option<String>,
+}
+
+/// Unzip a DOCX and read the headings, paragraphs and tables of its body
+///
+/// Deleted text (`w:del`) and field instructions are skipped; tabs read as
+/// `\t` and line breaks as `\n`. Headers, footers and notes are not read.
+pub fn open_and_extract_text(docx: &[u8]) -> Result<ExtractedText> {
+    let mut archive = ZipArchive::new(Cursor::new(docx))?;
+    let document = read_part(&mut archive, "word/document.xml")?
+        .ok_or_else(|| Error::Parse("DOCX has no word/document.xml".to_string()))?;
+    let styles = read_part(&mut archive, "word/styles.xml")?
+        .map(|xml| Styles::parse(&xml))
+        .unwrap_or_default();
+    Ok(ExtractedText {
+        blocks: extract_blocks(&document, &styles),
+    })
+}
+
+fn read_part(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+fn extract_blocks(xml: &str, styles: &Styles) -> Vec<Block> {
+    let mut reader = Reader::from_str(xml);
+    let mut blocks = Vec::new();
+    let mut tables: Vec<TableState> = Vec::new();
+    // Style and text of the paragraph being read
+    let mut para: Option<(Option<String>, String)> = None;
+    let mut in_run = false;
+    let mut in_text = false;
+    let mut skip_depth = 0usize;
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(event) => event,
+        };
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_start = matches!(event, Event::Start(_));
+                match e.name().as_ref() {
+                    b"w:del" | b"w:delText" | b"w:instrText" if is_start => skip_depth += 1,
+                    _ if skip_depth > 0 => {}
+                    b"w:p" if is_start => para = Some((None, String::new())),
+                    b"w:pStyle" => {
+                        if let Some((style, _)) = para.as_mut() {
+                            *style = attr(e, b"w:val");
+                        }
+                    }
+                    b"w:r" if is_start => in_run = true,
+                    b"w:t" if is_start => in_text = true,
+                    // Tab stops in w:pPr are w:tab too
+                    b"w:tab" if in_run => push_text(&mut para, "\t"),
+                    b"w:br" | b"w:cr" if in_run => push_text(&mut para, "\n"),
+                    b"w:tbl" if is_start => tables.push(TableState::default()),
+                    b"w:tr" if is_start => {
+                        if let Some(table) = tables.last_mut() {
+                            table.rows.push(Vec::new());
+                        }
+                    }
+                    b"w:tc" if is_start => {
+                        if let Some(table) = tables.last_mut() {
+                            table.cell = Some(String::new());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) if in_text && skip_depth == 0 => {
+                if let Ok(text) = t.unescape() {
+                    push_text(&mut para, &text);
+                }
+            }
+            Event::End(ref e) => match e.name().as_ref() {
+                b"w:del" | b"w:delText" | b"w:instrText" => {
+                    skip_depth = skip_depth.saturating_sub(1)
+                }
+                _ if skip_depth > 0 => {}
+                b"w:t" => in_text = false,
+                b"w:r" => in_run = false,
+                b"w:p" => {
+                    if let Some((style, text)) = para.take() {
+                        finish_paragraph(style, text, styles, &mut tables, &mut blocks);
+                    }
+                }
+                b"w:tc" => {
+                    if let Some(table) = tables.last_mut() {
+                        if let (Some(cell), Some(row)) = (table.cell.take(), table.rows.last_mut())
+                        {
+                            row.push(cell);
+                        }
+                    }
+                }
+                b"w:tbl" => {
+                    if let Some(table) = tables.pop() {
+                        let rows: Vec<Vec<String>> =
+                            table.rows.into_iter().filter(|r| !r.is_empty()).collect();
+                        match tables.last_mut().and_then(|t| t.cell.as_mut()) {
+                            // Nested tables are flattened into the outer cell
+                            Some(cell) => {
+                                for row in rows {
+                                    append_line(cell, &row.join(" "));
+                                }
+                            }
+                            None if !rows.is_empty() => blocks.push(Block::Table(rows)),
+                            None => {}
+                        }
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+fn push_text(para: &mut Option<(Option<String>, String)>, text: &str) {
+    if let Some((_, content)) = para.as_mut() {
+        content.push_str(text);
+    }
+}
+
+fn append_line(cell: &mut String, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if !cell.is_empty() {
+        cell.push('\n');
+    }
+    cell.push_str(text);
+}
+
+/// Add a finished paragraph to its table cell or as a block
+fn finish_paragraph(
+    style: Option<String>,
+    text: String,
+    styles: &Styles,
+    tables: &mut [TableState],
+    blocks: &mut Vec<Block>,
+) {
+    if let Some(cell) = tables.last_mut().and_then(|t| t.cell.as_mut()) {
+        append_line(cell, text.trim());
+        return;
+    }
+    let kind = style.as_deref().map(|s| styles.kind(s));
+    let block = match kind {
+        Some(StyleKind::Heading(level)) if !text.trim().is_empty() => Block::Heading {
+            level,
+            text: text.trim().to_string(),
+        },
+        // Code keeps its indentation
+        Some(StyleKind::Code) => Block::Paragraph { style, text },
+        _ if !text.trim().is_empty() => Block::Paragraph {
+            style,
+            text: text.trim().to_string(),
+        },
+        _ => return,
+    };
+    blocks.push(block);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_and_extract_text() {
+        let md = "# Guide\n\nSome **bold** and `code` text.\n\n## Setup\n\n- First item\n\n| Name | Value |\n|------|-------|\n| a | 1 |\n\n```\nlet x = 1;\n```\n";
+        let docx = crate::markdown_to_docx(md).unwrap();
+        let text = open_and_extract_text(&docx).unwrap();
+
+        assert_eq!(text.headings(), vec![(1, "Guide"), (2, "Setup")]);
+        let paragraphs = text.paragraphs();
+        assert!(paragraphs.contains(&"Some bold and code text."));
+        assert!(paragraphs.contains(&"First item"));
+        assert!(paragraphs.contains(&"let x = 1;"));
+        assert_eq!(
+            text.tables(),
+            vec![
+                &[
+                    vec!["Name".to_string(), "Value".to_string()],
+                    vec!["a".to_string(), "1".to_string()],
+                ][..]
+            ]
+        );
+        assert!(text.contains("Value"));
+        assert!(!text.contains("**"));
+    }
+
+    #[test]
+    fn test_extract_rejects_non_docx() {
+        assert!(open_and_extract_text(b"not a zip").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! In-memory file map standing in for the file system
+//!
+//! Browser builds have no file system to read includes, local images or
+//! template directories from. The host registers those files here instead,
+//! and [`IncludeResolver`](crate::IncludeResolver), image embedding and
+//! [`TemplateDir`](crate::TemplateDir) read them from the map.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Files registered by path, looked up in place of the file system
+#[derive(Debug, Clone, Default)]
+pub struct FileMap {
+    files: HashMap<PathBuf, Arc<[u8]>>,
+}
+
+impl FileMap {
+    /// Create an empty file map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a file, replacing any earlier file at the same path
+    pub fn insert(&mut self, path: impl AsRef<Path>, data: impl Into<Arc<[u8]>>) {
+        self.files.insert(normalize(path.as_ref()), data.into());
+    }
+
+    /// Bytes of the file at `path`
+    pub fn get(&self, path: &Path) -> Option<Arc<[u8]>> {
+        self.files.get(&normalize(path)).cloned()
+    }
+
+    /// Whether a file is registered at `path`
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+    /// Contents of the file at `path` as UTF-8 text
+    pub fn read_to_string(&self, path: &Path) -> Option<String> {
+        self.get(path)
+            .map(|data| String::from_utf8_lossy(&data).into_owned())
+    }
+
+    /// Number of registered files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether no files are registered
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// Lexically clean a path so `docs/./a/../b.md` and `docs/b.md` match
+///
+/// Stands in for `canonicalize`, which needs the file to exist on disk.
+/// Paths are relative to the map's root: `/docs/b.md` is `docs/b.md`.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_normalizes_paths() {
+        let mut files = FileMap::new();
+        files.insert("./docs/chapters/../intro.md", b"# Intro".to_vec());
+
+        assert!(files.contains(Path::new("docs/intro.md")));
+        assert!(files.contains(Path::new("/docs/intro.md")));
+        assert_eq!(
+            files
+                .read_to_string(Path::new("docs/./intro.md"))
+                .as_deref(),
+            Some("# Intro")
+        );
+        assert!(files.get(Path::new("intro.md")).is_none());
+        assert_eq!(files.len(), 1);
+    }
+}
@@ -1 +1,81 @@
-//! WASM bindings - TODO: Implement
+//! WASM bindings
+//!
+//! Browsers have no file system, so the files a document pulls in —
+//! includes, code listings, local images and template `.docx` files — are
+//! registered with a [`Converter`] first and read from memory while
+//! converting.
+//!
+//! ```javascript
+//! import init, { Converter } from 'md2docx';
+//!
+//! await init();
+//! const converter = new Converter();
+//! converter.addTextFile('docs/intro.md', '# Intro\n\n![Logo](img/logo.png)');
+//! converter.addFile('docs/img/logo.png', logoBytes);
+//! const docx = converter.convert('{!include:intro.md}', {
+//!     basePath: 'docs',
+//!     toc: { depth: 3 },
+//! });
+//! ```
+
+mod options;
+
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::FileMap;
+
+pub use options::JsConvertOptions;
+
+/// Convert markdown to DOCX bytes
+#[wasm_bindgen(js_name = markdownToDocx)]
+pub fn markdown_to_docx(
+    markdown: &str,
+    options: Option<JsConvertOptions>,
+) -> Result<Vec<u8>, JsError> {
+    Converter::default().convert(markdown, options)
+}
+
+/// Converter reading includes, images and templates from registered files
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct Converter {
+    files: Arc<FileMap>,
+}
+
+#[wasm_bindgen]
+impl Converter {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        console_error_panic_hook::set_once();
+        Self::default()
+    }
+
+    /// Register a file at `path`, replacing any earlier one
+    #[wasm_bindgen(js_name = addFile)]
+    pub fn add_file(&mut self, path: &str, data: &[u8]) {
+        Arc::make_mut(&mut self.files).insert(path, data);
+    }
+
+    /// Register a text file at `path`, replacing any earlier one
+    #[wasm_bindgen(js_name = addTextFile)]
+    pub fn add_text_file(&mut self, path: &str, text: &str) {
+        self.add_file(path, text.as_bytes());
+    }
+
+    /// Convert markdown to DOCX bytes
+    pub fn convert(
+        &self,
+        markdown: &str,
+        options: Option<JsConvertOptions>,
+    ) -> Result<Vec<u8>, JsError> {
+        options::from_js(options)?
+            .convert(markdown, self.files.clone())
+            .map_err(to_js_error)
+    }
+}
+
+fn to_js_error(error: crate::Error) -> JsError {
+    JsError::new(&error.to_string())
+}
@@ -0,0 +1,83 @@
+//! Options object accepted by the JavaScript API
+//!
+//! The options themselves are [`ConvertOptions`]; this module declares the
+//! matching TypeScript interface, emitted into the generated `.d.ts`, and
+//! reads the object passed from JavaScript.
+
+use wasm_bindgen::prelude::*;
+
+use crate::options::ConvertOptions;
+
+#[wasm_bindgen(typescript_custom_section)]
+const CONVERT_OPTIONS_TS: &str = r##"
+/** Options for `markdownToDocx` and `Converter.convert`; every field is optional. */
+export interface ConvertOptions {
+    /** Language code ("en", "th", ...); defaults to the frontmatter's, then English */
+    language?: string;
+    /** Document title, also the `{{title}}` placeholder */
+    title?: string;
+    /** Directory the document's includes and images are relative to */
+    basePath?: string;
+    /** Directory `{!code:...}` paths are relative to */
+    sourceRoot?: string;
+    /** Directory holding cover.docx, table.docx, image.docx and header-footer.docx */
+    templateDir?: string;
+    toc?: TocOptions;
+    fonts?: FontOptions;
+    page?: PageOptions;
+    /** Values for `{{key}}` placeholders in the cover template */
+    placeholders?: Record<string, string>;
+}
+
+export interface TocOptions {
+    /** Default true */
+    enabled?: boolean;
+    /** Heading levels listed (default 2) */
+    depth?: number;
+    /** Defaults to the localized "Table of Contents" */
+    title?: string;
+    afterCover?: boolean;
+    dotLeader?: boolean;
+    pageNumbers?: "right" | "inline" | "none";
+    hyperlinks?: boolean;
+}
+
+export interface FontOptions {
+    default?: string;
+    code?: string;
+    /** Sizes in points */
+    normalSize?: number;
+    captionSize?: number;
+    codeSize?: number;
+    /** Colors as "#RRGGBB" */
+    normalColor?: string;
+    h1Color?: string;
+    captionColor?: string;
+}
+
+/** Lengths such as "210mm", "8.5in", "2cm" or "72pt" */
+export interface PageOptions {
+    width?: string;
+    height?: string;
+    marginTop?: string;
+    marginRight?: string;
+    marginBottom?: string;
+    marginLeft?: string;
+}
+"##;
+
+#[wasm_bindgen]
+extern "C" {
+    /// A `ConvertOptions` object passed from JavaScript
+    #[wasm_bindgen(typescript_type = "ConvertOptions")]
+    pub type JsConvertOptions;
+}
+
+/// Read options from JavaScript; a missing object means all defaults
+pub(crate) fn from_js(options: Option<JsConvertOptions>) -> Result<ConvertOptions, JsError> {
+    match options {
+        Some(options) => serde_wasm_bindgen::from_value(options.into())
+            .map_err(|e| JsError::new(&format!("Invalid options: {}", e))),
+        None => Ok(ConvertOptions::default()),
+    }
+}
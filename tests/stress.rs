@@ -0,0 +1,136 @@
+//! Large-document stress tests
+//!
+//! Builds generated documents far bigger than typical books (10k
+//! paragraphs, 50k runs, 1k tables, 500 images, 500 equations) end to end
+//! and checks the output is complete and well formed. Benchmarks over the
+//! same documents live in `benches/pipeline.rs`.
+
+#[path = "support/generate.rs"]
+mod generate;
+
+use std::io::{Cursor, Read};
+
+use md2docx::docx::validate_docx;
+use md2docx::{
+    markdown_to_docx_bounded, markdown_to_docx_with_config, DocumentConfig, IncludeConfig,
+    Language, MemoryBudget,
+};
+
+fn build(markdown: &str, config: &DocumentConfig) -> Vec<u8> {
+    let docx = markdown_to_docx_with_config(markdown, Language::English, config).unwrap();
+    let issues = validate_docx(&docx).unwrap();
+    assert!(issues.is_empty(), "{:?}", issues);
+    docx
+}
+
+fn document_xml(docx: &[u8]) -> String {
+    let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+#[test]
+fn test_ten_thousand_paragraphs() {
+    let docx = build(&generate::paragraphs(10_000), &DocumentConfig::default());
+    let xml = document_xml(&docx);
+    assert!(xml.contains("Paragraph 0 has"));
+    assert!(xml.contains("Paragraph 9999 has"));
+    assert!(xml.matches("<w:p>").count() + xml.matches("<w:p ").count() >= 10_000);
+}
+
+#[test]
+fn test_fifty_thousand_runs() {
+    let docx = build(&generate::runs(50_000), &DocumentConfig::default());
+    let xml = document_xml(&docx);
+    assert!(xml.contains("Run 2499 "));
+    assert!(xml.matches("<w:r>").count() >= 40_000);
+    assert!(xml.contains("w:ascii=\"Sarabun\""));
+}
+
+#[test]
+fn test_thousand_tables() {
+    let docx = build(&generate::tables(1_000, 5), &DocumentConfig::default());
+    let xml = document_xml(&docx);
+    assert_eq!(xml.matches("<w:tbl>").count(), 1_000);
+    assert!(xml.contains("Item 999.4"));
+}
+
+#[test]
+fn test_five_hundred_images() {
+    let dir = tempfile::tempdir().unwrap();
+    let markdown = generate::images(dir.path(), 500);
+    let config = DocumentConfig {
+        base_path: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    };
+    let docx = build(&markdown, &config);
+
+    let archive = zip::ZipArchive::new(Cursor::new(&docx)).unwrap();
+    let media = archive
+        .file_names()
+        .filter(|name| name.starts_with("word/media/"))
+        .count();
+    assert_eq!(media, 500);
+    assert_eq!(document_xml(&docx).matches("<w:drawing>").count(), 500);
+}
+
+#[test]
+fn test_bounded_build_of_included_chapters() {
+    let dir = tempfile::tempdir().unwrap();
+    let spill_dir = dir.path().join("spill");
+    let mut book = String::new();
+    for ch in 0..20 {
+        let chapter =
+            generate::paragraphs(500).replacen("# Paragraphs", &format!("# Chapter {ch}"), 1);
+        std::fs::write(dir.path().join(format!("ch{ch:02}.md")), chapter).unwrap();
+        if ch > 0 {
+            book.push_str("\n---\n\n");
+        }
+        book.push_str(&format!("{{!include:ch{ch:02}.md}}\n"));
+    }
+    let includes = IncludeConfig {
+        base_path: dir.path().to_path_buf(),
+        source_root: dir.path().to_path_buf(),
+        max_depth: 10,
+    };
+
+    let budget = MemoryBudget::new(256 * 1024).spill_dir(&spill_dir);
+    let docx = markdown_to_docx_bounded(&book, &includes, budget).unwrap();
+    let issues = validate_docx(&docx).unwrap();
+    assert!(issues.is_empty(), "{:?}", issues);
+
+    let xml = document_xml(&docx);
+    assert!(xml.contains("Chapter 0"));
+    assert!(xml.contains("Chapter 19"));
+    assert_eq!(xml.matches("Paragraph 499 has").count(), 20);
+    // The spill file is removed once the document is packaged
+    assert_eq!(std::fs::read_dir(&spill_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_bounded_build_spills_rendered_equations() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DocumentConfig {
+        math_renderer: "rex".to_string(),
+        memory_budget: Some(MemoryBudget::new(64 * 1024).spill_dir(dir.path())),
+        ..Default::default()
+    };
+    let docx = build(&generate::equations(500), &config);
+
+    let archive = zip::ZipArchive::new(Cursor::new(&docx)).unwrap();
+    let svgs = archive
+        .file_names()
+        .filter(|name| name.starts_with("word/media/") && name.ends_with(".svg"))
+        .count();
+    // Equations ReX can't render (e.g. without its math font) fall back to
+    // OMML, so count both paths
+    let omml = document_xml(&docx).matches("<m:oMathPara>").count();
+    assert_eq!(svgs + omml, 500, "{svgs} SVG, {omml} OMML");
+    // Spilled equations are removed once the document is packaged
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,138 @@
+//! Synthetic markdown documents for the stress tests and benchmarks
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// `count` paragraphs of mixed English and Thai text with inline formatting
+pub fn paragraphs(count: usize) -> String {
+    let mut md = String::from("# Paragraphs\n\n");
+    for i in 0..count {
+        if i % 100 == 0 {
+            writeln!(md, "## Section {}\n", i / 100 + 1).unwrap();
+        }
+        writeln!(
+            md,
+            "Paragraph {i} has **bold**, *italic* and `code` text, \
+             ภาษาไทยปนกับภาษาอังกฤษ, and a [link](https://example.com/{i}).\n"
+        )
+        .unwrap();
+    }
+    md
+}
+
+/// Paragraphs holding about `count` runs in total: nested formatting,
+/// mixed English and Thai words, links and a font override region
+pub fn runs(count: usize) -> String {
+    let mut md = String::from("# Runs\n\n<!-- {font:Sarabun} -->\n\n");
+    // Each line below becomes about 20 runs once split by format and script
+    for i in 0..count / 20 {
+        writeln!(
+            md,
+            "Run {i} **bold *nested* ไทย** and *italic ภาษา* with `code`, ~~struck~~, \
+             **more *deep **text** here*** [link ลิงก์](https://example.com/{i}) end ท้าย.\n"
+        )
+        .unwrap();
+    }
+    md.push_str("<!-- {/font} -->\n");
+    md
+}
+
+/// `count` distinct display equations, each after a short paragraph
+pub fn equations(count: usize) -> String {
+    let mut md = String::from("# Equations\n\n");
+    for i in 0..count {
+        writeln!(
+            md,
+            "Equation {i}:\n\n$$\\sum_{{k=0}}^{{{i}}} \\frac{{x_k^2}}{{k + {i}}}$$\n"
+        )
+        .unwrap();
+    }
+    md
+}
+
+/// `count` tables of `rows` body rows each, separated by short captions
+pub fn tables(count: usize, rows: usize) -> String {
+    let mut md = String::from("# Tables\n\n");
+    for t in 0..count {
+        writeln!(md, "Table {t}:\n").unwrap();
+        md.push_str("| Item | Quantity | Note |\n|------|---------:|:----:|\n");
+        for r in 0..rows {
+            writeln!(md, "| Item {t}.{r} | {} | **ok** |", t * rows + r).unwrap();
+        }
+        md.push('\n');
+    }
+    md
+}
+
+/// Write `count` distinct PNG files into `dir` and return markdown showing them
+pub fn images(dir: &Path, count: usize) -> String {
+    let mut md = String::from("# Images\n\n");
+    for i in 0..count {
+        let name = format!("img{i:04}.png");
+        std::fs::write(dir.join(&name), png(i)).unwrap();
+        writeln!(md, "![Figure {i}]({name}){{width=50%}}\n").unwrap();
+    }
+    md
+}
+
+/// A 2x2 RGB PNG whose colour is unique for each `seed` below 65536
+pub fn png(seed: usize) -> Vec<u8> {
+    let pixel = [(seed % 256) as u8, (seed / 256 % 256) as u8, 0x80];
+    let mut raw = Vec::new();
+    for _ in 0..2 {
+        raw.push(0); // filter: none
+        raw.extend_from_slice(&pixel);
+        raw.extend_from_slice(&pixel);
+    }
+
+    // zlib stream holding one stored (uncompressed) deflate block
+    let mut idat = vec![0x78, 0x01, 0x01];
+    idat.extend_from_slice(&(raw.len() as u16).to_le_bytes());
+    idat.extend_from_slice(&(!(raw.len() as u16)).to_le_bytes());
+    idat.extend_from_slice(&raw);
+    idat.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&2u32.to_be_bytes());
+    ihdr.extend_from_slice(&2u32.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit RGB
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    chunk(&mut png, b"IHDR", &ihdr);
+    chunk(&mut png, b"IDAT", &idat);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}